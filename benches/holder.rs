@@ -0,0 +1,153 @@
+//! Benchmarks for the holder's hot paths: signing proofs of possession and
+//! presentations, verifying issued credentials, and matching a presentation
+//! request against a large credential store.
+//!
+//! Run with `cargo bench`.
+
+// `criterion_group!` generates an undocumented public function.
+#![allow(missing_docs)]
+
+use chrono::Utc;
+use credibil_holder::Kind;
+use credibil_holder::credential::Credential;
+use credibil_holder::infosec::jose::jws::JwsBuilder;
+use credibil_holder::issuance::ProofClaims;
+use credibil_holder::issuance::proof::{self as vci_proof, Type, Verify};
+use credibil_holder::presentation::proof::{self, W3cFormat};
+use credibil_holder::presentation::{
+    Authorized, Constraints, NotAuthorized, PresentationFlow, RequestObject,
+};
+use credibil_holder::provider::{CredentialStorer, Signer};
+use credibil_holder::test_utils::issuer::CREDENTIAL_ISSUER;
+use credibil_holder::test_utils::mock::MockProvider;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use futures::executor::block_on;
+use serde_json::{Map, json};
+
+// Sizes of the credential store used for matching.
+const STORE_SIZES: [usize; 2] = [100, 1_000];
+
+fn constraints(credential_type: &str) -> Constraints {
+    serde_json::from_value(json!({
+        "fields": [{
+            "path": ["$.type"],
+            "filter": {"type": "string", "const": credential_type}
+        }]
+    }))
+    .expect("should parse constraints")
+}
+
+fn request_object() -> RequestObject {
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeIDCredential",
+                "constraints": constraints("EmployeeIDCredential")
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+// Seed a store with `size` credentials, one in ten of which is an
+// `EmployeeIDCredential`.
+fn seeded(size: usize) -> (MockProvider, Vec<Credential>) {
+    let provider = MockProvider::new();
+    for n in 0..size {
+        let credential_type =
+            if n % 10 == 0 { "EmployeeIDCredential" } else { "MembershipCredential" };
+        let claims = json!({"memberId": n.to_string()}).as_object().cloned().unwrap_or_default();
+        block_on(provider.seed(credential_type, claims)).expect("should seed credential");
+    }
+    let credentials = block_on(provider.find(None)).expect("should find credentials");
+    (provider, credentials)
+}
+
+fn proof_of_possession(c: &mut Criterion) {
+    let provider = MockProvider::new();
+    c.bench_function("proof_of_possession", |b| {
+        b.iter(|| {
+            let claims = ProofClaims {
+                iss: Some("96bfb9cb-0513-7d64-5532-bed74c48f9ab".into()),
+                aud: CREDENTIAL_ISSUER.into(),
+                iat: Utc::now().timestamp(),
+                nonce: Some("tZignsnFbp".into()),
+            };
+            let jws = block_on(
+                JwsBuilder::new()
+                    .jwt_type(Type::Openid4VciProofJwt)
+                    .payload(claims)
+                    .add_signer(&provider)
+                    .build(),
+            )
+            .expect("should build jws");
+            jws.encode().expect("should encode proof claims")
+        });
+    });
+}
+
+fn presentation(c: &mut Criterion) {
+    let (provider, credentials) = seeded(10);
+    let flow: PresentationFlow<Authorized> =
+        PresentationFlow::<NotAuthorized>::new(request_object())
+            .expect("should start flow")
+            .authorize(&credentials);
+    let kid = block_on(provider.verification_method()).expect("should get verification method");
+
+    c.bench_function("presentation", |b| {
+        b.iter(|| {
+            let payload = flow.payload(&kid).expect("should build presentation");
+            block_on(proof::create(W3cFormat::JwtVcJson, payload, &provider))
+                .expect("should sign presentation")
+        });
+    });
+}
+
+fn verify_credential(c: &mut Criterion) {
+    let provider = MockProvider::new();
+    let credential =
+        block_on(provider.seed("EmployeeIDCredential", Map::new())).expect("should seed");
+    let vc = Kind::String(credential.issued);
+
+    c.bench_function("verify_credential", |b| {
+        b.iter(|| {
+            block_on(vci_proof::verify(Verify::Vc(&vc), provider.clone()))
+                .expect("should verify credential")
+        });
+    });
+}
+
+fn match_credentials(c: &mut Criterion) {
+    let filter = constraints("EmployeeIDCredential");
+    let mut group = c.benchmark_group("match_credentials");
+    for size in STORE_SIZES {
+        let (_, credentials) = seeded(size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &credentials,
+            |b, credentials| {
+                b.iter(|| {
+                    credentials
+                        .iter()
+                        .filter(|c| filter.satisfied(*c).expect("should evaluate constraints"))
+                        .count()
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, proof_of_possession, presentation, verify_credential, match_credentials);
+criterion_main!(benches);
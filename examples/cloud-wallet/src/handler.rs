@@ -0,0 +1,262 @@
+//! # Request handlers
+//!
+//! Endpoints a thin mobile (or web) UI calls to drive the user's wallet. Each
+//! handler restores the flow it operates on from the user's flow registry and
+//! persists it again once the step is complete.
+
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use chrono::{DateTime, Utc};
+use credibil_holder::agent::Flow;
+use credibil_holder::context::WalletContext;
+use credibil_holder::credential::Credential;
+use credibil_holder::issuance::{CredentialOffer, OfferType, parse_offer};
+use credibil_holder::presentation::ResponseResponse;
+use credibil_holder::provider::CredentialStorer;
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::Wallet;
+use crate::{AppError, AppJson, AppState};
+
+/// Header identifying the user. A real deployment would take the user from
+/// an authenticated session rather than trusting a header.
+const USER_HEADER: &str = "x-wallet-user";
+
+/// Header identifying the user's organization, if the wallet hosts users for
+/// more than one.
+const TENANT_HEADER: &str = "x-wallet-tenant";
+
+/// The user a request is made on behalf of.
+pub struct User(WalletContext);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for User {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let header = |name| parts.headers.get(name).and_then(|v| v.to_str().ok());
+        let Some(user_id) = header(USER_HEADER) else {
+            return Err(AppError::Status(
+                StatusCode::UNAUTHORIZED,
+                format!("missing {USER_HEADER} header"),
+            ));
+        };
+        let mut context = WalletContext::new(user_id);
+        if let Some(tenant_id) = header(TENANT_HEADER) {
+            context = context.tenant(tenant_id);
+        }
+        Ok(Self(context))
+    }
+}
+
+/// Receive offer request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OfferRequest {
+    /// The credential offer link (or QR code contents) received by the user.
+    pub offer: String,
+}
+
+/// Receive offer response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OfferResponse {
+    /// ID of the issuance flow started for the offer.
+    pub flow_id: String,
+
+    /// Credential configuration IDs of the credentials on offer.
+    pub offered: Vec<String>,
+
+    /// Whether the issuer requires a PIN to accept the offer.
+    pub pin_required: bool,
+}
+
+/// Accept offer request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AcceptRequest {
+    /// PIN (transaction code) sent to the user by the issuer, if required.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+}
+
+/// Presentation request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PresentationRequest {
+    /// The presentation request link (or QR code contents) received by the
+    /// user.
+    pub request: String,
+}
+
+/// Presentation request response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PresentationResponse {
+    /// ID of the presentation flow started for the request.
+    pub flow_id: String,
+
+    /// The user's credentials that match the verifier's request.
+    pub matches: Vec<Credential>,
+}
+
+/// Authorize presentation request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuthorizeRequest {
+    /// IDs of the credentials the user agrees to present.
+    pub credential_ids: Vec<String>,
+}
+
+/// An in-progress flow.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FlowSummary {
+    /// The flow ID.
+    pub id: String,
+
+    /// The step the flow is waiting on.
+    pub state: String,
+
+    /// The time after which the flow can no longer be resumed.
+    pub expires_at: DateTime<Utc>,
+}
+
+// Start an issuance flow from a credential offer
+#[axum::debug_handler]
+pub async fn offer(
+    State(state): State<AppState>, User(context): User, AppJson(req): AppJson<OfferRequest>,
+) -> Result<AppJson<OfferResponse>, AppError> {
+    let offer = match parse_offer(&req.offer)? {
+        OfferType::Object(offer) => offer,
+        OfferType::Uri(uri) => fetch_offer(&uri).await?,
+    };
+    let pin_required = offer.pre_authorized_code().is_some_and(|grant| grant.tx_code.is_some());
+
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    let flow_id = wallet.agent.offer(offer, &context.profile_id).await?;
+    let offered = match wallet.agent.flow(&flow_id).as_deref() {
+        Some(Flow::Offered(flow)) => flow.offered().into_keys().collect(),
+        _ => vec![],
+    };
+    wallet.save(&flow_id).await?;
+
+    Ok(AppJson(OfferResponse {
+        flow_id,
+        offered,
+        pin_required,
+    }))
+}
+
+// Accept all credentials on offer and save them to the user's wallet
+#[axum::debug_handler]
+pub async fn accept(
+    State(state): State<AppState>, User(context): User, Path(flow_id): Path<String>,
+    AppJson(req): AppJson<AcceptRequest>,
+) -> Result<AppJson<Vec<Credential>>, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    wallet.load(&flow_id).await.map_err(not_found)?;
+
+    wallet.agent.accept(&flow_id, &None, req.pin)?;
+    let credentials = wallet.agent.receive(&flow_id).await?;
+    wallet.agent.save(&flow_id).await?;
+    wallet.save(&flow_id).await?;
+
+    Ok(AppJson(credentials))
+}
+
+// Start a presentation flow from a verifier's request
+#[axum::debug_handler]
+pub async fn request(
+    State(state): State<AppState>, User(context): User, AppJson(req): AppJson<PresentationRequest>,
+) -> Result<AppJson<PresentationResponse>, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    let flow_id = wallet.agent.request(&req.request).await?;
+    let matches = wallet.agent.matches(&flow_id).await?;
+    wallet.save(&flow_id).await?;
+
+    Ok(AppJson(PresentationResponse { flow_id, matches }))
+}
+
+// Present the credentials the user has chosen to the verifier
+#[axum::debug_handler]
+pub async fn authorize(
+    State(state): State<AppState>, User(context): User, Path(flow_id): Path<String>,
+    AppJson(req): AppJson<AuthorizeRequest>,
+) -> Result<AppJson<ResponseResponse>, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    wallet.load(&flow_id).await.map_err(not_found)?;
+
+    let mut credentials = vec![];
+    for id in &req.credential_ids {
+        let Some(credential) = wallet.provider.load(id).await? else {
+            return Err(AppError::Status(StatusCode::NOT_FOUND, format!("no credential {id}")));
+        };
+        credentials.push(credential);
+    }
+    wallet.agent.authorize(&flow_id, &credentials)?;
+    let response = wallet.agent.present(&flow_id).await?;
+    wallet.save(&flow_id).await?;
+
+    Ok(AppJson(response))
+}
+
+// List the user's in-progress flows
+#[axum::debug_handler]
+pub async fn flows(
+    State(state): State<AppState>, User(context): User,
+) -> Result<AppJson<Vec<FlowSummary>>, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    let summaries = wallet
+        .registry
+        .list_active()
+        .await?
+        .into_iter()
+        .map(|record| FlowSummary {
+            id: record.id,
+            state: flow_state(&record.flow).into(),
+            expires_at: record.expires_at,
+        })
+        .collect();
+    Ok(AppJson(summaries))
+}
+
+// Cancel an in-progress flow
+#[axum::debug_handler]
+pub async fn cancel(
+    State(state): State<AppState>, User(context): User, Path(flow_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    wallet.registry.remove(&flow_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// List the user's credentials
+#[axum::debug_handler]
+pub async fn credentials(
+    State(state): State<AppState>, User(context): User,
+) -> Result<AppJson<Vec<Credential>>, AppError> {
+    let wallet = Wallet::new(&state.provider, &context, &state.client_id);
+    Ok(AppJson(wallet.provider.find(None).await?))
+}
+
+// Fetch a credential offer passed by reference.
+async fn fetch_offer(uri: &str) -> anyhow::Result<CredentialOffer> {
+    let client = reqwest::Client::new();
+    let result = client.get(uri).header(ACCEPT, "application/json").send().await?;
+    Ok(result.error_for_status()?.json::<CredentialOffer>().await?)
+}
+
+// The step a persisted flow is waiting on.
+const fn flow_state(flow: &Flow) -> &'static str {
+    match flow {
+        Flow::Offered(_) => "offered",
+        Flow::Accepted(_) => "accepted",
+        Flow::Exchanged(_) => "exchanged",
+        Flow::Issued(_) => "issued",
+        Flow::Requested(_) => "requested",
+        Flow::Authorized(_) => "authorized",
+        Flow::TimedOut(_) => "timed_out",
+        _ => "unknown",
+    }
+}
+
+// Report a flow that cannot be restored as not found.
+fn not_found(e: anyhow::Error) -> AppError {
+    AppError::Status(StatusCode::NOT_FOUND, e.to_string())
+}
@@ -0,0 +1,135 @@
+//! # Example Cloud Wallet
+//!
+//! A custodial wallet that runs on a server and holds credentials on behalf
+//! of many users. A thin mobile or web UI scans offers and presentation
+//! requests and calls this service to act on them; the credentials and keys
+//! never leave the server.
+//!
+//! Each user gets their own provider, scoped to a `WalletContext`, so their
+//! credentials, keys and flows are isolated from other users (and other
+//! tenants). In-progress flows are persisted to a flow registry between
+//! requests.
+//!
+//! Assumes pre-authorized issuance only.
+
+mod handler;
+mod provider;
+mod wallet;
+
+use std::env;
+
+use axum::Router;
+use axum::extract::FromRequest;
+use axum::extract::rejection::JsonRejection;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tower_http::trace::TraceLayer;
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
+
+use crate::provider::{Attester, Provider, Store};
+
+/// Application state.
+#[derive(Clone)]
+pub struct AppState {
+    provider: Provider,
+    client_id: String,
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let subscriber =
+        FmtSubscriber::builder().with_env_filter(EnvFilter::from_default_env()).finish();
+    tracing::subscriber::set_global_default(subscriber).expect("set default subscriber");
+    let http_addr = env::var("WALLET_HTTP_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8081".into());
+    let client_id = env::var("WALLET_CLIENT_ID").unwrap_or_else(|_| "cloud-wallet".into());
+    let attester_id =
+        env::var("WALLET_ATTESTER_ID").unwrap_or_else(|_| "https://cloud-wallet.example".into());
+
+    let app_state = AppState {
+        provider: Provider::new(Store::default(), Attester::test(attester_id)),
+        client_id,
+    };
+
+    let router = Router::new()
+        .route("/offers", post(handler::offer))
+        .route("/requests", post(handler::request))
+        .route("/flows", get(handler::flows))
+        .route("/flows/:flow_id", delete(handler::cancel))
+        .route("/flows/:flow_id/accept", post(handler::accept))
+        .route("/flows/:flow_id/authorize", post(handler::authorize))
+        .route("/credentials", get(handler::credentials))
+        .layer(TraceLayer::new_for_http())
+        .with_state(app_state);
+
+    let listener = TcpListener::bind(http_addr).await.expect("should bind to address");
+    tracing::info!("listening on {}", listener.local_addr().expect("listener should have address"));
+    axum::serve(listener, router).await.expect("server should run");
+}
+
+// Custom JSON extractor to enable overriding the rejection and create our own
+/// error response.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct AppJson<T>(pub T);
+
+impl<T> IntoResponse for AppJson<T>
+where
+    T: Serialize,
+    axum::Json<T>: IntoResponse,
+{
+    fn into_response(self) -> axum::response::Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Custom application errors.
+pub enum AppError {
+    /// The request body contained invalid JSON.
+    InvalidJson(JsonRejection),
+
+    /// Status code and message error.
+    Status(StatusCode, String),
+
+    /// Unspecified application error.
+    Other(anyhow::Error),
+}
+
+/// Error response.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    message: String,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::InvalidJson(rejection) => (rejection.status(), rejection.body_text()),
+            Self::Status(status, message) => {
+                tracing::error!("status error: {status} {message}");
+                (status, message)
+            }
+            Self::Other(error) => {
+                tracing::error!("internal server error: {error:#}");
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error".into())
+            }
+        };
+        (status, AppJson(ErrorResponse { message })).into_response()
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::InvalidJson(rejection)
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Other(error)
+    }
+}
@@ -0,0 +1,186 @@
+//! # Wallet Provider
+//!
+//! A single `Store` holds the credentials, keys, flow state and persisted flows
+//! of every user. Each user's `Provider` is scoped to their `WalletContext` so
+//! it can only see that user's records.
+//!
+//! The store is held in memory. A real deployment would use a database and
+//! keep signing keys in a hardware security module or key management service.
+//!
+//! Each user's key doubles as their wallet instance key: the provider attests
+//! it to issuers when requesting an access token.
+
+mod attestation;
+mod issuer_client;
+mod store;
+mod verifier_client;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use credibil_holder::context::WalletContext;
+use credibil_holder::credential::Credential;
+use credibil_holder::provider::{
+    Algorithm, ContextScoped, DidResolver, Document, HolderProvider, NonceCache, Result, Signer,
+    StateStore,
+};
+use credibil_holder::registry::FlowRecord;
+use ed25519_dalek::{Signer as _, SigningKey};
+use rand::rngs::OsRng;
+use reqwest::header::ACCEPT;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub use self::attestation::Attester;
+
+/// Multicodec prefix for an Ed25519 public key.
+const ED25519_CODEC: [u8; 2] = [0xed, 0x01];
+
+/// Records for all users, keyed by context-scoped keys.
+#[derive(Debug, Default)]
+struct Records {
+    credentials: HashMap<String, Credential>,
+    flows: HashMap<String, FlowRecord>,
+    keys: HashMap<String, SigningKey>,
+    nonces: HashSet<(String, String)>,
+    state: HashMap<String, Vec<u8>>,
+}
+
+/// Shared store for all users of the wallet.
+#[derive(Clone, Debug, Default)]
+pub struct Store {
+    records: Arc<Mutex<Records>>,
+}
+
+impl Store {
+    // The lock is never held across an await.
+    fn records(&self) -> MutexGuard<'_, Records> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Provider for a single user of the wallet.
+#[derive(Clone, Debug)]
+pub struct Provider {
+    context: WalletContext,
+    store: Store,
+    attester: Attester,
+}
+
+impl Provider {
+    /// Create a provider using the shared store and wallet attester, scoped
+    /// to the default context. Use `scoped` to get a provider for a user.
+    #[must_use]
+    pub fn new(store: Store, attester: Attester) -> Self {
+        Self {
+            context: WalletContext::default(),
+            store,
+            attester,
+        }
+    }
+
+    // Namespace a key to the provider's context.
+    fn key(&self, key: &str) -> String {
+        self.context.scoped_key(key)
+    }
+
+    // Prefix of all keys in the provider's context.
+    fn prefix(&self) -> String {
+        self.key("")
+    }
+
+    // The user's signing key, created the first time it is needed.
+    fn signing_key(&self) -> SigningKey {
+        let mut records = self.store.records();
+        records
+            .keys
+            .entry(self.prefix())
+            .or_insert_with(|| SigningKey::generate(&mut OsRng))
+            .clone()
+    }
+}
+
+impl ContextScoped for Provider {
+    fn scoped(&self, context: &WalletContext) -> Self {
+        Self {
+            context: context.clone(),
+            store: self.store.clone(),
+            attester: self.attester.clone(),
+        }
+    }
+
+    fn context(&self) -> WalletContext {
+        self.context.clone()
+    }
+}
+
+impl HolderProvider for Provider {}
+
+impl StateStore for Provider {
+    async fn put(&self, key: &str, state: impl Serialize + Send, _: DateTime<Utc>) -> Result<()> {
+        let state = serde_json::to_vec(&state)?;
+        self.store.records().state.insert(self.key(key), state);
+        Ok(())
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        let Some(state) = self.store.records().state.get(&self.key(key)).cloned() else {
+            return Err(anyhow!("state not found for key: {key}"));
+        };
+        Ok(serde_json::from_slice(&state)?)
+    }
+
+    async fn purge(&self, key: &str) -> Result<()> {
+        self.store.records().state.remove(&self.key(key));
+        Ok(())
+    }
+}
+
+/// Nonces are shared by all users: an issuer never issues the same `c_nonce`
+/// twice, whoever it was issued to.
+impl NonceCache for Provider {
+    async fn use_nonce(&self, credential_issuer: &str, nonce: &str) -> Result<bool> {
+        Ok(self.store.records().nonces.insert((credential_issuer.into(), nonce.into())))
+    }
+}
+
+impl DidResolver for Provider {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        let client = reqwest::Client::new();
+        let result = client.get(url).header(ACCEPT, "application/json").send().await?;
+        let doc = match result.json::<Document>().await {
+            Ok(doc) => doc,
+            Err(e) => {
+                tracing::error!("Error resolving DID document: {}", e);
+                return Err(e.into());
+            }
+        };
+        Ok(doc)
+    }
+}
+
+/// Each user has their own Ed25519 key and `did:key` identifier.
+impl Signer for Provider {
+    async fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.signing_key().sign(msg).to_bytes().to_vec())
+    }
+
+    async fn verifying_key(&self) -> Result<Vec<u8>> {
+        Ok(self.signing_key().verifying_key().as_bytes().to_vec())
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::EdDSA
+    }
+
+    async fn verification_method(&self) -> Result<String> {
+        let public_key = self.signing_key().verifying_key();
+        let multi_key = multibase::encode(
+            multibase::Base::Base58Btc,
+            [ED25519_CODEC.as_slice(), public_key.as_bytes()].concat(),
+        );
+        Ok(format!("did:key:{multi_key}#{multi_key}"))
+    }
+}
@@ -0,0 +1,130 @@
+//! # Test Wallet Attester
+//!
+//! Issues wallet attestations for OAuth 2.0 Attestation-Based Client
+//! Authentication so an issuer can check an access token request comes from a
+//! genuine instance of the wallet. Each token request carries:
+//!
+//! - `OAuth-Client-Attestation`: signed by the attester, binding the wallet's
+//!   client ID to the user's (wallet instance) key.
+//! - `OAuth-Client-Attestation-PoP`: signed by the user's key, proving the
+//!   wallet holds the attested key and addressed to the issuer.
+//!
+//! A real wallet provider would attest instances from a separate service,
+//! after checking the device and app integrity, and keep its key in a hardware
+//! security module. This attester signs with a well-known test key and must
+//! only be trusted for demonstrations.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{Duration, Utc};
+use ed25519_dalek::{Signer as _, SigningKey};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use serde_json::json;
+
+/// Header carrying the wallet attestation JWT.
+pub const ATTESTATION_HEADER: &str = "OAuth-Client-Attestation";
+
+/// Header carrying the proof of possession JWT.
+pub const ATTESTATION_POP_HEADER: &str = "OAuth-Client-Attestation-PoP";
+
+/// Seed of the test attester's Ed25519 key. The public key, for issuers to
+/// trust, is `GXxOY39Dw38xQrqPdxiG7QhNemg-5bnZjkzO2_IdonY`.
+const TEST_ATTESTER_SEED: &str = "rRHt4ofK2OonqEejkdPVcCHEkHPZPDPqicPeYgxzysY";
+
+/// How long an attestation is valid for.
+const ATTESTATION_EXPIRY: Duration = Duration::hours(1);
+
+/// Attester for instances of the wallet.
+#[derive(Clone, Debug)]
+pub struct Attester {
+    id: String,
+    key: SigningKey,
+}
+
+/// Wallet attestation and proof of possession to send with a token request.
+#[derive(Clone, Debug)]
+pub struct ClientAttestation {
+    /// The wallet attestation JWT.
+    pub attestation: String,
+
+    /// The proof of possession JWT.
+    pub pop: String,
+}
+
+impl Attester {
+    /// Create an attester, identified by `id`, using the test key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the test key seed is invalid.
+    #[must_use]
+    pub fn test(id: impl Into<String>) -> Self {
+        let mut seed = [0u8; 32];
+        Base64UrlUnpadded::decode(TEST_ATTESTER_SEED, &mut seed)
+            .expect("test attester seed should be valid");
+        Self {
+            id: id.into(),
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Attest the wallet instance key for the client and create a proof of
+    /// possession for the issuer (`audience`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either JWT cannot be serialized.
+    pub fn attest(
+        &self, client_id: &str, instance_key: &SigningKey, audience: &str,
+    ) -> anyhow::Result<ClientAttestation> {
+        let now = Utc::now();
+        let public_key = instance_key.verifying_key();
+
+        let attestation = sign_jwt(
+            &self.key,
+            "oauth-client-attestation+jwt",
+            &json!({
+                "iss": self.id,
+                "sub": client_id,
+                "iat": now.timestamp(),
+                "exp": (now + ATTESTATION_EXPIRY).timestamp(),
+                "cnf": {
+                    "jwk": {
+                        "kty": "OKP",
+                        "crv": "Ed25519",
+                        "x": Base64UrlUnpadded::encode_string(public_key.as_bytes()),
+                    }
+                },
+            }),
+        )?;
+
+        let mut jti = [0u8; 16];
+        OsRng.fill_bytes(&mut jti);
+        let pop = sign_jwt(
+            instance_key,
+            "oauth-client-attestation-pop+jwt",
+            &json!({
+                "iss": client_id,
+                "aud": audience,
+                "jti": Base64UrlUnpadded::encode_string(&jti),
+                "iat": now.timestamp(),
+            }),
+        )?;
+
+        Ok(ClientAttestation { attestation, pop })
+    }
+}
+
+// Create a compact JWS signed with EdDSA.
+fn sign_jwt(key: &SigningKey, typ: &str, claims: &impl Serialize) -> anyhow::Result<String> {
+    let header = serde_json::to_vec(&json!({"alg": "EdDSA", "typ": typ}))?;
+    let payload = serde_json::to_vec(claims)?;
+    let signing_input = format!(
+        "{}.{}",
+        Base64UrlUnpadded::encode_string(&header),
+        Base64UrlUnpadded::encode_string(&payload)
+    );
+    let signature = key.sign(signing_input.as_bytes());
+    Ok(format!("{signing_input}.{}", Base64UrlUnpadded::encode_string(&signature.to_bytes())))
+}
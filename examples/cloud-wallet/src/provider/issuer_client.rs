@@ -0,0 +1,158 @@
+use base64ct::{Base64, Encoding};
+use credibil_holder::credential::ImageData;
+use credibil_holder::error::{OAuthError, RetryLater};
+use credibil_holder::issuance::{
+    AuthorizationRequest, AuthorizationResponse, CredentialRequest, CredentialResponse,
+    DeferredCredentialRequest, DeferredCredentialResponse, MetadataRequest, MetadataResponse,
+    NotificationRequest, NotificationResponse, OAuthServerRequest, OAuthServerResponse,
+    TokenRequest, TokenResponse,
+};
+use credibil_holder::provider::Issuer;
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+
+use super::Provider;
+use super::attestation::{ATTESTATION_HEADER, ATTESTATION_POP_HEADER};
+
+impl Issuer for Provider {
+    /// Get issuer metadata.
+    async fn metadata(&self, req: MetadataRequest) -> anyhow::Result<MetadataResponse> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/.well-known/openid-credential-issuer", req.credential_issuer);
+        let result = client.get(&url).header(ACCEPT, "application/json").send().await?;
+        let mut md = match result.json::<MetadataResponse>().await {
+            Ok(md) => md,
+            Err(e) => {
+                tracing::error!("Error getting metadata: {}", e);
+                return Err(e.into());
+            }
+        };
+        md.credential_issuer.credential_issuer.clone_from(&req.credential_issuer);
+        Ok(md)
+    }
+
+    /// Get authorization server metadata.
+    async fn oauth_server(&self, req: OAuthServerRequest) -> anyhow::Result<OAuthServerResponse> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/.well-known/oauth-authorization-server", req.credential_issuer);
+        let result = client.get(&url).header(ACCEPT, "application/json").send().await?;
+        let md = match result.json::<OAuthServerResponse>().await {
+            Ok(md) => md,
+            Err(e) => {
+                tracing::error!("Error getting OAuth server metadata: {}", e);
+                return Err(e.into());
+            }
+        };
+        Ok(md)
+    }
+
+    /// Get an authorization code. Returns an error: this example assumes
+    /// issuer-initiated pre-authorized issuance.
+    async fn authorization(
+        &self, _req: AuthorizationRequest,
+    ) -> anyhow::Result<AuthorizationResponse> {
+        Err(anyhow::anyhow!("authorization code flow not supported by this example"))
+    }
+
+    /// Get an access token. The wallet authenticates to the issuer with a
+    /// wallet attestation for the user's key.
+    async fn token(&self, req: TokenRequest) -> anyhow::Result<TokenResponse> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/token", req.credential_issuer);
+        let client_id = req.client_id.clone().unwrap_or_default();
+        let attestation =
+            self.attester.attest(&client_id, &self.signing_key(), &req.credential_issuer)?;
+        let form = req.form_encode()?;
+        let result = client
+            .post(&url)
+            .header(CONTENT_TYPE, "multipart/form-data")
+            .header(ACCEPT, "application/json")
+            .header(ATTESTATION_HEADER, attestation.attestation)
+            .header(ATTESTATION_POP_HEADER, attestation.pop)
+            .form(&form)
+            .send()
+            .await?;
+        if !result.status().is_success() {
+            return Err(error_response(result).await);
+        }
+        let token = match result.json::<TokenResponse>().await {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::error!("Error getting token: {}", e);
+                return Err(e.into());
+            }
+        };
+        Ok(token)
+    }
+
+    /// Get a credential.
+    async fn credential(&self, req: CredentialRequest) -> anyhow::Result<CredentialResponse> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/credential", req.credential_issuer);
+        let result = client
+            .post(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, "application/json")
+            .header(AUTHORIZATION, &format!("Bearer {}", req.access_token))
+            .json(&req)
+            .send()
+            .await?;
+        if !result.status().is_success() {
+            return Err(error_response(result).await);
+        }
+        let cred = result.json::<CredentialResponse>().await?;
+        Ok(cred)
+    }
+
+    /// Get a deferred credential. Returns an error: deferred issuance is not
+    /// supported by this example.
+    async fn deferred(
+        &self, _req: DeferredCredentialRequest,
+    ) -> anyhow::Result<DeferredCredentialResponse> {
+        Err(anyhow::anyhow!("deferred credentials not supported by this example"))
+    }
+
+    /// Get a base64 encoded form of the credential logo.
+    async fn image(self, url: &str) -> anyhow::Result<ImageData> {
+        let client = reqwest::Client::new();
+        let result = client.get(url).header(ACCEPT, "image/*").send().await?;
+        let headers = result.headers().clone();
+        let media_type = match headers.get(CONTENT_TYPE) {
+            Some(mt) => mt.to_str()?,
+            None => "image/*",
+        };
+        let image_bytes = result.bytes().await?;
+        let image_data = Base64::encode_string(&image_bytes);
+        Ok(ImageData {
+            data: image_data,
+            media_type: media_type.to_string(),
+        })
+    }
+
+    /// Notify the issuer of issuance progress. Not implemented for this
+    /// example.
+    async fn notification(
+        &self, _req: NotificationRequest,
+    ) -> anyhow::Result<NotificationResponse> {
+        Ok(NotificationResponse::default())
+    }
+}
+
+// Convert an error response from the issuer to a typed OAuth error where
+// possible so the application can decide how to recover.
+async fn error_response(result: reqwest::Response) -> anyhow::Error {
+    let status = result.status();
+    let retry_after = result.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok());
+    if let Some(retry) = RetryLater::from_response(status.as_u16(), retry_after) {
+        tracing::warn!("Issuer asked to {retry}");
+        return retry.into();
+    }
+    let body = match result.bytes().await {
+        Ok(body) => body,
+        Err(e) => return e.into(),
+    };
+    let Some(err) = OAuthError::parse(&body) else {
+        return anyhow::anyhow!("issuer returned status {status}");
+    };
+    tracing::error!("Issuer returned error: {err}");
+    err.into()
+}
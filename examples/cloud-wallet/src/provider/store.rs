@@ -0,0 +1,81 @@
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::Constraints;
+use credibil_holder::provider::{CredentialStorer, FlowStore};
+use credibil_holder::registry::FlowRecord;
+
+use super::Provider;
+
+/// Credentials are only visible to the user they were issued to.
+impl CredentialStorer for Provider {
+    /// Save a `Credential` to the store. Overwrite any existing credential with
+    /// the same ID. Create a new credential if one with the same ID does
+    /// not exist.
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        tracing::debug!("saving credential {} for {}", credential.id, self.context);
+        self.store.records().credentials.insert(self.key(&credential.id), credential.clone());
+        Ok(())
+    }
+
+    /// Retrieve a `Credential` from the store with the given ID. Return None if
+    /// no credential with the ID exists.
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        Ok(self.store.records().credentials.get(&self.key(id)).cloned())
+    }
+
+    /// Find the credentials that match the the provided filter. If `filter` is
+    /// None, return all credentials in the store.
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        let prefix = self.prefix();
+        let list = self
+            .store
+            .records()
+            .credentials
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, credential)| credential.clone())
+            .collect::<Vec<Credential>>();
+
+        let Some(constraints) = filter else {
+            return Ok(list);
+        };
+        Ok(list.into_iter().filter(|cred| constraints.satisfied(cred).unwrap_or(false)).collect())
+    }
+
+    /// Remove the credential with the given ID from the store. Return an error
+    /// if the credential does not exist.
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        if self.store.records().credentials.remove(&self.key(id)).is_none() {
+            anyhow::bail!("credential with ID {id} does not exist");
+        }
+        Ok(())
+    }
+}
+
+/// In-progress flows are persisted per user so they can be listed and resumed.
+impl FlowStore for Provider {
+    async fn put(&self, record: &FlowRecord) -> anyhow::Result<()> {
+        self.store.records().flows.insert(self.key(&record.id), record.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<FlowRecord>> {
+        Ok(self.store.records().flows.get(&self.key(id)).cloned())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<FlowRecord>> {
+        let prefix = self.prefix();
+        Ok(self
+            .store
+            .records()
+            .flows
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, record)| record.clone())
+            .collect())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.store.records().flows.remove(&self.key(id));
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! # User Wallet
+//!
+//! A `Wallet` is created for each HTTP request from the provider scoped to the
+//! calling user. Flows are not held in memory between requests: they are
+//! persisted to the user's flow registry and restored to a fresh agent when
+//! the next request for the flow arrives. This keeps the service stateless so
+//! any instance can serve any request.
+
+use anyhow::bail;
+use chrono::{Duration, Utc};
+use credibil_holder::agent::HolderAgent;
+use credibil_holder::context::WalletContext;
+use credibil_holder::provider::ContextScoped;
+use credibil_holder::registry::FlowRegistry;
+
+use crate::provider::Provider;
+
+/// How long an in-progress flow can be resumed for. Pre-authorized codes and
+/// request objects typically expire well within this time.
+const FLOW_EXPIRY: Duration = Duration::minutes(10);
+
+/// A user's wallet: an agent and flow registry using the user's provider.
+pub struct Wallet {
+    /// The agent managing the user's flows.
+    pub agent: HolderAgent<Provider>,
+
+    /// The provider scoped to the user.
+    pub provider: Provider,
+
+    /// The user's persisted flows.
+    pub registry: FlowRegistry<Provider>,
+}
+
+impl Wallet {
+    /// Create a wallet for the user identified by the context.
+    pub fn new(provider: &Provider, context: &WalletContext, client_id: &str) -> Self {
+        let provider = provider.scoped(context);
+        Self {
+            agent: HolderAgent::new(provider.clone(), client_id),
+            registry: FlowRegistry::new(provider.clone()),
+            provider,
+        }
+    }
+
+    /// Restore a persisted flow to the agent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user has no flow with the ID or the flow has
+    /// expired.
+    pub async fn load(&self, id: &str) -> anyhow::Result<()> {
+        let Some(flow) = self.registry.resume(id).await? else {
+            bail!("no flow with id {id}");
+        };
+        self.agent.restore(flow);
+        Ok(())
+    }
+
+    /// Persist the agent's flow so it can be resumed by a later request. If
+    /// the flow has completed it is removed from the registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be updated.
+    pub async fn save(&self, id: &str) -> anyhow::Result<()> {
+        match self.agent.flow(id) {
+            Some(flow) => {
+                self.registry.register((*flow).clone(), Utc::now() + FLOW_EXPIRY).await?;
+            }
+            None => self.registry.remove(id).await?,
+        }
+        Ok(())
+    }
+}
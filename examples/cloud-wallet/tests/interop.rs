@@ -0,0 +1,188 @@
+//! End-to-end interoperability tests.
+//!
+//! Drives the cloud wallet against the example `vcservice` over real HTTP,
+//! from credential offer through to presentation verification, to catch
+//! wire-format regressions the in-process tests miss. Both services must be
+//! running, for example with:
+//!
+//! ```sh
+//! docker compose -f docker-compose.interop.yaml up --build --detach
+//! cargo test -p cloud-wallet --features interop --test interop
+//! ```
+//!
+//! Set `INTEROP_VCSERVICE_URL` and `INTEROP_WALLET_URL` to test services
+//! running elsewhere.
+
+use std::env;
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+
+const USER_HEADER: &str = "x-wallet-user";
+
+// How long to wait for the services to start.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Services {
+    client: Client,
+    vcservice: String,
+    wallet: String,
+}
+
+impl Services {
+    // Connect to the services, waiting for them to start.
+    async fn start() -> Self {
+        let services = Self {
+            client: Client::new(),
+            vcservice: env::var("INTEROP_VCSERVICE_URL")
+                .unwrap_or_else(|_| "http://localhost:8080".into()),
+            wallet: env::var("INTEROP_WALLET_URL")
+                .unwrap_or_else(|_| "http://localhost:8081".into()),
+        };
+        services
+            .wait_for(&format!("{}/.well-known/openid-credential-issuer", services.vcservice))
+            .await;
+        services.wait_for(&format!("{}/credentials", services.wallet)).await;
+        services
+    }
+
+    // Poll the URL until the service answers. Any HTTP response will do.
+    async fn wait_for(&self, url: &str) {
+        let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+        while self.client.get(url).send().await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "{url} did not respond in time");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    // Post JSON to the example issuer and verifier.
+    async fn vcservice(&self, path: &str, body: &Value) -> Value {
+        let response = self
+            .client
+            .post(format!("{}{path}", self.vcservice))
+            .json(body)
+            .send()
+            .await
+            .expect("vcservice should respond");
+        json_body(response).await
+    }
+
+    // Post JSON to the wallet on behalf of the user.
+    async fn wallet(&self, user: &str, path: &str, body: &Value) -> Value {
+        let response = self
+            .client
+            .post(format!("{}{path}", self.wallet))
+            .header(USER_HEADER, user)
+            .json(body)
+            .send()
+            .await
+            .expect("wallet should respond");
+        json_body(response).await
+    }
+}
+
+// Check the response succeeded and return its JSON body.
+async fn json_body(response: reqwest::Response) -> Value {
+    let status = response.status();
+    let url = response.url().clone();
+    let body = response.text().await.expect("response should have a body");
+    assert_eq!(status, StatusCode::OK, "{url} failed: {body}");
+    serde_json::from_str(&body).expect("response should be JSON")
+}
+
+// Issue an employee ID credential to the user, returning its ID.
+async fn issue(services: &Services, user: &str, by_reference: bool) -> String {
+    let created = services
+        .vcservice(
+            "/create_offer",
+            &json!({
+                "credential_issuer": "http://credibil.io",
+                "subject_id": "normal_user",
+                "credential_configuration_id": "EmployeeID_JWT",
+                "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+                "tx_code_required": true,
+                "by_reference": by_reference,
+            }),
+        )
+        .await;
+    let offer_json = created["offer_json"].as_str().expect("should have offer JSON");
+    let link = if by_reference {
+        let offer: Value = serde_json::from_str(offer_json).expect("offer should be JSON");
+        let uri = offer["credential_offer_uri"].as_str().expect("should have offer URI");
+        format!("openid-credential-offer://?credential_offer_uri={}", urlencoding::encode(uri))
+    } else {
+        format!("openid-credential-offer://?credential_offer={}", urlencoding::encode(offer_json))
+    };
+
+    let offered = services.wallet(user, "/offers", &json!({ "offer": link })).await;
+    assert_eq!(offered["offered"], json!(["EmployeeID_JWT"]));
+    assert_eq!(offered["pin_required"], true);
+    let flow_id = offered["flow_id"].as_str().expect("should have flow ID");
+
+    let credentials = services
+        .wallet(user, &format!("/flows/{flow_id}/accept"), &json!({ "pin": created["tx_code"] }))
+        .await;
+    let credentials = credentials.as_array().expect("should be a list of credentials");
+    assert_eq!(credentials.len(), 1);
+    assert!(
+        credentials[0]["type"]
+            .as_array()
+            .is_some_and(|types| types.contains(&json!("EmployeeIDCredential"))),
+        "unexpected credential: {}",
+        credentials[0]
+    );
+    credentials[0]["id"].as_str().expect("credential should have an ID").to_string()
+}
+
+// Request and present the employee ID credential.
+async fn present(services: &Services, user: &str, credential_id: &str) {
+    let created = services
+        .vcservice(
+            "/create_request",
+            &json!({
+                "purpose": "To verify employment",
+                "input_descriptors": [{
+                    "id": "EmployeeID_JWT",
+                    "constraints": {
+                        "fields": [{"path": ["$.type"], "filter_value": "EmployeeIDCredential"}]
+                    }
+                }],
+            }),
+        )
+        .await;
+    let request_uri = created["request_uri"].as_str().expect("should have request URI");
+
+    let requested = services.wallet(user, "/requests", &json!({ "request": request_uri })).await;
+    let matches = requested["matches"].as_array().expect("should have matches");
+    assert!(
+        matches.iter().any(|m| m["id"] == credential_id),
+        "issued credential should match request: {matches:?}"
+    );
+    let flow_id = requested["flow_id"].as_str().expect("should have flow ID");
+
+    // The verifier only responds successfully once it has verified the
+    // presentation.
+    services
+        .wallet(
+            user,
+            &format!("/flows/{flow_id}/authorize"),
+            &json!({ "credential_ids": [credential_id] }),
+        )
+        .await;
+}
+
+// Offer and presentation definition passed by value.
+#[tokio::test]
+async fn issue_and_present() {
+    let services = Services::start().await;
+    let credential_id = issue(&services, "interop-by-value", false).await;
+    present(&services, "interop-by-value", &credential_id).await;
+}
+
+// Offer passed by reference.
+#[tokio::test]
+async fn offer_by_reference() {
+    let services = Services::start().await;
+    issue(&services, "interop-by-reference", true).await;
+}
@@ -10,9 +10,11 @@ import SwiftUI
 
 struct ContentView: View {
     @ObservedObject var core: Core
+    @Environment(\.scenePhase) private var scenePhase
     
     init(core: Core) {
         self.core = core
+        core.update(Event.locale(Locale.current.identifier(.bcp47)))
         core.update(Event.credential(CredentialEvent.ready))
     }
     
@@ -20,31 +22,57 @@ struct ContentView: View {
         NavigationStack() {
             switch core.view.active_view {
             case .credentialList:
-                CredentialList(credentials: core.view.credential_view.credentials)
+                CredentialList(credentials: core.view.credential_view.credentials, pending: core.view.credential_view.pending)
                     .navBar(context: core.view.active_view)
             case .credentialDetail:
                 if let credential = core.view.credential_view.credentials.first(where: { $0.id == core.view.credential_view.id }) {
                     CredentialDetailView(credential: credential)
                         .navBar(context: core.view.active_view)
                 }
+            case .credentialDeleted:
+                if let deleted = core.view.credential_view.deleted {
+                    CredentialDeleted(deleted: deleted)
+                        .navBar(context: core.view.active_view)
+                }
+            case .linkEntry:
+                LinkEntry().navBar(context: core.view.active_view)
             case .issuanceScan:
                 IssuanceScan(core: Core()).navBar(context: core.view.active_view)
             case .issuanceOffer:
-                IssuanceOffer(credential: core.view.issuance_view.credentials[0]).navBar(context: core.view.active_view)
+                IssuanceOffer(offered: core.view.issuance_view.credentials).navBar(context: core.view.active_view)
             case .issuancePin:
-                IssuancePin(txCode: core.view.issuance_view.tx_code)
+                IssuancePin(
+                    txCode: core.view.issuance_view.tx_code,
+                    attemptsRemaining: core.view.issuance_view.pin_attempts_remaining
+                )
                     .navBar(context: core.view.active_view)
             case .presentationScan:
                 PresentationScan(core: Core()).navBar(context: core.view.active_view)
             case .presentationRequest:
-                PresentationRequest(credentials: core.view.presentation_view.credentials).navBar(context: core.view.active_view)
+                PresentationRequest(verifier: core.view.presentation_view.verifier, credentials: core.view.presentation_view.credentials).navBar(context: core.view.active_view)
             case .presentationSuccess:
                 PresentationSuccess().navBar(context: core.view.active_view)
             case .error:
-                ErrorDetail(message: core.view.error)
+                ErrorDetail(message: core.view.error, recovery: core.view.recovery)
             }
         }
         .environment(\.update, { e in core.update(e)})
+        .onOpenURL { url in
+            core.update(Event.link(LinkEvent.open(url.absoluteString)))
+        }
+        .onChange(of: scenePhase) { _, phase in
+            // Poll issuers for any deferred credentials when the app returns to the foreground.
+            if phase == .active {
+                core.update(Event.deferred(DeferredEvent.resume))
+            }
+            // Save any in-progress flow in case the OS kills the app while it is in the background.
+            if phase == .background {
+                core.update(Event.flow(FlowEvent.suspend))
+            }
+        }
+        .onReceive(NotificationCenter.default.publisher(for: NSLocale.currentLocaleDidChangeNotification)) { _ in
+            core.update(Event.locale(Locale.current.identifier(.bcp47)))
+        }
     }
 }
 
@@ -36,7 +36,7 @@ struct NavigationBar: ToolbarContent {
                 }
             }.disabled(
                 context == .init(.issuanceScan) || context == .init(.issuanceOffer) || context == .init(.issuancePin)
-                || context == .init(.presentationScan)
+                || context == .init(.presentationScan) || context == .init(.linkEntry)
             )
             Spacer()
             Button(action: {
@@ -48,6 +48,7 @@ struct NavigationBar: ToolbarContent {
                 }
             }.disabled(
                 context == .init(.presentationScan) || context == .init(.presentationRequest)
+                || context == .init(.linkEntry)
             )
         }
     }
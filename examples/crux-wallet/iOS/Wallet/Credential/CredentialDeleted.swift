@@ -0,0 +1,70 @@
+//
+//  CredentialDeleted.swift
+//  Wallet
+//
+//  Created by Andrew Goldie on 03/03/2025.
+//
+
+import SharedTypes
+import SwiftUI
+
+struct CredentialDeleted: View {
+    @Environment(\.update) var update
+    var deleted: DeletedCredentialView
+
+    var body: some View {
+        VStack(spacing: 12) {
+            Image(systemName: "trash.circle").font(.largeTitle).foregroundColor(.red)
+            Text("\(deleted.credential.name) has been removed from your wallet.")
+                .multilineTextAlignment(.center)
+            HStack {
+                if deleted.issuer_notified == .sending {
+                    ProgressView()
+                }
+                Text(issuerStatus).font(.caption).opacity(0.5).multilineTextAlignment(.center)
+            }
+            Button("OK") {
+                update(Event.credential(CredentialEvent.ready))
+            }
+            .padding()
+            .buttonStyle(.borderedProminent)
+        }
+        .padding(.horizontal, 20)
+    }
+
+    var issuerStatus: String {
+        let issuer = deleted.credential.issuer_name.isEmpty ? deleted.credential.issuer : deleted.credential.issuer_name
+        switch deleted.issuer_notified {
+        case .sending:
+            return "Letting \(issuer) know..."
+        case .notRequested:
+            return "\(issuer) did not ask to be told about deleted credentials."
+        case .sent:
+            return "\(issuer) has been told the credential was deleted."
+        case .failed:
+            return "\(issuer) could not be told the credential was deleted."
+        }
+    }
+}
+
+#Preview {
+    let credential = Credential(
+        id: "http://credibil.io/credentials/EmployeeIDCredential",
+        issuer: "http://credibil.io",
+        issuer_name: "Credibil",
+        issued: "encoded",
+        type: ["VerifiableCredential", "EmployeeIDCredential"],
+        format: "jwt_vc_json",
+        claims: [:],
+        issuance_date: "Tue, 29 Nov 2024",
+        valid_from: "",
+        valid_until: "",
+        name: "Employee ID",
+        description: "Credibil employee ID credential",
+        background_color: "#323ed2",
+        text_color: "#ffffff",
+        logo: ImageData(data: "", media_type: ""),
+        background: ImageData(data: "", media_type: "")
+    )
+    CredentialDeleted(deleted: DeletedCredentialView(credential: credential, issuer_notified: .sent))
+}
@@ -18,10 +18,26 @@ extension View {
 struct CredentialList: View {
     @Environment(\.update) var update
     var credentials: [Credential]
+    var pending: [PendingCredentialView] = []
     
     var body: some View {
         VStack {
             Text("Credentials").font(.title).fontWeight(.bold)
+            if !pending.isEmpty {
+                VStack(alignment: .leading) {
+                    Text("Waiting on issuer").font(.headline)
+                    ForEach(pending, id: \.transaction_id) { item in
+                        HStack {
+                            ProgressView()
+                            Text(item.credential.name)
+                            Spacer()
+                            Text(item.credential.issuer_name).foregroundStyle(.secondary)
+                        }
+                    }
+                }
+                .padding(.horizontal, 16)
+                .padding(.bottom, 8)
+            }
             ZStack {
                 ForEach(0..<credentials.count, id: \.self)  {index in
                     CredentialCard(credential: credentials[index])
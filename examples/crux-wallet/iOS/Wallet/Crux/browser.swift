@@ -0,0 +1,50 @@
+//
+//  browser.swift
+//  Wallet
+//
+
+import AuthenticationServices
+import Foundation
+import SharedTypes
+
+/// Provides the window the authentication session is presented from.
+class BrowserContextProvider: NSObject, ASWebAuthenticationPresentationContextProviding {
+    func presentationAnchor(for session: ASWebAuthenticationSession) -> ASPresentationAnchor {
+        let scene = UIApplication.shared.connectedScenes.first as? UIWindowScene
+        return scene?.windows.first { $0.isKeyWindow } ?? ASPresentationAnchor()
+    }
+}
+
+private let contextProvider = BrowserContextProvider()
+
+@MainActor
+func requestBrowser(_ request: BrowserOperation) async -> BrowserResult {
+    switch request {
+    case .authorize(let url, let callbackScheme):
+        print(">>> browser authorize \(url)")
+        guard let authUrl = URL(string: url) else {
+            return .err(error: .invalidRequest(message: "invalid authorization URL"))
+        }
+        return await withCheckedContinuation { continuation in
+            let session = ASWebAuthenticationSession(
+                url: authUrl,
+                callbackURLScheme: callbackScheme
+            ) { callbackUrl, error in
+                if let error = error as? ASWebAuthenticationSessionError,
+                    error.code == .canceledLogin
+                {
+                    continuation.resume(returning: .err(error: .cancelled))
+                    return
+                }
+                guard let callbackUrl = callbackUrl else {
+                    continuation.resume(returning: .err(error: .invalidResponse(
+                        message: error?.localizedDescription ?? "no redirect URL")))
+                    return
+                }
+                continuation.resume(returning: .ok(response: .redirected(url: callbackUrl.absoluteString)))
+            }
+            session.presentationContextProvider = contextProvider
+            session.start()
+        }
+    }
+}
@@ -73,10 +73,25 @@ class Core: ObservableObject {
                     processEffect(request)
                 }
             }
+        case let .browser(req):
+            Task {
+                let response = await requestBrowser(req)
+                let effects = [UInt8](handleResponse(request.id, Data(try! response.bincodeSerialize())))
+                let requests: [Request] = try! .bincodeDeserialize(input: effects)
+                for request in requests {
+                    processEffect(request)
+                }
+            }
         case let .keyStore(req):
             Task {
-                let response = try! await requestKeyStore(req).get()
-                let effects = [UInt8](handleResponse(request.id, Data(try! KeyStoreResult.ok(response: response).bincodeSerialize())))
+                let result: KeyStoreResult
+                switch await requestKeyStore(req) {
+                case .success(let response):
+                    result = .ok(response: response)
+                case .failure(let error):
+                    result = .err(error: .invalidRequest(message: String(describing: error)))
+                }
+                let effects = [UInt8](handleResponse(request.id, Data(try! result.bincodeSerialize())))
                 let requests: [Request] = try! .bincodeDeserialize(input: effects)
                 for request in requests {
                     processEffect(request)
@@ -111,6 +111,49 @@ struct Keyring {
         }
         return key
     }
+    
+    /// List the IDs of the keys stored in KeyChain for a purpose
+    static func listKeys(purpose: String) -> [String] {
+        let query: CFDictionary = [
+            kSecClass: kSecClassKey,
+            kSecMatchLimit: kSecMatchLimitAll,
+            kSecReturnAttributes: cfTrue
+        ] as CFDictionary
+        var ref: CFTypeRef?
+        let status = SecItemCopyMatching(query, &ref)
+        guard status == errSecSuccess, let items = ref as? [[CFString: Any]] else {
+            if status != errSecItemNotFound {
+                print(">>> keychain error \(status)")
+            }
+            return []
+        }
+        let prefix = "\(baseTag)."
+        let suffix = ".\(purpose)"
+        return items.compactMap { item in
+            guard let tagData = item[kSecAttrApplicationTag] as? Data,
+                let tag = String(data: tagData, encoding: .utf8),
+                tag.hasPrefix(prefix), tag.hasSuffix(suffix),
+                tag.count > prefix.count + suffix.count
+            else {
+                return nil
+            }
+            return String(tag.dropFirst(prefix.count).dropLast(suffix.count))
+        }
+    }
+    
+    /// Delete a key from KeyChain
+    static func deleteKey(id: String, purpose: String) -> Bool {
+        let query: CFDictionary = [
+            kSecClass: kSecClassKey,
+            kSecAttrApplicationTag: keyTag(id: id, purpose: purpose)
+        ] as CFDictionary
+        let status = SecItemDelete(query)
+        guard status == errSecSuccess || status == errSecItemNotFound else {
+            print(">>> keychain error \(status)")
+            return false
+        }
+        return true
+    }
 }
 
 let key_length: Int = 32
@@ -130,5 +173,31 @@ func requestKeyStore(_ request: KeyStoreOperation) async -> Result<KeyStoreRespo
             return .success(.retrieved(key: entry))
         }
         return .failure(.message("key not found or could not be generated"))
+    case .create(let id, let purpose):
+        print(">>> keystore create \(id) \(purpose)")
+        if let createdKey = Keyring.createKey(id: id, purpose: purpose) {
+            let entry = KeyStoreEntry(data: Array(createdKey))
+            return .success(.created(key: entry))
+        }
+        return .failure(.message("key already exists or could not be generated"))
+    case .list(let purpose):
+        print(">>> keystore list \(purpose)")
+        return .success(.listed(ids: Keyring.listKeys(purpose: purpose)))
+    case .rotate(let id, let purpose):
+        print(">>> keystore rotate \(id) \(purpose)")
+        guard Keyring.deleteKey(id: id, purpose: purpose) else {
+            return .failure(.message("key could not be removed"))
+        }
+        if let createdKey = Keyring.createKey(id: id, purpose: purpose) {
+            let entry = KeyStoreEntry(data: Array(createdKey))
+            return .success(.rotated(key: entry))
+        }
+        return .failure(.message("replacement key could not be generated"))
+    case .delete(let id, let purpose):
+        print(">>> keystore delete \(id) \(purpose)")
+        guard Keyring.deleteKey(id: id, purpose: purpose) else {
+            return .failure(.message("key could not be deleted"))
+        }
+        return .success(.deleted)
     }
 }
@@ -11,19 +11,40 @@ import SwiftUI
 struct ErrorDetail: View {
     @Environment(\.update) var update
     var message: String?
+    var recovery: Recovery = .dismiss
     
     var body: some View {
         VStack {
             Text(message ?? "No current error")
+            if let label = recoverLabel {
+                Button(label) {
+                    update(Event.recovery(RecoveryEvent.recover))
+                }
+                .padding()
+                .buttonStyle(.borderedProminent)
+            }
             Button("Dismiss") {
                 update(Event.credential(CredentialEvent.ready))
             }
             .padding()
-            .buttonStyle(.borderedProminent)
+            .buttonStyle(.bordered)
+        }
+    }
+    
+    var recoverLabel: String? {
+        switch recovery {
+        case .dismiss:
+            return nil
+        case .retryToken:
+            return "Try Again"
+        case .rescanOffer:
+            return "Scan Offer Again"
+        case .rescanRequest:
+            return "Scan Request Again"
         }
     }
 }
 
 #Preview {
-    ErrorDetail(message: "An error has occurred.")
+    ErrorDetail(message: "An error has occurred.", recovery: .retryToken)
 }
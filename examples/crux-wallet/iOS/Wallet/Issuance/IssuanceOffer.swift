@@ -11,44 +11,73 @@ import SwiftUI
 struct IssuanceOffer: View {
     @Environment(\.update) var update
     @State private var waiting: Bool = false
-    // This is an "empty" credential - a credential object representing a credential configuration
-    var credential: Credential
-    
+    // Each offered credential holds an "empty" credential - a credential object representing a
+    // credential configuration
+    var offered: [OfferedCredentialView]
+
     var body: some View {
         VStack {
             if waiting {
-                Text("Retrieving Credential").font(.title).padding(.bottom, 8)
+                Text("Retrieving Credentials").font(.title).padding(.bottom, 8)
                 ProgressView()
             } else {
-                Text("Accept Credential?").font(.title).padding(.bottom, 8)
-                Text(credential.name).font(.title2).fontWeight(.bold)
-                CredentialCard(credential: credential)
-                VStack(alignment: .leading) {
-                    DetailItem(title: "Description", content: credential.description)
-                    DetailItem(title: "Issued by", content: credential.issuer_name + " (" + credential.issuer + ")")
-                    Text("Details").font(.headline).fontWeight(.bold).padding([.vertical, .horizontal], 12)
-                    // TODO: Collect which claims the user will accept. For now it's all or nothing.
-                    ForEach(credential.claims.keys.sorted(), id: \.self) {key in
-                        if let value = credential.claims[key] {
-                            ClaimTitleList(claims: value)
-                        }
+                Text(offered.count == 1 ? "Accept Credential?" : "Accept Credentials?").font(.title).padding(.bottom, 8)
+                ScrollView {
+                    ForEach(offered, id: \.config_id) { item in
+                        OfferedCredentialItem(item: item, selectable: offered.count > 1)
+                    }
+                }
+                Spacer()
+                HStack {
+                    Button("Cancel") {
+                        update(Event.issuance(IssuanceEvent.cancel))
                     }
                     Spacer()
-                    HStack {
-                        Button("Cancel") {
-                            update(Event.issuance(IssuanceEvent.cancel))
-                        }
-                        Spacer()
-                        Button("Accept") {
-                            waiting = true
-                            update(Event.issuance(IssuanceEvent.accepted))
-                        }
-                        .buttonStyle(.borderedProminent)
+                    Button("Accept") {
+                        waiting = true
+                        update(Event.issuance(IssuanceEvent.accepted))
                     }
-                    .padding(.horizontal, 64)
+                    .buttonStyle(.borderedProminent)
+                    .disabled(!offered.contains(where: { $0.accepted }))
+                }
+                .padding(.horizontal, 64)
+            }
+        }
+    }
+}
+
+struct OfferedCredentialItem: View {
+    @Environment(\.update) var update
+    var item: OfferedCredentialView
+    // Only show the include/exclude toggle when there is a choice to make.
+    var selectable: Bool
+
+    var body: some View {
+        let credential = item.credential
+        VStack(alignment: .leading) {
+            HStack {
+                Text(credential.name).font(.title2).fontWeight(.bold)
+                Spacer()
+                if selectable {
+                    Toggle("", isOn: Binding(
+                        get: { item.accepted },
+                        set: { _ in update(Event.issuance(IssuanceEvent.toggle(item.config_id))) }
+                    ))
+                    .labelsHidden()
+                }
+            }
+            CredentialCard(credential: credential)
+            DetailItem(title: "Description", content: credential.description)
+            DetailItem(title: "Issued by", content: credential.issuer_name + " (" + credential.issuer + ")")
+            Text("Details").font(.headline).fontWeight(.bold).padding([.vertical, .horizontal], 12)
+            // TODO: Collect which claims the user will accept. For now it's all or nothing.
+            ForEach(credential.claims.keys.sorted(), id: \.self) {key in
+                if let value = credential.claims[key] {
+                    ClaimTitleList(claims: value)
                 }
             }
         }
+        .padding(.bottom, 16)
     }
 }
 
@@ -85,5 +114,7 @@ struct IssuanceOffer: View {
             mediaType: "image/png"
         )
     )
-    IssuanceOffer(credential: credential)
+    IssuanceOffer(offered: [
+        OfferedCredentialView(config_id: "EmployeeID_JWT", credential: credential, accepted: true, stored: false)
+    ])
 }
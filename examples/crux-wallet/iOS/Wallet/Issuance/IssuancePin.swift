@@ -11,6 +11,7 @@ import SwiftUI
 struct IssuancePin: View {
     @Environment(\.update) var update
     var txCode: TxCode
+    var attemptsRemaining: UInt32?
     @State private var pin: String = ""
     @State private var waiting: Bool = false
     
@@ -27,6 +28,11 @@ struct IssuancePin: View {
             } else {
                 Text("Transaction Code").font(.title).padding(.bottom, 8)
                 Text(txCode.description).padding(.bottom, 8)
+                if let attemptsRemaining {
+                    Text("Incorrect code. \(attemptsRemaining) \(attemptsRemaining == 1 ? "attempt" : "attempts") remaining.")
+                        .foregroundStyle(.red)
+                        .padding(.bottom, 8)
+                }
                 TextField("Transaction Code ", text: $pin)
                     .textFieldStyle(.roundedBorder)
                     .padding()
@@ -43,6 +49,11 @@ struct IssuancePin: View {
                 .padding()
             }
         }
+        .onChange(of: attemptsRemaining) {
+            // The issuer rejected the code, so ask for it again.
+            pin = ""
+            waiting = false
+        }
     }
 }
 
@@ -52,5 +63,5 @@ struct IssuancePin: View {
         length: 6,
         description: "Enter the code sent to you by email"
     )
-    IssuancePin(txCode: txCode)
+    IssuancePin(txCode: txCode, attemptsRemaining: 2)
 }
@@ -42,6 +42,9 @@ struct IssuanceScan: View {
                 .sheet(isPresented: $scannerVisible) {
                     self.scannerSheet
                 }
+                Button("Paste Link", systemImage: "doc.on.clipboard") {
+                    update(Event.link(LinkEvent.enter))
+                }
             }
         }
     }
@@ -51,14 +54,9 @@ struct IssuanceScan: View {
         switch result {
         case .success(let code):
             self.scanResult = "Offer scanned"
-            let parts = code.string.components(separatedBy: "credential_offer=")
-            guard parts.count == 2 else {
-                self.scanResult = "Invalid QR code"
-                return
-            }
-            let offer = parts[1]
+            let offer = code.string
             debugPrint("Offer: \(offer)")
-            update(Event.issuance(IssuanceEvent.offer(offer)))
+            update(Event.link(LinkEvent.open(offer)))
             self.offer = offer
         case .failure(let error):
             debugPrint(error.localizedDescription)
@@ -0,0 +1,56 @@
+//
+//  LinkEntry.swift
+//  Wallet
+//
+
+import SharedTypes
+import SwiftUI
+
+struct LinkEntry: View {
+    @Environment(\.update) var update
+    @State private var link: String = ""
+    @State private var waiting: Bool = false
+    
+    enum FocusField: Hashable {
+        case linkEntry
+    }
+    @FocusState private var focusField: FocusField?
+    
+    var body: some View {
+        VStack {
+            if waiting {
+                ProgressView()
+            } else {
+                Text("Paste Link").font(.title).padding(.bottom, 8)
+                Text("Paste or type a credential offer or presentation request link").padding(.bottom, 8)
+                TextField("Link", text: $link, axis: .vertical)
+                    .textFieldStyle(.roundedBorder)
+                    .textInputAutocapitalization(.never)
+                    .autocorrectionDisabled()
+                    .keyboardType(.URL)
+                    .padding()
+                    .focused($focusField, equals: .linkEntry)
+                    .onAppear {
+                        self.focusField = .linkEntry
+                    }
+                HStack {
+                    PasteButton(payloadType: String.self) { strings in
+                        guard let pasted = strings.first else { return }
+                        link = pasted
+                    }
+                    Button("Open") {
+                        waiting = true
+                        update(Event.link(LinkEvent.open(link)))
+                    }
+                    .buttonStyle(.borderedProminent)
+                    .disabled(link.trimmingCharacters(in: .whitespacesAndNewlines).isEmpty)
+                }
+                .padding()
+            }
+        }
+    }
+}
+
+#Preview {
+    LinkEntry()
+}
@@ -11,7 +11,8 @@ import SwiftUI
 struct PresentationRequest: View {
     @Environment(\.update) var update
     @State private var waiting: Bool = false
-    var credentials: [Credential]
+    var verifier: VerifierView
+    var credentials: [MatchedCredentialView]
 
     var body: some View {
         VStack {
@@ -21,13 +22,10 @@ struct PresentationRequest: View {
             }
             else {
                 Text("Send Credentials?").font(.title).padding(.bottom, 8)
-                ZStack {
-                    ForEach(0..<credentials.count, id: \.self)  {index in
-                        CredentialCard(credential: credentials[index])
-                            .stacked(at: credentials.count - index, in: credentials.count)
-                            .onTapGesture {
-                                update(Event.credential(CredentialEvent.select(credentials[index].id)))
-                            }
+                VerifierHeader(verifier: verifier)
+                ScrollView {
+                    ForEach(credentials, id: \.credential.id) { item in
+                        MatchedCredentialItem(item: item, selectable: credentials.count > 1)
                     }
                 }
                 Spacer()
@@ -41,6 +39,7 @@ struct PresentationRequest: View {
                         update(Event.presentation(PresentationEvent.approved))
                     }
                     .buttonStyle(.borderedProminent)
+                    .disabled(!credentials.contains(where: { $0.selected }))
                 }
                 .padding(.horizontal, 64)
             }
@@ -48,6 +47,73 @@ struct PresentationRequest: View {
     }
 }
 
+struct VerifierHeader: View {
+    var verifier: VerifierView
+
+    var body: some View {
+        VStack(spacing: 8) {
+            HStack {
+                if let logo = verifier.logo_uri, let url = URL(string: logo) {
+                    AsyncImage(url: url) { image in
+                        image.resizable().scaledToFit()
+                    } placeholder: {
+                        ProgressView()
+                    }
+                    .frame(width: 40, height: 40)
+                }
+                VStack(alignment: .leading) {
+                    Text(verifier.name).font(.headline)
+                    switch verifier.trust {
+                    case .trusted:
+                        Label("Trusted verifier", systemImage: "checkmark.seal.fill")
+                            .font(.caption).foregroundStyle(.green)
+                    case .unknown:
+                        Label("Unknown verifier", systemImage: "exclamationmark.triangle.fill")
+                            .font(.caption).foregroundStyle(.orange)
+                    }
+                }
+                Spacer()
+            }
+            if let purpose = verifier.purpose {
+                Text(purpose).font(.subheadline).frame(maxWidth: .infinity, alignment: .leading)
+            }
+        }
+        .padding(.horizontal)
+        .padding(.bottom, 8)
+    }
+}
+
+struct MatchedCredentialItem: View {
+    @Environment(\.update) var update
+    var item: MatchedCredentialView
+    // Only show the include/exclude toggle when there is a choice to make.
+    var selectable: Bool
+
+    var body: some View {
+        let credential = item.credential
+        VStack(alignment: .leading) {
+            HStack {
+                Text(credential.name).font(.title2).fontWeight(.bold)
+                Spacer()
+                if selectable {
+                    Toggle("", isOn: Binding(
+                        get: { item.selected },
+                        set: { _ in update(Event.presentation(PresentationEvent.toggle(credential.id))) }
+                    ))
+                    .labelsHidden()
+                }
+            }
+            CredentialCard(credential: credential)
+                .onTapGesture {
+                    update(Event.credential(CredentialEvent.select(credential.id)))
+                }
+            Text("Will share").font(.headline).fontWeight(.bold).padding([.vertical, .horizontal], 12)
+            ClaimList(claims: item.disclosed)
+        }
+        .padding(.bottom, 16)
+    }
+}
+
 #Preview {
     let employeeClaims: [String: [ClaimView]] = ["did:key:z6Mkj8Jr1rg3YjVWWhg7ahEYJibqhjBgZt1pDCbT4Lv7D4HX": [
         .init(name: "Address.Locality", value: "Wellington"),
@@ -109,6 +175,16 @@ struct PresentationRequest: View {
             mediaType: "image/png"
         )
     )
-    let credentials = [employee, developer]
-    PresentationRequest(credentials: credentials)
+    let credentials = [
+        MatchedCredentialView(credential: employee, disclosed: employeeClaims.values.flatMap { $0 }, selected: true),
+        MatchedCredentialView(credential: developer, disclosed: developerClaims.values.flatMap { $0 }, selected: false)
+    ]
+    let verifier = VerifierView(
+        client_id: "http://localhost:8080",
+        name: "Credibil Verifier",
+        logo_uri: nil,
+        purpose: "To verify employment",
+        trust: .trusted
+    )
+    PresentationRequest(verifier: verifier, credentials: credentials)
 }
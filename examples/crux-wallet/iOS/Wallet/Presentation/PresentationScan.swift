@@ -42,6 +42,9 @@ struct PresentationScan: View {
                 .sheet(isPresented: $scannerVisible) {
                     self.scannerSheet
                 }
+                Button("Paste Link", systemImage: "doc.on.clipboard") {
+                    update(Event.link(LinkEvent.enter))
+                }
             }
         }
     }
@@ -53,7 +56,7 @@ struct PresentationScan: View {
             self.scanResult = "Request scanned"
             let url = code.string
             debugPrint("Request URL: \(url)")
-            update(Event.presentation(PresentationEvent.request(url)))
+            update(Event.link(LinkEvent.open(url)))
             self.requestUrl = url
         case .failure(let error):
             debugPrint(error.localizedDescription)
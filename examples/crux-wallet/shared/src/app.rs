@@ -2,8 +2,13 @@
 //! the model, events, and effects that drive the application.
 
 pub mod credential;
+pub mod deferred;
+pub mod flow;
 pub mod issuance;
+pub mod link;
+pub mod notification;
 pub mod presentation;
+pub mod recovery;
 
 use std::ops::Deref;
 
@@ -11,13 +16,20 @@ use credential::{credential_event, CredentialEvent};
 use crux_core::render::{render, Render};
 use crux_core::Command;
 use crux_kv::KeyValue;
+use deferred::{deferred_event, DeferredEvent};
+use flow::{flow_event, FlowEvent};
 use issuance::{issuance_event, IssuanceEvent};
+use link::{link_event, LinkEvent};
+use notification::{notification_event, NotificationEvent};
 use presentation::{presentation_event, PresentationEvent};
+use recovery::{recovery_event, RecoveryEvent};
 use serde::{Deserialize, Serialize};
 
+use crate::capabilities::browser::Browser;
 use crate::capabilities::key::KeyStore;
 use crate::capabilities::sse::ServerSentEvents;
 use crate::capabilities::store::Store;
+use crate::config;
 use crate::model::{Model, State};
 use crate::view::ViewModel;
 
@@ -35,6 +47,13 @@ pub enum Aspect {
     /// Display of a single credential.
     CredentialDetail,
 
+    /// Confirmation that a credential has been deleted and whether its issuer
+    /// has been notified.
+    CredentialDeleted,
+
+    /// Paste or type a link to a credential offer or presentation request.
+    LinkEntry,
+
     /// Trigger a credential issuance using an offer QR code.
     IssuanceScan,
 
@@ -63,16 +82,39 @@ pub enum Aspect {
 /// Events that can be sent to the wallet application.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Event {
-    /// Error event is emitted by the core when an error occurs.
+    /// Error event is emitted by the core when an error occurs that the user
+    /// can't recover from other than by dismissing it. Recoverable errors are
+    /// raised as `RecoveryEvent::Failed`.
     #[serde(skip)]
     Error(String),
 
+    /// Error recovery events.
+    Recovery(RecoveryEvent),
+
+    /// Event emitted by the shell with the user's preferred locale as a BCP 47
+    /// language tag (e.g. `en-NZ`). Sent on launch and whenever the user
+    /// changes their language settings.
+    Locale(String),
+
+    /// Offer and presentation request link events.
+    Link(LinkEvent),
+
     /// Credential events.
     Credential(CredentialEvent),
 
     /// Issuance events.
     Issuance(IssuanceEvent),
 
+    /// Deferred credential events.
+    Deferred(DeferredEvent),
+
+    /// Saved flow events.
+    Flow(FlowEvent),
+
+    /// Issuer notification events. Emitted by the core only.
+    #[serde(skip)]
+    Notification(NotificationEvent),
+
     // Presentation events.
     Presentation(PresentationEvent),
 }
@@ -82,6 +124,7 @@ pub enum Event {
 #[derive(crux_core::macros::Effect)]
 pub struct Capabilities {
     pub render: Render<Event>,
+    pub browser: Browser<Event>,
     pub http: crux_http::Http<Event>,
     pub key_store: KeyStore<Event>,
     pub kv: KeyValue<Event>,
@@ -107,8 +150,17 @@ impl crux_core::App for App {
                 *model = model.error(&e);
                 render()
             }
+            Event::Locale(locale) => {
+                config::set_locale(&locale);
+                render()
+            }
+            Event::Recovery(ev) => recovery_event(ev, model),
+            Event::Link(ev) => link_event(ev, model),
             Event::Credential(ev) => credential_event(ev, model),
             Event::Issuance(ev) => issuance_event(ev, model),
+            Event::Deferred(ev) => deferred_event(ev, model),
+            Event::Flow(ev) => flow_event(ev, model),
+            Event::Notification(ev) => notification_event(ev),
             Event::Presentation(ev) => presentation_event(ev, model),
         }
     }
@@ -128,8 +180,9 @@ impl crux_core::App for App {
             State::Presentation(state) => {
                 vm.presentation_view = state.deref().clone().into();
             }
-            State::Error(error) => {
-                vm.error = error.clone();
+            State::Error(state) => {
+                vm.error = state.message.clone();
+                vm.recovery = state.recovery.clone();
             }
         }
         vm
@@ -1,10 +1,14 @@
 use crux_core::{render::render, Command};
+use crux_http::{command::Http, http::mime};
 use serde::{Deserialize, Serialize};
 
-use super::{Effect, Event};
+use super::{flow::restore, notification::subscribe, Effect, Event};
 use crate::{
-    capabilities::store::{Catalog, StoreCommand, StoreEntry, StoreError},
-    model::Model,
+    capabilities::{
+        key::{KeyStoreCommand, KeyStoreError},
+        store::{Catalog, StoreCommand, StoreEntry, StoreError},
+    },
+    model::{IssuerNotification, IssuerNotified, Model},
 };
 
 /// Events that can be sent to the wallet application that pertain to
@@ -18,7 +22,8 @@ pub enum CredentialEvent {
     /// stored credentials for detailed display.
     Select(String),
 
-    /// Event emitted by the shell to delete a credential from the wallet.
+    /// Event emitted by the shell to delete a credential from the wallet. The
+    /// credential's issuer is notified if it asked to be.
     Delete(String),
 
     /// Event emitted by the core when the store capability has loaded
@@ -35,6 +40,16 @@ pub enum CredentialEvent {
     /// credential.
     #[serde(skip)]
     Deleted(Result<(), StoreError>),
+
+    /// Event emitted by the core when the key store capability has deleted
+    /// the key a deleted credential was bound to.
+    #[serde(skip)]
+    KeyDeleted(Result<(), KeyStoreError>),
+
+    /// Event emitted by the core when it has finished notifying the issuer
+    /// of a deleted credential.
+    #[serde(skip)]
+    IssuerNotified(IssuerNotified),
 }
 
 /// Credential event processing.
@@ -42,20 +57,25 @@ pub fn credential_event(event: CredentialEvent, model: &mut Model) -> Command<Ef
     match event {
         CredentialEvent::Ready => ready(model),
         CredentialEvent::Select(id) => select(id, model),
-        CredentialEvent::Delete(id) => delete(id),
+        CredentialEvent::Delete(id) => delete(id, model),
         CredentialEvent::Loaded(Ok(entries)) => loaded(entries, model),
         CredentialEvent::Stored(Ok(())) | CredentialEvent::Deleted(Ok(())) => refresh_credentials(),
         CredentialEvent::Loaded(Err(error))
         | CredentialEvent::Stored(Err(error))
         | CredentialEvent::Deleted(Err(error)) => store_error(error, model),
+        CredentialEvent::KeyDeleted(Ok(())) => Command::done(),
+        CredentialEvent::KeyDeleted(Err(error)) => keystore_error(error, model),
+        CredentialEvent::IssuerNotified(notified) => issuer_notified(notified, model),
     }
 }
 
 /// Process a `CredentialEvent::Ready` event. Load the list of credentials from
-/// the credential store.
+/// the credential store, poll issuers for any pending credentials, restore any
+/// flow saved when the app was last backgrounded, and subscribe to issuer
+/// notifications.
 fn ready(model: &mut Model) -> Command<Effect, Event> {
     *model = model.ready();
-    refresh_credentials()
+    Command::all([restore(), subscribe()])
 }
 
 /// Process a `CredentialEvent::Select` event. Update the model with selected
@@ -66,10 +86,77 @@ fn select(id: String, model: &mut Model) -> Command<Effect, Event> {
 }
 
 /// Process a `CredentialEvent::Delete` event. Delete the selected credential
-/// from the credential store.
-fn delete(id: String) -> Command<Effect, Event> {
-    StoreCommand::delete("credential", id)
-        .then_send(|res| Event::Credential(CredentialEvent::Deleted(res)))
+/// from the credential store, along with the key it is bound to if no other
+/// credential uses the key, and notify the issuer. The deletion is confirmed
+/// to the user.
+fn delete(id: String, model: &mut Model) -> Command<Effect, Event> {
+    let unbound_key = model.get_unbound_key(&id);
+    *model = model.credential_deleted(&id);
+    let mut commands = vec![
+        render(),
+        StoreCommand::delete("credential", id.clone())
+            .then_send(|res| Event::Credential(CredentialEvent::Deleted(res))),
+        notify_deleted(id),
+    ];
+    if let Some(key_id) = unbound_key {
+        commands.push(
+            KeyStoreCommand::delete(key_id, "signing")
+                .then_send(|res| Event::Credential(CredentialEvent::KeyDeleted(res))),
+        );
+    }
+    Command::all(commands)
+}
+
+/// Tell the issuer a credential has been deleted if it returned a
+/// notification ID when the credential was issued. The notification details
+/// are removed from the store whether or not the issuer can be reached: the
+/// credential has gone so there is nothing to retry.
+fn notify_deleted(id: String) -> Command<Effect, Event> {
+    Command::new(|ctx| async move {
+        let entries = StoreCommand::list(Catalog::Notification.to_string())
+            .into_future(ctx.clone())
+            .await
+            .unwrap_or_default();
+        let notification = entries.into_iter().find_map(|entry| match entry {
+            StoreEntry::Data(data) => serde_json::from_slice::<IssuerNotification>(&data)
+                .ok()
+                .filter(|n| n.credential_id == id),
+            StoreEntry::None => None,
+        });
+        let Some(notification) = notification else {
+            return ctx.send_event(Event::Credential(CredentialEvent::IssuerNotified(
+                IssuerNotified::NotRequested,
+            )));
+        };
+
+        let sent = match Http::<Effect, Event>::post(notification.notification_endpoint.clone())
+            .header("accept", mime::JSON)
+            .header("Authorization", format!("Bearer {}", notification.access_token))
+            .body_json(&notification.deleted())
+        {
+            Ok(request) => request
+                .build()
+                .into_future(ctx.clone())
+                .await
+                .is_ok_and(|res| res.status().is_success()),
+            Err(_) => false,
+        };
+        // Nothing is lost if the details cannot be removed: they refer to a
+        // credential that no longer exists.
+        let _ = StoreCommand::delete(Catalog::Notification.to_string(), id)
+            .into_future(ctx.clone())
+            .await;
+
+        let notified = if sent { IssuerNotified::Sent } else { IssuerNotified::Failed };
+        ctx.send_event(Event::Credential(CredentialEvent::IssuerNotified(notified)));
+    })
+}
+
+/// Process a `CredentialEvent::IssuerNotified` event. Show the user whether
+/// the issuer of the deleted credential was notified.
+fn issuer_notified(notified: IssuerNotified, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.issuer_notified(notified);
+    render()
 }
 
 /// Process a `CredentialEvent::Loaded` event. Update the model with the loaded
@@ -92,3 +179,10 @@ pub fn store_error(error: StoreError, model: &mut Model) -> Command<Effect, Even
     *model = model.error(&error.to_string());
     render()
 }
+
+/// Process an event that results in an error being returned from the key
+/// store.
+fn keystore_error(error: KeyStoreError, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.error(&error.to_string());
+    render()
+}
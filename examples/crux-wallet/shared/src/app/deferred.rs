@@ -0,0 +1,270 @@
+use credibil_holder::{
+    credential::Credential,
+    did::Document,
+    issuance::{
+        proof::{self, Payload, Verify},
+        CredentialResponseType, DeferredCredentialResponse,
+    },
+    Kind,
+};
+use crux_core::{render::render, Command};
+use crux_http::{command::Http, http::mime, HttpError, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capabilities::store::{Catalog, StoreCommand, StoreEntry, StoreError},
+    did_resolver::{document_url, DidResolverProvider},
+    model::Model,
+};
+
+use super::{
+    credential::{refresh_credentials, store_error},
+    Effect, Event,
+};
+
+/// Events that can be sent to the wallet application that pertain to
+/// credentials the issuer has deferred.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DeferredEvent {
+    /// Event emitted by the shell when the app resumes (returns to the
+    /// foreground). Poll issuers for any pending credentials.
+    Resume,
+
+    /// Event emitted by the core when pending credentials have been loaded
+    /// from the store. The issuer is polled for each pending credential.
+    #[serde(skip)]
+    Pending(Result<Vec<StoreEntry>, StoreError>),
+
+    /// Event emitted by the core when pending credentials have been loaded
+    /// from the store without polling issuers.
+    #[serde(skip)]
+    Loaded(Result<Vec<StoreEntry>, StoreError>),
+
+    /// Event emitted by the core when the issuer has responded to a deferred
+    /// credential request.
+    #[serde(skip)]
+    Response { transaction_id: String, res: Result<crux_http::Response<Vec<u8>>, HttpError> },
+
+    /// Event emitted by the core when the DID document needed to verify a
+    /// deferred credential has been resolved. `compact` is the credential as
+    /// returned by the issuer.
+    #[serde(skip)]
+    DidResolved {
+        transaction_id: String,
+        compact: String,
+        res: Result<crux_http::Response<Vec<u8>>, HttpError>,
+    },
+
+    /// Event emitted by the core when a deferred credential has been verified.
+    #[serde(skip)]
+    Verified { transaction_id: String, credential: Box<Credential> },
+
+    /// Event emitted by the core when a deferred credential has been stored.
+    #[serde(skip)]
+    Stored { transaction_id: String, res: Result<(), StoreError> },
+
+    /// Event emitted by the core when a pending credential has been removed
+    /// from the store.
+    #[serde(skip)]
+    Removed(Result<(), StoreError>),
+}
+
+/// Deferred credential event processing.
+pub fn deferred_event(event: DeferredEvent, model: &mut Model) -> Command<Effect, Event> {
+    match event {
+        DeferredEvent::Resume => poll_pending(),
+        DeferredEvent::Pending(Ok(entries)) => pending(entries, model),
+        DeferredEvent::Loaded(Ok(entries)) => loaded(entries, model),
+        DeferredEvent::Response {
+            transaction_id,
+            res: Ok(res),
+        } => response(transaction_id, res, model),
+        DeferredEvent::DidResolved {
+            transaction_id,
+            compact,
+            res: Ok(res),
+        } => did_resolved(transaction_id, compact, res, model),
+        DeferredEvent::Verified {
+            transaction_id,
+            credential,
+        } => verified(transaction_id, *credential),
+        DeferredEvent::Stored {
+            transaction_id,
+            res: Ok(()),
+        } => stored(transaction_id),
+        DeferredEvent::Removed(Ok(())) => Command::all([refresh_credentials(), load_pending()]),
+        DeferredEvent::Pending(Err(error))
+        | DeferredEvent::Loaded(Err(error))
+        | DeferredEvent::Stored { res: Err(error), .. }
+        | DeferredEvent::Removed(Err(error)) => store_error(error, model),
+        // A failed poll leaves the credential pending. The issuer will be
+        // polled again when the app next resumes.
+        DeferredEvent::Response { res: Err(_), .. }
+        | DeferredEvent::DidResolved { res: Err(_), .. } => Command::done(),
+    }
+}
+
+/// Load the pending credentials from the store, then poll the issuer for
+/// each.
+pub fn poll_pending() -> Command<Effect, Event> {
+    StoreCommand::list(Catalog::Deferred.to_string())
+        .then_send(|res| Event::Deferred(DeferredEvent::Pending(res)))
+}
+
+/// Load the pending credentials from the store to refresh the view.
+pub fn load_pending() -> Command<Effect, Event> {
+    StoreCommand::list(Catalog::Deferred.to_string())
+        .then_send(|res| Event::Deferred(DeferredEvent::Loaded(res)))
+}
+
+/// Process a `DeferredEvent::Pending` event. Update the model with the pending
+/// credentials and make a deferred credential request to the issuer of each.
+fn pending(entries: Vec<StoreEntry>, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.pending_loaded(entries);
+
+    let mut commands: Vec<Command<Effect, Event>> = Vec::new();
+    for pending in model.get_pending_credentials() {
+        // Issuers without a deferred credential endpoint cannot be polled.
+        let Some(url) = pending.deferred_url() else {
+            continue;
+        };
+        let request = pending.request();
+        let transaction_id = pending.transaction_id.clone();
+        let http_request = match Http::<Effect, Event>::post(url)
+            .header("accept", mime::JSON)
+            .header("Authorization", format!("Bearer {}", request.access_token))
+            .body_json(&request)
+        {
+            Ok(hr) => hr,
+            Err(e) => {
+                return Command::event(Event::Error(e.to_string()));
+            }
+        };
+        commands.push(http_request.build().then_send(move |res| {
+            Event::Deferred(DeferredEvent::Response { transaction_id, res })
+        }));
+    }
+    commands.push(render());
+    Command::all(commands)
+}
+
+/// Process a `DeferredEvent::Loaded` event. Update the model with the pending
+/// credentials.
+fn loaded(entries: Vec<StoreEntry>, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.pending_loaded(entries);
+    render()
+}
+
+/// Process a `DeferredEvent::Response` event. If the credential has been
+/// issued, verify it, fetching the issuer's DID document first if it can't be
+/// resolved locally.
+fn response(
+    transaction_id: String, res: Response<Vec<u8>>, model: &Model,
+) -> Command<Effect, Event> {
+    // The issuer responds with an `issuance_pending` error until the
+    // credential is ready. Leave the credential pending and try again on the
+    // next resume.
+    if !res.status().is_success() {
+        return Command::done();
+    }
+    let Some(body) = &res.body() else {
+        return Command::event(Event::Error("no deferred credential returned".into()));
+    };
+    let Ok(deferred_response) = serde_json::from_slice::<DeferredCredentialResponse>(body) else {
+        return Command::event(Event::Error(
+            "deferred credential response deserialization failed".into(),
+        ));
+    };
+    match deferred_response.credential_response.response {
+        CredentialResponseType::Credential(vc_kind) => {
+            let Kind::String(compact) = vc_kind else {
+                return Command::event(Event::Error("expected response as compact JWT".into()));
+            };
+            match document_url(&compact) {
+                Ok(Some(url)) => Http::get(url).build().then_send(move |res| {
+                    Event::Deferred(DeferredEvent::DidResolved {
+                        transaction_id,
+                        compact,
+                        res,
+                    })
+                }),
+                Ok(None) => verify(transaction_id, compact, DidResolverProvider::default(), model),
+                Err(e) => Command::event(Event::Error(e.to_string())),
+            }
+        }
+        CredentialResponseType::Credentials(_creds) =>
+        // Multiple credentials in response.
+        // TODO: support this
+        {
+            Command::event(Event::Error("multiple credentials returned but not supported".into()))
+        }
+        // Still pending.
+        CredentialResponseType::TransactionId(_tx_id) => Command::done(),
+    }
+}
+
+/// Process a `DeferredEvent::DidResolved` event. The DID document has been
+/// fetched for the issuer's key ID. Verify the credential.
+fn did_resolved(
+    transaction_id: String, compact: String, res: Response<Vec<u8>>, model: &Model,
+) -> Command<Effect, Event> {
+    if !res.status().is_success() {
+        return Command::event(Event::Error("DID document request failed".into()));
+    }
+    let Some(body) = &res.body() else {
+        return Command::event(Event::Error("no DID document returned".into()));
+    };
+    let Ok(did_document) = serde_json::from_slice::<Document>(body) else {
+        return Command::event(Event::Error("DID document deserialization failed".into()));
+    };
+    verify(transaction_id, compact, DidResolverProvider::new(&did_document), model)
+}
+
+/// Verify a deferred credential and convert it to a format suitable for
+/// storage.
+fn verify(
+    transaction_id: String, compact: String, resolver: DidResolverProvider, model: &Model,
+) -> Command<Effect, Event> {
+    // The user may have moved on to another part of the app. The credential
+    // stays pending until the next resume.
+    let Some(pending) = model.get_pending_credential(&transaction_id) else {
+        return Command::done();
+    };
+
+    Command::new(|ctx| async move {
+        let vc_kind = Kind::String(compact);
+        let Payload::Vc { vc, issued_at } =
+            (match proof::verify(Verify::Vc(&vc_kind), resolver).await {
+                Ok(vc) => vc,
+                Err(e) => {
+                    return ctx.send_event(Event::Error(e.to_string()));
+                }
+            })
+        else {
+            return ctx.send_event(Event::Error("unable to verify credential".into()));
+        };
+        let credential = match pending.storable_credential(&vc, &vc_kind, &issued_at) {
+            Ok(c) => c,
+            Err(e) => {
+                return ctx.send_event(Event::Error(e.to_string()));
+            }
+        };
+        ctx.send_event(Event::Deferred(DeferredEvent::Verified {
+            transaction_id,
+            credential: Box::new(credential),
+        }))
+    })
+}
+
+/// Process a `DeferredEvent::Verified` event. Store the credential.
+fn verified(transaction_id: String, credential: Credential) -> Command<Effect, Event> {
+    StoreCommand::save(Catalog::Credential.to_string(), credential.id.clone(), credential)
+        .then_send(move |res| Event::Deferred(DeferredEvent::Stored { transaction_id, res }))
+}
+
+/// Process a `DeferredEvent::Stored` event. The credential is no longer
+/// pending so remove it from the store.
+fn stored(transaction_id: String) -> Command<Effect, Event> {
+    StoreCommand::delete(Catalog::Deferred.to_string(), transaction_id)
+        .then_send(|res| Event::Deferred(DeferredEvent::Removed(res)))
+}
@@ -0,0 +1,95 @@
+use crux_core::{render::render, Command};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capabilities::store::{Catalog, StoreCommand, StoreEntry, StoreError},
+    model::Model,
+};
+
+use super::{
+    credential::{store_error, CredentialEvent},
+    deferred::DeferredEvent,
+    Effect, Event,
+};
+
+/// Store ID of the saved flow. Only one flow can be in progress at a time.
+const FLOW_ID: &str = "active";
+
+/// Events that can be sent to the wallet application that pertain to saving
+/// an in-progress issuance or presentation flow so it survives the OS killing
+/// the app while it is in the background.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum FlowEvent {
+    /// Event emitted by the shell whenever the app is backgrounded. Save the
+    /// in-progress flow if the user can pick it up again after a restart,
+    /// otherwise remove any previously saved flow.
+    Suspend,
+
+    /// Event emitted by the core when the saved flow has been loaded from the
+    /// store on launch.
+    #[serde(skip)]
+    Restored(Result<Vec<StoreEntry>, StoreError>),
+
+    /// Event emitted by the core when the in-progress flow has been saved.
+    #[serde(skip)]
+    Saved(Result<(), StoreError>),
+
+    /// Event emitted by the core when the saved flow has been removed from
+    /// the store.
+    #[serde(skip)]
+    Removed(Result<(), StoreError>),
+}
+
+/// Saved flow event processing.
+pub fn flow_event(event: FlowEvent, model: &mut Model) -> Command<Effect, Event> {
+    match event {
+        FlowEvent::Suspend => suspend(model),
+        FlowEvent::Restored(Ok(entries)) => restored(entries, model),
+        FlowEvent::Restored(Err(error)) => store_error(error, model),
+        // Failing to save the flow doesn't affect the flow in progress; it
+        // just won't survive a restart.
+        FlowEvent::Saved(_) | FlowEvent::Removed(_) => Command::done(),
+    }
+}
+
+/// Load the stored and pending credentials, then the saved flow, if any.
+/// Loading credentials resets the model to the credential list so the flow
+/// must be restored last.
+pub fn restore() -> Command<Effect, Event> {
+    Command::new(|ctx| async move {
+        let credentials =
+            StoreCommand::list(Catalog::Credential.to_string()).into_future(ctx.clone()).await;
+        ctx.send_event(Event::Credential(CredentialEvent::Loaded(credentials)));
+        let pending =
+            StoreCommand::list(Catalog::Deferred.to_string()).into_future(ctx.clone()).await;
+        ctx.send_event(Event::Deferred(DeferredEvent::Pending(pending)));
+        let flows = StoreCommand::list(Catalog::Flow.to_string()).into_future(ctx.clone()).await;
+        ctx.send_event(Event::Flow(FlowEvent::Restored(flows)));
+    })
+}
+
+/// Process a `FlowEvent::Suspend` event. Save the in-progress flow to the
+/// store, or remove the saved flow if there is nothing to resume.
+fn suspend(model: &Model) -> Command<Effect, Event> {
+    match model.get_saved_flow() {
+        Some(saved) => StoreCommand::save(Catalog::Flow.to_string(), FLOW_ID, saved)
+            .then_send(|res| Event::Flow(FlowEvent::Saved(res))),
+        None => remove(),
+    }
+}
+
+/// Process a `FlowEvent::Restored` event. Restore the saved flow to the model
+/// and remove it from the store so it is only restored once.
+fn restored(entries: Vec<StoreEntry>, model: &mut Model) -> Command<Effect, Event> {
+    if entries.is_empty() {
+        return Command::done();
+    }
+    *model = model.flow_restored(entries);
+    Command::all([remove(), render()])
+}
+
+/// Remove the saved flow from the store.
+fn remove() -> Command<Effect, Event> {
+    StoreCommand::delete(Catalog::Flow.to_string(), FLOW_ID)
+        .then_send(|res| Event::Flow(FlowEvent::Removed(res)))
+}
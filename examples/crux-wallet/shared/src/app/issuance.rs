@@ -1,8 +1,13 @@
 use credibil_holder::{
-    did::Document, infosec::{jose::JwsBuilder, Jws}, issuance::{
+    did::Document,
+    error::{ErrorCode, OAuthError, Recovery as ErrorRecovery},
+    infosec::jose::JwsBuilder,
+    issuance::{
         proof::{self, Payload, Type, Verify},
-        CredentialResponseType, Issuer, VerifiableCredential,
-    }, provider::{CredentialResponse, TokenResponse}, Kind
+        CredentialResponseType, Issuer, OAuthServerResponse, VerifiableCredential,
+    },
+    provider::{CredentialResponse, TokenResponse},
+    Kind,
 };
 use crux_core::{render::render, Command};
 use crux_http::{command::Http, http::mime, HttpError, Response};
@@ -12,15 +17,23 @@ use std::ops::DerefMut;
 
 use crate::{
     capabilities::{
+        browser::{BrowserCommand, BrowserError},
         key::{KeyStoreCommand, KeyStoreEntry, KeyStoreError},
         store::{Catalog, StoreCommand, StoreError},
     },
-    did_resolver::DidResolverProvider,
+    config,
+    did_resolver::{document_url, DidResolverProvider},
     model::{IssuanceState, Model, State},
     signer::SignerProvider,
 };
 
-use super::{credential::CredentialEvent, Aspect, Effect, Event};
+use super::{
+    credential::{refresh_credentials, CredentialEvent},
+    deferred::load_pending,
+    notification::subscribe_issuer,
+    recovery::{recoverable, Recovery},
+    Aspect, Effect, Event,
+};
 
 /// Events that can be sent to the wallet application that pertain to the
 /// issuance of credentials.
@@ -37,22 +50,45 @@ pub enum IssuanceEvent {
     #[serde(skip)]
     Issuer(Result<crux_http::Response<Vec<u8>>, HttpError>),
 
+    /// Event emitted by the core when authorization server metadata has been
+    /// received for an offer that has not been pre-authorized.
+    #[serde(skip)]
+    AuthServer(Result<crux_http::Response<Vec<u8>>, HttpError>),
+
     /// Event emitted by the core when an offered credential's logo has been
     /// fetched.
     #[serde(skip)]
-    Logo(Result<crux_http::Response<Vec<u8>>, HttpError>),
+    Logo { config_id: String, res: Result<crux_http::Response<Vec<u8>>, HttpError> },
 
     /// Event emitted by the core when an offered credential's background image
     /// has been fetched.
     #[serde(skip)]
-    Background(Result<crux_http::Response<Vec<u8>>, HttpError>),
+    Background { config_id: String, res: Result<crux_http::Response<Vec<u8>>, HttpError> },
+
+    /// Event emitted by the shell when the user includes or excludes an
+    /// offered credential from their acceptance. The value is the credential
+    /// configuration ID.
+    Toggle(String),
 
-    /// Event emitted by the shell when the user has accepted an issuance offer.
+    /// Event emitted by the shell when the user has accepted an issuance offer
+    /// (for the offered credentials they have included).
     Accepted,
 
     /// Event emitted by the shell when the user has entered a PIN.
     Pin(String),
 
+    /// Event emitted by the core when the user needs to authorize issuance
+    /// with the issuer's authorization server. The authorization URL is opened
+    /// in the shell's browser.
+    #[serde(skip)]
+    AuthorizationRequested,
+
+    /// Event emitted by the core when the browser has been redirected back to
+    /// the app. The value is the redirect URL, containing the authorization
+    /// code.
+    #[serde(skip)]
+    Authorized(Result<String, BrowserError>),
+
     /// Event emitted by the core when an access token has been received.
     #[serde(skip)]
     Token(Result<crux_http::Response<Vec<u8>>, HttpError>),
@@ -65,8 +101,8 @@ pub enum IssuanceEvent {
     #[serde(skip)]
     DidResolved(Result<crux_http::Response<Vec<u8>>, HttpError>),
 
-    /// Event emitted by the core when a signing key has been retrieved from
-    /// the key store capability.
+    /// Event emitted by the core when a signing key has been created by the
+    /// key store capability.
     #[serde(skip)]
     SigningKey(Result<KeyStoreEntry, KeyStoreError>),
 
@@ -83,6 +119,16 @@ pub enum IssuanceEvent {
     #[serde(skip)]
     Stored(Result<(), StoreError>),
 
+    /// Event emitted by the core when the details needed to notify the
+    /// issuer of the credential's deletion have been stored.
+    #[serde(skip)]
+    NotificationStored(Result<(), StoreError>),
+
+    /// Event emitted by the core when a credential the issuer has deferred
+    /// has been saved as pending.
+    #[serde(skip)]
+    Deferred(Result<(), StoreError>),
+
     /// Event emitted by the shell to cancel an issuance.
     Cancel,
 }
@@ -93,26 +139,47 @@ pub fn issuance_event(event: IssuanceEvent, model: &mut Model) -> Command<Effect
         IssuanceEvent::ScanOffer => scan_offer(model),
         IssuanceEvent::Offer(encoded_offer) => offer(&encoded_offer, model),
         IssuanceEvent::Issuer(Ok(res)) => issuer(res, model),
-        IssuanceEvent::Logo(Ok(res)) => logo(res, model),
-        IssuanceEvent::Background(Ok(res)) => background(res, model),
+        IssuanceEvent::AuthServer(Ok(res)) => auth_server(res, model),
+        IssuanceEvent::Logo {
+            config_id,
+            res: Ok(res),
+        } => logo(&config_id, res, model),
+        IssuanceEvent::Background {
+            config_id,
+            res: Ok(res),
+        } => background(&config_id, res, model),
+        IssuanceEvent::Toggle(config_id) => toggle(&config_id, model),
         IssuanceEvent::Accepted => accepted(model),
         IssuanceEvent::Pin(input_pin) => pin(&input_pin, model),
+        IssuanceEvent::AuthorizationRequested => authorization_requested(model),
+        IssuanceEvent::Authorized(Ok(redirect)) => request_token(model, Some(&redirect)),
+        // The user closed the browser without authorizing issuance.
+        IssuanceEvent::Authorized(Err(BrowserError::Cancelled)) => cancel(model),
         IssuanceEvent::Token(Ok(res)) => token(res, model),
         IssuanceEvent::Proof(jws) => proof(&jws, model),
         IssuanceEvent::DidResolved(Ok(res)) => did_resolved(res, model),
         IssuanceEvent::SigningKey(Ok(key)) => signing_key(key, model),
         IssuanceEvent::Credential(Ok(res)) => credential(res, model),
         IssuanceEvent::ProofVerified { vc, issued_at } => proof_verified(vc, issued_at, model),
-        IssuanceEvent::Stored(Ok(())) => stored(),
+        IssuanceEvent::Stored(Ok(())) => stored(model),
+        // If the details could not be stored the issuer just won't be told
+        // when the credential is deleted.
+        IssuanceEvent::NotificationStored(_) => Command::done(),
+        IssuanceEvent::Deferred(Ok(())) => deferred(model),
         IssuanceEvent::Cancel => cancel(model),
-        IssuanceEvent::Stored(Err(error)) => store_error(error, model),
-        IssuanceEvent::Issuer(Err(error))
-        | IssuanceEvent::Logo(Err(error))
-        | IssuanceEvent::Background(Err(error))
-        | IssuanceEvent::Token(Err(error))
+        IssuanceEvent::Stored(Err(error)) | IssuanceEvent::Deferred(Err(error)) => {
+            store_error(error, model)
+        }
+        IssuanceEvent::Issuer(Err(error)) | IssuanceEvent::AuthServer(Err(error)) => {
+            recoverable(error.to_string(), Recovery::RescanOffer)
+        }
+        IssuanceEvent::Token(Err(error)) => token_failed(&error.to_string(), None, model),
+        IssuanceEvent::Logo { res: Err(error), .. }
+        | IssuanceEvent::Background { res: Err(error), .. }
         | IssuanceEvent::Credential(Err(error))
         | IssuanceEvent::DidResolved(Err(error)) => http_error(error, model),
         IssuanceEvent::SigningKey(Err(error)) => keystore_error(error, model),
+        IssuanceEvent::Authorized(Err(error)) => browser_error(error, model),
     }
 }
 
@@ -129,7 +196,7 @@ fn offer(encoded_offer: &str, model: &mut Model) -> Command<Effect, Event> {
     *model = match model.issuance_offer(encoded_offer) {
         Ok(m) => m,
         Err(e) => {
-            return Command::event(Event::Error(e.to_string()));
+            return recoverable(e.to_string(), Recovery::RescanOffer);
         }
     };
 
@@ -144,53 +211,96 @@ fn offer(encoded_offer: &str, model: &mut Model) -> Command<Effect, Event> {
 }
 
 /// Process an `IssuanceEvent::Issuer` event. Update the model with issuer
-/// metadata and go get display images.
+/// metadata. If the offer has not been pre-authorized, go get the
+/// authorization server metadata. Otherwise, go get display images for each
+/// offered credential.
 fn issuer(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
     if !res.status().is_success() {
-        return Command::event(Event::Error("issuer metadata fetch failed".into()));
+        return recoverable("issuer metadata fetch failed", Recovery::RescanOffer);
     }
     let Some(body) = &res.body() else {
-        return Command::event(Event::Error("no issuer metadata returned".into()));
+        return recoverable("no issuer metadata returned", Recovery::RescanOffer);
     };
     let Ok(issuer) = serde_json::from_slice::<Issuer>(body) else {
-        return Command::event(Event::Error("issuer metadata deserialization failed".into()));
+        return recoverable("issuer metadata deserialization failed", Recovery::RescanOffer);
     };
 
     // Update state with issuer metadata
+    let auth_server_url =
+        format!("{}/.well-known/oauth-authorization-server", issuer.credential_issuer);
     *model = match model.issuer_metadata(issuer) {
         Ok(m) => m,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
         }
     };
+    if model.issuance_needs_auth_server() {
+        return Http::get(auth_server_url)
+            .build()
+            .then_send(|res| Event::Issuance(IssuanceEvent::AuthServer(res)));
+    }
+    fetch_images(model)
+}
+
+/// Process an `IssuanceEvent::AuthServer` event. Update the model with
+/// authorization server metadata and go get display images for each offered
+/// credential.
+fn auth_server(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
+    if !res.status().is_success() {
+        return recoverable("authorization server metadata fetch failed", Recovery::RescanOffer);
+    }
+    let Some(body) = &res.body() else {
+        return recoverable("no authorization server metadata returned", Recovery::RescanOffer);
+    };
+    let Ok(metadata) = serde_json::from_slice::<OAuthServerResponse>(body) else {
+        return recoverable(
+            "authorization server metadata deserialization failed",
+            Recovery::RescanOffer,
+        );
+    };
+    *model = match model.issuance_auth_server(metadata.authorization_server) {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    fetch_images(model)
+}
 
-    // Fetch logo and background image.
-    let Some(cred_info) = model.get_offered_credential() else {
+/// Fetch logo and background images for each offered credential.
+fn fetch_images(model: &Model) -> Command<Effect, Event> {
+    let offered = model.get_offered_credentials();
+    if offered.is_empty() {
         return Command::event(Event::Error(
             "no credential configuration found in issuance state".into(),
         ));
-    };
+    }
 
-    let logo_command: Command<Effect, Event> = match cred_info.logo_url() {
-        Some(logo_url) => Http::get(logo_url)
-            .header("accept", "image/*")
-            .build()
-            .then_send(|res| Event::Issuance(IssuanceEvent::Logo(res))),
-        None => Command::done(),
-    };
-    let background_command: Command<Effect, Event> = match cred_info.background_url() {
-        Some(background_url) => Http::get(background_url)
-            .header("accept", "image/*")
-            .build()
-            .then_send(|res| Event::Issuance(IssuanceEvent::Background(res))),
-        None => Command::done(),
-    };
-    Command::all([logo_command, background_command, render()])
+    let mut commands: Vec<Command<Effect, Event>> = Vec::new();
+    for cred_info in offered {
+        if let Some(logo_url) = cred_info.logo_url() {
+            let config_id = cred_info.config_id.clone();
+            commands.push(
+                Http::get(logo_url)
+                    .header("accept", "image/*")
+                    .build()
+                    .then_send(move |res| Event::Issuance(IssuanceEvent::Logo { config_id, res })),
+            );
+        }
+        if let Some(background_url) = cred_info.background_url() {
+            let config_id = cred_info.config_id.clone();
+            commands.push(Http::get(background_url).header("accept", "image/*").build().then_send(
+                move |res| Event::Issuance(IssuanceEvent::Background { config_id, res }),
+            ));
+        }
+    }
+    commands.push(render());
+    Command::all(commands)
 }
 
 /// Process an `IssuanceEvent::Logo` event. A response has been received from
 /// the HTTP request for the credential logo image.
-fn logo(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
+fn logo(config_id: &str, mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
     if !res.status().is_success() {
         return Command::event(Event::Error("credential logo fetch failed".into()));
     }
@@ -201,7 +311,7 @@ fn logo(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event>
     let Ok(image_bytes) = &res.body_bytes() else {
         return Command::event(Event::Error("no logo image bytes returned".into()));
     };
-    *model = match model.issuance_logo(image_bytes, &media_type) {
+    *model = match model.issuance_logo(config_id, image_bytes, &media_type) {
         Ok(m) => m,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
@@ -212,7 +322,9 @@ fn logo(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event>
 
 /// Process an `IssuanceEvent::Background` event. A response has been received
 /// from the HTTP request for the credential background image.
-fn background(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
+fn background(
+    config_id: &str, mut res: Response<Vec<u8>>, model: &mut Model,
+) -> Command<Effect, Event> {
     if !res.status().is_success() {
         return Command::event(Event::Error("credential background image fetch failed".into()));
     }
@@ -223,7 +335,19 @@ fn background(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect,
     let Ok(image_bytes) = &res.body_bytes() else {
         return Command::event(Event::Error("no background image bytes returned".into()));
     };
-    *model = match model.issuance_background(image_bytes, &media_type) {
+    *model = match model.issuance_background(config_id, image_bytes, &media_type) {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    render()
+}
+
+/// Process an `IssuanceEvent::Toggle` event. The user has included or
+/// excluded an offered credential.
+fn toggle(config_id: &str, model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.issuance_toggle(config_id) {
         Ok(m) => m,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
@@ -234,7 +358,9 @@ fn background(mut res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect,
 
 /// Process an `IssuanceEvent::Accepted` event. The user has accepted the
 /// issuance offer. If a PIN is required, set the active view to the PIN entry
-/// screen. Otherwise, request an access token.
+/// screen. If the user needs to authorize issuance, raise an
+/// `IssuanceEvent::AuthorizationRequested` event. Otherwise, request an access
+/// token.
 fn accepted(model: &mut Model) -> Command<Effect, Event> {
     *model = match model.issuance_accept() {
         Ok(m) => m,
@@ -246,13 +372,40 @@ fn accepted(model: &mut Model) -> Command<Effect, Event> {
         *model = model.active_view(Aspect::IssuancePin);
         return render();
     }
+    if model.issuance_needs_authorization() {
+        return Command::event(Event::Issuance(IssuanceEvent::AuthorizationRequested));
+    }
+    request_token(model, None)
+}
 
-    // Request an access token.
+/// Process an `IssuanceEvent::AuthorizationRequested` event. Create an
+/// authorization request and open the authorization server in the shell's
+/// browser.
+fn authorization_requested(model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.issuance_authorize() {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    let url = match model.get_authorization_url() {
+        Ok(url) => url,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    BrowserCommand::authorize(url, config::callback_scheme())
+        .then_send(|res| Event::Issuance(IssuanceEvent::Authorized(res)))
+}
+
+/// Request an access token. For the authorization code flow, `redirect` is
+/// the URL the authorization server redirected the browser to.
+pub fn request_token(model: &Model, redirect: Option<&str>) -> Command<Effect, Event> {
     let Some(issuer) = model.issuer() else {
         return Command::event(Event::Error("expected issuer metadata on state".into()));
     };
     let token_url = format!("{}/token", issuer.credential_issuer);
-    let token_request = match model.get_token_request() {
+    let token_request = match model.get_token_request(redirect) {
         Ok(tr) => tr,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
@@ -292,7 +445,8 @@ fn pin(pin: &str, model: &mut Model) -> Command<Effect, Event> {
 fn token(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
     // Set the token on state.
     if !res.status().is_success() {
-        return Command::event(Event::Error("access token request failed".into()));
+        let error = res.body().and_then(|body| OAuthError::parse(body));
+        return token_failed("access token request failed", error, model);
     }
     let Some(body) = &res.body() else {
         return Command::event(Event::Error("no access token returned".into()));
@@ -307,12 +461,63 @@ fn token(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
         }
     };
 
-    // Get a signing key.
-    KeyStoreCommand::get("credential", "signing")
+    // Create a new signing key to bind the issued credentials to.
+    let key_id = match model.get_issuance_key_id() {
+        Ok(id) => id,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    KeyStoreCommand::create(key_id, "signing")
         .then_send(|res| Event::Issuance(IssuanceEvent::SigningKey(res)))
 }
 
-/// Process an `IssuanceEvent::Proof` event. Go get an access token.
+/// The access token request failed. Work out how the user can recover using
+/// the issuer's error response, if there is one. A pre-authorized token
+/// request can be retried (with a new PIN if the issuer rejected the one
+/// entered) but an authorization code can only be used once so the user must
+/// start again from the offer.
+fn token_failed(
+    message: &str, error: Option<OAuthError>, model: &mut Model,
+) -> Command<Effect, Event> {
+    let message =
+        error.as_ref().and_then(|e| e.description.clone()).unwrap_or_else(|| message.into());
+    let pin_sent = model.issuance_pin_required();
+    let recovery = match error.as_ref().map(|e| e.recovery(pin_sent)) {
+        _ if !model.issuance_pre_authorized() => Recovery::RescanOffer,
+        // No error response so the request may not have reached the issuer.
+        None | Some(Some(ErrorRecovery::Retry { .. })) => Recovery::RetryToken,
+        Some(Some(ErrorRecovery::ReenterPin)) => {
+            return pin_rejected(model);
+        }
+        // The pre-authorized code has expired or already been used.
+        Some(None) if error.as_ref().is_some_and(|e| e.code == ErrorCode::InvalidGrant) => {
+            Recovery::RescanOffer
+        }
+        Some(_) => Recovery::Dismiss,
+    };
+    recoverable(message, recovery)
+}
+
+/// The issuer rejected the PIN. Go back to PIN entry, showing how many
+/// attempts remain, or abandon the flow once the user has run out.
+fn pin_rejected(model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.issuance_pin_rejected() {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    if model.issuance_pin_attempts_remaining() == Some(0) {
+        return Command::event(Event::Error(
+            "the PIN was entered incorrectly too many times, so the offer can't be used".into(),
+        ));
+    }
+    render()
+}
+
+/// Process an `IssuanceEvent::Proof` event. Create credential requests for
+/// the accepted credentials and request the first of them.
 fn proof(jws: &str, model: &mut Model) -> Command<Effect, Event> {
     *model = match model.issuance_proof(jws) {
         Ok(m) => m,
@@ -320,7 +525,12 @@ fn proof(jws: &str, model: &mut Model) -> Command<Effect, Event> {
             return Command::event(Event::Error(e.to_string()));
         }
     };
-    let (_config_id, credential_request) = match model.get_credential_request(jws) {
+    request_credential(model)
+}
+
+/// Send the next pending credential request to the issuer.
+fn request_credential(model: &Model) -> Command<Effect, Event> {
+    let (_config_id, credential_request) = match model.get_credential_request() {
         Ok(cr) => cr,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
@@ -350,8 +560,7 @@ fn proof(jws: &str, model: &mut Model) -> Command<Effect, Event> {
 }
 
 /// Process an `IssuanceEvent::DidResolved` event. The DID document has been
-/// resolved from the issuer's key ID. Verify the credential and then issue a
-/// verified event.
+/// fetched for the issuer's key ID. Verify the credential.
 fn did_resolved(res: Response<Vec<u8>>, model: &Model) -> Command<Effect, Event> {
     if !res.status().is_success() {
         return Command::event(Event::Error("DID document request failed".into()));
@@ -362,14 +571,16 @@ fn did_resolved(res: Response<Vec<u8>>, model: &Model) -> Command<Effect, Event>
     let Ok(did_document) = serde_json::from_slice::<Document>(body) else {
         return Command::event(Event::Error("DID document deserialization failed".into()));
     };
-    println!(">>> DID document: {:#?}", did_document);
-    let resolver = DidResolverProvider::new(&did_document);
+    verify_credential(DidResolverProvider::new(&did_document), model)
+}
+
+/// Verify the issued credential and then issue a verified event.
+fn verify_credential(resolver: DidResolverProvider, model: &Model) -> Command<Effect, Event> {
     let Some(credential_response) = model.get_issued_credential() else {
         return Command::event(Event::Error(
             "unable to retrieve credential response from model".into(),
         ));
     };
-    println!(">>> Credential response: {credential_response:?}");
     match credential_response.response {
         CredentialResponseType::Credential(vc_kind) => {
             // Single credential in response.
@@ -447,68 +658,54 @@ fn credential(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Even
         }
     };
     match credential_response.response {
-        CredentialResponseType::Credential(vc_kind) =>
-        // Single credential in response.
-        // Crux won't let us pass a DID resolver that needs to
-        // use the shell, so we have to unpack the JWS and get
-        // the key ID and parse the URL to get the DID document.
-        // TODO: Support methods other than did:web
-        {
+        CredentialResponseType::Credential(vc_kind) => {
+            // Single credential in response. Fetch the issuer's DID document
+            // if it can't be resolved locally.
             let Kind::String(compact) = &vc_kind else {
                 return Command::event(Event::Error("expected response as compact JWT".into()));
             };
-            let jws: Jws = match compact.parse() {
-                Ok(jws) => jws,
+            match document_url(compact) {
+                Ok(Some(url)) => Http::get(url)
+                    .build()
+                    .then_send(|res| Event::Issuance(IssuanceEvent::DidResolved(res))),
+                Ok(None) => verify_credential(DidResolverProvider::default(), model),
+                Err(e) => Command::event(Event::Error(e.to_string())),
+            }
+        }
+        CredentialResponseType::Credentials(_creds) =>
+        // Multiple credentials in response.
+        // TODO: support this
+        {
+            Command::event(Event::Error("multiple credentials returned but not supported".into()))
+        }
+        CredentialResponseType::TransactionId(_tx_id) => {
+            // Deferred transaction ID. Save the pending credential so the
+            // issuer can be polled when the app resumes.
+            *model = match model.issuance_deferred() {
+                Ok(m) => m,
                 Err(e) => {
                     return Command::event(Event::Error(e.to_string()));
                 }
             };
-            let Some(signature) = jws.signatures.first() else {
-                return Command::event(Event::Error(
-                    "expected at least one signature in credential response".into(),
-                ));
-            };
-            let header = &signature.protected;
-            let Some(key_id) = header.kid() else {
-                return Command::event(Event::Error(
-                    "expected key ID in credential response".into(),
-                ));
-            };
-            let parts = key_id.split('#').collect::<Vec<&str>>();
-            let Some(url_part) = parts.first() else {
-                return Command::event(Event::Error("expected key ID to contain a URL".into()));
-            };
-            println!(">>> Key part: {url_part}");
-            let url = match credibil_holder::did::DidWeb::url(url_part) {
-                Ok(url) => {
-                    println! {">>> DidWeb URL: {url}"};
-                    url
-                }
+            let pending = match model.get_issuance_pending_credential() {
+                Ok(p) => p,
                 Err(e) => {
                     return Command::event(Event::Error(e.to_string()));
                 }
             };
-            Http::get(url).build().then_send(|res| Event::Issuance(IssuanceEvent::DidResolved(res)))
-        }
-        CredentialResponseType::Credentials(_creds) =>
-        // Multiple credentials in response.
-        // TODO: support this
-        {
-            Command::event(Event::Error("multiple credentials returned but not supported".into()))
-        }
-        CredentialResponseType::TransactionId(_tx_id) =>
-        // Deferred transaction ID.
-        // TODO: support this
-        {
-            Command::event(Event::Error(
-                "deferred transaction ID returned but not supported".into(),
-            ))
+            StoreCommand::save(
+                Catalog::Deferred.to_string(),
+                pending.transaction_id.clone(),
+                pending,
+            )
+            .then_send(|res| Event::Issuance(IssuanceEvent::Deferred(res)))
         }
     }
 }
 
 /// Process an `IssuanceEvent::ProofVerified` event. The credential has been
-/// verified. Store the credential.
+/// verified. Store the credential and, if the issuer wants to be told when it
+/// is deleted, the details needed to notify the issuer.
 fn proof_verified(
     vc: VerifiableCredential, issued_at: i64, model: &mut Model,
 ) -> Command<Effect, Event> {
@@ -526,15 +723,58 @@ fn proof_verified(
             return Command::event(Event::Error(e.to_string()));
         }
     };
-    StoreCommand::save(Catalog::Credential.to_string(), credential.id.clone(), credential)
-        .then_send(|res| Event::Issuance(IssuanceEvent::Stored(res)))
+    let store_credential =
+        StoreCommand::save(Catalog::Credential.to_string(), credential.id.clone(), credential)
+            .then_send(|res| Event::Issuance(IssuanceEvent::Stored(res)));
+    let Some(notification) = model.get_issuer_notification() else {
+        return store_credential;
+    };
+    let store_notification = StoreCommand::save(
+        Catalog::Notification.to_string(),
+        notification.credential_id.clone(),
+        notification,
+    )
+    .then_send(|res| Event::Issuance(IssuanceEvent::NotificationStored(res)));
+    Command::all([store_credential, store_notification])
 }
 
 /// Process an `IssuanceEvent::Stored` event. The credential has been stored.
-/// Refresh the list of credentials.
-fn stored() -> Command<Effect, Event> {
-    StoreCommand::list(Catalog::Credential.to_string())
-        .then_send(|res| Event::Credential(CredentialEvent::Loaded(res)))
+/// Request the next accepted credential or, if all have been requested,
+/// finish the issuance.
+fn stored(model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.issuance_stored() {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    next_credential(model)
+}
+
+/// Process an `IssuanceEvent::Deferred` event. The deferred credential has
+/// been saved as pending. Subscribe to the issuer's notifications so the
+/// credential can be collected when it is ready, then request the next
+/// accepted credential or, if all have been requested, finish the issuance.
+fn deferred(model: &mut Model) -> Command<Effect, Event> {
+    let Some(issuer) = model.issuer() else {
+        return Command::event(Event::Error("expected issuer metadata on state".into()));
+    };
+    *model = match model.issuance_next() {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    Command::all([subscribe_issuer(&issuer.credential_issuer), next_credential(model)])
+}
+
+/// Request the next pending credential or, if there are none, refresh the
+/// lists of stored and pending credentials.
+fn next_credential(model: &Model) -> Command<Effect, Event> {
+    if model.issuance_pending() {
+        return request_credential(model);
+    }
+    Command::all([refresh_credentials(), load_pending()])
 }
 
 /// Process an `IssuanceEvent::Cancel` event.
@@ -561,3 +801,9 @@ fn keystore_error(error: KeyStoreError, model: &mut Model) -> Command<Effect, Ev
     *model = model.error(&error.to_string());
     render()
 }
+
+/// Process a browser error.
+fn browser_error(error: BrowserError, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.error(&error.to_string());
+    render()
+}
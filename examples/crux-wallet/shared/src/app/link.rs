@@ -0,0 +1,136 @@
+use anyhow::bail;
+use crux_core::{render::render, Command};
+use serde::{Deserialize, Serialize};
+
+use crate::model::Model;
+
+use super::{issuance::IssuanceEvent, presentation::PresentationEvent, Effect, Event};
+
+/// Events that can be sent to the wallet application that pertain to links
+/// (URIs) for credential offers and presentation requests, however the shell
+/// received them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum LinkEvent {
+    /// Event emitted by the shell when the user wants to paste or type a link
+    /// rather than scan a QR code.
+    Enter,
+
+    /// Event emitted by the shell when it receives a link: scanned from a QR
+    /// code, pasted from the clipboard, typed by the user or opened as a deep
+    /// link. The core works out whether it is an offer or a presentation
+    /// request and starts the corresponding flow.
+    Open(String),
+}
+
+/// A link to a credential offer or presentation request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Link {
+    /// A URL-encoded credential offer.
+    Offer(String),
+
+    /// The URL to fetch a presentation request from.
+    Request(String),
+}
+
+/// Link event processing.
+pub fn link_event(event: LinkEvent, model: &mut Model) -> Command<Effect, Event> {
+    match event {
+        LinkEvent::Enter => enter(model),
+        LinkEvent::Open(link) => open(&link, model),
+    }
+}
+
+/// Process a `LinkEvent::Enter` event.
+fn enter(model: &mut Model) -> Command<Effect, Event> {
+    *model = model.link_entry();
+    render()
+}
+
+/// Process a `LinkEvent::Open` event. Start an issuance flow for an offer or a
+/// presentation flow for a presentation request.
+fn open(link: &str, model: &mut Model) -> Command<Effect, Event> {
+    match parse_link(link) {
+        Ok(Link::Offer(encoded_offer)) => {
+            *model = model.scan_issuance_offer();
+            Command::all([
+                render(),
+                Command::event(Event::Issuance(IssuanceEvent::Offer(encoded_offer))),
+            ])
+        }
+        Ok(Link::Request(url)) => {
+            *model = model.scan_presentation_request();
+            Command::all([
+                render(),
+                Command::event(Event::Presentation(PresentationEvent::Request(url))),
+            ])
+        }
+        Err(e) => Command::event(Event::Error(e.to_string())),
+    }
+}
+
+/// Work out what a link is for. Offers are recognised by their
+/// `credential_offer` parameter and presentation requests by their
+/// `request_uri` parameter. Any other HTTP(S) URL is taken to be a
+/// presentation request URL.
+fn parse_link(link: &str) -> anyhow::Result<Link> {
+    let link = link.trim();
+    let query =
+        link.split_once('?').or_else(|| link.split_once("://")).map_or(link, |(_, query)| query);
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "credential_offer" => return Ok(Link::Offer(value.into())),
+            "credential_offer_uri" => {
+                bail!("credential offers passed by reference are not supported")
+            }
+            "request_uri" => return Ok(Link::Request(urlencoding::decode(value)?.into_owned())),
+            _ => {}
+        }
+    }
+    if link.starts_with("https://") || link.starts_with("http://") {
+        return Ok(Link::Request(link.into()));
+    }
+    bail!("link is not a credential offer or presentation request")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offer() {
+        let link = "openid-credential-offer://?credential_offer=%7B%22credential_issuer%22%3A%22https%3A%2F%2Fissuer%22%7D";
+        let Link::Offer(offer) = parse_link(link).expect("should parse") else {
+            panic!("should be an offer");
+        };
+        assert_eq!(offer, "%7B%22credential_issuer%22%3A%22https%3A%2F%2Fissuer%22%7D");
+
+        // Scanned QR codes may omit the `?`.
+        let link = "openid-credential-offer://credential_offer=%7B%7D";
+        assert_eq!(parse_link(link).expect("should parse"), Link::Offer("%7B%7D".into()));
+    }
+
+    #[test]
+    fn request() {
+        let link =
+            "openid4vp://?client_id=verifier&request_uri=https%3A%2F%2Fverifier%2Frequest%2F1234";
+        assert_eq!(
+            parse_link(link).expect("should parse"),
+            Link::Request("https://verifier/request/1234".into())
+        );
+
+        // Pasted links have surrounding whitespace trimmed.
+        let link = " https://verifier/request/1234\n";
+        assert_eq!(
+            parse_link(link).expect("should parse"),
+            Link::Request("https://verifier/request/1234".into())
+        );
+    }
+
+    #[test]
+    fn unrecognised() {
+        parse_link("wibble").expect_err("should not parse");
+        parse_link("openid-credential-offer://?credential_offer_uri=https%3A%2F%2Fissuer")
+            .expect_err("offer by reference should not parse");
+    }
+}
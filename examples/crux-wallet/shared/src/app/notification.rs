@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+
+use credibil_holder::credential::Credential;
+use crux_core::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capabilities::{
+        sse::SseCommand,
+        store::{Catalog, StoreCommand, StoreEntry},
+    },
+    model::PendingCredential,
+};
+
+use super::{credential::refresh_credentials, deferred::poll_pending, Effect, Event};
+
+/// A notification published by an issuer on its server-sent event stream.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    /// A deferred credential is ready to be collected.
+    DeferredReady { transaction_id: String },
+
+    /// A credential has been revoked by the issuer.
+    CredentialRevoked { credential_id: String },
+}
+
+/// Events that can be sent to the wallet application that pertain to
+/// notifications from issuers.
+///
+/// The wallet subscribes to the notification stream of each issuer it holds
+/// credentials from when the app starts, and to an issuer's stream when the
+/// issuer defers a credential. An issuer can be subscribed to more than once
+/// in a session so notifications are handled by refreshing state rather than
+/// acting on the notification directly.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum NotificationEvent {
+    /// Event emitted by the core with the issuers to subscribe to.
+    #[serde(skip)]
+    Subscribe(Vec<String>),
+
+    /// Event emitted by the core when an issuer has sent a notification.
+    #[serde(skip)]
+    Received(Notification),
+}
+
+/// Notification event processing.
+pub fn notification_event(event: NotificationEvent) -> Command<Effect, Event> {
+    match event {
+        NotificationEvent::Subscribe(issuers) => {
+            Command::all(issuers.iter().map(|issuer| subscribe_issuer(issuer)))
+        }
+        NotificationEvent::Received(notification) => received(notification),
+    }
+}
+
+/// Find the issuers of stored and pending credentials and subscribe to their
+/// notification streams.
+pub fn subscribe() -> Command<Effect, Event> {
+    Command::new(|ctx| async move {
+        let mut issuers = BTreeSet::new();
+        let stored = StoreCommand::list(Catalog::Credential.to_string())
+            .into_future(ctx.clone())
+            .await
+            .unwrap_or_default();
+        for data in entry_data(stored) {
+            if let Ok(credential) = serde_json::from_slice::<Credential>(&data) {
+                issuers.insert(credential.issuer);
+            }
+        }
+        let pending = StoreCommand::list(Catalog::Deferred.to_string())
+            .into_future(ctx.clone())
+            .await
+            .unwrap_or_default();
+        for data in entry_data(pending) {
+            if let Ok(pending) = serde_json::from_slice::<PendingCredential>(&data) {
+                issuers.insert(pending.flow.issuer().credential_issuer.clone());
+            }
+        }
+        ctx.send_event(Event::Notification(NotificationEvent::Subscribe(
+            issuers.into_iter().collect(),
+        )));
+    })
+}
+
+/// Subscribe to an issuer's notification stream.
+pub fn subscribe_issuer(issuer: &str) -> Command<Effect, Event> {
+    SseCommand::get_json(format!("{issuer}/notifications"), |notification| {
+        Event::Notification(NotificationEvent::Received(notification))
+    })
+}
+
+/// Process a `NotificationEvent::Received` event. Refresh the part of the
+/// wallet's state the notification is about.
+fn received(notification: Notification) -> Command<Effect, Event> {
+    match notification {
+        Notification::DeferredReady { .. } => poll_pending(),
+        Notification::CredentialRevoked { .. } => refresh_credentials(),
+    }
+}
+
+// Get the data from store entries.
+fn entry_data(entries: Vec<StoreEntry>) -> impl Iterator<Item = Vec<u8>> {
+    entries.into_iter().filter_map(|entry| match entry {
+        StoreEntry::Data(data) => Some(data),
+        _ => None,
+    })
+}
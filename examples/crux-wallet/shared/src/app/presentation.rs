@@ -1,7 +1,6 @@
 use credibil_holder::{
     credential::Credential,
     did::Document,
-    infosec::Jws,
     issuance::proof::Payload,
     presentation::{
         parse_request_object_jwt, RequestObject, RequestObjectResponse, RequestObjectType,
@@ -16,12 +15,18 @@ use crate::{
         key::{KeyStoreCommand, KeyStoreEntry, KeyStoreError},
         store::{Catalog, StoreCommand, StoreEntry, StoreError},
     },
-    did_resolver::DidResolverProvider,
+    config,
+    did_resolver::{document_url, DidResolverProvider},
     model::Model,
     signer::SignerProvider,
+    trust_list,
 };
 
-use super::{credential::CredentialEvent, Aspect, Effect, Event};
+use super::{
+    credential::CredentialEvent,
+    recovery::{recoverable, Recovery, RecoveryEvent},
+    Aspect, Effect, Event,
+};
 
 /// Events that can be sent to the wallet application that pertain to the
 /// issuance of credentials.
@@ -51,6 +56,10 @@ pub enum PresentationEvent {
     #[serde(skip)]
     RequestVerified(Box<RequestObject>),
 
+    /// Event emitted by the core when the trust list has been fetched.
+    #[serde(skip)]
+    TrustListReceived(Result<crux_http::Response<Vec<u8>>, HttpError>),
+
     /// Event emitted by the core when all credentials have been loaded from
     /// storage, before they are filtered.
     #[serde(skip)]
@@ -61,12 +70,13 @@ pub enum PresentationEvent {
     #[serde(skip)]
     CredentialsFound(Vec<Credential>),
 
+    /// Event emitted by the shell when the user includes or excludes a
+    /// matching credential from the presentation. The value is the credential
+    /// ID.
+    Toggle(String),
+
     /// Event emitted by the shell when a user approves the presentation of
-    /// the credential to the verifier.
-    ///
-    /// TODO: We only let the user send the first matching credential for now.
-    /// If the app extends to support a choice we would need to know which one
-    /// has been selected here.
+    /// the selected credentials to the verifier.
     Approved,
 
     /// Event emitted by the core when a signing key has been retrieved from
@@ -74,7 +84,8 @@ pub enum PresentationEvent {
     #[serde(skip)]
     SigningKey(Result<KeyStoreEntry, KeyStoreError>),
 
-    /// Event emitted by the core when a proof has been constructed.
+    /// Event emitted by the core when a proof has been constructed for one of
+    /// the keys the credentials are bound to.
     ///
     /// The string is a proof JWT.
     #[serde(skip)]
@@ -97,18 +108,25 @@ pub fn presentation_event(event: PresentationEvent, model: &mut Model) -> Comman
         PresentationEvent::RequestReceived(Ok(res)) => request_received(res, model),
         PresentationEvent::DidResolved(Ok(res)) => did_resolved(res, model),
         PresentationEvent::RequestVerified(req) => request_verified(req, model),
+        PresentationEvent::TrustListReceived(Ok(res)) => trust_list_received(&res),
         PresentationEvent::CredentialsLoaded(Ok(entries)) => credentials_loaded(entries, model),
         PresentationEvent::CredentialsFound(creds) => credentials_found(creds, model),
+        PresentationEvent::Toggle(id) => toggle(&id, model),
         PresentationEvent::Approved => approved(model),
         PresentationEvent::SigningKey(Ok(key)) => signing_key(key, model),
         PresentationEvent::Proof(jws) => proof(&jws, model),
         PresentationEvent::Response(Ok(res)) => response(res, model),
         PresentationEvent::Cancel => cancel(model),
         PresentationEvent::CredentialsLoaded(Err(error)) => store_error(error, model),
-        PresentationEvent::RequestReceived(Err(error))
-        | PresentationEvent::Response(Err(error))
-        | PresentationEvent::DidResolved(Err(error)) => http_error(error, model),
+        PresentationEvent::RequestReceived(Err(error)) => {
+            recoverable(error.to_string(), Recovery::RescanRequest)
+        }
+        PresentationEvent::Response(Err(error)) | PresentationEvent::DidResolved(Err(error)) => {
+            http_error(error, model)
+        }
         PresentationEvent::SigningKey(Err(error)) => keystore_error(error, model),
+        // Without a trust list the verifier is shown as unknown.
+        PresentationEvent::TrustListReceived(Err(_)) => Command::done(),
     }
 }
 
@@ -127,55 +145,35 @@ fn request(url: &str) -> Command<Effect, Event> {
         .then_send(|res| Event::Presentation(PresentationEvent::RequestReceived(res)))
 }
 
-/// Process a `PresentationEvent::RequestReceived` event.
+/// Process a `PresentationEvent::RequestReceived` event. If the request can't
+/// be fetched (for example, it has expired) the user can scan a new one.
 fn request_received(res: Response<Vec<u8>>, model: &mut Model) -> Command<Effect, Event> {
     if !res.status().is_success() {
-        return Command::event(Event::Error("presentation request fetch failed".into()));
+        return recoverable("presentation request fetch failed", Recovery::RescanRequest);
     }
     let Some(body) = &res.body() else {
-        return Command::event(Event::Error("no presentation request returned".into()));
+        return recoverable("no presentation request returned", Recovery::RescanRequest);
     };
     let Ok(request_object_response) = serde_json::from_slice::<RequestObjectResponse>(body) else {
-        return Command::event(Event::Error("presentation request deserialization failed".into()));
+        return recoverable("presentation request deserialization failed", Recovery::RescanRequest);
     };
     let RequestObjectType::Jwt(token) = request_object_response.request_object else {
-        return Command::event(Event::Error("expected presentation request as JWT".into()));
+        return recoverable("expected presentation request as JWT", Recovery::RescanRequest);
     };
-    let jws: Jws = match token.parse() {
-        Ok(jws) => jws,
-        Err(e) => {
-            return Command::event(Event::Error(e.to_string()));
-        }
-    };
-    // Store the payload in state while we deal with the DID.
+    // Store the payload in state while we deal with the DID. Fetch the
+    // verifier's DID document if it can't be resolved locally.
     *model = model.presentation_request(&token);
-    let Some(signature) = jws.signatures.first() else {
-        return Command::event(Event::Error(
-            "expected at least one signature in presentation request".into(),
-        ));
-    };
-    let header = &signature.protected;
-    let Some(key_id) = header.kid() else {
-        return Command::event(Event::Error("expected key ID in presentation request".into()));
-    };
-    let parts = key_id.split('#').collect::<Vec<&str>>();
-    let Some(url_part) = parts.first() else {
-        return Command::event(Event::Error("expected key ID to contain a URL".into()));
-    };
-    println!(">>> Key part: {url_part}");
-    let url = match credibil_holder::did::DidWeb::url(url_part) {
-        Ok(url) => {
-            println! {">>> DidWeb URL: {url}"};
-            url
-        }
-        Err(e) => {
-            return Command::event(Event::Error(e.to_string()));
-        }
-    };
-    Http::get(url).build().then_send(|res| Event::Presentation(PresentationEvent::DidResolved(res)))
+    match document_url(&token) {
+        Ok(Some(url)) => Http::get(url)
+            .build()
+            .then_send(|res| Event::Presentation(PresentationEvent::DidResolved(res))),
+        Ok(None) => verify_request(DidResolverProvider::default(), model),
+        Err(e) => Command::event(Event::Error(e.to_string())),
+    }
 }
 
-/// Process a `PresentationEvent::DidResolved` event.
+/// Process a `PresentationEvent::DidResolved` event. The DID document has been
+/// fetched for the verifier's key ID. Verify the presentation request.
 fn did_resolved(res: Response<Vec<u8>>, model: &Model) -> Command<Effect, Event> {
     if !res.status().is_success() {
         return Command::event(Event::Error("DID document request failed".into()));
@@ -186,8 +184,11 @@ fn did_resolved(res: Response<Vec<u8>>, model: &Model) -> Command<Effect, Event>
     let Ok(did_document) = serde_json::from_slice::<Document>(body) else {
         return Command::event(Event::Error("DID document deserialization failed".into()));
     };
-    println!(">>> DID document: {:#?}", did_document);
-    let resolver = DidResolverProvider::new(&did_document);
+    verify_request(DidResolverProvider::new(&did_document), model)
+}
+
+/// Verify the presentation request and then issue a verified event.
+fn verify_request(resolver: DidResolverProvider, model: &Model) -> Command<Effect, Event> {
     let Some(presentation_request) = model.get_presentation_request() else {
         return Command::event(Event::Error(
             "unable to retrieve presentation request from model".into(),
@@ -197,7 +198,10 @@ fn did_resolved(res: Response<Vec<u8>>, model: &Model) -> Command<Effect, Event>
         let req_obj = match parse_request_object_jwt(&presentation_request, resolver).await {
             Ok(jwt) => jwt,
             Err(e) => {
-                return ctx.send_event(Event::Error(e.to_string()));
+                return ctx.send_event(Event::Recovery(RecoveryEvent::Failed {
+                    message: e.to_string(),
+                    recovery: Recovery::RescanRequest,
+                }));
             }
         };
         ctx.send_event(Event::Presentation(PresentationEvent::RequestVerified(Box::new(req_obj))));
@@ -212,9 +216,34 @@ fn request_verified(req: Box<RequestObject>, model: &mut Model) -> Command<Effec
             return Command::event(Event::Error(e.to_string()));
         }
     };
-    // Load credentials from storage.
-    StoreCommand::list(Catalog::Credential.to_string())
-        .then_send(|res| Event::Presentation(PresentationEvent::CredentialsLoaded(res)))
+    // Load credentials from storage and fetch the latest trust list so the
+    // user can be told whether the verifier is trusted.
+    Command::all([
+        StoreCommand::list(Catalog::Credential.to_string())
+            .then_send(|res| Event::Presentation(PresentationEvent::CredentialsLoaded(res))),
+        Http::get(config::trust_list_url())
+            .build()
+            .then_send(|res| Event::Presentation(PresentationEvent::TrustListReceived(res))),
+    ])
+}
+
+/// Process a `PresentationEvent::TrustListReceived` event. A list that can't
+/// be verified is ignored, leaving the verifier shown as unknown.
+fn trust_list_received(res: &Response<Vec<u8>>) -> Command<Effect, Event> {
+    if !res.status().is_success() {
+        return Command::done();
+    }
+    let Some(body) = res.body() else {
+        return Command::done();
+    };
+    let Ok(compact) = std::str::from_utf8(body) else {
+        return Command::done();
+    };
+    let Ok(list) = trust_list::verify(compact) else {
+        return Command::done();
+    };
+    config::set_trust_list(list);
+    render()
 }
 
 /// Process a `PresentationEvent::CredentialsLoaded` event.
@@ -258,6 +287,18 @@ fn credentials_found(creds: Vec<Credential>, model: &mut Model) -> Command<Effec
     render()
 }
 
+/// Process a `PresentationEvent::Toggle` event. The user has included or
+/// excluded a matching credential.
+fn toggle(id: &str, model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.presentation_toggle(id) {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    render()
+}
+
 /// Process a `PresentationEvent::Approved` event.
 fn approved(model: &mut Model) -> Command<Effect, Event> {
     // Authorize the presentation.
@@ -267,8 +308,19 @@ fn approved(model: &mut Model) -> Command<Effect, Event> {
             return Command::event(Event::Error(e.to_string()));
         }
     };
-    // Get a signing key.
-    KeyStoreCommand::get("credential", "signing")
+    get_signing_key(model)
+}
+
+/// Get the next key the credentials are bound to. A presentation is signed
+/// with each key in turn.
+fn get_signing_key(model: &Model) -> Command<Effect, Event> {
+    let key_id = match model.get_presentation_key_id() {
+        Ok(id) => id,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    KeyStoreCommand::get(key_id, "signing")
         .then_send(|res| Event::Presentation(PresentationEvent::SigningKey(res)))
 }
 
@@ -310,9 +362,19 @@ fn signing_key(key: KeyStoreEntry, model: &Model) -> Command<Effect, Event> {
     })
 }
 
-/// Process a `PresentationEvent::Proof` event.
-fn proof(jws: &str, model: &Model) -> Command<Effect, Event> {
-    let (res_req, uri) = match model.create_response_request(jws) {
+/// Process a `PresentationEvent::Proof` event. Once a presentation has been
+/// signed with every key the presentation response is sent.
+fn proof(jws: &str, model: &mut Model) -> Command<Effect, Event> {
+    *model = match model.presentation_proof(jws) {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    if !model.presentation_signed() {
+        return get_signing_key(model);
+    }
+    let (res_req, uri) = match model.create_response_request() {
         Ok(rr) => rr,
         Err(e) => {
             return Command::event(Event::Error(e.to_string()));
@@ -0,0 +1,85 @@
+use crux_core::{render::render, Command};
+use serde::{Deserialize, Serialize};
+
+use crate::model::Model;
+
+use super::{credential::refresh_credentials, issuance::request_token, Effect, Event};
+
+/// How the user can recover from an error.
+///
+/// The core decides how an error can be recovered from, using the issuer's or
+/// verifier's error response where there is one. The shell offers the user
+/// the recovery as an action alongside the error message.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Recovery {
+    /// The error can't be recovered from. The user can only dismiss it and
+    /// return to the credential list.
+    #[default]
+    Dismiss,
+
+    /// Retry the access token request. The issuer was unavailable or the
+    /// request didn't reach it.
+    RetryToken,
+
+    /// The offer can't be used. Scan it (or a new offer) again.
+    RescanOffer,
+
+    /// The presentation request can't be used. Scan it (or a new request)
+    /// again.
+    RescanRequest,
+}
+
+/// Events that can be sent to the wallet application that pertain to
+/// recovering from errors.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RecoveryEvent {
+    /// Event emitted by the core when an error occurs that the user may be
+    /// able to recover from. The flow the error occurred in is kept so it can
+    /// be resumed.
+    #[serde(skip)]
+    Failed { message: String, recovery: Recovery },
+
+    /// Event emitted by the shell when the user chooses to recover from the
+    /// error.
+    Recover,
+}
+
+/// Recovery event processing.
+pub fn recovery_event(event: RecoveryEvent, model: &mut Model) -> Command<Effect, Event> {
+    match event {
+        RecoveryEvent::Failed { message, recovery } => failed(&message, recovery, model),
+        RecoveryEvent::Recover => recover(model),
+    }
+}
+
+/// Raise an error the user may be able to recover from.
+pub fn recoverable(message: impl Into<String>, recovery: Recovery) -> Command<Effect, Event> {
+    Command::event(Event::Recovery(RecoveryEvent::Failed {
+        message: message.into(),
+        recovery,
+    }))
+}
+
+/// Process a `RecoveryEvent::Failed` event. Show the error along with the
+/// recovery available to the user.
+fn failed(message: &str, recovery: Recovery, model: &mut Model) -> Command<Effect, Event> {
+    *model = model.recoverable_error(message, recovery);
+    render()
+}
+
+/// Process a `RecoveryEvent::Recover` event. Restore the flow the error
+/// occurred in and take the next step for the recovery.
+fn recover(model: &mut Model) -> Command<Effect, Event> {
+    let recovery = model.get_recovery();
+    *model = match model.recover() {
+        Ok(m) => m,
+        Err(e) => {
+            return Command::event(Event::Error(e.to_string()));
+        }
+    };
+    match recovery {
+        Recovery::RetryToken => request_token(model, None),
+        Recovery::Dismiss => refresh_credentials(),
+        Recovery::RescanOffer | Recovery::RescanRequest => render(),
+    }
+}
@@ -0,0 +1,187 @@
+//! # Browser Capability
+//!
+//! Opens a URL in the shell's system browser (an authentication session on
+//! mobile platforms) and waits for the browser to be redirected back to the
+//! app. Used to send the holder to an issuer's authorization server for
+//! authorization code issuance.
+use std::fmt::Debug;
+use std::future::Future;
+use std::marker::PhantomData;
+
+use crux_core::capability::{CapabilityContext, Operation};
+use crux_core::command::RequestBuilder;
+use crux_core::{Capability, Command, Request};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors that can be returned by the browser capability.
+#[derive(Clone, Debug, Deserialize, Serialize, Error, PartialEq, Eq)]
+pub enum BrowserError {
+    /// The user closed the browser before being redirected back to the app.
+    #[error("browser session cancelled")]
+    Cancelled,
+
+    /// Invalid request.
+    #[error("invalid browser request {message}")]
+    InvalidRequest { message: String },
+
+    /// The response from the shell capability was invalid.
+    #[error("invalid browser response {message}")]
+    InvalidResponse { message: String },
+}
+
+//--- Command based API --------------------------------------------------------
+
+pub struct BrowserCommand<Effect, Event> {
+    effect: PhantomData<Effect>,
+    event: PhantomData<Event>,
+}
+
+type AuthorizeResult = Result<String, BrowserError>;
+
+impl<Effect, Event> BrowserCommand<Effect, Event>
+where
+    Effect: Send + From<Request<BrowserOperation>> + 'static,
+    Event: Send + 'static,
+{
+    pub fn authorize(
+        url: impl Into<String>, callback_scheme: impl Into<String>,
+    ) -> RequestBuilder<Effect, Event, impl Future<Output = AuthorizeResult>> {
+        Command::request_from_shell(BrowserOperation::Authorize {
+            url: url.into(),
+            callback_scheme: callback_scheme.into(),
+        })
+        .map(|result| result.unwrap_authorize())
+    }
+}
+
+//------------------------------------------------------------------------------
+
+/// Supported operations for the browser capability.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BrowserOperation {
+    /// Open the URL in the system browser and wait for a redirect to a URL
+    /// with the callback scheme.
+    Authorize { url: String, callback_scheme: String },
+}
+
+impl Debug for BrowserOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BrowserOperation::Authorize { url, callback_scheme } => f
+                .debug_struct("Authorize")
+                .field("url", url)
+                .field("callback_scheme", callback_scheme)
+                .finish(),
+        }
+    }
+}
+
+/// The possible responses from the browser capability.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BrowserResponse {
+    /// The browser was redirected back to the app. The URL includes the
+    /// authorization server's response as query parameters.
+    Redirected { url: String },
+}
+
+/// The result of an operation on the browser.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BrowserResult {
+    /// The operation was successful.
+    Ok { response: BrowserResponse },
+
+    /// The operation failed.
+    Err { error: BrowserError },
+}
+
+impl BrowserResult {
+    fn unwrap_authorize(self) -> Result<String, BrowserError> {
+        match self {
+            BrowserResult::Ok {
+                response: BrowserResponse::Redirected { url },
+            } => Ok(url),
+            BrowserResult::Err { error } => Err(error),
+        }
+    }
+}
+
+impl Operation for BrowserOperation {
+    type Output = BrowserResult;
+}
+
+/// Capability type for the browser.
+pub struct Browser<Ev> {
+    context: CapabilityContext<BrowserOperation, Ev>,
+}
+
+impl<Ev> Capability<Ev> for Browser<Ev> {
+    type MappedSelf<MappedEv> = Browser<MappedEv>;
+    type Operation = BrowserOperation;
+
+    fn map_event<F, NewEv>(&self, f: F) -> Self::MappedSelf<NewEv>
+    where
+        F: Fn(NewEv) -> Ev + Send + Sync + 'static,
+        Ev: 'static,
+        NewEv: 'static + Send,
+    {
+        Browser::new(self.context.map_event(f))
+    }
+
+    #[cfg(feature = "typegen")]
+    fn register_types(generator: &mut crux_core::typegen::TypeGen) -> crux_core::typegen::Result {
+        generator.register_type::<BrowserResponse>()?;
+        generator.register_type::<BrowserError>()?;
+        generator.register_type::<Self::Operation>()?;
+        generator.register_type::<<Self::Operation as Operation>::Output>()?;
+        Ok(())
+    }
+}
+
+impl<Ev> Clone for Browser<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<Ev> Browser<Ev>
+where
+    Ev: 'static,
+{
+    /// Create a new browser capability.
+    pub fn new(context: CapabilityContext<BrowserOperation, Ev>) -> Self {
+        Self { context }
+    }
+
+    /// Open a URL in the system browser and send an update event to the
+    /// application with the URL the browser was redirected to.
+    pub fn authorize<F>(
+        &self, url: impl Into<String> + Send + 'static,
+        callback_scheme: impl Into<String> + Send + 'static, make_event: F,
+    ) where
+        F: FnOnce(Result<String, BrowserError>) -> Ev + Send + Sync + 'static,
+    {
+        self.context.spawn({
+            let context = self.context.clone();
+            async move {
+                let response = authorize(&context, url, callback_scheme).await;
+                context.update_app(make_event(response))
+            }
+        });
+    }
+}
+
+async fn authorize<Ev: 'static>(
+    context: &CapabilityContext<BrowserOperation, Ev>, url: impl Into<String>,
+    callback_scheme: impl Into<String>,
+) -> Result<String, BrowserError> {
+    context
+        .request_from_shell(BrowserOperation::Authorize {
+            url: url.into(),
+            callback_scheme: callback_scheme.into(),
+        })
+        .await
+        .unwrap_authorize()
+}
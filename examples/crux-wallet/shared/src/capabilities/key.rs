@@ -1,4 +1,7 @@
 //! # Key Store Capability
+//!
+//! Keys are stored by ID and purpose so the wallet can hold multiple named
+//! keys. Credentials record the ID of the key they are bound to.
 use std::fmt::Debug;
 use std::future::Future;
 use std::marker::PhantomData;
@@ -51,6 +54,8 @@ pub struct KeyStoreCommand<Effect, Event> {
 }
 
 type GetResult = Result<KeyStoreEntry, KeyStoreError>;
+type ListResult = Result<Vec<String>, KeyStoreError>;
+type OpResult = Result<(), KeyStoreError>;
 
 impl<Effect, Event> KeyStoreCommand<Effect, Event>
 where 
@@ -64,6 +69,50 @@ where
         })
         .map(|result| result.unwrap_get())
     }
+
+    /// Generate and store a new key. Fails if a key with the ID already
+    /// exists.
+    pub fn create(
+        id: impl Into<String>, purpose: impl Into<String>,
+    ) -> RequestBuilder<Effect, Event, impl Future<Output = GetResult>> {
+        Command::request_from_shell(KeyStoreOperation::Create {
+            id: id.into(),
+            purpose: purpose.into(),
+        })
+        .map(|result| result.unwrap_create())
+    }
+
+    /// List the IDs of the keys stored for a purpose.
+    pub fn list(
+        purpose: impl Into<String>,
+    ) -> RequestBuilder<Effect, Event, impl Future<Output = ListResult>> {
+        Command::request_from_shell(KeyStoreOperation::List {
+            purpose: purpose.into(),
+        })
+        .map(|result| result.unwrap_list())
+    }
+
+    /// Replace a stored key with a newly generated one.
+    pub fn rotate(
+        id: impl Into<String>, purpose: impl Into<String>,
+    ) -> RequestBuilder<Effect, Event, impl Future<Output = GetResult>> {
+        Command::request_from_shell(KeyStoreOperation::Rotate {
+            id: id.into(),
+            purpose: purpose.into(),
+        })
+        .map(|result| result.unwrap_rotate())
+    }
+
+    /// Delete a key from the key store.
+    pub fn delete(
+        id: impl Into<String>, purpose: impl Into<String>,
+    ) -> RequestBuilder<Effect, Event, impl Future<Output = OpResult>> {
+        Command::request_from_shell(KeyStoreOperation::Delete {
+            id: id.into(),
+            purpose: purpose.into(),
+        })
+        .map(|result| result.unwrap_delete())
+    }
 }
 
 //------------------------------------------------------------------------------
@@ -74,6 +123,20 @@ pub enum KeyStoreOperation {
     /// Get a serialized private key from the key store. If none exists, one
     /// is generated and stored for future get requests.
     Get { id: String, purpose: String },
+
+    /// Generate a new private key and store it. Fails if a key with the same
+    /// ID and purpose already exists.
+    Create { id: String, purpose: String },
+
+    /// List the IDs of the keys stored for a purpose.
+    List { purpose: String },
+
+    /// Replace a stored private key with a newly generated one. Anything
+    /// bound to the old key will need to be re-bound to the new one.
+    Rotate { id: String, purpose: String },
+
+    /// Delete a private key from the key store.
+    Delete { id: String, purpose: String },
 }
 
 impl Debug for KeyStoreOperation {
@@ -82,6 +145,18 @@ impl Debug for KeyStoreOperation {
             KeyStoreOperation::Get { id, purpose } => {
                 f.debug_struct("Get").field("id", id).field("purpose", purpose).finish()
             }
+            KeyStoreOperation::Create { id, purpose } => {
+                f.debug_struct("Create").field("id", id).field("purpose", purpose).finish()
+            }
+            KeyStoreOperation::List { purpose } => {
+                f.debug_struct("List").field("purpose", purpose).finish()
+            }
+            KeyStoreOperation::Rotate { id, purpose } => {
+                f.debug_struct("Rotate").field("id", id).field("purpose", purpose).finish()
+            }
+            KeyStoreOperation::Delete { id, purpose } => {
+                f.debug_struct("Delete").field("id", id).field("purpose", purpose).finish()
+            }
         }
     }
 }
@@ -91,6 +166,18 @@ impl Debug for KeyStoreOperation {
 pub enum KeyStoreResponse {
     /// The result of a get operation.
     Retrieved { key: KeyStoreEntry },
+
+    /// The result of a create operation.
+    Created { key: KeyStoreEntry },
+
+    /// The result of a list operation.
+    Listed { ids: Vec<String> },
+
+    /// The result of a rotate operation. Contains the new key.
+    Rotated { key: KeyStoreEntry },
+
+    /// The result of a delete operation.
+    Deleted,
 }
 
 /// The result of an operation on the key store.
@@ -109,9 +196,58 @@ impl KeyStoreResult {
             KeyStoreResult::Ok {
                 response: KeyStoreResponse::Retrieved { key },
             } => Ok(key),
+            KeyStoreResult::Ok { .. } => Err(unexpected("Get")),
+            KeyStoreResult::Err { error } => Err(error),
+        }
+    }
+
+    fn unwrap_create(self) -> Result<KeyStoreEntry, KeyStoreError> {
+        match self {
+            KeyStoreResult::Ok {
+                response: KeyStoreResponse::Created { key },
+            } => Ok(key),
+            KeyStoreResult::Ok { .. } => Err(unexpected("Create")),
+            KeyStoreResult::Err { error } => Err(error),
+        }
+    }
+
+    fn unwrap_list(self) -> Result<Vec<String>, KeyStoreError> {
+        match self {
+            KeyStoreResult::Ok {
+                response: KeyStoreResponse::Listed { ids },
+            } => Ok(ids),
+            KeyStoreResult::Ok { .. } => Err(unexpected("List")),
+            KeyStoreResult::Err { error } => Err(error),
+        }
+    }
+
+    fn unwrap_rotate(self) -> Result<KeyStoreEntry, KeyStoreError> {
+        match self {
+            KeyStoreResult::Ok {
+                response: KeyStoreResponse::Rotated { key },
+            } => Ok(key),
+            KeyStoreResult::Ok { .. } => Err(unexpected("Rotate")),
             KeyStoreResult::Err { error } => Err(error),
         }
     }
+
+    fn unwrap_delete(self) -> Result<(), KeyStoreError> {
+        match self {
+            KeyStoreResult::Ok {
+                response: KeyStoreResponse::Deleted,
+            } => Ok(()),
+            KeyStoreResult::Ok { .. } => Err(unexpected("Delete")),
+            KeyStoreResult::Err { error } => Err(error),
+        }
+    }
+}
+
+// Error for a shell response that does not match the operation. The response
+// is not included as it may contain key material.
+fn unexpected(operation: &str) -> KeyStoreError {
+    KeyStoreError::InvalidResponse {
+        message: format!("unexpected response for {operation} operation"),
+    }
 }
 
 impl Operation for KeyStoreOperation {
@@ -1,10 +1,16 @@
+//! # Server-Sent Events Capability
+//!
+//! Subscribes to a server-sent event stream. Each message is expected to be
+//! JSON and is deserialized before being sent to the app as an event.
+use std::marker::PhantomData;
 
-use async_sse::{decode, Event};
+use async_sse::{decode, Event as SseEvent};
 use async_std::io::Cursor;
 use futures::StreamExt;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crux_core::capability::{CapabilityContext, Operation};
+use crux_core::{Command, Request};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct SseRequest {
@@ -21,6 +27,50 @@ impl Operation for SseRequest {
     type Output = SseResponse;
 }
 
+//--- Command based API --------------------------------------------------------
+
+pub struct SseCommand<Effect, Event> {
+    effect: PhantomData<Effect>,
+    event: PhantomData<Event>,
+}
+
+impl<Effect, Event> SseCommand<Effect, Event>
+where
+    Effect: Send + From<Request<SseRequest>> + 'static,
+    Event: Send + 'static,
+{
+    /// Subscribe to a stream of JSON messages and send an event to the app for
+    /// each. Messages that cannot be deserialized are ignored.
+    pub fn get_json<F, T>(url: impl Into<String>, make_event: F) -> Command<Effect, Event>
+    where
+        F: Fn(T) -> Event + Send + 'static,
+        T: DeserializeOwned,
+    {
+        let url = url.into();
+        Command::new(move |ctx| async move {
+            let mut stream = ctx.stream_from_shell(SseRequest { url });
+            while let Some(response) = stream.next().await {
+                match response {
+                    SseResponse::Chunk(data) => {
+                        let mut reader = decode(Cursor::new(data));
+                        while let Some(sse_event) = reader.next().await {
+                            let Ok(SseEvent::Message(msg)) = sse_event else {
+                                continue;
+                            };
+                            if let Ok(t) = serde_json::from_slice(msg.data()) {
+                                ctx.send_event(make_event(t));
+                            }
+                        }
+                    }
+                    SseResponse::Done => break,
+                }
+            }
+        })
+    }
+}
+
+//------------------------------------------------------------------------------
+
 #[derive(crux_core::macros::Capability)]
 pub struct ServerSentEvents<Ev> {
     context: CapabilityContext<SseRequest, Ev>,
@@ -54,7 +104,7 @@ where
                             let mut reader = decode(Cursor::new(data));
 
                             while let Some(sse_event) = reader.next().await {
-                                if let Ok(Event::Message(msg)) = sse_event {
+                                if let Ok(SseEvent::Message(msg)) = sse_event {
                                     let t: T = serde_json::from_slice(msg.data()).unwrap();
                                     context.update_app(make_event(t));
                                 }
@@ -1,24 +1,50 @@
 //! # Verifiable Credential Store Capability
+//!
+//! The command based API encrypts data before it is handed to the shell and
+//! decrypts it on the way back, so the shell only ever stores ciphertext. The
+//! encryption key is sourced from the key store capability.
 use std::fmt::{self, Debug, Display};
 use std::future::Future;
 use std::marker::PhantomData;
 
+use credibil_holder::provider::Encryptor;
 use crux_core::capability::{CapabilityContext, Operation};
-use crux_core::command::RequestBuilder;
+use crux_core::command::{CommandContext, RequestBuilder};
 use crux_core::{Capability, Command, Request};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::key::{KeyStoreCommand, KeyStoreOperation};
+use crate::encryptor::EncryptorProvider;
+
+/// Key store ID of the key used to encrypt the store.
+const ENCRYPTION_KEY_ID: &str = "store";
+
+/// Key store purpose of the key used to encrypt the store.
+const ENCRYPTION_KEY_PURPOSE: &str = "encryption";
+
 /// Pre-defined store catalogs.
 pub enum Catalog {
     /// Cedentials collection.
     Credential,
+
+    /// Deferred credentials the wallet is waiting on.
+    Deferred,
+
+    /// An in-progress flow saved while the app is in the background.
+    Flow,
+
+    /// Details for notifying issuers when their credentials are deleted.
+    Notification,
 }
 
 impl Display for Catalog {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Catalog::Credential => write!(f, "credential"),
+            Catalog::Deferred => write!(f, "deferred"),
+            Catalog::Flow => write!(f, "flow"),
+            Catalog::Notification => write!(f, "notification"),
         }
     }
 }
@@ -85,27 +111,53 @@ type ListResult = Result<Vec<StoreEntry>, StoreError>;
 
 impl<Effect, Event> StoreCommand<Effect, Event>
 where
-    Effect: Send + From<Request<StoreOperation>> + 'static,
+    Effect: Send + From<Request<StoreOperation>> + From<Request<KeyStoreOperation>> + 'static,
     Event: Send + 'static,
 {
+    /// Serialize and encrypt the data and save it to the store.
     pub fn save(
         catalog: impl Into<String>, id: impl Into<String>, data: impl Serialize,
     ) -> RequestBuilder<Effect, Event, impl Future<Output = OpResult>> {
-        Command::request_from_shell(StoreOperation::Save {
-            catalog: catalog.into(),
-            id: id.into(),
-            data: serde_json::to_vec(&data).unwrap(),
+        let catalog = catalog.into();
+        let id = id.into();
+        let plaintext = serde_json::to_vec(&data).map_err(|e| StoreError::InvalidRequest {
+            message: format!("failed to serialize data: {e}"),
+        });
+        RequestBuilder::new(move |ctx| async move {
+            let plaintext = plaintext?;
+            let encryptor = encryptor(&ctx).await?;
+            let data =
+                encryptor.encrypt(&plaintext).await.map_err(|e| StoreError::InvalidRequest {
+                    message: format!("failed to encrypt data: {e}"),
+                })?;
+            ctx.request_from_shell(StoreOperation::Save { catalog, id, data }).await.unwrap_save()
         })
-        .map(|result| result.unwrap_save())
     }
 
+    /// Get all entries from the store, decrypted.
     pub fn list(
         catalog: impl Into<String>,
     ) -> RequestBuilder<Effect, Event, impl Future<Output = ListResult>> {
-        Command::request_from_shell(StoreOperation::List {
-            catalog: catalog.into(),
+        let catalog = catalog.into();
+        RequestBuilder::new(move |ctx| async move {
+            let encryptor = encryptor(&ctx).await?;
+            let entries =
+                ctx.request_from_shell(StoreOperation::List { catalog }).await.unwrap_list()?;
+            let mut decrypted = Vec::with_capacity(entries.len());
+            for entry in entries {
+                let StoreEntry::Data(ciphertext) = entry else {
+                    decrypted.push(entry);
+                    continue;
+                };
+                let plaintext = encryptor.decrypt(&ciphertext).await.map_err(|e| {
+                    StoreError::InvalidResponse {
+                        message: format!("failed to decrypt data: {e}"),
+                    }
+                })?;
+                decrypted.push(StoreEntry::Data(plaintext));
+            }
+            Ok(decrypted)
         })
-        .map(|result| result.unwrap_list())
     }
 
     pub fn delete(
@@ -119,6 +171,26 @@ where
     }
 }
 
+// Get an encryptor using the store's key from the key store.
+async fn encryptor<Effect, Event>(
+    ctx: &CommandContext<Effect, Event>,
+) -> Result<EncryptorProvider, StoreError>
+where
+    Effect: Send + From<Request<KeyStoreOperation>> + 'static,
+    Event: Send + 'static,
+{
+    let key = KeyStoreCommand::get(ENCRYPTION_KEY_ID, ENCRYPTION_KEY_PURPOSE)
+        .into_future(ctx.clone())
+        .await
+        .map_err(|e| StoreError::InvalidRequest {
+            message: format!("failed to get encryption key: {e}"),
+        })?;
+    let bytes: Vec<u8> = key.into();
+    EncryptorProvider::new(&bytes).map_err(|e| StoreError::InvalidRequest {
+        message: format!("invalid encryption key: {e}"),
+    })
+}
+
 //------------------------------------------------------------------------------
 
 /// Supported operations for the Store capability.
@@ -3,6 +3,10 @@
 //! Some hard-coded configuration values for the wallet app. In a real app,
 //! each installed instance would need its own configuration.
 
+use std::sync::{PoisonError, RwLock};
+
+use crate::trust_list::TrustList;
+
 /// Get the client ID for the wallet app. In practice this should be a unique
 /// device ID that has been registered with the issuer.
 pub fn client_id() -> String {
@@ -14,3 +18,83 @@ pub fn client_id() -> String {
 pub fn subject_id() -> String {
     "normal_user".to_string()
 }
+
+/// Get the URI the issuer's authorization server redirects the holder back to
+/// after authorizing issuance. The shell must be able to handle the URI's
+/// scheme.
+pub fn redirect_uri() -> String {
+    format!("{}://callback", callback_scheme())
+}
+
+/// Get the custom URL scheme the shell listens on for authorization
+/// redirects.
+pub fn callback_scheme() -> String {
+    "io.credibil.wallet".to_string()
+}
+
+/// Get the number of PINs the issuer can reject before the wallet abandons the
+/// issuance flow. Issuers may also stop accepting PINs for an offer sooner.
+pub fn max_pin_attempts() -> u32 {
+    3
+}
+
+/// Get the URL of the trust list naming the issuers and verifiers the wallet
+/// trusts. In practice this would be published by the operator of a trust
+/// framework rather than by the demonstration service.
+pub fn trust_list_url() -> String {
+    "http://localhost:8080/trust_list".to_string()
+}
+
+/// Get the public key (base64url Ed25519) the trust list must be signed with.
+pub fn trust_list_key() -> String {
+    "y1LUzVdFhyQ2uqjFz3u-4nAJmc7Ry8XexfOWdecayqA".to_string()
+}
+
+/// The most recently fetched trust list.
+static TRUST_LIST: RwLock<Option<TrustList>> = RwLock::new(None);
+
+/// Get the client IDs of the verifiers the wallet trusts. Empty if the trust
+/// list hasn't been fetched or has expired.
+pub fn trusted_verifiers() -> Vec<String> {
+    TRUST_LIST
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .filter(|list| !list.is_expired())
+        .map(|list| list.verifiers.iter().map(|v| v.id.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Set the trust list once it has been fetched and verified.
+pub fn set_trust_list(list: TrustList) {
+    *TRUST_LIST.write().unwrap_or_else(PoisonError::into_inner) = Some(list);
+}
+
+/// The user's preferred locale as a BCP 47 language tag (e.g. `en-NZ`). Set by
+/// the shell from the device settings.
+static LOCALE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Get the user's preferred locale, if the shell has set one. Used to select
+/// the issuer's display information (names, claim labels and images) in the
+/// user's language.
+pub fn locale() -> Option<String> {
+    LOCALE.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Set the user's preferred locale.
+pub fn set_locale(locale: &str) {
+    *LOCALE.write().unwrap_or_else(PoisonError::into_inner) = Some(locale.into());
+}
+
+/// Select the display for the user's locale from an issuer's list of
+/// localized displays. Falls back to the issuer's default display (no locale),
+/// then the first display if the issuer doesn't provide a translation.
+pub fn localized<'a, T>(
+    displays: &'a [T], locale_of: impl Fn(&T) -> Option<&str>,
+) -> Option<&'a T> {
+    let locale = locale();
+    locale
+        .and_then(|loc| displays.iter().find(|d| locale_of(d) == Some(loc.as_str())))
+        .or_else(|| displays.iter().find(|d| locale_of(d).is_none()))
+        .or_else(|| displays.first())
+}
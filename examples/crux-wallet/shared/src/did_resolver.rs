@@ -1,17 +1,32 @@
 //! DID Resolver provider callbacks for resolving DID documents.
+//!
+//! `did:key` and `did:jwk` documents are derived from the DID itself so are
+//! resolved without a network request. Crux won't let us pass a DID resolver
+//! that needs to use the shell, so `did:web` documents are fetched by the app
+//! first (see [`document_url`]) and handed to the provider.
 
-use credibil_holder::did::{DidResolver, Document};
+use anyhow::bail;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_holder::did::{DidResolver, DidWeb, Document};
+use credibil_holder::infosec::Jws;
+use serde_json::{json, Value};
+
+/// Multicodec prefix for an Ed25519 public key.
+const ED25519_CODEC: [u8; 2] = [0xed, 0x01];
 
 /// DID Resolver provider.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct DidResolverProvider {
-    did_document: Document,
+    did_document: Option<Document>,
 }
 
 impl DidResolverProvider {
-    /// Create a new provider.
+    /// Create a new provider with a DID document that has been fetched by the
+    /// app.
     pub fn new(did_document: &Document) -> Self {
-        Self { did_document: did_document.clone() }
+        Self {
+            did_document: Some(did_document.clone()),
+        }
     }
 }
 
@@ -20,9 +35,123 @@ impl DidResolver for DidResolverProvider {
     ///
     /// # Errors
     ///
-    /// Does not return an error, but has a `Result` return type to satisfy the
-    /// trait.
-    async fn resolve(&self, _url:  &str) -> anyhow::Result<Document> {
-        Ok(self.did_document.clone())
+    /// Returns an error if the DID method is not supported, the DID cannot be
+    /// decoded, or a `did:web` document has not been fetched.
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        let did = url.split('#').next().unwrap_or_default();
+        match method(did) {
+            Some("key") => did_key(did),
+            Some("jwk") => did_jwk(did),
+            Some("web") => match &self.did_document {
+                Some(document) if document.id == did => Ok(document.clone()),
+                _ => bail!("DID document for {did} has not been fetched"),
+            },
+            _ => bail!("unsupported DID method for {did}"),
+        }
+    }
+}
+
+/// Get the URL of the DID document needed to verify a compact JWS, if the
+/// document has to be fetched. Returns `None` if the signer's DID can be
+/// resolved without a network request.
+///
+/// # Errors
+///
+/// Returns an error if the JWS cannot be parsed, has no key ID, or the key ID
+/// uses an unsupported DID method.
+pub fn document_url(compact: &str) -> anyhow::Result<Option<String>> {
+    let jws: Jws = compact.parse()?;
+    let Some(signature) = jws.signatures.first() else {
+        bail!("expected at least one signature");
+    };
+    let Some(key_id) = signature.protected.kid() else {
+        bail!("expected key ID in signature header");
+    };
+    let did = key_id.split('#').next().unwrap_or_default();
+    match method(did) {
+        Some("key" | "jwk") => Ok(None),
+        Some("web") => Ok(Some(DidWeb::url(did)?)),
+        _ => bail!("unsupported DID method for {did}"),
     }
-}
\ No newline at end of file
+}
+
+// Get the method name from a DID.
+fn method(did: &str) -> Option<&str> {
+    let mut parts = did.splitn(3, ':');
+    if parts.next() != Some("did") {
+        return None;
+    }
+    parts.next()
+}
+
+// Derive the DID document for an Ed25519 `did:key`.
+fn did_key(did: &str) -> anyhow::Result<Document> {
+    let Some(multi_key) = did.strip_prefix("did:key:") else {
+        bail!("expected did:key");
+    };
+    let (_, bytes) = multibase::decode(multi_key)?;
+    let Some(key) = bytes.strip_prefix(&ED25519_CODEC) else {
+        bail!("only Ed25519 did:key is supported");
+    };
+    let jwk = json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "x": Base64UrlUnpadded::encode_string(key),
+    });
+    document(did, &format!("{did}#{multi_key}"), jwk)
+}
+
+// Derive the DID document for a `did:jwk`.
+fn did_jwk(did: &str) -> anyhow::Result<Document> {
+    let Some(encoded) = did.strip_prefix("did:jwk:") else {
+        bail!("expected did:jwk");
+    };
+    let Ok(bytes) = Base64UrlUnpadded::decode_vec(encoded) else {
+        bail!("did:jwk is not base64url encoded");
+    };
+    let jwk: Value = serde_json::from_slice(&bytes)?;
+    document(did, &format!("{did}#0"), jwk)
+}
+
+// Construct a DID document with a single verification method.
+fn document(did: &str, method_id: &str, jwk: Value) -> anyhow::Result<Document> {
+    let document = json!({
+        "@context": ["https://www.w3.org/ns/did/v1"],
+        "id": did,
+        "verificationMethod": [{
+            "id": method_id,
+            "type": "JsonWebKey2020",
+            "controller": did,
+            "publicKeyJwk": jwk,
+        }],
+        "authentication": [method_id],
+        "assertionMethod": [method_id],
+    });
+    Ok(serde_json::from_value(document)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::signer::SignerProvider;
+
+    // The wallet's own did:key resolves without a fetched document.
+    #[test]
+    fn resolve_did_key() {
+        let signer = SignerProvider::new(&[7u8; 32]).expect("should create signer");
+        let kid = signer.verification_method_sync().expect("should get verification method");
+        let resolver = DidResolverProvider::default();
+        let document = block_on(resolver.resolve(&kid)).expect("should resolve did:key");
+        assert_eq!(Some(document.id.as_str()), kid.split('#').next());
+    }
+
+    // A did:web cannot be resolved until its document has been fetched.
+    #[test]
+    fn resolve_did_web_not_fetched() {
+        let resolver = DidResolverProvider::default();
+        let result = block_on(resolver.resolve("did:web:example.com#key-0"));
+        assert!(result.is_err());
+    }
+}
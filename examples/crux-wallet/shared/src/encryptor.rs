@@ -0,0 +1,65 @@
+//! Encryptor provider callbacks for protecting stored data at rest.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail};
+use credibil_holder::provider::Encryptor;
+
+/// Size of the AES-GCM nonce in bytes. The nonce is prepended to the
+/// ciphertext.
+const NONCE_SIZE: usize = 12;
+
+/// Encryptor provider using AES-256-GCM with a key from the key store.
+pub struct EncryptorProvider {
+    cipher: Aes256Gcm,
+}
+
+impl EncryptorProvider {
+    /// Create a new provider.
+    pub fn new(secret: &[u8]) -> anyhow::Result<Self> {
+        let bytes: [u8; 32] = secret.try_into()?;
+        Ok(Self {
+            cipher: Aes256Gcm::new(&bytes.into()),
+        })
+    }
+}
+
+/// Implementation of the `credibil-holder::Encryptor` trait.
+impl Encryptor for EncryptorProvider {
+    /// Encrypt the plaintext with a random nonce.
+    async fn encrypt(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext).map_err(|e| anyhow!("{e}"))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// Decrypt ciphertext created by `encrypt`.
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if ciphertext.len() < NONCE_SIZE {
+            bail!("ciphertext too short");
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_SIZE);
+        self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|e| anyhow!("{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    // Data encrypted by the provider can be decrypted, but not if it has been
+    // modified.
+    #[test]
+    fn round_trip() {
+        let encryptor = EncryptorProvider::new(&[3u8; 32]).expect("should create encryptor");
+        let mut ciphertext = block_on(encryptor.encrypt(b"credential")).expect("should encrypt");
+        let plaintext = block_on(encryptor.decrypt(&ciphertext)).expect("should decrypt");
+        assert_eq!(plaintext, b"credential");
+
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+        assert!(block_on(encryptor.decrypt(&ciphertext)).is_err());
+    }
+}
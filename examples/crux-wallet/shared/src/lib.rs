@@ -5,7 +5,9 @@ pub mod app;
 pub mod capabilities;
 mod config;
 mod did_resolver;
+mod encryptor;
 mod signer;
+mod trust_list;
 mod model;
 pub mod view;
 
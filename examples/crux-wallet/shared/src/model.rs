@@ -1,22 +1,31 @@
 //! Model for the wallet application state.
 
 pub mod credential;
+mod deferred;
+mod error;
+mod flow;
 mod issuance;
 mod presentation;
 
+use std::sync::Arc;
+
 use anyhow::bail;
-pub use credential::CredentialState;
+pub use credential::{CredentialState, IssuerNotification, IssuerNotified};
+pub use deferred::PendingCredential;
+pub use error::ErrorState;
+pub use flow::{SavedFlow, SavedState};
 use credibil_holder::credential::Credential;
 use credibil_holder::issuance::proof::Payload;
 use credibil_holder::issuance::{
-    CredentialRequest, CredentialResponse, Issuer, ProofClaims, TokenRequest, TokenResponse,
-    VerifiableCredential,
+    CredentialRequest, CredentialResponse, Issuer, ProofClaims, Server, TokenRequest,
+    TokenResponse, VerifiableCredential,
 };
 use credibil_holder::presentation::{Constraints, RequestObject, ResponseRequest};
-pub use issuance::{IssuanceState, OfferedCredential};
-pub use presentation::PresentationState;
+pub use issuance::{GrantFlow, IssuanceState, OfferedCredential};
+pub use presentation::{PresentationState, VerifierIdentity};
 
 use super::Aspect;
+use crate::app::recovery::Recovery;
 use crate::capabilities::store::StoreEntry;
 
 /// State for the wallet application.
@@ -32,7 +41,7 @@ pub enum State {
     Presentation(Box<PresentationState>),
 
     /// The application is in an error state.
-    Error(String),
+    Error(Box<ErrorState>),
 }
 
 /// Application state model. Combines the aspect (screen or page) with the
@@ -61,10 +70,60 @@ impl Model {
     pub fn error(&self, error: &str) -> Self {
         Self {
             active_view: Aspect::Error,
-            state: State::Error(error.into()),
+            state: State::Error(Box::new(ErrorState {
+                message: error.into(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// An error has occurred that the user may be able to recover from. Set
+    /// the error state, keeping the current flow so it can be resumed.
+    pub fn recoverable_error(&self, error: &str, recovery: Recovery) -> Self {
+        let flow = match &self.state {
+            State::Error(state) => state.flow.clone(),
+            state => Some(state.clone()),
+        };
+        Self {
+            active_view: Aspect::Error,
+            state: State::Error(Box::new(ErrorState {
+                message: error.into(),
+                recovery,
+                flow,
+            })),
         }
     }
 
+    /// Get the recovery available for the current error. `Dismiss` if not in
+    /// an error state.
+    pub fn get_recovery(&self) -> Recovery {
+        let State::Error(state) = &self.state else {
+            return Recovery::Dismiss;
+        };
+        state.recovery.clone()
+    }
+
+    /// The user has chosen to recover from the current error. Resume the flow
+    /// the error occurred in or start a new one, depending on the recovery.
+    pub fn recover(&self) -> anyhow::Result<Self> {
+        let State::Error(state) = &self.state else {
+            bail!("not in error state");
+        };
+        let active_view = match state.recovery {
+            Recovery::Dismiss => return Ok(self.ready()),
+            Recovery::RescanOffer => return Ok(self.scan_issuance_offer()),
+            Recovery::RescanRequest => return Ok(self.scan_presentation_request()),
+            Recovery::RetryToken => Aspect::IssuanceOffer,
+        };
+        let Some(flow) = &state.flow else {
+            bail!("no flow to resume");
+        };
+        Ok(Self {
+            active_view,
+            state: flow.clone(),
+        })
+    }
+
     /// Set up the model with an initial state.
     pub fn ready(&self) -> Self {
         Self {
@@ -127,16 +186,105 @@ impl Model {
         }
     }
 
-    /// The credentials have been retrieved from the wallet's store.
+    /// The credentials have been retrieved from the wallet's store. Stay on
+    /// the confirmation of a deleted credential if it is showing.
     pub fn credentials_loaded(&self, entries: Vec<StoreEntry>) -> Self {
         let mut new_state = CredentialState::init();
+        if let Ok(cred_state) = self.credential_state() {
+            new_state.pending.clone_from(&cred_state.pending);
+            new_state.deleted.clone_from(&cred_state.deleted);
+        }
         new_state.set_credentials(entries);
+        let active_view = if new_state.deleted.is_some() {
+            Aspect::CredentialDeleted
+        } else {
+            Aspect::CredentialList
+        };
         Self {
-            active_view: Aspect::CredentialList,
+            active_view,
             state: State::Credential(Box::new(new_state)),
         }
     }
 
+    /// The user has deleted a credential. Confirm the deletion while the
+    /// issuer is notified.
+    pub fn credential_deleted(&self, id: &str) -> Self {
+        let Ok(cred_state) = self.credential_state() else {
+            return self.ready();
+        };
+        let mut new_state = cred_state.clone();
+        new_state.delete(id);
+        Self {
+            active_view: Aspect::CredentialDeleted,
+            state: State::Credential(Box::new(new_state)),
+        }
+    }
+
+    /// The wallet has finished notifying the issuer of a deleted credential.
+    pub fn issuer_notified(&self, notified: IssuerNotified) -> Self {
+        let Ok(cred_state) = self.credential_state() else {
+            return self.clone();
+        };
+        let mut new_state = cred_state.clone();
+        if let Some(deleted) = &mut new_state.deleted {
+            deleted.issuer_notified = notified;
+        }
+        Self {
+            active_view: self.active_view.clone(),
+            state: State::Credential(Box::new(new_state)),
+        }
+    }
+
+    /// The pending (deferred) credentials have been retrieved from the
+    /// wallet's store.
+    pub fn pending_loaded(&self, entries: Vec<StoreEntry>) -> Self {
+        let mut new_state = match self.credential_state() {
+            Ok(cred_state) => cred_state.clone(),
+            Err(_) => CredentialState::init(),
+        };
+        new_state.set_pending(entries);
+        Self {
+            active_view: self.active_view.clone(),
+            state: State::Credential(Box::new(new_state)),
+        }
+    }
+
+    /// Get the pending (deferred) credentials.
+    pub fn get_pending_credentials(&self) -> Vec<PendingCredential> {
+        let Ok(state) = self.credential_state() else {
+            return Vec::new();
+        };
+        state.pending.clone()
+    }
+
+    /// Get the ID of the key a credential is bound to if deleting the
+    /// credential leaves the key unused.
+    pub fn get_unbound_key(&self, id: &str) -> Option<String> {
+        let Ok(state) = self.credential_state() else {
+            return None;
+        };
+        state.unbound_key(id)
+    }
+
+    /// Get a pending credential by transaction ID.
+    pub fn get_pending_credential(&self, transaction_id: &str) -> Option<PendingCredential> {
+        let Ok(state) = self.credential_state() else {
+            return None;
+        };
+        state.pending.iter().find(|p| p.transaction_id == transaction_id).cloned()
+    }
+
+    //--- Link entry -----------------------------------------------------------
+
+    /// The user wants to paste or type a link to an offer or presentation
+    /// request.
+    pub fn link_entry(&self) -> Self {
+        Self {
+            active_view: Aspect::LinkEntry,
+            state: self.state.clone(),
+        }
+    }
+
     //--- Issuance state -------------------------------------------------------
 
     /// The user wants to scan an issuance offer QR code.
@@ -147,8 +295,8 @@ impl Model {
         }
     }
 
-    /// The user has scanned an issuance offer QR code so we can initiate a
-    /// pre-authorized issuance flow.
+    /// The user has scanned an issuance offer QR code so we can initiate an
+    /// issuance flow.
     pub fn issuance_offer(&self, encoded_offer: &str) -> anyhow::Result<Self> {
         let state = IssuanceState::from_offer(encoded_offer)?;
         Ok(Self {
@@ -167,29 +315,63 @@ impl Model {
         })
     }
 
-    /// Get the first offered credential from issuance state.
-    /// TODO: Add support for multiple offered credentials.
-    pub fn get_offered_credential(&self) -> Option<OfferedCredential> {
+    /// Check to see if the issuance flow needs authorization server metadata
+    /// before it can start.
+    pub fn issuance_needs_auth_server(&self) -> bool {
+        if let State::Issuance(state) = &self.state {
+            return state.needs_auth_server();
+        };
+        false
+    }
+
+    /// The app has received the authorization server metadata.
+    pub fn issuance_auth_server(&self, server: Server) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.auth_server(server)?;
+        Ok(Self {
+            active_view: Aspect::IssuanceOffer,
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// Get the offered credentials from issuance state.
+    pub fn get_offered_credentials(&self) -> Vec<OfferedCredential> {
         let Ok(state) = self.issuance_state() else {
-            return None;
+            return Vec::new();
         };
-        state.get_offered_credential()
+        state.get_offered_credentials()
     }
 
-    /// The app has received display logo information.
-    pub fn issuance_logo(&self, image_data: &[u8], media_type: &str) -> anyhow::Result<Self> {
+    /// The app has received display logo information for an offered
+    /// credential.
+    pub fn issuance_logo(
+        &self, config_id: &str, image_data: &[u8], media_type: &str,
+    ) -> anyhow::Result<Self> {
         let state = self.issuance_state()?;
-        let new_state = state.logo(image_data, media_type)?;
+        let new_state = state.logo(config_id, image_data, media_type)?;
         Ok(Self {
             active_view: self.active_view.clone(),
             state: State::Issuance(Box::new(new_state)),
         })
     }
 
-    /// The app has received display background image information.
-    pub fn issuance_background(&self, image_data: &[u8], media_type: &str) -> anyhow::Result<Self> {
+    /// The app has received display background image information for an
+    /// offered credential.
+    pub fn issuance_background(
+        &self, config_id: &str, image_data: &[u8], media_type: &str,
+    ) -> anyhow::Result<Self> {
         let state = self.issuance_state()?;
-        let new_state = state.background(image_data, media_type)?;
+        let new_state = state.background(config_id, image_data, media_type)?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// The user has included or excluded an offered credential.
+    pub fn issuance_toggle(&self, config_id: &str) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.toggle(config_id)?;
         Ok(Self {
             active_view: self.active_view.clone(),
             state: State::Issuance(Box::new(new_state)),
@@ -215,17 +397,61 @@ impl Model {
     }
 
     /// Get the issuer metadata for the current issuance flow.
-    pub fn issuer(&self) -> Option<Issuer> {
+    pub fn issuer(&self) -> Option<Arc<Issuer>> {
         if let State::Issuance(state) = &self.state {
             return state.issuer();
         };
         None
     }
 
-    /// Construct a token request from issuance state.
-    pub fn get_token_request(&self) -> anyhow::Result<TokenRequest> {
+    /// Check to see if the issuance flow has been pre-authorized by the
+    /// issuer.
+    pub fn issuance_pre_authorized(&self) -> bool {
+        if let State::Issuance(state) = &self.state {
+            return state.is_pre_authorized();
+        };
+        false
+    }
+
+    /// Check to see if the issuer requires a PIN for the issuance flow,
+    /// whether or not one has been entered.
+    pub fn issuance_pin_required(&self) -> bool {
+        if let State::Issuance(state) = &self.state {
+            return state.pin_required();
+        };
+        false
+    }
+
+    /// Check to see if the user needs to authorize issuance with the issuer's
+    /// authorization server.
+    pub fn issuance_needs_authorization(&self) -> bool {
+        if let State::Issuance(state) = &self.state {
+            return state.needs_authorization();
+        };
+        false
+    }
+
+    /// Create an authorization request for the issuance flow.
+    pub fn issuance_authorize(&self) -> anyhow::Result<Self> {
         let state = self.issuance_state()?;
-        state.token_request()
+        let new_state = state.authorize()?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// Get the URL to send the user to the authorization server.
+    pub fn get_authorization_url(&self) -> anyhow::Result<String> {
+        let state = self.issuance_state()?;
+        state.get_authorization_url()
+    }
+
+    /// Construct a token request from issuance state. `redirect` is the URL
+    /// the authorization server redirected the user to, if any.
+    pub fn get_token_request(&self, redirect: Option<&str>) -> anyhow::Result<TokenRequest> {
+        let state = self.issuance_state()?;
+        state.token_request(redirect)
     }
 
     /// The user has entered their PIN to prove they are in control of the
@@ -239,6 +465,25 @@ impl Model {
         })
     }
 
+    /// The issuer has rejected the PIN the user entered. Ask for it again.
+    pub fn issuance_pin_rejected(&self) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.pin_rejected()?;
+        Ok(Self {
+            active_view: Aspect::IssuancePin,
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// Get the number of PINs the user can still enter before the issuance
+    /// flow is abandoned. `None` until the issuer has rejected a PIN.
+    pub fn issuance_pin_attempts_remaining(&self) -> Option<u32> {
+        if let State::Issuance(state) = &self.state {
+            return state.pin_attempts_remaining();
+        };
+        None
+    }
+
     /// Update the model state with a token response.
     pub fn issuance_token(&self, token: &TokenResponse) -> anyhow::Result<Self> {
         let state = self.issuance_state()?;
@@ -249,6 +494,12 @@ impl Model {
         })
     }
 
+    /// Get the ID of the key the credentials being issued are bound to.
+    pub fn get_issuance_key_id(&self) -> anyhow::Result<String> {
+        let state = self.issuance_state()?;
+        state.get_key_id()
+    }
+
     /// Get proof claims from issuance flow state.
     pub fn get_proof_claims(&self) -> anyhow::Result<ProofClaims> {
         let state = self.issuance_state()?;
@@ -265,11 +516,18 @@ impl Model {
         })
     }
 
-    /// Get a credential request for the first offered credential.
-    /// TODO: Add support for multiple offered credentials.
-    pub fn get_credential_request(&self, jwt: &str) -> anyhow::Result<(String, CredentialRequest)> {
+    /// Get the next credential request to send to the issuer.
+    pub fn get_credential_request(&self) -> anyhow::Result<(String, CredentialRequest)> {
         let state = &self.issuance_state()?;
-        state.get_credential_request(jwt)
+        state.get_credential_request()
+    }
+
+    /// Check to see if there are credential requests still to be sent.
+    pub fn issuance_pending(&self) -> bool {
+        if let State::Issuance(state) = &self.state {
+            return state.has_pending();
+        };
+        false
     }
 
     /// Retrieve the access token from the issuance flow state.
@@ -312,14 +570,57 @@ impl Model {
         })
     }
 
-    /// Get the credential from the issuance flow that is in a format suitable
-    /// for storage and display in the wallet.
-    /// TODO: Add support for multiple credentials.
+    /// Get the most recently issued credential from the issuance flow in a
+    /// format suitable for storage and display in the wallet.
     pub fn get_storable_credential(&self) -> anyhow::Result<Credential> {
         let state = self.issuance_state()?;
         state.get_storable_credential()
     }
 
+    /// Get the details needed to tell the issuer if the most recently issued
+    /// credential is deleted. `None` if the issuer does not want to be told.
+    pub fn get_issuer_notification(&self) -> Option<IssuerNotification> {
+        let state = self.issuance_state().ok()?;
+        state.get_issuer_notification()
+    }
+
+    /// The most recently issued credential has been stored.
+    pub fn issuance_stored(&self) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.stored()?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// The issuer has deferred the most recently requested credential.
+    pub fn issuance_deferred(&self) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.deferred()?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
+    /// Get a pending credential for the deferred credential response so it
+    /// can be saved to the wallet's store.
+    pub fn get_issuance_pending_credential(&self) -> anyhow::Result<PendingCredential> {
+        let state = self.issuance_state()?;
+        state.get_pending_credential()
+    }
+
+    /// Move the issuance flow on to the next credential request, if any.
+    pub fn issuance_next(&self) -> anyhow::Result<Self> {
+        let state = self.issuance_state()?;
+        let new_state = state.next()?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Issuance(Box::new(new_state)),
+        })
+    }
+
     //--- Presentation state ---------------------------------------------------
 
     /// The user wants to scan an issuance offer QR code.
@@ -376,6 +677,17 @@ impl Model {
         })
     }
 
+    /// The user has included or excluded a matching credential from the
+    /// presentation.
+    pub fn presentation_toggle(&self, id: &str) -> anyhow::Result<Self> {
+        let state = self.presentation_state()?;
+        let new_state = state.toggle(id)?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Presentation(Box::new(new_state)),
+        })
+    }
+
     /// User authorizes the presentation.
     pub fn presentation_approve(&self) -> anyhow::Result<Self> {
         let state = self.presentation_state()?;
@@ -386,17 +698,79 @@ impl Model {
         })
     }
 
-    /// Construct a presentation payload from the presentation flow state.
+    /// Get the ID of the next key to sign a presentation with.
+    pub fn get_presentation_key_id(&self) -> anyhow::Result<String> {
+        let state = self.presentation_state()?;
+        state.get_key_id()
+    }
+
+    /// Construct a presentation payload for the next key to sign with from
+    /// the presentation flow state.
     pub fn get_presentation_payload(&self, kid: &str) -> anyhow::Result<Payload> {
         let state = self.presentation_state()?;
         state.get_payload(kid)
     }
 
+    /// A presentation has been signed.
+    pub fn presentation_proof(&self, jws: &str) -> anyhow::Result<Self> {
+        let state = self.presentation_state()?;
+        let new_state = state.proof(jws)?;
+        Ok(Self {
+            active_view: self.active_view.clone(),
+            state: State::Presentation(Box::new(new_state)),
+        })
+    }
+
+    /// Whether a presentation has been signed with every key the approved
+    /// credentials are bound to.
+    pub fn presentation_signed(&self) -> bool {
+        self.presentation_state().is_ok_and(PresentationState::is_signed)
+    }
+
     /// Construct a presentation response request.
-    pub fn create_response_request(
-        &self, jws: &str,
-    ) -> anyhow::Result<(ResponseRequest, Option<String>)> {
+    pub fn create_response_request(&self) -> anyhow::Result<(ResponseRequest, Option<String>)> {
         let state = self.presentation_state()?;
-        state.create_response_request(jws)
+        state.create_response_request()
+    }
+
+    //--- Saved flow -----------------------------------------------------------
+
+    /// Get the in-progress flow to save when the app is backgrounded. `None`
+    /// if there is no flow in progress or it can't be resumed after a
+    /// restart.
+    pub fn get_saved_flow(&self) -> Option<SavedFlow> {
+        let state = match &self.state {
+            State::Issuance(state) if state.is_resumable() => SavedState::Issuance(state.clone()),
+            State::Presentation(state) if state.is_resumable() => {
+                SavedState::Presentation(state.clone())
+            }
+            _ => return None,
+        };
+        Some(SavedFlow::new(self.active_view.clone(), state))
+    }
+
+    /// A saved flow has been retrieved from the wallet's store. The flow is
+    /// restored unless it has expired or the user has already left the
+    /// credential list. A flow saved by an incompatible version of the app is
+    /// discarded.
+    pub fn flow_restored(&self, entries: Vec<StoreEntry>) -> Self {
+        if self.active_view != Aspect::CredentialList || self.credential_state().is_err() {
+            return self.clone();
+        }
+        let saved = entries.into_iter().find_map(|entry| match entry {
+            StoreEntry::Data(bytes) => serde_json::from_slice::<SavedFlow>(&bytes).ok(),
+            StoreEntry::None => None,
+        });
+        let Some(saved) = saved.filter(|s| !s.is_expired()) else {
+            return self.clone();
+        };
+        let state = match saved.state {
+            SavedState::Issuance(state) => State::Issuance(state),
+            SavedState::Presentation(state) => State::Presentation(state),
+        };
+        Self {
+            active_view: saved.active_view,
+            state,
+        }
     }
 }
@@ -1,8 +1,10 @@
 //! Credential sub-app state.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use credibil_holder::credential::Credential;
 
+use super::PendingCredential;
 use crate::capabilities::store::StoreEntry;
 
 /// Application state for the credential sub-app.
@@ -14,6 +16,72 @@ pub struct CredentialState {
 
     /// Credentials stored in the wallet.
     pub credentials: Vec<Credential>,
+
+    /// Credentials the issuer has deferred that the wallet is waiting on.
+    pub pending: Vec<PendingCredential>,
+
+    /// The credential the user has just deleted, while the deletion is
+    /// confirmed to them.
+    pub deleted: Option<DeletedCredential>,
+}
+
+/// A credential the user has deleted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeletedCredential {
+    /// The deleted credential.
+    pub credential: Credential,
+
+    /// Whether the issuer has been told the credential was deleted.
+    pub issuer_notified: IssuerNotified,
+}
+
+/// Progress of telling an issuer a credential has been deleted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum IssuerNotified {
+    /// The wallet is finding out whether the issuer wants to be notified and,
+    /// if so, notifying it.
+    #[default]
+    Sending,
+
+    /// The issuer did not ask to be notified about the credential.
+    NotRequested,
+
+    /// The issuer has accepted the notification.
+    Sent,
+
+    /// The issuer could not be notified.
+    Failed,
+}
+
+/// Details needed to tell an issuer a credential has been deleted.
+///
+/// Saved to the store when a credential is issued if the issuer has a
+/// notification endpoint and returned a notification ID with the credential.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IssuerNotification {
+    /// ID of the credential the notification is about. Used as the store ID.
+    pub credential_id: String,
+
+    /// The issuer's notification endpoint.
+    pub notification_endpoint: String,
+
+    /// Notification ID the issuer returned with the credential.
+    pub notification_id: String,
+
+    /// Access token the credential was issued with. Notification requests are
+    /// authorized with the same token.
+    pub access_token: String,
+}
+
+impl IssuerNotification {
+    /// Body of a notification request telling the issuer the credential has
+    /// been deleted.
+    pub fn deleted(&self) -> Value {
+        json!({
+            "notification_id": self.notification_id,
+            "event": "credential_deleted",
+        })
+    }
 }
 
 impl CredentialState {
@@ -23,6 +91,8 @@ impl CredentialState {
         Self {
             id: None,
             credentials: vec![],
+            pending: vec![],
+            deleted: None,
         }
     }
 
@@ -38,4 +108,46 @@ impl CredentialState {
         }
         self.credentials = credentials;
     }
+
+    /// Set the pending (deferred) credential list from a set of StoreEntries.
+    pub fn set_pending(&mut self, entries: Vec<StoreEntry>) {
+        let mut pending = vec![];
+        for entry in entries {
+            if let StoreEntry::Data(bytes) = entry {
+                let credential: PendingCredential =
+                    serde_json::from_slice(&bytes).expect("should deserialize");
+                pending.push(credential);
+            }
+        }
+        self.pending = pending;
+    }
+
+    /// Mark a credential as deleted so the deletion can be confirmed to the
+    /// user.
+    pub fn delete(&mut self, id: &str) {
+        self.deleted =
+            self.credentials.iter().find(|c| c.id == id).map(|credential| DeletedCredential {
+                credential: credential.clone(),
+                issuer_notified: IssuerNotified::Sending,
+            });
+        self.credentials.retain(|c| c.id != id);
+    }
+
+    /// Get the ID of the key a credential is bound to if no other stored or
+    /// pending credential is bound to it, so the key can be deleted along with
+    /// the credential.
+    pub fn unbound_key(&self, id: &str) -> Option<String> {
+        let key_id = self.credentials.iter().find(|c| c.id == id)?.key_id.clone()?;
+        let in_use = self
+            .credentials
+            .iter()
+            .filter(|c| c.id != id)
+            .filter_map(|c| c.key_id.as_ref())
+            .chain(self.pending.iter().filter_map(|p| p.key_id.as_ref()))
+            .any(|k| *k == key_id);
+        if in_use {
+            return None;
+        }
+        Some(key_id)
+    }
 }
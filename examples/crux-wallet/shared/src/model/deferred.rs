@@ -0,0 +1,108 @@
+//! Deferred credential state.
+
+use anyhow::bail;
+use credibil_holder::credential::{Credential, ImageData};
+use credibil_holder::issuance::{
+    Accepted, DeferredCredentialRequest, VerifiableCredential, WithToken,
+};
+use credibil_holder::Kind;
+use serde::{Deserialize, Serialize};
+
+use super::{GrantFlow, OfferedCredential};
+
+/// A credential the issuer has deferred.
+///
+/// Pending credentials are saved to the store so the wallet can poll the
+/// issuer's deferred credential endpoint when the app resumes. The issuance
+/// flow is saved with the transaction so the access token and issuer metadata
+/// are available to request and store the credential.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingCredential {
+    /// Transaction ID returned by the issuer. Used as the store ID.
+    pub transaction_id: String,
+
+    /// Credential configuration identifier.
+    pub config_id: String,
+
+    /// Logo image data.
+    pub logo: Option<ImageData>,
+
+    /// Background image data.
+    pub background: Option<ImageData>,
+
+    /// ID of the key the credential will be bound to.
+    #[serde(default)]
+    pub key_id: Option<String>,
+
+    /// Issuance flow state at the time the credential was deferred.
+    pub flow: GrantFlow<Accepted, WithToken>,
+}
+
+impl PendingCredential {
+    /// Create a pending credential from an offered credential, the ID of the
+    /// key used to sign the proof, and the issuance flow.
+    pub fn new(
+        transaction_id: &str, offered: &OfferedCredential, key_id: &str,
+        flow: GrantFlow<Accepted, WithToken>,
+    ) -> Self {
+        Self {
+            transaction_id: transaction_id.into(),
+            config_id: offered.config_id.clone(),
+            logo: offered.logo.clone(),
+            background: offered.background.clone(),
+            key_id: Some(key_id.into()),
+            flow,
+        }
+    }
+
+    /// The issuer's deferred credential endpoint, if it has one.
+    pub fn deferred_url(&self) -> Option<String> {
+        self.flow.issuer().deferred_credential_endpoint.clone()
+    }
+
+    /// Construct a deferred credential request for the transaction.
+    pub fn request(&self) -> DeferredCredentialRequest {
+        self.flow.deferred_request(&self.transaction_id)
+    }
+
+    /// The offered credential the transaction is for. Used to display the
+    /// pending credential.
+    pub fn offered(&self) -> OfferedCredential {
+        let issuer = self.flow.issuer();
+        OfferedCredential {
+            config_id: self.config_id.clone(),
+            config: issuer
+                .credential_configurations_supported
+                .get(&self.config_id)
+                .cloned()
+                .unwrap_or_default(),
+            logo: self.logo.clone(),
+            background: self.background.clone(),
+            accepted: true,
+            stored: false,
+            deferred: true,
+        }
+    }
+
+    /// Get the issued credential in a format suitable for storage and display
+    /// in the wallet. The credential is bound to the key used to sign the
+    /// proof.
+    pub fn storable_credential(
+        &self, vc: &VerifiableCredential, vc_kind: &Kind<VerifiableCredential>, issued_at: &i64,
+    ) -> anyhow::Result<Credential> {
+        let mut flow = self.flow.clone();
+        flow.add_credential(
+            vc,
+            vc_kind,
+            issued_at,
+            &self.config_id,
+            self.logo.clone(),
+            self.background.clone(),
+        )?;
+        let Some(mut credential) = flow.credentials().pop() else {
+            bail!("no credential in issuance flow");
+        };
+        credential.key_id.clone_from(&self.key_id);
+        Ok(credential)
+    }
+}
@@ -0,0 +1,18 @@
+//! Error state.
+
+use super::State;
+use crate::app::recovery::Recovery;
+
+/// Application state when an error has occurred.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorState {
+    /// The error message.
+    pub message: String,
+
+    /// How the user can recover from the error.
+    pub recovery: Recovery,
+
+    /// The state of the flow the error occurred in. Restored if the user
+    /// retries the failed step.
+    pub flow: Option<State>,
+}
@@ -0,0 +1,52 @@
+//! Saved flow state.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{IssuanceState, PresentationState};
+use crate::app::Aspect;
+
+/// How long a saved flow can be restored for. Pre-authorized codes and
+/// presentation requests typically expire well within this time.
+const FLOW_EXPIRY: Duration = Duration::minutes(15);
+
+/// An issuance or presentation flow saved to the store when the app is
+/// backgrounded so it survives the OS killing the app.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SavedFlow {
+    /// The aspect the user was on when the flow was saved.
+    pub active_view: Aspect,
+
+    /// The flow state.
+    pub state: SavedState,
+
+    /// When the flow was saved.
+    pub saved_at: DateTime<Utc>,
+}
+
+/// State of a saved flow.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum SavedState {
+    /// An issuance flow waiting on the user to accept the offer or enter a
+    /// PIN.
+    Issuance(Box<IssuanceState>),
+
+    /// A presentation flow waiting on the user to approve the presentation.
+    Presentation(Box<PresentationState>),
+}
+
+impl SavedFlow {
+    /// Create a saved flow for the state, timestamped now.
+    pub fn new(active_view: Aspect, state: SavedState) -> Self {
+        Self {
+            active_view,
+            state,
+            saved_at: Utc::now(),
+        }
+    }
+
+    /// Whether the flow is too old to be restored.
+    pub fn is_expired(&self) -> bool {
+        self.saved_at + FLOW_EXPIRY < Utc::now()
+    }
+}
@@ -1,19 +1,24 @@
 //! Issuance sub-app state.
+use std::sync::Arc;
+
 use anyhow::bail;
 use base64ct::{Base64, Encoding};
 use credibil_holder::credential::{Credential, ImageData};
 use credibil_holder::issuance::{
-    Accepted, CredentialConfiguration, CredentialOffer, CredentialResponse, CredentialResponseType,
-    IssuanceFlow, Issuer, NotAccepted, PreAuthorized, PreAuthorizedCodeGrant, ProofClaims,
-    VerifiableCredential, WithOffer, WithToken, WithoutToken,
+    Accepted, AuthCode, AuthorizationRequest, AuthorizationSpec, CredentialConfiguration,
+    CredentialOffer, CredentialResponse, CredentialResponseType, DeferredCredentialRequest,
+    IssuanceFlow, IssuanceFlowBuilder, Issuer, NotAccepted, PreAuthorized, PreAuthorizedCodeGrant,
+    ProofClaims, Server, VerifiableCredential, WithOffer, WithToken, WithoutToken,
 };
 use credibil_holder::provider::{CredentialRequest, TokenRequest, TokenResponse};
-use credibil_holder::urlencode;
+use credibil_holder::{urlencode, Kind};
+use serde::{Deserialize, Serialize};
 
+use super::{IssuerNotification, PendingCredential};
 use crate::config;
 
 /// Configuration and image information for an offered credential.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct OfferedCredential {
     /// Credential configuration identifier.
     pub config_id: String,
@@ -26,95 +31,257 @@ pub struct OfferedCredential {
 
     /// Background image data.
     pub background: Option<ImageData>,
+
+    /// Whether the user has chosen to accept this credential.
+    pub accepted: bool,
+
+    /// Whether the credential has been issued and stored in the wallet.
+    pub stored: bool,
+
+    /// Whether the issuer has deferred issuance of the credential.
+    pub deferred: bool,
 }
 
 impl OfferedCredential {
-    /// Determine if the credential logo needs to be fetched.
+    /// Determine if the credential logo needs to be fetched. The logo is
+    /// taken from the display for the user's locale.
     pub fn logo_url(&self) -> Option<String> {
         if self.logo.is_some() {
             return None;
         }
-        if let Some(display) = &self.config.display {
-            if let Some(logo) = &display[0].logo {
-                return logo.uri.clone();
-            }
-        }
-        None
+        let display = self.config.display.as_deref()?;
+        let display = config::localized(display, |d| d.locale.as_deref())?;
+        display.logo.as_ref()?.uri.clone()
     }
 
-    /// Determine if the credential background needs to be fetched.
+    /// Determine if the credential background needs to be fetched. The
+    /// background is taken from the display for the user's locale.
     pub fn background_url(&self) -> Option<String> {
         if self.background.is_some() {
             return None;
         }
-        if let Some(display) = &self.config.display {
-            if let Some(background) = &display[0].background_image {
-                return background.uri.clone();
+        let display = self.config.display.as_deref()?;
+        let display = config::localized(display, |d| d.locale.as_deref())?;
+        display.background_image.as_ref()?.uri.clone()
+    }
+}
+
+/// An issuance flow for either of the grant types supported by the wallet.
+///
+/// The SDK tracks the grant type as a type parameter so the wallet can't know
+/// the flow's type until it has seen the offer. Methods common to both grant
+/// types are delegated to the wrapped flow.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum GrantFlow<A, T> {
+    /// The issuer has pre-authorized issuance.
+    PreAuthorized(IssuanceFlow<WithOffer, PreAuthorized, A, T>),
+
+    /// The holder must authorize issuance with the issuer's authorization
+    /// server.
+    AuthCode(IssuanceFlow<WithOffer, AuthCode, A, T>),
+}
+
+impl<A, T> GrantFlow<A, T> {
+    /// Get the credential issuer metadata.
+    pub fn issuer(&self) -> Arc<Issuer> {
+        match self {
+            Self::PreAuthorized(flow) => flow.issuer(),
+            Self::AuthCode(flow) => flow.issuer(),
+        }
+    }
+
+    /// Get the original offer.
+    pub fn offer(&self) -> Arc<CredentialOffer> {
+        match self {
+            Self::PreAuthorized(flow) => flow.offer(),
+            Self::AuthCode(flow) => flow.offer(),
+        }
+    }
+}
+
+impl GrantFlow<NotAccepted, WithoutToken> {
+    /// Accept the offered credentials.
+    pub fn accept(
+        self, accepted: &Option<Vec<AuthorizationSpec>>,
+    ) -> GrantFlow<Accepted, WithoutToken> {
+        match self {
+            Self::PreAuthorized(flow) => GrantFlow::PreAuthorized(flow.accept(accepted, None)),
+            Self::AuthCode(flow) => GrantFlow::AuthCode(flow.accept(accepted, None)),
+        }
+    }
+}
+
+impl<T> GrantFlow<Accepted, T> {
+    /// Get the PIN entered by the user. Always `None` for authorization code
+    /// flows.
+    pub fn pin(&self) -> Option<String> {
+        match self {
+            Self::PreAuthorized(flow) => flow.pin().map(|pin| pin.expose().to_string()),
+            Self::AuthCode(_) => None,
+        }
+    }
+}
+
+impl<A> GrantFlow<A, WithoutToken> {
+    /// Add the token response to the flow.
+    pub fn token(self, token: TokenResponse) -> GrantFlow<A, WithToken> {
+        match self {
+            Self::PreAuthorized(flow) => GrantFlow::PreAuthorized(flow.token(token)),
+            Self::AuthCode(flow) => GrantFlow::AuthCode(flow.token(token)),
+        }
+    }
+}
+
+impl GrantFlow<Accepted, WithToken> {
+    /// Get the token response.
+    pub fn get_token(&self) -> TokenResponse {
+        match self {
+            Self::PreAuthorized(flow) => flow.get_token(),
+            Self::AuthCode(flow) => flow.get_token(),
+        }
+    }
+
+    /// Construct a credential request for each of the credential identifiers.
+    pub fn credential_requests(
+        &self, identifiers: &[String], jwt: &str,
+    ) -> Vec<(String, CredentialRequest)> {
+        match self {
+            Self::PreAuthorized(flow) => flow.credential_requests(identifiers, jwt).collect(),
+            Self::AuthCode(flow) => flow.credential_requests(identifiers, jwt).collect(),
+        }
+    }
+}
+
+impl<A> GrantFlow<A, WithToken> {
+    /// Get the claims for a proof of possession.
+    pub fn proof(&self) -> ProofClaims {
+        match self {
+            Self::PreAuthorized(flow) => flow.proof(),
+            Self::AuthCode(flow) => flow.proof(),
+        }
+    }
+
+    /// Add an issued credential to the flow.
+    pub fn add_credential(
+        &mut self, vc: &VerifiableCredential, encoded: &Kind<VerifiableCredential>,
+        issued_at: &i64, config_id: &str, logo: Option<ImageData>, background: Option<ImageData>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::PreAuthorized(flow) => {
+                flow.add_credential(vc, encoded, issued_at, config_id, logo, background)
+            }
+            Self::AuthCode(flow) => {
+                flow.add_credential(vc, encoded, issued_at, config_id, logo, background)
             }
         }
-        None
+    }
+
+    /// Get the credentials issued in the flow.
+    pub fn credentials(&self) -> Vec<Credential> {
+        match self {
+            Self::PreAuthorized(flow) => flow.credentials(),
+            Self::AuthCode(flow) => flow.credentials(),
+        }
+    }
+
+    /// Construct a deferred credential request for the transaction.
+    pub fn deferred_request(&self, transaction_id: &str) -> DeferredCredentialRequest {
+        match self {
+            Self::PreAuthorized(flow) => flow.deferred_request(transaction_id),
+            Self::AuthCode(flow) => flow.deferred_request(transaction_id),
+        }
     }
 }
 
 /// Application state for the issuance sub-app.
 ///
-/// Note: We use a `Vec` to store the offered credentials because the standard
-/// allows for multiple credentials to be offered at once. However, the
-/// application event model only supports a single credential at this point in
-/// time. The first credential encountered in the offer is the one that will
-/// move through the issuance process. Perhaps the solution to this is to
-/// check state of each credential and keep raising the same event on each step,
-/// but thought is required on the user experience in controlling this "loop".
-#[derive(Clone, Debug, Default)]
+/// The standard allows for multiple credentials to be offered at once. All
+/// offered credentials are displayed and the user can choose which of them to
+/// accept. Once a proof has been created, a credential request is made for
+/// each accepted credential. The requests are kept in `pending` and processed
+/// one at a time: each response is verified and stored before the next request
+/// is sent.
+///
+/// Offers with a pre-authorized code grant go straight from acceptance to a
+/// token request. Otherwise, the wallet fetches the issuer's authorization
+/// server metadata and, once the offer is accepted, sends the user to the
+/// authorization server in the system browser. The authorization code
+/// returned to the redirect URI is exchanged for an access token.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[allow(clippy::module_name_repetitions)]
 pub enum IssuanceState {
     /// No issuance is in progress.
     #[default]
     Inactive,
 
-    /// An offer has been received
-    Offered { offer: CredentialOffer, grant: PreAuthorizedCodeGrant },
+    /// An offer has been received. The grant is `None` if the offer has not
+    /// been pre-authorized.
+    Offered { offer: CredentialOffer, grant: Option<PreAuthorizedCodeGrant> },
+
+    /// Issuer metadata has been received for an offer that has not been
+    /// pre-authorized. The authorization server metadata is needed before the
+    /// flow can be started.
+    AwaitingAuthServer { offer: CredentialOffer, issuer: Issuer },
 
     /// Issuer metadata has been received. Can use this state to keep updating
     /// the offered credentials' logo and background images.
-    IssuerMetadata {
-        flow: IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken>,
-        offered: Vec<OfferedCredential>,
-    },
+    IssuerMetadata { flow: GrantFlow<NotAccepted, WithoutToken>, offered: Vec<OfferedCredential> },
 
     /// The offer has been accepted by the user. Can use this state to update
-    /// the PIN number if needed.
+    /// the PIN number if needed. `pin_attempts` counts the PINs the issuer has
+    /// rejected.
     Accepted {
-        flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>,
+        flow: GrantFlow<Accepted, WithoutToken>,
         offered: Vec<OfferedCredential>,
+        #[serde(default)]
+        pin_attempts: u32,
     },
 
-    /// An access token has been received.
-    Token {
-        flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+    /// The user has been sent to the authorization server to authorize
+    /// issuance. The PKCE verifier is kept for the token request.
+    Authorizing {
+        flow: IssuanceFlow<WithOffer, AuthCode, Accepted, WithoutToken>,
         offered: Vec<OfferedCredential>,
+        request: AuthorizationRequest,
+        verifier: String,
     },
 
+    /// An access token has been received. `key_id` is the ID of the new key
+    /// the issued credentials will be bound to.
+    Token { flow: GrantFlow<Accepted, WithToken>, offered: Vec<OfferedCredential>, key_id: String },
+
     /// A proof has been created. Can use this state to receive credentials and
     /// update the offered list to keep track of outstanding credentials. Can
     /// also use it to keep track of the credentials stored.
     Proof {
-        flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+        flow: GrantFlow<Accepted, WithToken>,
         offered: Vec<OfferedCredential>,
+        key_id: String,
         proof: String,
+        pending: Vec<(String, CredentialRequest)>,
     },
 
-    /// A credential response has been received.
+    /// A credential response has been received for the credential
+    /// configuration `config_id`.
     Issued {
-        flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+        flow: GrantFlow<Accepted, WithToken>,
         offered: Vec<OfferedCredential>,
+        key_id: String,
         proof: String,
+        pending: Vec<(String, CredentialRequest)>,
+        config_id: String,
         issued: CredentialResponse,
     },
 }
 
 /// State change implementation.
 impl IssuanceState {
+    /// Whether the flow is waiting on the user and can be resumed if the app
+    /// is restarted. Flows waiting on the issuer or the system browser cannot.
+    pub fn is_resumable(&self) -> bool {
+        matches!(self, Self::IssuerMetadata { .. } | Self::Accepted { .. })
+    }
+
     /// Create an issuance state from a URL-encoded offer.
     pub fn from_offer(encoded_offer: &str) -> anyhow::Result<Self> {
         // let Ok(offer_str) = urlencoding::decode(encoded_offer) else {
@@ -127,16 +294,10 @@ impl IssuanceState {
             bail!("failed to deserialize offer string");
         };
 
-        // Check the offer has a pre-authorized grant. This is the only flow
-        // type supported by this wallet (for now).
-        let Some(pre_auth_code_grant) = offer.pre_authorized_code() else {
-            bail!("grant other than pre-authorized code is not supported");
-        };
-
-        Ok(Self::Offered {
-            offer,
-            grant: pre_auth_code_grant,
-        })
+        // Offers without a pre-authorized grant use the authorization code
+        // flow.
+        let grant = offer.pre_authorized_code();
+        Ok(Self::Offered { offer, grant })
     }
 
     /// Determine if a PIN is required.
@@ -155,96 +316,160 @@ impl IssuanceState {
         }
     }
 
-    /// Update flow based on receiving issuer metadata.
+    /// Determine if the offer has been accepted using a pre-authorized code
+    /// grant.
+    pub fn is_pre_authorized(&self) -> bool {
+        matches!(
+            self,
+            Self::Accepted {
+                flow: GrantFlow::PreAuthorized(_),
+                ..
+            }
+        )
+    }
+
+    /// Determine if the issuer requires a PIN for the accepted offer, whether
+    /// or not one has been entered.
+    pub fn pin_required(&self) -> bool {
+        match self {
+            Self::Accepted { flow, .. } => flow
+                .offer()
+                .pre_authorized_code()
+                .is_some_and(|pre_auth| pre_auth.tx_code.is_some()),
+            _ => false,
+        }
+    }
+
+    /// Update flow based on receiving issuer metadata. If the offer has not
+    /// been pre-authorized, wait for the authorization server metadata.
     pub fn issuer_metadata(&self, issuer: Issuer) -> anyhow::Result<Self> {
         let Self::Offered { offer, grant } = self else {
             bail!("unexpected issuance state to apply issuer metadata");
         };
-        let flow = IssuanceFlow::<WithOffer, PreAuthorized, NotAccepted, WithoutToken>::new(
-            &config::client_id(),
-            &config::subject_id(),
-            issuer.clone(),
-            offer.clone(),
-            grant.clone(),
-        );
-        let mut creds = Vec::<OfferedCredential>::new();
-        for config_id in &offer.credential_configuration_ids {
-            if let Some(config) = issuer.credential_configurations_supported.get(config_id) {
-                creds.push(OfferedCredential {
-                    config_id: config_id.clone(),
-                    config: config.clone(),
-                    logo: None,
-                    background: None,
-                });
-            }
-        }
-        let new_state = Self::IssuerMetadata { flow, offered: creds };
+        let Some(grant) = grant else {
+            return Ok(Self::AwaitingAuthServer {
+                offer: offer.clone(),
+                issuer,
+            });
+        };
+        let flow = IssuanceFlowBuilder::new(config::client_id(), issuer.clone())
+            .subject_id(config::subject_id())
+            .pre_authorized(offer.clone(), grant.clone());
+        let offered = offered_credentials(offer, &issuer);
+        let new_state = Self::IssuerMetadata {
+            flow: GrantFlow::PreAuthorized(flow),
+            offered,
+        };
+        Ok(new_state)
+    }
+
+    /// Determine if the authorization server metadata is needed to start the
+    /// flow.
+    pub fn needs_auth_server(&self) -> bool {
+        matches!(self, Self::AwaitingAuthServer { .. })
+    }
+
+    /// Update flow based on receiving authorization server metadata.
+    pub fn auth_server(&self, server: Server) -> anyhow::Result<Self> {
+        let Self::AwaitingAuthServer { offer, issuer } = self else {
+            bail!("unexpected issuance state to apply authorization server metadata");
+        };
+        let flow = IssuanceFlowBuilder::new(config::client_id(), issuer.clone())
+            .subject_id(config::subject_id())
+            .offer(offer.clone(), server);
+        let offered = offered_credentials(offer, issuer);
+        let new_state = Self::IssuerMetadata {
+            flow: GrantFlow::AuthCode(flow),
+            offered,
+        };
         Ok(new_state)
     }
 
     /// Get the issuer metadata.
-    pub fn issuer(&self) -> Option<Issuer> {
+    pub fn issuer(&self) -> Option<Arc<Issuer>> {
         match self {
             Self::Inactive | Self::Offered { .. } => None,
-            Self::IssuerMetadata { flow, .. } => Some(flow.issuer().clone()),
-            Self::Accepted { flow, .. } => Some(flow.issuer().clone()),
-            Self::Token { flow, .. } => Some(flow.issuer().clone()),
-            Self::Proof { flow, .. } => Some(flow.issuer().clone()),
-            Self::Issued { flow, .. } => Some(flow.issuer().clone()),
+            Self::AwaitingAuthServer { issuer, .. } => Some(Arc::new(issuer.clone())),
+            Self::IssuerMetadata { flow, .. } => Some(flow.issuer()),
+            Self::Accepted { flow, .. } => Some(flow.issuer()),
+            Self::Authorizing { flow, .. } => Some(flow.issuer()),
+            Self::Token { flow, .. } => Some(flow.issuer()),
+            Self::Proof { flow, .. } => Some(flow.issuer()),
+            Self::Issued { flow, .. } => Some(flow.issuer()),
         }
     }
 
     /// Get the offered credentials.
-    pub fn get_offered_credential(&self) -> Option<OfferedCredential> {
+    pub fn get_offered_credentials(&self) -> Vec<OfferedCredential> {
         match self {
             Self::IssuerMetadata { offered, .. }
             | Self::Accepted { offered, .. }
+            | Self::Authorizing { offered, .. }
             | Self::Token { offered, .. }
-            | Self::Proof { offered, .. } => offered.first().cloned(),
-            _ => None,
+            | Self::Proof { offered, .. }
+            | Self::Issued { offered, .. } => offered.clone(),
+            _ => Vec::new(),
         }
     }
 
-    /// Update the state with credential logo image data.
-    /// TODO: Add support for multiple offered credentials.
-    pub fn logo(&self, image_data: &[u8], media_type: &str) -> anyhow::Result<Self> {
+    /// Update the state with credential logo image data for the offered
+    /// credential with the given configuration ID.
+    pub fn logo(
+        &self, config_id: &str, image_data: &[u8], media_type: &str,
+    ) -> anyhow::Result<Self> {
         let Self::IssuerMetadata { flow, offered } = self else {
             bail!("unexpected issuance state to apply logo");
         };
-        if let Some(credential) = offered.clone().first_mut() {
-            credential.logo = Some(ImageData {
-                data: Base64::encode_string(image_data),
-                media_type: media_type.into(),
-            });
-            let new_state = IssuanceState::IssuerMetadata {
-                flow: flow.clone(),
-                offered: vec![credential.clone()],
-            };
-            Ok(new_state)
-        } else {
-            Ok(self.clone())
-        }
+        let mut offered = offered.clone();
+        let Some(credential) = offered.iter_mut().find(|c| c.config_id == config_id) else {
+            bail!("no offered credential for configuration {config_id}");
+        };
+        credential.logo = Some(ImageData {
+            data: Base64::encode_string(image_data),
+            media_type: media_type.into(),
+        });
+        Ok(Self::IssuerMetadata {
+            flow: flow.clone(),
+            offered,
+        })
     }
 
-    /// Update the state with credential background image data.
-    /// TODO: Add support for multiple offered credentials.
-    pub fn background(&self, image_data: &[u8], media_type: &str) -> anyhow::Result<Self> {
+    /// Update the state with credential background image data for the offered
+    /// credential with the given configuration ID.
+    pub fn background(
+        &self, config_id: &str, image_data: &[u8], media_type: &str,
+    ) -> anyhow::Result<Self> {
         let Self::IssuerMetadata { flow, offered } = self else {
-            bail!("unexpected issuance state to apply logo");
+            bail!("unexpected issuance state to apply background");
         };
-        if let Some(credential) = offered.clone().first_mut() {
-            credential.background = Some(ImageData {
-                data: Base64::encode_string(image_data),
-                media_type: media_type.into(),
-            });
-            let new_state = IssuanceState::IssuerMetadata {
-                flow: flow.clone(),
-                offered: vec![credential.clone()],
-            };
-            Ok(new_state)
-        } else {
-            Ok(self.clone())
-        }
+        let mut offered = offered.clone();
+        let Some(credential) = offered.iter_mut().find(|c| c.config_id == config_id) else {
+            bail!("no offered credential for configuration {config_id}");
+        };
+        credential.background = Some(ImageData {
+            data: Base64::encode_string(image_data),
+            media_type: media_type.into(),
+        });
+        Ok(Self::IssuerMetadata {
+            flow: flow.clone(),
+            offered,
+        })
+    }
+
+    /// Include or exclude an offered credential from the user's acceptance.
+    pub fn toggle(&self, config_id: &str) -> anyhow::Result<Self> {
+        let Self::IssuerMetadata { flow, offered } = self else {
+            bail!("unexpected issuance state to select offered credential");
+        };
+        let mut offered = offered.clone();
+        let Some(credential) = offered.iter_mut().find(|c| c.config_id == config_id) else {
+            bail!("no offered credential for configuration {config_id}");
+        };
+        credential.accepted = !credential.accepted;
+        Ok(Self::IssuerMetadata {
+            flow: flow.clone(),
+            offered,
+        })
     }
 
     /// Update the flow state with the user accepting the offer (but not yet
@@ -256,50 +481,172 @@ impl IssuanceState {
         let Self::IssuerMetadata { flow, offered } = self else {
             bail!("unexpected issuance state to accept offer");
         };
-        let updated_flow = flow.clone().accept(&None, None);
+        let accepted = offered
+            .iter()
+            .filter(|c| c.accepted)
+            .map(|c| AuthorizationSpec {
+                credential_configuration_id: c.config_id.clone(),
+                claims: None,
+            })
+            .collect::<Vec<_>>();
+        if accepted.is_empty() {
+            bail!("no offered credentials have been accepted");
+        }
+        let updated_flow = flow.clone().accept(&Some(accepted));
         let new_state = Self::Accepted {
             flow: updated_flow,
             offered: offered.clone(),
+            pin_attempts: 0,
+        };
+        Ok(new_state)
+    }
+
+    /// Determine if the user needs to authorize issuance with the issuer's
+    /// authorization server.
+    pub fn needs_authorization(&self) -> bool {
+        matches!(
+            self,
+            Self::Accepted {
+                flow: GrantFlow::AuthCode(_),
+                ..
+            }
+        )
+    }
+
+    /// Create an authorization request and move to the authorizing state.
+    pub fn authorize(&self) -> anyhow::Result<Self> {
+        let Self::Accepted {
+            flow: GrantFlow::AuthCode(flow),
+            offered,
+            ..
+        } = self
+        else {
+            bail!("unexpected issuance state to request authorization");
+        };
+        let redirect_uri = config::redirect_uri();
+        let (request, verifier) = flow.authorization_request(Some(&redirect_uri))?;
+        let new_state = Self::Authorizing {
+            flow: flow.clone(),
+            offered: offered.clone(),
+            request,
+            verifier,
         };
         Ok(new_state)
     }
 
-    /// Get a token request from the flow state.
-    pub fn token_request(&self) -> anyhow::Result<TokenRequest> {
-        if let Self::Accepted { flow, .. } = self {
-            Ok(flow.token_request())
-        } else {
-            bail!("unexpected issuance state to get token request");
+    /// Get the URL to open in the browser to send the user to the
+    /// authorization server.
+    pub fn get_authorization_url(&self) -> anyhow::Result<String> {
+        let Self::Authorizing { flow, request, .. } = self else {
+            bail!("unexpected issuance state to get authorization URL");
+        };
+        let endpoint = &flow.authorization_server().oauth.authorization_endpoint;
+        let query = urlencode::to_string(request)?;
+        Ok(format!("{endpoint}?{query}"))
+    }
+
+    /// Get a token request from the flow state. For the authorization code
+    /// flow, `redirect` is the URL the authorization server redirected the
+    /// user to and must contain the authorization code.
+    pub fn token_request(&self, redirect: Option<&str>) -> anyhow::Result<TokenRequest> {
+        match self {
+            Self::Accepted {
+                flow: GrantFlow::PreAuthorized(flow),
+                ..
+            } => Ok(flow.token_request()),
+            Self::Authorizing { flow, verifier, .. } => {
+                let Some(redirect) = redirect else {
+                    bail!("authorization redirect is required for token request");
+                };
+                let url = url::Url::parse(redirect)?;
+                let mut code = None;
+                let mut state = None;
+                for (key, value) in url.query_pairs() {
+                    match key.as_ref() {
+                        "code" => code = Some(value.to_string()),
+                        "state" => state = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+                flow.verify_state(state.as_deref())?;
+                let Some(code) = code else {
+                    bail!("authorization response is missing code");
+                };
+                let redirect_uri = config::redirect_uri();
+                Ok(flow.token_request(&code, verifier, Some(&redirect_uri)))
+            }
+            _ => bail!("unexpected issuance state to get token request"),
         }
     }
 
     /// Add a user-entered PIN to flow state.
     pub fn pin(&self, pin: &str) -> anyhow::Result<Self> {
-        let Self::Accepted { flow, offered } = self else {
+        let Self::Accepted {
+            flow: GrantFlow::PreAuthorized(flow),
+            offered,
+            pin_attempts,
+        } = self
+        else {
             bail!("unexpected issuance state to add PIN");
         };
         let mut updated_flow = flow.clone();
         updated_flow.set_pin(pin);
         let new_state = Self::Accepted {
-            flow: updated_flow,
+            flow: GrantFlow::PreAuthorized(updated_flow),
             offered: offered.clone(),
+            pin_attempts: *pin_attempts,
+        };
+        Ok(new_state)
+    }
+
+    /// Record that the issuer has rejected the PIN the user entered.
+    pub fn pin_rejected(&self) -> anyhow::Result<Self> {
+        let mut new_state = self.clone();
+        let Self::Accepted { pin_attempts, .. } = &mut new_state else {
+            bail!("unexpected issuance state to reject PIN");
         };
+        *pin_attempts += 1;
         Ok(new_state)
     }
 
+    /// Get the number of PINs the user can still enter before the flow is
+    /// abandoned. `None` until the issuer has rejected a PIN.
+    pub fn pin_attempts_remaining(&self) -> Option<u32> {
+        match self {
+            Self::Accepted { pin_attempts, .. } if *pin_attempts > 0 => {
+                Some(config::max_pin_attempts().saturating_sub(*pin_attempts))
+            }
+            _ => None,
+        }
+    }
+
     /// Update state with a token response.
     pub fn token(&self, token: &TokenResponse) -> anyhow::Result<Self> {
-        let Self::Accepted { flow, offered } = self else {
-            bail!("unexpected issuance state to add token");
+        let (updated_flow, offered) = match self {
+            Self::Accepted { flow, offered, .. } => (flow.clone().token(token.clone()), offered),
+            Self::Authorizing { flow, offered, .. } => {
+                (GrantFlow::AuthCode(flow.clone().token(token.clone())), offered)
+            }
+            _ => bail!("unexpected issuance state to add token"),
         };
-        let updated_flow = flow.clone().token(token.clone());
         let new_state = Self::Token {
             flow: updated_flow,
             offered: offered.clone(),
+            key_id: new_key_id(),
         };
         Ok(new_state)
     }
 
+    /// Get the ID of the key the credentials being issued are bound to.
+    pub fn get_key_id(&self) -> anyhow::Result<String> {
+        match self {
+            Self::Token { key_id, .. }
+            | Self::Proof { key_id, .. }
+            | Self::Issued { key_id, .. } => Ok(key_id.clone()),
+            _ => bail!("unexpected issuance state to get key ID"),
+        }
+    }
+
     /// Get proof claims from the flow state.
     pub fn get_proof_claims(&self) -> anyhow::Result<ProofClaims> {
         let Self::Token { flow, .. } = self else {
@@ -308,45 +655,60 @@ impl IssuanceState {
         Ok(flow.proof())
     }
 
-    /// Update state with a proof.
+    /// Update state with a proof and create a credential request for each
+    /// accepted credential.
     /// TODO: Could extend this to review and refresh existing proof if
     /// proof has expired.
     pub fn proof(&self, encoded_proof: &str) -> anyhow::Result<Self> {
-        let Self::Token { flow, offered } = self else {
+        let Self::Token {
+            flow,
+            offered,
+            key_id,
+        } = self
+        else {
             bail!("unexpected issuance state to add proof");
         };
+        let identifiers = flow
+            .get_token()
+            .authorization_details
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|auth| auth.credential_identifiers)
+            .collect::<Vec<_>>();
+        let pending = flow.credential_requests(&identifiers, encoded_proof);
+        if pending.is_empty() {
+            bail!("no credentials authorized in token response");
+        }
         let new_state = Self::Proof {
             flow: flow.clone(),
             offered: offered.clone(),
+            key_id: key_id.clone(),
             proof: encoded_proof.into(),
+            pending,
         };
         Ok(new_state)
     }
 
-    /// Get a credential request for the first offered credential.
-    /// TODO: Add support for multiple offered credentials.
-    pub fn get_credential_request(&self, jwt: &str) -> anyhow::Result<(String, CredentialRequest)> {
-        let Self::Proof { flow, .. } = self else {
-            bail!("unexpected issuance state to get authorization details");
-        };
-        let tr = flow.get_token();
-        let Some(authorized) = tr.authorization_details else {
-            bail!("no authorized details in token response");
-        };
-        let Some(auth) = authorized.first() else {
-            bail!("empty authorized details in token response");
+    /// Get the next credential request to send to the issuer, along with the
+    /// credential configuration ID it is for.
+    pub fn get_credential_request(&self) -> anyhow::Result<(String, CredentialRequest)> {
+        let Self::Proof { pending, .. } = self else {
+            bail!("unexpected issuance state to get credential request");
         };
-        let Some(cred_id) = auth.credential_identifiers.first() else {
-            bail!("empty credential identifiers in authorized details");
-        };
-        let identifiers = vec![cred_id.clone()];
-        let requests = flow.credential_requests(&identifiers, jwt);
-        let Some(request) = requests.first() else {
-            bail!("no credential request for first credential identifier");
+        let Some(request) = pending.first() else {
+            bail!("no pending credential requests");
         };
         Ok(request.clone())
     }
 
+    /// Determine if there are credential requests still to be sent.
+    pub fn has_pending(&self) -> bool {
+        match self {
+            Self::Proof { pending, .. } | Self::Issued { pending, .. } => !pending.is_empty(),
+            _ => false,
+        }
+    }
+
     /// Retrieve the access token from the flow.
     pub fn get_token(&self) -> anyhow::Result<String> {
         match self {
@@ -360,13 +722,26 @@ impl IssuanceState {
 
     /// Update state with a credential response.
     pub fn issued(&self, response: &CredentialResponse) -> anyhow::Result<Self> {
-        let Self::Proof { flow, offered, proof } = self else {
+        let Self::Proof {
+            flow,
+            offered,
+            key_id,
+            proof,
+            pending,
+        } = self
+        else {
             bail!("unexpected issuance state to add credential response");
         };
+        let Some(((config_id, _), rest)) = pending.split_first() else {
+            bail!("no pending credential request for credential response");
+        };
         let new_state = Self::Issued {
             flow: flow.clone(),
             offered: offered.clone(),
+            key_id: key_id.clone(),
             proof: proof.clone(),
+            pending: rest.to_vec(),
+            config_id: config_id.clone(),
             issued: response.clone(),
         };
         Ok(new_state)
@@ -390,7 +765,10 @@ impl IssuanceState {
         let Self::Issued {
             flow,
             offered,
+            key_id,
             proof,
+            pending,
+            config_id,
             issued,
         } = self
         else {
@@ -399,8 +777,8 @@ impl IssuanceState {
         let CredentialResponseType::Credential(vc_kind) = &issued.response else {
             bail!("unexpected credential response type");
         };
-        let Some(cred) = offered.first() else {
-            bail!("no offered credential to add credential");
+        let Some(cred) = offered.iter().find(|c| c.config_id == *config_id) else {
+            bail!("no offered credential for configuration {config_id}");
         };
         let mut updated_flow = flow.clone();
         updated_flow.add_credential(
@@ -415,25 +793,163 @@ impl IssuanceState {
         let new_state = IssuanceState::Issued {
             flow: updated_flow,
             offered: offered.clone(),
+            key_id: key_id.clone(),
             proof: proof.clone(),
+            pending: pending.clone(),
+            config_id: config_id.clone(),
             issued: issued.clone(),
         };
         Ok(new_state)
     }
 
-    /// Get the credential from the issuance flow that is in a format suitable
-    /// for storage and display in the wallet.
-    /// TODO: Add support for multiple credentials.
+    /// Get the most recently issued credential from the issuance flow in a
+    /// format suitable for storage and display in the wallet. The credential
+    /// is bound to the key used to sign the proof.
     pub fn get_storable_credential(&self) -> anyhow::Result<Credential> {
-        let Self::Issued { flow, .. } = self else {
+        let Self::Issued { flow, key_id, .. } = self else {
             bail!("unexpected issuance state to get storable credential");
         };
-        let flow_credentials = flow.credentials();
-        let Some(credential) = flow_credentials.first() else {
+        let Some(mut credential) = flow.credentials().pop() else {
             bail!("no credential in issuance flow");
         };
-        Ok(credential.clone())
+        credential.key_id = Some(key_id.clone());
+        Ok(credential)
+    }
+
+    /// Get the details needed to notify the issuer if the most recently
+    /// issued credential is deleted. Only available if the issuer has a
+    /// notification endpoint and returned a notification ID.
+    pub fn get_issuer_notification(&self) -> Option<IssuerNotification> {
+        let Self::Issued { flow, issued, .. } = self else {
+            return None;
+        };
+        let notification_endpoint = flow.issuer().notification_endpoint.clone()?;
+        let notification_id = issued.notification_id.clone()?;
+        let credential = self.get_storable_credential().ok()?;
+        Some(IssuerNotification {
+            credential_id: credential.id,
+            notification_endpoint,
+            notification_id,
+            access_token: flow.get_token().access_token,
+        })
     }
+
+    /// Mark the most recently issued credential as stored and move on to the
+    /// next credential request, if any.
+    pub fn stored(&self) -> anyhow::Result<Self> {
+        let Self::Issued {
+            offered, config_id, ..
+        } = self
+        else {
+            bail!("unexpected issuance state to mark credential stored");
+        };
+        let mut offered = offered.clone();
+        if let Some(credential) = offered.iter_mut().find(|c| c.config_id == *config_id) {
+            credential.stored = true;
+        }
+        self.with_offered(offered).next()
+    }
+
+    /// Mark the most recently requested credential as deferred by the issuer.
+    pub fn deferred(&self) -> anyhow::Result<Self> {
+        let Self::Issued {
+            offered, config_id, ..
+        } = self
+        else {
+            bail!("unexpected issuance state to mark credential deferred");
+        };
+        let mut offered = offered.clone();
+        if let Some(credential) = offered.iter_mut().find(|c| c.config_id == *config_id) {
+            credential.deferred = true;
+        }
+        Ok(self.with_offered(offered))
+    }
+
+    /// Get a pending credential for a deferred credential response so it can
+    /// be saved and the issuer polled later.
+    pub fn get_pending_credential(&self) -> anyhow::Result<PendingCredential> {
+        let Self::Issued {
+            flow,
+            offered,
+            key_id,
+            config_id,
+            issued,
+            ..
+        } = self
+        else {
+            bail!("unexpected issuance state to get pending credential");
+        };
+        let CredentialResponseType::TransactionId(transaction_id) = &issued.response else {
+            bail!("credential response is not deferred");
+        };
+        let Some(cred) = offered.iter().find(|c| c.config_id == *config_id) else {
+            bail!("no offered credential for configuration {config_id}");
+        };
+        Ok(PendingCredential::new(transaction_id, cred, key_id, flow.clone()))
+    }
+
+    /// If there are more credential requests pending, move back to the proof
+    /// state so the next request can be sent. Otherwise, stay in the issued
+    /// state.
+    pub fn next(&self) -> anyhow::Result<Self> {
+        let Self::Issued {
+            flow,
+            offered,
+            key_id,
+            proof,
+            pending,
+            ..
+        } = self
+        else {
+            bail!("unexpected issuance state to request next credential");
+        };
+        if pending.is_empty() {
+            return Ok(self.clone());
+        }
+        Ok(Self::Proof {
+            flow: flow.clone(),
+            offered: offered.clone(),
+            key_id: key_id.clone(),
+            proof: proof.clone(),
+            pending: pending.clone(),
+        })
+    }
+
+    // Replace the offered credentials on the current state.
+    fn with_offered(&self, offered: Vec<OfferedCredential>) -> Self {
+        let mut new_state = self.clone();
+        match &mut new_state {
+            Self::IssuerMetadata { offered: o, .. }
+            | Self::Accepted { offered: o, .. }
+            | Self::Authorizing { offered: o, .. }
+            | Self::Token { offered: o, .. }
+            | Self::Proof { offered: o, .. }
+            | Self::Issued { offered: o, .. } => *o = offered,
+            _ => {}
+        }
+        new_state
+    }
+}
+
+// Get the offered credentials' configurations from the issuer metadata.
+fn offered_credentials(offer: &CredentialOffer, issuer: &Issuer) -> Vec<OfferedCredential> {
+    let mut creds = Vec::<OfferedCredential>::new();
+    for config_id in &offer.credential_configuration_ids {
+        if let Some(config) = issuer.credential_configurations_supported.get(config_id) {
+            creds.push(OfferedCredential {
+                config_id: config_id.clone(),
+                config: config.clone(),
+                accepted: true,
+                ..OfferedCredential::default()
+            });
+        }
+    }
+    creds
+}
+
+// Generate an ID for a new key to bind issued credentials to.
+fn new_key_id() -> String {
+    format!("credential-{:016x}", rand::random::<u64>())
 }
 
 #[cfg(test)]
@@ -453,6 +969,9 @@ mod tests {
             IssuanceState::Offered { offer, grant } => {
                 assert_eq!(offer.credential_issuer, "https://light-sheep-safe.ngrok-free.app");
                 assert_eq!(offer.credential_configuration_ids, vec!["EmployeeID_JWT"]);
+                let Some(grant) = grant else {
+                    panic!("expected pre-authorized code grant");
+                };
                 assert_eq!(
                     grant.pre_authorized_code,
                     "TWxBc3Q0d1poZjg2cVd-UEVWT1k1UE0kWmhyb3QjdUM"
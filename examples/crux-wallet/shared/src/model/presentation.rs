@@ -5,9 +5,58 @@ use credibil_holder::credential::Credential;
 use credibil_holder::issuance::proof::Payload;
 use credibil_holder::presentation::{Authorized, NotAuthorized, PresentationFlow, RequestObject, ResponseRequest};
 use credibil_holder::provider::Constraints;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// ID of the signing key used for credentials that were stored before
+/// credentials were bound to named keys.
+const DEFAULT_KEY_ID: &str = "credential";
+
+/// The verifier's identity as presented in its request. The request's
+/// signature has been verified against the verifier's DID but the name, logo
+/// and purpose are the verifier's own claims.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VerifierIdentity {
+    /// The verifier's client ID.
+    pub client_id: String,
+
+    /// The verifier's name from its client metadata.
+    pub name: Option<String>,
+
+    /// URL of the verifier's logo from its client metadata.
+    pub logo_uri: Option<String>,
+
+    /// Why the verifier is asking for the credentials, from the presentation
+    /// definition or, failing that, its first input descriptor.
+    pub purpose: Option<String>,
+}
+
+impl VerifierIdentity {
+    /// Get the verifier's identity from a verified request object.
+    pub fn from_request(request: &RequestObject) -> Self {
+        let request = serde_json::to_value(request).unwrap_or_default();
+        let text = |value: &Value, key: &str| {
+            value.get(key).and_then(Value::as_str).filter(|s| !s.is_empty()).map(String::from)
+        };
+        let metadata = request.get("client_metadata").cloned().unwrap_or_default();
+        let definition = request.get("presentation_definition").cloned().unwrap_or_default();
+        let purpose = text(&definition, "purpose").or_else(|| {
+            definition
+                .get("input_descriptors")
+                .and_then(Value::as_array)
+                .and_then(|descriptors| descriptors.iter().find_map(|d| text(d, "purpose")))
+        });
+        Self {
+            client_id: text(&request, "client_id").unwrap_or_default(),
+            name: text(&metadata, "client_name"),
+            logo_uri: text(&metadata, "logo_uri"),
+            purpose,
+        }
+    }
+}
 
 /// Application state for the presentation sub-app.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub enum PresentationState {
     /// No presentation is in progress.
     #[default]
@@ -18,16 +67,36 @@ pub enum PresentationState {
     Requested { request_payload: String },
 
     /// The presentation request has been decoded and verified.
-    Verified { flow: PresentationFlow<NotAuthorized> },
+    Verified { flow: PresentationFlow<NotAuthorized>, verifier: VerifierIdentity },
 
-    /// Credentials have been identified that match the request.
-    Credentials { flow: PresentationFlow<NotAuthorized>, credentials: Vec<Credential> },
+    /// Credentials have been identified that match the request. `selected`
+    /// holds the IDs of the credentials the user has chosen to present.
+    Credentials {
+        flow: PresentationFlow<NotAuthorized>,
+        verifier: VerifierIdentity,
+        credentials: Vec<Credential>,
+        selected: Vec<String>,
+    },
 
-    /// The user has approved the presentation.
-    Approved { flow: PresentationFlow<Authorized>, credentials: Vec<Credential> },
+    /// The user has approved the presentation of the selected credentials.
+    /// `proofs` holds the presentations signed so far, one for each key the
+    /// credentials are bound to.
+    Approved {
+        flow: PresentationFlow<Authorized>,
+        verifier: VerifierIdentity,
+        credentials: Vec<Credential>,
+        #[serde(default)]
+        proofs: Vec<String>,
+    },
 }
 
 impl PresentationState {
+    /// Whether the flow is waiting on the user to approve the presentation
+    /// and can be resumed if the app is restarted.
+    pub fn is_resumable(&self) -> bool {
+        matches!(self, Self::Credentials { .. })
+    }
+
     /// Get the presentation request back from state.
     pub fn get_request(&self) -> Option<String> {
         match self {
@@ -41,7 +110,10 @@ impl PresentationState {
         match self {
             Self::Requested { .. } => {
                 let flow = PresentationFlow::<NotAuthorized>::new(request.clone())?;
-                Ok(Self::Verified { flow })
+                Ok(Self::Verified {
+                    flow,
+                    verifier: VerifierIdentity::from_request(request),
+                })
             }
             _ => bail!("unexpected presentation state to apply verified request"),
         }
@@ -50,49 +122,144 @@ impl PresentationState {
     /// Get a credential filter from the presentation flow state.
     pub fn get_filter(&self) -> anyhow::Result<Constraints> {
         match self {
-            PresentationState::Verified { flow } => Ok(flow.filter()?),
+            PresentationState::Verified { flow, .. } => Ok(flow.filter()?),
             _ => bail!("unexpected presentation state to get filter"),
         }
     }
 
-    /// Update state after credentials have been identified.
+    /// Update state after credentials have been identified. The first
+    /// matching credential is selected.
     pub fn credentials(&self, credentials: &[Credential]) -> anyhow::Result<Self> {
-        let Self::Verified { flow } = self else {
+        let Self::Verified { flow, verifier } = self else {
             bail!("unexpected presentation state to apply credentials");
         };
         Ok(Self::Credentials {
             flow: flow.clone(),
+            verifier: verifier.clone(),
             credentials: credentials.to_vec(),
+            selected: credentials.first().map(|c| c.id.clone()).into_iter().collect(),
+        })
+    }
+
+    /// Include or exclude a matching credential from the presentation.
+    pub fn toggle(&self, id: &str) -> anyhow::Result<Self> {
+        let Self::Credentials {
+            flow,
+            verifier,
+            credentials,
+            selected,
+        } = self
+        else {
+            bail!("unexpected presentation state to select credential");
+        };
+        if !credentials.iter().any(|c| c.id == id) {
+            bail!("no matching credential with ID {id}");
+        }
+        let mut selected = selected.clone();
+        if let Some(pos) = selected.iter().position(|s| s == id) {
+            selected.remove(pos);
+        } else {
+            selected.push(id.into());
+        }
+        Ok(Self::Credentials {
+            flow: flow.clone(),
+            verifier: verifier.clone(),
+            credentials: credentials.clone(),
+            selected,
         })
     }
 
-    /// Update state after the user has approved the presentation.
+    /// Update state after the user has approved the presentation of the
+    /// selected credentials.
     pub fn approve(&self) -> anyhow::Result<Self> {
-        let Self::Credentials { flow, credentials } = self else {
+        let Self::Credentials {
+            flow,
+            verifier,
+            credentials,
+            selected,
+        } = self
+        else {
             bail!("unexpected presentation state to approve");
         };
-        let updated_flow = flow.clone().authorize(credentials);
+        let chosen =
+            credentials.iter().filter(|c| selected.contains(&c.id)).cloned().collect::<Vec<_>>();
+        if chosen.is_empty() {
+            bail!("no credentials have been selected");
+        }
+        let updated_flow = flow.clone().authorize(&chosen);
         Ok(Self::Approved {
             flow: updated_flow,
-            credentials: credentials.to_vec(),
+            verifier: verifier.clone(),
+            credentials: chosen,
+            proofs: vec![],
         })
     }
 
-    /// Construct a presentation payload from the presentation flow state.
+    /// Get the ID of the next key to sign a presentation with. A presentation
+    /// is signed with each key the approved credentials are bound to.
+    pub fn get_key_id(&self) -> anyhow::Result<String> {
+        let Some(key) = self.next_key()? else {
+            bail!("presentations have been signed with every key");
+        };
+        Ok(key.unwrap_or_else(|| DEFAULT_KEY_ID.into()))
+    }
+
+    /// Construct a presentation payload for the credentials bound to the next
+    /// key to sign with. `kid` is the key's verification method.
     pub fn get_payload(&self, kid: &str) -> anyhow::Result<Payload> {
-        match self {
-            PresentationState::Approved { flow, .. } => Ok(flow.payload(kid)?),
-            _ => bail!("unexpected presentation state to get payload"),
-        }
+        let PresentationState::Approved { flow, .. } = self else {
+            bail!("unexpected presentation state to get payload");
+        };
+        let Some(key) = self.next_key()? else {
+            bail!("presentations have been signed with every key");
+        };
+        flow.key_payload(key.as_deref(), kid)
     }
 
-    /// Construct a presentation response request.
-    pub fn create_response_request(
-        &self, jws: &str,
-    ) -> anyhow::Result<(ResponseRequest, Option<String>)> {
+    /// Update state with a signed presentation.
+    pub fn proof(&self, jws: &str) -> anyhow::Result<Self> {
+        let PresentationState::Approved {
+            flow,
+            verifier,
+            credentials,
+            proofs,
+        } = self
+        else {
+            bail!("unexpected presentation state to add proof");
+        };
+        let mut proofs = proofs.clone();
+        proofs.push(jws.into());
+        Ok(Self::Approved {
+            flow: flow.clone(),
+            verifier: verifier.clone(),
+            credentials: credentials.clone(),
+            proofs,
+        })
+    }
+
+    /// Whether a presentation has been signed with every key the approved
+    /// credentials are bound to.
+    pub fn is_signed(&self) -> bool {
+        matches!(self.next_key(), Ok(None))
+    }
+
+    /// Construct a presentation response request from the signed
+    /// presentations.
+    pub fn create_response_request(&self) -> anyhow::Result<(ResponseRequest, Option<String>)> {
         match self {
-            PresentationState::Approved { flow, .. } => Ok(flow.create_response_request(jws)),
+            PresentationState::Approved { flow, proofs, .. } => {
+                flow.create_keyed_response_request(proofs)
+            }
             _ => bail!("unexpected presentation state to create response request"),
         }
     }
+
+    // The next key to sign a presentation with, if any. The inner `None` is
+    // the default key.
+    fn next_key(&self) -> anyhow::Result<Option<Option<String>>> {
+        let PresentationState::Approved { flow, proofs, .. } = self else {
+            bail!("unexpected presentation state to get signing key");
+        };
+        Ok(flow.holder_keys().get(proofs.len()).cloned())
+    }
 }
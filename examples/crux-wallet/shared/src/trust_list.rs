@@ -0,0 +1,138 @@
+//! Verification of the trust list published by the demonstration service.
+//!
+//! The list is a compact JWS signed with `EdDSA` by a key the wallet pins (see
+//! [`config::trust_list_key`]), naming the issuers and verifiers the scheme
+//! operator vouches for.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::Utc;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// A verified trust list.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TrustList {
+    /// Time after which the list can no longer be relied on, as a UNIX
+    /// timestamp.
+    pub exp: i64,
+
+    /// Trusted issuers.
+    #[serde(default)]
+    pub issuers: Vec<TrustedEntity>,
+
+    /// Trusted verifiers.
+    #[serde(default)]
+    pub verifiers: Vec<TrustedEntity>,
+}
+
+/// An issuer or verifier on the trust list.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TrustedEntity {
+    /// The issuer's identifier or the verifier's client ID.
+    pub id: String,
+
+    /// Display name.
+    pub name: String,
+}
+
+impl TrustList {
+    /// Whether the list has expired.
+    pub fn is_expired(&self) -> bool {
+        self.exp <= Utc::now().timestamp()
+    }
+}
+
+/// JWS header of a trust list.
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+/// Verify a trust list signed with the pinned trust list key.
+///
+/// # Errors
+///
+/// Returns an error if the list is malformed, its signature does not verify
+/// or it has expired.
+pub fn verify(compact: &str) -> anyhow::Result<TrustList> {
+    let mut key = [0u8; 32];
+    Base64UrlUnpadded::decode(&config::trust_list_key(), &mut key)
+        .map_err(|e| anyhow!("invalid trust list key: {e}"))?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|e| anyhow!("invalid trust list key: {e}"))?;
+    verify_with(compact, &key)
+}
+
+// Verify a trust list signed with the given key.
+fn verify_with(compact: &str, key: &VerifyingKey) -> anyhow::Result<TrustList> {
+    let parts: Vec<&str> = compact.split('.').collect();
+    let [encoded_header, payload, signature] = parts.as_slice() else {
+        bail!("trust list should be a compact JWS");
+    };
+    let header: Header = serde_json::from_slice(&Base64UrlUnpadded::decode_vec(encoded_header)?)?;
+    if header.alg != "EdDSA" {
+        bail!("unsupported trust list signature algorithm {}", header.alg);
+    }
+    let signature = Signature::from_slice(&Base64UrlUnpadded::decode_vec(signature)?)
+        .map_err(|e| anyhow!("invalid trust list signature: {e}"))?;
+    key.verify_strict(format!("{encoded_header}.{payload}").as_bytes(), &signature)
+        .map_err(|_| anyhow!("trust list signature does not verify"))?;
+
+    let list: TrustList = serde_json::from_slice(&Base64UrlUnpadded::decode_vec(payload)?)?;
+    if list.is_expired() {
+        bail!("trust list has expired");
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer as _, SigningKey};
+    use serde_json::json;
+
+    use super::*;
+
+    // Sign a trust list payload with the key.
+    fn sign(key: &SigningKey, payload: &serde_json::Value) -> String {
+        let header = Base64UrlUnpadded::encode_string(br#"{"alg":"EdDSA","typ":"trust-list+jwt"}"#);
+        let payload = Base64UrlUnpadded::encode_string(payload.to_string().as_bytes());
+        let signature = key.sign(format!("{header}.{payload}").as_bytes());
+        format!("{header}.{payload}.{}", Base64UrlUnpadded::encode_string(&signature.to_bytes()))
+    }
+
+    // A list signed by the pinned key is accepted.
+    #[test]
+    fn verify_signed_list() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let exp = Utc::now().timestamp() + 60;
+        let compact = sign(
+            &key,
+            &json!({
+                "exp": exp,
+                "verifiers": [{"id": "http://localhost:8080", "name": "Verifier"}],
+            }),
+        );
+        let list = verify_with(&compact, &key.verifying_key()).expect("should verify");
+        assert_eq!(list.verifiers[0].id, "http://localhost:8080");
+        assert!(list.issuers.is_empty());
+    }
+
+    // A list signed by any other key is rejected.
+    #[test]
+    fn verify_wrong_key() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        let compact = sign(&key, &json!({"exp": Utc::now().timestamp() + 60}));
+        assert!(verify_with(&compact, &other.verifying_key()).is_err());
+    }
+
+    // An expired list is rejected.
+    #[test]
+    fn verify_expired() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let compact = sign(&key, &json!({"exp": Utc::now().timestamp() - 60}));
+        assert!(verify_with(&compact, &key.verifying_key()).is_err());
+    }
+}
@@ -10,6 +10,7 @@ use presentation::PresentationView;
 use serde::{Deserialize, Serialize};
 
 use super::Aspect;
+use crate::app::recovery::Recovery;
 
 /// View model for the wallet application.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -28,4 +29,7 @@ pub struct ViewModel {
 
     /// Error message.
     pub error: String,
+
+    /// How the user can recover from the error.
+    pub recovery: Recovery,
 }
@@ -3,9 +3,15 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use credibil_holder::credential::{Credential as CredentialModel, ImageData};
+use credibil_holder::credential::{
+    Credential as CredentialModel, CredentialGroup, GroupBy, ImageData, SortOrder, organize,
+};
 
-use crate::model::{CredentialState, OfferedCredential};
+use crate::config;
+use crate::model::credential::DeletedCredential;
+use crate::model::{CredentialState, OfferedCredential, PendingCredential};
+
+pub use crate::model::IssuerNotified;
 
 /// View model for nested claims
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
@@ -101,30 +107,28 @@ pub struct Credential {
 
 impl From<CredentialModel> for Credential {
     fn from(credential: CredentialModel) -> Self {
+        let locale = config::locale();
         let mut claims = HashMap::new();
         for sub_claims in &credential.subject_claims {
-            let claims_display = credential.claims_display(sub_claims.id.as_deref(), None);
+            let claims_display =
+                credential.claims_display(sub_claims.id.as_deref(), locale.as_deref());
             let mut claims_view = Vec::new();
             for (name, value) in claims_display {
                 claims_view.push((name, value).into());
             }
             claims.insert(sub_claims.id.clone().unwrap_or_default(), claims_view);
         }
-        let (name, description, background_color, text_color) = match credential.display {
-            Some(display_list) => {
-                // TODO: Locale support.
-                let display = display_list
-                    .clone()
-                    .into_iter()
-                    .find(|display| display.locale.is_none())
-                    .unwrap_or_else(|| display_list[0].clone());
-                (
-                    display.name,
-                    display.description.unwrap_or_default(),
-                    display.background_color.unwrap_or_default(),
-                    display.text_color.unwrap_or_default(),
-                )
-            }
+        let display = credential
+            .display
+            .as_deref()
+            .and_then(|displays| config::localized(displays, |d| d.locale.as_deref()));
+        let (name, description, background_color, text_color) = match display {
+            Some(display) => (
+                display.name.clone(),
+                display.description.clone().unwrap_or_default(),
+                display.background_color.clone().unwrap_or_default(),
+                display.text_color.clone().unwrap_or_default(),
+            ),
             None => (String::new(), String::new(), String::new(), String::new()),
         };
         Self {
@@ -158,27 +162,24 @@ impl Credential {
     /// Some data is empty. We just use the credential data shape as a template.
     pub fn from_offer(issuer: &str, issuer_name: &str, offered: OfferedCredential) -> Self {
         let mut claims = HashMap::new();
-        let claims_display = offered.config.claims_display(None);
+        let claims_display = offered.config.claims_display(config::locale().as_deref());
         let mut claims_view = Vec::new();
         for name in claims_display {
             claims_view.push((name, String::new()).into());
         }
         claims.insert(String::new(), claims_view);
-        let (name, description, background_color, text_color) = match offered.config.display {
-            Some(display_list) => {
-                // TODO: Locale support.
-                let display = display_list
-                    .clone()
-                    .into_iter()
-                    .find(|display| display.locale.is_none())
-                    .unwrap_or_else(|| display_list[0].clone());
-                (
-                    display.name,
-                    display.description.unwrap_or_default(),
-                    display.background_color.unwrap_or_default(),
-                    display.text_color.unwrap_or_default(),
-                )
-            }
+        let display = offered
+            .config
+            .display
+            .as_deref()
+            .and_then(|displays| config::localized(displays, |d| d.locale.as_deref()));
+        let (name, description, background_color, text_color) = match display {
+            Some(display) => (
+                display.name.clone(),
+                display.description.clone().unwrap_or_default(),
+                display.background_color.clone().unwrap_or_default(),
+                display.text_color.clone().unwrap_or_default(),
+            ),
             None => (String::new(), String::new(), String::new(), String::new()),
         };
         Self {
@@ -196,6 +197,65 @@ impl Credential {
     }
 }
 
+/// View model for a credential the issuer has deferred.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PendingCredentialView {
+    /// Transaction ID of the deferred issuance.
+    pub transaction_id: String,
+
+    /// "Empty" credential representing the credential configuration.
+    pub credential: Credential,
+}
+
+impl From<PendingCredential> for PendingCredentialView {
+    fn from(pending: PendingCredential) -> Self {
+        let issuer = pending.flow.issuer();
+        let name = issuer.display_name(config::locale().as_deref()).unwrap_or_default();
+        Self {
+            transaction_id: pending.transaction_id.clone(),
+            credential: Credential::from_offer(&issuer.credential_issuer, &name, pending.offered()),
+        }
+    }
+}
+
+/// View model for a credential the user has just deleted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DeletedCredentialView {
+    /// The deleted credential.
+    pub credential: Credential,
+
+    /// Whether the issuer has been told the credential was deleted.
+    pub issuer_notified: IssuerNotified,
+}
+
+impl From<DeletedCredential> for DeletedCredentialView {
+    fn from(deleted: DeletedCredential) -> Self {
+        Self {
+            credential: deleted.credential.into(),
+            issuer_notified: deleted.issuer_notified,
+        }
+    }
+}
+
+/// View model for a group of stored credentials.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CredentialGroupView {
+    /// The group's heading.
+    pub label: String,
+
+    /// The IDs of the credentials in the group, in display order.
+    pub credential_ids: Vec<String>,
+}
+
+impl From<CredentialGroup> for CredentialGroupView {
+    fn from(group: CredentialGroup) -> Self {
+        Self {
+            label: group.label,
+            credential_ids: group.credentials.into_iter().map(|c| c.id).collect(),
+        }
+    }
+}
+
 /// View for the verifiable credential sub-app
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct CredentialView {
@@ -204,13 +264,26 @@ pub struct CredentialView {
 
     /// List of stored credentials
     pub credentials: Vec<Credential>,
+
+    /// Stored credentials grouped by issuer, newest first
+    pub groups: Vec<CredentialGroupView>,
+
+    /// List of credentials the issuer has deferred
+    pub pending: Vec<PendingCredentialView>,
+
+    /// The credential the user has just deleted, if any
+    pub deleted: Option<DeletedCredentialView>,
 }
 
 impl From<CredentialState> for CredentialView {
     fn from(state: CredentialState) -> Self {
+        let groups = organize(state.credentials.clone(), GroupBy::Issuer, SortOrder::Newest);
         Self {
             id: state.id,
             credentials: state.credentials.into_iter().map(Credential::from).collect(),
+            groups: groups.into_iter().map(CredentialGroupView::from).collect(),
+            pending: state.pending.into_iter().map(PendingCredentialView::from).collect(),
+            deleted: state.deleted.map(DeletedCredentialView::from),
         }
     }
 }
@@ -362,8 +435,8 @@ mod tests {
         let offer = OfferedCredential {
             config_id: "EmployeeID_JWT".into(),
             config,
-            logo: None,
-            background: None,
+            accepted: true,
+            ..OfferedCredential::default()
         };
         let credential = Credential::from_offer("issuer", "Issuer", offer);
         assert_yaml_snapshot!("offer", credential, {
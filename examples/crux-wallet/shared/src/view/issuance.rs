@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::credential::Credential;
+use crate::config;
 use crate::model::IssuanceState;
 
 /// View-friendly representation of a transaction code specification.
@@ -41,32 +42,62 @@ impl From<Option<credibil_holder::issuance::TxCode>> for TxCode {
     }
 }
 
+/// View model for a credential on offer.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct OfferedCredentialView {
+    /// Credential configuration identifier. Used by the shell to include or
+    /// exclude the credential from the user's acceptance.
+    pub config_id: String,
+
+    /// "Empty" credential representing the credential configuration.
+    pub credential: Credential,
+
+    /// Whether the user has chosen to accept the credential.
+    pub accepted: bool,
+
+    /// Whether the credential has been issued and stored in the wallet.
+    pub stored: bool,
+
+    /// Whether the issuer has deferred issuance of the credential.
+    pub deferred: bool,
+}
+
 /// View model for an issuance flow.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct IssuanceView {
     /// Credentials on offer.
-    pub credentials: Vec<Credential>,
+    pub credentials: Vec<OfferedCredentialView>,
 
     /// PIN as entered by the user.
     pub pin: String,
 
     /// PIN requirements.
     pub tx_code: TxCode,
+
+    /// How many more PINs the user can enter if the issuer has rejected the
+    /// last one. The flow is abandoned when none remain.
+    pub pin_attempts_remaining: Option<u32>,
 }
 
 impl From<IssuanceState> for IssuanceView {
     fn from(model_state: IssuanceState) -> Self {
         let mut credentials = Vec::new();
+        let pin_attempts_remaining = model_state.pin_attempts_remaining();
 
         let (on_offer, issuer, offer, pin) = match model_state {
-            IssuanceState::Inactive | IssuanceState::Offered { .. } => return Self::default(),
+            IssuanceState::Inactive
+            | IssuanceState::Offered { .. }
+            | IssuanceState::AwaitingAuthServer { .. } => return Self::default(),
             IssuanceState::IssuerMetadata { flow, offered } => {
                 (offered, flow.issuer(), flow.offer(), None)
             }
-            IssuanceState::Accepted { flow, offered } => {
+            IssuanceState::Accepted { flow, offered, .. } => {
                 (offered, flow.issuer(), flow.offer(), flow.pin())
             }
-            IssuanceState::Token { flow, offered } => {
+            IssuanceState::Authorizing { flow, offered, .. } => {
+                (offered, flow.issuer(), flow.offer(), None)
+            }
+            IssuanceState::Token { flow, offered, .. } => {
                 (offered, flow.issuer(), flow.offer(), flow.pin())
             }
             IssuanceState::Proof { flow, offered, .. } => {
@@ -77,14 +108,19 @@ impl From<IssuanceState> for IssuanceView {
             }
         };
 
-        for offered_credential in &on_offer {
-            let name = issuer.display_name(None).unwrap_or_default();
-            let cred = Credential::from_offer(
-                &issuer.credential_issuer,
-                &name,
-                offered_credential.clone(),
-            );
-            credentials.push(cred);
+        let name = issuer.display_name(config::locale().as_deref()).unwrap_or_default();
+        for offered_credential in on_offer {
+            credentials.push(OfferedCredentialView {
+                config_id: offered_credential.config_id.clone(),
+                accepted: offered_credential.accepted,
+                stored: offered_credential.stored,
+                deferred: offered_credential.deferred,
+                credential: Credential::from_offer(
+                    &issuer.credential_issuer,
+                    &name,
+                    offered_credential,
+                ),
+            });
         }
 
         let tx_code = match offer.pre_authorized_code() {
@@ -96,6 +132,7 @@ impl From<IssuanceState> for IssuanceView {
             credentials,
             pin: pin.unwrap_or_default(),
             tx_code,
+            pin_attempts_remaining,
         }
     }
 }
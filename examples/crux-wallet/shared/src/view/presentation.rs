@@ -2,15 +2,98 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::credential::Credential;
-use crate::model::PresentationState;
+use super::credential::{ClaimView, Credential};
+use crate::config;
+use crate::model::{PresentationState, VerifierIdentity};
+
+/// Whether the wallet trusts the verifier.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum VerifierTrust {
+    /// The verifier is on the wallet's list of trusted verifiers.
+    Trusted,
+
+    /// The request was signed by the verifier but the wallet doesn't know
+    /// the verifier. The user should check the verifier is who they claim to
+    /// be before presenting their credentials.
+    #[default]
+    Unknown,
+}
+
+/// View model for the verifier requesting the presentation.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VerifierView {
+    /// The verifier's client ID.
+    pub client_id: String,
+
+    /// The verifier's name. Falls back to the client ID if the verifier
+    /// doesn't provide one.
+    pub name: String,
+
+    /// URL of the verifier's logo, if any.
+    pub logo_uri: Option<String>,
+
+    /// Why the verifier is asking for the credentials, if given.
+    pub purpose: Option<String>,
+
+    /// Whether the wallet trusts the verifier.
+    pub trust: VerifierTrust,
+}
+
+impl From<VerifierIdentity> for VerifierView {
+    fn from(verifier: VerifierIdentity) -> Self {
+        let trust = if config::trusted_verifiers().contains(&verifier.client_id) {
+            VerifierTrust::Trusted
+        } else {
+            VerifierTrust::Unknown
+        };
+        Self {
+            name: verifier.name.unwrap_or_else(|| verifier.client_id.clone()),
+            client_id: verifier.client_id,
+            logo_uri: verifier.logo_uri,
+            purpose: verifier.purpose,
+            trust,
+        }
+    }
+}
+
+/// View model for a credential that matches the presentation request.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct MatchedCredentialView {
+    /// The matching credential.
+    pub credential: Credential,
+
+    /// Claims that will be disclosed to the verifier if the credential is
+    /// presented. Credentials are presented whole, so this is every claim in
+    /// the credential.
+    pub disclosed: Vec<ClaimView>,
+
+    /// Whether the user has chosen to present the credential.
+    pub selected: bool,
+}
+
+impl MatchedCredentialView {
+    fn new(credential: Credential, selected: bool) -> Self {
+        let mut subjects = credential.claims.keys().collect::<Vec<_>>();
+        subjects.sort();
+        let disclosed =
+            subjects.into_iter().flat_map(|subject| credential.claims[subject].clone()).collect();
+        Self {
+            credential,
+            disclosed,
+            selected,
+        }
+    }
+}
 
 /// View model for a presentation flow.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct PresentationView {
-    /// Credentials requested to be presented.
-    /// (TODO: App only supports the first one in the list at this time.)
-    pub credentials: Vec<Credential>,
+    /// The verifier requesting the presentation.
+    pub verifier: VerifierView,
+
+    /// Credentials that match the presentation request. The user chooses which
+    /// of them to present.
+    pub credentials: Vec<MatchedCredentialView>,
 }
 
 impl From<PresentationState> for PresentationView {
@@ -19,13 +102,32 @@ impl From<PresentationState> for PresentationView {
             PresentationState::Inactive
             | PresentationState::Requested { .. }
             | PresentationState::Verified { .. } => Self::default(),
-            PresentationState::Credentials { credentials, .. }
-            | PresentationState::Approved { credentials, .. } => {
-                let view_credentials = credentials.into_iter().map(Into::into).collect();
-                Self {
-                    credentials: view_credentials,
-                }
-            }
+            PresentationState::Credentials {
+                verifier,
+                credentials,
+                selected,
+                ..
+            } => Self {
+                verifier: verifier.into(),
+                credentials: credentials
+                    .into_iter()
+                    .map(|c| {
+                        let is_selected = selected.contains(&c.id);
+                        MatchedCredentialView::new(c.into(), is_selected)
+                    })
+                    .collect(),
+            },
+            PresentationState::Approved {
+                verifier,
+                credentials,
+                ..
+            } => Self {
+                verifier: verifier.into(),
+                credentials: credentials
+                    .into_iter()
+                    .map(|c| MatchedCredentialView::new(c.into(), true))
+                    .collect(),
+            },
         }
     }
 }
@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use crux_core::typegen::TypeGen;
 use crux_http::HttpError;
-use wallet::{app::credential::CredentialEvent, issuance::IssuanceEvent, presentation::PresentationEvent, App, Aspect};
+use wallet::{app::credential::CredentialEvent, deferred::DeferredEvent, flow::FlowEvent, issuance::IssuanceEvent, link::LinkEvent, presentation::PresentationEvent, recovery::{Recovery, RecoveryEvent}, view::{credential::IssuerNotified, presentation::VerifierTrust}, App, Aspect};
 
 fn main() -> anyhow::Result<()> {
     println!("cargo:rerun-if-changed=../shared");
@@ -17,9 +17,16 @@ fn main() -> anyhow::Result<()> {
 
     // Register other types the code generator is having trouble inferring
     gen.register_type::<Aspect>()?;
+    gen.register_type::<LinkEvent>()?;
     gen.register_type::<CredentialEvent>()?;
+    gen.register_type::<IssuerNotified>()?;
     gen.register_type::<IssuanceEvent>()?;
+    gen.register_type::<DeferredEvent>()?;
+    gen.register_type::<FlowEvent>()?;
     gen.register_type::<PresentationEvent>()?;
+    gen.register_type::<Recovery>()?;
+    gen.register_type::<RecoveryEvent>()?;
+    gen.register_type::<VerifierTrust>()?;
 
     gen.swift("SharedTypes", out_dir.join("swift"))?;
     gen.java("io.credibil.wallet.shared_types", out_dir.join("java"))?;
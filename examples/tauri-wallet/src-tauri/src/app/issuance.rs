@@ -1,13 +1,13 @@
 //! Application state implementation for issuance operations.
 
 use anyhow::bail;
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{Payload, Type, Verify};
+use credibil_holder::issuance::proof::{Payload, Verify};
 use credibil_holder::issuance::{
-    Accepted, CredentialOffer, CredentialResponseType, IssuanceFlow, MetadataRequest, NotAccepted,
-    PreAuthorized, WithOffer, WithToken, WithoutToken,
+    Accepted, CredentialOffer, CredentialResponseType, IssuanceFlow, IssuanceFlowBuilder,
+    MetadataRequest, NotAccepted, PreAuthorized, WithOffer, WithToken, WithoutToken,
 };
 use credibil_holder::provider::{CredentialStorer, Issuer};
+use credibil_holder::redact::redacted;
 use credibil_holder::test_utils::issuer::NORMAL_USER;
 
 use super::{AppState, SubApp};
@@ -53,13 +53,9 @@ impl AppState {
         let issuer_metadata = provider.metadata(metadata_request).await?;
 
         // Initiate flow state with the offer and metadata.
-        let state = IssuanceFlow::<WithOffer, PreAuthorized, NotAccepted, WithoutToken>::new(
-            CLIENT_ID,
-            NORMAL_USER,
-            issuer_metadata.credential_issuer,
-            offer,
-            pre_auth_code_grant,
-        );
+        let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+            .subject_id(NORMAL_USER)
+            .pre_authorized(offer, pre_auth_code_grant);
 
         self.issuance = IssuanceState::Offered(state);
         self.sub_app = SubApp::Issuance;
@@ -106,8 +102,8 @@ impl AppState {
         );
 
         // Request an access token from the issuer.
-        let token_request = state.clone().token_request();
-        log::info!("Requesting token with {:?}", token_request);
+        let token_request = state.token_request();
+        log::info!("Requesting token with {}", redacted(&token_request));
         let token_response = match provider.token(token_request).await {
             Ok(token) => token,
             Err(e) => {
@@ -115,7 +111,7 @@ impl AppState {
                 return Err(e);
             }
         };
-        log::info!("Updating state with token response {:?}", token_response);
+        log::info!("Updating state with token response {}", redacted(&token_response));
         let mut state = state.token(token_response.clone());
 
         log::info!("Getting credentials for issuance {}", state.id());
@@ -125,17 +121,11 @@ impl AppState {
             bail!("no authorized credentials in token response");
         };
         let identifier = authorized[0].credential_identifiers[0].clone();
-        let jws_claims = state.proof();
-        let jws = JwsBuilder::new()
-            .jwt_type(Type::Openid4VciProofJwt)
-            .payload(jws_claims)
-            .add_signer(&provider)
-            .build()
-            .await?;
-        let jwt_proof = jws.encode()?;
-
-        let requests = state.credential_requests(&[identifier], &jwt_proof).clone();
-        let request = requests[0].clone();
+        let jwt_proof = state.build_proof(&provider).await?;
+
+        let Some(request) = state.credential_requests(&[identifier], &jwt_proof).next() else {
+            bail!("no credential request for the authorized identifier");
+        };
         let credential_response = provider.credential(request.1).await?;
         match credential_response.response {
             CredentialResponseType::Credential(vc_kind) => {
@@ -2,11 +2,10 @@
 
 use anyhow::bail;
 use credibil_holder::credential::Credential;
-use credibil_holder::presentation::proof::{self, Payload, W3cFormat};
 use credibil_holder::presentation::{
     Authorized, NotAuthorized, PresentationFlow, parse_request_object_response,
 };
-use credibil_holder::provider::{CredentialStorer, Signer, Verifier};
+use credibil_holder::provider::{CredentialStorer, Verifier};
 
 use super::{AppState, SubApp};
 use crate::provider::Provider;
@@ -67,14 +66,7 @@ impl AppState {
         let PresentationState::Authorized(flow) = &self.presentation else {
             bail!("expected authorized presentation state");
         };
-        let kid = provider.verification_method().await?;
-        let vp = flow.payload(&kid)?;
-        let Payload::Vp { vp, client_id, nonce } = vp else {
-            bail!("expected verifiable presentation payload type");
-        };
-        let jwt =
-            proof::create(W3cFormat::JwtVcJson, Payload::Vp { vp, client_id, nonce }, &provider)
-                .await?;
+        let jwt = flow.sign_payload(&provider).await?;
         let (res_req, uri) = flow.create_response_request(&jwt);
         log::info!("presentation URI: {uri:?}");
         log::info!("presentation request: {res_req:?}");
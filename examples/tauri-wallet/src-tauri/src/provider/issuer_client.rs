@@ -1,5 +1,6 @@
 use base64ct::{Base64, Encoding};
 use credibil_holder::credential::ImageData;
+use credibil_holder::error::{OAuthError, RetryLater};
 use credibil_holder::issuance::{
     AuthorizationRequest, AuthorizationResponse, CredentialRequest, CredentialResponse,
     DeferredCredentialRequest, DeferredCredentialResponse, MetadataRequest, MetadataResponse,
@@ -7,7 +8,7 @@ use credibil_holder::issuance::{
     TokenRequest, TokenResponse,
 };
 use credibil_holder::provider::Issuer;
-use http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use http::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
 use tauri_plugin_http::reqwest;
 
 use super::Provider;
@@ -64,6 +65,9 @@ impl Issuer for Provider {
             .form(&form)
             .send()
             .await?;
+        if !result.status().is_success() {
+            return Err(error_response(result).await);
+        }
         let token = match result.json::<TokenResponse>().await {
             Ok(token) => token,
             Err(e) => {
@@ -86,6 +90,9 @@ impl Issuer for Provider {
             .json(&req)
             .send()
             .await?;
+        if !result.status().is_success() {
+            return Err(error_response(result).await);
+        }
         let cred = result.json::<CredentialResponse>().await?;
         Ok(cred)
     }
@@ -122,3 +129,23 @@ impl Issuer for Provider {
         Ok(NotificationResponse::default())
     }
 }
+
+// Convert an error response from the issuer to a typed OAuth error where
+// possible so the application can decide how to recover.
+async fn error_response(result: reqwest::Response) -> anyhow::Error {
+    let status = result.status();
+    let retry_after = result.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok());
+    if let Some(retry) = RetryLater::from_response(status.as_u16(), retry_after) {
+        log::warn!("Issuer asked to {retry}");
+        return retry.into();
+    }
+    let body = match result.bytes().await {
+        Ok(body) => body,
+        Err(e) => return e.into(),
+    };
+    let Some(err) = OAuthError::parse(&body) else {
+        return anyhow::anyhow!("issuer returned status {status}");
+    };
+    log::error!("Issuer returned error: {err}");
+    err.into()
+}
@@ -1,3 +1,4 @@
+use credibil_holder::presentation::siop::IdTokenResponse;
 use credibil_holder::presentation::{RequestObjectResponse, ResponseRequest, ResponseResponse};
 use credibil_holder::provider::Verifier;
 use http::header::{ACCEPT, CONTENT_TYPE};
@@ -46,4 +47,28 @@ impl Verifier for Provider {
         };
         Ok(response)
     }
+
+    /// Send a self-issued ID token to the relying party.
+    async fn self_issued(
+        &self, uri: Option<&str>, response: &IdTokenResponse,
+    ) -> anyhow::Result<ResponseResponse> {
+        let client = reqwest::Client::new();
+        let Some(response_url) = uri else {
+            return Err(anyhow::anyhow!("No URI provided"));
+        };
+        let result = client
+            .post(response_url)
+            .header(ACCEPT, "application/json")
+            .form(&response.form_encode())
+            .send()
+            .await?;
+        let response = match result.json::<ResponseResponse>().await {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Error sending ID token: {}", e);
+                return Err(e.into());
+            }
+        };
+        Ok(response)
+    }
 }
@@ -59,14 +59,14 @@ impl From<IssuanceState> for IssuanceView {
                 state.offered(),
                 state.issuer(),
                 state.offer(),
-                state.pin(),
+                state.pin().map(|pin| pin.expose().to_string()),
                 IssuanceStatus::Accepted,
             ),
             IssuanceState::Token(state) => (
                 state.offered(),
                 state.issuer(),
                 state.offer(),
-                state.pin(),
+                state.pin().map(|pin| pin.expose().to_string()),
                 IssuanceStatus::Tokenized,
             ),
         };
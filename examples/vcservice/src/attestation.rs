@@ -0,0 +1,185 @@
+//! # Wallet Attestation
+//!
+//! Verification of OAuth 2.0 Attestation-Based Client Authentication at the
+//! token endpoint. A wallet authenticates with two JWTs sent as headers:
+//!
+//! - `OAuth-Client-Attestation`: issued by a trusted attester (typically the
+//!   wallet provider's backend). Binds the wallet's client ID to a key held by
+//!   the wallet instance.
+//! - `OAuth-Client-Attestation-PoP`: signed by the wallet instance's key to
+//!   prove it holds the attested key and that the attestation was meant for
+//!   this issuer.
+//!
+//! Only `EdDSA` (Ed25519) signatures are supported. A production issuer would
+//! also reject proofs with a `jti` it has already seen.
+
+use anyhow::{anyhow, bail};
+use axum::http::HeaderMap;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{Duration, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// Header carrying the wallet attestation JWT.
+pub const ATTESTATION_HEADER: &str = "OAuth-Client-Attestation";
+
+/// Header carrying the proof of possession JWT.
+pub const ATTESTATION_POP_HEADER: &str = "OAuth-Client-Attestation-PoP";
+
+/// How old a proof of possession can be.
+const POP_MAX_AGE: Duration = Duration::minutes(5);
+
+/// Allowance for clock skew between the wallet and the service.
+const CLOCK_SKEW: Duration = Duration::seconds(60);
+
+/// Verifies wallet attestations issued by a trusted attester.
+#[derive(Clone, Debug)]
+pub struct AttestationVerifier {
+    attester_key: VerifyingKey,
+    audiences: Vec<String>,
+}
+
+/// Wallet attestation claims.
+#[derive(Debug, Deserialize)]
+struct Attestation {
+    sub: String,
+    exp: i64,
+    cnf: Confirmation,
+}
+
+/// Key the attestation is bound to.
+#[derive(Debug, Deserialize)]
+struct Confirmation {
+    jwk: Jwk,
+}
+
+/// Public key of the wallet instance.
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+}
+
+/// Proof of possession claims.
+#[derive(Debug, Deserialize)]
+struct ProofOfPossession {
+    iss: String,
+    aud: String,
+    jti: String,
+    iat: i64,
+}
+
+/// JWT header.
+#[derive(Debug, Deserialize)]
+struct Header {
+    alg: String,
+    typ: String,
+}
+
+impl AttestationVerifier {
+    /// Create a verifier trusting attestations signed by the attester's
+    /// Ed25519 public key (base64url encoded). `audiences` are the
+    /// identifiers a proof of possession can be addressed to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is not a valid Ed25519 public key.
+    pub fn new(attester_key: &str, audiences: Vec<String>) -> anyhow::Result<Self> {
+        let bytes = Base64UrlUnpadded::decode_vec(attester_key)
+            .map_err(|e| anyhow!("attester key is not base64url encoded: {e}"))?;
+        let bytes: [u8; 32] =
+            bytes.try_into().map_err(|_| anyhow!("attester key should be 32 bytes"))?;
+        Ok(Self {
+            attester_key: VerifyingKey::from_bytes(&bytes)?,
+            audiences,
+        })
+    }
+
+    /// Verify the wallet attestation and proof of possession in the request
+    /// headers. If the token request contains a client ID it must be the one
+    /// attested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either header is missing or the attestation or
+    /// proof of possession is invalid.
+    pub fn verify(&self, headers: &HeaderMap, client_id: Option<&str>) -> anyhow::Result<()> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| anyhow!("missing {name} header"))
+        };
+        let now = Utc::now().timestamp();
+
+        // Attestation, signed by the attester.
+        let attestation: Attestation = decode_jwt(
+            header(ATTESTATION_HEADER)?,
+            "oauth-client-attestation+jwt",
+            &self.attester_key,
+        )?;
+        if attestation.exp <= now {
+            bail!("wallet attestation has expired");
+        }
+        if client_id.is_some_and(|id| id != attestation.sub) {
+            bail!("wallet attestation is for a different client");
+        }
+        let jwk = &attestation.cnf.jwk;
+        if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+            bail!("unsupported wallet instance key type");
+        }
+        let instance_key = Base64UrlUnpadded::decode_vec(&jwk.x)
+            .map_err(|e| anyhow!("wallet instance key is not base64url encoded: {e}"))?;
+        let instance_key: [u8; 32] = instance_key
+            .try_into()
+            .map_err(|_| anyhow!("wallet instance key should be 32 bytes"))?;
+        let instance_key = VerifyingKey::from_bytes(&instance_key)?;
+
+        // Proof of possession, signed by the attested wallet instance key.
+        let pop: ProofOfPossession = decode_jwt(
+            header(ATTESTATION_POP_HEADER)?,
+            "oauth-client-attestation-pop+jwt",
+            &instance_key,
+        )?;
+        if pop.iss != attestation.sub {
+            bail!("proof of possession issuer does not match attested client");
+        }
+        if !self.audiences.contains(&pop.aud) {
+            bail!("proof of possession is for a different audience: {}", pop.aud);
+        }
+        if pop.jti.is_empty() {
+            bail!("proof of possession has no jti");
+        }
+        if pop.iat > now + CLOCK_SKEW.num_seconds() || pop.iat < now - POP_MAX_AGE.num_seconds() {
+            bail!("proof of possession is stale or issued in the future");
+        }
+        Ok(())
+    }
+}
+
+// Verify a compact JWS signed with EdDSA and of the expected type, returning
+// its claims.
+fn decode_jwt<T: DeserializeOwned>(jwt: &str, typ: &str, key: &VerifyingKey) -> anyhow::Result<T> {
+    let parts: Vec<&str> = jwt.split('.').collect();
+    let [header, payload, signature] = parts[..] else {
+        bail!("{typ} is not a compact JWS");
+    };
+    let decode = |part: &str| {
+        Base64UrlUnpadded::decode_vec(part).map_err(|e| anyhow!("invalid {typ} encoding: {e}"))
+    };
+
+    let jws_header: Header = serde_json::from_slice(&decode(header)?)?;
+    if jws_header.typ != typ {
+        bail!("expected {typ} but got {}", jws_header.typ);
+    }
+    if jws_header.alg != "EdDSA" {
+        bail!("unsupported {typ} algorithm: {}", jws_header.alg);
+    }
+    let signature = Signature::from_slice(&decode(signature)?)?;
+    key.verify_strict(format!("{header}.{payload}").as_bytes(), &signature)
+        .map_err(|_| anyhow!("{typ} signature is invalid"))?;
+
+    Ok(serde_json::from_slice(&decode(payload)?)?)
+}
@@ -5,6 +5,9 @@ use crate::{AppError, AppJson, AppState};
 
 pub mod assets;
 pub mod issuer;
+pub mod notification;
+pub mod status;
+pub mod trust;
 pub mod verifier;
 
 #[derive(Serialize)]
@@ -1,22 +1,35 @@
 //! # Request handlers for issuer endpoints.
 
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::vec;
 
-use anyhow::anyhow;
-use axum::extract::State;
-use axum::http::{HeaderMap, StatusCode};
-use axum::response::Result;
+use aes_gcm::Aes256Gcm;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use anyhow::{anyhow, bail};
+use axum::extract::{Path, RawQuery, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Redirect, Response, Result};
 use axum::{Form, Json};
 use axum_extra::TypedHeader;
 use axum_extra::headers::Authorization;
 use axum_extra::headers::authorization::Bearer;
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
 use credibil_vc::issuer::{
-    CredentialDisplay, CredentialRequest, CredentialResponse, Image, MetadataRequest, MetadataResponse, OfferType, SendType, TokenRequest, TokenResponse
+    AuthorizationRequest, CredentialDisplay, CredentialOffer, CredentialOfferRequest,
+    CredentialRequest, CredentialResponse, Image, MetadataRequest, MetadataResponse,
+    OAuthServerRequest, OAuthServerResponse, OfferType, PushedAuthorizationRequest,
+    PushedAuthorizationResponse, RequestObject, SendType, TokenRequest, TokenResponse,
 };
+use credibil_vc::urlencode;
+use image::{ImageFormat, Luma};
+use qrcode::QrCode;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
 use typeshare::typeshare;
+use url::Url;
+use x25519_dalek::{EphemeralSecret, PublicKey};
 
 use super::{AppError, AppJson};
 use crate::AppState;
@@ -34,12 +47,23 @@ pub struct CreateOfferRequest {
     /// The identifier of the type of credential to be issued.
     pub credential_configuration_id: String,
 
+    /// Identifiers of further credentials to offer alongside
+    /// `credential_configuration_id`. The wallet can request them all at once
+    /// from the batch credential endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_configuration_ids: Option<Vec<String>>,
+
     /// Type of authorization grant to include in the offer.
     pub grant_type: String,
 
     /// Whether or not a PIN is required to validate requester of the credential
     /// offer is the person accepting the credential.
     pub tx_code_required: bool,
+
+    /// Pass the offer by reference. The QR code will contain a
+    /// `credential_offer_uri` the wallet uses to fetch the offer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_reference: Option<bool>,
 }
 
 /// Create offer response.
@@ -67,26 +91,47 @@ pub async fn create_offer(
         return Err(anyhow!("invalid grant type: {}", req.grant_type).into());
     };
 
+    let mut configuration_ids = vec![req.credential_configuration_id.clone()];
+    configuration_ids.extend(req.additional_configuration_ids.unwrap_or_default());
+
     let request = credibil_vc::issuer::CreateOfferRequest {
         credential_issuer: state.issuer.to_string(),
         subject_id: Some(req.subject_id),
-        credential_configuration_ids: vec![req.credential_configuration_id.clone()],
+        credential_configuration_ids: configuration_ids.clone(),
         grant_types: Some(vec![grant_type]),
         tx_code_required: req.tx_code_required,
-        send_type: SendType::ByVal,
+        send_type: if req.by_reference.unwrap_or_default() {
+            SendType::ByRef
+        } else {
+            SendType::ByVal
+        },
     };
 
     let response: credibil_vc::issuer::CreateOfferResponse =
         credibil_vc::issuer::create_offer(state.issuer_provider, request).await?;
     let mut offer = match response.offer_type {
         OfferType::Object(offer) => offer,
-        OfferType::Uri(s) => return Err(anyhow!("unexpected URI offer {s}").into()),
+        OfferType::Uri(uri) => {
+            // Override the issuer's endpoint so the wallet fetches the offer
+            // from our hosting location.
+            let Some(offer_id) = uri.split('/').next_back() else {
+                return Err(anyhow!("no offer ID in URI {uri}").into());
+            };
+            let offer_uri = format!("{}/credential_offer/{offer_id}", state.external_address);
+            let qr_code = qrcode(&format!(
+                "openid-credential-offer://?credential_offer_uri={}",
+                urlencoding::encode(&offer_uri)
+            ))?;
+            let offer_json = serde_json::json!({ "credential_offer_uri": offer_uri }).to_string();
+            return Ok(AppJson(CreateOfferResponse {
+                qr_code,
+                tx_code: response.tx_code,
+                offer_json,
+            }));
+        }
     };
-    if offer.credential_configuration_ids.len() != 1 {
-        return Err(anyhow!("expected 1 credential configuration ID").into());
-    }
-    if offer.credential_configuration_ids[0] != req.credential_configuration_id {
-        return Err(anyhow!("unexpected credential configuration ID").into());
+    if offer.credential_configuration_ids != configuration_ids {
+        return Err(anyhow!("unexpected credential configuration IDs").into());
     }
 
     // Override the issuer's identifier with the environment variable if it
@@ -104,6 +149,30 @@ pub async fn create_offer(
     Ok(AppJson(rsp))
 }
 
+// Generate a PNG QR code for the data as a data URL.
+fn qrcode(data: &str) -> anyhow::Result<String> {
+    let image = QrCode::new(data)?.render::<Luma<u8>>().build();
+    let mut buffer = Vec::new();
+    image.write_to(&mut Cursor::new(&mut buffer), ImageFormat::Png)?;
+    Ok(format!("data:image/png;base64,{}", Base64::encode_string(&buffer)))
+}
+
+// Credential offer endpoint for offers passed by reference
+#[axum::debug_handler]
+pub async fn credential_offer(
+    State(state): State<AppState>, Path(offer_id): Path<String>,
+) -> Result<AppJson<CredentialOffer>, AppError> {
+    let request = CredentialOfferRequest {
+        credential_issuer: state.issuer.to_string(),
+        id: offer_id,
+    };
+    let response =
+        credibil_vc::issuer::credential_offer(state.issuer_provider.clone(), request).await?;
+    let mut offer = response.credential_offer;
+    offer.credential_issuer = state.external_address.to_string();
+    Ok(AppJson(offer))
+}
+
 // Metadata endpoint
 #[axum::debug_handler]
 pub async fn metadata(
@@ -126,8 +195,19 @@ pub async fn metadata(
     response.credential_issuer.credential_issuer = state.external_address.to_string();
     response.credential_issuer.credential_endpoint =
         format!("{}/credential", state.external_address);
+    response.credential_issuer.batch_credential_endpoint =
+        Some(format!("{}/batch_credential", state.external_address));
     response.credential_issuer.deferred_credential_endpoint =
         Some(format!("{}/deferred", state.external_address));
+    // Wallets can ask for credential responses to be encrypted.
+    response.credential_issuer.credential_response_encryption = Some(
+        serde_json::from_value(json!({
+            "alg_values_supported": ["ECDH-ES"],
+            "enc_values_supported": ["A256GCM"],
+            "encryption_required": false,
+        }))
+        .map_err(|e| anyhow!(e))?,
+    );
     // Display image file URLs
     let mut updated_supported =
         response.credential_issuer.credential_configurations_supported.clone();
@@ -166,6 +246,81 @@ pub async fn metadata(
     Ok(AppJson(response))
 }
 
+// Authorization server metadata endpoint
+#[axum::debug_handler]
+pub async fn oauth_server(
+    State(state): State<AppState>,
+) -> Result<AppJson<OAuthServerResponse>, AppError> {
+    let request = OAuthServerRequest {
+        credential_issuer: state.issuer.to_string(),
+        issuer: None,
+    };
+    let mut response =
+        credibil_vc::issuer::oauth_server(state.issuer_provider.clone(), request).await?;
+
+    // Override the server's endpoints with the environment variable if it
+    // exists so the wallet is sent back to our hosting location.
+    let oauth = &mut response.authorization_server.oauth;
+    oauth.issuer = state.external_address.to_string();
+    oauth.authorization_endpoint = format!("{}/auth", state.external_address);
+    oauth.token_endpoint = format!("{}/token", state.external_address);
+    oauth.pushed_authorization_request_endpoint = Some(format!("{}/par", state.external_address));
+
+    Ok(AppJson(response))
+}
+
+// Authorization endpoint. The holder's browser is sent here by the wallet.
+// There is no login in this simple service: the subject in the request is
+// trusted and the browser is redirected straight back to the wallet with an
+// authorization code.
+#[axum::debug_handler]
+pub async fn authorize(
+    State(state): State<AppState>, RawQuery(query): RawQuery,
+) -> Result<Redirect, AppError> {
+    let query = query.unwrap_or_default();
+    tracing::debug!("raw authorization request: {query}");
+    let Ok(mut request) = urlencode::from_str::<AuthorizationRequest>(&query) else {
+        return Err(AppError::Status(
+            StatusCode::BAD_REQUEST,
+            format!("unable to turn query {query} into AuthorizationRequest"),
+        ));
+    };
+    if let AuthorizationRequest::Object(request_object) = &mut request {
+        request_object.credential_issuer = state.issuer.to_string();
+    }
+
+    let response = credibil_vc::issuer::authorize(state.issuer_provider.clone(), request).await?;
+
+    let mut redirect = Url::parse(&response.redirect_uri).map_err(|e| anyhow!(e))?;
+    redirect.query_pairs_mut().append_pair("code", &response.code);
+    if let Some(state) = &response.state {
+        redirect.query_pairs_mut().append_pair("state", state);
+    }
+    Ok(Redirect::to(redirect.as_str()))
+}
+
+// Pushed authorization request endpoint
+#[axum::debug_handler]
+pub async fn par(
+    State(state): State<AppState>, body: String,
+) -> Result<(StatusCode, AppJson<PushedAuthorizationResponse>), AppError> {
+    tracing::debug!("raw pushed authorization request: {body}");
+    let Ok(mut request) = urlencode::from_str::<RequestObject>(&body) else {
+        return Err(AppError::Status(
+            StatusCode::BAD_REQUEST,
+            format!("unable to turn body {body} into RequestObject"),
+        ));
+    };
+    request.credential_issuer = state.issuer.to_string();
+
+    let request = PushedAuthorizationRequest {
+        request,
+        client_assertion: None,
+    };
+    let response = credibil_vc::issuer::par(state.issuer_provider.clone(), request).await?;
+    Ok((StatusCode::CREATED, AppJson(response)))
+}
+
 // DID document endpoint
 #[axum::debug_handler]
 pub async fn did(State(state): State<AppState>) -> Result<AppJson<Value>, AppError> {
@@ -221,7 +376,7 @@ pub fn did_json(external_address: &str) -> anyhow::Result<Value> {
 // Token endpoint
 #[axum::debug_handler]
 pub async fn token(
-    State(state): State<AppState>, Form(req): Form<HashMap<String, String>>,
+    State(state): State<AppState>, headers: HeaderMap, Form(req): Form<HashMap<String, String>>,
 ) -> Result<AppJson<TokenResponse>, AppError> {
     tracing::debug!("raw token request: {req:?}");
     let Ok(mut token_request) = TokenRequest::form_decode(&req) else {
@@ -233,6 +388,12 @@ pub async fn token(
     token_request.credential_issuer = state.issuer.to_string();
     tracing::debug!("decoded token request: {token_request:?}");
 
+    if let Some(attestation) = &state.attestation {
+        attestation.verify(&headers, token_request.client_id.as_deref()).map_err(|e| {
+            AppError::Status(StatusCode::UNAUTHORIZED, format!("invalid_client: {e}"))
+        })?;
+    }
+
     let response = credibil_vc::issuer::token(state.issuer_provider.clone(), token_request).await?;
     Ok(AppJson(response))
 }
@@ -242,10 +403,145 @@ pub async fn token(
 pub async fn credential(
     State(state): State<AppState>, TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
     Json(mut req): Json<CredentialRequest>,
-) -> Result<AppJson<CredentialResponse>, AppError> {
+) -> Result<Response, AppError> {
     req.credential_issuer = state.issuer.to_string();
     req.access_token = auth.token().to_string();
 
+    // Encrypt the response here rather than in the issuer library.
+    let encryption = req.credential_response_encryption.take();
+
     let response = credibil_vc::issuer::credential(state.issuer_provider.clone(), req).await?;
-    Ok(AppJson(response))
+    let Some(encryption) = encryption else {
+        return Ok(AppJson(response).into_response());
+    };
+    let encryption = serde_json::to_value(&encryption).map_err(|e| anyhow!(e))?;
+    let jwe = match encrypt_response(&response, &encryption) {
+        Ok(jwe) => jwe,
+        Err(e) => return Err(AppError::Status(StatusCode::BAD_REQUEST, e.to_string())),
+    };
+    Ok(([(header::CONTENT_TYPE, "application/jwt")], jwe).into_response())
+}
+
+/// Batch credential request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BatchCredentialRequest {
+    /// Credential requests, each as would be sent to the credential endpoint.
+    pub credential_requests: Vec<CredentialRequest>,
+}
+
+/// Batch credential response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BatchCredentialResponse {
+    /// Credential responses, in the same order as the requests.
+    pub credential_responses: Vec<CredentialResponse>,
+}
+
+// Batch credential endpoint
+//
+// Each request is processed as it would be by the credential endpoint. The
+// batch fails as a whole if any request fails, so the wallet can retry it
+// without receiving any credential twice.
+#[axum::debug_handler]
+pub async fn batch_credential(
+    State(state): State<AppState>, TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    Json(req): Json<BatchCredentialRequest>,
+) -> Result<AppJson<BatchCredentialResponse>, AppError> {
+    if req.credential_requests.is_empty() {
+        return Err(AppError::Status(
+            StatusCode::BAD_REQUEST,
+            "invalid_request: no credential requests in batch".into(),
+        ));
+    }
+    if req.credential_requests.iter().any(|r| r.credential_response_encryption.is_some()) {
+        return Err(AppError::Status(
+            StatusCode::BAD_REQUEST,
+            "invalid_encryption_parameters: batch responses cannot be encrypted".into(),
+        ));
+    }
+
+    let mut credential_responses = vec![];
+    for mut request in req.credential_requests {
+        request.credential_issuer = state.issuer.to_string();
+        request.access_token = auth.token().to_string();
+        let response =
+            credibil_vc::issuer::credential(state.issuer_provider.clone(), request).await?;
+        credential_responses.push(response);
+    }
+    Ok(AppJson(BatchCredentialResponse { credential_responses }))
+}
+
+// Encrypt a credential response as a compact JWE for the wallet's key. Only
+// ECDH-ES key agreement with an X25519 key and A256GCM content encryption are
+// supported.
+fn encrypt_response(response: &CredentialResponse, encryption: &Value) -> anyhow::Result<String> {
+    if encryption["alg"] != "ECDH-ES" || encryption["enc"] != "A256GCM" {
+        bail!("unsupported credential response encryption: {encryption}");
+    }
+    let jwk = &encryption["jwk"];
+    if jwk["crv"] != "X25519" {
+        bail!("unsupported credential response encryption key: {jwk}");
+    }
+    let Some(x) = jwk["x"].as_str() else {
+        bail!("credential response encryption key has no x coordinate");
+    };
+    let recipient: [u8; 32] = Base64UrlUnpadded::decode_vec(x)
+        .map_err(|e| anyhow!("{e}"))?
+        .try_into()
+        .map_err(|_| anyhow!("invalid X25519 key length"))?;
+
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let epk = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient));
+    let cek = concat_kdf(shared.as_bytes(), "A256GCM");
+
+    let mut header = json!({
+        "alg": "ECDH-ES",
+        "enc": "A256GCM",
+        "epk": {
+            "kty": "OKP",
+            "crv": "X25519",
+            "x": Base64UrlUnpadded::encode_string(epk.as_bytes()),
+        },
+    });
+    if let Some(kid) = jwk.get("kid") {
+        header["kid"] = kid.clone();
+    }
+    let protected = Base64UrlUnpadded::encode_string(&serde_json::to_vec(&header)?);
+
+    let cipher = Aes256Gcm::new(&cek.into());
+    let iv = Aes256Gcm::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(response)?;
+    let mut ciphertext = cipher
+        .encrypt(
+            &iv,
+            Payload {
+                msg: &plaintext,
+                aad: protected.as_bytes(),
+            },
+        )
+        .map_err(|e| anyhow!("{e}"))?;
+    // The authentication tag is appended to the ciphertext.
+    let tag = ciphertext.split_off(ciphertext.len() - 16);
+
+    Ok(format!(
+        "{protected}..{}.{}.{}",
+        Base64UrlUnpadded::encode_string(&iv),
+        Base64UrlUnpadded::encode_string(&ciphertext),
+        Base64UrlUnpadded::encode_string(&tag)
+    ))
+}
+
+// Derive a 256-bit content encryption key from the shared secret using the
+// Concat KDF (RFC 7518 section 4.6.2) with empty party info.
+fn concat_kdf(shared_secret: &[u8], algorithm: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(shared_secret);
+    for info in [algorithm.as_bytes(), &[], &[]] {
+        #[allow(clippy::cast_possible_truncation)]
+        hasher.update((info.len() as u32).to_be_bytes());
+        hasher.update(info);
+    }
+    hasher.update(256u32.to_be_bytes());
+    hasher.finalize().into()
 }
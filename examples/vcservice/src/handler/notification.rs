@@ -0,0 +1,62 @@
+//! # Request handlers for issuer notifications.
+//!
+//! Wallets subscribe to a server-sent event stream of notifications. There is
+//! no notion of a holder in this simple service so every subscriber receives
+//! every notification.
+
+use std::convert::Infallible;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use serde::{Deserialize, Serialize};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use super::AppError;
+use crate::AppState;
+
+/// A notification sent to wallets.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    /// A deferred credential is ready to be collected.
+    DeferredReady {
+        /// Transaction ID the wallet was given when the credential was
+        /// deferred.
+        transaction_id: String,
+    },
+
+    /// A credential has been revoked.
+    CredentialRevoked {
+        /// ID of the revoked credential.
+        credential_id: String,
+    },
+}
+
+// Notification stream endpoint
+#[axum::debug_handler]
+pub async fn notifications(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Lagging subscribers skip missed notifications rather than ending the
+    // stream.
+    let stream = BroadcastStream::new(state.notifier.subscribe()).filter_map(|notification| {
+        let notification = notification.ok()?;
+        Event::default().json_data(notification).ok().map(Ok)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// Publish a notification to subscribed wallets
+#[axum::debug_handler]
+pub async fn notify(
+    State(state): State<AppState>, Json(notification): Json<Notification>,
+) -> Result<StatusCode, AppError> {
+    tracing::debug!("notification: {notification:?}");
+
+    // Sending only fails if there are no subscribers, which is not an error.
+    let _ = state.notifier.send(notification);
+    Ok(StatusCode::ACCEPTED)
+}
@@ -0,0 +1,106 @@
+//! # Request handlers for credential status endpoints.
+//!
+//! Issued credentials refer to a single revocation status list published as a
+//! bitstring status list credential. The admin endpoint revokes a credential
+//! by setting its bit in the list.
+
+use std::io::Write;
+
+use anyhow::anyhow;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::Utc;
+use credibil_vc::infosec::jose::JwsBuilder;
+use credibil_vc::issuer::proof::Type;
+use credibil_vc::issuer::provider::Signer;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use typeshare::typeshare;
+
+use super::AppError;
+use super::notification::Notification;
+use crate::AppState;
+use crate::provider::issuer::STATUS_LIST_ID;
+
+/// Revoke credential request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[typeshare]
+pub struct RevokeRequest {
+    /// Issuer's identifier of the holder the credential was issued to.
+    pub subject_id: String,
+
+    /// Identifier of the issued credential.
+    pub credential_identifier: String,
+}
+
+// Status list credential endpoint
+#[axum::debug_handler]
+pub async fn status_list(
+    State(state): State<AppState>, Path(list_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if list_id != STATUS_LIST_ID {
+        return Err(AppError::Status(
+            StatusCode::NOT_FOUND,
+            format!("no status list with ID {list_id}"),
+        ));
+    }
+    let provider = &state.issuer_provider;
+
+    // The encoded list is the GZIP-compressed bitstring, multibase encoded
+    // with the base64url alphabet.
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&provider.status.bitstring()?).map_err(|e| anyhow!(e))?;
+    let compressed = encoder.finish().map_err(|e| anyhow!(e))?;
+    let encoded_list = format!("u{}", Base64UrlUnpadded::encode_string(&compressed));
+
+    let list_url = provider.status_list_url();
+    let verification_method = provider.verification_method().await?;
+    let issuer_did = verification_method.split('#').next().unwrap_or_default();
+    let now = Utc::now();
+    let claims = json!({
+        "iss": issuer_did,
+        "iat": now.timestamp(),
+        "jti": list_url,
+        "vc": {
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "id": list_url,
+            "type": ["VerifiableCredential", "BitstringStatusListCredential"],
+            "issuer": issuer_did,
+            "validFrom": now.to_rfc3339(),
+            "credentialSubject": {
+                "id": format!("{list_url}#list"),
+                "type": "BitstringStatusList",
+                "statusPurpose": "revocation",
+                "encodedList": encoded_list,
+            },
+        },
+    });
+
+    let jws =
+        JwsBuilder::new().jwt_type(Type::Jwt).payload(claims).add_signer(provider).build().await?;
+    Ok(([(header::CONTENT_TYPE, "application/vc+jwt")], jws.encode()?))
+}
+
+// Revoke an issued credential
+#[axum::debug_handler]
+pub async fn revoke(
+    State(state): State<AppState>, Json(req): Json<RevokeRequest>,
+) -> Result<StatusCode, AppError> {
+    tracing::debug!("revoke: {req:?}");
+
+    if let Err(e) = state.issuer_provider.status.revoke(&req.subject_id, &req.credential_identifier)
+    {
+        return Err(AppError::Status(StatusCode::NOT_FOUND, e.to_string()));
+    }
+
+    // Let subscribed wallets know to check the status of their credentials.
+    let _ = state.notifier.send(Notification::CredentialRevoked {
+        credential_id: req.credential_identifier,
+    });
+    Ok(StatusCode::NO_CONTENT)
+}
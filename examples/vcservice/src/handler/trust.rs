@@ -0,0 +1,37 @@
+//! # Request handler for the trust list endpoint.
+//!
+//! Wallets fetch the list to decide whether to trust the issuers and
+//! verifiers they interact with. See [`crate::trust_list`].
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+
+use super::AppError;
+use crate::trust_list::{self, TrustedEntity};
+use crate::{AppState, x509};
+
+// Trust list endpoint
+#[axum::debug_handler]
+pub async fn trust_list(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    let issuers = vec![TrustedEntity {
+        id: state.issuer.to_string(),
+        name: "Credibil Demonstration Issuer".into(),
+    }];
+
+    // The verifier is listed under its DID-based client ID and, where the
+    // service's host is covered by the test certificate, its X.509 client ID.
+    let mut verifiers = vec![TrustedEntity {
+        id: state.verifier.to_string(),
+        name: "Credibil Demonstration Verifier".into(),
+    }];
+    if let Ok(client_id) = x509::client_id(&state.external_address) {
+        verifiers.push(TrustedEntity {
+            id: client_id,
+            name: "Credibil Demonstration Verifier".into(),
+        });
+    }
+
+    let jws = trust_list::sign(&state.external_address, &issuers, &verifiers)?;
+    Ok(([(header::CONTENT_TYPE, "application/jwt")], jws))
+}
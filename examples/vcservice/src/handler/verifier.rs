@@ -3,20 +3,28 @@
 use std::collections::HashMap;
 use std::vec;
 
-use anyhow::anyhow;
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use anyhow::{anyhow, bail};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::{Form, Json};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_vc::infosec::jose::JwsBuilder;
+use credibil_vc::verifier::proof::Type;
 use credibil_vc::verifier::{
     Constraints, CreateRequestRequest, DeviceFlow, Field, Filter, FilterValue, InputDescriptor,
-    RequestObjectRequest, RequestObjectResponse, ResponseRequest, ResponseResponse,
+    PresentationDefinition, RequestObject, RequestObjectRequest, RequestObjectResponse,
+    RequestObjectType, ResponseRequest, ResponseResponse,
 };
+use credibil_vc::{Kind, urlencode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use typeshare::typeshare;
+use url::Url;
 
 use super::{AppError, AppJson};
-use crate::AppState;
+use crate::{AppState, x509};
 
 /// Create authorization request. This is almost a copy of the
 /// `CreateRequestRequest` struct from the `credibil_vc::verifier` crate but repeated
@@ -30,6 +38,29 @@ pub struct GenerateRequest {
 
     /// Input Descriptors describe the information required from the holder.
     pub input_descriptors: Vec<GenerateInputDescriptor>,
+
+    /// Pass the presentation definition by reference. The request object will
+    /// contain a `presentation_definition_uri` instead of the definition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_reference: Option<bool>,
+
+    /// Create a request for a wallet on the same device as the verifier's web
+    /// page. The request object is passed by value in a link that opens the
+    /// wallet instead of a request URI.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_device: Option<bool>,
+
+    /// Where to send the user's browser once the wallet has presented. Only
+    /// used for same-device requests. A `response_code` query parameter is
+    /// added that can be exchanged for the result of the presentation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+
+    /// Sign the request object with the verifier's test X.509 certificate
+    /// chain instead of its DID. The verifier is identified by an
+    /// `x509_san_dns` client ID. Not supported for same-device requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x509: Option<bool>,
 }
 
 /// Input descriptor for the request. Type-generation friendly copy of the
@@ -73,13 +104,34 @@ pub struct GenerateField {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[typeshare]
 pub struct GenerateRequestResponse {
-    /// URI to the authorization request.
+    /// URI to the authorization request. For same-device requests, a link that
+    /// opens the wallet with the request object passed by value.
     pub request_uri: String,
 
     /// QR code for the request URI.
     pub qr_code: String,
 }
 
+/// Result of a same-device presentation, retrieved by the verifier's web page
+/// using the response code it was redirected with.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[typeshare]
+pub struct PresentationResult {
+    /// Whether the wallet's presentation was verified.
+    pub verified: bool,
+}
+
+/// A same-device presentation the verifier's web page is waiting on.
+#[derive(Clone, Debug, Default)]
+pub struct SameDeviceFlow {
+    /// URI to redirect the user's browser to once the wallet has presented.
+    redirect_uri: String,
+
+    /// Code the web page uses to get the result of the presentation. Set once
+    /// the wallet has presented.
+    response_code: Option<String>,
+}
+
 // Generate Authorization Request endpoint
 #[axum::debug_handler]
 pub async fn create_request(
@@ -110,6 +162,24 @@ pub async fn create_request(
         });
     }
 
+    let x509 = req.x509.unwrap_or_default();
+    if req.same_device.unwrap_or_default() {
+        if x509 {
+            return Err(AppError::Status(
+                StatusCode::BAD_REQUEST,
+                "same-device requests cannot be signed with X.509 certificates".into(),
+            ));
+        }
+        let request = CreateRequestRequest {
+            client_id: state.external_address.to_string(),
+            device_flow: DeviceFlow::SameDevice, // we will get a full request object.
+            purpose: req.purpose,
+            input_descriptors,
+            ..Default::default()
+        };
+        return same_device_request(&state, &request, req.redirect_uri).await;
+    }
+
     let request = CreateRequestRequest {
         client_id: state.external_address.to_string(),
         device_flow: DeviceFlow::CrossDevice, // we will get a URI, not a full request object.
@@ -138,6 +208,39 @@ pub async fn create_request(
     };
     response.request_uri = Some(format!("{}/request/{}", state.external_address, request_id));
 
+    // Keep the presentation definition so it can be served by reference. The
+    // definition is created by `credibil_vc` so is taken from the request
+    // object it has stored.
+    if req.by_reference.unwrap_or_default() {
+        let request = RequestObjectRequest {
+            client_id: state.external_address.to_string(),
+            id: (*request_id).to_string(),
+        };
+        let object =
+            credibil_vc::verifier::request_object(state.verifier_provider.clone(), &request)
+                .await?;
+        let Kind::Object(definition) = request_object_claims(&object)?.presentation_definition
+        else {
+            return Err(AppError::Status(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "no presentation definition in request object".into(),
+            ));
+        };
+        state
+            .presentation_definitions
+            .lock()
+            .map_err(|e| anyhow!("{e}"))?
+            .insert((*request_id).to_string(), definition);
+    }
+
+    // Check the verifier's address is named by the test certificate before
+    // handing out the request.
+    if x509 {
+        x509::client_id(&state.external_address)
+            .map_err(|e| AppError::Status(StatusCode::BAD_REQUEST, e.to_string()))?;
+        state.x509_requests.lock().map_err(|e| anyhow!("{e}"))?.insert((*request_id).to_string());
+    }
+
     let qr_code = response.to_qrcode(None)?;
 
     let gen_response = GenerateRequestResponse {
@@ -148,20 +251,116 @@ pub async fn create_request(
     Ok(AppJson(gen_response))
 }
 
+// Create a request object to pass by value to a wallet on the same device.
+// The request's state is used to find the flow when the wallet presents.
+async fn same_device_request(
+    state: &AppState, request: &CreateRequestRequest, redirect_uri: Option<String>,
+) -> Result<AppJson<GenerateRequestResponse>, AppError> {
+    let Some(redirect_uri) = redirect_uri else {
+        return Err(AppError::Status(
+            StatusCode::BAD_REQUEST,
+            "same-device requests need a redirect URI".into(),
+        ));
+    };
+    let response =
+        credibil_vc::verifier::create_request(state.verifier_provider.clone(), request).await?;
+    let Some(request_object) = &response.request_object else {
+        return Err(AppError::Status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "no request object returned".into(),
+        ));
+    };
+    let Some(request_state) = request_object.state.clone() else {
+        return Err(AppError::Status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "no state in request object".into(),
+        ));
+    };
+    let query = urlencode::to_string(request_object).map_err(|e| anyhow!("{e}"))?;
+    let request_uri = format!("openid4vp://?{query}");
+    let qr_code = response.to_qrcode(None)?;
+
+    state.same_device_flows.lock().map_err(|e| anyhow!("{e}"))?.insert(
+        request_state,
+        SameDeviceFlow {
+            redirect_uri,
+            response_code: None,
+        },
+    );
+
+    Ok(AppJson(GenerateRequestResponse { request_uri, qr_code }))
+}
+
 // Return an authorization request object.
 #[axum::debug_handler]
 pub async fn request_object(
     State(state): State<AppState>, Path(object_id): Path<String>,
 ) -> Result<AppJson<RequestObjectResponse>, AppError> {
+    let by_reference =
+        state.presentation_definitions.lock().map_err(|e| anyhow!("{e}"))?.contains_key(&object_id);
+    let x509 = state.x509_requests.lock().map_err(|e| anyhow!("{e}"))?.contains(&object_id);
+
     let request = RequestObjectRequest {
         client_id: state.external_address.to_string(),
-        id: object_id,
+        id: object_id.clone(),
     };
-    let response =
+    let mut response =
         credibil_vc::verifier::request_object(state.verifier_provider.clone(), &request).await?;
+    if !by_reference && !x509 {
+        return Ok(AppJson(response));
+    }
+
+    // Replace the presentation definition with a reference to it and/or sign
+    // the request object with the X.509 certificate chain.
+    let mut claims = request_object_claims(&response)?;
+    if by_reference {
+        claims.presentation_definition =
+            Kind::String(format!("{}/presentation_definition/{object_id}", state.external_address));
+    }
+    if x509 {
+        let client_id = x509::client_id(&state.external_address)?;
+        response.request_object =
+            RequestObjectType::Jwt(x509::sign_request_object(&claims, &client_id)?);
+        return Ok(AppJson(response));
+    }
+    let jws = JwsBuilder::new()
+        .jwt_type(Type::OauthAuthzReqJwt)
+        .payload(claims)
+        .add_signer(&state.verifier_provider)
+        .build()
+        .await?;
+    response.request_object = RequestObjectType::Jwt(jws.encode()?);
     Ok(AppJson(response))
 }
 
+// Return a presentation definition referred to by a request object.
+#[axum::debug_handler]
+pub async fn presentation_definition(
+    State(state): State<AppState>, Path(definition_id): Path<String>,
+) -> Result<AppJson<PresentationDefinition>, AppError> {
+    let definitions = state.presentation_definitions.lock().map_err(|e| anyhow!("{e}"))?;
+    let Some(definition) = definitions.get(&definition_id) else {
+        return Err(AppError::Status(
+            StatusCode::NOT_FOUND,
+            format!("no presentation definition with ID {definition_id}"),
+        ));
+    };
+    Ok(AppJson(definition.clone()))
+}
+
+// Get the claims from a request object JWT without verifying the signature.
+// The JWT has just been created by this service.
+fn request_object_claims(response: &RequestObjectResponse) -> anyhow::Result<RequestObject> {
+    let RequestObjectType::Jwt(token) = &response.request_object else {
+        bail!("no serialized JWT found in response");
+    };
+    let Some(payload) = token.split('.').nth(1) else {
+        bail!("request object is not a compact JWT");
+    };
+    let decoded = Base64UrlUnpadded::decode_vec(payload).map_err(|e| anyhow!("{e}"))?;
+    Ok(serde_json::from_slice(&decoded)?)
+}
+
 // Wallet authorization response (the actual presentation of the credential to
 // the verifier).
 #[axum::debug_handler]
@@ -174,11 +373,51 @@ pub async fn response(
             format!("unable to turn HashMap {req:?} into ResponseRequest"),
         ));
     };
-    let response =
+    let mut response =
         credibil_vc::verifier::response(state.verifier_provider.clone(), &response_request).await?;
+
+    // Send the user's browser back to the verifier's web page if the wallet
+    // is on the same device.
+    let mut flows = state.same_device_flows.lock().map_err(|e| anyhow!("{e}"))?;
+    if let Some(flow) = response_request.state.as_ref().and_then(|s| flows.get_mut(s)) {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let response_code = Base64UrlUnpadded::encode_string(&bytes);
+        let mut redirect_uri = Url::parse(&flow.redirect_uri).map_err(|e| anyhow!(e))?;
+        redirect_uri.query_pairs_mut().append_pair("response_code", &response_code);
+        response.redirect_uri = Some(redirect_uri.to_string());
+        response.response_code = Some(response_code.clone());
+        flow.response_code = Some(response_code);
+    }
+    drop(flows);
+
     Ok(AppJson(response))
 }
 
+// Return the result of a same-device presentation to the verifier's web page.
+// The response code can only be used once.
+#[axum::debug_handler]
+pub async fn presentation_result(
+    State(state): State<AppState>, Path(response_code): Path<String>,
+) -> Result<AppJson<PresentationResult>, AppError> {
+    let mut flows = state.same_device_flows.lock().map_err(|e| anyhow!("{e}"))?;
+    let Some(request_state) = flows
+        .iter()
+        .find(|(_, flow)| flow.response_code.as_deref() == Some(response_code.as_str()))
+        .map(|(request_state, _)| request_state.clone())
+    else {
+        return Err(AppError::Status(
+            StatusCode::NOT_FOUND,
+            format!("no presentation with response code {response_code}"),
+        ));
+    };
+    flows.remove(&request_state);
+
+    // The response endpoint only sets a response code once the presentation
+    // has been verified.
+    Ok(AppJson(PresentationResult { verified: true }))
+}
+
 // DID document endpoint
 #[axum::debug_handler]
 pub async fn did(State(state): State<AppState>) -> Result<AppJson<Value>, AppError> {
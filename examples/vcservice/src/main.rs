@@ -2,14 +2,20 @@
 //!
 //! Simple, hard-coded service useful for demonstrating the Credibil example
 //! wallets.
-//! 
-//! Assumes pre-authorized, issuer-initiated flow only.
+//!
+//! Assumes issuer-initiated flows only. Supports the pre-authorized code grant
+//! and the authorization code grant with PKCE.
 
+mod attestation;
 mod handler;
 mod provider;
+mod trust_list;
+mod x509;
 
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::sync::{Arc, Mutex};
 
 use axum::Router;
 use axum::body::Body;
@@ -18,9 +24,12 @@ use axum::extract::rejection::JsonRejection;
 use axum::http::{HeaderValue, Request, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
-use handler::{assets, issuer, verifier};
+use credibil_vc::verifier::PresentationDefinition;
+use handler::notification::Notification;
+use handler::{assets, issuer, notification, status, trust, verifier};
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
+use tokio::sync::broadcast;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
@@ -29,13 +38,21 @@ use tracing_subscriber::{EnvFilter, FmtSubscriber};
 use typeshare::typeshare;
 use url::Url;
 
+use crate::attestation::AttestationVerifier;
+
 /// Application state.
 #[derive(Clone)]
 pub struct AppState {
     external_address: Cow<'static, str>,
     issuer: Cow<'static, str>,
+    verifier: Cow<'static, str>,
     issuer_provider: provider::issuer::Provider,
     verifier_provider: provider::verifier::Provider,
+    notifier: broadcast::Sender<Notification>,
+    presentation_definitions: Arc<Mutex<HashMap<String, PresentationDefinition>>>,
+    same_device_flows: Arc<Mutex<HashMap<String, verifier::SameDeviceFlow>>>,
+    attestation: Option<AttestationVerifier>,
+    x509_requests: Arc<Mutex<HashSet<String>>>,
 }
 
 #[tokio::main]
@@ -50,25 +67,51 @@ async fn main() {
     let issuer = env::var("CREDIBIL_ISSUER").unwrap_or_else(|_| "http://credibil.io".into());
     let verifier = env::var("CREDIBIL_VERIFIER").unwrap_or_else(|_| "http://localhost:8080".into());
 
+    // Require wallet attestation at the token endpoint if an attester is
+    // trusted.
+    let attestation = env::var("CREDIBIL_WALLET_ATTESTER").ok().map(|key| {
+        AttestationVerifier::new(&key, vec![external_address.clone(), issuer.clone()])
+            .expect("CREDIBIL_WALLET_ATTESTER should be a base64url Ed25519 public key")
+    });
+
+    let (notifier, _) = broadcast::channel(16);
     let app_state = AppState {
         external_address: external_address.clone().into(),
         issuer: issuer.into(),
+        verifier: verifier.clone().into(),
         issuer_provider: provider::issuer::Provider::new(&external_address),
         verifier_provider: provider::verifier::Provider::new(&external_address, &verifier),
+        notifier,
+        presentation_definitions: Arc::default(),
+        same_device_flows: Arc::default(),
+        attestation,
+        x509_requests: Arc::default(),
     };
 
     let cors = CorsLayer::new().allow_methods(Any).allow_origin(Any).allow_headers(Any);
     let router = Router::new()
         .route("/", get(handler::index))
         .route("/create_offer", post(issuer::create_offer))
+        .route("/credential_offer/:offer_id", get(issuer::credential_offer))
         .route("/.well-known/openid-credential-issuer", get(issuer::metadata))
+        .route("/.well-known/oauth-authorization-server", get(issuer::oauth_server))
         .route("/.well-known/did.json", get(issuer::did))
+        .route("/auth", get(issuer::authorize))
+        .route("/par", post(issuer::par))
         .route("/token", post(issuer::token))
         .route("/credential", post(issuer::credential))
+        .route("/batch_credential", post(issuer::batch_credential))
+        .route("/statuslists/:list_id", get(status::status_list))
+        .route("/revoke", post(status::revoke))
+        .route("/trust_list", get(trust::trust_list))
+        .route("/notifications", get(notification::notifications))
+        .route("/notify", post(notification::notify))
         .route("/create_request", post(verifier::create_request))
         .route("/verifier/did.json", get(verifier::did))
         .route("/request/:object_id", get(verifier::request_object))
+        .route("/presentation_definition/:definition_id", get(verifier::presentation_definition))
         .route("/post", post(verifier::response))
+        .route("/presentation_result/:response_code", get(verifier::presentation_result))
         .nest_service("/assets/:filename", get(assets::asset))
         .layer(
             TraceLayer::new_for_http()
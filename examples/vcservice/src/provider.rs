@@ -1,2 +1,3 @@
+pub mod formats;
 pub mod issuer;
 pub mod verifier;
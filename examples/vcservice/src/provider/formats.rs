@@ -0,0 +1,122 @@
+//! # Credential Formats
+//!
+//! The hard-coded issuer metadata only has `jwt_vc_json` credential
+//! configurations. So wallets holding credentials of several formats can be
+//! tested against this service, the employee ID credential is also offered
+//! as an SD-JWT (`vc+sd-jwt`) and as an mdoc (`mso_mdoc`).
+//!
+//! Each additional configuration is derived from the `jwt_vc_json`
+//! configuration it is based on, with the same display, binding methods and
+//! proof types, and is issued from the same subject datasets. Credential
+//! identifiers for the additional configurations are prefixed with the
+//! configuration ID so the dataset can be shaped for the format when the
+//! credential is issued.
+
+use anyhow::anyhow;
+use credibil_vc::issuer::provider::{Dataset, Issuer};
+use serde_json::{Value, json};
+
+/// Configuration ID of the employee ID credential issued as an SD-JWT.
+pub const SD_JWT_CONFIGURATION_ID: &str = "EmployeeID_SD-JWT";
+
+/// Configuration ID of the employee ID credential issued as an mdoc.
+pub const MDOC_CONFIGURATION_ID: &str = "EmployeeID_mDoc";
+
+/// Verifiable credential type of the SD-JWT employee ID credential.
+const EMPLOYEE_VCT: &str = "EmployeeIDCredential";
+
+/// Document type of the mdoc employee ID credential. Its claims are all in
+/// the namespace of the same name.
+const EMPLOYEE_DOCTYPE: &str = "io.credibil.employee.1";
+
+/// Additional configurations and the `jwt_vc_json` configurations they are
+/// based on.
+const CONFIGURATIONS: [(&str, &str); 2] =
+    [(SD_JWT_CONFIGURATION_ID, "EmployeeID_JWT"), (MDOC_CONFIGURATION_ID, "EmployeeID_JWT")];
+
+/// Add the additional configurations to the issuer's metadata. A
+/// configuration is left out if the configuration it is based on is missing.
+///
+/// # Errors
+///
+/// Returns an error if a configuration cannot be converted.
+pub fn add_configurations(issuer: &mut Issuer) -> anyhow::Result<()> {
+    for (id, base_id) in CONFIGURATIONS {
+        let Some(base) = issuer.credential_configurations_supported.get(base_id) else {
+            continue;
+        };
+        let config = configuration(id, serde_json::to_value(base)?)?;
+        issuer
+            .credential_configurations_supported
+            .insert(id.into(), serde_json::from_value(config)?);
+    }
+    Ok(())
+}
+
+/// The `jwt_vc_json` configuration an additional configuration is based on,
+/// or `None` for any other configuration.
+#[must_use]
+pub fn base_configuration(configuration_id: &str) -> Option<&'static str> {
+    CONFIGURATIONS.iter().find(|(id, _)| *id == configuration_id).map(|(_, base_id)| *base_id)
+}
+
+/// The credential identifier for an additional configuration's credential,
+/// given the identifier of the credential it is based on.
+#[must_use]
+pub fn identifier(configuration_id: &str, base_identifier: &str) -> String {
+    format!("{configuration_id}/{base_identifier}")
+}
+
+/// Split a credential identifier for an additional configuration's
+/// credential into the configuration ID and the identifier of the credential
+/// it is based on. Returns `None` for any other credential identifier.
+#[must_use]
+pub fn split_identifier(identifier: &str) -> Option<(&'static str, &str)> {
+    CONFIGURATIONS.iter().find_map(|(id, _)| {
+        let base_identifier = identifier.strip_prefix(id)?.strip_prefix('/')?;
+        Some((*id, base_identifier))
+    })
+}
+
+/// Shape a dataset for the credential's format: mdoc claims are grouped by
+/// namespace.
+///
+/// # Errors
+///
+/// Returns an error if the dataset cannot be converted.
+pub fn dataset(configuration_id: &str, dataset: Dataset) -> anyhow::Result<Dataset> {
+    if configuration_id != MDOC_CONFIGURATION_ID {
+        return Ok(dataset);
+    }
+    let mut dataset = serde_json::to_value(dataset)?;
+    let claims = dataset.get_mut("claims").ok_or_else(|| anyhow!("dataset has no claims"))?;
+    *claims = json!({ EMPLOYEE_DOCTYPE: claims.take() });
+    Ok(serde_json::from_value(dataset)?)
+}
+
+// Derive an additional configuration from the JSON of the configuration it
+// is based on, replacing the W3C credential definition with the format's
+// type and claims.
+fn configuration(configuration_id: &str, base: Value) -> anyhow::Result<Value> {
+    let Value::Object(mut config) = base else {
+        return Err(anyhow!("credential configuration is not an object"));
+    };
+    let subject = config
+        .remove("credential_definition")
+        .and_then(|mut definition| definition.get_mut("credentialSubject").map(Value::take))
+        .unwrap_or_else(|| json!({}));
+    // the scope belongs to the base configuration
+    config.remove("scope");
+    config.insert("credential_signing_alg_values_supported".into(), json!(["EdDSA"]));
+
+    if configuration_id == MDOC_CONFIGURATION_ID {
+        config.insert("format".into(), json!("mso_mdoc"));
+        config.insert("doctype".into(), json!(EMPLOYEE_DOCTYPE));
+        config.insert("claims".into(), json!({ EMPLOYEE_DOCTYPE: subject }));
+    } else {
+        config.insert("format".into(), json!("vc+sd-jwt"));
+        config.insert("vct".into(), json!(EMPLOYEE_VCT));
+        config.insert("claims".into(), subject);
+    }
+    Ok(Value::Object(config))
+}
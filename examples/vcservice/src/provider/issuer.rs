@@ -2,9 +2,13 @@
 //!
 //! Provider implementation for the issuer aspect of the service.
 
-use anyhow::anyhow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail};
 use base64ct::{Base64UrlUnpadded, Encoding};
 use chrono::{DateTime, Utc};
+use credibil_vc::issuer::CredentialStatus;
 use credibil_vc::issuer::provider::{
     Algorithm, Client, Dataset, DidResolver, Document, Issuer, Metadata, PublicKey, Receiver,
     Result, Server, SharedSecret, Signer, StateStore, Status, Subject,
@@ -13,6 +17,9 @@ use credibil_vc::test_utils::store::{issuance, resolver, state};
 use ed25519_dalek::{SecretKey, Signer as _, SigningKey};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::provider::formats;
 
 const ISSUER_DID: &str = "did:web:credibil.io";
 const ISSUER_VERIFY_KEY: &str = "key-0";
@@ -25,6 +32,7 @@ pub struct Provider {
     pub server: issuance::ServerStore,
     pub subject: issuance::DatasetStore,
     pub state: state::Store,
+    pub status: StatusStore,
     pub external_address: String,
 }
 
@@ -37,9 +45,92 @@ impl Provider {
             server: issuance::ServerStore::new(),
             subject: issuance::DatasetStore::new(),
             state: state::Store::new(),
+            status: StatusStore::default(),
             external_address: external_address.into(),
         }
     }
+
+    /// URL of the status list credential issued credentials refer to.
+    #[must_use]
+    pub fn status_list_url(&self) -> String {
+        format!("{}/statuslists/{STATUS_LIST_ID}", self.external_address)
+    }
+}
+
+/// ID of the single revocation status list published by the service.
+pub const STATUS_LIST_ID: &str = "1";
+
+/// Number of entries in the status list. The bitstring status list
+/// specification requires at least 16KB of entries for herd privacy.
+const STATUS_LIST_SIZE: usize = 131_072;
+
+/// Revocation status list. Each issued credential is allocated the next
+/// index in the list.
+#[derive(Clone, Debug, Default)]
+pub struct StatusStore {
+    list: Arc<Mutex<StatusList>>,
+}
+
+#[derive(Debug, Default)]
+struct StatusList {
+    indexes: HashMap<String, usize>,
+    revoked: Vec<usize>,
+}
+
+impl StatusStore {
+    /// Get the status list index for a credential, allocating one if the
+    /// credential has not been seen before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status list is full.
+    pub fn index(&self, subject_id: &str, credential_identifier: &str) -> anyhow::Result<usize> {
+        let mut list = self.list.lock().map_err(|e| anyhow!("{e}"))?;
+        let next = list.indexes.len();
+        if next >= STATUS_LIST_SIZE {
+            bail!("status list is full");
+        }
+        let index =
+            *list.indexes.entry(status_key(subject_id, credential_identifier)).or_insert(next);
+        Ok(index)
+    }
+
+    /// Revoke a credential.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no credential has been issued to the subject with
+    /// the identifier.
+    pub fn revoke(&self, subject_id: &str, credential_identifier: &str) -> anyhow::Result<()> {
+        let mut list = self.list.lock().map_err(|e| anyhow!("{e}"))?;
+        let Some(&index) = list.indexes.get(&status_key(subject_id, credential_identifier)) else {
+            bail!("no credential {credential_identifier} issued to {subject_id}");
+        };
+        if !list.revoked.contains(&index) {
+            list.revoked.push(index);
+        }
+        Ok(())
+    }
+
+    /// The status list as a bitstring, where the bit for a revoked credential
+    /// is set. Index 0 is the most significant bit of the first byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status list cannot be read.
+    pub fn bitstring(&self) -> anyhow::Result<Vec<u8>> {
+        let list = self.list.lock().map_err(|e| anyhow!("{e}"))?;
+        let mut bitstring = vec![0u8; STATUS_LIST_SIZE / 8];
+        for index in &list.revoked {
+            bitstring[index / 8] |= 0x80 >> (index % 8);
+        }
+        Ok(bitstring)
+    }
+}
+
+// Key a credential in the status list by subject and credential identifier.
+fn status_key(subject_id: &str, credential_identifier: &str) -> String {
+    format!("{subject_id}:{credential_identifier}")
 }
 
 impl credibil_vc::issuer::provider::Provider for Provider {}
@@ -53,8 +144,12 @@ impl Metadata for Provider {
         self.client.add(client)
     }
 
+    /// Issuer metadata, including the configurations for credential formats
+    /// other than `jwt_vc_json`.
     async fn issuer(&self, issuer_id: &str) -> Result<Issuer> {
-        self.issuer.get(issuer_id)
+        let mut issuer = self.issuer.get(issuer_id)?;
+        formats::add_configurations(&mut issuer)?;
+        Ok(issuer)
     }
 
     async fn server(&self, server_id: &str, _issuer_id: Option<&str>) -> Result<Server> {
@@ -67,11 +162,24 @@ impl Subject for Provider {
     async fn authorize(
         &self, subject_id: &str, credential_configuration_id: &str,
     ) -> Result<Vec<String>> {
-        self.subject.authorize(subject_id, credential_configuration_id)
+        let Some(base_id) = formats::base_configuration(credential_configuration_id) else {
+            return self.subject.authorize(subject_id, credential_configuration_id);
+        };
+        let identifiers = self.subject.authorize(subject_id, base_id)?;
+        Ok(identifiers
+            .iter()
+            .map(|identifier| formats::identifier(credential_configuration_id, identifier))
+            .collect())
     }
 
+    /// Dataset for the credential, shaped for the credential's format.
     async fn dataset(&self, subject_id: &str, credential_identifier: &str) -> Result<Dataset> {
-        self.subject.dataset(subject_id, credential_identifier)
+        let Some((configuration_id, identifier)) = formats::split_identifier(credential_identifier)
+        else {
+            return self.subject.dataset(subject_id, credential_identifier);
+        };
+        let dataset = self.subject.dataset(subject_id, identifier)?;
+        formats::dataset(configuration_id, dataset)
     }
 }
 
@@ -134,4 +242,20 @@ impl Receiver for Provider {
     }
 }
 
-impl Status for Provider {}
+impl Status for Provider {
+    /// Add a revocation status list entry to each issued credential.
+    async fn status(
+        &self, subject_id: &str, credential_identifier: &str,
+    ) -> Result<Option<CredentialStatus>> {
+        let index = self.status.index(subject_id, credential_identifier)?;
+        let list_url = self.status_list_url();
+        let entry = json!({
+            "id": format!("{list_url}#{index}"),
+            "type": "BitstringStatusListEntry",
+            "statusPurpose": "revocation",
+            "statusListIndex": index.to_string(),
+            "statusListCredential": list_url,
+        });
+        Ok(Some(serde_json::from_value(entry)?))
+    }
+}
@@ -0,0 +1,68 @@
+//! # Trust List
+//!
+//! Publishes a signed list of the issuers and verifiers this service runs so
+//! that wallets can tell a known party from an unknown one. In a real
+//! ecosystem the list would be published by a scheme operator rather than by
+//! the parties on it.
+//!
+//! The list is a compact JWS signed with `EdDSA` by a dedicated trust list key.
+//! Wallets pin the key's public half, which is
+//! `y1LUzVdFhyQ2uqjFz3u-4nAJmc7Ry8XexfOWdecayqA` (base64url). The private key
+//! is published here so the list must only be trusted for demonstrations.
+
+use anyhow::anyhow;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{Duration, Utc};
+use ed25519_dalek::{Signer as _, SigningKey};
+use serde::Serialize;
+use serde_json::json;
+
+/// Seed of the trust list signing key.
+const TRUST_LIST_SEED: &str = "ZcjKlDwhLMFWqZkhqFSgVyMirAabobrf4kU-gY_0pTQ";
+
+/// How long wallets can rely on a copy of the list.
+const TRUST_LIST_VALIDITY: Duration = Duration::hours(24);
+
+/// An issuer or verifier on the trust list.
+#[derive(Clone, Debug, Serialize)]
+pub struct TrustedEntity {
+    /// The issuer's identifier or the verifier's client ID.
+    pub id: String,
+
+    /// Display name.
+    pub name: String,
+}
+
+/// Sign a trust list naming the given issuers and verifiers.
+///
+/// # Errors
+///
+/// Returns an error if the list cannot be serialized.
+pub fn sign(
+    list_issuer: &str, issuers: &[TrustedEntity], verifiers: &[TrustedEntity],
+) -> anyhow::Result<String> {
+    let mut seed = [0u8; 32];
+    Base64UrlUnpadded::decode(TRUST_LIST_SEED, &mut seed)
+        .map_err(|e| anyhow!("invalid seed: {e}"))?;
+    let key = SigningKey::from_bytes(&seed);
+
+    let now = Utc::now();
+    let header = serde_json::to_vec(&json!({
+        "alg": "EdDSA",
+        "typ": "trust-list+jwt",
+    }))?;
+    let payload = serde_json::to_vec(&json!({
+        "iss": list_issuer,
+        "iat": now.timestamp(),
+        "exp": (now + TRUST_LIST_VALIDITY).timestamp(),
+        "issuers": issuers,
+        "verifiers": verifiers,
+    }))?;
+    let signing_input = format!(
+        "{}.{}",
+        Base64UrlUnpadded::encode_string(&header),
+        Base64UrlUnpadded::encode_string(&payload)
+    );
+    let signature = key.sign(signing_input.as_bytes());
+    Ok(format!("{signing_input}.{}", Base64UrlUnpadded::encode_string(&signature.to_bytes())))
+}
@@ -0,0 +1,83 @@
+//! # X.509 Request Signing
+//!
+//! Signs request objects with a key certified by an X.509 certificate chain
+//! rather than a DID. The chain is sent in the JWS `x5c` header and the
+//! verifier identifies itself with an `x509_san_dns:` client ID naming a DNS
+//! subject alternative name of the leaf certificate. A wallet trusting the
+//! root certificate can then check the request came from the verifier.
+//!
+//! The chain is a test chain generated for this service: a self-signed root
+//! and an Ed25519 leaf with subject alternative names `localhost`,
+//! `vcservice` and `credibil.io`, valid from 2025 to 2035. Its keys are
+//! published here so it must only be trusted for demonstrations.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use ed25519_dalek::{Signer as _, SigningKey};
+use serde::Serialize;
+use serde_json::{Value, json};
+use url::Url;
+
+/// Seed of the leaf certificate's Ed25519 key.
+const LEAF_SEED: &str = "FC6pOGXY4WuR5qv1LKCobSqVrDR46KvIuFlWvPuPxJk";
+
+/// DER encoded leaf certificate (standard base64, as used by `x5c`).
+const LEAF_CERTIFICATE: &str = "MIIBezCCAS2gAwIBAgIURdQMRBNhXA0xoXhhQpRdwAb68HowBQYDK2VwMDcxETAPBgNVBAoMCENyZWRpYmlsMSIwIAYDVQQDDBlDcmVkaWJpbCBUZXN0IFZlcmlmaWVyIENBMB4XDTI1MDEwMTAwMDAwMFoXDTM1MDEwMTAwMDAwMFowNDERMA8GA1UECgwIQ3JlZGliaWwxHzAdBgNVBAMMFkNyZWRpYmlsIFRlc3QgVmVyaWZpZXIwKjAFBgMrZXADIQCdS1yillbZ4kzhWtqrKUYk/AABqI4sMLdE26qW3JHoNaNOMEwwDAYDVR0TAQH/BAIwADAOBgNVHQ8BAf8EBAMCB4AwLAYDVR0RBCUwI4IJbG9jYWxob3N0ggl2Y3NlcnZpY2WCC2NyZWRpYmlsLmlvMAUGAytlcANBAE1NfwRLZjUINaToXzUaNNXK1qh1m+GfslH6TI5ZmIM4TxQv8mDfHFb02P4CoJrBybRo2R0mMniSJpjZkeRgMQQ=";
+
+/// DER encoded root certificate (standard base64, as used by `x5c`).
+const ROOT_CERTIFICATE: &str = "MIIBVjCCAQigAwIBAgIUexPVlBTPii93EUyjg+8ZvwHKZmswBQYDK2VwMDcxETAPBgNVBAoMCENyZWRpYmlsMSIwIAYDVQQDDBlDcmVkaWJpbCBUZXN0IFZlcmlmaWVyIENBMB4XDTI1MDEwMTAwMDAwMFoXDTM1MDEwMTAwMDAwMFowNzERMA8GA1UECgwIQ3JlZGliaWwxIjAgBgNVBAMMGUNyZWRpYmlsIFRlc3QgVmVyaWZpZXIgQ0EwKjAFBgMrZXADIQDuvGwbl3RvShHuifVefvlKqhbmxbGZhA8Rev3xtlrdhqMmMCQwEgYDVR0TAQH/BAgwBgEB/wIBADAOBgNVHQ8BAf8EBAMCAQYwBQYDK2VwA0EAugg9oEXEH72EXfMV1doI+SznxqK+jSaHmX91d5YtfCKsMCqGi6lIDHItiPLyZoqW26CXZbXuzW+f8MatMzrtCQ==";
+
+/// DNS subject alternative names of the leaf certificate.
+const SAN_DNS_NAMES: [&str; 3] = ["localhost", "vcservice", "credibil.io"];
+
+/// The `x509_san_dns:` client ID for the verifier at `address`. The address's
+/// host must be one of the leaf certificate's DNS names.
+///
+/// # Errors
+///
+/// Returns an error if the address has no host or the host is not named by
+/// the leaf certificate.
+pub fn client_id(address: &str) -> anyhow::Result<String> {
+    let url = Url::parse(address)?;
+    let Some(host) = url.host_str() else {
+        bail!("verifier address {address} has no host");
+    };
+    if !SAN_DNS_NAMES.contains(&host) {
+        bail!("the test certificate is not valid for {host}: use one of {SAN_DNS_NAMES:?}");
+    }
+    Ok(format!("x509_san_dns:{host}"))
+}
+
+/// Sign request object claims as a compact JWS with the leaf certificate's
+/// key, identifying the verifier by `client_id` and including the certificate
+/// chain in the `x5c` header.
+///
+/// # Errors
+///
+/// Returns an error if the claims cannot be serialized to a JSON object.
+pub fn sign_request_object(claims: &impl Serialize, client_id: &str) -> anyhow::Result<String> {
+    let mut claims = serde_json::to_value(claims)?;
+    let Value::Object(object) = &mut claims else {
+        bail!("request object claims should be a JSON object");
+    };
+    object.insert("client_id".into(), client_id.into());
+    object.insert("client_id_scheme".into(), "x509_san_dns".into());
+
+    let mut seed = [0u8; 32];
+    Base64UrlUnpadded::decode(LEAF_SEED, &mut seed).map_err(|e| anyhow!("invalid seed: {e}"))?;
+    let key = SigningKey::from_bytes(&seed);
+
+    let header = serde_json::to_vec(&json!({
+        "alg": "EdDSA",
+        "typ": "oauth-authz-req+jwt",
+        "x5c": [LEAF_CERTIFICATE, ROOT_CERTIFICATE],
+    }))?;
+    let payload = serde_json::to_vec(&claims)?;
+    let signing_input = format!(
+        "{}.{}",
+        Base64UrlUnpadded::encode_string(&header),
+        Base64UrlUnpadded::encode_string(&payload)
+    );
+    let signature = key.sign(signing_input.as_bytes());
+    Ok(format!("{signing_input}.{}", Base64UrlUnpadded::encode_string(&signature.to_bytes())))
+}
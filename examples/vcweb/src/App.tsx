@@ -7,6 +7,7 @@ import './index.css';
 import Layout from "./Layout";
 import Offer from "./Offer";
 import Request from "./Request";
+import Complete from "./Request/Complete";
 import { theme } from "./theme";
 
 const App = () => {
@@ -19,6 +20,7 @@ const App = () => {
                         <Route index element={<Home />} />
                         <Route path="/issuer" element={<Offer />} />
                         <Route path="/verifier" element={<Request />} />
+                        <Route path="/verifier/complete" element={<Complete />} />
                     </Route>
                 </Routes>
             </BrowserRouter>
@@ -0,0 +1,76 @@
+import { useEffect } from "react";
+
+import Box from "@mui/material/Box";
+import Button from "@mui/material/Button";
+import Stack from "@mui/material/Stack";
+import Typography from "@mui/material/Typography";
+import { useQuery } from "@tanstack/react-query";
+import { useNavigate, useSearchParams } from "react-router-dom";
+import { useSetRecoilState } from "recoil";
+
+import { instanceOfErrorResponse } from "../api";
+import { presentationResult } from "../api/verification";
+import FullLogo from "../components/FullLogo";
+import { headerState } from "../state";
+
+// Page the wallet redirects back to after a same-device presentation.
+const Complete = () => {
+    const [searchParams] = useSearchParams();
+    const responseCode = searchParams.get("response_code") || "";
+    const setHeader = useSetRecoilState(headerState);
+    const navigate = useNavigate();
+
+    useEffect(() => {
+        setHeader({
+            title: "Credential Verifier",
+            action: undefined,
+            secondaryAction: undefined,
+        });
+    }, [setHeader]);
+
+    // The response code can only be exchanged once so don't refetch.
+    const { data, isPending } = useQuery({
+        queryKey: ["presentationResult", responseCode],
+        queryFn: () => presentationResult(responseCode),
+        enabled: responseCode !== "",
+        retry: false,
+        staleTime: Infinity,
+    });
+
+    const message = () => {
+        if (responseCode === "") {
+            return "No presentation to show.";
+        }
+        if (isPending) {
+            return "Checking the presentation...";
+        }
+        if (!data || instanceOfErrorResponse(data) || !data.verified) {
+            return "The credential could not be verified.";
+        }
+        return "The credential has been presented and verified.";
+    };
+
+    return (
+        <Stack spacing={4} py={4} id="pageContent">
+            <Typography variant="h1">
+                Credential Presentation
+            </Typography>
+            <Typography variant="body1">
+                {message()}
+            </Typography>
+            <Box sx={{ display: "flex", justifyContent: "center" }}>
+                <Button
+                    variant="contained"
+                    color="secondary"
+                    onClick={() => navigate("/verifier")}
+                    sx={{ maxWidth: "200px" }}
+                >
+                    Start Over
+                </Button>
+            </Box>
+            <FullLogo />
+        </Stack>
+    );
+};
+
+export default Complete;
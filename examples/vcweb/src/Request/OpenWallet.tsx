@@ -0,0 +1,47 @@
+import Box from "@mui/material/Box";
+import Button from "@mui/material/Button";
+import Stack from "@mui/material/Stack";
+import Typography from "@mui/material/Typography";
+
+export type OpenWalletProps = {
+    title?: string;
+    url: string;
+};
+
+const OpenWallet = (props: OpenWalletProps) => {
+    const { title, url } = props;
+
+    return (
+        <Box
+            sx={{
+                borderRadius: "8px",
+                p: 6,
+                backgroundColor: theme => theme.palette.background.paper,
+            }}
+        >
+            <Stack spacing={2}>
+                {title && <Typography variant="h5">{title}</Typography>}
+                <Typography variant="body2">
+                    Open the wallet app on this device to present the credential. The wallet will
+                    bring you back here once the credential has been presented.
+                </Typography>
+                <Box sx={{
+                    display: "flex", justifyContent: "center"
+                }}>
+                    <Button
+                        variant="contained"
+                        color="primary"
+                        href={url}
+                        sx={{
+                            maxWidth: "200px"
+                        }}
+                    >
+                        Open Wallet
+                    </Button>
+                </Box>
+            </Stack>
+        </Box>
+    );
+};
+
+export default OpenWallet;
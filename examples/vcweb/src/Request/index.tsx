@@ -10,6 +10,7 @@ import { useMutation } from "@tanstack/react-query";
 import { useSetRecoilState } from "recoil";
 
 import CreateRequest from "./CreateRequest";
+import OpenWallet from "./OpenWallet";
 import RequestUrl from "./RequestUrl";
 import { instanceOfErrorResponse } from "../api";
 import { createRequest } from "../api/verification";
@@ -28,6 +29,7 @@ const Request = () => {
     const [qrCode, setQrCode] = useState<string>("");
     const [requestUrl, setRequestUrl] = useState<string>("");
     const [showUrl, setShowUrl] = useState(false);
+    const [sameDevice, setSameDevice] = useState(false);
     const setHeader = useSetRecoilState(headerState);
 
     // Translate some hard-coded values for the supported credentials.
@@ -113,6 +115,10 @@ const Request = () => {
             purpose: purpose(configId),
             // eslint-disable-next-line camelcase
             input_descriptors: inputDescriptors(configId),
+            // eslint-disable-next-line camelcase
+            same_device: sameDevice || undefined,
+            // eslint-disable-next-line camelcase
+            redirect_uri: sameDevice ? `${window.location.origin}/verifier/complete` : undefined,
         };
         mut.mutate(req);
     };
@@ -128,15 +134,20 @@ const Request = () => {
                 Credential Presentation
             </Typography>
             {processing === null &&
-                <Typography variant="body1">
-                    Start the process of verifying a credential by choosing the credential type you
-                    would like to verify. The user can then scan a QR code to present the
-                    credential.
-                </Typography>
+                <>
+                    <Typography variant="body1">
+                        Start the process of verifying a credential by choosing the credential type
+                        you would like to verify. The user can then scan a QR code to present the
+                        credential, or open the wallet directly if it is on the same device.
+                    </Typography>
+                    <SameDevice checked={sameDevice} onChange={() => setSameDevice(!sameDevice)} />
+                </>
             }
             <Grid container spacing={4}>
                 <Grid size={{ xs: 12, sm: 6 }}>
-                    {processing === "EmployeeID_JWT"
+                    {processing === "EmployeeID_JWT" && sameDevice
+                        ? <OpenWallet title="Employee ID" url={requestUrl} />
+                        : processing === "EmployeeID_JWT"
                         ? <>
                         {
                             showUrl
@@ -152,7 +163,9 @@ const Request = () => {
                     }
                 </Grid>
                 <Grid size={{ xs: 12, sm: 6 }}>
-                    {processing === "Developer_JWT"
+                    {processing === "Developer_JWT" && sameDevice
+                        ? <OpenWallet title="Developer" url={requestUrl} />
+                        : processing === "Developer_JWT"
                         ? <>
                         {
                             showUrl
@@ -194,4 +207,14 @@ const QrOrUrl = (props: { checked: boolean; onChange: () => void }) => {
     );
 }
 
+const SameDevice = (props: { checked: boolean; onChange: () => void }) => {
+    return (
+        <Stack direction="row" spacing={1} sx={{ alignItems: "center" }}>
+            <Typography variant="body2">Wallet on another device</Typography>
+            <Switch checked={props.checked} onChange={props.onChange} />
+            <Typography variant="body2">Wallet on this device</Typography>
+        </Stack>
+    );
+}
+
 export default Request;
\ No newline at end of file
@@ -1,5 +1,7 @@
 import { stdHeaders, svcUrl } from "./index";
-import { ErrorResponse, GenerateRequest, GenerateRequestResponse } from "../types/generated";
+import {
+    ErrorResponse, GenerateRequest, GenerateRequestResponse, PresentationResult
+} from "../types/generated";
 
 // Create a credential request.
 export const createRequest = async (req: GenerateRequest)
@@ -14,3 +16,17 @@ export const createRequest = async (req: GenerateRequest)
     const result = await response.json();
     return result;
 };
+
+// Get the result of a same-device presentation using the response code the
+// wallet redirected back with.
+export const presentationResult = async (responseCode: string)
+    : Promise<PresentationResult | ErrorResponse> => {
+
+    const url = `${svcUrl}/presentation_result/${encodeURIComponent(responseCode)}`;
+    const response = await fetch(url, {
+        method: "GET",
+        headers: { ...stdHeaders },
+    });
+    const result = await response.json();
+    return result;
+};
@@ -10,6 +10,12 @@ export interface CreateOfferRequest {
 	subject_id: string;
 	/** The identifier of the type of credential to be issued. */
 	credential_configuration_id: string;
+	/**
+	 * Identifiers of further credentials to offer alongside
+	 * `credential_configuration_id`. The wallet can request them all at once
+	 * from the batch credential endpoint.
+	 */
+	additional_configuration_ids?: string[];
 	/** Type of authorization grant to include in the offer. */
 	grant_type: string;
 	/**
@@ -17,6 +23,11 @@ export interface CreateOfferRequest {
 	 * offer is the person accepting the credential.
 	 */
 	tx_code_required: boolean;
+	/**
+	 * Pass the offer by reference. The QR code will contain a
+	 * `credential_offer_uri` the wallet uses to fetch the offer.
+	 */
+	by_reference?: boolean;
 }
 
 /** Create offer response. */
@@ -84,13 +95,48 @@ export interface GenerateRequest {
 	purpose: string;
 	/** Input Descriptors describe the information required from the holder. */
 	input_descriptors: GenerateInputDescriptor[];
+	/**
+	 * Pass the presentation definition by reference. The request object will
+	 * contain a `presentation_definition_uri` instead of the definition.
+	 */
+	by_reference?: boolean;
+	/**
+	 * Create a request for a wallet on the same device as the verifier's web
+	 * page. The request object is passed by value in a link that opens the
+	 * wallet instead of a request URI.
+	 */
+	same_device?: boolean;
+	/**
+	 * Where to send the user's browser once the wallet has presented. Only
+	 * used for same-device requests. A `response_code` query parameter is
+	 * added that can be exchanged for the result of the presentation.
+	 */
+	redirect_uri?: string;
+	/**
+	 * Sign the request object with the verifier's test X.509 certificate
+	 * chain instead of its DID. The verifier is identified by an
+	 * `x509_san_dns` client ID. Not supported for same-device requests.
+	 */
+	x509?: boolean;
 }
 
 /** Create authorization request response. */
 export interface GenerateRequestResponse {
-	/** URI to the authorization request. */
+	/**
+	 * URI to the authorization request. For same-device requests, a link that
+	 * opens the wallet with the request object passed by value.
+	 */
 	request_uri: string;
 	/** QR code for the request URI. */
 	qr_code: string;
 }
 
+/**
+ * Result of a same-device presentation, retrieved by the verifier's web page
+ * using the response code it was redirected with.
+ */
+export interface PresentationResult {
+	/** Whether the wallet's presentation was verified. */
+	verified: boolean;
+}
+
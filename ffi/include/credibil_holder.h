@@ -0,0 +1,78 @@
+/*
+ * C API for the Credibil holder agent SDK.
+ *
+ * Build `credibil-holder-ffi` with the `capi` feature and link against the
+ * resulting static or dynamic library. See `src/capi.rs` for documentation.
+ *
+ * Sessions are opaque handles. Functions return a status code and exchange
+ * data as JSON in library-owned buffers, which must be released with
+ * `holder_buffer_free`. On error, the output buffer holds a UTF-8 message.
+ */
+
+#ifndef CREDIBIL_HOLDER_H
+#define CREDIBIL_HOLDER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define HOLDER_OK 0
+#define HOLDER_INVALID_INPUT 1
+#define HOLDER_INVALID_STATE 2
+#define HOLDER_FAILED 3
+
+typedef struct HolderIssuanceSession HolderIssuanceSession;
+typedef struct HolderPresentationSession HolderPresentationSession;
+
+typedef struct HolderBuffer {
+    uint8_t *data;
+    size_t len;
+} HolderBuffer;
+
+/* Callbacks write their output using `holder_buffer_write`. */
+typedef struct HolderSigner {
+    void *context;
+    int32_t algorithm; /* 0 = EdDSA, 1 = ES256K */
+    int32_t (*sign)(void *context, const uint8_t *msg, size_t len, HolderBuffer *out);
+    int32_t (*verifying_key)(void *context, HolderBuffer *out);
+    int32_t (*verification_method)(void *context, HolderBuffer *out);
+} HolderSigner;
+
+typedef struct HolderResolver {
+    void *context;
+    int32_t (*resolve)(void *context, const uint8_t *url, size_t len, HolderBuffer *out);
+} HolderResolver;
+
+void holder_buffer_free(HolderBuffer buffer);
+void holder_buffer_write(HolderBuffer *out, const uint8_t *data, size_t len);
+
+int32_t holder_issuance_new(const uint8_t *input, size_t len,
+                            const HolderIssuanceSession **session, HolderBuffer *out);
+int32_t holder_issuance_restore(const uint8_t *snapshot, size_t len,
+                                const HolderIssuanceSession **session, HolderBuffer *out);
+int32_t holder_issuance_call(const HolderIssuanceSession *session, const char *method,
+                             const uint8_t *input, size_t len, const HolderSigner *signer,
+                             const HolderResolver *resolver, HolderBuffer *out);
+void holder_issuance_free(const HolderIssuanceSession *session);
+
+int32_t holder_presentation_new(const uint8_t *request, size_t len,
+                                const HolderPresentationSession **session, HolderBuffer *out);
+int32_t holder_presentation_restore(const uint8_t *snapshot, size_t len,
+                                    const HolderPresentationSession **session,
+                                    HolderBuffer *out);
+int32_t holder_presentation_call(const HolderPresentationSession *session, const char *method,
+                                 const uint8_t *input, size_t len, const HolderSigner *signer,
+                                 HolderBuffer *out);
+void holder_presentation_free(const HolderPresentationSession *session);
+
+int32_t holder_decode_request_object(const uint8_t *jwt, size_t len,
+                                     const HolderResolver *resolver, HolderBuffer *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CREDIBIL_HOLDER_H */
@@ -0,0 +1,5 @@
+//! Generate foreign language bindings for the library.
+
+fn main() {
+    uniffi::uniffi_bindgen_main();
+}
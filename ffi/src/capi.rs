@@ -0,0 +1,579 @@
+//! # C API
+//!
+//! A stable `extern "C"` facade over the session objects for environments
+//! without `UniFFI` support (Flutter via `dart:ffi`, C++ middleware, etc.).
+//!
+//! Sessions are exposed as opaque handles. Every function returns a status
+//! code and exchanges data as JSON in byte buffers. All memory returned to the
+//! caller is owned by the library and must be released with
+//! `holder_buffer_free` (buffers) or the relevant `*_free` function (handles).
+//!
+//! Signing and DID resolution are provided by the caller as tables of
+//! callbacks. Callbacks write their output using `holder_buffer_write` so that
+//! memory is never freed across allocators. Async SDK operations are driven to
+//! completion on the calling thread, so callbacks are invoked on that thread.
+//!
+//! See `include/credibil_holder.h` for the C declarations.
+
+use std::ffi::{CStr, c_char, c_void};
+use std::ptr;
+use std::sync::Arc;
+
+use futures::executor::block_on;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::provider::{DocumentResolver, KeySigner, SigningAlgorithm};
+use crate::{HolderError, IssuanceSession, PresentationSession, decode_request_object};
+
+/// The operation succeeded.
+pub const HOLDER_OK: i32 = 0;
+/// Input could not be parsed.
+pub const HOLDER_INVALID_INPUT: i32 = 1;
+/// The operation is not valid for the current state of the flow.
+pub const HOLDER_INVALID_STATE: i32 = 2;
+/// The operation failed.
+pub const HOLDER_FAILED: i32 = 3;
+
+/// A library-owned byte buffer.
+#[repr(C)]
+#[derive(Debug)]
+pub struct HolderBuffer {
+    /// Pointer to the data, or null if empty.
+    pub data: *mut u8,
+
+    /// The length of the data in bytes.
+    pub len: usize,
+}
+
+impl HolderBuffer {
+    const fn empty() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn from_vec(data: Vec<u8>) -> Self {
+        let len = data.len();
+        if len == 0 {
+            return Self::empty();
+        }
+        let data = Box::into_raw(data.into_boxed_slice()).cast::<u8>();
+        Self { data, len }
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        if self.data.is_null() {
+            return vec![];
+        }
+        // SAFETY: non-null buffers are only created by `from_vec`.
+        unsafe { std::slice::from_raw_parts(self.data, self.len) }.to_vec()
+    }
+}
+
+/// Callbacks implemented by the caller to sign messages with the holder's key.
+///
+/// Each callback returns `HOLDER_OK` on success, writing its output to `out`
+/// using `holder_buffer_write`. On failure, an error message may be written to
+/// `out`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct HolderSigner {
+    /// Caller context passed to each callback.
+    pub context: *mut c_void,
+
+    /// The signing algorithm: 0 for `EdDSA`, 1 for `ES256K`.
+    pub algorithm: i32,
+
+    /// Sign the message.
+    pub sign: extern "C" fn(
+        context: *mut c_void,
+        msg: *const u8,
+        len: usize,
+        out: *mut HolderBuffer,
+    ) -> i32,
+
+    /// Write the verifying (public) key.
+    pub verifying_key: extern "C" fn(context: *mut c_void, out: *mut HolderBuffer) -> i32,
+
+    /// Write the verification method (UTF-8).
+    pub verification_method: extern "C" fn(context: *mut c_void, out: *mut HolderBuffer) -> i32,
+}
+
+/// Callbacks implemented by the caller to resolve DID URLs.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct HolderResolver {
+    /// Caller context passed to each callback.
+    pub context: *mut c_void,
+
+    /// Resolve the UTF-8 DID URL, writing the DID document as JSON to `out`.
+    pub resolve: extern "C" fn(
+        context: *mut c_void,
+        url: *const u8,
+        len: usize,
+        out: *mut HolderBuffer,
+    ) -> i32,
+}
+
+/// Free a buffer returned by the library.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_buffer_free(buffer: HolderBuffer) {
+    if !buffer.data.is_null() {
+        // SAFETY: non-null buffers are only created by `from_vec`.
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+}
+
+/// Copy data into a buffer. Used by callbacks to return their output.
+///
+/// # Safety
+///
+/// `out` must be the buffer passed to the callback and `data` must point to
+/// `len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_buffer_write(out: *mut HolderBuffer, data: *const u8, len: usize) {
+    if out.is_null() {
+        return;
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        let bytes = input(data, len).to_vec();
+        holder_buffer_free(ptr::replace(out, HolderBuffer::empty()));
+        *out = HolderBuffer::from_vec(bytes);
+    }
+}
+
+/// Start an issuance session.
+///
+/// `input` is a JSON object with `client_id`, `subject_id`, `issuer` (issuer
+/// metadata) and `offer` (credential offer) fields. On success, the session
+/// handle is written to `session`. On failure, an error message is written to
+/// `out`.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes and `session` and `out` must be
+/// valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_issuance_new(
+    input: *const u8, len: usize, session: *mut *const IssuanceSession, out: *mut HolderBuffer,
+) -> i32 {
+    #[derive(Deserialize)]
+    struct Start {
+        client_id: String,
+        subject_id: String,
+        issuer: serde_json::Value,
+        offer: serde_json::Value,
+    }
+
+    // SAFETY: guaranteed by the caller.
+    let result = parse::<Start>(unsafe { self::input(input, len) }).and_then(|start| {
+        IssuanceSession::new(
+            &start.client_id,
+            &start.subject_id,
+            &start.issuer.to_string(),
+            &start.offer.to_string(),
+        )
+    });
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle(result, session, out) }
+}
+
+/// Restore an issuance session from a snapshot.
+///
+/// # Safety
+///
+/// `snapshot` must point to `len` readable bytes and `session` and `out` must
+/// be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_issuance_restore(
+    snapshot: *const u8, len: usize, session: *mut *const IssuanceSession, out: *mut HolderBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let result = utf8(unsafe { input(snapshot, len) }).and_then(IssuanceSession::restore);
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle(result, session, out) }
+}
+
+/// Call a method on an issuance session.
+///
+/// `method` is the name of an `IssuanceSession` method. Arguments are passed
+/// as a JSON object in `input` and the result is written to `out` as JSON:
+///
+/// | method                | input                                     |
+/// |-----------------------|-------------------------------------------|
+/// | `snapshot`            |                                           |
+/// | `id`                  |                                           |
+/// | `offered`             |                                           |
+/// | `accept`              | `{"accepted": [...], "pin": "..."}`       |
+/// | `token_request`       |                                           |
+/// | `token`               | token response                            |
+/// | `proof`               | (requires `signer`)                       |
+/// | `credential_requests` | `{"identifiers": [...], "proof": "..."}`  |
+/// | `add_credential`      | `{"credential_configuration_id": "...", "response": {...}}` (requires `resolver`) |
+/// | `credentials`         |                                           |
+/// | `deferred`            |                                           |
+///
+/// # Safety
+///
+/// `session` must be a live handle, `method` a NUL-terminated string, `input`
+/// must point to `len` readable bytes, `signer` and `resolver` must be null or
+/// valid for the duration of the call, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_issuance_call(
+    session: *const IssuanceSession, method: *const c_char, input: *const u8, len: usize,
+    signer: *const HolderSigner, resolver: *const HolderResolver, out: *mut HolderBuffer,
+) -> i32 {
+    #[derive(Deserialize)]
+    struct Accept {
+        accepted: Option<serde_json::Value>,
+        pin: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Requests {
+        #[serde(default)]
+        identifiers: Vec<String>,
+        proof: String,
+    }
+    #[derive(Deserialize)]
+    struct Add {
+        credential_configuration_id: String,
+        response: serde_json::Value,
+    }
+
+    // SAFETY: guaranteed by the caller.
+    let (session, method, input) = unsafe { (&*session, name(method), self::input(input, len)) };
+    let result = method.and_then(|method| match method {
+        "snapshot" => session.snapshot().map(String::into_bytes),
+        "id" => session.id().map(|id| json(&id)),
+        "offered" => session.offered().map(String::into_bytes),
+        "accept" => {
+            let args = parse::<Accept>(input)?;
+            session.accept(args.accepted.map(|a| a.to_string()), args.pin).map(|()| vec![])
+        }
+        "token_request" => session.token_request().map(String::into_bytes),
+        "token" => session.token(utf8(input)?).map(|()| vec![]),
+        "proof" => {
+            // SAFETY: guaranteed by the caller.
+            let signer = unsafe { foreign_signer(signer) }?;
+            block_on(session.proof(signer)).map(|jwt| json(&jwt))
+        }
+        "credential_requests" => {
+            let args = parse::<Requests>(input)?;
+            let entries = session.credential_requests(args.identifiers, &args.proof)?;
+            let entries = entries
+                .into_iter()
+                .map(|e| {
+                    let request: serde_json::Value = serde_json::from_str(&e.request)?;
+                    Ok(serde_json::json!({
+                        "credential_configuration_id": e.credential_configuration_id,
+                        "request": request,
+                    }))
+                })
+                .collect::<Result<Vec<_>, HolderError>>()?;
+            Ok(json(&entries))
+        }
+        "add_credential" => {
+            let args = parse::<Add>(input)?;
+            // SAFETY: guaranteed by the caller.
+            let resolver = unsafe { foreign_resolver(resolver) }?;
+            block_on(session.add_credential(
+                args.credential_configuration_id,
+                args.response.to_string(),
+                resolver,
+            ))
+            .map(|()| vec![])
+        }
+        "credentials" => session.credentials().map(String::into_bytes),
+        "deferred" => session.deferred().map(|deferred| json(&deferred)),
+        _ => Err(unknown(method)),
+    });
+    // SAFETY: guaranteed by the caller.
+    unsafe { output(result, out) }
+}
+
+/// Free an issuance session handle.
+///
+/// # Safety
+///
+/// `session` must be a handle returned by this library and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_issuance_free(session: *const IssuanceSession) {
+    if !session.is_null() {
+        // SAFETY: handles are only created by `Arc::into_raw`.
+        drop(unsafe { Arc::from_raw(session) });
+    }
+}
+
+/// Start a presentation session from a request object (JSON).
+///
+/// # Safety
+///
+/// `request` must point to `len` readable bytes and `session` and `out` must
+/// be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_presentation_new(
+    request: *const u8, len: usize, session: *mut *const PresentationSession,
+    out: *mut HolderBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let result = utf8(unsafe { input(request, len) }).and_then(PresentationSession::new);
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle(result, session, out) }
+}
+
+/// Restore a presentation session from a snapshot.
+///
+/// # Safety
+///
+/// `snapshot` must point to `len` readable bytes and `session` and `out` must
+/// be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_presentation_restore(
+    snapshot: *const u8, len: usize, session: *mut *const PresentationSession,
+    out: *mut HolderBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let result = utf8(unsafe { input(snapshot, len) }).and_then(PresentationSession::restore);
+    // SAFETY: guaranteed by the caller.
+    unsafe { handle(result, session, out) }
+}
+
+/// Call a method on a presentation session.
+///
+/// `method` is the name of a `PresentationSession` method. Arguments are passed
+/// as JSON in `input` and the result is written to `out` as JSON:
+///
+/// | method      | input                                  |
+/// |-------------|----------------------------------------|
+/// | `snapshot`  |                                        |
+/// | `id`        |                                        |
+/// | `filter`    |                                        |
+/// | `authorize` | array of credentials                   |
+/// | `response`  | (requires `signer`)                    |
+///
+/// # Safety
+///
+/// `session` must be a live handle, `method` a NUL-terminated string, `input`
+/// must point to `len` readable bytes, `signer` must be null or valid for the
+/// duration of the call, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_presentation_call(
+    session: *const PresentationSession, method: *const c_char, input: *const u8, len: usize,
+    signer: *const HolderSigner, out: *mut HolderBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let (session, method, input) = unsafe { (&*session, name(method), self::input(input, len)) };
+    let result = method.and_then(|method| match method {
+        "snapshot" => session.snapshot().map(String::into_bytes),
+        "id" => session.id().map(|id| json(&id)),
+        "filter" => session.filter().map(String::into_bytes),
+        "authorize" => session.authorize(utf8(input)?).map(|()| vec![]),
+        "response" => {
+            // SAFETY: guaranteed by the caller.
+            let signer = unsafe { foreign_signer(signer) }?;
+            let response = block_on(session.response(signer))?;
+            let request: serde_json::Value = serde_json::from_str(&response.request)?;
+            Ok(json(&serde_json::json!({"request": request, "uri": response.uri})))
+        }
+        _ => Err(unknown(method)),
+    });
+    // SAFETY: guaranteed by the caller.
+    unsafe { output(result, out) }
+}
+
+/// Free a presentation session handle.
+///
+/// # Safety
+///
+/// `session` must be a handle returned by this library and not already freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_presentation_free(session: *const PresentationSession) {
+    if !session.is_null() {
+        // SAFETY: handles are only created by `Arc::into_raw`.
+        drop(unsafe { Arc::from_raw(session) });
+    }
+}
+
+/// Decode and verify a request object JWT, writing the request object as JSON
+/// to `out`.
+///
+/// # Safety
+///
+/// `jwt` must point to `len` readable bytes, `resolver` must be valid for the
+/// duration of the call, and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn holder_decode_request_object(
+    jwt: *const u8, len: usize, resolver: *const HolderResolver, out: *mut HolderBuffer,
+) -> i32 {
+    // SAFETY: guaranteed by the caller.
+    let result = utf8(unsafe { input(jwt, len) }).and_then(|jwt| {
+        // SAFETY: guaranteed by the caller.
+        let resolver = unsafe { foreign_resolver(resolver) }?;
+        block_on(decode_request_object(jwt.to_string(), resolver)).map(String::into_bytes)
+    });
+    // SAFETY: guaranteed by the caller.
+    unsafe { output(result, out) }
+}
+
+// Adapts C signer callbacks to the `KeySigner` foreign trait.
+struct CSigner(HolderSigner);
+
+// SAFETY: callbacks are only invoked on the thread making the C API call.
+unsafe impl Send for CSigner {}
+// SAFETY: as above.
+unsafe impl Sync for CSigner {}
+
+impl KeySigner for CSigner {
+    fn sign(&self, msg: Vec<u8>) -> Result<Vec<u8>, HolderError> {
+        callback(|out| (self.0.sign)(self.0.context, msg.as_ptr(), msg.len(), out))
+    }
+
+    fn verifying_key(&self) -> Result<Vec<u8>, HolderError> {
+        callback(|out| (self.0.verifying_key)(self.0.context, out))
+    }
+
+    fn algorithm(&self) -> SigningAlgorithm {
+        if self.0.algorithm == 1 { SigningAlgorithm::Es256k } else { SigningAlgorithm::EdDsa }
+    }
+
+    fn verification_method(&self) -> Result<String, HolderError> {
+        let bytes = callback(|out| (self.0.verification_method)(self.0.context, out))?;
+        Ok(utf8(&bytes)?.to_string())
+    }
+}
+
+// Adapts C resolver callbacks to the `DocumentResolver` foreign trait.
+struct CResolver(HolderResolver);
+
+// SAFETY: callbacks are only invoked on the thread making the C API call.
+unsafe impl Send for CResolver {}
+// SAFETY: as above.
+unsafe impl Sync for CResolver {}
+
+impl DocumentResolver for CResolver {
+    fn resolve(&self, url: String) -> Result<String, HolderError> {
+        let bytes = callback(|out| (self.0.resolve)(self.0.context, url.as_ptr(), url.len(), out))?;
+        Ok(utf8(&bytes)?.to_string())
+    }
+}
+
+// Invoke a callback, collecting its output.
+fn callback(f: impl FnOnce(*mut HolderBuffer) -> i32) -> Result<Vec<u8>, HolderError> {
+    let mut out = HolderBuffer::empty();
+    let status = f(&raw mut out);
+    let bytes = out.to_vec();
+    // SAFETY: `out` was written by `holder_buffer_write`, or is empty.
+    unsafe { holder_buffer_free(out) };
+    if status != HOLDER_OK {
+        return Err(HolderError::Failed {
+            message: String::from_utf8_lossy(&bytes).into_owned(),
+        });
+    }
+    Ok(bytes)
+}
+
+unsafe fn foreign_signer(signer: *const HolderSigner) -> Result<Arc<dyn KeySigner>, HolderError> {
+    if signer.is_null() {
+        return Err(HolderError::InvalidInput {
+            message: "a signer is required".into(),
+        });
+    }
+    // SAFETY: guaranteed by the caller.
+    Ok(Arc::new(CSigner(unsafe { *signer })))
+}
+
+unsafe fn foreign_resolver(
+    resolver: *const HolderResolver,
+) -> Result<Arc<dyn DocumentResolver>, HolderError> {
+    if resolver.is_null() {
+        return Err(HolderError::InvalidInput {
+            message: "a resolver is required".into(),
+        });
+    }
+    // SAFETY: guaranteed by the caller.
+    Ok(Arc::new(CResolver(unsafe { *resolver })))
+}
+
+const unsafe fn input<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if data.is_null() || len == 0 {
+        return &[];
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe { std::slice::from_raw_parts(data, len) }
+}
+
+unsafe fn name<'a>(method: *const c_char) -> Result<&'a str, HolderError> {
+    if method.is_null() {
+        return Err(HolderError::InvalidInput {
+            message: "method is required".into(),
+        });
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe { CStr::from_ptr(method) }.to_str().map_err(|e| HolderError::InvalidInput {
+        message: e.to_string(),
+    })
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, HolderError> {
+    std::str::from_utf8(bytes).map_err(|e| HolderError::InvalidInput {
+        message: e.to_string(),
+    })
+}
+
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, HolderError> {
+    Ok(serde_json::from_slice(bytes)?)
+}
+
+fn json(value: &impl serde::Serialize) -> Vec<u8> {
+    serde_json::to_vec(value).unwrap_or_default()
+}
+
+fn unknown(method: &str) -> HolderError {
+    HolderError::InvalidInput {
+        message: format!("unknown method: {method}"),
+    }
+}
+
+const fn status(e: &HolderError) -> i32 {
+    match e {
+        HolderError::InvalidInput { .. } => HOLDER_INVALID_INPUT,
+        HolderError::InvalidState { .. } => HOLDER_INVALID_STATE,
+        HolderError::Failed { .. } => HOLDER_FAILED,
+    }
+}
+
+unsafe fn output(result: Result<Vec<u8>, HolderError>, out: *mut HolderBuffer) -> i32 {
+    let (code, bytes) = match result {
+        Ok(bytes) => (HOLDER_OK, bytes),
+        Err(e) => (status(&e), e.to_string().into_bytes()),
+    };
+    if !out.is_null() {
+        // SAFETY: guaranteed by the caller.
+        unsafe { *out = HolderBuffer::from_vec(bytes) };
+    }
+    code
+}
+
+unsafe fn handle<T>(
+    result: Result<Arc<T>, HolderError>, session: *mut *const T, out: *mut HolderBuffer,
+) -> i32 {
+    match result {
+        Ok(s) => {
+            if session.is_null() {
+                return HOLDER_INVALID_INPUT;
+            }
+            // SAFETY: guaranteed by the caller.
+            unsafe { *session = Arc::into_raw(s) };
+            HOLDER_OK
+        }
+        // SAFETY: guaranteed by the caller.
+        Err(e) => unsafe { output(Err(e), out) },
+    }
+}
@@ -0,0 +1,341 @@
+//! # Issuance
+//!
+//! A session object wrapping the pre-authorized, issuer-initiated issuance
+//! flow.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use credibil_holder::infosec::jose::jws::JwsBuilder;
+use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
+use credibil_holder::issuance::{
+    Accepted, AuthorizationSpec, CredentialOffer, CredentialResponse, CredentialResponseType,
+    IssuanceFlow, IssuanceFlowBuilder, Issuer, NotAccepted, PreAuthorized, TokenResponse,
+    WithOffer, WithToken, WithoutToken,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::HolderError;
+use crate::provider::{DocumentResolver, ForeignResolver, ForeignSigner, KeySigner};
+
+// The runtime equivalent of the issuance flow's typestate.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "state", content = "flow", rename_all = "snake_case")]
+enum State {
+    Offered(IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken>),
+    Accepted(IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>),
+    Authorized(IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>),
+}
+
+/// A credential request to send to the issuer's credential endpoint.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct CredentialRequestEntry {
+    /// The credential configuration ID the request is for. Pass this back to
+    /// `IssuanceSession::add_credential` with the issuer's response.
+    pub credential_configuration_id: String,
+
+    /// The credential request as JSON.
+    pub request: String,
+}
+
+/// An issuance session for a pre-authorized credential offer.
+///
+/// The session is advanced by calling its methods in order:
+///
+/// 1. `offered` to display the offer to the holder.
+/// 2. `accept` with the holder's choices and PIN (if required).
+/// 3. `token_request`, then `token` with the issuer's response.
+/// 4. `proof` and `credential_requests`, then `add_credential` with each of
+///    the issuer's responses.
+/// 5. `credentials` to get the issued credentials for storage.
+#[derive(uniffi::Object)]
+pub struct IssuanceSession {
+    state: Mutex<State>,
+}
+
+#[uniffi::export]
+impl IssuanceSession {
+    /// Start an issuance session from a credential offer.
+    ///
+    /// `issuer` is the issuer's metadata and `offer` the credential offer, both
+    /// as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata or offer cannot be parsed, or if the
+    /// offer is not pre-authorized.
+    #[uniffi::constructor]
+    pub fn new(
+        client_id: &str, subject_id: &str, issuer: &str, offer: &str,
+    ) -> Result<Arc<Self>, HolderError> {
+        let issuer: Issuer = serde_json::from_str(issuer)?;
+        let offer: CredentialOffer = serde_json::from_str(offer)?;
+        let Some(grant) = offer.pre_authorized_code() else {
+            return Err(HolderError::InvalidInput {
+                message: "offer does not contain a pre-authorized code grant".into(),
+            });
+        };
+        let flow = IssuanceFlowBuilder::new(client_id, issuer)
+            .subject_id(subject_id)
+            .pre_authorized(offer, grant);
+        Ok(Arc::new(Self {
+            state: Mutex::new(State::Offered(flow)),
+        }))
+    }
+
+    /// Restore a session from a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot cannot be parsed.
+    #[uniffi::constructor]
+    pub fn restore(snapshot: &str) -> Result<Arc<Self>, HolderError> {
+        Ok(Arc::new(Self {
+            state: Mutex::new(serde_json::from_str(snapshot)?),
+        }))
+    }
+
+    /// Take a snapshot of the session that can be stored and later passed to
+    /// `restore` to resume the flow.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session cannot be serialized.
+    pub fn snapshot(&self) -> Result<String, HolderError> {
+        let state = self.lock()?;
+        serde_json::to_string(&*state).map_err(|e| HolderError::Failed {
+            message: e.to_string(),
+        })
+    }
+
+    /// The ID of the issuance flow.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is unusable.
+    pub fn id(&self) -> Result<String, HolderError> {
+        Ok(match &*self.lock()? {
+            State::Offered(flow) => flow.id(),
+            State::Accepted(flow) => flow.id(),
+            State::Authorized(flow) => flow.id(),
+        })
+    }
+
+    /// The credential configurations on offer, as JSON, keyed by credential
+    /// configuration ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is unusable.
+    pub fn offered(&self) -> Result<String, HolderError> {
+        let offered = match &*self.lock()? {
+            State::Offered(flow) => flow.offered(),
+            State::Accepted(flow) => flow.offered(),
+            State::Authorized(flow) => flow.offered(),
+        };
+        Ok(serde_json::to_string(&offered)?)
+    }
+
+    /// Accept the offer.
+    ///
+    /// `accepted` is an optional JSON array of `AuthorizationSpec`s narrowing
+    /// the credentials and claims accepted. If `None`, everything on offer is
+    /// accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offer has already been accepted or `accepted`
+    /// cannot be parsed.
+    pub fn accept(&self, accepted: Option<String>, pin: Option<String>) -> Result<(), HolderError> {
+        let accepted: Option<Vec<AuthorizationSpec>> =
+            accepted.map(|a| serde_json::from_str(&a)).transpose()?;
+        let mut state = self.lock()?;
+        let State::Offered(flow) = &*state else {
+            return Err(HolderError::state("offer has already been accepted"));
+        };
+        *state = State::Accepted(flow.clone().accept(&accepted, pin));
+        drop(state);
+        Ok(())
+    }
+
+    /// The token request to send to the issuer's token endpoint, as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offer has not been accepted or a token has
+    /// already been received.
+    pub fn token_request(&self) -> Result<String, HolderError> {
+        let State::Accepted(flow) = &*self.lock()? else {
+            return Err(HolderError::state("token request requires an accepted offer"));
+        };
+        Ok(serde_json::to_string(&flow.token_request())?)
+    }
+
+    /// Advance the flow with the issuer's token response (JSON).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the flow is not waiting for a token or the response
+    /// cannot be parsed.
+    pub fn token(&self, response: &str) -> Result<(), HolderError> {
+        let token: TokenResponse = serde_json::from_str(response)?;
+        let mut state = self.lock()?;
+        let State::Accepted(flow) = &*state else {
+            return Err(HolderError::state("flow is not waiting for a token"));
+        };
+        *state = State::Authorized(flow.clone().token(token));
+        drop(state);
+        Ok(())
+    }
+
+    /// Create a proof of possession of the holder's key, signed using the
+    /// provided signer, for inclusion in credential requests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the flow does not have a token or signing fails.
+    pub async fn proof(&self, signer: Arc<dyn KeySigner>) -> Result<String, HolderError> {
+        let claims = {
+            let State::Authorized(flow) = &*self.lock()? else {
+                return Err(HolderError::state("proof requires an access token"));
+            };
+            flow.proof()
+        };
+        let jws = JwsBuilder::new()
+            .jwt_type(Type::Openid4VciProofJwt)
+            .payload(claims)
+            .add_signer(&ForeignSigner(signer))
+            .build()
+            .await?;
+        Ok(jws.encode()?)
+    }
+
+    /// Create credential requests for the specified credential identifiers
+    /// using the proof from `proof`.
+    ///
+    /// If `identifiers` is empty, requests are created for every credential
+    /// identifier authorized by the issuer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the flow does not have a token.
+    pub fn credential_requests(
+        &self, identifiers: Vec<String>, proof: &str,
+    ) -> Result<Vec<CredentialRequestEntry>, HolderError> {
+        let State::Authorized(flow) = &*self.lock()? else {
+            return Err(HolderError::state("credential requests require an access token"));
+        };
+        let identifiers = if identifiers.is_empty() {
+            flow.get_token()
+                .authorization_details
+                .iter()
+                .flatten()
+                .flat_map(|auth| auth.credential_identifiers.clone())
+                .collect()
+        } else {
+            identifiers
+        };
+
+        let mut entries = vec![];
+        for (cfg_id, request) in flow.credential_requests(&identifiers, proof) {
+            entries.push(CredentialRequestEntry {
+                credential_configuration_id: cfg_id,
+                request: serde_json::to_string(&request)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Advance the flow with the issuer's credential response (JSON).
+    ///
+    /// Issued credentials are verified using the resolver and added to the
+    /// session. Deferred transactions are recorded for later retrieval.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the flow does not have a token, the response cannot
+    /// be parsed, or a credential fails verification.
+    pub async fn add_credential(
+        &self, credential_configuration_id: String, response: String,
+        resolver: Arc<dyn DocumentResolver>,
+    ) -> Result<(), HolderError> {
+        let response: CredentialResponse = serde_json::from_str(&response)?;
+        let resolver = ForeignResolver(resolver);
+
+        let vc_kinds = match response.response {
+            CredentialResponseType::Credential(vc_kind) => vec![vc_kind],
+            CredentialResponseType::Credentials(vc_kinds) => vc_kinds,
+            CredentialResponseType::TransactionId(tx_id) => {
+                let mut state = self.lock()?;
+                let State::Authorized(flow) = &mut *state else {
+                    return Err(HolderError::state("credentials require an access token"));
+                };
+                flow.add_deferred(&tx_id, &credential_configuration_id);
+                drop(state);
+                return Ok(());
+            }
+        };
+
+        let mut verified = vec![];
+        for vc_kind in vc_kinds {
+            let Payload::Vc { vc, issued_at } =
+                proof::verify(Verify::Vc(&vc_kind), resolver.clone()).await?
+            else {
+                return Err(HolderError::InvalidInput {
+                    message: "expected a verifiable credential".into(),
+                });
+            };
+            verified.push((vc, vc_kind, issued_at));
+        }
+
+        let mut state = self.lock()?;
+        let State::Authorized(flow) = &mut *state else {
+            return Err(HolderError::state("credentials require an access token"));
+        };
+        for (vc, vc_kind, issued_at) in verified {
+            flow.add_credential(
+                &vc,
+                &vc_kind,
+                &issued_at,
+                &credential_configuration_id,
+                None,
+                None,
+            )?;
+        }
+        drop(state);
+        Ok(())
+    }
+
+    /// The credentials received so far, as a JSON array, ready to be saved to
+    /// the wallet's storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is unusable.
+    pub fn credentials(&self) -> Result<String, HolderError> {
+        let credentials = match &*self.lock()? {
+            State::Authorized(flow) => flow.credentials(),
+            State::Offered(_) | State::Accepted(_) => vec![],
+        };
+        Ok(serde_json::to_string(&credentials)?)
+    }
+
+    /// Pending deferred transaction IDs, mapped to the credential
+    /// configuration ID they were requested for.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is unusable.
+    pub fn deferred(&self) -> Result<HashMap<String, String>, HolderError> {
+        Ok(match &*self.lock()? {
+            State::Authorized(flow) => flow.deferred(),
+            State::Offered(_) | State::Accepted(_) => HashMap::new(),
+        })
+    }
+}
+
+impl IssuanceSession {
+    fn lock(&self) -> Result<MutexGuard<'_, State>, HolderError> {
+        self.state.lock().map_err(|_| HolderError::state("session lock is poisoned"))
+    }
+}
@@ -0,0 +1,109 @@
+//! # Credibil Holder FFI
+//!
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/) bindings for the
+//! `credibil-holder` SDK so Kotlin and Swift wallets can drive issuance and
+//! presentation flows directly instead of re-implementing the state machines.
+//!
+//! The typestate flows in the core crate cannot be expressed in a foreign
+//! language, so this crate wraps them in session objects that track the
+//! current state at runtime. The API surface is deliberately simple:
+//!
+//! * Start a flow by constructing a session.
+//! * Advance the flow by passing in responses received from the issuer or
+//!   verifier. Requests and responses cross the FFI boundary as JSON strings
+//!   so the wallet can send them using its own HTTP stack.
+//! * Snapshot a session to a string and restore it later to resume a flow
+//!   (for example, after the app has been suspended).
+//!
+//! Key management and DID resolution remain the responsibility of the wallet,
+//! which implements the `KeySigner` and `DocumentResolver` foreign traits.
+//!
+//! An optional C ABI over the same sessions is available in the [`capi`]
+//! module by enabling the `capi` feature.
+//!
+//! Generate bindings with the bundled `uniffi-bindgen` binary:
+//!
+//! ```sh
+//! cargo build -p credibil-holder-ffi --release
+//! cargo run -p credibil-holder-ffi --features cli --bin uniffi-bindgen -- \
+//!     generate --library target/release/libcredibil_holder_ffi.so \
+//!     --language kotlin --out-dir out
+//! ```
+
+#[cfg(feature = "capi")]
+pub mod capi;
+mod issuance;
+mod presentation;
+mod provider;
+
+use std::fmt::{self, Display};
+
+pub use issuance::{CredentialRequestEntry, IssuanceSession};
+pub use presentation::{PresentationResponse, PresentationSession, decode_request_object};
+pub use provider::{DocumentResolver, KeySigner, SigningAlgorithm};
+
+uniffi::setup_scaffolding!();
+
+/// Errors returned across the FFI boundary.
+#[derive(Debug, uniffi::Error)]
+pub enum HolderError {
+    /// Input passed to the SDK (typically JSON) could not be parsed.
+    InvalidInput {
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The operation is not valid for the current state of the flow.
+    InvalidState {
+        /// A description of the problem.
+        message: String,
+    },
+
+    /// The SDK, or a foreign callback, failed to complete the operation.
+    Failed {
+        /// A description of the problem.
+        message: String,
+    },
+}
+
+impl HolderError {
+    pub(crate) fn state(message: impl Into<String>) -> Self {
+        Self::InvalidState {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for HolderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidInput { message } => write!(f, "invalid input: {message}"),
+            Self::InvalidState { message } => write!(f, "invalid state: {message}"),
+            Self::Failed { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for HolderError {}
+
+impl From<anyhow::Error> for HolderError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Failed {
+            message: format!("{e:#}"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for HolderError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::InvalidInput {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<uniffi::UnexpectedUniFFICallbackError> for HolderError {
+    fn from(e: uniffi::UnexpectedUniFFICallbackError) -> Self {
+        Self::Failed { message: e.reason }
+    }
+}
@@ -0,0 +1,180 @@
+//! # Presentation
+//!
+//! A session object wrapping the presentation flow.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::proof::{self, W3cFormat};
+use credibil_holder::presentation::{
+    Authorized, NotAuthorized, PresentationFlow, RequestObject, parse_request_object_jwt,
+};
+use credibil_holder::provider::Signer;
+use serde::{Deserialize, Serialize};
+
+use crate::HolderError;
+use crate::provider::{DocumentResolver, ForeignResolver, ForeignSigner, KeySigner};
+
+// The runtime equivalent of the presentation flow's typestate.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "state", content = "flow", rename_all = "snake_case")]
+enum State {
+    Requested(PresentationFlow<NotAuthorized>),
+    Authorized(PresentationFlow<Authorized>),
+}
+
+/// The presentation response to send to the verifier.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct PresentationResponse {
+    /// The response request as JSON.
+    pub request: String,
+
+    /// The verifier's response URI, if provided in the request object.
+    pub uri: Option<String>,
+}
+
+/// Decode and verify a request object JWT (as returned from a verifier's
+/// request URI), returning the request object as JSON.
+///
+/// # Errors
+///
+/// Returns an error if the JWT cannot be decoded or verified.
+#[uniffi::export]
+pub async fn decode_request_object(
+    jwt: String, resolver: Arc<dyn DocumentResolver>,
+) -> Result<String, HolderError> {
+    let request = parse_request_object_jwt(&jwt, ForeignResolver(resolver)).await?;
+    Ok(serde_json::to_string(&request)?)
+}
+
+/// A presentation session for a verifier's request object.
+///
+/// The session is advanced by calling its methods in order:
+///
+/// 1. `filter` to find matching credentials in the wallet's storage.
+/// 2. `authorize` with the credentials the holder agrees to present.
+/// 3. `response` to create the presentation response to send to the
+///    verifier.
+#[derive(uniffi::Object)]
+pub struct PresentationSession {
+    state: Mutex<State>,
+}
+
+#[uniffi::export]
+impl PresentationSession {
+    /// Start a presentation session from a request object (JSON).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request object cannot be parsed or does not
+    /// contain a presentation definition.
+    #[uniffi::constructor]
+    pub fn new(request: &str) -> Result<Arc<Self>, HolderError> {
+        let request: RequestObject = serde_json::from_str(request)?;
+        let flow = PresentationFlow::new(request)?;
+        Ok(Arc::new(Self {
+            state: Mutex::new(State::Requested(flow)),
+        }))
+    }
+
+    /// Restore a session from a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot cannot be parsed.
+    #[uniffi::constructor]
+    pub fn restore(snapshot: &str) -> Result<Arc<Self>, HolderError> {
+        Ok(Arc::new(Self {
+            state: Mutex::new(serde_json::from_str(snapshot)?),
+        }))
+    }
+
+    /// Take a snapshot of the session that can be stored and later passed to
+    /// `restore` to resume the flow.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session cannot be serialized.
+    pub fn snapshot(&self) -> Result<String, HolderError> {
+        let state = self.lock()?;
+        serde_json::to_string(&*state).map_err(|e| HolderError::Failed {
+            message: e.to_string(),
+        })
+    }
+
+    /// The ID of the presentation flow.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session is unusable.
+    pub fn id(&self) -> Result<String, HolderError> {
+        Ok(match &*self.lock()? {
+            State::Requested(flow) => flow.id(),
+            State::Authorized(flow) => flow.id(),
+        })
+    }
+
+    /// The constraints (JSON) used to find credentials in the wallet that
+    /// satisfy the verifier's request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the presentation has already been authorized.
+    pub fn filter(&self) -> Result<String, HolderError> {
+        let State::Requested(flow) = &*self.lock()? else {
+            return Err(HolderError::state("presentation has already been authorized"));
+        };
+        Ok(serde_json::to_string(&flow.filter()?)?)
+    }
+
+    /// Authorize the presentation of the specified credentials (JSON array).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the presentation has already been authorized or
+    /// the credentials cannot be parsed.
+    pub fn authorize(&self, credentials: &str) -> Result<(), HolderError> {
+        let credentials: Vec<Credential> = serde_json::from_str(credentials)?;
+        let mut state = self.lock()?;
+        let State::Requested(flow) = &*state else {
+            return Err(HolderError::state("presentation has already been authorized"));
+        };
+        *state = State::Authorized(flow.clone().authorize(&credentials));
+        drop(state);
+        Ok(())
+    }
+
+    /// Create the presentation, signed using the provided signer, and the
+    /// response to send to the verifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the presentation has not been authorized or
+    /// signing fails.
+    pub async fn response(
+        &self, signer: Arc<dyn KeySigner>,
+    ) -> Result<PresentationResponse, HolderError> {
+        let flow = {
+            let State::Authorized(flow) = &*self.lock()? else {
+                return Err(HolderError::state("presentation has not been authorized"));
+            };
+            flow.clone()
+        };
+        let signer = ForeignSigner(signer);
+        let kid = signer.verification_method().await?;
+        let payload = flow.payload(&kid)?;
+        let jwt = proof::create(W3cFormat::JwtVcJson, payload, &signer).await?;
+        let (request, uri) = flow.create_response_request(&jwt);
+
+        Ok(PresentationResponse {
+            request: serde_json::to_string(&request)?,
+            uri,
+        })
+    }
+}
+
+impl PresentationSession {
+    fn lock(&self) -> Result<MutexGuard<'_, State>, HolderError> {
+        self.state.lock().map_err(|_| HolderError::state("session lock is poisoned"))
+    }
+}
@@ -0,0 +1,103 @@
+//! # Provider
+//!
+//! Foreign traits implemented by the wallet to provide signing and DID
+//! resolution, and adapters that allow them to be used as `credibil-holder`
+//! providers.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use credibil_holder::provider::{Algorithm, DidResolver, Document, Result, Signer};
+
+use crate::HolderError;
+
+/// Signature algorithms supported by the SDK.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum SigningAlgorithm {
+    /// `EdDSA` using the Ed25519 curve.
+    EdDsa,
+
+    /// `ES256K` using the secp256k1 curve.
+    Es256k,
+}
+
+/// Implemented by the wallet to sign messages with the holder's key.
+///
+/// Implementations will typically delegate to a platform key store (Android
+/// Keystore, iOS Secure Enclave, etc.).
+#[uniffi::export(with_foreign)]
+pub trait KeySigner: Send + Sync {
+    /// Sign the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be signed.
+    fn sign(&self, msg: Vec<u8>) -> Result<Vec<u8>, HolderError>;
+
+    /// The verifying (public) key of the signing key pair.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key could not be retrieved.
+    fn verifying_key(&self) -> Result<Vec<u8>, HolderError>;
+
+    /// The algorithm used to sign messages.
+    fn algorithm(&self) -> SigningAlgorithm;
+
+    /// The verification method a verifier should use to verify signatures.
+    /// This is typically a DID URL + # + verification key ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification method could not be determined.
+    fn verification_method(&self) -> Result<String, HolderError>;
+}
+
+/// Implemented by the wallet to resolve DID URLs to DID documents.
+#[uniffi::export(with_foreign)]
+pub trait DocumentResolver: Send + Sync {
+    /// Resolve the DID URL, returning the DID document as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DID URL could not be resolved.
+    fn resolve(&self, url: String) -> Result<String, HolderError>;
+}
+
+// Adapts a `KeySigner` to the SDK's `Signer` provider trait. Foreign callbacks
+// are synchronous, but provider trait methods are async by contract.
+pub struct ForeignSigner(pub Arc<dyn KeySigner>);
+
+#[allow(clippy::unused_async_trait_impl)]
+impl Signer for ForeignSigner {
+    async fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        self.0.sign(msg.to_vec()).map_err(|e| anyhow!(e))
+    }
+
+    async fn verifying_key(&self) -> Result<Vec<u8>> {
+        self.0.verifying_key().map_err(|e| anyhow!(e))
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        match self.0.algorithm() {
+            SigningAlgorithm::EdDsa => Algorithm::EdDSA,
+            SigningAlgorithm::Es256k => Algorithm::ES256K,
+        }
+    }
+
+    async fn verification_method(&self) -> Result<String> {
+        self.0.verification_method().map_err(|e| anyhow!(e))
+    }
+}
+
+// Adapts a `DocumentResolver` to the SDK's `DidResolver` provider trait.
+#[derive(Clone)]
+pub struct ForeignResolver(pub Arc<dyn DocumentResolver>);
+
+#[allow(clippy::unused_async_trait_impl)]
+impl DidResolver for ForeignResolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        let json = self.0.resolve(url.to_string()).map_err(|e| anyhow!(e))?;
+        serde_json::from_str(&json).map_err(Into::into)
+    }
+}
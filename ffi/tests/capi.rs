@@ -0,0 +1,154 @@
+//! Tests for driving an issuance session through the C API.
+
+use std::ffi::{CStr, c_void};
+use std::ptr;
+
+use credibil_holder::issuance::{OfferType, SendType, TokenRequest};
+use credibil_holder::provider::MetadataRequest;
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_holder::test_utils::store::keystore::HolderKeystore;
+use credibil_holder_ffi::IssuanceSession;
+use credibil_holder_ffi::capi::{
+    HOLDER_INVALID_INPUT, HOLDER_INVALID_STATE, HOLDER_OK, HolderBuffer, HolderSigner,
+    holder_buffer_free, holder_buffer_write, holder_issuance_call, holder_issuance_free,
+    holder_issuance_new, holder_issuance_restore,
+};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use serde_json::{Value, json};
+
+extern "C" fn sign(_: *mut c_void, msg: *const u8, len: usize, out: *mut HolderBuffer) -> i32 {
+    let msg = unsafe { std::slice::from_raw_parts(msg, len) };
+    let sig = HolderKeystore::try_sign(msg).expect("should sign");
+    unsafe { holder_buffer_write(out, sig.as_ptr(), sig.len()) };
+    HOLDER_OK
+}
+
+extern "C" fn verifying_key(_: *mut c_void, out: *mut HolderBuffer) -> i32 {
+    let key = HolderKeystore::public_key().expect("should get key");
+    unsafe { holder_buffer_write(out, key.as_ptr(), key.len()) };
+    HOLDER_OK
+}
+
+extern "C" fn verification_method(_: *mut c_void, out: *mut HolderBuffer) -> i32 {
+    let vm = HolderKeystore::verification_method();
+    unsafe { holder_buffer_write(out, vm.as_ptr(), vm.len()) };
+    HOLDER_OK
+}
+
+// Call a session method, returning the status code and output.
+fn call(
+    session: *const IssuanceSession, method: &CStr, input: &[u8], signer: *const HolderSigner,
+) -> (i32, Vec<u8>) {
+    let mut out = HolderBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+    let status = unsafe {
+        holder_issuance_call(
+            session,
+            method.as_ptr(),
+            input.as_ptr(),
+            input.len(),
+            signer,
+            ptr::null(),
+            &raw mut out,
+        )
+    };
+    let bytes = if out.data.is_null() {
+        vec![]
+    } else {
+        unsafe { std::slice::from_raw_parts(out.data, out.len) }.to_vec()
+    };
+    unsafe { holder_buffer_free(out) };
+    (status, bytes)
+}
+
+// Start a session, snapshot and restore it, and sign a proof using callbacks.
+#[tokio::test]
+async fn issuance() {
+    let issuer_provider = issuer::Provider::new();
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    let metadata_request = MetadataRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        languages: None,
+    };
+    let metadata = credibil_vc::issuer::metadata(issuer_provider.clone(), metadata_request)
+        .await
+        .expect("should get metadata");
+
+    // Start the session.
+    let start = serde_json::to_vec(&json!({
+        "client_id": CLIENT_ID,
+        "subject_id": NORMAL_USER,
+        "issuer": metadata.credential_issuer,
+        "offer": offer,
+    }))
+    .expect("should serialize");
+    let mut session: *const IssuanceSession = ptr::null();
+    let mut out = HolderBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+    let status =
+        unsafe { holder_issuance_new(start.as_ptr(), start.len(), &raw mut session, &raw mut out) };
+    assert_eq!(status, HOLDER_OK);
+
+    let accept = serde_json::to_vec(&json!({"pin": offer_resp.tx_code})).expect("should serialize");
+    assert_eq!(call(session, c"accept", &accept, ptr::null()).0, HOLDER_OK);
+    assert_eq!(call(session, c"accept", &accept, ptr::null()).0, HOLDER_INVALID_STATE);
+    assert_eq!(call(session, c"unknown", &[], ptr::null()).0, HOLDER_INVALID_INPUT);
+
+    // Snapshot and restore.
+    let (status, snapshot) = call(session, c"snapshot", &[], ptr::null());
+    assert_eq!(status, HOLDER_OK);
+    unsafe { holder_issuance_free(session) };
+    let status = unsafe {
+        holder_issuance_restore(snapshot.as_ptr(), snapshot.len(), &raw mut session, &raw mut out)
+    };
+    assert_eq!(status, HOLDER_OK);
+
+    // Get a token.
+    let (status, token_request) = call(session, c"token_request", &[], ptr::null());
+    assert_eq!(status, HOLDER_OK);
+    let token_request: TokenRequest =
+        serde_json::from_slice(&token_request).expect("should parse token request");
+    let token_response =
+        credibil_vc::issuer::token(issuer_provider, token_request).await.expect("should get token");
+    let token = serde_json::to_vec(&token_response).expect("should serialize");
+    assert_eq!(call(session, c"token", &token, ptr::null()).0, HOLDER_OK);
+
+    // Sign a proof using the callbacks and create credential requests.
+    assert_eq!(call(session, c"proof", &[], ptr::null()).0, HOLDER_INVALID_INPUT);
+    let signer = HolderSigner {
+        context: ptr::null_mut(),
+        algorithm: 0,
+        sign,
+        verifying_key,
+        verification_method,
+    };
+    let (status, proof) = call(session, c"proof", &[], &raw const signer);
+    assert_eq!(status, HOLDER_OK);
+    let proof: String = serde_json::from_slice(&proof).expect("should parse proof");
+
+    let input = serde_json::to_vec(&json!({"proof": proof})).expect("should serialize");
+    let (status, requests) = call(session, c"credential_requests", &input, ptr::null());
+    assert_eq!(status, HOLDER_OK);
+    let requests: Vec<Value> = serde_json::from_slice(&requests).expect("should parse requests");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["credential_configuration_id"], "EmployeeID_JWT");
+
+    unsafe { holder_issuance_free(session) };
+}
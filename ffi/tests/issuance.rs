@@ -0,0 +1,128 @@
+//! Tests for driving a pre-authorized issuance flow through the FFI session,
+//! including snapshot and restore part way through the flow.
+
+use std::sync::Arc;
+
+use credibil_holder::credential::Credential;
+use credibil_holder::issuance::{CredentialRequest, OfferType, SendType, TokenRequest};
+use credibil_holder::provider::{MetadataRequest, Signer};
+use credibil_holder::test_utils::holder;
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_holder::test_utils::store::resolver;
+use credibil_holder_ffi::{
+    DocumentResolver, HolderError, IssuanceSession, KeySigner, SigningAlgorithm,
+};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use futures::executor::block_on;
+
+// Foreign callbacks are synchronous, so the test implementations block on the
+// async test providers.
+
+// Foreign signer backed by the test holder's keystore.
+struct TestSigner(holder::Provider);
+
+impl KeySigner for TestSigner {
+    fn sign(&self, msg: Vec<u8>) -> Result<Vec<u8>, HolderError> {
+        Ok(block_on(self.0.try_sign(&msg))?)
+    }
+
+    fn verifying_key(&self) -> Result<Vec<u8>, HolderError> {
+        Ok(block_on(self.0.verifying_key())?)
+    }
+
+    fn algorithm(&self) -> SigningAlgorithm {
+        SigningAlgorithm::EdDsa
+    }
+
+    fn verification_method(&self) -> Result<String, HolderError> {
+        Ok(block_on(self.0.verification_method())?)
+    }
+}
+
+// Foreign resolver backed by the test DID store.
+struct TestResolver;
+
+impl DocumentResolver for TestResolver {
+    fn resolve(&self, url: String) -> Result<String, HolderError> {
+        let doc = block_on(resolver::resolve_did(&url))?;
+        Ok(serde_json::to_string(&doc)?)
+    }
+}
+
+// Drive the issuance flow using JSON strings only, as a foreign wallet would.
+#[tokio::test]
+async fn preauth_session() {
+    let issuer_provider = issuer::Provider::new();
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    let metadata_request = MetadataRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        languages: None,
+    };
+    let metadata = credibil_vc::issuer::metadata(issuer_provider.clone(), metadata_request)
+        .await
+        .expect("should get metadata");
+
+    let session = IssuanceSession::new(
+        CLIENT_ID,
+        NORMAL_USER,
+        &serde_json::to_string(&metadata.credential_issuer).expect("should serialize"),
+        &serde_json::to_string(&offer).expect("should serialize"),
+    )
+    .expect("should start session");
+    session.accept(None, offer_resp.tx_code).expect("should accept offer");
+
+    // Resume the flow from a snapshot.
+    let snapshot = session.snapshot().expect("should take snapshot");
+    let session = IssuanceSession::restore(&snapshot).expect("should restore session");
+    assert!(session.accept(None, None).is_err());
+
+    let token_request: TokenRequest =
+        serde_json::from_str(&session.token_request().expect("should get token request"))
+            .expect("should parse token request");
+    let token_response = credibil_vc::issuer::token(issuer_provider.clone(), token_request)
+        .await
+        .expect("should get token");
+    session
+        .token(&serde_json::to_string(&token_response).expect("should serialize"))
+        .expect("should add token");
+
+    let signer = Arc::new(TestSigner(holder::Provider::new()));
+    let proof = session.proof(signer).await.expect("should create proof");
+    let requests = session.credential_requests(vec![], &proof).expect("should get requests");
+    assert_eq!(requests.len(), 1);
+
+    for entry in requests {
+        let request: CredentialRequest =
+            serde_json::from_str(&entry.request).expect("should parse credential request");
+        let response = Box::pin(credibil_vc::issuer::credential(issuer_provider.clone(), request))
+            .await
+            .expect("should get credential");
+        session
+            .add_credential(
+                entry.credential_configuration_id,
+                serde_json::to_string(&response).expect("should serialize"),
+                Arc::new(TestResolver),
+            )
+            .await
+            .expect("should add credential");
+    }
+
+    let credentials: Vec<Credential> =
+        serde_json::from_str(&session.credentials().expect("should get credentials"))
+            .expect("should parse credentials");
+    assert_eq!(credentials.len(), 1);
+    assert_eq!(credentials[0].issuer, CREDENTIAL_ISSUER);
+}
@@ -0,0 +1,13 @@
+//! Credential offer links are scanned from QR codes or received as deep links
+//! and so are entirely attacker-controlled.
+
+#![no_main]
+
+use credibil_holder::issuance::{OfferType, parse_offer};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|link: &str| {
+    if let Ok(OfferType::Object(offer)) = parse_offer(link) {
+        let _ = offer.pre_authorized_code();
+    }
+});
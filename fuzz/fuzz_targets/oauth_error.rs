@@ -0,0 +1,13 @@
+//! Error response bodies are returned by issuer and verifier services and are
+//! parsed before the wallet decides how to recover.
+
+#![no_main]
+
+use credibil_holder::error::OAuthError;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|body: &[u8]| {
+    if let Some(error) = OAuthError::parse(body) {
+        let _ = error.to_string();
+    }
+});
@@ -0,0 +1,42 @@
+//! Presentation definitions come from the verifier. Their constraints contain
+//! `JSONPath` expressions and filters that the wallet evaluates against stored
+//! credentials, so both parsing and matching are exercised.
+
+#![no_main]
+
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use libfuzzer_sys::fuzz_target;
+use serde_json::{Value, json};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(definition) = serde_json::from_slice::<Value>(data) else {
+        return;
+    };
+    let request = json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": definition,
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    });
+    let Ok(request_object) = serde_json::from_value::<RequestObject>(request) else {
+        return;
+    };
+    let Ok(flow) = PresentationFlow::<NotAuthorized>::new(request_object) else {
+        return;
+    };
+    if let Ok(constraints) = flow.filter() {
+        let credential = Credential {
+            type_: vec!["VerifiableCredential".into(), "EmployeeIDCredential".into()],
+            ..Credential::default()
+        };
+        let _ = constraints.satisfied(&credential);
+    }
+});
@@ -0,0 +1,15 @@
+//! Presentation requests passed by value are scanned from QR codes and so are
+//! entirely attacker-controlled.
+
+#![no_main]
+
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, parse_request_object};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|request: &str| {
+    if let Ok(Some(request_object)) = parse_request_object(request) {
+        if let Ok(flow) = PresentationFlow::<NotAuthorized>::new(request_object) {
+            let _ = flow.filter();
+        }
+    }
+});
@@ -0,0 +1,24 @@
+//! Request object JWTs are fetched from a `request_uri` supplied by the
+//! verifier. Header and payload decoding happens before (and during) key
+//! resolution, so malformed tokens must be rejected without panicking.
+
+#![no_main]
+
+use credibil_holder::presentation::parse_request_object_jwt;
+use credibil_holder::provider::{DidResolver, Document};
+use credibil_holder::test_utils::store::resolver;
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Clone)]
+struct Resolver;
+
+impl DidResolver for Resolver {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        resolver::resolve_did(url).await
+    }
+}
+
+fuzz_target!(|token: &str| {
+    let _ = block_on(parse_request_object_jwt(token, Resolver));
+});
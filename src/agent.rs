@@ -0,0 +1,1148 @@
+//! # Holder Agent
+//!
+//! The `HolderAgent` owns the wallet's providers and manages any number of
+//! simultaneous issuance and presentation flows, each identified by the flow's
+//! ID.
+//!
+//! The typestate flows in the [`crate::issuance`] and [`crate::presentation`]
+//! modules are ideal for driving a single flow, but a wallet will typically
+//! have several in progress at once (an offer waiting on a PIN, a presentation
+//! request waiting on the holder's consent, etc.). The agent tracks the state
+//! of each flow at runtime so wallets only need to keep hold of flow IDs.
+//!
+//! Flows can be persisted to the provider's `StateStore` and resumed later,
+//! for example when the wallet is restarted.
+//!
+//! When a token authorizes several credentials, the agent can request them
+//! concurrently (see [`HolderAgent::with_concurrency`]), verifying each
+//! credential as its response arrives. Use [`HolderAgent::receive_with_cancel`]
+//! to let the holder cancel a slow issuance.
+//!
+//! Reactive UIs can subscribe to [`HolderAgent::events`] to be notified as
+//! flows progress, when the holder's input is required, and when flows
+//! complete, rather than polling flow state.
+//!
+//! Credentials the issuer defers are retrieved using
+//! [`HolderAgent::poll_deferred`], which paces requests to the issuer using
+//! the `interval` it provides.
+//!
+//! Wallets holding large credentials can list candidates for a presentation
+//! as metadata using [`HolderAgent::candidates`], loading only the
+//! credentials the holder selects (see [`HolderAgent::authorize_selected`]).
+//!
+//! Issuers can also wake the wallet by push (see [`crate::push`]): pass the
+//! payload to [`HolderAgent::handle_push`] to act on it.
+//!
+//! Flows can be given an overall deadline (see [`HolderAgent::with_deadlines`])
+//! so tokens and nonces do not linger in an app that is suspended mid-flow.
+//! A flow past its deadline is replaced by a [`Flow::TimedOut`] record, and
+//! its steps return a [`FlowTimedOut`] error.
+//!
+//! Credentials re-issued by an issuer can replace the stored credentials
+//! they supersede (see [`HolderAgent::save_replacing`]).
+//!
+//! The agent enforces the host's [`Policy`] (see [`HolderAgent::with_policy`])
+//! on the credentials it is offered, and on those it matches to or authorizes
+//! for a presentation request.
+//!
+//! The agent only supports pre-authorized issuance flows. Wallets needing the
+//! authorization code flow should use [`crate::issuance::IssuanceFlow`]
+//! directly.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_channel::mpsc::{self, UnboundedSender};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use serde::{Deserialize, Serialize};
+
+use crate::bearer::Nonce;
+use crate::cancel::CancellationToken;
+use crate::credential::{Credential, CredentialMetadata, Sharing};
+use crate::error::{FlowTimedOut, NonceReused, OAuthError, Recovery, RetryLater};
+use crate::issuance::{
+    Accepted, AuthorizationSpec, CredentialOffer, CredentialResponseType, IssuanceFlow,
+    IssuanceFlowBuilder, MetadataRequest, NotAccepted, PreAuthorized, Proof, SingleProof,
+    VerifiableCredential, WithOffer, WithToken, WithoutToken, proof as vci_proof,
+};
+use crate::linkage::IssuerTrust;
+use crate::metadata::WalletMetadata;
+use crate::policy::{Policy, PolicyTarget};
+use crate::presentation::proof::{self as vp_proof, Payload};
+use crate::presentation::{
+    Authorized, NotAuthorized, PresentationFlow, PresentationTemplate, ResponseResponse,
+    StatePolicy, parse_request_object, parse_request_object_response,
+};
+use crate::provider::{
+    CredentialArchive, DidConfigurationResolver, HolderProvider, NonceCache, PushProvider,
+    StateStore,
+};
+use crate::push::{PushAction, PushNotification};
+use crate::reissue::{self, Replaced, Supersession};
+use crate::{Kind, jwt_vc};
+
+/// The state of a flow managed by the [`HolderAgent`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "state", content = "flow", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Flow {
+    /// A credential offer has been received and is waiting for the holder to
+    /// accept it.
+    Offered(IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken>),
+
+    /// The holder has accepted the offer and credentials can be requested.
+    Accepted(IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>),
+
+    /// The pre-authorized code has been exchanged for a token, but requesting
+    /// credentials did not complete. Credentials can be requested again with
+    /// the token.
+    Exchanged(IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>),
+
+    /// Credentials have been issued (or deferred) and are ready to be saved.
+    Issued(IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>),
+
+    /// A presentation request has been received and is waiting for the holder
+    /// to authorize it.
+    Requested(PresentationFlow<NotAuthorized>),
+
+    /// The holder has authorized the presentation request.
+    Authorized(PresentationFlow<Authorized>),
+
+    /// The flow did not complete before its deadline. The flow's tokens,
+    /// codes and nonces have been dropped.
+    TimedOut(TimedOut),
+}
+
+impl Flow {
+    /// The ID of the underlying flow.
+    #[must_use]
+    pub fn id(&self) -> String {
+        match self {
+            Self::Offered(flow) => flow.id(),
+            Self::Accepted(flow) => flow.id(),
+            Self::Exchanged(flow) | Self::Issued(flow) => flow.id(),
+            Self::Requested(flow) => flow.id(),
+            Self::Authorized(flow) => flow.id(),
+            Self::TimedOut(timed_out) => timed_out.id.clone(),
+        }
+    }
+
+    /// The time by which an active flow must complete, if any.
+    #[must_use]
+    pub const fn deadline(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Offered(flow) => flow.deadline(),
+            Self::Accepted(flow) => flow.deadline(),
+            Self::Exchanged(flow) | Self::Issued(flow) => flow.deadline(),
+            Self::Requested(flow) => flow.deadline(),
+            Self::Authorized(flow) => flow.deadline(),
+            Self::TimedOut(_) => None,
+        }
+    }
+}
+
+/// A flow that did not complete before its deadline.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TimedOut {
+    /// The flow ID.
+    pub id: String,
+
+    /// The deadline the flow missed.
+    pub deadline: DateTime<Utc>,
+}
+
+/// Overall deadlines for the flows managed by a [`HolderAgent`]. Flows
+/// without a deadline wait indefinitely on the holder and the issuer or
+/// verifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Deadlines {
+    /// How long an issuance flow has to complete once the holder accepts the
+    /// offer.
+    pub issuance: Option<Duration>,
+
+    /// How long a presentation flow has to complete once the request is
+    /// received.
+    pub presentation: Option<Duration>,
+}
+
+impl Deadlines {
+    // The deadline for a flow starting now, if any.
+    fn after(limit: Option<Duration>) -> Option<DateTime<Utc>> {
+        let limit = TimeDelta::from_std(limit?).unwrap_or(TimeDelta::MAX);
+        Some(Utc::now().checked_add_signed(limit).unwrap_or(DateTime::<Utc>::MAX_UTC))
+    }
+}
+
+/// Events emitted by the [`HolderAgent`] as flows progress.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum HolderEvent {
+    /// The flow is waiting on the holder.
+    InputRequired {
+        /// The flow ID.
+        id: String,
+
+        /// The input required.
+        input: Input,
+    },
+
+    /// The flow has moved on a step.
+    Progress {
+        /// The flow ID.
+        id: String,
+
+        /// The step completed.
+        step: Step,
+    },
+
+    /// The flow completed successfully and has been removed from the agent.
+    Completed {
+        /// The flow ID.
+        id: String,
+    },
+
+    /// The flow was cancelled.
+    Cancelled {
+        /// The flow ID.
+        id: String,
+    },
+
+    /// The flow passed its deadline and can no longer be advanced. It is kept
+    /// as [`Flow::TimedOut`] until cancelled.
+    TimedOut {
+        /// The flow ID.
+        id: String,
+    },
+
+    /// A step in the flow failed. The flow is left in its previous state so
+    /// the step can be retried or the flow cancelled.
+    Failed {
+        /// The flow ID.
+        id: String,
+
+        /// A description of the error.
+        error: String,
+    },
+}
+
+/// Input required from the holder to advance a flow.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Input {
+    /// The holder should accept (some or all of) the credentials on offer
+    /// using [`HolderAgent::accept`].
+    Acceptance {
+        /// Whether the issuer requires a PIN (transaction code).
+        pin_required: bool,
+    },
+
+    /// The holder should select credentials matching the verifier's request
+    /// and authorize their presentation using [`HolderAgent::authorize`].
+    Authorization,
+}
+
+/// A completed step in a flow.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Step {
+    /// The holder accepted the credential offer.
+    Accepted,
+
+    /// An access token was received from the issuer.
+    TokenReceived,
+
+    /// A credential was issued.
+    CredentialIssued {
+        /// The credential configuration ID of the issued credential.
+        credential_configuration_id: String,
+    },
+
+    /// Issuance of a credential was deferred by the issuer.
+    CredentialDeferred {
+        /// The transaction ID to use to retrieve the credential later.
+        transaction_id: String,
+    },
+
+    /// Issued credentials were saved, but deferred credentials are still
+    /// outstanding.
+    CredentialsSaved,
+
+    /// The holder authorized the presentation.
+    Authorized,
+}
+
+/// Orchestrates concurrent issuance and presentation flows using a single
+/// set of providers.
+///
+/// The agent is cheap to clone: clones share the same providers and flows.
+#[derive(Clone, Debug)]
+pub struct HolderAgent<P: HolderProvider> {
+    provider: P,
+    client_id: String,
+    flows: Arc<Mutex<HashMap<String, Arc<Flow>>>>,
+    subscribers: Arc<Mutex<Vec<UnboundedSender<HolderEvent>>>>,
+    concurrency: usize,
+    state_policy: StatePolicy,
+    presentation: PresentationTemplate,
+    deadlines: Deadlines,
+    policy: Policy,
+}
+
+// The result of a credential request made by the agent.
+enum Retrieved {
+    Issued(Vec<(VerifiableCredential, Kind<VerifiableCredential>, i64)>),
+    Deferred(String),
+}
+
+impl<P: HolderProvider> HolderAgent<P> {
+    /// Create a new agent. The `client_id` is used to identify the wallet to
+    /// issuers.
+    pub fn new(provider: P, client_id: impl Into<String>) -> Self {
+        Self {
+            provider,
+            client_id: client_id.into(),
+            flows: Arc::new(Mutex::new(HashMap::new())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            concurrency: 1,
+            state_policy: StatePolicy::default(),
+            presentation: PresentationTemplate::default(),
+            deadlines: Deadlines::default(),
+            policy: Policy::default(),
+        }
+    }
+
+    /// Set the maximum number of credential requests made concurrently when
+    /// a token authorizes several credentials. Defaults to 1 (requests are
+    /// made one at a time).
+    #[must_use]
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Set how the verifier's `state` is returned in presentation responses.
+    /// With [`StatePolicy::Required`], presentation requests without a
+    /// `state` are rejected. Defaults to [`StatePolicy::Echo`].
+    #[must_use]
+    pub const fn with_state_policy(mut self, policy: StatePolicy) -> Self {
+        self.state_policy = policy;
+        self
+    }
+
+    /// Set how the presentations built by the agent are described (see
+    /// [`PresentationTemplate`]).
+    #[must_use]
+    pub fn with_presentation(mut self, template: PresentationTemplate) -> Self {
+        self.presentation = template;
+        self
+    }
+
+    /// Set overall deadlines for the flows the agent starts. A flow past its
+    /// deadline is timed out the next time the agent is used (or when
+    /// [`HolderAgent::check_deadlines`] is called).
+    #[must_use]
+    pub const fn with_deadlines(mut self, deadlines: Deadlines) -> Self {
+        self.deadlines = deadlines;
+        self
+    }
+
+    /// Set the policy restricting the credentials the agent accepts from
+    /// issuers and presents to verifiers. Defaults to allowing all
+    /// credentials.
+    #[must_use]
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Time out the flows that have passed their deadline, returning their
+    /// IDs. Wallets can call this when the app is resumed to clear out stale
+    /// flows before rendering them.
+    pub fn check_deadlines(&self) -> Vec<String> {
+        let mut flows = self.flows.lock().unwrap_or_else(PoisonError::into_inner);
+        let timed_out = time_out(&mut flows);
+        drop(flows);
+        for id in &timed_out {
+            self.emit(&HolderEvent::TimedOut { id: id.clone() });
+        }
+        timed_out
+    }
+
+    /// The agent's provider.
+    pub const fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Subscribe to events for all flows managed by the agent. Events are
+    /// buffered until read, and the stream ends when the agent (and all its
+    /// clones) are dropped.
+    pub fn events(&self) -> impl Stream<Item = HolderEvent> + use<P> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(tx);
+        rx
+    }
+
+    /// The flow with the given ID, if it exists. The flow is shared, not
+    /// copied, and is not affected by later changes to the flow in the agent.
+    pub fn flow(&self, id: &str) -> Option<Arc<Flow>> {
+        self.flows().get(id).cloned()
+    }
+
+    /// The IDs of all flows currently managed by the agent.
+    pub fn flow_ids(&self) -> Vec<String> {
+        self.flows().keys().cloned().collect()
+    }
+
+    /// Cancel the flow with the given ID, returning the flow if it existed.
+    /// A flow cancelled while waiting on the issuer is not restored when the
+    /// issuer responds.
+    pub fn cancel(&self, id: &str) -> Option<Flow> {
+        let flow = self.flows().remove(id)?;
+        self.emit(&HolderEvent::Cancelled { id: id.into() });
+        Some(Arc::unwrap_or_clone(flow))
+    }
+
+    /// Start an issuance flow from a pre-authorized credential offer,
+    /// returning the flow ID.
+    ///
+    /// # Errors
+    /// Will return an error if the offer does not contain a pre-authorized
+    /// code grant or the issuer's metadata cannot be retrieved, or a
+    /// [`crate::error::PolicyDenied`] error if the agent's policy denies a
+    /// credential on offer.
+    pub async fn offer(&self, offer: CredentialOffer, subject_id: &str) -> anyhow::Result<String> {
+        let Some(grant) = offer.pre_authorized_code() else {
+            bail!("offer does not contain a pre-authorized code grant");
+        };
+        let pin_required = grant.tx_code.is_some();
+        let request = MetadataRequest {
+            credential_issuer: offer.credential_issuer.clone(),
+            languages: None,
+        };
+        let metadata = self.provider.metadata(request).await?;
+        let flow = IssuanceFlowBuilder::new(&self.client_id, metadata.credential_issuer)
+            .subject_id(subject_id)
+            .pre_authorized(offer, grant);
+        flow.check_policy(&self.policy)?;
+        let id = self.insert(Flow::Offered(flow));
+        self.emit(&HolderEvent::InputRequired {
+            id: id.clone(),
+            input: Input::Acceptance { pin_required },
+        });
+        Ok(id)
+    }
+
+    /// Accept the credentials on offer. Passing `None` for `accepted` accepts
+    /// all credentials on offer.
+    ///
+    /// # Errors
+    /// Will return an error if there is no offered flow with the given ID. The
+    /// flow is left unchanged on error.
+    pub fn accept(
+        &self, id: &str, accepted: &Option<Vec<AuthorizationSpec>>, pin: Option<String>,
+    ) -> anyhow::Result<()> {
+        let mut flows = self.flows();
+        let flow = match flows.get(id).map(AsRef::as_ref) {
+            Some(Flow::Offered(flow)) => flow.clone(),
+            other => return Err(unexpected(other, "offered issuance flow", id)),
+        };
+        let mut flow = flow.accept(accepted, pin);
+        if let Some(deadline) = Deadlines::after(self.deadlines.issuance) {
+            flow = flow.with_deadline(deadline);
+        }
+        flows.insert(id.into(), Arc::new(Flow::Accepted(flow)));
+        drop(flows);
+        self.progress(id, Step::Accepted);
+        Ok(())
+    }
+
+    /// Request an access token and all authorized credentials from the issuer,
+    /// returning the credentials issued. Deferred credentials are recorded on
+    /// the flow.
+    ///
+    /// # Errors
+    /// Will return an error if there is no accepted flow with the given ID, or
+    /// if the issuer returns an error. The flow is left unchanged on error,
+    /// except that a flow whose code has been exchanged is kept as
+    /// [`Flow::Exchanged`] so a retry does not resend the code.
+    pub async fn receive(&self, id: &str) -> anyhow::Result<Vec<Credential>>
+    where
+        P: NonceCache,
+    {
+        self.receive_with_cancel(id, &CancellationToken::new()).await
+    }
+
+    /// Request an access token and all authorized credentials from the issuer
+    /// as for [`HolderAgent::receive`], stopping if the token is cancelled.
+    /// In-flight requests are aborted on cancellation.
+    ///
+    /// # Errors
+    /// Will return an error if there is no accepted flow with the given ID, if
+    /// the issuer returns an error, or a [`crate::cancel::Cancelled`] error if
+    /// the token is cancelled. The flow is left as for
+    /// [`HolderAgent::receive`] on error.
+    pub async fn receive_with_cancel(
+        &self, id: &str, cancel: &CancellationToken,
+    ) -> anyhow::Result<Vec<Credential>>
+    where
+        P: NonceCache,
+    {
+        let shared = self.flow(id);
+        let issued = match shared.as_deref() {
+            Some(Flow::Accepted(flow)) => {
+                let flow = flow.clone();
+                cancel.run(async { self.issue(id, self.exchange(id, flow).await?).await }).await
+            }
+            Some(Flow::Exchanged(flow)) => cancel.run(self.issue(id, flow.clone())).await,
+            other => return Err(unexpected(other, "accepted issuance flow", id)),
+        };
+        self.report(id, issued.and_then(|issued| issued))
+    }
+
+    // Exchange the pre-authorized code for a token. The flow is kept with the
+    // token so a retry requests credentials without resending the code.
+    async fn exchange(
+        &self, id: &str, flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>,
+    ) -> anyhow::Result<IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>> {
+        // refuse a holder key the issuer cannot bind before using the code
+        flow.check_binding(&self.provider).await?;
+
+        let request = flow.checked_token_request(&self.provider).await?;
+        let token = self.provider.token(request).await?;
+        flow.record_exchange(&self.provider, &token).await?;
+        let flow = flow.token(token);
+        self.update(id, Flow::Exchanged(flow.clone()));
+        self.progress(id, Step::TokenReceived);
+        Ok(flow)
+    }
+
+    async fn issue(
+        &self, id: &str, mut flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+    ) -> anyhow::Result<Vec<Credential>>
+    where
+        P: NonceCache,
+    {
+        let identifiers = flow
+            .get_token()
+            .authorization_details
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|auth| auth.credential_identifiers)
+            .collect::<Vec<_>>();
+
+        // request credentials concurrently (up to the limit), verifying each
+        // response as it arrives. A nonce used in an earlier attempt is never
+        // reused, but requests share the nonce the issuer supplied for this
+        // token until the issuer rotates it. Without a nonce, a request is sent
+        // without a proof so the issuer rejects it with a fresh nonce. A
+        // request whose proof the issuer rejects is sent once more, with the
+        // fresh nonce the issuer returns.
+        let provider = &self.provider;
+        let credential_issuer = flow.issuer().credential_issuer.clone();
+        let mut pending = flow
+            .credential_requests(&identifiers, "")
+            .map(|(cfg_id, request)| (cfg_id, request, false));
+        let mut queued = Vec::new();
+        let mut nonce_used = false;
+        let mut in_flight = FuturesUnordered::new();
+        loop {
+            while in_flight.len() < self.concurrency {
+                let Some((cfg_id, mut request, resent)) = queued.pop().or_else(|| pending.next())
+                else {
+                    break;
+                };
+                let jwt = match flow.build_fresh_proof(provider, provider).await {
+                    Ok(jwt) => Some(jwt),
+                    Err(e) if e.is::<NonceReused>() && nonce_used => {
+                        Some(flow.build_proof(provider).await?)
+                    }
+                    Err(e) if e.is::<NonceReused>() && in_flight.is_empty() => None,
+                    Err(e) if e.is::<NonceReused>() => {
+                        // wait for the fresh nonce the request in flight gets
+                        queued.push((cfg_id, request, resent));
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                };
+                nonce_used |= jwt.is_some();
+                request.proof = jwt.map(|jwt| Proof::Single {
+                    proof_type: SingleProof::Jwt { jwt },
+                });
+                in_flight.push(async move {
+                    let result = async {
+                        let response = Box::pin(provider.credential(request.clone())).await?;
+                        let nonce =
+                            response.c_nonce.map(|c_nonce| (c_nonce, response.c_nonce_expires_in));
+                        anyhow::Ok((nonce, self.verify(response.response).await?))
+                    }
+                    .await;
+                    (cfg_id, request, resent, result)
+                });
+            }
+
+            let Some((cfg_id, request, resent, result)) = in_flight.next().await else {
+                break;
+            };
+            let (nonce, retrieved) = match result {
+                Ok(retrieved) => retrieved,
+                Err(e) => {
+                    let Some(nonce) = fresh_nonce(&e, &credential_issuer).filter(|_| !resent)
+                    else {
+                        return Err(e);
+                    };
+                    flow.set_nonce(&nonce)?;
+                    nonce_used = false;
+                    queued.push((cfg_id, request, true));
+                    continue;
+                }
+            };
+            if let Some((c_nonce, expires_in)) = nonce {
+                let nonce =
+                    Nonce::new(c_nonce, &credential_issuer).expires_in(expires_in, Utc::now());
+                flow.set_nonce(&nonce)?;
+                nonce_used = false;
+            }
+            self.record(id, &mut flow, &cfg_id, retrieved)?;
+        }
+
+        let credentials = flow.credentials();
+        self.update(id, Flow::Issued(flow));
+        Ok(credentials)
+    }
+
+    // Verify the credentials in an issuer's response, or return the
+    // transaction ID if issuance was deferred.
+    async fn verify(&self, response: CredentialResponseType) -> anyhow::Result<Retrieved> {
+        let vc_kinds = match response {
+            CredentialResponseType::Credential(vc_kind) => vec![vc_kind],
+            CredentialResponseType::Credentials(vc_kinds) => vc_kinds,
+            CredentialResponseType::TransactionId(tx_id) => return Ok(Retrieved::Deferred(tx_id)),
+        };
+        let mut verified = vec![];
+        for vc_kind in vc_kinds {
+            let vci_proof::Payload::Vc { vc, issued_at } =
+                jwt_vc::verify(&vc_kind, self.provider.clone()).await?
+            else {
+                bail!("expected verifiable credential payload");
+            };
+            verified.push((vc, vc_kind, issued_at));
+        }
+        Ok(Retrieved::Issued(verified))
+    }
+
+    // Add retrieved credentials (or the deferred transaction) to the flow.
+    fn record(
+        &self, id: &str, flow: &mut IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+        cfg_id: &str, retrieved: Retrieved,
+    ) -> anyhow::Result<()> {
+        match retrieved {
+            Retrieved::Deferred(tx_id) => {
+                flow.add_deferred(&tx_id, &cfg_id.into());
+                self.progress(
+                    id,
+                    Step::CredentialDeferred {
+                        transaction_id: tx_id,
+                    },
+                );
+            }
+            Retrieved::Issued(verified) => {
+                for (vc, vc_kind, issued_at) in verified {
+                    flow.add_credential(&vc, &vc_kind, &issued_at, cfg_id, None, None)?;
+                    self.progress(
+                        id,
+                        Step::CredentialIssued {
+                            credential_configuration_id: cfg_id.into(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Poll the issuer for the flow's deferred credentials that are due,
+    /// returning the credentials issued. Issued credentials are added to the
+    /// flow to be saved using [`HolderAgent::save`].
+    ///
+    /// Each transaction is polled no more often than the interval the issuer
+    /// asks for in `issuance_pending` responses (see
+    /// [`IssuanceFlow::set_deferred_interval`]). Use
+    /// [`HolderAgent::next_poll`] to find when to poll again.
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID, or
+    /// if the issuer returns an error other than asking the wallet to retry
+    /// later. The flow is left unchanged on error.
+    pub async fn poll_deferred(&self, id: &str) -> anyhow::Result<Vec<Credential>> {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "issued flow", id));
+        };
+        let result = self.retrieve_deferred(id, flow.clone()).await;
+        self.report(id, result)
+    }
+
+    async fn retrieve_deferred(
+        &self, id: &str, mut flow: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+    ) -> anyhow::Result<Vec<Credential>> {
+        let issued = flow.credentials().len();
+        let deferred = flow.deferred();
+        for tx_id in flow.deferred_due() {
+            let Some(cfg_id) = deferred.get(&tx_id) else {
+                continue;
+            };
+            let response = match self.provider.deferred(flow.deferred_request(&tx_id)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    let Some(retry) = RetryLater::from_error(&e) else {
+                        return Err(e);
+                    };
+                    flow.set_deferred_interval(&tx_id, retry.delay());
+                    continue;
+                }
+            };
+            flow.remove_deferred(&tx_id);
+            let retrieved = self.verify(response.credential_response.response).await?;
+            self.record(id, &mut flow, cfg_id, retrieved)?;
+        }
+
+        let credentials = flow.credentials().split_off(issued);
+        self.update(id, Flow::Issued(flow));
+        Ok(credentials)
+    }
+
+    /// Check the issuer of an issued flow controls the origin of its
+    /// `credential_issuer` URL, returning the verdict to present to the holder
+    /// with the credentials. The verdict is also recorded on the flow (see
+    /// [`IssuanceFlow::issuer_trust`]).
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID.
+    pub async fn verify_issuer(&self, id: &str) -> anyhow::Result<IssuerTrust>
+    where
+        P: DidConfigurationResolver,
+    {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "issued flow", id));
+        };
+        let mut flow = flow.clone();
+        let trust = flow.verify_issuer(self.provider.clone()).await;
+        self.update(id, Flow::Issued(flow));
+        Ok(trust)
+    }
+
+    /// When to next poll for the flow's deferred credentials using
+    /// [`HolderAgent::poll_deferred`]. `None` if there is no issued flow with
+    /// the given ID or no deferred credentials are outstanding.
+    pub fn next_poll(&self, id: &str) -> Option<DateTime<Utc>> {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return None;
+        };
+        flow.next_deferred_poll()
+    }
+
+    /// Act on a push notification from an issuer, returning the action taken.
+    /// A deferred credential that is ready is retrieved without waiting for
+    /// the next scheduled poll, and a re-issuance offer starts an issuance
+    /// flow.
+    ///
+    /// # Errors
+    /// Will return an error if the payload cannot be decoded, no issued flow
+    /// is waiting on the deferred credential, or the flow action fails.
+    pub async fn handle_push(&self, payload: &[u8]) -> anyhow::Result<PushAction>
+    where
+        P: PushProvider,
+    {
+        match self.provider.decode_push(payload).await? {
+            PushNotification::DeferredReady { transaction_id } => {
+                let flow = self.flows().iter().find_map(|(id, flow)| match flow.as_ref() {
+                    Flow::Issued(flow) if flow.deferred().contains_key(&transaction_id) => {
+                        Some((id.clone(), flow.clone()))
+                    }
+                    _ => None,
+                });
+                let Some((id, mut flow)) = flow else {
+                    bail!("no issued flow awaiting deferred credential {transaction_id}");
+                };
+                flow.set_deferred_ready(&transaction_id);
+                let result = self.retrieve_deferred(&id, flow).await;
+                let credentials = self.report(&id, result)?;
+                Ok(PushAction::Retrieved { id, credentials })
+            }
+            PushNotification::CredentialRevoked { credential_id } => {
+                Ok(PushAction::Revoked { credential_id })
+            }
+            PushNotification::ReissuanceOffered {
+                credential_id,
+                subject_id,
+                credential_offer,
+            } => {
+                let id = self.offer(*credential_offer, &subject_id).await?;
+                Ok(PushAction::Offered {
+                    id,
+                    replaces: credential_id,
+                })
+            }
+        }
+    }
+
+    /// Save the credentials issued to the wallet. The flow is complete and
+    /// removed unless there are deferred credentials outstanding.
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID or the
+    /// credentials could not be saved.
+    pub async fn save(&self, id: &str) -> anyhow::Result<()> {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "issued flow", id));
+        };
+        for credential in flow.credentials() {
+            let result = self.provider.save(&credential).await;
+            self.report(id, result)?;
+        }
+        self.saved(id, flow);
+        Ok(())
+    }
+
+    /// Find the stored credentials superseded by the credentials issued in
+    /// the flow (see [`crate::reissue`]), so the holder can be shown what
+    /// will be replaced before the credentials are saved.
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID or
+    /// the credential store returns an error.
+    pub async fn replacements(&self, id: &str) -> anyhow::Result<Vec<Supersession>> {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "issued flow", id));
+        };
+        let stored = self.provider.list(None).await?;
+        Ok(reissue::superseded(&flow.credentials(), &stored))
+    }
+
+    /// Save the credentials issued to the wallet as [`HolderAgent::save`]
+    /// does, replacing the stored credentials they supersede. Replaced
+    /// records are archived (see [`reissue::replace`]).
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID, or
+    /// the credentials could not be saved or replaced.
+    pub async fn save_replacing(
+        &self, id: &str, archive: &impl CredentialArchive,
+    ) -> anyhow::Result<Vec<Replaced>> {
+        let shared = self.flow(id);
+        let Some(Flow::Issued(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "issued flow", id));
+        };
+        let credentials = flow.credentials();
+        let stored = self.provider.list(None).await;
+        let supersessions = reissue::superseded(&credentials, &self.report(id, stored)?);
+
+        let mut replaced = vec![];
+        for credential in credentials {
+            let result = match supersessions.iter().find(|s| s.credential_id == credential.id) {
+                Some(s) => reissue::replace(&self.provider, archive, &s.replaces.id, &credential)
+                    .await
+                    .map(|r| replaced.push(r)),
+                None => self.provider.save(&credential).await,
+            };
+            self.report(id, result)?;
+        }
+        self.saved(id, flow);
+        Ok(replaced)
+    }
+
+    // Complete an issued flow once its credentials have been saved, keeping
+    // it if there are deferred credentials outstanding.
+    fn saved(&self, id: &str, flow: &IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>) {
+        if flow.deferred().is_empty() {
+            self.flows().remove(id);
+            self.emit(&HolderEvent::Completed { id: id.into() });
+        } else {
+            // keep the flow for deferred credentials but don't save these again
+            let mut flow = flow.clone();
+            flow.clear_credentials();
+            self.update(id, Flow::Issued(flow));
+            self.progress(id, Step::CredentialsSaved);
+        }
+    }
+
+    /// Start a presentation flow from a presentation request, returning the
+    /// flow ID.
+    ///
+    /// The request can be either a URL-encoded request object or a URI from
+    /// which to retrieve the request object.
+    ///
+    /// # Errors
+    /// Will return an error if the request object cannot be retrieved or is
+    /// not valid.
+    pub async fn request(&self, request: &str) -> anyhow::Result<String> {
+        let request_object = if let Some(request_object) = parse_request_object(request)? {
+            request_object
+        } else {
+            let url = urlencoding::decode(request)?;
+            let response = self.provider.request_object(&url).await?;
+            parse_request_object_response(&response, self.provider.clone()).await?
+        };
+        let mut flow = PresentationFlow::<NotAuthorized>::new(request_object)?
+            .with_state_policy(self.state_policy)?
+            .with_presentation(self.presentation.clone());
+        if let Some(deadline) = Deadlines::after(self.deadlines.presentation) {
+            flow = flow.with_deadline(deadline);
+        }
+        let id = self.insert(Flow::Requested(flow));
+        self.emit(&HolderEvent::InputRequired {
+            id: id.clone(),
+            input: Input::Authorization,
+        });
+        Ok(id)
+    }
+
+    /// Find the credentials in the wallet that match the verifier's request
+    /// and can be shared with the verifier for the holder to select from.
+    /// Credentials requiring the holder to be verified are not included (see
+    /// [`HolderAgent::matches_with`]).
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID or
+    /// the credential store returns an error.
+    pub async fn matches(&self, id: &str) -> anyhow::Result<Vec<Credential>> {
+        self.matches_with(id, Sharing::default()).await
+    }
+
+    /// Find the credentials in the wallet that match the verifier's request
+    /// and whose sharing policies permit presenting them in the circumstances
+    /// described by `sharing`.
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID or
+    /// the credential store returns an error.
+    pub async fn matches_with(
+        &self, id: &str, sharing: Sharing,
+    ) -> anyhow::Result<Vec<Credential>> {
+        let shared = self.flow(id);
+        let Some(Flow::Requested(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "requested presentation flow", id));
+        };
+        let credentials = self.provider.find(Some(flow.filter()?)).await?;
+        let permitted =
+            credentials.into_iter().filter(|c| self.policy.permits(&PolicyTarget::from(c)));
+        Ok(flow.shareable(permitted.collect(), sharing))
+    }
+
+    /// Find the credentials in the wallet that match the verifier's request
+    /// and can be shared in the circumstances described by `sharing`, loading
+    /// only their metadata. Authorize the holder's selection using
+    /// [`HolderAgent::authorize_selected`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID or
+    /// the credential store returns an error.
+    pub async fn candidates(
+        &self, id: &str, sharing: Sharing,
+    ) -> anyhow::Result<Vec<CredentialMetadata>> {
+        let shared = self.flow(id);
+        let Some(Flow::Requested(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "requested presentation flow", id));
+        };
+        let candidates = self.provider.list(Some(flow.filter()?)).await?;
+        let permitted =
+            candidates.into_iter().filter(|c| self.policy.permits(&PolicyTarget::from(c)));
+        Ok(permitted.filter(|c| flow.permits(&c.sharing_policy, sharing)).collect())
+    }
+
+    /// Authorize the presentation of the selected credentials to the
+    /// verifier, loading the full credentials from the wallet.
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID or
+    /// a selected credential cannot be loaded.
+    pub async fn authorize_selected(
+        &self, id: &str, selected: &[CredentialMetadata],
+    ) -> anyhow::Result<()> {
+        let mut credentials = vec![];
+        for metadata in selected {
+            let Some(credential) = self.provider.load(&metadata.id).await? else {
+                bail!("credential {} not found", metadata.id);
+            };
+            credentials.push(credential);
+        }
+        self.authorize(id, &credentials)
+    }
+
+    /// Authorize the presentation of the given credentials to the verifier.
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID,
+    /// or a [`crate::error::PolicyDenied`] error if the agent's policy denies
+    /// one of the credentials. The flow is left unchanged on error.
+    pub fn authorize(&self, id: &str, credentials: &[Credential]) -> anyhow::Result<()> {
+        for credential in credentials {
+            self.policy.check(&PolicyTarget::from(credential))?;
+        }
+        let mut flows = self.flows();
+        let flow = match flows.get(id).map(AsRef::as_ref) {
+            Some(Flow::Requested(flow)) => flow.clone(),
+            other => return Err(unexpected(other, "requested presentation flow", id)),
+        };
+        flows.insert(id.into(), Arc::new(Flow::Authorized(flow.authorize(credentials))));
+        drop(flows);
+        self.progress(id, Step::Authorized);
+        Ok(())
+    }
+
+    /// Send the authorized presentation to the verifier. The flow is complete
+    /// and removed on success.
+    ///
+    /// # Errors
+    /// Will return an error if there is no authorized flow with the given ID or
+    /// the presentation could not be sent.
+    pub async fn present(&self, id: &str) -> anyhow::Result<ResponseResponse> {
+        let shared = self.flow(id);
+        let Some(Flow::Authorized(flow)) = shared.as_deref() else {
+            return Err(unexpected(shared.as_deref(), "authorized presentation flow", id));
+        };
+        let result = self.send(flow).await;
+        let response = self.report(id, result)?;
+        self.flows().remove(id);
+        self.emit(&HolderEvent::Completed { id: id.into() });
+        Ok(response)
+    }
+
+    async fn send(&self, flow: &PresentationFlow<Authorized>) -> anyhow::Result<ResponseResponse> {
+        let algorithm = self.provider.algorithm();
+        let negotiated = flow.negotiate(&WalletMetadata::new(algorithm.clone()), &algorithm)?;
+        let kid = self.provider.verification_method().await?;
+        let payload @ Payload::Vp { .. } = flow.payload(&kid)? else {
+            bail!("expected verifiable presentation payload");
+        };
+        let jwt = vp_proof::create(negotiated.proof_format(), payload, &self.provider).await?;
+        let (request, uri) = flow.create_response_request(&jwt);
+        flow.verify_state(&request)?;
+        self.provider.present(uri.as_deref(), &request).await
+    }
+
+    /// Persist the flow to the provider's `StateStore` so it can be resumed
+    /// later. The flow remains active in the agent.
+    ///
+    /// # Errors
+    /// Will return an error if there is no flow with the given ID or the
+    /// state store returns an error.
+    pub async fn persist(&self, id: &str, expiry: DateTime<Utc>) -> anyhow::Result<()> {
+        let flow = self.flow(id).ok_or_else(|| anyhow!("no flow with id {id}"))?;
+        StateStore::put(&self.provider, id, &*flow, expiry).await
+    }
+
+    /// Resume a flow previously persisted to the provider's `StateStore`.
+    ///
+    /// # Errors
+    /// Will return an error if the flow cannot be retrieved from the state
+    /// store.
+    pub async fn resume(&self, id: &str) -> anyhow::Result<()> {
+        let flow: Flow = StateStore::get(&self.provider, id).await?;
+        self.flows().insert(id.into(), Arc::new(flow));
+        Ok(())
+    }
+
+    /// Add a flow obtained elsewhere (for example, resumed from a
+    /// [`crate::registry::FlowRegistry`]) to the agent, returning its ID.
+    pub fn restore(&self, flow: Flow) -> String {
+        self.insert(flow)
+    }
+
+    fn progress(&self, id: &str, step: Step) {
+        self.emit(&HolderEvent::Progress { id: id.into(), step });
+    }
+
+    // Emit a `Failed` event for errors returned by providers.
+    fn report<T>(&self, id: &str, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        if let Err(e) = &result {
+            self.emit(&HolderEvent::Failed {
+                id: id.into(),
+                error: format!("{e:#}"),
+            });
+        }
+        result
+    }
+
+    // Send the event to all subscribers, dropping any that have gone away.
+    fn emit(&self, event: &HolderEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+    }
+
+    // Replace a flow advanced while the lock was released, unless it has been
+    // cancelled (or otherwise removed) in the meantime.
+    fn update(&self, id: &str, flow: Flow) {
+        if let Some(slot) = self.flows().get_mut(id) {
+            *slot = Arc::new(flow);
+        }
+    }
+
+    fn insert(&self, flow: Flow) -> String {
+        let id = flow.id();
+        self.flows().insert(id.clone(), Arc::new(flow));
+        id
+    }
+
+    // Flows are shared out of the map so the lock is never held across an
+    // await. Flows past their deadline are timed out first.
+    fn flows(&self) -> MutexGuard<'_, HashMap<String, Arc<Flow>>> {
+        let mut flows = self.flows.lock().unwrap_or_else(PoisonError::into_inner);
+        for id in time_out(&mut flows) {
+            self.emit(&HolderEvent::TimedOut { id });
+        }
+        flows
+    }
+}
+
+// Replace flows past their deadline with a `TimedOut` record, dropping their
+// tokens, codes and nonces. Returns the IDs of the flows timed out.
+fn time_out(flows: &mut HashMap<String, Arc<Flow>>) -> Vec<String> {
+    let now = Utc::now();
+    let mut timed_out = Vec::new();
+    for (id, flow) in flows.iter_mut() {
+        let Some(deadline) = flow.deadline().filter(|deadline| *deadline <= now) else {
+            continue;
+        };
+        *flow = Arc::new(Flow::TimedOut(TimedOut {
+            id: id.clone(),
+            deadline,
+        }));
+        timed_out.push(id.clone());
+    }
+    timed_out
+}
+
+// The fresh nonce returned with an error rejecting a proof of possession, if
+// any.
+fn fresh_nonce(error: &anyhow::Error, credential_issuer: &str) -> Option<Nonce> {
+    let oauth = OAuthError::from_error(error)?;
+    let Some(Recovery::RegenerateProof { .. }) = oauth.recovery(false) else {
+        return None;
+    };
+    oauth.nonce(credential_issuer)
+}
+
+// The error for a step on a flow that is missing or in another state: a
+// `FlowTimedOut` error if the flow has timed out.
+fn unexpected(flow: Option<&Flow>, expected: &str, id: &str) -> anyhow::Error {
+    match flow {
+        Some(Flow::TimedOut(timed_out)) => anyhow!(FlowTimedOut {
+            id: id.into(),
+            deadline: timed_out.deadline,
+        }),
+        _ => anyhow!("no {expected} with id {id}"),
+    }
+}
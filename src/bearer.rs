@@ -0,0 +1,189 @@
+//! # Bearer Material
+//!
+//! Access tokens, nonces and codes are bearer material: whoever holds one can
+//! use it. Passed around as plain strings they are easily logged, and in a
+//! wallet running several flows at once, easily sent to the wrong issuer or
+//! used after they have expired.
+//!
+//! A [`Bearer`] holds the value (as a [`Secret`], so it is zeroed on drop and
+//! redacted from `Debug` output) together with its origin (the issuer or
+//! verifier that issued it) and, where known, when it expires. The value is
+//! only exposed for its origin (see [`Bearer::expose_for`]), so material
+//! from one flow cannot be used in another by mistake. [`Token`], [`Nonce`]
+//! and [`Code`] are distinct types, so an access token cannot be passed where
+//! a nonce is expected.
+//!
+//! Flows hand out bearer material this way (for example,
+//! [`crate::issuance::IssuanceFlow::access_token`]) and take it back (see
+//! [`crate::issuance::IssuanceFlow::set_nonce`]). Requests sent to issuers
+//! and verifiers still carry plain strings, copied at the last moment.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::clock::TimeValidator;
+use crate::error::OriginMismatch;
+use crate::secret::Secret;
+
+/// An access token issued by an authorization server.
+pub type Token = Bearer<AccessTokenKind>;
+
+/// A nonce (such as a `c_nonce`) issued by an issuer or verifier.
+pub type Nonce = Bearer<NonceKind>;
+
+/// A pre-authorized or authorization code issued by an issuer.
+pub type Code = Bearer<CodeKind>;
+
+/// The kind of bearer material. Implemented by [`AccessTokenKind`],
+/// [`NonceKind`] and [`CodeKind`].
+pub trait BearerKind: sealed::Sealed {
+    /// The name of the kind of material, used in `Debug` output and errors.
+    const NAME: &'static str;
+}
+
+/// Marks a [`Bearer`] as an access token.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessTokenKind;
+
+/// Marks a [`Bearer`] as a nonce.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NonceKind;
+
+/// Marks a [`Bearer`] as a code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CodeKind;
+
+impl BearerKind for AccessTokenKind {
+    const NAME: &'static str = "Token";
+}
+
+impl BearerKind for NonceKind {
+    const NAME: &'static str = "Nonce";
+}
+
+impl BearerKind for CodeKind {
+    const NAME: &'static str = "Code";
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::AccessTokenKind {}
+    impl Sealed for super::NonceKind {}
+    impl Sealed for super::CodeKind {}
+}
+
+/// Bearer material of kind `K`, bound to the issuer or verifier it came
+/// from.
+pub struct Bearer<K: BearerKind> {
+    value: Secret,
+    origin: String,
+    expires_at: Option<DateTime<Utc>>,
+    kind: PhantomData<K>,
+}
+
+impl<K: BearerKind> Bearer<K> {
+    /// Bearer material issued by the origin (a credential issuer URL or a
+    /// verifier's client ID).
+    #[must_use]
+    pub fn new(value: impl Into<String>, origin: impl Into<String>) -> Self {
+        Self {
+            value: Secret::new(value.into()),
+            origin: origin.into(),
+            expires_at: None,
+            kind: PhantomData,
+        }
+    }
+
+    /// Set when the material expires.
+    #[must_use]
+    pub const fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set the material to expire a number of seconds (such as an
+    /// `expires_in` or `c_nonce_expires_in` parameter) after the time given.
+    /// Lifetimes that are not positive are ignored.
+    #[must_use]
+    pub fn expires_in(self, seconds: Option<i64>, from: DateTime<Utc>) -> Self {
+        match seconds.filter(|secs| *secs > 0).and_then(TimeDelta::try_seconds) {
+            Some(lifetime) => self.with_expiry(from + lifetime),
+            None => self,
+        }
+    }
+
+    /// The issuer or verifier the material was issued by.
+    #[must_use]
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    /// When the material expires, if known.
+    #[must_use]
+    pub const fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+    }
+
+    /// Whether the material has expired, according to the validator's clock
+    /// and allowing for its leeway. Material without an expiry never expires.
+    #[must_use]
+    pub fn is_expired(&self, times: &TimeValidator) -> bool {
+        times.is_expired(self.expires_at)
+    }
+
+    /// Expose the value, for use with any party. Prefer
+    /// [`Self::expose_for`].
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        self.value.expose()
+    }
+
+    /// Expose the value for use with the origin.
+    ///
+    /// # Errors
+    /// Will return an [`OriginMismatch`] error if the material was issued by
+    /// a different origin.
+    pub fn expose_for(&self, origin: &str) -> Result<&str, OriginMismatch> {
+        if self.origin != origin {
+            return Err(OriginMismatch {
+                kind: K::NAME.into(),
+                origin: self.origin.clone(),
+                used_with: origin.into(),
+            });
+        }
+        Ok(self.value.expose())
+    }
+}
+
+impl<K: BearerKind> Clone for Bearer<K> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            origin: self.origin.clone(),
+            expires_at: self.expires_at,
+            kind: PhantomData,
+        }
+    }
+}
+
+impl<K: BearerKind> PartialEq for Bearer<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+            && self.origin == other.origin
+            && self.expires_at == other.expires_at
+    }
+}
+
+impl<K: BearerKind> Eq for Bearer<K> {}
+
+impl<K: BearerKind> Debug for Bearer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(K::NAME)
+            .field("value", &self.value)
+            .field("origin", &self.origin)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
@@ -0,0 +1,264 @@
+//! # Blocking
+//!
+//! Synchronous wrappers around the agent and verification functions for hosts
+//! that cannot await, such as synchronous plugin systems or FFI layers.
+//!
+//! Each call drives the underlying future to completion on the calling thread
+//! with [`block_on`], a minimal executor that parks the thread while the
+//! future is pending. Provider futures are therefore polled on the caller's
+//! thread: providers that rely on a runtime being current (for example, a
+//! tokio reactor for network IO) should enter that runtime in their own
+//! implementation.
+//!
+//! Do not call these functions from async code. Blocking a thread that an
+//! executor is using to run other tasks stalls those tasks.
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use chrono::{DateTime, Utc};
+
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::agent::HolderAgent;
+#[cfg(any(feature = "status", all(feature = "issuance", feature = "presentation")))]
+use crate::credential::Credential;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::issuance::{AuthorizationSpec, CredentialOffer};
+#[cfg(feature = "presentation")]
+use crate::presentation::RequestObject;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::presentation::ResponseResponse;
+#[cfg(feature = "presentation")]
+use crate::presentation::siop::IdTokenClaims;
+#[cfg(any(feature = "presentation", feature = "status"))]
+use crate::provider::DidResolver;
+#[cfg(feature = "status")]
+use crate::provider::StatusListResolver;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::provider::{HolderProvider, NonceCache};
+#[cfg(feature = "status")]
+use crate::status::{CredentialStatus, Status};
+
+/// Run a future to completion on the current thread, parking the thread
+/// whenever the future is pending until it is woken.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+// Wakes a future by unparking the thread blocked on it.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// A [`HolderAgent`] whose flow methods block until complete.
+///
+/// Other agent methods (cancelling a flow, subscribing to events, etc.) are
+/// synchronous already and are available using [`BlockingAgent::agent`].
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+#[derive(Clone, Debug)]
+pub struct BlockingAgent<P: HolderProvider> {
+    agent: HolderAgent<P>,
+}
+
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+impl<P: HolderProvider> From<HolderAgent<P>> for BlockingAgent<P> {
+    fn from(agent: HolderAgent<P>) -> Self {
+        Self { agent }
+    }
+}
+
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+impl<P: HolderProvider> BlockingAgent<P> {
+    /// Create a new agent. The `client_id` is used to identify the wallet to
+    /// issuers.
+    pub fn new(provider: P, client_id: impl Into<String>) -> Self {
+        Self {
+            agent: HolderAgent::new(provider, client_id),
+        }
+    }
+
+    /// The wrapped agent.
+    pub const fn agent(&self) -> &HolderAgent<P> {
+        &self.agent
+    }
+
+    /// Start an issuance flow from a pre-authorized credential offer, as for
+    /// [`HolderAgent::offer`].
+    ///
+    /// # Errors
+    /// Will return an error if the offer does not contain a pre-authorized
+    /// code grant or the issuer's metadata cannot be retrieved.
+    pub fn offer(&self, offer: CredentialOffer, subject_id: &str) -> anyhow::Result<String> {
+        block_on(self.agent.offer(offer, subject_id))
+    }
+
+    /// Accept the credentials on offer, as for [`HolderAgent::accept`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no offered flow with the given ID.
+    pub fn accept(
+        &self, id: &str, accepted: &Option<Vec<AuthorizationSpec>>, pin: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.agent.accept(id, accepted, pin)
+    }
+
+    /// Request an access token and all authorized credentials from the
+    /// issuer, as for [`HolderAgent::receive`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no accepted flow with the given ID, or
+    /// if the issuer returns an error.
+    pub fn receive(&self, id: &str) -> anyhow::Result<Vec<Credential>>
+    where
+        P: NonceCache,
+    {
+        block_on(self.agent.receive(id))
+    }
+
+    /// Save the credentials issued to the wallet, as for
+    /// [`HolderAgent::save`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID or the
+    /// credentials could not be saved.
+    pub fn save(&self, id: &str) -> anyhow::Result<()> {
+        block_on(self.agent.save(id))
+    }
+
+    /// Poll the issuer for deferred credentials that are due, as for
+    /// [`HolderAgent::poll_deferred`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no issued flow with the given ID, or
+    /// if the issuer returns an error other than asking the wallet to retry
+    /// later.
+    pub fn poll_deferred(&self, id: &str) -> anyhow::Result<Vec<Credential>> {
+        block_on(self.agent.poll_deferred(id))
+    }
+
+    /// Start a presentation flow from a presentation request, as for
+    /// [`HolderAgent::request`].
+    ///
+    /// # Errors
+    /// Will return an error if the request object cannot be retrieved or is
+    /// not valid.
+    pub fn request(&self, request: &str) -> anyhow::Result<String> {
+        block_on(self.agent.request(request))
+    }
+
+    /// Find the credentials in the wallet that match the verifier's request,
+    /// as for [`HolderAgent::matches`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID or
+    /// the credential store returns an error.
+    pub fn matches(&self, id: &str) -> anyhow::Result<Vec<Credential>> {
+        block_on(self.agent.matches(id))
+    }
+
+    /// Authorize the presentation of the given credentials to the verifier,
+    /// as for [`HolderAgent::authorize`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no requested flow with the given ID.
+    pub fn authorize(&self, id: &str, credentials: &[Credential]) -> anyhow::Result<()> {
+        self.agent.authorize(id, credentials)
+    }
+
+    /// Send the authorized presentation to the verifier, as for
+    /// [`HolderAgent::present`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no authorized flow with the given ID or
+    /// the presentation could not be sent.
+    pub fn present(&self, id: &str) -> anyhow::Result<ResponseResponse> {
+        block_on(self.agent.present(id))
+    }
+
+    /// Persist the flow to the provider's `StateStore`, as for
+    /// [`HolderAgent::persist`].
+    ///
+    /// # Errors
+    /// Will return an error if there is no flow with the given ID or the
+    /// state store returns an error.
+    pub fn persist(&self, id: &str, expiry: DateTime<Utc>) -> anyhow::Result<()> {
+        block_on(self.agent.persist(id, expiry))
+    }
+
+    /// Resume a flow previously persisted to the provider's `StateStore`, as
+    /// for [`HolderAgent::resume`].
+    ///
+    /// # Errors
+    /// Will return an error if the flow cannot be retrieved from the state
+    /// store.
+    pub fn resume(&self, id: &str) -> anyhow::Result<()> {
+        block_on(self.agent.resume(id))
+    }
+}
+
+/// Parse and verify a request object JWT, as for
+/// [`crate::presentation::parse_request_object_jwt`].
+///
+/// # Errors
+/// If decoding or verifying the JWT fails an error is returned.
+#[cfg(feature = "presentation")]
+pub fn parse_request_object_jwt(
+    token: &str, resolver: impl DidResolver,
+) -> anyhow::Result<RequestObject> {
+    block_on(crate::presentation::parse_request_object_jwt(token, resolver))
+}
+
+/// Verify a self-issued ID token, as for
+/// [`crate::presentation::siop::verify_id_token`].
+///
+/// # Errors
+/// If decoding or verifying the JWT fails, the token has expired, the
+/// audience or nonce do not match, or the token is not self-issued, an error
+/// is returned.
+#[cfg(feature = "presentation")]
+pub fn verify_id_token(
+    token: &str, client_id: &str, nonce: &str, resolver: impl DidResolver,
+) -> anyhow::Result<IdTokenClaims> {
+    block_on(crate::presentation::siop::verify_id_token(token, client_id, nonce, resolver))
+}
+
+/// Check the status of a held credential, as for [`crate::status::check`].
+///
+/// # Errors
+/// Will return an error if the issued credential cannot be decoded and
+/// verified, or if the provider is unable to resolve a status list.
+#[cfg(feature = "status")]
+pub fn check_status(
+    credential: &Credential, provider: impl Status + DidResolver,
+) -> anyhow::Result<Vec<CredentialStatus>> {
+    block_on(crate::status::check(credential, provider))
+}
+
+/// Check the status of a batch of held credentials, as for
+/// [`crate::status::check_statuses`].
+#[cfg(feature = "status")]
+pub fn check_statuses(
+    credentials: &[Credential], provider: impl StatusListResolver + DidResolver,
+) -> Vec<anyhow::Result<Vec<CredentialStatus>>> {
+    block_on(crate::status::check_statuses(credentials, provider))
+}
@@ -0,0 +1,124 @@
+//! # Cancellation
+//!
+//! Requests to issuers and verifiers, DID resolution and status list fetches
+//! can take some time on a slow network. A [`CancellationToken`] lets a
+//! wallet's UI abort this work promptly (for example, when the holder taps
+//! "Cancel") rather than waiting for the request to time out.
+//!
+//! Cancellation is cooperative: the SDK stops waiting on in-flight work when
+//! the token is cancelled and returns a [`Cancelled`] error. Work is aborted by
+//! dropping its future, so providers should not assume a request they started
+//! will run to completion.
+//!
+//! Routines that accept a token include [`crate::status::check_with_cancel`]
+//! and `HolderAgent::receive_with_cancel`. Any other future, such as a call
+//! to a provider's `DidResolver`, can be made cancellable using
+//! [`CancellationToken::run`].
+
+use std::future::Future;
+use std::pin::{Pin, pin};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use futures_util::future::{Either, select};
+
+pub use crate::error::Cancelled;
+
+/// A token used to cancel in-progress work. Clones share the same state, so a
+/// clone can be handed to the UI while the original is passed to the SDK.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Create a new token.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the work the token was passed to. Cancelling a token more than
+    /// once has no further effect.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut *self.wakers());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Whether the token has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Return an error if the token has been cancelled.
+    ///
+    /// # Errors
+    /// Returns a [`Cancelled`] error if the token has been cancelled.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// A future that completes when the token is cancelled.
+    #[must_use]
+    pub const fn cancelled(&self) -> WaitForCancellation<'_> {
+        WaitForCancellation { token: self }
+    }
+
+    /// Run the future to completion unless the token is cancelled first, in
+    /// which case the future is dropped.
+    ///
+    /// # Errors
+    /// Returns a [`Cancelled`] error if the token is cancelled before the
+    /// future completes.
+    pub async fn run<F: Future>(&self, future: F) -> anyhow::Result<F::Output> {
+        self.check()?;
+        match select(pin!(future), self.cancelled()).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(((), _)) => Err(Cancelled.into()),
+        }
+    }
+
+    fn wakers(&self) -> MutexGuard<'_, Vec<Waker>> {
+        self.inner.wakers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A future that completes when a [`CancellationToken`] is cancelled.
+#[derive(Debug)]
+pub struct WaitForCancellation<'a> {
+    token: &'a CancellationToken,
+}
+
+impl Future for WaitForCancellation<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.token.wakers();
+        // check again while holding the lock so a concurrent cancel cannot be
+        // missed
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
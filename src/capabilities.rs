@@ -0,0 +1,144 @@
+//! # Capabilities
+//!
+//! A [`HolderCapabilities`] describes what a build of the SDK can actually
+//! do: the features it was compiled with, the signers the wallet has
+//! registered, and the credential and presentation formats it supports.
+//!
+//! Features and formats are taken from the compiled crate, so the
+//! description cannot drift from the build. The serialized capabilities are
+//! useful for debugging and support reports, and [`HolderCapabilities::metadata`]
+//! derives the [`WalletMetadata`] used for client registration and
+//! `request_uri_method=post` from them.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::metadata::WalletMetadata;
+use crate::provider::{Algorithm, Signer};
+
+// Crate features and whether each is enabled in this build.
+const FEATURES: [(&str, bool); 5] = [
+    ("issuance", cfg!(feature = "issuance")),
+    ("presentation", cfg!(feature = "presentation")),
+    ("status", cfg!(feature = "status")),
+    ("qr", cfg!(feature = "qr")),
+    ("blocking", cfg!(feature = "blocking")),
+];
+
+// Credential formats the wallet can verify, store and present.
+const CREDENTIAL_FORMATS: [&str; 2] = ["jwt_vc_json", "jwt_vc"];
+
+/// A signer registered with the wallet.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignerCapability {
+    /// The verification method (key ID) the signer signs with.
+    pub verification_method: String,
+
+    /// The signer's algorithm.
+    pub algorithm: Algorithm,
+}
+
+/// What this build of the SDK, and the signers registered with it, can do.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HolderCapabilities {
+    /// The version of the SDK.
+    pub version: String,
+
+    /// The crate features enabled in this build.
+    pub features: Vec<String>,
+
+    /// The signers registered with the wallet.
+    pub signers: Vec<SignerCapability>,
+
+    /// The credential formats the wallet supports.
+    pub credential_formats: Vec<String>,
+
+    /// The presentation formats the wallet can create, in order of
+    /// preference. Empty unless the `presentation` feature is enabled.
+    pub presentation_formats: Vec<String>,
+}
+
+impl Default for HolderCapabilities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HolderCapabilities {
+    /// The capabilities of this build, without any registered signers.
+    #[must_use]
+    pub fn new() -> Self {
+        let features = FEATURES.iter().filter(|(_, enabled)| *enabled);
+
+        #[cfg(feature = "presentation")]
+        let presentation_formats = crate::presentation::format::PRESENTATION_FORMATS
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        #[cfg(not(feature = "presentation"))]
+        let presentation_formats = vec![];
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            features: features.map(|(name, _)| (*name).to_string()).collect(),
+            signers: vec![],
+            credential_formats: CREDENTIAL_FORMATS.iter().map(ToString::to_string).collect(),
+            presentation_formats,
+        }
+    }
+
+    /// Register a signer.
+    ///
+    /// # Errors
+    /// Will return an error if the signer's verification method cannot be
+    /// retrieved.
+    pub async fn signer(mut self, signer: &impl Signer) -> anyhow::Result<Self> {
+        self.signers.push(SignerCapability {
+            verification_method: signer.verification_method().await?,
+            algorithm: signer.algorithm(),
+        });
+        Ok(self)
+    }
+
+    /// Whether a crate feature is enabled in this build.
+    #[must_use]
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    /// The wallet metadata for the capabilities: each supported format with
+    /// the algorithms of the registered signers. Builds with the
+    /// `presentation` feature also support the `id_token` response type, as
+    /// a self-issued `OpenID` provider.
+    ///
+    /// # Errors
+    /// Will return an error if no signers are registered, or an algorithm
+    /// cannot be serialized.
+    pub fn metadata(&self) -> anyhow::Result<WalletMetadata> {
+        let Some(first) = self.signers.first() else {
+            bail!("no signers registered");
+        };
+
+        // The distinct algorithms of the signers, compared by identifier.
+        let mut algorithms: Vec<Algorithm> = vec![];
+        let mut identifiers = vec![];
+        for signer in &self.signers {
+            let identifier = serde_json::to_value(&signer.algorithm)?;
+            if !identifiers.contains(&identifier) {
+                identifiers.push(identifier);
+                algorithms.push(signer.algorithm.clone());
+            }
+        }
+
+        let mut metadata = WalletMetadata::new(first.algorithm.clone());
+        metadata.vp_formats_supported.clear();
+        for format in self.credential_formats.iter().chain(&self.presentation_formats) {
+            metadata = metadata.format(format, algorithms.clone());
+        }
+        metadata.request_object_signing_alg_values_supported.clone_from(&algorithms);
+        if self.has_feature("presentation") {
+            metadata = metadata.response_type("id_token");
+        }
+        Ok(metadata)
+    }
+}
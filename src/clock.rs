@@ -0,0 +1,160 @@
+//! # Clock
+//!
+//! Tokens and credentials carry times (`iat`, `nbf` and `exp` claims, and
+//! credential validity periods) that are checked against the device clock.
+//! Device clocks are often a little wrong, so a strict comparison rejects a
+//! token issued a few seconds "in the future", or one that expired a moment
+//! ago by the verifier's clock but not by the issuer's.
+//!
+//! All time checks are made by a [`TimeValidator`], which reads the time from
+//! a [`Clock`] (the system clock unless another is injected, for example in
+//! tests or on devices with a trusted time source) and allows for a clock
+//! skew leeway ([`DEFAULT_LEEWAY`] unless configured). A check that fails
+//! returns an [`InvalidTime`] error.
+
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::Value;
+
+use crate::error::InvalidTime;
+
+/// The clock skew allowed when checking times, unless configured.
+pub const DEFAULT_LEEWAY: TimeDelta = TimeDelta::seconds(60);
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// The current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Checks times against a clock, allowing for clock skew.
+#[derive(Clone)]
+pub struct TimeValidator {
+    clock: Arc<dyn Clock>,
+    leeway: TimeDelta,
+}
+
+impl Default for TimeValidator {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl Debug for TimeValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeValidator")
+            .field("now", &self.now())
+            .field("leeway", &self.leeway)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TimeValidator {
+    /// Check times against the clock, with the default leeway.
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            leeway: DEFAULT_LEEWAY,
+        }
+    }
+
+    /// Set the clock skew allowed. Negative durations are treated as no
+    /// leeway.
+    #[must_use]
+    pub fn with_leeway(mut self, leeway: TimeDelta) -> Self {
+        self.leeway = leeway.max(TimeDelta::zero());
+        self
+    }
+
+    /// The clock skew allowed.
+    #[must_use]
+    pub const fn leeway(&self) -> TimeDelta {
+        self.leeway
+    }
+
+    /// The current time, according to the clock.
+    #[must_use]
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Check the `iat`, `nbf` and `exp` claims (in seconds since the epoch)
+    /// of a token: it must not be issued or valid only in the future, and
+    /// must not have expired. Claims that are absent (or null) are not checked.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidTime`] error for the first claim that fails,
+    /// or if a claim is not a valid time.
+    pub fn check_claims(&self, claims: &Value) -> Result<(), InvalidTime> {
+        let now = self.now();
+        for claim in ["iat", "nbf", "exp"] {
+            let Some(value) = claims.get(claim).filter(|value| !value.is_null()) else {
+                continue;
+            };
+            let Some(at) = value.as_i64().and_then(|secs| DateTime::from_timestamp(secs, 0)) else {
+                return Err(self.invalid(claim, None));
+            };
+            let valid =
+                if claim == "exp" { now - self.leeway < at } else { now + self.leeway >= at };
+            if !valid {
+                return Err(self.invalid(claim, Some(at)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the current time is within a validity period: at or after
+    /// `valid_from` and before `valid_until`. An absent bound is not checked.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidTime`] error naming the bound (`valid_from` or
+    /// `valid_until`) that fails.
+    pub fn check_period(
+        &self, valid_from: Option<DateTime<Utc>>, valid_until: Option<DateTime<Utc>>,
+    ) -> Result<(), InvalidTime> {
+        let now = self.now();
+        if let Some(from) = valid_from.filter(|from| now + self.leeway < *from) {
+            return Err(self.invalid("valid_from", Some(from)));
+        }
+        if let Some(until) = valid_until.filter(|until| now - self.leeway >= *until) {
+            return Err(self.invalid("valid_until", Some(until)));
+        }
+        Ok(())
+    }
+
+    /// Whether the current time is within a validity period (see
+    /// [`Self::check_period`]).
+    #[must_use]
+    pub fn is_valid(
+        &self, valid_from: Option<DateTime<Utc>>, valid_until: Option<DateTime<Utc>>,
+    ) -> bool {
+        self.check_period(valid_from, valid_until).is_ok()
+    }
+
+    /// Whether a time limit has passed.
+    #[must_use]
+    pub fn is_expired(&self, valid_until: Option<DateTime<Utc>>) -> bool {
+        self.check_period(None, valid_until).is_err()
+    }
+
+    fn invalid(&self, claim: &str, at: Option<DateTime<Utc>>) -> InvalidTime {
+        InvalidTime {
+            claim: claim.into(),
+            at,
+            now: self.now(),
+            leeway: self.leeway,
+        }
+    }
+}
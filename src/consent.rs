@@ -0,0 +1,141 @@
+//! # Consent
+//!
+//! Types supporting the `ConsentGate` provider, which gives the holder the
+//! opportunity to approve (or refuse) each signature the wallet makes on their
+//! behalf.
+//!
+//! Flows do not sign anything themselves, so consent is enforced by wrapping
+//! the wallet's `Signer` in a [`GatedSigner`]. The flows provide a `signer`
+//! method that constructs the wrapper with a description of the operation
+//! being signed, which is passed to the `ConsentGate` immediately before the
+//! signature is made.
+//!
+//! `Signer` futures must always be `Send`, while `ConsentGate` futures need
+//! not be on `wasm32`, so `GatedSigner` only implements `Signer` on other
+//! targets. On `wasm32`, call [`GatedSigner::consent`] before signing with
+//! [`GatedSigner::signer`].
+
+use std::fmt::{self, Display};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::provider::Algorithm;
+use crate::provider::{ConsentGate, Result, Signer};
+
+/// A description of the signing operation the holder is being asked to
+/// approve.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SigningOperation {
+    /// Proof of possession of key material to be sent with a credential
+    /// request.
+    ProofOfPossession {
+        /// The credential issuer the proof is for.
+        credential_issuer: String,
+    },
+
+    /// A Verifiable Presentation to be sent to a verifier.
+    Presentation {
+        /// The client ID of the verifier.
+        client_id: String,
+    },
+
+    /// A self-issued ID token to be sent to a relying party.
+    IdToken {
+        /// The client ID of the relying party.
+        client_id: String,
+    },
+}
+
+/// The holder's response to a request for consent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Consent {
+    /// The holder has approved the signing operation.
+    Granted,
+
+    /// The holder has refused the signing operation.
+    Refused,
+}
+
+/// Error returned by a [`GatedSigner`] when the holder refuses consent.
+///
+/// Applications can detect a refusal (as opposed to a signing failure) using
+/// `anyhow::Error::downcast_ref::<ConsentRefused>()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsentRefused(pub SigningOperation);
+
+impl Display for ConsentRefused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "holder refused consent for signing operation: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ConsentRefused {}
+
+/// A `Signer` that asks a `ConsentGate` for the holder's approval before each
+/// signature.
+///
+/// Key information (verifying key, algorithm and verification method) is
+/// passed through from the wrapped signer without asking for consent.
+#[derive(Debug)]
+pub struct GatedSigner<'a, S, G> {
+    signer: &'a S,
+    gate: &'a G,
+    operation: SigningOperation,
+}
+
+impl<'a, S: Signer, G: ConsentGate> GatedSigner<'a, S, G> {
+    /// Wrap a signer so the gate is consulted before signing the specified
+    /// operation.
+    #[must_use]
+    pub const fn new(signer: &'a S, gate: &'a G, operation: SigningOperation) -> Self {
+        Self {
+            signer,
+            gate,
+            operation,
+        }
+    }
+
+    /// The operation the holder will be asked to consent to.
+    #[must_use]
+    pub const fn operation(&self) -> &SigningOperation {
+        &self.operation
+    }
+
+    /// The wrapped signer.
+    #[must_use]
+    pub const fn signer(&self) -> &S {
+        self.signer
+    }
+
+    /// Ask the gate for the holder's approval of the operation.
+    ///
+    /// # Errors
+    /// Will return a [`ConsentRefused`] error if the holder refuses, or an
+    /// error if consent could not be obtained.
+    pub async fn consent(&self) -> Result<()> {
+        match self.gate.consent(&self.operation).await? {
+            Consent::Granted => Ok(()),
+            Consent::Refused => Err(ConsentRefused(self.operation.clone()).into()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<S: Signer, G: ConsentGate> Signer for GatedSigner<'_, S, G> {
+    async fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        self.consent().await?;
+        self.signer.try_sign(msg).await
+    }
+
+    async fn verifying_key(&self) -> Result<Vec<u8>> {
+        self.signer.verifying_key().await
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        self.signer.algorithm()
+    }
+
+    async fn verification_method(&self) -> Result<String> {
+        self.signer.verification_method().await
+    }
+}
@@ -0,0 +1,79 @@
+//! # Wallet Context
+//!
+//! A `WalletContext` identifies the user profile (and, optionally, the tenant)
+//! that a flow or provider call is operating on behalf of. This allows a single
+//! SDK instance to host multiple user profiles with isolated credentials, keys
+//! and histories, such as on a shared device or in an enterprise wallet.
+//!
+//! Flows carry their context from start to finish so the application always
+//! knows which profile a flow belongs to. Providers implementing the
+//! `ContextScoped` trait can be narrowed to a single context before being
+//! used with a flow.
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The profile identifier used when an application does not host multiple
+/// profiles.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Identifies the profile (and tenant) a flow or provider is scoped to.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub struct WalletContext {
+    /// The user profile identifier.
+    pub profile_id: String,
+
+    /// The tenant identifier, for deployments hosting profiles for more than
+    /// one organization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+}
+
+impl Default for WalletContext {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROFILE)
+    }
+}
+
+impl WalletContext {
+    /// Create a context for the specified profile.
+    #[must_use]
+    pub fn new(profile_id: impl Into<String>) -> Self {
+        Self {
+            profile_id: profile_id.into(),
+            tenant_id: None,
+        }
+    }
+
+    /// Set the tenant the profile belongs to.
+    #[must_use]
+    pub fn tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    /// Namespace a storage key (or similar identifier) to this context so
+    /// records belonging to different profiles cannot collide.
+    ///
+    /// The tenant and profile identifiers are length-prefixed, so identifiers
+    /// containing separators cannot be mistaken for another context. Keys for
+    /// a context share the prefix `scoped_key("")`.
+    #[must_use]
+    pub fn scoped_key(&self, key: &str) -> String {
+        let profile = format!("{}:{}", self.profile_id.len(), self.profile_id);
+        self.tenant_id.as_ref().map_or_else(
+            || format!("{profile}/{key}"),
+            |tenant_id| format!("{}:{tenant_id}:{profile}/{key}", tenant_id.len()),
+        )
+    }
+}
+
+impl Display for WalletContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.tenant_id {
+            Some(tenant_id) => write!(f, "{tenant_id}/{}", self.profile_id),
+            None => write!(f, "{}", self.profile_id),
+        }
+    }
+}
@@ -4,18 +4,34 @@
 //! They do not follow any prescribed standard but where appropriate are
 //! convertible to standard types.
 
+use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::fmt::{self, Debug};
 
-use chrono::{DateTime, Utc};
+use anyhow::anyhow;
+use chrono::{DateTime, TimeDelta, Utc};
 use credibil_vc::issuer::{Claim, CredentialDisplay, CredentialSubject};
 use credibil_vc::verifier::Claims;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
+use crate::clock::TimeValidator;
+use crate::error::InvalidTime;
+use crate::jwt_vc;
+use crate::redact::{self, REDACTED};
+
+/// The `@context` URL identifying a Verifiable Credentials Data Model 1.1
+/// credential.
+pub const VCDM_1_1_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// The `@context` URL identifying a Verifiable Credentials Data Model 2.0
+/// credential.
+pub const VCDM_2_0_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
 /// A set of claims for a subject (holder).
 ///
 /// (Some credentials can be issued to multiple subjects).
-#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SubjectClaims {
     /// An identifier of the subject (holder) of the claims.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -25,6 +41,16 @@ pub struct SubjectClaims {
     pub claims: Map<String, Value>,
 }
 
+/// Claim values are personal data so only claim names are printed.
+impl Debug for SubjectClaims {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubjectClaims")
+            .field("id", &self.id)
+            .field("claims", &self.claims.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
 impl From<CredentialSubject> for SubjectClaims {
     fn from(subject: CredentialSubject) -> Self {
         Self {
@@ -36,19 +62,26 @@ impl From<CredentialSubject> for SubjectClaims {
 
 /// The Credential model contains information about a credential owned by the
 /// Wallet.
-#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Credential {
     /// Credential `id` is the credential's unique identifier
     /// (from Verifiable Credential `id` or generated if credential has no
     /// `id`).
     pub id: String,
 
-    /// The credential issuer ID.
+    /// The credential issuer URL (`credential_issuer` in the issuer's
+    /// metadata), rather than the DID that signed the credential.
     pub issuer: String,
 
     /// The credential issuer's name. (from the issuer's metadata).
     pub issuer_name: String,
 
+    /// Issuer branding captured from the issuer's metadata at issuance, one
+    /// entry per locale, so the credential can be rendered without fetching
+    /// the metadata again.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issuer_display: Vec<IssuerDisplay>,
+
     /// The Verifiable Credential as issued, for use in Presentation
     /// Submissions. This could be a base64-encoded JWT or 'stringified'
     /// JSON.
@@ -63,6 +96,11 @@ pub struct Credential {
     /// formatted.
     pub format: String,
 
+    /// The version of the W3C Verifiable Credentials Data Model the credential
+    /// was issued under.
+    #[serde(default)]
+    pub data_model: DataModel,
+
     /// Claim definitions that can be used for displaying the credential.
     pub claim_definitions: Option<HashMap<String, Claim>>,
 
@@ -93,6 +131,44 @@ pub struct Credential {
     /// url in the display section of the metadata.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub background: Option<ImageData>,
+
+    /// Identifier of the holder's key the credential is bound to. Set by the
+    /// wallet to the key used to sign the proof of possession at issuance so
+    /// the same key can be used to sign presentations of the credential.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    /// Restrictions set by the wallet on when the credential can be
+    /// presented.
+    #[serde(default, skip_serializing_if = "SharingPolicy::is_unrestricted")]
+    pub sharing_policy: SharingPolicy,
+
+    /// A tag protecting the integrity of the stored record, set when the
+    /// credential is saved through [`crate::integrity::Protected`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+}
+
+/// The issued credential and claim values are redacted. Display metadata and
+/// images are omitted.
+impl Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credential")
+            .field("id", &self.id)
+            .field("issuer", &self.issuer)
+            .field("issuer_name", &self.issuer_name)
+            .field("issued", &REDACTED)
+            .field("type_", &self.type_)
+            .field("format", &self.format)
+            .field("data_model", &self.data_model)
+            .field("subject_claims", &self.subject_claims)
+            .field("issuance_date", &self.issuance_date)
+            .field("valid_from", &self.valid_from)
+            .field("valid_until", &self.valid_until)
+            .field("key_id", &self.key_id)
+            .field("sharing_policy", &self.sharing_policy)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Get the claims on the VC as a JSON object.
@@ -108,7 +184,180 @@ impl Claims for Credential {
     }
 }
 
+/// A stored credential without its issued form or images, which can be large
+/// (for example, an mdoc with a portrait).
+///
+/// Credential storers list and search metadata (see
+/// [`crate::provider::CredentialStorer::list`]) so the full credential is only
+/// loaded when it is presented.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CredentialMetadata {
+    /// The credential's unique identifier.
+    pub id: String,
+
+    /// The credential issuer URL (`credential_issuer` in the issuer's
+    /// metadata), rather than the DID that signed the credential.
+    pub issuer: String,
+
+    /// The credential issuer's name.
+    pub issuer_name: String,
+
+    /// Issuer branding captured from the issuer's metadata at issuance.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issuer_display: Vec<IssuerDisplay>,
+
+    /// The credential type.
+    #[serde(rename = "type")]
+    pub type_: Vec<String>,
+
+    /// Credential format.
+    pub format: String,
+
+    /// The version of the W3C Verifiable Credentials Data Model the credential
+    /// was issued under.
+    #[serde(default)]
+    pub data_model: DataModel,
+
+    /// Claim definitions that can be used for displaying the credential.
+    pub claim_definitions: Option<HashMap<String, Claim>>,
+
+    /// Claims for one or more subjects (holders).
+    pub subject_claims: Vec<SubjectClaims>,
+
+    /// The date the credential was issued.
+    pub issuance_date: DateTime<Utc>,
+
+    /// The date the credential is valid from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<DateTime<Utc>>,
+
+    /// The date the credential is valid until (expiry).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+
+    /// Display information from the issuer's metadata for this credential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<Vec<CredentialDisplay>>,
+
+    /// Identifier of the holder's key the credential is bound to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+
+    /// Restrictions set by the wallet on when the credential can be
+    /// presented.
+    #[serde(default, skip_serializing_if = "SharingPolicy::is_unrestricted")]
+    pub sharing_policy: SharingPolicy,
+}
+
+impl From<Credential> for CredentialMetadata {
+    fn from(credential: Credential) -> Self {
+        Self {
+            id: credential.id,
+            issuer: credential.issuer,
+            issuer_name: credential.issuer_name,
+            issuer_display: credential.issuer_display,
+            type_: credential.type_,
+            format: credential.format,
+            data_model: credential.data_model,
+            claim_definitions: credential.claim_definitions,
+            subject_claims: credential.subject_claims,
+            issuance_date: credential.issuance_date,
+            valid_from: credential.valid_from,
+            valid_until: credential.valid_until,
+            display: credential.display,
+            key_id: credential.key_id,
+            sharing_policy: credential.sharing_policy,
+        }
+    }
+}
+
+/// Get the claims on the credential metadata as a JSON object, so credential
+/// storers can match metadata against presentation requests.
+impl Claims for CredentialMetadata {
+    fn to_json(&self) -> anyhow::Result<serde_json::Value> {
+        serde_json::to_value(self).map_err(Into::into)
+    }
+}
+
 impl Credential {
+    /// The issuer display information for the preferred locale, falling back
+    /// to the first entry when there is no entry for the locale (or no locale
+    /// is given).
+    #[must_use]
+    pub fn issuer_display_for(&self, locale: Option<&str>) -> Option<&IssuerDisplay> {
+        locale
+            .and_then(|locale| {
+                self.issuer_display.iter().find(|display| display.locale.as_deref() == Some(locale))
+            })
+            .or_else(|| self.issuer_display.first())
+    }
+
+    /// A view of the credential suitable for logging, with the issued
+    /// credential and claim values redacted.
+    #[must_use]
+    pub fn redacted(&self) -> Value {
+        redact::redacted(self)
+    }
+
+    /// Whether the credential is within its validity period at the given time.
+    /// A credential without a `valid_from` date is valid from issuance and one
+    /// without a `valid_until` date does not expire.
+    #[must_use]
+    pub fn is_valid_at(&self, at: DateTime<Utc>) -> bool {
+        let from = self.valid_from.unwrap_or(self.issuance_date);
+        at >= from && self.valid_until.is_none_or(|until| at < until)
+    }
+
+    /// Whether the credential has passed its `valid_until` date, allowing for
+    /// clock skew (see [`crate::clock`]).
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        TimeValidator::default().is_expired(self.valid_until)
+    }
+
+    /// Check the credential is within its validity period at the time given
+    /// by the validator's clock, allowing for its clock skew leeway.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidTime`] error if the credential is not yet valid or has expired.
+    pub fn check_validity(&self, times: &TimeValidator) -> Result<(), InvalidTime> {
+        let from = self.valid_from.unwrap_or(self.issuance_date);
+        times.check_period(Some(from), self.valid_until)
+    }
+
+    /// Compare the credential with one replacing it (for example, a refreshed
+    /// or re-issued credential) so the user can be shown what changed before
+    /// the stored credential is overwritten.
+    ///
+    /// Subjects are compared in order. Nested claims are compared claim by
+    /// claim, while arrays are compared as a whole.
+    #[must_use]
+    pub fn diff(&self, replacement: &Self) -> CredentialDiff {
+        let mut diff = CredentialDiff::default();
+
+        let count = self.subject_claims.len().max(replacement.subject_claims.len());
+        for i in 0..count {
+            let before = self.subject_claims.get(i);
+            let after = replacement.subject_claims.get(i);
+            let subject = after.or(before).and_then(|s| s.id.clone());
+            let empty = Map::new();
+            diff_claims(
+                &mut diff,
+                subject.as_deref(),
+                &[],
+                before.map_or(&empty, |s| &s.claims),
+                after.map_or(&empty, |s| &s.claims),
+            );
+        }
+
+        let before = ValidityPeriod::of(self);
+        let after = ValidityPeriod::of(replacement);
+        if before != after {
+            diff.validity = Some(ValidityChange { before, after });
+        }
+        diff
+    }
+
     /// Convenience method to display the claims and their values as a vector
     /// of labels and values, where the labels honour locale display
     /// configuration.
@@ -157,18 +406,15 @@ impl Credential {
                 if let Some(claim_def) = self.claim_definitions.as_ref().and_then(|cd| cd.get(name))
                 {
                     if let Claim::Entry(def) = claim_def {
+                        // Fall back to the default display (no locale) if the issuer
+                        // doesn't provide a translation for the requested locale.
                         let locale_display = def.display.as_ref().and_then(|display| {
-                            locale.as_ref().map_or_else(
-                                || {
-                                    Some(
-                                        display
-                                            .iter()
-                                            .find(|d| d.locale.is_none())
-                                            .unwrap_or_else(|| &display[0]),
-                                    )
-                                },
-                                |loc| display.iter().find(|d| d.locale.as_deref() == Some(loc)),
-                            )
+                            locale
+                                .and_then(|loc| {
+                                    display.iter().find(|d| d.locale.as_deref() == Some(loc))
+                                })
+                                .or_else(|| display.iter().find(|d| d.locale.is_none()))
+                                .or_else(|| display.first())
                         });
                         match locale_display {
                             Some(display) => claim_set.push((
@@ -200,6 +446,474 @@ impl Credential {
     }
 }
 
+/// Version of the W3C Verifiable Credentials Data Model.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DataModel {
+    /// VCDM 1.1: validity is given by `issuanceDate` and `expirationDate`.
+    #[serde(rename = "1.1")]
+    V1_1,
+
+    /// VCDM 2.0: validity is given by `validFrom` and `validUntil`.
+    #[default]
+    #[serde(rename = "2.0")]
+    V2_0,
+}
+
+/// The data model version and validity period of an issued credential,
+/// normalized from either VCDM 1.1 or 2.0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Validity {
+    /// The data model version the credential was issued under.
+    pub data_model: DataModel,
+
+    /// The date the credential is valid from.
+    pub valid_from: Option<DateTime<Utc>>,
+
+    /// The date the credential is valid until (expiry).
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl Validity {
+    /// Read the data model version and validity period from the claims of a
+    /// JWT-encoded credential. The signature is not verified, so the
+    /// credential should already have been verified by the caller.
+    ///
+    /// Properties of legacy JWT-VCs carried in registered claims are restored
+    /// first (see [`crate::jwt_vc`]). The version is taken from the base
+    /// `@context` of the credential, falling back to the presence of a 1.1
+    /// `issuanceDate`. Issuers that mix versions are tolerated by reading the
+    /// other version's date properties when the expected ones are missing.
+    /// For 1.1 credentials, the JWT `exp` claim stands in for an
+    /// `expirationDate` removed from the `vc` claim.
+    ///
+    /// # Errors
+    /// Will return an error if the token is not a JWT, the `vc` claim is
+    /// missing, or a validity date cannot be parsed.
+    pub fn from_jwt(token: &str) -> anyhow::Result<Self> {
+        let claims = jwt_vc::claims(token)?;
+        let vc = &jwt_vc::credential(&claims)?;
+
+        let context = match &vc["@context"] {
+            Value::Array(contexts) => contexts.first().and_then(Value::as_str),
+            context => context.as_str(),
+        };
+        let data_model = match context {
+            Some(VCDM_1_1_CONTEXT) => DataModel::V1_1,
+            Some(VCDM_2_0_CONTEXT) => DataModel::V2_0,
+            _ if vc.get("issuanceDate").is_some() => DataModel::V1_1,
+            _ => DataModel::default(),
+        };
+
+        let validity = match data_model {
+            DataModel::V1_1 => Self {
+                data_model,
+                valid_from: date(vc, "issuanceDate")?.or(date(vc, "validFrom")?),
+                valid_until: date(vc, "expirationDate")?
+                    .or(date(vc, "validUntil")?)
+                    .or_else(|| timestamp(&claims, "exp")),
+            },
+            DataModel::V2_0 => Self {
+                data_model,
+                valid_from: date(vc, "validFrom")?.or(date(vc, "issuanceDate")?),
+                valid_until: date(vc, "validUntil")?.or(date(vc, "expirationDate")?),
+            },
+        };
+        Ok(validity)
+    }
+}
+
+// Parse an RFC 3339 date property, if present. A `null` date (as serialized
+// for an unset optional date) is treated as absent.
+fn date(value: &Value, name: &str) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let Some(date) = value.get(name).filter(|date| !date.is_null()) else {
+        return Ok(None);
+    };
+    let Some(date) = date.as_str() else {
+        return Err(anyhow!("`{name}` is not a string"));
+    };
+    let date = DateTime::parse_from_rfc3339(date).map_err(|e| anyhow!("invalid `{name}`: {e}"))?;
+    Ok(Some(date.with_timezone(&Utc)))
+}
+
+// Read a JWT `NumericDate` claim, if present.
+fn timestamp(claims: &Value, name: &str) -> Option<DateTime<Utc>> {
+    claims.get(name).and_then(Value::as_i64).and_then(|ts| DateTime::from_timestamp(ts, 0))
+}
+
+/// Restrictions the wallet (or holder) places on presenting a credential.
+/// Credentials that do not permit a presentation are left out of the
+/// candidates offered to the holder (see [`Sharing`]).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SharingPolicy {
+    /// Never present the credential without the holder selecting it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub never_auto_present: bool,
+
+    /// Only present the credential once the holder has been verified (for
+    /// example, by biometric or PIN).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_user_verification: bool,
+
+    /// When set, only present the credential to verifiers with one of these
+    /// client IDs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_verifiers: Option<Vec<String>>,
+}
+
+impl SharingPolicy {
+    /// Whether the policy places no restrictions on presenting the credential.
+    #[must_use]
+    pub fn is_unrestricted(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether the policy permits presenting the credential to the verifier
+    /// in the circumstances described by `sharing`.
+    #[must_use]
+    pub fn permits(&self, verifier: &str, sharing: Sharing) -> bool {
+        !(self.never_auto_present && sharing.automatic)
+            && (!self.require_user_verification || sharing.user_verified)
+            && self
+                .trusted_verifiers
+                .as_ref()
+                .is_none_or(|trusted| trusted.iter().any(|v| v == verifier))
+    }
+}
+
+/// The circumstances in which credentials are being selected for
+/// presentation, checked against each credential's [`SharingPolicy`].
+///
+/// The default is an interactive selection by a holder who has not been
+/// verified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sharing {
+    /// Credentials are being selected for presentation without the holder's
+    /// involvement.
+    pub automatic: bool,
+
+    /// The holder has been verified (for example, by biometric or PIN).
+    pub user_verified: bool,
+}
+
+/// What changed between a stored credential and the credential replacing it
+/// (see [`Credential::diff`]).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CredentialDiff {
+    /// Claims in the replacement credential only.
+    pub added: Vec<ClaimChange>,
+
+    /// Claims in the stored credential only.
+    pub removed: Vec<ClaimChange>,
+
+    /// Claims with a different value in the replacement credential.
+    pub changed: Vec<ClaimChange>,
+
+    /// The change in validity period, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity: Option<ValidityChange>,
+}
+
+impl CredentialDiff {
+    /// Whether the replacement credential is unchanged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.validity.is_none()
+    }
+}
+
+/// A claim added, removed or changed when a credential is replaced.
+#[derive(Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ClaimChange {
+    /// The subject the claim belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    /// The path to the claim: its name, preceded by the names of any claims
+    /// it is nested in.
+    pub path: Vec<String>,
+
+    /// The value in the stored credential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+
+    /// The value in the replacement credential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// Claim values are personal data so only the claim path is printed.
+impl Debug for ClaimChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClaimChange")
+            .field("subject", &self.subject)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The period a credential is valid for.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ValidityPeriod {
+    /// The date the credential is valid from (its issuance date if it has no
+    /// `valid_from` date).
+    pub valid_from: DateTime<Utc>,
+
+    /// The date the credential is valid until, if it expires.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl ValidityPeriod {
+    fn of(credential: &Credential) -> Self {
+        Self {
+            valid_from: credential.valid_from.unwrap_or(credential.issuance_date),
+            valid_until: credential.valid_until,
+        }
+    }
+}
+
+/// A change in validity period when a credential is replaced.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ValidityChange {
+    /// The validity period of the stored credential.
+    pub before: ValidityPeriod,
+
+    /// The validity period of the replacement credential.
+    pub after: ValidityPeriod,
+}
+
+// Compare two sets of claims, recursing into nested claims.
+fn diff_claims(
+    diff: &mut CredentialDiff, subject: Option<&str>, path: &[String], before: &Map<String, Value>,
+    after: &Map<String, Value>,
+) {
+    let change = |name: &str, before: Option<&Value>, after: Option<&Value>| {
+        let mut path = path.to_vec();
+        path.push(name.to_string());
+        ClaimChange {
+            subject: subject.map(String::from),
+            path,
+            before: before.cloned(),
+            after: after.cloned(),
+        }
+    };
+
+    for (name, old) in before {
+        match (old, after.get(name)) {
+            (_, None) => diff.removed.push(change(name, Some(old), None)),
+            (Value::Object(old), Some(Value::Object(new))) => {
+                let mut nested = path.to_vec();
+                nested.push(name.clone());
+                diff_claims(diff, subject, &nested, old, new);
+            }
+            (_, Some(new)) if new != old => {
+                diff.changed.push(change(name, Some(old), Some(new)));
+            }
+            _ => {}
+        }
+    }
+    for (name, new) in after {
+        if !before.contains_key(name) {
+            diff.added.push(change(name, None, Some(new)));
+        }
+    }
+}
+
+/// Credentials expiring within this period are grouped as expiring soon by
+/// [`organize`].
+pub const EXPIRING_SOON: TimeDelta = TimeDelta::days(30);
+
+// The groups used for `GroupBy::Expiry`, in display order.
+const EXPIRY_GROUPS: [(&str, &str); 4] = [
+    ("expired", "Expired"),
+    ("expiring_soon", "Expiring soon"),
+    ("valid", "Valid"),
+    ("no_expiry", "No expiry"),
+];
+
+/// How [`organize`] groups credentials.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One group per issuer, ordered by issuer name.
+    #[default]
+    Issuer,
+
+    /// One group per credential type, ordered by name.
+    Type,
+
+    /// Expired, expiring soon (see [`EXPIRING_SOON`]), valid, and
+    /// non-expiring credentials, in that order.
+    Expiry,
+}
+
+/// How [`organize`] orders the credentials within each group.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Most recently issued first.
+    #[default]
+    Newest,
+
+    /// Least recently issued first.
+    Oldest,
+
+    /// By credential display name.
+    Name,
+
+    /// Soonest to expire first, with non-expiring credentials last.
+    Expiring,
+}
+
+/// A group of credentials ready to render in a wallet's list view.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CredentialGroup {
+    /// A stable key for the group: the issuer ID, the credential type, or
+    /// the expiry status (`expired`, `expiring_soon`, `valid`, or
+    /// `no_expiry`).
+    pub key: String,
+
+    /// The group's heading: the issuer name, the credential display name, or
+    /// the expiry status.
+    pub label: String,
+
+    /// The credentials in the group.
+    pub credentials: Vec<Credential>,
+}
+
+/// Group and sort credentials for display, so wallet list views can render
+/// the stored credentials without reimplementing the grouping.
+#[must_use]
+pub fn organize(
+    credentials: Vec<Credential>, group_by: GroupBy, order: SortOrder,
+) -> Vec<CredentialGroup> {
+    let now = Utc::now();
+    let mut groups: Vec<CredentialGroup> = Vec::new();
+    for credential in credentials {
+        let (key, label) = group_key(&credential, group_by, now);
+        match groups.iter_mut().find(|group| group.key == key) {
+            Some(group) => group.credentials.push(credential),
+            None => groups.push(CredentialGroup {
+                key,
+                label,
+                credentials: vec![credential],
+            }),
+        }
+    }
+
+    if group_by == GroupBy::Expiry {
+        groups.sort_by_key(|group| EXPIRY_GROUPS.iter().position(|(key, _)| *key == group.key));
+    } else {
+        groups.sort_by_cached_key(|group| (group.label.to_lowercase(), group.key.clone()));
+    }
+    for group in &mut groups {
+        let credentials = &mut group.credentials;
+        match order {
+            SortOrder::Newest => credentials.sort_by_key(|c| Reverse(c.issuance_date)),
+            SortOrder::Oldest => credentials.sort_by_key(|c| c.issuance_date),
+            SortOrder::Name => credentials.sort_by_cached_key(|c| display_name(c).to_lowercase()),
+            SortOrder::Expiring => {
+                credentials.sort_by_key(|c| (c.valid_until.is_none(), c.valid_until));
+            }
+        }
+    }
+    groups
+}
+
+// The key and label of the group a credential belongs to.
+fn group_key(credential: &Credential, group_by: GroupBy, now: DateTime<Utc>) -> (String, String) {
+    match group_by {
+        GroupBy::Issuer => {
+            let label = if credential.issuer_name.is_empty() {
+                &credential.issuer
+            } else {
+                &credential.issuer_name
+            };
+            (credential.issuer.clone(), label.clone())
+        }
+        GroupBy::Type => (primary_type(credential).to_string(), display_name(credential)),
+        GroupBy::Expiry => {
+            let (key, label) = match credential.valid_until {
+                Some(until) if until <= now => EXPIRY_GROUPS[0],
+                Some(until) if until - now <= EXPIRING_SOON => EXPIRY_GROUPS[1],
+                Some(_) => EXPIRY_GROUPS[2],
+                None => EXPIRY_GROUPS[3],
+            };
+            (key.to_string(), label.to_string())
+        }
+    }
+}
+
+// The most specific credential type: the first other than
+// `VerifiableCredential`.
+fn primary_type(credential: &Credential) -> &str {
+    let types = &credential.type_;
+    types
+        .iter()
+        .find(|type_| *type_ != "VerifiableCredential")
+        .or_else(|| types.first())
+        .map_or("", String::as_str)
+}
+
+// The credential's name from the issuer's metadata, falling back to its type.
+fn display_name(credential: &Credential) -> String {
+    credential
+        .display
+        .as_ref()
+        .and_then(|display| display.first())
+        .map_or_else(|| primary_type(credential).to_string(), |display| display.name.clone())
+}
+
+/// Issuer display information for a locale, captured from the `display`
+/// section of the issuer's metadata.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuerDisplay {
+    /// The issuer's display name.
+    pub name: String,
+
+    /// The language tag (BCP 47) the display information is for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+
+    /// The issuer's logo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<IssuerLogo>,
+
+    /// The issuer's brand background color (CSS color value).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+
+    /// The issuer's brand text color (CSS color value).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<String>,
+}
+
+impl IssuerDisplay {
+    /// Capture display information from the issuer metadata's `display`
+    /// section, which may be a single object or one object per locale.
+    /// Entries without a name are skipped.
+    #[cfg(feature = "issuance")]
+    pub(crate) fn from_metadata(display: Value) -> Vec<Self> {
+        let entries = match display {
+            Value::Array(entries) => entries,
+            entry => vec![entry],
+        };
+        entries.into_iter().filter_map(|entry| serde_json::from_value(entry).ok()).collect()
+    }
+}
+
+/// The location of an issuer's logo.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuerLogo {
+    /// The URI of the logo image.
+    pub uri: String,
+
+    /// Alternative text for the logo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+}
+
 /// Image information for a credential.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ImageData {
@@ -237,6 +951,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn test_claims_display() {
         let json = serde_json::json!({
             "id": "http://credibil.io/credentials/EmployeeIDCredential",
@@ -369,5 +1084,8 @@ mod tests {
         assert_yaml_snapshot!("claims_display_default", &default, {
             "." => insta::sorted_redaction(),
         });
+        let untranslated =
+            credential.claims_display(credential.subject_claims[0].id.as_deref(), Some("fr-FR"));
+        assert_eq!(untranslated, default);
     }
 }
@@ -0,0 +1,756 @@
+//! # Errors
+//!
+//! Issuers and verifiers report errors using the OAuth 2.0 error response
+//! format (`{"error": "invalid_grant", "error_description": "..."}`), extended
+//! by `OpenID` for Verifiable Credentials with fields such as `c_nonce` and
+//! `interval`.
+//!
+//! Provider implementations can parse error response bodies into an
+//! [`OAuthError`] and return it as their error. Because the crate uses
+//! `anyhow`, the error is carried through flows unchanged and applications
+//! can recover it with [`OAuthError::from_error`] to react programmatically:
+//! retrying, re-prompting for a PIN, or regenerating a proof.
+//!
+//! Services that are rate limiting the wallet respond with HTTP status 429
+//! (or 503) and a `Retry-After` header. Provider implementations can return a
+//! [`RetryLater`] error for these responses, and applications can use
+//! [`RetryLater::from_error`] to schedule a retry rather than failing the
+//! flow. Deferred issuance `interval` hints are surfaced the same way.
+//!
+//! Providers that cannot reach a service at all (the device is offline)
+//! can return an [`Offline`] error so the request can be queued and sent
+//! later (see [`crate::outbox`]).
+//!
+//! Work aborted using a [`crate::cancel::CancellationToken`] returns a
+//! [`Cancelled`] error.
+//!
+//! A presentation flow for a verifier that accepts no format the wallet can
+//! present returns a [`NoCommonFormat`] error before anything is signed.
+//!
+//! A verifier whose DID does not control the origin its request came from
+//! returns a [`DomainNotLinked`] error (see [`crate::linkage`]).
+//!
+//! Bearer material used with a party other than the one that issued it
+//! returns an [`OriginMismatch`] error (see [`crate::bearer`]).
+//!
+//! A proof of possession that would reuse a `c_nonce` returns a
+//! [`NonceReused`] error (see [`crate::provider::NonceCache`]).
+//!
+//! A token request retried after the pre-authorized code has been exchanged
+//! returns a [`CodeExchanged`] error rather than resending the code (see
+//! [`crate::issuance::IssuanceFlow::checked_token_request`]).
+//!
+//! A verifier whose reader certificate is not trusted, or does not identify
+//! the verifier, returns a [`ReaderNotAuthenticated`] error (see
+//! [`crate::presentation::reader`]).
+//!
+//! A request object whose verified signatures do not meet the wallet's
+//! signature policy returns a [`SignaturePolicyUnmet`] error (see
+//! [`crate::presentation::signatures`]).
+//!
+//! A presentation request without exactly one of `response_uri` (for the
+//! `direct_post` response modes) or `redirect_uri` (for other modes), or
+//! whose endpoint does not use `https`, returns an
+//! [`InvalidResponseEndpoint`] error (see [`crate::presentation::endpoint`]).
+//!
+//! A stored credential whose record has been modified outside the SDK returns
+//! a [`RecordTampered`] error when loaded (see [`crate::integrity`]).
+//!
+//! A credential the wallet's policy does not allow returns a [`PolicyDenied`]
+//! error when offered or presented (see [`crate::policy`]).
+//!
+//! A step in a flow that has passed its deadline returns a [`FlowTimedOut`]
+//! error (see [`crate::agent::Deadlines`]).
+//!
+//! A token that is not yet valid or has expired, or a credential outside its
+//! validity period, allowing for clock skew, returns an [`InvalidTime`] error
+//! (see [`crate::clock`]).
+//!
+//! A holder key that cannot satisfy a credential configuration's binding
+//! methods, proof types or proof signing algorithms returns a
+//! [`KeyBindingUnsupported`] error before a proof is sent to the issuer (see
+//! [`crate::issuance::binding`]).
+
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+
+use crate::bearer::Nonce;
+
+/// An OAuth 2.0 (or `OpenID` for Verifiable Credentials) error returned by an
+/// issuer or verifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OAuthError {
+    /// The error code.
+    pub code: ErrorCode,
+
+    /// A human-readable description of the error.
+    pub description: Option<String>,
+
+    /// A fresh `c_nonce` to use when creating a new proof.
+    pub c_nonce: Option<String>,
+
+    /// The lifetime of the `c_nonce`, in seconds.
+    pub c_nonce_expires_in: Option<i64>,
+
+    /// The minimum time, in seconds, to wait before retrying a request.
+    pub interval: Option<i64>,
+}
+
+/// OAuth 2.0 and `OpenID` for Verifiable Credentials error codes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// `invalid_request`
+    InvalidRequest,
+
+    /// `invalid_client`
+    InvalidClient,
+
+    /// `invalid_grant`. In the pre-authorized code flow this is returned when
+    /// the holder provides the wrong PIN.
+    InvalidGrant,
+
+    /// `unauthorized_client`
+    UnauthorizedClient,
+
+    /// `unsupported_grant_type`
+    UnsupportedGrantType,
+
+    /// `invalid_scope`
+    InvalidScope,
+
+    /// `invalid_authorization_details`
+    InvalidAuthorizationDetails,
+
+    /// `access_denied`
+    AccessDenied,
+
+    /// `unsupported_response_type`
+    UnsupportedResponseType,
+
+    /// `server_error`
+    ServerError,
+
+    /// `temporarily_unavailable`
+    TemporarilyUnavailable,
+
+    /// `invalid_credential_request`
+    InvalidCredentialRequest,
+
+    /// `unsupported_credential_type`
+    UnsupportedCredentialType,
+
+    /// `unsupported_credential_format`
+    UnsupportedCredentialFormat,
+
+    /// `invalid_proof`
+    InvalidProof,
+
+    /// `invalid_nonce`
+    InvalidNonce,
+
+    /// `invalid_encryption_parameters`
+    InvalidEncryptionParameters,
+
+    /// `issuance_pending`
+    IssuancePending,
+
+    /// `invalid_transaction_id`
+    InvalidTransactionId,
+
+    /// `vp_formats_not_supported`
+    VpFormatsNotSupported,
+
+    /// `invalid_presentation_definition_uri`
+    InvalidPresentationDefinitionUri,
+
+    /// `invalid_presentation_definition_reference`
+    InvalidPresentationDefinitionReference,
+
+    /// `wallet_unavailable`
+    WalletUnavailable,
+
+    /// An error code not defined by the specifications.
+    Other(String),
+}
+
+/// The action an application can take to recover from an [`OAuthError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Recovery {
+    /// Retry the request, waiting at least `interval` seconds.
+    Retry {
+        /// The minimum time to wait, in seconds, if specified.
+        interval: Option<i64>,
+    },
+
+    /// Ask the holder to re-enter their PIN and request a new token.
+    ReenterPin,
+
+    /// Create a new proof using the `c_nonce` provided and retry the
+    /// credential request.
+    RegenerateProof {
+        /// The nonce to include in the new proof.
+        c_nonce: String,
+    },
+}
+
+// The default interval for deferred issuance, per the specification.
+const DEFAULT_INTERVAL: i64 = 5;
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+    error_description: Option<String>,
+    c_nonce: Option<String>,
+    c_nonce_expires_in: Option<i64>,
+    interval: Option<i64>,
+}
+
+impl OAuthError {
+    /// Parse an error response body. Returns `None` if the body is not a JSON
+    /// OAuth error response.
+    #[must_use]
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        let body: ErrorBody = serde_json::from_slice(body).ok()?;
+        Some(Self {
+            code: ErrorCode::from(body.error.as_str()),
+            description: body.error_description,
+            c_nonce: body.c_nonce,
+            c_nonce_expires_in: body.c_nonce_expires_in,
+            interval: body.interval,
+        })
+    }
+
+    /// Find an OAuth error in the chain of errors returned by a flow or
+    /// provider. Errors returned by `credibil-vc` (for example, by issuer or
+    /// verifier services embedded in the wallet) are also recognized.
+    #[must_use]
+    pub fn from_error(error: &anyhow::Error) -> Option<Self> {
+        error.chain().find_map(|e| {
+            if let Some(oauth) = e.downcast_ref::<Self>() {
+                return Some(oauth.clone());
+            }
+            let vc_error = e.downcast_ref::<credibil_vc::issuer::Error>()?;
+            Self::parse(vc_error.to_string().as_bytes())
+        })
+    }
+
+    /// The action an application can take to recover from the error, if any.
+    ///
+    /// An `invalid_grant` error is only recovered by re-entering the PIN when
+    /// `pin_sent` indicates the failed token request included one.
+    #[must_use]
+    pub fn recovery(&self, pin_sent: bool) -> Option<Recovery> {
+        match &self.code {
+            ErrorCode::IssuancePending => Some(Recovery::Retry {
+                interval: Some(self.interval.unwrap_or(DEFAULT_INTERVAL)),
+            }),
+            ErrorCode::TemporarilyUnavailable => Some(Recovery::Retry {
+                interval: self.interval,
+            }),
+            ErrorCode::InvalidGrant if pin_sent => Some(Recovery::ReenterPin),
+            ErrorCode::InvalidProof | ErrorCode::InvalidNonce => {
+                self.c_nonce.as_ref().map(|c_nonce| Recovery::RegenerateProof {
+                    c_nonce: c_nonce.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The fresh `c_nonce` provided with the error, bound to the credential
+    /// issuer that returned it and expiring after `c_nonce_expires_in`
+    /// seconds (from now). Use with
+    /// [`crate::issuance::IssuanceFlow::set_nonce`].
+    #[must_use]
+    pub fn nonce(&self, credential_issuer: &str) -> Option<Nonce> {
+        let nonce = Nonce::new(self.c_nonce.as_ref()?, credential_issuer);
+        Some(nonce.expires_in(self.c_nonce_expires_in, Utc::now()))
+    }
+}
+
+impl Display for OAuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{}: {description}", self.code),
+            None => write!(f, "{}", self.code),
+        }
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+/// An issuer or verifier asked the wallet to retry the request after a
+/// delay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryLater(pub Duration);
+
+impl RetryLater {
+    /// Determine whether an HTTP response asks the wallet to retry later:
+    /// status 429 (Too Many Requests) or 503 (Service Unavailable). The delay
+    /// is taken from the `Retry-After` header value, if provided, which may
+    /// be a number of seconds or an HTTP date.
+    #[must_use]
+    pub fn from_response(status: u16, retry_after: Option<&str>) -> Option<Self> {
+        if status != 429 && status != 503 {
+            return None;
+        }
+        let delay = retry_after.and_then(parse_retry_after);
+        if status == 503 && delay.is_none() {
+            return None;
+        }
+        Some(Self(delay.unwrap_or(Duration::from_secs(DEFAULT_INTERVAL.unsigned_abs()))))
+    }
+
+    /// Find a request to retry later in the chain of errors returned by a
+    /// flow or provider. OAuth errors that can be recovered from by retrying
+    /// (such as `issuance_pending` with an `interval`) are also recognized.
+    #[must_use]
+    pub fn from_error(error: &anyhow::Error) -> Option<Self> {
+        if let Some(retry) = error.chain().find_map(|e| e.downcast_ref::<Self>()) {
+            return Some(*retry);
+        }
+        let Recovery::Retry { interval } = OAuthError::from_error(error)?.recovery(false)? else {
+            return None;
+        };
+        let seconds = interval.unwrap_or(DEFAULT_INTERVAL).max(0).unsigned_abs();
+        Some(Self(Duration::from_secs(seconds)))
+    }
+
+    /// The time to wait before retrying.
+    #[must_use]
+    pub const fn delay(&self) -> Duration {
+        self.0
+    }
+}
+
+impl Display for RetryLater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "retry after {} seconds", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for RetryLater {}
+
+/// The issuer or verifier could not be reached, for example because the
+/// device has no network connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Offline;
+
+impl Display for Offline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("service unreachable")
+    }
+}
+
+impl std::error::Error for Offline {}
+
+/// The operation was cancelled using a [`crate::cancel::CancellationToken`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// The verifier and the wallet have no mutually supported presentation
+/// format (see [`crate::presentation::format`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoCommonFormat {
+    /// Formats advertised in the verifier's `vp_formats` metadata.
+    pub verifier: Vec<String>,
+
+    /// Formats in the wallet's metadata.
+    pub wallet: Vec<String>,
+}
+
+impl Display for NoCommonFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no mutually supported presentation format: verifier accepts [{}], wallet supports [{}]",
+            self.verifier.join(", "),
+            self.wallet.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NoCommonFormat {}
+
+/// The DID configuration published by an origin does not link it to the
+/// verifier's DID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DomainNotLinked {
+    /// The verifier's DID.
+    pub did: String,
+
+    /// The origin the DID is not linked to.
+    pub origin: String,
+}
+
+impl Display for DomainNotLinked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not linked to {}", self.origin, self.did)
+    }
+}
+
+impl std::error::Error for DomainNotLinked {}
+
+/// A proof of possession was not built because its `c_nonce` has already
+/// been used in a proof for the issuer. Get a fresh nonce from the issuer
+/// before retrying.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonceReused {
+    /// The credential issuer the nonce was issued by.
+    pub credential_issuer: String,
+}
+
+impl Display for NonceReused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "c_nonce from {} has already been used in a proof", self.credential_issuer)
+    }
+}
+
+impl std::error::Error for NonceReused {}
+
+/// Bearer material (an access token, nonce or code) was used with a party
+/// other than the issuer or verifier that issued it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OriginMismatch {
+    /// The kind of material (`Token`, `Nonce` or `Code`).
+    pub kind: String,
+
+    /// The issuer or verifier that issued the material.
+    pub origin: String,
+
+    /// The party the material was used with.
+    pub used_with: String,
+}
+
+impl Display for OriginMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from {} cannot be used with {}", self.kind, self.origin, self.used_with)
+    }
+}
+
+impl std::error::Error for OriginMismatch {}
+
+/// The pre-authorized code has already been exchanged for a token, so a token
+/// request would be rejected by the issuer with `invalid_grant`. Resume the
+/// flow from the existing token instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeExchanged {
+    /// The credential issuer the code was issued by.
+    pub credential_issuer: String,
+}
+
+impl Display for CodeExchanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pre-authorized code from {} has already been exchanged", self.credential_issuer)
+    }
+}
+
+impl std::error::Error for CodeExchanged {}
+
+/// A presentation request signed with a reader certificate could not be
+/// authenticated: the certificate chain or signature was not verified, or the
+/// certificate does not identify the verifier's `client_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReaderNotAuthenticated {
+    /// The verifier's client ID.
+    pub client_id: String,
+
+    /// Why the reader was not authenticated.
+    pub reason: String,
+}
+
+impl Display for ReaderNotAuthenticated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reader {} not authenticated: {}", self.client_id, self.reason)
+    }
+}
+
+impl std::error::Error for ReaderNotAuthenticated {}
+
+/// The verified signatures of a request object do not meet the wallet's
+/// signature policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignaturePolicyUnmet {
+    /// The policy that was not met.
+    pub policy: String,
+
+    /// The signers (DIDs) whose signatures were verified.
+    pub signers: Vec<String>,
+
+    /// Why the other signatures were not verified.
+    pub errors: Vec<String>,
+}
+
+impl Display for SignaturePolicyUnmet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request object signatures do not meet policy: {} required", self.policy)?;
+        if !self.errors.is_empty() {
+            write!(f, " ({})", self.errors.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SignaturePolicyUnmet {}
+
+/// A presentation request's response endpoint is invalid: the
+/// `response_uri` or `redirect_uri` it requires for its response mode is
+/// missing, both are present, or the endpoint does not use `https`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidResponseEndpoint {
+    /// The request's response mode.
+    pub response_mode: String,
+
+    /// Why the endpoint is invalid.
+    pub reason: String,
+}
+
+impl Display for InvalidResponseEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid response endpoint for `{}`: {}", self.response_mode, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidResponseEndpoint {}
+
+/// A stored credential record failed its integrity check: it has been
+/// modified outside the SDK, or was not saved through the SDK.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordTampered {
+    /// The ID of the credential.
+    pub id: String,
+}
+
+impl Display for RecordTampered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stored credential {} failed its integrity check", self.id)
+    }
+}
+
+impl std::error::Error for RecordTampered {}
+
+/// The wallet's policy denies a credential: it will not be accepted from an
+/// offer or presented to a verifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyDenied {
+    /// The credential issuer URL.
+    pub issuer: String,
+
+    /// The credential's types.
+    pub credential_types: Vec<String>,
+
+    /// The credential's format.
+    pub format: String,
+
+    /// The index of the rule that denied the credential, or `None` if it was
+    /// denied by default.
+    pub rule: Option<usize>,
+}
+
+impl Display for PolicyDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "policy denies {} credential from {}",
+            self.credential_types.join(", "),
+            self.issuer
+        )?;
+        match self.rule {
+            Some(rule) => write!(f, " (rule {rule})"),
+            None => write!(f, " (by default)"),
+        }
+    }
+}
+
+impl std::error::Error for PolicyDenied {}
+
+/// The flow did not complete before its deadline and can no longer be
+/// advanced. Start a new flow (for example, ask the issuer for a new offer).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlowTimedOut {
+    /// The ID of the flow.
+    pub id: String,
+
+    /// The deadline the flow missed.
+    pub deadline: DateTime<Utc>,
+}
+
+impl Display for FlowTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flow {} timed out at {}", self.id, self.deadline.to_rfc3339())
+    }
+}
+
+impl std::error::Error for FlowTimedOut {}
+
+/// A time claim of a token (`iat`, `nbf` or `exp`), or a bound of a
+/// credential's validity period (`valid_from` or `valid_until`), is not
+/// valid at the current time, even allowing for clock skew.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTime {
+    /// The claim (or validity bound) that failed.
+    pub claim: String,
+
+    /// The time given by the claim, or `None` if it is not a valid time.
+    pub at: Option<DateTime<Utc>>,
+
+    /// The current time, according to the clock used for the check.
+    pub now: DateTime<Utc>,
+
+    /// The clock skew allowed.
+    pub leeway: TimeDelta,
+}
+
+impl Display for InvalidTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(at) = self.at else {
+            return write!(f, "`{}` is not a valid time", self.claim);
+        };
+        write!(
+            f,
+            "`{}` ({}) is not valid at {} allowing {}s clock skew",
+            self.claim,
+            at.to_rfc3339(),
+            self.now.to_rfc3339(),
+            self.leeway.num_seconds()
+        )
+    }
+}
+
+impl std::error::Error for InvalidTime {}
+
+/// The holder's key cannot be bound to a credential configuration: the
+/// configuration does not support the key's binding method, the `jwt` proof
+/// type, or the key's algorithm for proofs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyBindingUnsupported {
+    /// The ID of the credential configuration.
+    pub configuration_id: String,
+
+    /// The verification method (key ID) of the holder's key.
+    pub verification_method: String,
+
+    /// The algorithm of the holder's key.
+    pub algorithm: String,
+
+    /// The configuration parameter the key does not satisfy
+    /// (`cryptographic_binding_methods_supported`, `proof_types_supported`
+    /// or `proof_signing_alg_values_supported`).
+    pub requirement: String,
+
+    /// The values the configuration supports for the parameter.
+    pub supported: Vec<String>,
+}
+
+impl Display for KeyBindingUnsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {} ({}) cannot be bound to `{}`: `{}` supports {}",
+            self.verification_method,
+            self.algorithm,
+            self.configuration_id,
+            self.requirement,
+            self.supported.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for KeyBindingUnsupported {}
+
+// Parse a `Retry-After` header value: either delay-seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - Utc::now()).to_std().unwrap_or_default())
+}
+
+impl ErrorCode {
+    /// The error code as it appears in an error response.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::InvalidRequest => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant => "invalid_grant",
+            Self::UnauthorizedClient => "unauthorized_client",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::InvalidScope => "invalid_scope",
+            Self::InvalidAuthorizationDetails => "invalid_authorization_details",
+            Self::AccessDenied => "access_denied",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::ServerError => "server_error",
+            Self::TemporarilyUnavailable => "temporarily_unavailable",
+            Self::InvalidCredentialRequest => "invalid_credential_request",
+            Self::UnsupportedCredentialType => "unsupported_credential_type",
+            Self::UnsupportedCredentialFormat => "unsupported_credential_format",
+            Self::InvalidProof => "invalid_proof",
+            Self::InvalidNonce => "invalid_nonce",
+            Self::InvalidEncryptionParameters => "invalid_encryption_parameters",
+            Self::IssuancePending => "issuance_pending",
+            Self::InvalidTransactionId => "invalid_transaction_id",
+            Self::VpFormatsNotSupported => "vp_formats_not_supported",
+            Self::InvalidPresentationDefinitionUri => "invalid_presentation_definition_uri",
+            Self::InvalidPresentationDefinitionReference => {
+                "invalid_presentation_definition_reference"
+            }
+            Self::WalletUnavailable => "wallet_unavailable",
+            Self::Other(code) => code,
+        }
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "invalid_request" => Self::InvalidRequest,
+            "invalid_client" => Self::InvalidClient,
+            "invalid_grant" => Self::InvalidGrant,
+            "unauthorized_client" => Self::UnauthorizedClient,
+            "unsupported_grant_type" => Self::UnsupportedGrantType,
+            "invalid_scope" => Self::InvalidScope,
+            "invalid_authorization_details" => Self::InvalidAuthorizationDetails,
+            "access_denied" => Self::AccessDenied,
+            "unsupported_response_type" => Self::UnsupportedResponseType,
+            "server_error" => Self::ServerError,
+            "temporarily_unavailable" => Self::TemporarilyUnavailable,
+            "invalid_credential_request" => Self::InvalidCredentialRequest,
+            "unsupported_credential_type" => Self::UnsupportedCredentialType,
+            "unsupported_credential_format" => Self::UnsupportedCredentialFormat,
+            "invalid_proof" => Self::InvalidProof,
+            "invalid_nonce" => Self::InvalidNonce,
+            "invalid_encryption_parameters" => Self::InvalidEncryptionParameters,
+            "issuance_pending" => Self::IssuancePending,
+            "invalid_transaction_id" => Self::InvalidTransactionId,
+            "vp_formats_not_supported" => Self::VpFormatsNotSupported,
+            "invalid_presentation_definition_uri" => Self::InvalidPresentationDefinitionUri,
+            "invalid_presentation_definition_reference" => {
+                Self::InvalidPresentationDefinitionReference
+            }
+            "wallet_unavailable" => Self::WalletUnavailable,
+            other => Self::Other(other.into()),
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,140 @@
+//! # Integrity
+//!
+//! Credential records are kept in wallet storage the SDK does not control,
+//! and are otherwise trusted as loaded: a record whose claim values have been
+//! swapped would be displayed and matched against presentation requests as
+//! if the issuer had issued it.
+//!
+//! [`Protected`] wraps a `CredentialStorer` to detect such tampering. When a
+//! credential is saved, a SHA-256 digest of the record is sealed using the
+//! wallet's `Encryptor` (whose key is held in the platform key store) and
+//! stored with the record as its `integrity` tag. When the record is loaded,
+//! the tag is opened and compared with a digest of the record as loaded. A
+//! record that does not match, or has no tag, is rejected with a
+//! [`RecordTampered`] error, as is a record loaded for another record's ID.
+//!
+//! Credential metadata listed without loading full records (see
+//! [`CredentialStorer::list`]) is not checked until the credential is loaded.
+
+use anyhow::anyhow;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::credential::{Credential, CredentialMetadata};
+use crate::error::RecordTampered;
+use crate::provider::{Constraints, CredentialStorer, Encryptor};
+use crate::secret::constant_time_eq;
+
+/// A `CredentialStorer` that protects the integrity of the records it saves
+/// and checks them when loaded.
+#[derive(Clone, Debug)]
+pub struct Protected<S, E> {
+    store: S,
+    encryptor: E,
+}
+
+impl<S: CredentialStorer, E: Encryptor> Protected<S, E> {
+    /// Protect the records saved to the store using the encryptor.
+    pub const fn new(store: S, encryptor: E) -> Self {
+        Self { store, encryptor }
+    }
+
+    /// Seal a digest of the credential record, returning the record with its
+    /// `integrity` tag set.
+    ///
+    /// # Errors
+    /// Will return an error if the record cannot be serialized or the
+    /// encryptor returns an error.
+    pub async fn seal(&self, credential: &Credential) -> anyhow::Result<Credential> {
+        let tag = self.encryptor.encrypt(&digest(credential)?).await?;
+        Ok(Credential {
+            integrity: Some(Base64UrlUnpadded::encode_string(&tag)),
+            ..credential.clone()
+        })
+    }
+
+    /// Check the `integrity` tag of a credential record.
+    ///
+    /// # Errors
+    /// Will return a [`RecordTampered`] error if the record has no tag or does
+    /// not match it.
+    pub async fn check(&self, credential: &Credential) -> anyhow::Result<()> {
+        let tampered = || {
+            anyhow!(RecordTampered {
+                id: credential.id.clone(),
+            })
+        };
+        let Some(tag) = &credential.integrity else {
+            return Err(tampered());
+        };
+        let tag = Base64UrlUnpadded::decode_vec(tag).map_err(|_| tampered())?;
+        let sealed = self.encryptor.decrypt(&tag).await.map_err(|_| tampered())?;
+        if !constant_time_eq(&sealed, &digest(credential)?) {
+            return Err(tampered());
+        }
+        Ok(())
+    }
+}
+
+impl<S: CredentialStorer, E: Encryptor> CredentialStorer for Protected<S, E> {
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        self.store.save(&self.seal(credential).await?).await
+    }
+
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        let Some(credential) = self.store.load(id).await? else {
+            return Ok(None);
+        };
+        // a valid record returned for another ID has been swapped
+        if credential.id != id {
+            return Err(anyhow!(RecordTampered { id: id.into() }));
+        }
+        self.check(&credential).await?;
+        Ok(Some(credential))
+    }
+
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        let credentials = self.store.find(filter).await?;
+        for credential in &credentials {
+            self.check(credential).await?;
+        }
+        Ok(credentials)
+    }
+
+    async fn list(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<CredentialMetadata>> {
+        self.store.list(filter).await
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.store.remove(id).await
+    }
+
+    async fn replace(&self, id: &str, credential: &Credential) -> anyhow::Result<()> {
+        self.store.replace(id, &self.seal(credential).await?).await
+    }
+}
+
+// A SHA-256 digest of the credential record (without its tag), serialized
+// with object fields sorted so the digest does not depend on field order.
+fn digest(credential: &Credential) -> anyhow::Result<Vec<u8>> {
+    let record = Credential {
+        integrity: None,
+        ..credential.clone()
+    };
+    let canonical = sorted(serde_json::to_value(record)?);
+    Ok(Sha256::digest(serde_json::to_vec(&canonical)?).to_vec())
+}
+
+// Sort object fields by name, recursively.
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<(String, Value)> = fields.into_iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(fields.into_iter().map(|(k, v)| (k, sorted(v))).collect::<Map<_, _>>())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sorted).collect()),
+        value => value,
+    }
+}
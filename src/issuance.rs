@@ -2,10 +2,14 @@
 //!
 //! The Issuance types implement the credential issuance flow.
 use std::collections::HashMap;
-use std::fmt::Debug;
-
-use anyhow::bail;
-use chrono::DateTime;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use credibil_vc::infosec::jose::jws::JwsBuilder;
+use credibil_vc::issuer::AuthorizedDetail;
 pub use credibil_vc::issuer::proof;
 /// Re-exports from `credibil_vc` for issuance.
 pub use credibil_vc::issuer::{
@@ -21,9 +25,84 @@ pub use credibil_vc::issuer::{
 };
 use credibil_vc::{Kind, Quota};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::bearer::{Code, Nonce, Token};
+use crate::consent::{GatedSigner, SigningOperation};
+use crate::context::WalletContext;
+use crate::credential::{Credential, ImageData, IssuerDisplay, SharingPolicy, Validity};
+use crate::error::{CodeExchanged, NonceReused, OriginMismatch, PolicyDenied};
+use crate::issuance::binding::KeyBinding;
+use crate::linkage::{self, IssuerTrust};
+use crate::parse::{ParseMode, Parsed};
+use crate::policy::{Policy, PolicyTarget};
+use crate::provider::{
+    ConsentGate, DidConfigurationResolver, DidResolver, MaybeSync, NonceCache, Signer, StateStore,
+};
+use crate::redact::{self, StableView, fmt_redacted};
+use crate::secret::{Secret, constant_time_eq};
+
+pub mod binding;
+pub mod compat;
+
+/// Utility to extract a credential offer from an offer link, typically scanned
+/// from a QR code (`openid-credential-offer://?credential_offer=...`).
+///
+/// Links without the `?` separator and the query string alone are also
+/// accepted. Query values are form-decoded, so `+` is read as a space.
+///
+/// Offers passed by value are returned as `OfferType::Object`, offers passed
+/// by reference as `OfferType::Uri`.
+///
+/// # Errors
+/// If the link contains no credential offer or the offer cannot be decoded or
+/// deserialized, an error is returned.
+pub fn parse_offer(link: &str) -> anyhow::Result<OfferType> {
+    parse_offer_with_mode(link, ParseMode::default()).map(|parsed| parsed.value)
+}
+
+/// Extract a credential offer from an offer link as for [`parse_offer`],
+/// deserializing offers passed by value according to the parse mode.
+///
+/// # Errors
+/// If the link contains no credential offer or the offer cannot be decoded or
+/// deserialized in the given mode, an error is returned.
+pub fn parse_offer_with_mode(link: &str, mode: ParseMode) -> anyhow::Result<Parsed<OfferType>> {
+    let query =
+        link.split_once('?').or_else(|| link.split_once("://")).map_or(link, |(_, query)| query);
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "credential_offer" => {
+                let json = urlencoding::decode(&value.replace('+', " "))?.into_owned();
+                let parsed = mode
+                    .parse::<CredentialOffer>(&json)
+                    .map_err(|e| anyhow!("failed to parse credential offer: {e}"))?;
+                return Ok(parsed.map(OfferType::Object));
+            }
+            "credential_offer_uri" => {
+                let uri = urlencoding::decode(&value.replace('+', " "))?.into_owned();
+                return Ok(Parsed {
+                    value: OfferType::Uri(uri),
+                    warnings: vec![],
+                });
+            }
+            _ => {}
+        }
+    }
+    bail!("no credential offer found")
+}
 
-use crate::credential::{Credential, ImageData};
+/// Deserialize (JSON) credential issuer metadata according to the parse mode.
+///
+/// # Errors
+/// If the metadata cannot be deserialized in the given mode, an error is
+/// returned.
+pub fn parse_metadata(json: &str, mode: ParseMode) -> anyhow::Result<Parsed<Issuer>> {
+    mode.parse(json).map_err(|e| anyhow!("failed to parse issuer metadata: {e}"))
+}
 
 /// A configuration ID and a list of claims that can be used by the holder to
 /// narrow the scope of the acceptance from the full set on offer.
@@ -32,7 +111,9 @@ pub struct AuthorizationSpec {
     /// The credential configuration ID to include.
     pub credential_configuration_id: String,
 
-    /// The list of claims to include.
+    /// The list of claims to include. Only these claims are requested from
+    /// the issuer, in the authorization details of the token request and in
+    /// credential requests made by format.
     ///
     /// If `None`, all claims are included.
     pub claims: Option<HashMap<String, Claim>>,
@@ -40,7 +121,12 @@ pub struct AuthorizationSpec {
 
 /// An issuance flow is used to orchestrate the change in state as the wallet
 /// progresses through a credential issuance.
-#[derive(Clone, Debug)]
+///
+/// Flows are created using an [`IssuanceFlowBuilder`].
+///
+/// Flows can be serialized so an in-progress issuance can be persisted and
+/// resumed (for example, when the wallet app is suspended).
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IssuanceFlow<O, P, A, T> {
     offer: O,
     authorization: P,
@@ -50,11 +136,84 @@ pub struct IssuanceFlow<O, P, A, T> {
     /// Perhaps useful to the wallet for tracking a particular flow instance.
     id: String,
 
+    /// The profile (and tenant) the flow is running on behalf of.
+    context: WalletContext,
+
     client_id: String,
     subject_id: String,
-    issuer: Issuer,
+    issuer: Arc<Issuer>,
     deferred: HashMap<String, String>,
-    credentials: Vec<Credential>,
+    #[serde(default)]
+    polling: HashMap<String, DeferredPoll>,
+    credentials: Arc<Vec<Credential>>,
+    #[serde(default)]
+    issuer_trust: Option<IssuerTrust>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deadline: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_expires_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce_expires_at: Option<DateTime<Utc>>,
+}
+
+/// The interval to wait between polls for a deferred credential when the
+/// issuer does not provide one.
+pub const DEFAULT_DEFERRED_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The shortest interval between polls for a deferred credential. Shorter
+/// intervals provided by the issuer are raised to this value.
+pub const MIN_DEFERRED_INTERVAL: Duration = Duration::from_secs(1);
+
+// How long an exchange of a pre-authorized code is recorded when the issuer
+// does not say how long the token lasts.
+const DEFAULT_EXCHANGE_LIFETIME: TimeDelta = TimeDelta::hours(1);
+
+/// When to next poll for a deferred credential.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DeferredPoll {
+    /// The interval between polls, in seconds.
+    pub interval: u64,
+
+    /// The time before which the issuer should not be polled.
+    pub retry_at: DateTime<Utc>,
+}
+
+impl DeferredPoll {
+    // Schedule the next poll after the interval, raised to the minimum.
+    fn after(interval: Duration) -> Self {
+        let interval = interval.max(MIN_DEFERRED_INTERVAL).as_secs();
+        let delay = TimeDelta::try_seconds(i64::try_from(interval).unwrap_or(i64::MAX))
+            .unwrap_or(TimeDelta::MAX);
+        Self {
+            interval,
+            retry_at: Utc::now().checked_add_signed(delay).unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// Whether the issuer can be polled now.
+    #[must_use]
+    pub fn is_due(&self) -> bool {
+        self.retry_at <= Utc::now()
+    }
+}
+
+impl<O, P, A, T> IssuanceFlow<O, P, A, T>
+where
+    Self: Serialize,
+{
+    /// A view of the flow state suitable for logging, with tokens, codes,
+    /// nonces, the PIN and credentials redacted.
+    #[must_use]
+    pub fn redacted(&self) -> Value {
+        redact::redacted(self)
+    }
+
+    /// A redacted view of the flow state that is stable across runs, for
+    /// snapshot testing.
+    #[must_use]
+    pub fn stable_view(&self) -> StableView {
+        StableView::new(self.redacted())
+    }
 }
 
 impl<O, P, A, T> IssuanceFlow<O, P, A, T> {
@@ -63,90 +222,207 @@ impl<O, P, A, T> IssuanceFlow<O, P, A, T> {
         self.id.clone()
     }
 
-    /// Get the credential issuer metadata.
-    pub fn issuer(&self) -> Issuer {
-        self.issuer.clone()
+    /// Get the credential issuer metadata. The metadata is shared, not
+    /// copied.
+    pub fn issuer(&self) -> Arc<Issuer> {
+        Arc::clone(&self.issuer)
+    }
+
+    /// Get the profile (and tenant) the flow is running on behalf of.
+    pub const fn context(&self) -> &WalletContext {
+        &self.context
+    }
+
+    /// Set the profile (and tenant) the flow is running on behalf of.
+    #[must_use]
+    pub fn with_context(mut self, context: WalletContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// The time by which the flow must complete, if any.
+    pub const fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.deadline
+    }
+
+    /// Set the time by which the flow must complete. The deadline is carried
+    /// through the flow's states and persisted with it. Wallets should drop a
+    /// flow that is overdue rather than continue it.
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: DateTime<Utc>) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether the flow has passed its deadline.
+    pub fn is_overdue(&self) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= Utc::now())
     }
 }
 
 /// Type guard for `IssuanceFlow` typestate pattern for flows that are initiated
 /// with an offer from the issuer.
-#[derive(Clone, Debug)]
-pub struct WithOffer(CredentialOffer);
+#[derive(Clone, Deserialize, Serialize)]
+pub struct WithOffer(Arc<CredentialOffer>);
+
+impl Debug for WithOffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted(f, "WithOffer", &self.0)
+    }
+}
+
 /// Type guard for `IssuanceFlow` typestate pattern for flows that are initiated
 /// without an offer from the issuer.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WithoutOffer;
 
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have had an
 /// offer fully or partly accepted and a PIN number (if required).
-#[derive(Clone, Debug)]
-pub struct Accepted(Vec<AuthorizationDetail>, Option<String>);
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Accepted {
+    authorization_details: Vec<AuthorizationDetail>,
+    pin: Option<Secret>,
+}
+
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have not had
 /// any any offer or authorization details accepted.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NotAccepted;
 
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have had been
 /// pre-authorized by the issuer.
-#[derive(Clone, Debug)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct PreAuthorized(PreAuthorizedCodeGrant);
+
+impl Debug for PreAuthorized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted(f, "PreAuthorized", &self.0)
+    }
+}
+
+impl Drop for PreAuthorized {
+    fn drop(&mut self) {
+        self.0.pre_authorized_code.zeroize();
+    }
+}
+
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have not been
 /// pre-authorized by the issuer.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AuthCode(Server);
 
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have had an
 /// authorization token issued.
-#[derive(Clone, Debug)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct WithToken(TokenResponse);
+
+impl Debug for WithToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted(f, "WithToken", &self.0)
+    }
+}
+
+impl Drop for WithToken {
+    fn drop(&mut self) {
+        self.0.access_token.zeroize();
+        self.0.c_nonce.zeroize();
+    }
+}
+
 /// Type guard for `IssuanceFlow` typestate pattern for flows that have not had
 /// an authorization token issued.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct WithoutToken;
 
-impl IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken> {
-    /// Create a new issuance flow with a preauthorized offer from the issuer.
+/// Builds a new [`IssuanceFlow`] for a credential issuer.
+///
+/// Set the flow's properties and then call the method for the kind of flow to
+/// start: [`pre_authorized`](Self::pre_authorized) for a pre-authorized
+/// offer, [`offer`](Self::offer) for an offer without pre-authorization, or
+/// [`wallet_initiated`](Self::wallet_initiated) for a flow started by the
+/// wallet.
+#[derive(Clone, Debug)]
+pub struct IssuanceFlowBuilder {
+    client_id: String,
+    subject_id: String,
+    issuer: Arc<Issuer>,
+    context: WalletContext,
+}
+
+impl IssuanceFlowBuilder {
+    /// Create a builder for a flow run by the given client (wallet) with the
+    /// credential issuer described by the metadata. Wallets running many
+    /// flows with the same issuer can pass an `Arc<Issuer>` to share the
+    /// metadata between flows.
     #[must_use]
-    pub fn new(
-        client_id: &str, subject_id: &str, issuer: Issuer, offer: CredentialOffer,
-        pre_auth_code_grant: PreAuthorizedCodeGrant,
-    ) -> Self {
+    pub fn new(client_id: impl Into<String>, issuer: impl Into<Arc<Issuer>>) -> Self {
         Self {
-            offer: WithOffer(offer),
-            accepted: NotAccepted,
-            authorization: PreAuthorized(pre_auth_code_grant),
-            token: WithoutToken,
-
-            id: Uuid::new_v4().to_string(),
             client_id: client_id.into(),
-            subject_id: subject_id.into(),
-            issuer,
-            deferred: HashMap::new(),
-            credentials: Vec::new(),
+            subject_id: String::new(),
+            issuer: issuer.into(),
+            context: WalletContext::default(),
         }
     }
-}
 
-impl IssuanceFlow<WithOffer, AuthCode, NotAccepted, WithoutToken> {
+    /// Set the subject (holder) the credentials are issued to.
+    #[must_use]
+    pub fn subject_id(mut self, subject_id: impl Into<String>) -> Self {
+        self.subject_id = subject_id.into();
+        self
+    }
+
+    /// Set the profile (and tenant) the flow is running on behalf of.
+    #[must_use]
+    pub fn context(mut self, context: WalletContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Create a new issuance flow with a preauthorized offer from the issuer.
+    #[must_use]
+    pub fn pre_authorized(
+        self, offer: CredentialOffer, pre_auth_code_grant: PreAuthorizedCodeGrant,
+    ) -> IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken> {
+        self.build(WithOffer(Arc::new(offer)), PreAuthorized(pre_auth_code_grant))
+    }
+
     /// Create a new issuance flow with an offer but no pre-authorization.
     #[must_use]
-    pub fn new(
-        client_id: &str, subject_id: &str, issuer: Issuer, auth_server: Server,
-        offer: CredentialOffer,
-    ) -> Self {
-        Self {
-            offer: WithOffer(offer),
+    pub fn offer(
+        self, offer: CredentialOffer, auth_server: Server,
+    ) -> IssuanceFlow<WithOffer, AuthCode, NotAccepted, WithoutToken> {
+        self.build(WithOffer(Arc::new(offer)), AuthCode(auth_server))
+    }
+
+    /// Create a new wallet-initiated issuance flow.
+    #[must_use]
+    pub fn wallet_initiated(
+        self, auth_server: Server,
+    ) -> IssuanceFlow<WithoutOffer, AuthCode, NotAccepted, WithoutToken> {
+        self.build(WithoutOffer, AuthCode(auth_server))
+    }
+
+    fn build<O, P>(
+        self, offer: O, authorization: P,
+    ) -> IssuanceFlow<O, P, NotAccepted, WithoutToken> {
+        IssuanceFlow {
+            offer,
             accepted: NotAccepted,
-            authorization: AuthCode(auth_server),
+            authorization,
             token: WithoutToken,
 
             id: Uuid::new_v4().to_string(),
-            client_id: client_id.into(),
-            subject_id: subject_id.into(),
-            issuer,
+            context: self.context,
+            client_id: self.client_id,
+            subject_id: self.subject_id,
+            issuer: self.issuer,
             deferred: HashMap::new(),
-            credentials: Vec::new(),
+            polling: HashMap::new(),
+            credentials: Arc::new(Vec::new()),
+            issuer_trust: None,
+            deadline: None,
+            token_expires_at: None,
+            nonce_expires_at: None,
         }
     }
 }
@@ -166,11 +442,18 @@ impl<P> IssuanceFlow<WithOffer, P, NotAccepted, WithoutToken> {
             let Some(cred_config) = creds_supported.get(cfg_id) else {
                 continue;
             };
-            if let Some(accepted) = &accepted {
-                if !accepted.iter().any(|a| a.credential_configuration_id == *cfg_id) {
-                    continue;
+            // Claims the holder narrowed acceptance to, if any.
+            let narrowed = match &accepted {
+                Some(accepted) => {
+                    let Some(spec) =
+                        accepted.iter().find(|a| a.credential_configuration_id == *cfg_id)
+                    else {
+                        continue;
+                    };
+                    spec.claims.as_ref()
                 }
-            }
+                None => None,
+            };
             let claims: Option<ProfileClaims> =
                 cred_config.format.claims().map(|claims| match &cred_config.format {
                     Format::JwtVcJson(w3c) | Format::LdpVc(w3c) | Format::JwtVcJsonLd(w3c) => {
@@ -178,11 +461,14 @@ impl<P> IssuanceFlow<WithOffer, P, NotAccepted, WithoutToken> {
                             credential_subject: w3c
                                 .credential_definition
                                 .credential_subject
-                                .clone(),
+                                .clone()
+                                .map(|subject| narrow_claims(subject, narrowed)),
                             ..Default::default()
                         })
                     }
-                    Format::IsoMdl(_) | Format::VcSdJwt(_) => ProfileClaims::Claims(claims),
+                    Format::IsoMdl(_) | Format::VcSdJwt(_) => {
+                        ProfileClaims::Claims(narrow_claims(claims, narrowed))
+                    }
                 });
             let detail = AuthorizationDetail {
                 credential: CredentialAuthorization::ConfigurationId {
@@ -197,16 +483,25 @@ impl<P> IssuanceFlow<WithOffer, P, NotAccepted, WithoutToken> {
 
         IssuanceFlow {
             offer: self.offer,
-            accepted: Accepted(auth_details, pin),
+            accepted: Accepted {
+                authorization_details: auth_details,
+                pin: pin.map(Secret::from),
+            },
             authorization: self.authorization,
             token: WithoutToken,
 
             id: self.id,
+            context: self.context,
             client_id: self.client_id,
             subject_id: self.subject_id,
             issuer: self.issuer,
             deferred: self.deferred,
+            polling: self.polling,
             credentials: self.credentials,
+            issuer_trust: self.issuer_trust,
+            deadline: self.deadline,
+            token_expires_at: self.token_expires_at,
+            nonce_expires_at: self.nonce_expires_at,
         }
     }
 }
@@ -232,17 +527,38 @@ impl<P, A, T> IssuanceFlow<WithOffer, P, A, T> {
         offered
     }
 
+    /// Check the policy allows each credential on offer, before the holder
+    /// is asked to accept the offer.
+    ///
+    /// # Errors
+    /// Will return a [`PolicyDenied`] error for the first credential on offer
+    /// the policy denies.
+    pub fn check_policy(&self, policy: &Policy) -> Result<(), PolicyDenied> {
+        let creds_supported = &self.issuer.credential_configurations_supported;
+        for cfg_id in &self.offer.0.credential_configuration_ids {
+            let Some(config) = creds_supported.get(cfg_id) else {
+                continue;
+            };
+            policy.check(&PolicyTarget {
+                issuer: self.issuer.credential_issuer.clone(),
+                credential_types: configuration_types(config),
+                format: config.format.to_string(),
+            })?;
+        }
+        Ok(())
+    }
+
     /// Convenience method to get the original offer details.
     #[must_use]
-    pub fn offer(&self) -> CredentialOffer {
-        self.offer.0.clone()
+    pub fn offer(&self) -> Arc<CredentialOffer> {
+        Arc::clone(&self.offer.0)
     }
 }
 
 impl IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken> {
     /// Add a PIN to an accepted offer.
     pub fn set_pin(&mut self, pin: &str) {
-        self.accepted.1 = Some(pin.into());
+        self.accepted.pin = Some(pin.into());
     }
 
     /// Create a token request from the current state.
@@ -253,18 +569,124 @@ impl IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken> {
             client_id: Some(self.client_id.clone()),
             grant_type: TokenGrantType::PreAuthorizedCode {
                 pre_authorized_code: self.authorization.0.pre_authorized_code.clone(),
-                tx_code: self.accepted.1.clone(),
+                tx_code: self.accepted.pin.as_ref().map(|pin| pin.expose().to_string()),
             },
-            authorization_details: Some(self.accepted.0.clone()),
+            authorization_details: Some(self.accepted.authorization_details.clone()),
             client_assertion: None,
         }
     }
+
+    /// Create a token request as for [`Self::token_request`], unless the
+    /// pre-authorized code has already been exchanged for a token. Wallets
+    /// retrying a token exchange (for example, after a network error) should
+    /// use this rather than resend a code the issuer will reject.
+    ///
+    /// Exchanges are recorded in the state store by
+    /// [`Self::record_exchange`], so they are seen by copies of the flow made
+    /// before the exchange and by flows restored from a snapshot.
+    ///
+    /// # Errors
+    /// Will return a [`CodeExchanged`] error if the code has already been
+    /// exchanged. Continue the flow with the token it was exchanged for.
+    pub async fn checked_token_request(
+        &self, store: &impl StateStore,
+    ) -> anyhow::Result<TokenRequest> {
+        if self.is_exchanged(store).await {
+            return Err(anyhow!(CodeExchanged {
+                credential_issuer: self.issuer.credential_issuer.clone(),
+            }));
+        }
+        Ok(self.token_request())
+    }
+
+    /// Record in the state store that the pre-authorized code has been
+    /// exchanged for the token. Only the fact of the exchange is recorded,
+    /// not the token, and the record is kept until the token expires.
+    ///
+    /// # Errors
+    /// Will return an error if the state store returns an error.
+    pub async fn record_exchange(
+        &self, store: &impl StateStore, token: &TokenResponse,
+    ) -> anyhow::Result<()> {
+        let lifetime = Some(token.expires_in)
+            .filter(|secs| *secs > 0)
+            .and_then(TimeDelta::try_seconds)
+            .unwrap_or(DEFAULT_EXCHANGE_LIFETIME);
+        StateStore::put(store, &self.exchange_key(), &true, Utc::now() + lifetime).await
+    }
+
+    /// Whether the state store records the pre-authorized code as exchanged
+    /// for a token.
+    pub async fn is_exchanged(&self, store: &impl StateStore) -> bool {
+        StateStore::get::<bool>(store, &self.exchange_key()).await.is_ok()
+    }
+
+    // The state store key recording the exchange of this flow's code.
+    fn exchange_key(&self) -> String {
+        self.context.scoped_key(&format!("exchange/{}", self.id))
+    }
 }
 
 impl<T> IssuanceFlow<WithOffer, PreAuthorized, Accepted, T> {
-    /// Get a copy of the entered PIN
-    pub fn pin(&self) -> Option<String> {
-        self.accepted.1.clone()
+    /// Get the entered PIN.
+    #[must_use]
+    pub const fn pin(&self) -> Option<&Secret> {
+        self.accepted.pin.as_ref()
+    }
+
+    /// The pre-authorized code, bound to the credential issuer.
+    #[must_use]
+    pub fn pre_authorized_code(&self) -> Code {
+        Code::new(&self.authorization.0.pre_authorized_code, &self.issuer.credential_issuer)
+    }
+}
+
+impl<O, P, T> IssuanceFlow<O, P, Accepted, T> {
+    /// How credentials of each accepted credential configuration can be
+    /// bound to the holder's key.
+    #[must_use]
+    pub fn key_bindings(&self) -> Vec<KeyBinding> {
+        let creds_supported = &self.issuer.credential_configurations_supported;
+        let mut bindings: Vec<KeyBinding> = vec![];
+        for detail in &self.accepted.authorization_details {
+            let cfg_id = match &detail.credential {
+                CredentialAuthorization::ConfigurationId {
+                    credential_configuration_id,
+                    ..
+                } => credential_configuration_id,
+                CredentialAuthorization::Format(format_identifier) => {
+                    match self.issuer.credential_configuration_id(format_identifier) {
+                        Ok(cfg_id) => cfg_id,
+                        Err(_) => continue,
+                    }
+                }
+            };
+            let Some(config) = creds_supported.get(cfg_id) else {
+                continue;
+            };
+            if !bindings.iter().any(|b| b.configuration_id == *cfg_id) {
+                bindings.push(KeyBinding::new(cfg_id.clone(), config));
+            }
+        }
+        bindings
+    }
+
+    /// Check the credentials accepted can be bound to the signer's key, so a
+    /// key the issuer cannot accept is reported before the issuer is asked
+    /// for a token or credentials.
+    ///
+    /// # Errors
+    /// Will return a [`crate::error::KeyBindingUnsupported`] error for the
+    /// first accepted credential configuration the key does not satisfy, or
+    /// an error if the signer's verification method cannot be retrieved.
+    pub async fn check_binding(&self, signer: &(impl Signer + MaybeSync)) -> anyhow::Result<()>
+    where
+        Self: Sync,
+    {
+        for binding in self.key_bindings() {
+            binding.check_signer(signer).await?;
+        }
+        Ok(())
     }
 }
 
@@ -314,7 +736,7 @@ impl IssuanceFlow<WithOffer, AuthCode, Accepted, WithoutToken> {
             state: Some(self.id.clone()),
             code_challenge,
             code_challenge_method: code_challenge_methods[0].clone(),
-            authorization_details: Some(self.accepted.0.clone()),
+            authorization_details: Some(self.accepted.authorization_details.clone()),
             scope: None,
             resource: Some(self.issuer.credential_issuer.clone()),
             subject_id: self.subject_id.clone(),
@@ -328,43 +750,39 @@ impl IssuanceFlow<WithOffer, AuthCode, Accepted, WithoutToken> {
 }
 
 impl IssuanceFlow<WithoutOffer, AuthCode, NotAccepted, WithoutToken> {
-    /// Create a new wallet-initiated issuance flow.
-    /// Create a new issuance flow with an offer from the issuer.
-    #[must_use]
-    pub fn new(client_id: &str, subject_id: &str, issuer: Issuer, auth_server: Server) -> Self {
-        Self {
-            offer: WithoutOffer,
-            accepted: NotAccepted,
-            authorization: AuthCode(auth_server),
-            token: WithoutToken,
-
-            id: Uuid::new_v4().to_string(),
-            client_id: client_id.into(),
-            subject_id: subject_id.into(),
-            issuer,
-            deferred: HashMap::new(),
-            credentials: Vec::new(),
-        }
-    }
-
     /// Create an updated state with the credentials and claims to accept for
     /// a wallet-initiated issuance flow.
+    ///
+    /// The credential issuer is added to the `locations` of each
+    /// authorization detail that does not already name it, so an authorization
+    /// server acting for more than one credential issuer can audience-restrict
+    /// the access token it issues.
     #[must_use]
     pub fn accept(
         self, accepted: Vec<AuthorizationDetail>,
     ) -> IssuanceFlow<WithoutOffer, AuthCode, Accepted, WithoutToken> {
+        let accepted = with_locations(accepted, &self.issuer.credential_issuer);
         IssuanceFlow {
             offer: self.offer,
-            accepted: Accepted(accepted, None),
+            accepted: Accepted {
+                authorization_details: accepted,
+                pin: None,
+            },
             authorization: self.authorization,
             token: self.token,
 
             id: self.id,
+            context: self.context,
             client_id: self.client_id,
             subject_id: self.subject_id,
             issuer: self.issuer,
             deferred: self.deferred,
+            polling: self.polling,
             credentials: self.credentials,
+            issuer_trust: self.issuer_trust,
+            deadline: self.deadline,
+            token_expires_at: self.token_expires_at,
+            nonce_expires_at: self.nonce_expires_at,
         }
     }
 
@@ -470,7 +888,7 @@ impl IssuanceFlow<WithoutOffer, AuthCode, Accepted, WithoutToken> {
             state: Some(self.id.clone()),
             code_challenge,
             code_challenge_method: code_challenge_methods[0].clone(),
-            authorization_details: Some(self.accepted.0.clone()),
+            authorization_details: Some(self.accepted.authorization_details.clone()),
             scope: None,
             resource: Some(self.issuer.credential_issuer.clone()),
             subject_id: self.subject_id.clone(),
@@ -497,16 +915,49 @@ impl<O> IssuanceFlow<O, AuthCode, Accepted, WithoutToken> {
                 redirect_uri: redirect_uri.map(ToString::to_string),
                 code_verifier: Some(verifier.into()),
             },
-            authorization_details: Some(self.accepted.0.clone()),
+            authorization_details: Some(self.accepted.authorization_details.clone()),
             client_assertion: None,
         }
     }
 }
 
+impl<O, A> IssuanceFlow<O, AuthCode, A, WithoutToken> {
+    /// Get the authorization server metadata, for example to find the
+    /// authorization endpoint the holder is sent to.
+    pub const fn authorization_server(&self) -> &Server {
+        &self.authorization.0
+    }
+
+    /// Check the `state` parameter returned to the redirect URI with the
+    /// authorization code matches the `state` sent in the authorization
+    /// request. The comparison is made in constant time.
+    ///
+    /// # Errors
+    /// Will return an error if the state is missing or does not match.
+    pub fn verify_state(&self, state: Option<&str>) -> anyhow::Result<()> {
+        let Some(state) = state else {
+            bail!("authorization response is missing state");
+        };
+        if !constant_time_eq(state.as_bytes(), self.id.as_bytes()) {
+            bail!("authorization response state does not match");
+        }
+        Ok(())
+    }
+}
+
 impl<O, P, A> IssuanceFlow<O, P, A, WithoutToken> {
     /// Add the token response to the flow state.
     #[must_use]
     pub fn token(self, token: TokenResponse) -> IssuanceFlow<O, P, A, WithToken> {
+        // lifetimes are relative to when the token is received
+        let now = Utc::now();
+        let expires_at = |seconds: Option<i64>| {
+            let seconds = seconds.filter(|secs| *secs > 0)?;
+            Some(now + TimeDelta::try_seconds(seconds)?)
+        };
+        let token_expires_at = expires_at(Some(token.expires_in));
+        let nonce_expires_at = expires_at(token.c_nonce_expires_in);
+
         IssuanceFlow {
             offer: self.offer,
             accepted: self.accepted,
@@ -514,11 +965,17 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithoutToken> {
             token: WithToken(token),
 
             id: self.id,
+            context: self.context,
             client_id: self.client_id,
             subject_id: self.subject_id,
             issuer: self.issuer,
             deferred: self.deferred,
+            polling: self.polling,
             credentials: self.credentials,
+            issuer_trust: self.issuer_trust,
+            deadline: self.deadline,
+            token_expires_at,
+            nonce_expires_at,
         }
     }
 }
@@ -538,13 +995,44 @@ impl<O, P> IssuanceFlow<O, P, Accepted, WithToken> {
     ///
     /// If any inconsistencies are found between the authorization details may
     /// result in an empty or partial set of credential requests.
+    ///
+    /// Issuers that do not return authorization details in the token response
+    /// (some draft 13 issuers) do not issue credential identifiers. In that
+    /// case one request is made for each accepted credential configuration,
+    /// by format, and `identifiers` is ignored.
+    ///
+    /// Requests are created as they are iterated, so the access token is only
+    /// copied into a request when the wallet is ready to send it.
     pub fn credential_requests(
         &self, identifiers: &[String], jwt: &str,
-    ) -> Vec<(String, CredentialRequest)> {
-        let mut requests = Vec::new();
-        let Some(authorized) = &self.token.0.authorization_details else {
-            return requests;
-        };
+    ) -> impl Iterator<Item = (String, CredentialRequest)> + Clone + use<O, P> {
+        let credentials = self.token.0.authorization_details.as_ref().map_or_else(
+            || self.format_credentials(),
+            |authorized| self.identifier_credentials(authorized, identifiers),
+        );
+        let credential_issuer = self.issuer.credential_issuer.clone();
+        let access_token = Secret::from(self.token.0.access_token.as_str());
+        let jwt = jwt.to_string();
+        credentials.into_iter().map(move |(cfg_id, credential)| {
+            let request = CredentialRequest {
+                credential_issuer: credential_issuer.clone(),
+                access_token: access_token.expose().to_string(),
+                credential,
+                proof: Some(Proof::Single {
+                    proof_type: SingleProof::Jwt { jwt: jwt.clone() },
+                }),
+                ..Default::default()
+            };
+            (cfg_id, request)
+        })
+    }
+
+    // The credential identifiers the holder wants from those authorized, with
+    // their credential configuration IDs.
+    fn identifier_credentials(
+        &self, authorized: &[AuthorizedDetail], identifiers: &[String],
+    ) -> Vec<(String, CredentialIssuance)> {
+        let mut credentials = Vec::new();
         for auth in authorized {
             let cfg_id = match &auth.authorization_detail.credential {
                 CredentialAuthorization::ConfigurationId {
@@ -565,26 +1053,47 @@ impl<O, P> IssuanceFlow<O, P, Accepted, WithToken> {
             };
             for cred_id in &auth.credential_identifiers {
                 // Check the holder wants this credential.
-                if !identifiers.to_vec().contains(cred_id) {
+                if !identifiers.contains(cred_id) {
                     continue;
                 }
-                let credential_issuer = self.issuer.credential_issuer.clone();
-                let access_token = self.token.0.access_token.clone().to_string();
-                let request = CredentialRequest {
-                    credential_issuer,
-                    access_token,
-                    credential: CredentialIssuance::Identifier {
-                        credential_identifier: cred_id.to_string(),
-                    },
-                    proof: Some(Proof::Single {
-                        proof_type: SingleProof::Jwt { jwt: jwt.to_string() },
-                    }),
-                    ..Default::default()
+                let credential = CredentialIssuance::Identifier {
+                    credential_identifier: cred_id.clone(),
                 };
-                requests.push((cfg_id.to_string(), request));
+                credentials.push((cfg_id.clone(), credential));
             }
         }
-        requests
+        credentials
+    }
+
+    // Request by format each accepted credential configuration, requesting
+    // only the accepted claims.
+    fn format_credentials(&self) -> Vec<(String, CredentialIssuance)> {
+        let mut credentials = Vec::new();
+        for detail in &self.accepted.authorization_details {
+            let CredentialAuthorization::ConfigurationId {
+                credential_configuration_id: cfg_id,
+                claims,
+            } = &detail.credential
+            else {
+                continue;
+            };
+            let Some(config) = self.issuer.credential_configurations_supported.get(cfg_id) else {
+                continue;
+            };
+            // Request only the claims the holder accepted.
+            let mut format = config.format.clone();
+            if let (
+                Format::JwtVcJson(w3c) | Format::LdpVc(w3c) | Format::JwtVcJsonLd(w3c),
+                Some(ProfileClaims::W3c(definition)),
+            ) = (&mut format, claims)
+            {
+                w3c.credential_definition
+                    .credential_subject
+                    .clone_from(&definition.credential_subject);
+            }
+            credentials.push((cfg_id.clone(), CredentialIssuance::Format(format)));
+        }
+        credentials
     }
 }
 
@@ -615,7 +1124,7 @@ impl<O, P> IssuanceFlow<O, P, NotAccepted, WithToken> {
             }),
             ..Default::default()
         };
-        Ok(((*cfg_id).to_string(), request))
+        Ok(((*cfg_id).clone(), request))
     }
 }
 
@@ -631,6 +1140,101 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
         }
     }
 
+    /// The access token, bound to the credential issuer, with its expiry if
+    /// the issuer provided one.
+    #[must_use]
+    pub fn access_token(&self) -> Token {
+        let token = Token::new(&self.token.0.access_token, &self.issuer.credential_issuer);
+        match self.token_expires_at {
+            Some(expires_at) => token.with_expiry(expires_at),
+            None => token,
+        }
+    }
+
+    /// The nonce to use in proofs of possession, bound to the credential
+    /// issuer, with its expiry if the issuer provided one.
+    #[must_use]
+    pub fn nonce(&self) -> Option<Nonce> {
+        let c_nonce = self.token.0.c_nonce.as_ref()?;
+        let nonce = Nonce::new(c_nonce, &self.issuer.credential_issuer);
+        Some(match self.nonce_expires_at {
+            Some(expires_at) => nonce.with_expiry(expires_at),
+            None => nonce,
+        })
+    }
+
+    /// Set the nonce to use in proofs of possession. Use when the issuer
+    /// provides nonces from a nonce endpoint (draft 15) or returns a fresh
+    /// `c_nonce` in a credential or error response (see
+    /// [`crate::error::OAuthError::nonce`]).
+    ///
+    /// # Errors
+    /// Will return an [`OriginMismatch`] error if the nonce was not issued by
+    /// the flow's credential issuer.
+    pub fn set_nonce(&mut self, c_nonce: &Nonce) -> Result<(), OriginMismatch> {
+        let value = c_nonce.expose_for(&self.issuer.credential_issuer)?;
+        self.token.0.c_nonce = Some(value.into());
+        self.nonce_expires_at = c_nonce.expires_at();
+        Ok(())
+    }
+
+    /// Wrap the holder's signer so the consent gate is consulted before the
+    /// proof of possession is signed.
+    pub fn signer<'a, S: Signer, G: ConsentGate>(
+        &self, signer: &'a S, gate: &'a G,
+    ) -> GatedSigner<'a, S, G> {
+        let operation = SigningOperation::ProofOfPossession {
+            credential_issuer: self.issuer.credential_issuer.clone(),
+        };
+        GatedSigner::new(signer, gate, operation)
+    }
+
+    /// Sign the proof of possession (see [`Self::proof`]) as a JWT of type
+    /// `openid4vci-proof+jwt`, for use in credential requests. The signing
+    /// algorithm and key ID are taken from the signer. Use [`Self::signer`]
+    /// to obtain the holder's consent first.
+    ///
+    /// # Errors
+    /// Will return an error if the signer fails to sign the proof.
+    pub async fn build_proof(&self, signer: &(impl Signer + MaybeSync)) -> anyhow::Result<String>
+    where
+        Self: Sync,
+    {
+        let jws = JwsBuilder::new()
+            .jwt_type(proof::Type::Openid4VciProofJwt)
+            .payload(self.proof())
+            .add_signer(signer)
+            .build()
+            .await?;
+        jws.encode()
+    }
+
+    /// Sign the proof of possession as [`Self::build_proof`] does, first
+    /// recording its `c_nonce` in the cache so the nonce is never used in more
+    /// than one proof. The nonce is recorded even if signing fails.
+    ///
+    /// # Errors
+    /// Will return a [`NonceReused`] error if the nonce has already been used
+    /// (set a fresh nonce using [`Self::set_nonce`]), or an error if the cache
+    /// or the signer fails.
+    pub async fn build_fresh_proof(
+        &self, signer: &(impl Signer + MaybeSync), cache: &impl NonceCache,
+    ) -> anyhow::Result<String>
+    where
+        Self: Sync,
+    {
+        if let Some(nonce) = &self.token.0.c_nonce {
+            let credential_issuer = &self.issuer.credential_issuer;
+            if !cache.use_nonce(credential_issuer, nonce).await? {
+                return Err(NonceReused {
+                    credential_issuer: credential_issuer.clone(),
+                }
+                .into());
+            }
+        }
+        self.build_proof(signer).await
+    }
+
     /// Outstanding deferred credential transaction IDs (key) and corresponding
     /// credential configuration IDs (value).
     ///
@@ -643,12 +1247,24 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
     ///
     /// Will be empty until credentials have been issued.
     pub fn credentials(&self) -> Vec<Credential> {
-        self.credentials.clone()
+        self.credentials.to_vec()
+    }
+
+    /// The verdict on whether the issuer controls the origin of its
+    /// `credential_issuer` URL, if checked using [`Self::verify_issuer`].
+    pub const fn issuer_trust(&self) -> Option<&IssuerTrust> {
+        self.issuer_trust.as_ref()
+    }
+
+    /// Remove the credentials received from the issuer, for example once they
+    /// have been saved while deferred credentials are still outstanding.
+    pub fn clear_credentials(&mut self) {
+        self.credentials = Arc::new(Vec::new());
     }
 
     /// Add a credential to the issuance state, converting the W3C format to a
     /// convenient wallet format.
-    /// 
+    ///
     /// TODO: Add support for formats other than `jwt_vc_json`.
     ///
     /// # Errors
@@ -670,6 +1286,10 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
             .display
             .as_ref()
             .map_or_else(|| issuer_id.clone(), |display| display.name.clone());
+        let issuer_display = match &self.issuer.display {
+            Some(display) => IssuerDisplay::from_metadata(serde_json::to_value(display)?),
+            None => Vec::new(),
+        };
 
         let Some(config) = &self.issuer.credential_configurations_supported.get(config_id) else {
             bail!("credential configuration not found in issuer metadata");
@@ -679,6 +1299,7 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
         let Kind::String(token) = encoded else {
             bail!("credential is not a JWT");
         };
+        let validity = Validity::from_jwt(token)?;
 
         // Turn a Quota of Strings into a Vec of Strings for the type of credential.
         let mut type_ = Vec::new();
@@ -702,23 +1323,48 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
             id: vc.id.clone().unwrap_or_else(|| format!("urn:uuid:{}", uuid::Uuid::new_v4())),
             issuer: issuer_id,
             issuer_name,
+            issuer_display,
             type_,
             format: config.format.to_string(),
+            data_model: validity.data_model,
             subject_claims,
             claim_definitions: config.format.claims(),
             issued: token.into(),
             issuance_date,
-            valid_from: vc.valid_from,
-            valid_until: vc.valid_until,
+            valid_from: validity.valid_from,
+            valid_until: validity.valid_until,
             display: config.display.clone(),
             logo,
             background,
+            key_id: None,
+            sharing_policy: SharingPolicy::default(),
+            integrity: None,
         };
 
-        self.credentials.push(storable_credential);
+        Arc::make_mut(&mut self.credentials).push(storable_credential);
         Ok(())
     }
 
+    /// Check the DID that issued the credentials received is linked to the
+    /// origin of the `credential_issuer` URL (see [`linkage::issuer_trust`]),
+    /// recording the verdict on the flow so it can be presented to the holder
+    /// with the credentials.
+    pub async fn verify_issuer(
+        &mut self, provider: impl DidConfigurationResolver + DidResolver,
+    ) -> IssuerTrust {
+        let trust = match self.credentials.first() {
+            Some(credential) => {
+                linkage::issuer_trust(&self.issuer.credential_issuer, &credential.issued, provider)
+                    .await
+            }
+            None => IssuerTrust::Unverified {
+                reason: "no credentials have been issued".into(),
+            },
+        };
+        self.issuer_trust = Some(trust.clone());
+        trust
+    }
+
     /// Construct a deferred credential request.
     ///
     /// # Errors
@@ -732,13 +1378,102 @@ impl<O, P, A> IssuanceFlow<O, P, A, WithToken> {
         }
     }
 
-    /// Add a deferred transaction ID to the issuance state.
+    /// Add a deferred transaction ID to the issuance state. The issuer is
+    /// next polled after [`DEFAULT_DEFERRED_INTERVAL`] unless an interval is
+    /// set using [`Self::set_deferred_interval`].
     pub fn add_deferred(&mut self, tx_id: &String, cfg_id: &String) {
         self.deferred.insert(tx_id.into(), cfg_id.into());
+        self.polling.insert(tx_id.into(), DeferredPoll::after(DEFAULT_DEFERRED_INTERVAL));
     }
 
     /// Remove a pending deferred credential transaction from state.
     pub fn remove_deferred(&mut self, transaction_id: &str) {
         self.deferred.remove(transaction_id);
+        self.polling.remove(transaction_id);
+    }
+
+    /// Mark a deferred credential as ready to be polled for now, for example
+    /// when the issuer notifies the wallet it is ready.
+    pub fn set_deferred_ready(&mut self, transaction_id: &str) {
+        self.polling.remove(transaction_id);
+    }
+
+    /// Set the interval to wait before polling for a deferred credential, as
+    /// hinted by the issuer (for example, in an `issuance_pending` error).
+    /// The interval is raised to [`MIN_DEFERRED_INTERVAL`] if shorter and the
+    /// next poll is scheduled after it.
+    pub fn set_deferred_interval(&mut self, transaction_id: &str, interval: Duration) {
+        if self.deferred.contains_key(transaction_id) {
+            self.polling.insert(transaction_id.into(), DeferredPoll::after(interval));
+        }
+    }
+
+    /// When to next poll for the deferred credential. `None` if there is no
+    /// such transaction or it was recorded before polls were scheduled, in
+    /// which case the issuer can be polled now.
+    #[must_use]
+    pub fn deferred_poll(&self, transaction_id: &str) -> Option<DeferredPoll> {
+        self.polling.get(transaction_id).copied()
+    }
+
+    /// Outstanding deferred credential transaction IDs that can be polled
+    /// now.
+    #[must_use]
+    pub fn deferred_due(&self) -> Vec<String> {
+        self.deferred
+            .keys()
+            .filter(|tx_id| self.polling.get(*tx_id).is_none_or(DeferredPoll::is_due))
+            .cloned()
+            .collect()
+    }
+
+    /// The earliest time any outstanding deferred credential can be polled
+    /// for. `None` if there are no outstanding deferred credentials.
+    #[must_use]
+    pub fn next_deferred_poll(&self) -> Option<DateTime<Utc>> {
+        self.deferred
+            .keys()
+            .map(|tx_id| self.polling.get(tx_id).map_or_else(Utc::now, |poll| poll.retry_at))
+            .min()
+    }
+}
+
+// Keep only the claims the holder accepted. If the holder did not narrow
+// acceptance, all claims are kept.
+fn narrow_claims(
+    claims: HashMap<String, Claim>, accepted: Option<&HashMap<String, Claim>>,
+) -> HashMap<String, Claim> {
+    let Some(accepted) = accepted else {
+        return claims;
+    };
+    claims.into_iter().filter(|(name, _)| accepted.contains_key(name)).collect()
+}
+
+// Make sure each authorization detail lists the credential issuer in its
+// `locations` so the authorization server knows which issuer the token is for.
+fn with_locations(
+    details: Vec<AuthorizationDetail>, credential_issuer: &str,
+) -> Vec<AuthorizationDetail> {
+    details
+        .into_iter()
+        .map(|mut detail| {
+            let locations = detail.locations.get_or_insert_with(Vec::new);
+            if !locations.iter().any(|l| l == credential_issuer) {
+                locations.push(credential_issuer.to_string());
+            }
+            detail
+        })
+        .collect()
+}
+
+// The credential types of a credential configuration: the W3C credential
+// definition's `type`, or the SD-JWT `vct` or mdoc `doctype`.
+fn configuration_types(config: &CredentialConfiguration) -> Vec<String> {
+    match &config.format {
+        Format::JwtVcJson(w3c) | Format::LdpVc(w3c) | Format::JwtVcJsonLd(w3c) => {
+            w3c.credential_definition.type_.clone().unwrap_or_default()
+        }
+        Format::VcSdJwt(sd_jwt) => vec![sd_jwt.vct.clone()],
+        Format::IsoMdl(iso_mdl) => vec![iso_mdl.doctype.clone()],
     }
 }
@@ -0,0 +1,146 @@
+//! # Key Binding
+//!
+//! Issued credentials are bound to a key held by the wallet, and each
+//! credential request proves possession of the key. An issuer's credential
+//! configuration says how credentials can be bound
+//! (`cryptographic_binding_methods_supported`, for example `did:key` or
+//! `jwk`) and which proofs it accepts (`proof_types_supported`, with the
+//! signing algorithms accepted for each proof type).
+//!
+//! The wallet binds credentials to the DID of the holder's signer and proves
+//! possession with a `jwt` proof signed by it. A [`KeyBinding`] exposes a
+//! configuration's requirements, and [`KeyBinding::check`] returns a
+//! [`KeyBindingUnsupported`] error when the holder's key cannot satisfy them,
+//! so the mismatch is reported before the issuer is asked for a credential
+//! rather than as a rejected proof. A configuration that omits a parameter
+//! places no restriction on it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::KeyBindingUnsupported;
+use crate::issuance::CredentialConfiguration;
+use crate::provider::{Algorithm, Signer};
+
+/// The proof type the wallet uses to prove possession of the holder's key.
+pub const PROOF_TYPE: &str = "jwt";
+
+/// How credentials of a credential configuration can be bound to the
+/// holder's key.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// The ID of the credential configuration.
+    pub configuration_id: String,
+
+    /// The methods used to bind credentials to the holder's key (for
+    /// example, `did:key` or `jwk`). Empty if the configuration does not
+    /// restrict them.
+    pub binding_methods: Vec<String>,
+
+    /// The proof types the issuer accepts, with the signing algorithms
+    /// accepted for each. Empty if the configuration does not restrict them.
+    pub proof_types: BTreeMap<String, Vec<String>>,
+}
+
+impl KeyBinding {
+    /// The key binding requirements of a credential configuration.
+    #[must_use]
+    pub fn new(configuration_id: impl Into<String>, config: &CredentialConfiguration) -> Self {
+        let config = serde_json::to_value(config).unwrap_or_default();
+        let strings = |value: &Value| -> Vec<String> {
+            let values = value.as_array().into_iter().flatten().filter_map(Value::as_str);
+            values.map(ToString::to_string).collect()
+        };
+
+        let proof_types = config["proof_types_supported"].as_object().into_iter().flatten();
+        Self {
+            configuration_id: configuration_id.into(),
+            binding_methods: strings(&config["cryptographic_binding_methods_supported"]),
+            proof_types: proof_types
+                .map(|(name, proof)| {
+                    (name.clone(), strings(&proof["proof_signing_alg_values_supported"]))
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether credentials can be bound to the key with the verification
+    /// method (a DID URL): a binding method names the key's DID method, or
+    /// accepts any DID (`did`).
+    #[must_use]
+    pub fn binds(&self, verification_method: &str) -> bool {
+        self.binding_methods.is_empty()
+            || self.binding_methods.iter().any(|method| {
+                if method == "did" {
+                    verification_method.starts_with("did:")
+                } else {
+                    method.starts_with("did:")
+                        && verification_method.starts_with(&format!("{method}:"))
+                }
+            })
+    }
+
+    /// Whether the issuer accepts a `jwt` proof signed with the algorithm.
+    #[must_use]
+    pub fn accepts(&self, algorithm: &str) -> bool {
+        if self.proof_types.is_empty() {
+            return true;
+        }
+        self.proof_types.get(PROOF_TYPE).is_some_and(|algorithms| {
+            algorithms.is_empty() || algorithms.iter().any(|a| a == algorithm)
+        })
+    }
+
+    /// Check credentials can be bound to the key with the verification
+    /// method, proven by a `jwt` proof signed with the algorithm.
+    ///
+    /// # Errors
+    /// Will return a [`KeyBindingUnsupported`] error naming the first
+    /// configuration parameter the key does not satisfy.
+    pub fn check(
+        &self, verification_method: &str, algorithm: &str,
+    ) -> Result<(), KeyBindingUnsupported> {
+        let unsupported = |requirement: &str, supported: Vec<String>| KeyBindingUnsupported {
+            configuration_id: self.configuration_id.clone(),
+            verification_method: verification_method.into(),
+            algorithm: algorithm.into(),
+            requirement: requirement.into(),
+            supported,
+        };
+        if !self.binds(verification_method) {
+            let supported = self.binding_methods.clone();
+            return Err(unsupported("cryptographic_binding_methods_supported", supported));
+        }
+        if !self.accepts(algorithm) {
+            return Err(self.proof_types.get(PROOF_TYPE).map_or_else(
+                || unsupported("proof_types_supported", self.proof_types.keys().cloned().collect()),
+                |algorithms| unsupported("proof_signing_alg_values_supported", algorithms.clone()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check credentials can be bound to the signer's key (see
+    /// [`Self::check`]).
+    ///
+    /// # Errors
+    /// Will return a [`KeyBindingUnsupported`] error if the key does not
+    /// satisfy the configuration, or an error if the signer's verification
+    /// method cannot be retrieved.
+    pub async fn check_signer(&self, signer: &impl Signer) -> anyhow::Result<()> {
+        let verification_method = signer.verification_method().await?;
+        self.check(&verification_method, &algorithm_name(&signer.algorithm()))?;
+        Ok(())
+    }
+}
+
+// The identifier of an algorithm, as it appears in metadata (for example,
+// `EdDSA`).
+fn algorithm_name(algorithm: &Algorithm) -> String {
+    match serde_json::to_value(algorithm) {
+        Ok(Value::String(name)) => name,
+        _ => format!("{algorithm:?}"),
+    }
+}
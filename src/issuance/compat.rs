@@ -0,0 +1,147 @@
+//! # Draft Compatibility
+//!
+//! Issuers in the wild implement different drafts of OpenID for Verifiable
+//! Credential Issuance. Flows in this crate produce requests in the shape used
+//! by `credibil-vc` (draft 14). This module detects the draft an issuer
+//! implements from its metadata and adapts credential requests and responses
+//! to and from the issuer's wire format, so the same wallet code works against
+//! each generation of issuer.
+//!
+//! Differences handled:
+//!
+//! * Draft 13 issuers may not return `credential_identifiers` in the token
+//!   response. Flows fall back to requesting each accepted credential
+//!   configuration by format (see `IssuanceFlow::credential_requests`).
+//! * Draft 15 issuers take a `credential_configuration_id` instead of format
+//!   parameters, and a `proofs` object instead of a single `proof`.
+//! * Draft 15 issuers return each credential wrapped in an object in the
+//!   `credentials` array.
+//! * Draft 15 issuers provide `c_nonce` from a nonce endpoint rather than in
+//!   the token response. Use [`nonce_endpoint`] to find the endpoint and
+//!   `IssuanceFlow::set_nonce` to use the nonce in proofs.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::issuance::{CredentialRequest, CredentialResponse};
+
+// Format-specific credential request parameters replaced by a configuration
+// ID in draft 15.
+const FORMAT_PARAMS: [&str; 5] = ["format", "credential_definition", "doctype", "vct", "claims"];
+
+/// The draft of OpenID for Verifiable Credential Issuance implemented by an
+/// issuer.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Draft {
+    /// Draft 13.
+    Draft13,
+
+    /// Draft 14. The draft implemented by `credibil-vc`.
+    #[default]
+    Draft14,
+
+    /// Draft 15.
+    Draft15,
+}
+
+impl Draft {
+    /// Detect the draft implemented by an issuer from its (JSON) credential
+    /// issuer metadata.
+    ///
+    /// Issuers advertising a `nonce_endpoint` implement draft 15, issuers
+    /// advertising `credential_identifiers_supported` implement draft 13.
+    /// Otherwise draft 14 is assumed.
+    #[must_use]
+    pub fn detect(metadata: &Value) -> Self {
+        if metadata.get("nonce_endpoint").is_some() {
+            Self::Draft15
+        } else if metadata.get("credential_identifiers_supported").is_some() {
+            Self::Draft13
+        } else {
+            Self::Draft14
+        }
+    }
+
+    /// Convert a credential request created by a flow into the JSON body to
+    /// send to the issuer's credential endpoint. The access token is removed
+    /// from the body: it is sent in the `Authorization` header.
+    ///
+    /// # Errors
+    /// Will return an error if the request cannot be serialized.
+    pub fn credential_request(
+        self, credential_configuration_id: &str, request: &CredentialRequest,
+    ) -> anyhow::Result<Value> {
+        let Value::Object(mut body) = serde_json::to_value(request)? else {
+            bail!("credential request is not a JSON object");
+        };
+        body.remove("credential_issuer");
+        body.remove("access_token");
+
+        if self == Self::Draft15 {
+            if let Some(proof) = body.remove("proof") {
+                body.insert("proofs".into(), proofs(proof)?);
+            }
+            if !body.contains_key("credential_identifier") {
+                for param in FORMAT_PARAMS {
+                    body.remove(param);
+                }
+                body.insert(
+                    "credential_configuration_id".into(),
+                    credential_configuration_id.into(),
+                );
+            }
+        }
+        Ok(Value::Object(body))
+    }
+
+    /// Convert the JSON body returned by the issuer's credential (or deferred
+    /// credential) endpoint into a credential response.
+    ///
+    /// # Errors
+    /// Will return an error if the body is not a valid credential response.
+    pub fn credential_response(self, mut body: Value) -> anyhow::Result<CredentialResponse> {
+        if self == Self::Draft15 {
+            if let Some(Value::Array(credentials)) = body.get_mut("credentials") {
+                for credential in credentials {
+                    if let Some(inner) = credential.get_mut("credential") {
+                        *credential = inner.take();
+                    }
+                }
+            }
+        }
+        Ok(serde_json::from_value(body)?)
+    }
+}
+
+/// The issuer's nonce endpoint, if it provides `c_nonce` values from a
+/// separate endpoint (draft 15).
+#[must_use]
+pub fn nonce_endpoint(metadata: &Value) -> Option<String> {
+    metadata.get("nonce_endpoint")?.as_str().map(ToString::to_string)
+}
+
+/// The response from an issuer's nonce endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NonceResponse {
+    /// The nonce to include in proofs of possession.
+    pub c_nonce: String,
+}
+
+// Convert a single proof (`{"proof_type": "jwt", "jwt": "..."}`) into a
+// proofs object (`{"jwt": ["..."]}`).
+fn proofs(proof: Value) -> anyhow::Result<Value> {
+    let Value::Object(mut proof) = proof else {
+        bail!("proof is not a JSON object");
+    };
+    let Some(Value::String(proof_type)) = proof.remove("proof_type") else {
+        bail!("proof has no proof type");
+    };
+    let Some(value) = proof.remove(&proof_type) else {
+        bail!("proof has no {proof_type} value");
+    };
+    let mut proofs = Map::new();
+    proofs.insert(proof_type, Value::Array(vec![value]));
+    Ok(Value::Object(proofs))
+}
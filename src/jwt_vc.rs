@@ -0,0 +1,157 @@
+//! # Legacy JWT-VC
+//!
+//! Credentials encoded using the JWT encoding of the Verifiable Credentials
+//! Data Model 1.1 (sometimes called `jwt_vc`) carry the credential in the
+//! `vc` claim, but may move some of its properties into registered JWT
+//! claims:
+//!
+//! * `iss` for `issuer`,
+//! * `jti` for `id`,
+//! * `sub` for `credentialSubject.id`,
+//! * `nbf` for `issuanceDate`, and
+//! * `exp` for `expirationDate`.
+//!
+//! Older versions of `credibil-vc` and many third-party issuers produce
+//! credentials like this. [`verify`] accepts them by verifying the JWT and
+//! restoring the missing properties to the credential, so they can be stored
+//! and presented like any other. Credentials that carry all of their own
+//! properties are verified as usual.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, SecondsFormat};
+use credibil_vc::did::{DidResolver, Resource, dereference};
+use credibil_vc::infosec::jose::jws;
+use credibil_vc::issuer::VerifiableCredential;
+use credibil_vc::issuer::proof::{self, Payload, Verify};
+use serde_json::{Map, Value};
+
+use crate::Kind;
+use crate::credential::VCDM_1_1_CONTEXT;
+
+/// Verify a credential, accepting legacy JWT-VCs whose properties are carried
+/// in registered JWT claims.
+///
+/// # Errors
+/// Will return an error if the credential cannot be decoded, its signature
+/// cannot be verified, or it is missing properties that are not supplied by
+/// registered claims.
+pub async fn verify(
+    vc_kind: &Kind<VerifiableCredential>, resolver: impl DidResolver,
+) -> anyhow::Result<Payload> {
+    if let Kind::String(token) = vc_kind {
+        let claims = claims(token)?;
+        if is_legacy(&claims)? {
+            return verify_legacy(token, resolver).await;
+        }
+    }
+    proof::verify(Verify::Vc(vc_kind), resolver).await
+}
+
+/// Decode the claims of a JWT-encoded credential without verifying its
+/// signature.
+///
+/// # Errors
+/// Will return an error if the token is not a JWT or its claims are not JSON.
+pub fn claims(token: &str) -> anyhow::Result<Value> {
+    let Some(payload) = token.split('.').nth(1) else {
+        bail!("credential is not a JWT");
+    };
+    let decoded = Base64UrlUnpadded::decode_vec(payload)
+        .map_err(|e| anyhow!("issue decoding credential: {e}"))?;
+    Ok(serde_json::from_slice(&decoded)?)
+}
+
+/// The credential in the `vc` claim of a JWT-encoded credential's claims.
+/// For legacy JWT-VCs, properties carried in registered claims are restored
+/// to the credential.
+///
+/// # Errors
+/// Will return an error if there is no `vc` claim or it is not an object.
+pub fn credential(claims: &Value) -> anyhow::Result<Value> {
+    if is_legacy(claims)? { normalize(claims) } else { Ok(claims["vc"].clone()) }
+}
+
+// The credential in the `vc` claim with properties carried in registered
+// claims restored. Properties present in the credential take precedence.
+fn normalize(claims: &Value) -> anyhow::Result<Value> {
+    let Some(Value::Object(vc)) = claims.get("vc") else {
+        bail!("credential has no `vc` claim");
+    };
+    let mut vc = vc.clone();
+    vc.entry("@context").or_insert_with(|| Value::from(vec![VCDM_1_1_CONTEXT]));
+    restore(&mut vc, "issuer", claims.get("iss"));
+    restore(&mut vc, "id", claims.get("jti"));
+
+    // only a single subject can take its ID from `sub`
+    let subject = vc.entry("credentialSubject").or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(subject) = subject {
+        restore(subject, "id", claims.get("sub"));
+    }
+
+    let date = |name: &str| {
+        claims
+            .get(name)
+            .and_then(Value::as_i64)
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|date| Value::String(date.to_rfc3339_opts(SecondsFormat::Secs, true)))
+    };
+    if !vc.contains_key("validFrom") {
+        restore(&mut vc, "issuanceDate", date("nbf").as_ref());
+    }
+    if !vc.contains_key("validUntil") {
+        restore(&mut vc, "expirationDate", date("exp").as_ref());
+    }
+    Ok(Value::Object(vc))
+}
+
+// A credential is legacy if it relies on registered claims for properties
+// the credential model requires.
+fn is_legacy(claims: &Value) -> anyhow::Result<bool> {
+    let Some(vc) = claims.get("vc") else {
+        bail!("credential has no `vc` claim");
+    };
+    let subject_id = match &vc["credentialSubject"] {
+        Value::Array(_) => true,
+        subject => subject.get("id").is_some() || claims.get("sub").is_none(),
+    };
+    Ok(vc.get("@context").is_none()
+        || (vc.get("issuer").is_none() && claims.get("iss").is_some())
+        || (vc.get("id").is_none() && claims.get("jti").is_some())
+        || !subject_id
+        || claims.get("iat").is_none())
+}
+
+// Set a property from a registered claim if the credential does not have it.
+fn restore(object: &mut Map<String, Value>, name: &str, claim: Option<&Value>) {
+    if let Some(claim) = claim {
+        object.entry(name).or_insert_with(|| claim.clone());
+    }
+}
+
+// Verify the signature of a legacy JWT-VC and map it to the credential model.
+async fn verify_legacy(token: &str, resolver: impl DidResolver) -> anyhow::Result<Payload> {
+    let jwt: jws::Jwt<Value> = jws::decode(token, move |kid| {
+        let local_resolver = resolver.clone();
+        async move {
+            let resp = dereference(&kid, None, local_resolver)
+                .await
+                .map_err(|e| anyhow!("issue dereferencing DID: {e}"))?;
+            let Some(Resource::VerificationMethod(vm)) = resp.content_stream else {
+                return Err(anyhow!("Verification method not found"));
+            };
+            vm.method_type.jwk().map_err(|e| anyhow!("JWK not found: {e}"))
+        }
+    })
+    .await
+    .map_err(|e| anyhow!("failed to parse JWT: {e}"))?;
+
+    let claims = jwt.claims;
+    let Some(issued_at) = claims.get("iat").or_else(|| claims.get("nbf")).and_then(Value::as_i64)
+    else {
+        bail!("credential has no issuance time");
+    };
+    let vc = serde_json::from_value(normalize(&claims)?)
+        .map_err(|e| anyhow!("credential is missing required properties: {e}"))?;
+    Ok(Payload::Vc { vc, issued_at })
+}
@@ -25,8 +25,9 @@
 //! demonstrate how to use the library with all the possible variations of VC
 //! issuance supported by the standards as implemented in `credibil-vc`.
 //!
-//! At present, the only supported credential data type in this crate is the
-//! [W3C Verifiable Credentials Data Model v2.0](https://www.w3.org/TR/vc-data-model-2.0/).
+//! The supported credential data types in this crate are the
+//! [W3C Verifiable Credentials Data Model v2.0](https://www.w3.org/TR/vc-data-model-2.0/)
+//! and v1.1, including legacy JWT-VCs (see the `jwt_vc` module).
 //!
 //! ** Provider **
 //!
@@ -36,6 +37,59 @@
 //! the `provider` module in this crate for traits specific to
 //! holder agents.
 //!
+//! ** Feature Flags **
+//!
+//! All features other than `qr`, `blocking` and `test-utils` are enabled by
+//! default.
+//! Applications that only need one flow can disable default features and
+//! enable just the modules they use:
+//!
+//! * `issuance` - Enables the `issuance` module and `Issuer` provider.
+//! * `presentation` - Enables the `presentation` module and `Verifier`
+//!   provider.
+//! * `status` - Enables the `status` module for checking the status
+//!   (revocation, suspension, etc.) of held credentials.
+//! * `qr` - Enables the `qr` module for decoding offers and presentation
+//!   requests from QR code images. Requires `issuance` or `presentation`.
+//! * `blocking` - Enables the `blocking` module of synchronous wrappers for
+//!   hosts that cannot await.
+//! * `test-utils` - Enables the `test_utils` module of hard-coded and mock
+//!   providers for tests and examples. Not for production wallets.
+//!
+//! The `agent`, `registry` and `outbox` modules, which manage concurrent and
+//! persisted flows on behalf of the wallet, require both `issuance` and
+//! `presentation`.
+//!
+//! ** Async Runtime **
+//!
+//! The crate does not depend on an async runtime. It never spawns tasks or
+//! sets timers: flow methods are synchronous and provider methods return
+//! futures that the caller drives, so the crate works equally well under
+//! tokio, async-std, smol or a single-threaded executor. Any timeouts or
+//! retries belong in the application's provider implementations. Hosts that
+//! cannot await can use the synchronous wrappers in the `blocking` module.
+//!
+//! ** Thread Safety **
+//!
+//! Flow types (`IssuanceFlow`, `PresentationFlow`, `IdTokenFlow` and the
+//! agent's `Flow`) are plain data: they are `Send + Sync` and hold no
+//! interior mutability, so a flow can be shared between tasks in an `Arc`
+//! without a mutex. Transitions consume the flow and return a new one, so a
+//! flow held in a shared map is replaced rather than mutated in place.
+//!
+//! `HolderAgent`, `FlowRegistry`, `Outbox` and `CancellationToken` are
+//! `Send + Sync` when their providers are, and are cheap to clone: clones share
+//! the same underlying state. The agent hands out flows as `Arc<Flow>` so
+//! reading a flow does not copy it.
+//!
+//! ** Web Assembly **
+//!
+//! The crate compiles for `wasm32-unknown-unknown` without any tokio-specific
+//! primitives, so it can be used directly by browser wallets and the crux
+//! shell. On `wasm32`, provider futures are not required to be `Send`. Note
+//! that `getrandom` needs the `wasm_js` backend selected with
+//! `--cfg getrandom_backend="wasm_js"` (see `.cargo/config.toml`).
+//!
 //! # Example
 //!
 //! See the `examples` directory for some simple applications that make use of
@@ -43,16 +97,46 @@
 //! applications for Issuers and Verifiers using `credibil-vc` and that work
 //! in conjunction with the example wallets.
 
-// TODO: implement client registration/ client metadata endpoints
-
-// TODO: support [SIOPv2](https://openid.net/specs/openid-connect-self-issued-v2-1_0.html)(https://openid.net/specs/openid-connect-self-issued-v2-1_0.html)
-//        - add Token endpoint
-//        - add Metadata endpoint
-//        - add Registration endpoint
-
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub mod agent;
+pub mod bearer;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+pub mod cancel;
+pub mod capabilities;
+pub mod clock;
+pub mod consent;
+pub mod context;
 pub mod credential;
+pub mod error;
+pub mod integrity;
+#[cfg(feature = "issuance")]
 pub mod issuance;
+pub mod jwt_vc;
+pub mod linkage;
+pub mod metadata;
+pub mod observe;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub mod outbox;
+pub mod parse;
+pub mod policy;
+#[cfg(feature = "presentation")]
 pub mod presentation;
 pub mod provider;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub mod push;
+#[cfg(all(feature = "qr", any(feature = "issuance", feature = "presentation")))]
+pub mod qr;
+pub mod redact;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub mod registry;
+pub mod reissue;
+pub mod secret;
+pub mod snapshot;
+#[cfg(feature = "status")]
+pub mod status;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod transcript;
 
-pub use credibil_vc::{Kind, Quota, did, infosec, test_utils, urlencode};
+pub use credibil_vc::{Kind, Quota, did, infosec, urlencode};
@@ -0,0 +1,228 @@
+//! # Domain Linkage
+//!
+//! Verifiers and issuers identify themselves with a DID but are reached at
+//! an HTTPS origin. The wallet can confirm the DID controls that origin using
+//! the [Well Known DID Configuration](https://identity.foundation/.well-known/resources/did-configuration/):
+//! the origin publishes `/.well-known/did-configuration.json`, listing
+//! Domain Linkage Credentials issued by the DIDs linked to it.
+//!
+//! * A verifier identified by a DID (`client_id_scheme` `did`) can send its
+//!   request from, or receive responses at, an HTTPS origin. Check the
+//!   linkage before asking the holder for consent.
+//! * An issuer signs credentials with a DID but is known to the holder by its
+//!   `credential_issuer` URL. [`issuer_trust`] checks the linkage and gives a
+//!   verdict to present to the holder with the credentials issued.
+//!
+//! Only domain linkage credentials in the JWT format are supported. Linked
+//! DIDs in the JSON-LD format are ignored.
+
+use anyhow::{anyhow, bail};
+use credibil_vc::did::{DidResolver, Resource, dereference};
+use credibil_vc::infosec::jose::jws;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "issuance")]
+use crate::Kind;
+use crate::clock::TimeValidator;
+use crate::error::DomainNotLinked;
+#[cfg(feature = "issuance")]
+use crate::issuance::proof::Payload;
+#[cfg(feature = "issuance")]
+use crate::jwt_vc;
+use crate::provider::DidConfigurationResolver;
+
+/// The path at which an origin publishes its DID configuration.
+pub const DID_CONFIGURATION_PATH: &str = "/.well-known/did-configuration.json";
+
+// The credential type of a domain linkage credential.
+const DOMAIN_LINKAGE_CREDENTIAL: &str = "DomainLinkageCredential";
+
+/// A DID configuration resource.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DidConfiguration {
+    /// Domain linkage credentials, as JWTs or JSON-LD objects.
+    pub linked_dids: Vec<Value>,
+}
+
+/// The wallet's verdict on whether an issuer controls the origin it issues
+/// credentials from.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum IssuerTrust {
+    /// The DID that issued the credentials is linked to the credential
+    /// issuer's origin.
+    DomainLinked {
+        /// The issuer's DID.
+        did: String,
+    },
+
+    /// The credential issuer's origin is not linked to the DID that issued
+    /// the credentials.
+    NotLinked {
+        /// The issuer's DID.
+        did: String,
+    },
+
+    /// The linkage could not be checked: for example, the credentials were
+    /// not issued by a DID or the DID configuration could not be retrieved.
+    Unverified {
+        /// Why the linkage could not be checked.
+        reason: String,
+    },
+}
+
+/// The HTTPS origin (`https://host[:port]`) of a URL.
+///
+/// # Errors
+/// Will return an error if the URL does not use the `https` scheme or has no
+/// host.
+pub fn origin(url: &str) -> anyhow::Result<String> {
+    let Some(rest) = url.get(8..).filter(|_| url[..8].eq_ignore_ascii_case("https://")) else {
+        bail!("{url} is not an https URL");
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if authority.is_empty() || authority.contains('@') {
+        bail!("{url} has no valid host");
+    }
+    Ok(format!("https://{}", authority.to_ascii_lowercase()))
+}
+
+/// Confirm the DID controls the origin of the URL using the DID
+/// configuration published by the origin.
+///
+/// The configuration is retrieved using the provider and each domain linkage
+/// credential is verified using its DID resolver.
+///
+/// # Errors
+/// Will return a [`DomainNotLinked`] error if no valid domain linkage
+/// credential issued by the DID names the origin, or an error if the URL is
+/// not an https URL or the configuration cannot be retrieved.
+pub async fn verify_domain_linkage(
+    did: &str, url: &str, provider: impl DidConfigurationResolver + DidResolver,
+) -> anyhow::Result<()> {
+    verify_domain_linkage_with(did, url, provider, &TimeValidator::default()).await
+}
+
+/// Confirm the DID controls the origin of the URL as
+/// [`verify_domain_linkage`] does, checking the validity of domain linkage
+/// credentials with the time validator.
+///
+/// # Errors
+/// Will return a [`DomainNotLinked`] error if no valid domain linkage
+/// credential issued by the DID names the origin, or an error if the URL is
+/// not an https URL or the configuration cannot be retrieved.
+pub async fn verify_domain_linkage_with(
+    did: &str, url: &str, provider: impl DidConfigurationResolver + DidResolver,
+    times: &TimeValidator,
+) -> anyhow::Result<()> {
+    let origin = origin(url)?;
+    let json = provider.did_configuration(&format!("{origin}{DID_CONFIGURATION_PATH}")).await?;
+    let config: DidConfiguration =
+        serde_json::from_str(&json).map_err(|e| anyhow!("invalid DID configuration: {e}"))?;
+
+    for linked in &config.linked_dids {
+        let Value::String(token) = linked else {
+            continue;
+        };
+        // a credential that cannot be verified doesn't link the domain, but
+        // another credential might
+        if verify_credential(token, did, &origin, provider.clone(), times).await.is_ok() {
+            return Ok(());
+        }
+    }
+    Err(DomainNotLinked {
+        did: did.into(),
+        origin,
+    }
+    .into())
+}
+
+/// Determine whether the DID that issued (signed) a credential is linked to
+/// the origin of the `credential_issuer` the holder received it from. The
+/// credential is verified using the provider's DID resolver.
+#[cfg(feature = "issuance")]
+pub async fn issuer_trust(
+    credential_issuer: &str, issued: &str, provider: impl DidConfigurationResolver + DidResolver,
+) -> IssuerTrust {
+    let did = match issuer_did(issued, provider.clone()).await {
+        Ok(did) => did,
+        Err(e) => {
+            return IssuerTrust::Unverified {
+                reason: format!("{e:#}"),
+            };
+        }
+    };
+    match verify_domain_linkage(&did, credential_issuer, provider).await {
+        Ok(()) => IssuerTrust::DomainLinked { did },
+        Err(e) if e.is::<DomainNotLinked>() => IssuerTrust::NotLinked { did },
+        Err(e) => IssuerTrust::Unverified {
+            reason: format!("{e:#}"),
+        },
+    }
+}
+
+// Verify a credential and return the DID that issued it.
+#[cfg(feature = "issuance")]
+async fn issuer_did(issued: &str, resolver: impl DidResolver) -> anyhow::Result<String> {
+    let vc_kind = Kind::String(issued.into());
+    let Payload::Vc { vc, .. } = jwt_vc::verify(&vc_kind, resolver).await? else {
+        bail!("expected a verifiable credential");
+    };
+    let vc_issuer = serde_json::to_value(&vc.issuer)?;
+    let did = vc_issuer.as_str().or_else(|| vc_issuer["id"].as_str()).unwrap_or_default();
+    if !did.starts_with("did:") {
+        bail!("credential issuer {did} is not a DID");
+    }
+    Ok(did.to_string())
+}
+
+// Verify a JWT domain linkage credential was issued (and signed) by the DID
+// for the origin and has not expired.
+async fn verify_credential(
+    token: &str, did: &str, origin: &str, resolver: impl DidResolver, times: &TimeValidator,
+) -> anyhow::Result<()> {
+    let signer = did.to_string();
+    let jwt: jws::Jwt<Value> = jws::decode(token, move |kid| {
+        let local_resolver = resolver.clone();
+        let signed_by_did = kid.split('#').next() == Some(signer.as_str());
+        async move {
+            if !signed_by_did {
+                return Err(anyhow!("credential is not signed by the DID"));
+            }
+            let resp = dereference(&kid, None, local_resolver)
+                .await
+                .map_err(|e| anyhow!("issue dereferencing DID: {e}"))?;
+            let Some(Resource::VerificationMethod(vm)) = resp.content_stream else {
+                return Err(anyhow!("Verification method not found"));
+            };
+            vm.method_type.jwk().map_err(|e| anyhow!("JWK not found: {e}"))
+        }
+    })
+    .await
+    .map_err(|e| anyhow!("failed to parse JWT: {e}"))?;
+
+    let claims = jwt.claims;
+    if claims["iss"] != did {
+        bail!("credential is not issued by the DID");
+    }
+    times.check_claims(&claims)?;
+    let vc = &claims["vc"];
+    let is_linkage = match &vc["type"] {
+        Value::Array(types) => types.iter().any(|t| t == DOMAIN_LINKAGE_CREDENTIAL),
+        type_ => type_ == DOMAIN_LINKAGE_CREDENTIAL,
+    };
+    if !is_linkage {
+        bail!("not a domain linkage credential");
+    }
+    let subject = &vc["credentialSubject"];
+    if subject["id"] != did {
+        bail!("credential subject is not the DID");
+    }
+    let linked = subject["origin"].as_str().and_then(|o| self::origin(o).ok());
+    if linked.as_deref() != Some(origin) {
+        bail!("credential does not name the origin");
+    }
+    Ok(())
+}
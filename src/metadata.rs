@@ -0,0 +1,209 @@
+//! # Wallet Metadata
+//!
+//! A `WalletMetadata` describes the capabilities of the wallet: the credential
+//! and presentation formats it supports, the algorithms it signs with, how it
+//! binds credentials to the holder's keys, and the response modes it can use.
+//!
+//! The same metadata is used when registering the wallet as a client with an
+//! issuer's authorization server (OAuth 2.0 Dynamic Client Registration) and
+//! when exchanging metadata with a verifier using `request_uri_method=post`
+//! (OpenID for Verifiable Presentations, section 5.10).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Algorithm;
+
+/// The algorithms supported for a credential or presentation format.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FormatSupport {
+    /// The signing algorithms supported for the format.
+    pub alg_values_supported: Vec<Algorithm>,
+}
+
+/// Metadata describing the wallet's capabilities.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WalletMetadata {
+    /// Human-readable name of the wallet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+
+    /// Redirection URIs used by the wallet in authorization requests.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redirect_uris: Vec<String>,
+
+    /// OAuth grant types the wallet uses.
+    pub grant_types: Vec<String>,
+
+    /// OAuth response types the wallet uses as a client.
+    pub response_types: Vec<String>,
+
+    /// How the wallet authenticates to token endpoints. Wallets are public
+    /// clients so this is `none`.
+    pub token_endpoint_auth_method: String,
+
+    /// The wallet's credential offer endpoint (or custom URL scheme).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_offer_endpoint: Option<String>,
+
+    /// The wallet's authorization endpoint (or custom URL scheme) used by
+    /// verifiers to send presentation requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_endpoint: Option<String>,
+
+    /// Supported credential and presentation formats, keyed by format
+    /// identifier (for example, `jwt_vc_json`).
+    pub vp_formats_supported: HashMap<String, FormatSupport>,
+
+    /// Methods used to bind credentials to the holder's keys (for example,
+    /// `did:key` or `jwk`).
+    pub cryptographic_binding_methods_supported: Vec<String>,
+
+    /// Algorithms supported for signed request objects.
+    pub request_object_signing_alg_values_supported: Vec<Algorithm>,
+
+    /// Response types supported when responding to verifiers.
+    pub response_types_supported: Vec<String>,
+
+    /// Response modes supported when responding to verifiers.
+    pub response_modes_supported: Vec<String>,
+
+    /// Client ID schemes supported for verifiers.
+    pub client_id_schemes_supported: Vec<String>,
+
+    /// Whether the wallet can retrieve presentation definitions by reference.
+    pub presentation_definition_uri_supported: bool,
+}
+
+impl WalletMetadata {
+    /// Create metadata for a wallet that signs with the given algorithm.
+    ///
+    /// The wallet supports `jwt_vc_json` credentials and `jwt_vp_json`
+    /// presentations signed with the algorithm, `did:key` holder binding, and
+    /// the `vp_token` response type using the `direct_post` response mode.
+    #[must_use]
+    pub fn new(algorithm: Algorithm) -> Self {
+        let support = FormatSupport {
+            alg_values_supported: vec![algorithm.clone()],
+        };
+        Self {
+            client_name: None,
+            redirect_uris: vec![],
+            grant_types: vec![
+                "authorization_code".into(),
+                "urn:ietf:params:oauth:grant-type:pre-authorized_code".into(),
+            ],
+            response_types: vec!["code".into()],
+            token_endpoint_auth_method: "none".into(),
+            credential_offer_endpoint: None,
+            authorization_endpoint: None,
+            vp_formats_supported: HashMap::from([
+                ("jwt_vc_json".into(), support.clone()),
+                ("jwt_vp_json".into(), support),
+            ]),
+            cryptographic_binding_methods_supported: vec!["did:key".into()],
+            request_object_signing_alg_values_supported: vec![algorithm],
+            response_types_supported: vec!["vp_token".into()],
+            response_modes_supported: vec!["direct_post".into()],
+            client_id_schemes_supported: vec!["redirect_uri".into(), "did".into()],
+            presentation_definition_uri_supported: false,
+        }
+    }
+
+    /// Set the wallet's human-readable name.
+    #[must_use]
+    pub fn client_name(mut self, client_name: impl Into<String>) -> Self {
+        self.client_name = Some(client_name.into());
+        self
+    }
+
+    /// Add a redirection URI.
+    #[must_use]
+    pub fn redirect_uri(mut self, redirect_uri: impl Into<String>) -> Self {
+        self.redirect_uris.push(redirect_uri.into());
+        self
+    }
+
+    /// Set the wallet's credential offer endpoint.
+    #[must_use]
+    pub fn credential_offer_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.credential_offer_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Set the wallet's authorization endpoint.
+    #[must_use]
+    pub fn authorization_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.authorization_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Add (or replace) a supported format and the algorithms supported for
+    /// it.
+    #[must_use]
+    pub fn format(mut self, format: impl Into<String>, algorithms: Vec<Algorithm>) -> Self {
+        self.vp_formats_supported.insert(
+            format.into(),
+            FormatSupport {
+                alg_values_supported: algorithms,
+            },
+        );
+        self
+    }
+
+    /// Add a supported cryptographic binding method.
+    #[must_use]
+    pub fn binding_method(mut self, method: impl Into<String>) -> Self {
+        let method = method.into();
+        if !self.cryptographic_binding_methods_supported.contains(&method) {
+            self.cryptographic_binding_methods_supported.push(method);
+        }
+        self
+    }
+
+    /// Add a supported response type (for example, `id_token` for wallets
+    /// that act as a self-issued OpenID provider).
+    #[must_use]
+    pub fn response_type(mut self, response_type: impl Into<String>) -> Self {
+        let response_type = response_type.into();
+        if !self.response_types_supported.contains(&response_type) {
+            self.response_types_supported.push(response_type);
+        }
+        self
+    }
+
+    /// Add a supported response mode (for example, `direct_post.jwt`).
+    #[must_use]
+    pub fn response_mode(mut self, response_mode: impl Into<String>) -> Self {
+        let response_mode = response_mode.into();
+        if !self.response_modes_supported.contains(&response_mode) {
+            self.response_modes_supported.push(response_mode);
+        }
+        self
+    }
+
+    /// Set whether the wallet can retrieve presentation definitions by
+    /// reference.
+    #[must_use]
+    pub const fn presentation_definition_uri_supported(mut self, supported: bool) -> Self {
+        self.presentation_definition_uri_supported = supported;
+        self
+    }
+
+    /// The form parameters to post to a verifier's `request_uri` when the
+    /// request specifies `request_uri_method=post`. The wallet nonce, if
+    /// provided, must be present in the returned request object.
+    ///
+    /// # Errors
+    /// Will return an error if the metadata cannot be serialized.
+    pub fn request_uri_form(
+        &self, wallet_nonce: Option<&str>,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let mut form = HashMap::from([("wallet_metadata".into(), serde_json::to_string(self)?)]);
+        if let Some(nonce) = wallet_nonce {
+            form.insert("wallet_nonce".into(), nonce.into());
+        }
+        Ok(form)
+    }
+}
@@ -0,0 +1,99 @@
+//! # Store Observation
+//!
+//! Host applications often mirror wallet contents elsewhere: a backup
+//! service, or a cache behind the wallet's UI. Re-listing the whole store
+//! after every operation is slow for large wallets, so [`Observed`] wraps a
+//! `CredentialStorer` to notify a [`CredentialObserver`] of each change as it
+//! is made, with the metadata of the record that changed.
+//!
+//! Saving a credential whose ID is not in the store notifies
+//! [`CredentialObserver::on_added`]; saving over an existing record notifies
+//! [`CredentialObserver::on_updated`]; removing a credential notifies
+//! [`CredentialObserver::on_deleted`] with the record as it was (only its ID
+//! if the record could not be loaded). Replacing a credential with one
+//! superseding it (see [`CredentialStorer::replace`]) notifies
+//! [`CredentialObserver::on_deleted`] for the replaced record and
+//! [`CredentialObserver::on_added`] for its replacement. Observers are only
+//! notified once the change has been made, and not if the store returns an
+//! error.
+//!
+//! Wrap the store with [`Observed`] outside any other wrappers (such as
+//! [`crate::integrity::Protected`]) so observers see each record as saved.
+
+use crate::credential::{Credential, CredentialMetadata};
+use crate::provider::{Constraints, CredentialObserver, CredentialStorer};
+
+/// A `CredentialStorer` that notifies an observer of the changes made to the
+/// store.
+#[derive(Clone, Debug)]
+pub struct Observed<S, O> {
+    store: S,
+    observer: O,
+}
+
+impl<S: CredentialStorer, O: CredentialObserver> Observed<S, O> {
+    /// Notify the observer of changes made to the store.
+    pub const fn new(store: S, observer: O) -> Self {
+        Self { store, observer }
+    }
+}
+
+impl<S: CredentialStorer, O: CredentialObserver> CredentialStorer for Observed<S, O> {
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        // a record that cannot be loaded (for example, one that has been
+        // tampered with) is still replaced
+        let exists = !matches!(self.store.load(&credential.id).await, Ok(None));
+        self.store.save(credential).await?;
+        let record = CredentialMetadata::from(credential.clone());
+        if exists {
+            self.observer.on_updated(&record).await;
+        } else {
+            self.observer.on_added(&record).await;
+        }
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        self.store.load(id).await
+    }
+
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        self.store.find(filter).await
+    }
+
+    async fn list(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<CredentialMetadata>> {
+        self.store.list(filter).await
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        let removed = self.store.load(id).await.ok().flatten();
+        self.store.remove(id).await?;
+        self.observer.on_deleted(&metadata(id, removed)).await;
+        Ok(())
+    }
+
+    async fn replace(&self, id: &str, credential: &Credential) -> anyhow::Result<()> {
+        let replaced = self.store.load(id).await.ok().flatten();
+        self.store.replace(id, credential).await?;
+        let record = CredentialMetadata::from(credential.clone());
+        if credential.id == id {
+            self.observer.on_updated(&record).await;
+            return Ok(());
+        }
+        self.observer.on_deleted(&metadata(id, replaced)).await;
+        self.observer.on_added(&record).await;
+        Ok(())
+    }
+}
+
+// The metadata of a record as loaded, or only its ID if it could not be
+// loaded.
+fn metadata(id: &str, credential: Option<Credential>) -> CredentialMetadata {
+    credential.map_or_else(
+        || CredentialMetadata {
+            id: id.into(),
+            ..CredentialMetadata::default()
+        },
+        CredentialMetadata::from,
+    )
+}
@@ -0,0 +1,305 @@
+//! # Outbox
+//!
+//! Presentation responses and issuer notifications are prepared by the wallet
+//! and sent in a single request. If the device is offline when the request is
+//! made, the interaction would otherwise be lost. The `Outbox` persists such
+//! requests using the `OutboxStore` provider so the wallet can complete the
+//! interaction once it is back online by calling [`Outbox::flush_outbox`].
+//!
+//! Requests are only queued when the provider reports that it could not reach
+//! the service by returning an [`Offline`] error. Any other error is returned
+//! to the caller unchanged. Each queued request has an expiry: verifiers and
+//! issuers only accept responses for a limited time (for example, while the
+//! request's nonce is valid), so expired requests are discarded rather than
+//! sent. When flushing, a queued request the service asks the wallet to retry
+//! later (see [`RetryLater`]) also remains queued.
+//!
+//! Queued requests carry access tokens and presentations, so they are sealed
+//! with the wallet's [`Encryptor`] (see [`crate::snapshot`]) before being
+//! handed to the store.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Offline, RetryLater};
+use crate::issuance::{NotificationRequest, NotificationResponse};
+use crate::presentation::{ResponseRequest, ResponseResponse};
+use crate::provider::{Encryptor, Issuer, MaybeSync, OutboxStore, Verifier};
+use crate::snapshot;
+
+/// A request waiting to be sent.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub enum OutboundRequest {
+    /// A presentation response to a verifier.
+    Presentation {
+        /// The verifier's response URI.
+        uri: Option<String>,
+
+        /// The presentation response.
+        request: ResponseRequest,
+    },
+
+    /// A notification to an issuer.
+    Notification(NotificationRequest),
+}
+
+/// A request queued by the [`Outbox`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OutboxItem {
+    /// The item ID.
+    pub id: String,
+
+    /// The request to send.
+    pub request: OutboundRequest,
+
+    /// The time after which the request can no longer be sent.
+    pub expires_at: DateTime<Utc>,
+
+    /// The number of times sending the request has failed.
+    pub attempts: u32,
+}
+
+impl OutboxItem {
+    /// Whether the request has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// A queued request as persisted by the [`OutboxStore`]: the request itself
+/// is sealed by the outbox's [`Encryptor`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SealedOutboxItem {
+    /// The item ID.
+    pub id: String,
+
+    /// The sealed request.
+    pub sealed: Vec<u8>,
+
+    /// The time after which the request can no longer be sent.
+    pub expires_at: DateTime<Utc>,
+
+    /// The number of times sending the request has failed.
+    pub attempts: u32,
+}
+
+/// The result of sending a request through the outbox.
+#[derive(Clone, Debug)]
+pub enum Delivery<T> {
+    /// The request was sent and the service responded.
+    Delivered(T),
+
+    /// The service could not be reached. The request has been queued with the
+    /// given ID.
+    Queued(String),
+}
+
+/// The result of sending a queued request when flushing the outbox.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Flushed {
+    /// A presentation response was delivered.
+    Presented {
+        /// The item ID.
+        id: String,
+
+        /// The verifier's response.
+        response: ResponseResponse,
+    },
+
+    /// A notification was delivered.
+    Notified {
+        /// The item ID.
+        id: String,
+    },
+
+    /// The service still could not be reached, or asked the wallet to retry
+    /// later. The request remains queued.
+    Pending {
+        /// The item ID.
+        id: String,
+
+        /// The delay the service asked the wallet to wait before retrying,
+        /// if any.
+        retry_after: Option<Duration>,
+    },
+
+    /// The request expired before it could be sent and has been discarded.
+    Expired {
+        /// The item ID.
+        id: String,
+    },
+
+    /// The service rejected the request. It has been discarded.
+    Failed {
+        /// The item ID.
+        id: String,
+
+        /// The error returned.
+        error: String,
+    },
+}
+
+/// Queue of outbound requests backed by an [`OutboxStore`] provider.
+#[derive(Clone, Debug)]
+pub struct Outbox<S: OutboxStore, E: Encryptor> {
+    store: S,
+    encryptor: E,
+}
+
+impl<S: OutboxStore, E: Encryptor> Outbox<S, E> {
+    /// Create an outbox using the given store, sealing queued requests with
+    /// the encryptor.
+    pub const fn new(store: S, encryptor: E) -> Self {
+        Self { store, encryptor }
+    }
+
+    /// Send a presentation response to the verifier, queueing it if the
+    /// verifier cannot be reached.
+    ///
+    /// # Errors
+    /// Will return an error if the verifier rejects the presentation or the
+    /// store returns an error.
+    pub async fn present(
+        &self, verifier: &(impl Verifier + MaybeSync), uri: Option<&str>,
+        request: &ResponseRequest, expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<Delivery<ResponseResponse>> {
+        match verifier.present(uri, request).await {
+            Ok(response) => Ok(Delivery::Delivered(response)),
+            Err(e) if is_offline(&e) => {
+                let request = OutboundRequest::Presentation {
+                    uri: uri.map(ToString::to_string),
+                    request: request.clone(),
+                };
+                self.enqueue(request, expires_at).await.map(Delivery::Queued)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send a notification to the issuer, queueing it if the issuer cannot be
+    /// reached.
+    ///
+    /// # Errors
+    /// Will return an error if the issuer rejects the notification or the
+    /// store returns an error.
+    pub async fn notify(
+        &self, issuer: &(impl Issuer + MaybeSync), request: NotificationRequest,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<Delivery<NotificationResponse>> {
+        match issuer.notification(request.clone()).await {
+            Ok(response) => Ok(Delivery::Delivered(response)),
+            Err(e) if is_offline(&e) => {
+                let request = OutboundRequest::Notification(request);
+                self.enqueue(request, expires_at).await.map(Delivery::Queued)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Try to send each queued request. Delivered, expired and rejected
+    /// requests are removed from the outbox. Requests that still cannot be
+    /// sent because the service is unreachable or asked the wallet to retry
+    /// later remain queued.
+    ///
+    /// # Errors
+    /// Will return an error if the store or encryptor returns an error.
+    pub async fn flush_outbox(
+        &self, provider: &(impl Issuer + Verifier + MaybeSync),
+    ) -> anyhow::Result<Vec<Flushed>> {
+        let mut flushed = vec![];
+        for mut item in self.pending().await? {
+            let id = item.id.clone();
+            if item.is_expired() {
+                self.store.remove(&id).await?;
+                flushed.push(Flushed::Expired { id });
+                continue;
+            }
+
+            let result = match &item.request {
+                OutboundRequest::Presentation { uri, request } => provider
+                    .present(uri.as_deref(), request)
+                    .await
+                    .map(|response| Flushed::Presented {
+                        id: id.clone(),
+                        response,
+                    }),
+                OutboundRequest::Notification(request) => provider
+                    .notification(request.clone())
+                    .await
+                    .map(|_| Flushed::Notified { id: id.clone() }),
+            };
+
+            match result {
+                Err(e) if is_offline(&e) || RetryLater::from_error(&e).is_some() => {
+                    item.attempts += 1;
+                    self.put(&item).await?;
+                    let retry_after = RetryLater::from_error(&e).map(|retry| retry.delay());
+                    flushed.push(Flushed::Pending { id, retry_after });
+                }
+                Err(e) => {
+                    self.store.remove(&id).await?;
+                    flushed.push(Flushed::Failed {
+                        id,
+                        error: format!("{e:#}"),
+                    });
+                }
+                Ok(delivered) => {
+                    self.store.remove(&id).await?;
+                    flushed.push(delivered);
+                }
+            }
+        }
+        Ok(flushed)
+    }
+
+    /// List the requests waiting to be sent.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error or a request cannot
+    /// be unsealed.
+    pub async fn pending(&self) -> anyhow::Result<Vec<OutboxItem>> {
+        let mut items = vec![];
+        for sealed in self.store.list().await? {
+            items.push(OutboxItem {
+                request: snapshot::restore(&sealed.sealed, &self.encryptor).await?,
+                id: sealed.id,
+                expires_at: sealed.expires_at,
+                attempts: sealed.attempts,
+            });
+        }
+        Ok(items)
+    }
+
+    async fn enqueue(
+        &self, request: OutboundRequest, expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<String> {
+        let item = OutboxItem {
+            id: Uuid::new_v4().to_string(),
+            request,
+            expires_at,
+            attempts: 1,
+        };
+        self.put(&item).await?;
+        Ok(item.id)
+    }
+
+    async fn put(&self, item: &OutboxItem) -> anyhow::Result<()> {
+        let sealed = SealedOutboxItem {
+            id: item.id.clone(),
+            sealed: snapshot::snapshot(&item.request, &self.encryptor).await?,
+            expires_at: item.expires_at,
+            attempts: item.attempts,
+        };
+        self.store.put(&sealed).await
+    }
+}
+
+fn is_offline(error: &anyhow::Error) -> bool {
+    error.chain().any(<dyn std::error::Error>::is::<Offline>)
+}
@@ -0,0 +1,187 @@
+//! # Parsing
+//!
+//! Offers, metadata and request objects received from issuers and verifiers
+//! are deserialized according to a [`ParseMode`].
+//!
+//! * [`ParseMode::Strict`] (the default) rejects input with unknown or
+//!   invalid fields. Use it for conformance testing of issuer and verifier
+//!   services.
+//! * [`ParseMode::Lenient`] tolerates sloppy servers in the wild. Null
+//!   values, unknown fields and an invalid field that is not needed to
+//!   construct the value are ignored, and a warning is recorded for each so
+//!   the application can log or report them.
+
+use anyhow::{anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How strictly to deserialize input received from issuers and verifiers.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Reject unknown and invalid fields.
+    #[default]
+    Strict,
+
+    /// Ignore unknown and invalid fields, recording a warning for each.
+    Lenient,
+}
+
+/// A value deserialized according to a [`ParseMode`], with any warnings
+/// recorded while parsing in lenient mode.
+#[derive(Clone, Debug)]
+pub struct Parsed<T> {
+    /// The deserialized value.
+    pub value: T,
+
+    /// Problems with the input that were tolerated.
+    pub warnings: Vec<String>,
+}
+
+impl<T> Parsed<T> {
+    /// Transform the parsed value, keeping the warnings.
+    #[must_use]
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Parsed<U> {
+        Parsed {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+}
+
+impl ParseMode {
+    /// Deserialize a JSON string.
+    ///
+    /// # Errors
+    /// Will return an error if the string is not valid JSON or cannot be
+    /// deserialized in this mode.
+    pub fn parse<T: DeserializeOwned>(self, json: &str) -> anyhow::Result<Parsed<T>> {
+        let value = serde_json::from_str(json).map_err(|e| anyhow!("invalid JSON: {e}"))?;
+        self.parse_value(value)
+    }
+
+    /// Deserialize a JSON value.
+    ///
+    /// # Errors
+    /// In strict mode, will return an error if the value has unknown fields
+    /// or cannot be deserialized. In lenient mode, will return an error only
+    /// if the value cannot be deserialized after ignoring invalid fields.
+    pub fn parse_value<T: DeserializeOwned>(self, mut value: Value) -> anyhow::Result<Parsed<T>> {
+        let mut warnings = vec![];
+
+        if self == Self::Strict {
+            let (value, unknown) = deserialize::<T>(value)?;
+            if !unknown.is_empty() {
+                bail!("unknown fields: {}", unknown.join(", "));
+            }
+            return Ok(Parsed { value, warnings });
+        }
+
+        strip_nulls(&mut value, "", &mut warnings);
+        let (value, unknown) = match deserialize::<T>(value.clone()) {
+            Ok(parsed) => parsed,
+            Err(e) => without_invalid(&value, &mut warnings).ok_or(e)?,
+        };
+        warnings.extend(unknown.into_iter().map(|path| format!("ignored unknown field {path}")));
+        Ok(Parsed { value, warnings })
+    }
+}
+
+// Deserialize a value, returning the paths of any fields ignored because
+// they are unknown.
+fn deserialize<T: DeserializeOwned>(value: Value) -> anyhow::Result<(T, Vec<String>)> {
+    let mut unknown = vec![];
+    let value = serde_ignored::deserialize(value, |path| unknown.push(path.to_string()))
+        .map_err(|e| anyhow!("invalid field: {e}"))?;
+    Ok((value, unknown))
+}
+
+// Try deserializing a value without each of its (nested) fields in turn,
+// innermost first, returning the first value that can be deserialized. Only
+// the invalid field is dropped, not the object containing it.
+fn without_invalid<T: DeserializeOwned>(
+    value: &Value, warnings: &mut Vec<String>,
+) -> Option<(T, Vec<String>)> {
+    let mut fields = vec![];
+    nested_fields(value, "", "", &mut fields);
+    fields.sort_by_key(|field| std::cmp::Reverse(field.depth));
+
+    for field in fields {
+        let mut candidate = value.clone();
+        if let Some(Value::Object(parent)) = candidate.pointer_mut(&field.parent) {
+            parent.remove(&field.key);
+        }
+        if let Ok(parsed) = deserialize(candidate) {
+            warnings.push(format!("ignored invalid field {}", field.path));
+            return Some(parsed);
+        }
+    }
+    None
+}
+
+// A field of an object nested in a value.
+struct Field {
+    // JSON pointer to the object holding the field.
+    parent: String,
+    key: String,
+    // Dotted path of the field, for warnings.
+    path: String,
+    depth: usize,
+}
+
+// Collect the fields of every object nested in a value.
+fn nested_fields(value: &Value, pointer: &str, path: &str, fields: &mut Vec<Field>) {
+    match value {
+        Value::Object(object) => {
+            for (key, field) in object {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                fields.push(Field {
+                    parent: pointer.to_string(),
+                    key: key.clone(),
+                    path: format!("{path}{key}"),
+                    depth: pointer.matches('/').count(),
+                });
+                nested_fields(
+                    field,
+                    &format!("{pointer}/{escaped}"),
+                    &format!("{path}{key}."),
+                    fields,
+                );
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                nested_fields(
+                    item,
+                    &format!("{pointer}/{index}"),
+                    &format!("{path}{index}."),
+                    fields,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+// Remove null values (sent by some servers for absent optional fields).
+fn strip_nulls(value: &mut Value, path: &str, warnings: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            fields.retain(|key, field| {
+                if field.is_null() {
+                    warnings.push(format!("ignored null field {path}{key}"));
+                }
+                !field.is_null()
+            });
+            for (key, field) in fields {
+                strip_nulls(field, &format!("{path}{key}."), warnings);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                strip_nulls(item, path, warnings);
+            }
+        }
+        _ => {}
+    }
+}
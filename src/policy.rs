@@ -0,0 +1,254 @@
+//! # Policy
+//!
+//! Enterprise deployments often need to restrict which credentials a wallet
+//! will accept or present: only credentials from issuers published on a
+//! trusted list, none of a given type, or only in a given format. A
+//! [`Policy`] expresses these as an ordered list of allow and deny rules over
+//! the issuer, the issuer's jurisdiction, the credential type and the
+//! credential format, evaluated inside the SDK so every flow is subject to
+//! them.
+//!
+//! Rules are evaluated in order and the first rule that matches decides; if
+//! none matches, the policy's default applies. A rule matches when every
+//! criterion it sets matches (a criterion that is not set matches anything).
+//! Criteria match exactly, or by prefix when they end with `*`, so `*` alone
+//! matches any value.
+//!
+//! Issuers are identified by their credential issuer URL (`credential_issuer`
+//! in the issuer's metadata) both when an offer is received and when a
+//! credential is presented, since that is all that is known of an issuer at
+//! offer time. Credentials record the URL as [`Credential::issuer`], rather
+//! than the DID that signed them, so a rule matches the same issuer at both
+//! points.
+//!
+//! An issuer's jurisdiction is taken from the policy's trusted list, which
+//! maps issuers (credential issuer URLs) to the jurisdiction that published
+//! them. Issuers that are not on the list have no jurisdiction, so
+//! a rule with a jurisdiction criterion never matches them. A policy that
+//! denies by default and allows jurisdiction `*` accepts credentials only
+//! from listed issuers.
+//!
+//! The agent checks its policy when an offer is received (see
+//! [`crate::issuance::IssuanceFlow::check_policy`]) and when credentials are
+//! matched to, or authorized for, a presentation request. A credential the
+//! policy denies returns a [`PolicyDenied`] error.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::credential::{Credential, CredentialMetadata};
+use crate::error::PolicyDenied;
+
+/// Whether a rule allows or denies the credentials it matches.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    /// The credentials are allowed.
+    #[default]
+    Allow,
+
+    /// The credentials are denied.
+    Deny,
+}
+
+/// A rule allowing or denying credentials.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PolicyRule {
+    /// Whether the rule allows or denies the credentials it matches.
+    pub effect: Effect,
+
+    /// The issuer (credential issuer URL) the rule matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+
+    /// The jurisdiction of the issuer, from the trusted list, the rule
+    /// matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jurisdiction: Option<String>,
+
+    /// A credential type the rule matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_type: Option<String>,
+
+    /// The credential format (for example, `jwt_vc_json`) the rule matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl PolicyRule {
+    /// A rule allowing the credentials it matches. Without criteria, the rule
+    /// matches all credentials.
+    #[must_use]
+    pub fn allow() -> Self {
+        Self::default()
+    }
+
+    /// A rule denying the credentials it matches. Without criteria, the rule
+    /// matches all credentials.
+    #[must_use]
+    pub fn deny() -> Self {
+        Self {
+            effect: Effect::Deny,
+            ..Self::default()
+        }
+    }
+
+    /// Match credentials from the issuer.
+    #[must_use]
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Match credentials from issuers published by the jurisdiction.
+    #[must_use]
+    pub fn jurisdiction(mut self, jurisdiction: impl Into<String>) -> Self {
+        self.jurisdiction = Some(jurisdiction.into());
+        self
+    }
+
+    /// Match credentials of the type.
+    #[must_use]
+    pub fn credential_type(mut self, credential_type: impl Into<String>) -> Self {
+        self.credential_type = Some(credential_type.into());
+        self
+    }
+
+    /// Match credentials in the format.
+    #[must_use]
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    // Whether the rule matches the credential.
+    fn matches(&self, target: &PolicyTarget, jurisdiction: Option<&str>) -> bool {
+        let criterion = |pattern: &Option<String>, value: Option<&str>| {
+            pattern.as_deref().is_none_or(|pattern| value.is_some_and(|v| like(pattern, v)))
+        };
+        criterion(&self.issuer, Some(&target.issuer))
+            && criterion(&self.jurisdiction, jurisdiction)
+            && criterion(&self.format, Some(&target.format))
+            && self
+                .credential_type
+                .as_deref()
+                .is_none_or(|pattern| target.credential_types.iter().any(|t| like(pattern, t)))
+    }
+}
+
+/// The credential (or credential on offer) a policy is evaluated for.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PolicyTarget {
+    /// The issuer (credential issuer URL).
+    pub issuer: String,
+
+    /// The credential's types.
+    pub credential_types: Vec<String>,
+
+    /// The credential's format.
+    pub format: String,
+}
+
+impl From<&Credential> for PolicyTarget {
+    fn from(credential: &Credential) -> Self {
+        Self {
+            issuer: credential.issuer.clone(),
+            credential_types: credential.type_.clone(),
+            format: credential.format.clone(),
+        }
+    }
+}
+
+impl From<&CredentialMetadata> for PolicyTarget {
+    fn from(metadata: &CredentialMetadata) -> Self {
+        Self {
+            issuer: metadata.issuer.clone(),
+            credential_types: metadata.type_.clone(),
+            format: metadata.format.clone(),
+        }
+    }
+}
+
+/// Rules allowing or denying credentials, configured by the host application.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Policy {
+    /// The rules, in the order they are evaluated.
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+
+    /// The trusted list: issuers keyed by credential issuer URL, with the
+    /// jurisdiction that published them.
+    #[serde(default)]
+    pub trusted_list: HashMap<String, String>,
+
+    /// The effect when no rule matches. Defaults to allowing credentials.
+    #[serde(default)]
+    pub default: Effect,
+}
+
+impl Policy {
+    /// A policy that denies credentials no rule allows.
+    #[must_use]
+    pub fn deny_by_default() -> Self {
+        Self {
+            default: Effect::Deny,
+            ..Self::default()
+        }
+    }
+
+    /// Add a rule, evaluated after the rules already added.
+    #[must_use]
+    pub fn rule(mut self, rule: PolicyRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Add an issuer to the trusted list, published by the jurisdiction.
+    #[must_use]
+    pub fn trusted_issuer(
+        mut self, issuer: impl Into<String>, jurisdiction: impl Into<String>,
+    ) -> Self {
+        self.trusted_list.insert(issuer.into(), jurisdiction.into());
+        self
+    }
+
+    /// The effect of the policy for the credential, and the index of the rule
+    /// that decided it (`None` if the default applied).
+    #[must_use]
+    pub fn evaluate(&self, target: &PolicyTarget) -> (Effect, Option<usize>) {
+        let jurisdiction = self.trusted_list.get(&target.issuer).map(String::as_str);
+        self.rules
+            .iter()
+            .position(|rule| rule.matches(target, jurisdiction))
+            .map_or((self.default, None), |index| (self.rules[index].effect, Some(index)))
+    }
+
+    /// Check the policy allows the credential.
+    ///
+    /// # Errors
+    /// Will return a [`PolicyDenied`] error if the policy denies the
+    /// credential.
+    pub fn check(&self, target: &PolicyTarget) -> Result<(), PolicyDenied> {
+        match self.evaluate(target) {
+            (Effect::Allow, _) => Ok(()),
+            (Effect::Deny, rule) => Err(PolicyDenied {
+                issuer: target.issuer.clone(),
+                credential_types: target.credential_types.clone(),
+                format: target.format.clone(),
+                rule,
+            }),
+        }
+    }
+
+    /// Whether the policy allows the credential.
+    #[must_use]
+    pub fn permits(&self, target: &PolicyTarget) -> bool {
+        self.check(target).is_ok()
+    }
+}
+
+// Match a value exactly, or by prefix if the pattern ends with `*`.
+fn like(pattern: &str, value: &str) -> bool {
+    pattern.strip_suffix('*').map_or(pattern == value, |prefix| value.starts_with(prefix))
+}
@@ -1,12 +1,12 @@
 //! # Presentation
 //!
 //! Types needed to implement a credential presentation flow.
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::vec;
 
 use anyhow::{anyhow, bail};
-use credibil_vc::did::{DidResolver, Resource, dereference};
-use credibil_vc::infosec::jose::jws;
+use chrono::{DateTime, Utc};
+use credibil_vc::did::DidResolver;
 pub use credibil_vc::verifier::proof;
 // Re-export types from `credibil-vc` for use in the presentation module.
 pub use credibil_vc::verifier::{
@@ -14,43 +14,152 @@ pub use credibil_vc::verifier::{
     PresentationSubmission, RequestObject, RequestObjectRequest, RequestObjectResponse,
     RequestObjectType, ResponseRequest, ResponseResponse, VerifiablePresentation,
 };
-use credibil_vc::{Kind, urlencode};
+use credibil_vc::{Kind, Quota};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
-use crate::credential::Credential;
+use crate::consent::{GatedSigner, SigningOperation};
+use crate::context::WalletContext;
+use crate::credential::{Credential, Sharing, SharingPolicy, VCDM_1_1_CONTEXT};
+use crate::metadata::WalletMetadata;
+use crate::parse::{ParseMode, Parsed};
+use crate::presentation::disclosure::DisclosurePreview;
+use crate::presentation::endpoint::ResponseEndpoint;
+use crate::presentation::format::NegotiatedFormat;
+use crate::presentation::reader::ReaderAuthentication;
+use crate::provider::{Algorithm, ConsentGate, DidConfigurationResolver, Signer};
+use crate::redact::{self, StableView};
+use crate::secret::constant_time_eq;
+
+pub mod compat;
+pub mod disclosure;
+pub mod endpoint;
+pub mod format;
+pub mod reader;
+pub mod signatures;
+pub mod siop;
 
 /// Utility to extract a presentation `RequestObject` from a URL-encoded string.
-/// If the request string can be decoded but appears to be something other than
-/// a `RequestObject`, None is returned.
 ///
-/// Wrapper to the function `credibil_vc::core::urlencode`.
+/// Requests in any supported dialect (see [`compat::Dialect`]) are accepted.
+/// If the request string can be decoded but has neither a presentation
+/// definition nor a DCQL query, None is returned.
 ///
 /// # Errors
 /// If the string cannot be decoded or appears to be an encoded `RequestObject`
-/// but cannot be successfully deserialized, an error is returned.
+/// but cannot be successfully converted, an error is returned.
 pub fn parse_request_object(request: &str) -> anyhow::Result<Option<RequestObject>> {
-    let req_obj = if request.contains("&presentation_definition") {
-        Some(
-            urlencode::from_str::<RequestObject>(request)
-                .map_err(|e| anyhow!("failed to parse request object: {e}"))?,
-        )
-    } else {
-        None
-    };
+    let parsed = compat::parse_request_object_with_mode(request, ParseMode::default())?;
+    Ok(parsed.map(|parsed| parsed.value))
+}
+
+/// How the verifier's `state` is returned in the response to a presentation
+/// request.
+///
+/// `OpenID` for Verifiable Presentations requires the wallet to return the
+/// `state` unchanged when the request contains one. Some profiles also require
+/// the verifier to send a `state`, while some older verifiers reject responses
+/// that contain one.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum StatePolicy {
+    /// Return the request's `state`, if it has one.
+    #[default]
+    Echo,
+
+    /// Return the request's `state`, rejecting requests without one.
+    Required,
 
-    Ok(req_obj)
+    /// Never return a `state`.
+    Omit,
+}
+
+// The context added to presentations when no contexts are configured.
+const EXAMPLES_CONTEXT: &str = "https://www.w3.org/2018/credentials/examples/v1";
+
+// The base type of every presentation.
+const VERIFIABLE_PRESENTATION: &str = "VerifiablePresentation";
+
+// Presentation properties set by the flow that cannot be configured.
+const FLOW_PROPERTIES: [&str; 5] = ["@context", "type", "holder", "verifiableCredential", "proof"];
+
+/// How the presentations built by a flow are described.
+///
+/// By default, the `credentials/examples/v1` context is added and the
+/// presentation types are taken from the `const` filters of the presentation
+/// definition. Production wallets should set the contexts and types
+/// explicitly.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PresentationTemplate {
+    /// Contexts to add after the base Verifiable Credentials context.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contexts: Option<Vec<String>>,
+
+    /// Types to add after `VerifiablePresentation`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<String>>,
+
+    /// Additional properties of the presentation (for example, `id`). Only
+    /// properties modelled by `VerifiablePresentation` can be set.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub properties: Map<String, Value>,
 }
 
 /// A presentation flow is used to orchestrate the change in state as the
 /// wallet progresses through a credential verification.
-#[derive(Clone, Debug)]
+///
+/// Flows can be serialized so an in-progress presentation can be persisted and
+/// resumed.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct PresentationFlow<A> {
     authorize: A,
 
     /// Perhaps useful to the wallet for tracking a particular flow instance.
     id: String,
+    context: WalletContext,
     request: RequestObject,
     submission: PresentationSubmission,
+    #[serde(default)]
+    state_policy: StatePolicy,
+    #[serde(default)]
+    presentation: PresentationTemplate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deadline: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "ReaderAuthentication::is_anonymous")]
+    reader: ReaderAuthentication,
+}
+
+/// The request's nonce and state are redacted.
+impl<A: Debug> Debug for PresentationFlow<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PresentationFlow")
+            .field("authorize", &self.authorize)
+            .field("id", &self.id)
+            .field("context", &self.context)
+            .field("request", &redact::redacted(&self.request))
+            .field("submission", &self.submission)
+            .field("state_policy", &self.state_policy)
+            .field("presentation", &self.presentation)
+            .field("deadline", &self.deadline)
+            .field("reader", &self.reader)
+            .finish()
+    }
+}
+
+impl<A: Serialize> PresentationFlow<A> {
+    /// A view of the flow state suitable for logging, with the request's
+    /// nonce and state and any authorized credentials redacted.
+    #[must_use]
+    pub fn redacted(&self) -> Value {
+        redact::redacted(self)
+    }
+
+    /// A redacted view of the flow state that is stable across runs, for
+    /// snapshot testing.
+    #[must_use]
+    pub fn stable_view(&self) -> StableView {
+        StableView::new(self.redacted())
+    }
 }
 
 impl<A> PresentationFlow<A> {
@@ -58,13 +167,134 @@ impl<A> PresentationFlow<A> {
     pub fn id(&self) -> String {
         self.id.clone()
     }
+
+    /// Get the profile (and tenant) the flow is running on behalf of.
+    pub const fn context(&self) -> &WalletContext {
+        &self.context
+    }
+
+    /// Set the profile (and tenant) the flow is running on behalf of.
+    #[must_use]
+    pub fn with_context(mut self, context: WalletContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// The time by which the flow must complete, if any.
+    pub const fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.deadline
+    }
+
+    /// Set the time by which the flow must complete. The deadline is carried
+    /// through to the authorized flow and persisted with it.
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: DateTime<Utc>) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Whether the flow has passed its deadline.
+    pub fn is_overdue(&self) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= Utc::now())
+    }
+
+    /// How the verifier was authenticated. Verifiers are anonymous unless
+    /// authenticated by a reader certificate (see [`reader`]).
+    pub const fn reader(&self) -> &ReaderAuthentication {
+        &self.reader
+    }
+
+    /// Where the response to the request is sent, or `None` if the response
+    /// is returned to the caller.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidResponseEndpoint`](crate::error::InvalidResponseEndpoint)
+    /// error if the request's `response_uri` or `redirect_uri` is invalid for
+    /// its response mode.
+    pub fn response_endpoint(&self) -> anyhow::Result<Option<ResponseEndpoint>> {
+        Ok(ResponseEndpoint::from_request(&serde_json::to_value(&self.request)?)?)
+    }
+
+    /// Preview which disclosures of an SD-JWT credential would be released
+    /// to the verifier, and which withheld (see [`disclosure`]).
+    ///
+    /// # Errors
+    /// Will return an error if the credential is not a valid SD-JWT or the
+    /// request does not contain a presentation definition object.
+    pub fn disclosure_preview(&self, credential: &Credential) -> anyhow::Result<DisclosurePreview> {
+        disclosure::preview(credential, &self.request)
+    }
+
+    // The (normalized) URI to post the response to. The endpoint is checked
+    // when the flow is created.
+    fn response_uri(&self) -> Option<String> {
+        let endpoint = self.response_endpoint().ok().flatten()?;
+        endpoint.post_uri().map(ToString::to_string)
+    }
+
+    /// Record how the verifier was authenticated, for example using the
+    /// identity returned by [`reader::parse_signed_request`].
+    #[must_use]
+    pub fn with_reader(mut self, reader: ReaderAuthentication) -> Self {
+        self.reader = reader;
+        self
+    }
+
+    /// Set how the request's `state` is returned to the verifier. Defaults to
+    /// [`StatePolicy::Echo`].
+    ///
+    /// # Errors
+    /// Will return an error if the policy is [`StatePolicy::Required`] and the
+    /// verifier did not send a `state`.
+    pub fn with_state_policy(mut self, policy: StatePolicy) -> anyhow::Result<Self> {
+        if policy == StatePolicy::Required && self.request.state.is_none() {
+            bail!("request object has no state");
+        }
+        self.state_policy = policy;
+        Ok(self)
+    }
+
+    /// Set how the presentations built by the flow are described. Defaults to
+    /// the (example) description used by earlier releases.
+    #[must_use]
+    pub fn with_presentation(mut self, template: PresentationTemplate) -> Self {
+        self.presentation = template;
+        self
+    }
+
+    /// Check the `state` in a response request is exactly the one the
+    /// verifier sent, or is absent if the policy is [`StatePolicy::Omit`].
+    ///
+    /// # Errors
+    /// Will return an error if the state does not match.
+    pub fn verify_state(&self, response: &ResponseRequest) -> anyhow::Result<()> {
+        let matches = match (&response.state, self.response_state()) {
+            (Some(state), Some(expected)) => {
+                constant_time_eq(state.as_bytes(), expected.as_bytes())
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if !matches {
+            bail!("response state does not match request state");
+        }
+        Ok(())
+    }
+
+    // The state to return to the verifier.
+    fn response_state(&self) -> Option<String> {
+        match self.state_policy {
+            StatePolicy::Echo | StatePolicy::Required => self.request.state.clone(),
+            StatePolicy::Omit => None,
+        }
+    }
 }
 
 /// Type guard for a `PresentationFlow` that has been authorized.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Authorized(Vec<Credential>);
 /// Type guard for a `PresentationFlow` that has not been authorized.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NotAuthorized;
 
 impl PresentationFlow<NotAuthorized> {
@@ -73,18 +303,40 @@ impl PresentationFlow<NotAuthorized> {
     /// # Errors
     /// Will return an error if the request object does not contain a
     /// presentation definition object: this is the only currently supported
-    /// type.
+    /// type, or an [`InvalidResponseEndpoint`](crate::error::InvalidResponseEndpoint)
+    /// error if its response endpoint is invalid.
     pub fn new(request: RequestObject) -> anyhow::Result<Self> {
         let submission = create_submission(&request)?;
+        ResponseEndpoint::from_request(&serde_json::to_value(&request)?)?;
         Ok(Self {
             authorize: NotAuthorized,
 
             id: Uuid::new_v4().to_string(),
+            context: WalletContext::default(),
             request,
             submission,
+            state_policy: StatePolicy::default(),
+            presentation: PresentationTemplate::default(),
+            deadline: None,
+            reader: ReaderAuthentication::Anonymous,
         })
     }
 
+    /// The credentials (typically those matching [`Self::filter`]) whose
+    /// sharing policies permit presenting them to the verifier in the
+    /// circumstances described by `sharing`.
+    #[must_use]
+    pub fn shareable(&self, credentials: Vec<Credential>, sharing: Sharing) -> Vec<Credential> {
+        credentials.into_iter().filter(|c| self.permits(&c.sharing_policy, sharing)).collect()
+    }
+
+    /// Whether the sharing policy permits presenting a credential to the
+    /// verifier in the circumstances described by `sharing`.
+    #[must_use]
+    pub fn permits(&self, policy: &SharingPolicy, sharing: Sharing) -> bool {
+        policy.permits(&self.request.client_id, sharing)
+    }
+
     /// Get a filter from the request object on the state.
     ///
     /// # Errors
@@ -104,6 +356,27 @@ impl PresentationFlow<NotAuthorized> {
         Ok(constraints)
     }
 
+    /// Confirm a verifier identified by a DID controls the HTTPS origin at
+    /// `url` (for example, the URL the request object was retrieved from),
+    /// before asking the holder for consent. See [`crate::linkage`].
+    /// Verifiers not identified by a DID are not checked.
+    ///
+    /// # Errors
+    /// Will return a [`crate::error::DomainNotLinked`] error if the DID is not
+    /// linked to the origin, or an error if the DID configuration cannot be
+    /// retrieved.
+    pub async fn verify_domain_linkage(
+        &self, url: &str, provider: impl DidConfigurationResolver + DidResolver,
+    ) -> anyhow::Result<()> {
+        // later drafts prefix the DID with the client ID scheme
+        let client_id = &self.request.client_id;
+        let did = client_id.strip_prefix("decentralized_identifier:").unwrap_or(client_id);
+        if !did.starts_with("did:") {
+            return Ok(());
+        }
+        crate::linkage::verify_domain_linkage(did, url, provider).await
+    }
+
     /// Authorize the presentation flow.
     #[must_use]
     pub fn authorize(self, credentials: &[Credential]) -> PresentationFlow<Authorized> {
@@ -111,13 +384,45 @@ impl PresentationFlow<NotAuthorized> {
             authorize: Authorized(credentials.to_vec()),
 
             id: self.id,
+            context: self.context,
             request: self.request,
             submission: self.submission,
+            state_policy: self.state_policy,
+            presentation: self.presentation,
+            deadline: self.deadline,
+            reader: self.reader,
         }
     }
 }
 
 impl PresentationFlow<Authorized> {
+    /// Choose the presentation format for the authorized credentials from
+    /// the formats advertised by the verifier and those in the wallet's
+    /// metadata, to be signed with the given algorithm.
+    ///
+    /// # Errors
+    /// Will return a [`crate::error::NoCommonFormat`] error if the verifier
+    /// and the wallet have no format in common.
+    pub fn negotiate(
+        &self, wallet: &WalletMetadata, algorithm: &Algorithm,
+    ) -> anyhow::Result<NegotiatedFormat> {
+        format::negotiate(&self.request, &self.authorize.0, wallet, algorithm)
+    }
+
+    /// Choose the presentation format for the authorized credentials as for
+    /// [`Self::negotiate`], from the verifier's client metadata as received
+    /// (JSON). Use when the metadata lists formats `RequestObject` cannot
+    /// represent (see [`format`]).
+    ///
+    /// # Errors
+    /// Will return a [`crate::error::NoCommonFormat`] error if the verifier
+    /// and the wallet have no format in common.
+    pub fn negotiate_metadata(
+        &self, client_metadata: &Value, wallet: &WalletMetadata, algorithm: &Algorithm,
+    ) -> anyhow::Result<NegotiatedFormat> {
+        format::negotiate_metadata(client_metadata, &self.authorize.0, wallet, algorithm)
+    }
+
     /// Construct a presentation payload.
     ///
     /// # Errors
@@ -125,34 +430,99 @@ impl PresentationFlow<Authorized> {
     /// presentation definition object: this is the only currently supported
     /// type.
     pub fn payload(&self, key_identifier: &str) -> anyhow::Result<proof::Payload> {
+        self.build_payload(&self.authorize.0, key_identifier)
+    }
+
+    /// Identifiers of the holder keys the authorized credentials are bound to
+    /// (see [`Credential::key_id`]), in the order the credentials were
+    /// authorized. `None` stands for credentials not bound to a named key.
+    ///
+    /// Wallets that bind credentials to different keys create a presentation
+    /// for each key using [`Self::key_payload`] so that each is signed with
+    /// the key its credentials are bound to.
+    #[must_use]
+    pub fn holder_keys(&self) -> Vec<Option<String>> {
+        let mut keys = Vec::new();
+        for c in &self.authorize.0 {
+            if !keys.contains(&c.key_id) {
+                keys.push(c.key_id.clone());
+            }
+        }
+        keys
+    }
+
+    /// Construct a presentation payload for the authorized credentials bound
+    /// to the holder key `key_id`, to be signed by that key. `key_identifier`
+    /// is the key's verification method.
+    ///
+    /// # Errors
+    /// Will return an error if no authorized credentials are bound to the key
+    /// or the request object does not contain a presentation definition
+    /// object.
+    pub fn key_payload(
+        &self, key_id: Option<&str>, key_identifier: &str,
+    ) -> anyhow::Result<proof::Payload> {
+        let credentials = self
+            .authorize
+            .0
+            .iter()
+            .filter(|c| c.key_id.as_deref() == key_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        if credentials.is_empty() {
+            bail!("no authorized credentials are bound to key {key_id:?}");
+        }
+        self.build_payload(&credentials, key_identifier)
+    }
+
+    // Construct a presentation payload for the credentials.
+    fn build_payload(
+        &self, credentials: &[Credential], key_identifier: &str,
+    ) -> anyhow::Result<proof::Payload> {
         let holder_did = key_identifier.split('#').collect::<Vec<&str>>()[0];
 
-        // presentation with 2 VCs: one as JSON, one as base64url encoded JWT
-        let mut builder = VerifiablePresentation::builder()
-            .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
-            .holder(holder_did);
+        // presentations are built from their fields: the VP builder requires
+        // a type and context beyond the defaults, which a template may not add
+        let template = &self.presentation;
+        let contexts = template.contexts.clone().unwrap_or_else(|| vec![EXAMPLES_CONTEXT.into()]);
+        let mut context = vec![Kind::String(VCDM_1_1_CONTEXT.into())];
+        context.extend(contexts.into_iter().map(Kind::String));
 
         let pd = match &self.request.presentation_definition {
             Kind::Object(pd) => pd,
             Kind::String(_) => bail!("presentation_definition_uri is unsupported"),
         };
 
-        for input in &pd.input_descriptors {
-            if let Some(fields) = &input.constraints.fields {
-                for field in fields {
-                    if let Some(filter) = &field.filter {
-                        if let FilterValue::Const(val) = &filter.value {
-                            builder = builder.add_type(val.clone());
+        let mut types = vec![VERIFIABLE_PRESENTATION.to_string()];
+        if let Some(template_types) = &template.types {
+            types.extend(template_types.iter().cloned());
+        } else {
+            for input in &pd.input_descriptors {
+                if let Some(fields) = &input.constraints.fields {
+                    for field in fields {
+                        if let Some(filter) = &field.filter {
+                            if let FilterValue::Const(val) = &filter.value {
+                                types.push(val.clone());
+                            }
                         }
                     }
                 }
             }
         }
 
-        for c in &self.authorize.0 {
-            builder = builder.add_credential(Kind::String(c.issued.clone()));
+        let mut vp = VerifiablePresentation {
+            context,
+            id: Some(format!("urn:uuid:{}", Uuid::new_v4())),
+            type_: Quota::Many(types),
+            verifiable_credential: Some(
+                credentials.iter().map(|c| Kind::String(c.issued.clone())).collect(),
+            ),
+            holder: Some(holder_did.into()),
+            proof: None,
+        };
+        if !template.properties.is_empty() {
+            vp = with_properties(&vp, &template.properties)?;
         }
-        let vp = builder.build()?;
 
         let payload = proof::Payload::Vp {
             vp,
@@ -163,6 +533,31 @@ impl PresentationFlow<Authorized> {
         Ok(payload)
     }
 
+    /// Wrap the holder's signer so the consent gate is consulted before the
+    /// presentation is signed.
+    pub fn signer<'a, S: Signer, G: ConsentGate>(
+        &self, signer: &'a S, gate: &'a G,
+    ) -> GatedSigner<'a, S, G> {
+        let operation = SigningOperation::Presentation {
+            client_id: self.request.client_id.clone(),
+        };
+        GatedSigner::new(signer, gate, operation)
+    }
+
+    /// Sign a presentation of the authorized credentials (see
+    /// [`Self::payload`]) as a JWT, with the signer's verification method as
+    /// the holder's key identifier. Use [`Self::signer`] to obtain the
+    /// holder's consent first.
+    ///
+    /// # Errors
+    /// Will return an error if the presentation cannot be constructed or the
+    /// signer fails to sign it.
+    pub async fn sign_payload(&self, signer: &impl Signer) -> anyhow::Result<String> {
+        let kid = signer.verification_method().await?;
+        let payload = self.payload(&kid)?;
+        proof::create(proof::W3cFormat::JwtVcJson, payload, signer).await
+    }
+
     /// Create a presentation response request and the presentation URI from the
     /// current flow state and the provided proof.
     #[must_use]
@@ -170,13 +565,126 @@ impl PresentationFlow<Authorized> {
         let res_req = ResponseRequest {
             vp_token: Some(vec![Kind::String(jwt.into())]),
             presentation_submission: Some(self.submission.clone()),
-            state: self.request.state.clone(),
+            state: self.response_state(),
         };
-        let res_uri =
-            self.request.response_uri.clone().map(|uri| uri.trim_end_matches('/').to_string());
+        let res_uri = self.response_uri();
         (res_req, res_uri)
     }
 
+    /// Create a presentation response request and the presentation URI from
+    /// the current flow state and a proof for each holder key, in the order
+    /// returned by [`Self::holder_keys`]. The presentation submission maps
+    /// each input descriptor to the presentation holding a credential of the
+    /// type it asks for.
+    ///
+    /// # Errors
+    /// Will return an error if there is not one proof per holder key.
+    pub fn create_keyed_response_request(
+        &self, jwts: &[String],
+    ) -> anyhow::Result<(ResponseRequest, Option<String>)> {
+        let keys = self.holder_keys();
+        if jwts.len() != keys.len() {
+            bail!("expected {} proofs, one per holder key, got {}", keys.len(), jwts.len());
+        }
+        if let [jwt] = jwts {
+            return Ok(self.create_response_request(jwt));
+        }
+
+        // Position of each credential: the presentation for its key and its
+        // index in that presentation.
+        let mut counts = vec![0usize; keys.len()];
+        let mut positions = Vec::new();
+        for c in &self.authorize.0 {
+            let vp_index = keys.iter().position(|k| *k == c.key_id).unwrap_or_default();
+            positions.push((c, vp_index, counts[vp_index]));
+            counts[vp_index] += 1;
+        }
+
+        let mut submission = self.submission.clone();
+        if let Kind::Object(pd) = &self.request.presentation_definition {
+            for dm in &mut submission.descriptor_map {
+                let types = pd
+                    .input_descriptors
+                    .iter()
+                    .find(|d| d.id == dm.id)
+                    .map(descriptor_types)
+                    .unwrap_or_default();
+                let (vp_index, nested_index) = positions
+                    .iter()
+                    .find(|(c, ..)| types.iter().any(|t| c.type_.contains(t)))
+                    .map_or((0, 0), |(_, vp_index, nested_index)| (*vp_index, *nested_index));
+                dm.path = format!("$[{vp_index}]");
+                dm.path_nested.path = format!("$.verifiableCredential[{nested_index}]");
+            }
+        }
+
+        let res_req = ResponseRequest {
+            vp_token: Some(jwts.iter().map(|jwt| Kind::String(jwt.clone())).collect()),
+            presentation_submission: Some(submission),
+            state: self.response_state(),
+        };
+        let res_uri = self.response_uri();
+        Ok((res_req, res_uri))
+    }
+
+    /// Construct a presentation payload for the authorized credentials
+    /// matching the input descriptor (or DCQL credential query) `query_id`.
+    /// DCQL verifiers expect a separate presentation for each credential
+    /// query: create one for each descriptor in the presentation submission
+    /// and pass them to [`Self::create_query_response_request`].
+    ///
+    /// # Errors
+    /// Will return an error if the request has no such input descriptor or
+    /// no authorized credentials match it.
+    pub fn query_payload(
+        &self, query_id: &str, key_identifier: &str,
+    ) -> anyhow::Result<proof::Payload> {
+        let Kind::Object(pd) = &self.request.presentation_definition else {
+            bail!("presentation_definition_uri is unsupported");
+        };
+        let Some(descriptor) = pd.input_descriptors.iter().find(|d| d.id == query_id) else {
+            bail!("no input descriptor {query_id}");
+        };
+        let mut credentials = vec![];
+        for c in &self.authorize.0 {
+            if descriptor.constraints.satisfied(c)? {
+                credentials.push(c.clone());
+            }
+        }
+        if credentials.is_empty() {
+            bail!("no authorized credentials match {query_id}");
+        }
+        self.build_payload(&credentials, key_identifier)
+    }
+
+    /// Create a presentation response request and the presentation URI from
+    /// the current flow state and a proof for each input descriptor (see
+    /// [`Self::query_payload`]), in the order of the presentation
+    /// submission's descriptor map.
+    ///
+    /// # Errors
+    /// Will return an error if there is not one proof per input descriptor.
+    pub fn create_query_response_request(
+        &self, jwts: &[String],
+    ) -> anyhow::Result<(ResponseRequest, Option<String>)> {
+        let descriptors = self.submission.descriptor_map.len();
+        if jwts.len() != descriptors {
+            bail!("expected {descriptors} proofs, one per input descriptor, got {}", jwts.len());
+        }
+
+        let mut submission = self.submission.clone();
+        for (index, dm) in submission.descriptor_map.iter_mut().enumerate() {
+            dm.path = format!("$[{index}]");
+        }
+        let res_req = ResponseRequest {
+            vp_token: Some(jwts.iter().map(|jwt| Kind::String(jwt.clone())).collect()),
+            presentation_submission: Some(submission),
+            state: self.response_state(),
+        };
+        let res_uri = self.response_uri();
+        Ok((res_req, res_uri))
+    }
+
     /// Get the credentials from the authorized presentation flow.
     #[must_use]
     pub fn credentials(&self) -> Vec<Credential> {
@@ -184,6 +692,33 @@ impl PresentationFlow<Authorized> {
     }
 }
 
+// Add configured properties to a presentation. Properties that are set by the
+// flow, or that `VerifiablePresentation` does not model, are rejected rather
+// than silently dropped.
+fn with_properties(
+    vp: &VerifiablePresentation, properties: &Map<String, Value>,
+) -> anyhow::Result<VerifiablePresentation> {
+    let Value::Object(mut json) = serde_json::to_value(vp)? else {
+        bail!("presentation is not a JSON object");
+    };
+    for (name, value) in properties {
+        if FLOW_PROPERTIES.contains(&name.as_str()) {
+            bail!("presentation property `{name}` is set by the flow");
+        }
+        json.insert(name.clone(), value.clone());
+    }
+
+    let vp: VerifiablePresentation = serde_json::from_value(Value::Object(json.clone()))
+        .map_err(|e| anyhow!("invalid presentation property: {e}"))?;
+    let built = serde_json::to_value(&vp)?;
+    for name in properties.keys() {
+        if built.get(name) != json.get(name) {
+            bail!("presentation property `{name}` is not supported");
+        }
+    }
+    Ok(vp)
+}
+
 /// Utility to extract a presentation `RequestObject` from a
 /// `RequestObjectResponse`. Uses a DID resolver to verify the JWT.
 ///
@@ -206,22 +741,44 @@ pub async fn parse_request_object_response(
 pub async fn parse_request_object_jwt(
     token: &str, resolver: impl DidResolver,
 ) -> anyhow::Result<RequestObject> {
-    let jwt: jws::Jwt<RequestObject> = jws::decode(token, move |kid| {
-        let local_resolver = resolver.clone();
-        async move {
-            let resp = dereference(&kid, None, local_resolver)
-                .await
-                .map_err(|e| anyhow!("issue dereferencing DID: {e}"))?;
-            let Some(Resource::VerificationMethod(vm)) = resp.content_stream else {
-                return Err(anyhow!("Verification method not found"));
-            };
-            vm.method_type.jwk().map_err(|e| anyhow!("JWK not found: {e}"))
-        }
-    })
-    .await
-    .map_err(|e| anyhow!("failed to parse JWT: {e}"))?;
+    let parsed = parse_request_object_jwt_with_mode(token, resolver, ParseMode::default()).await?;
+    Ok(parsed.value)
+}
+
+/// Parse a JWT into a `RequestObject` according to the parse mode.
+///
+/// As for [`parse_request_object_jwt`], Presentation Exchange v1 definitions
+/// are converted to v2 (see [`compat::PexVersion`]).
+///
+/// Request objects with more than one signature (using the JWS JSON
+/// serialization) are accepted if any signature verifies. Use
+/// [`signatures::verify_request_object`] to require other signatures and
+/// find out who signed.
+///
+/// # Errors
+/// If decoding or verifying the JWT fails, or the request object cannot be
+/// deserialized in the given mode, an error is returned.
+pub async fn parse_request_object_jwt_with_mode(
+    token: &str, resolver: impl DidResolver, mode: ParseMode,
+) -> anyhow::Result<Parsed<RequestObject>> {
+    let policy = signatures::SignaturePolicy::Any;
+    let signed = signatures::verify_request_object(token, resolver, &policy, mode).await?;
+    Ok(signed.request)
+}
 
-    Ok(jwt.claims)
+// Credential types an input descriptor filters on.
+fn descriptor_types(descriptor: &InputDescriptor) -> Vec<String> {
+    let mut types = Vec::new();
+    for field in descriptor.constraints.fields.iter().flatten() {
+        if let Some(Filter {
+            value: FilterValue::Const(val),
+            ..
+        }) = &field.filter
+        {
+            types.push(val.clone());
+        }
+    }
+    types
 }
 
 // Construct a presentation submission from a request object.
@@ -0,0 +1,534 @@
+//! # Draft Compatibility
+//!
+//! Verifiers in the wild implement different drafts of OpenID for Verifiable
+//! Presentations. Flows in this crate work with request objects in the shape
+//! used by `credibil-vc`: a Presentation Exchange `presentation_definition`
+//! and a separate `client_id_scheme`. This module detects the dialect a
+//! verifier uses from the request itself and adapts requests and responses to
+//! and from the verifier's wire format, so the same wallet code works against
+//! each generation of verifier.
+//!
+//! Differences handled:
+//!
+//! * Later drafts replace `presentation_definition` with a DCQL `dcql_query`.
+//!   Each credential query is converted to an input descriptor with the same
+//!   ID, so credential matching and consent work unchanged. Queries that
+//!   cannot be expressed as input descriptors (for example, claim values
+//!   with several alternatives, or claim and credential sets) are rejected.
+//! * Later drafts drop `client_id_scheme` in favour of a prefix on the
+//!   `client_id` (for example, `redirect_uri:https://verifier.example.com`).
+//!   The scheme is derived from the prefix for the `redirect_uri` and `did`
+//!   schemes, the only ones modelled by the request object. The `client_id`
+//!   is left as is since it is the audience of the presentation.
+//! * Presentation Exchange verifiers expect `vp_token` to be a single
+//!   presentation (or an array when there are several) together with a
+//!   `presentation_submission`. DCQL verifiers expect a JSON object keyed by
+//!   credential query ID, with a presentation of the credentials matching
+//!   each query, and no submission. From draft 25 each value in the object is
+//!   an array of presentations.
+//! * Presentation Exchange v1 definitions identify the credentials an input
+//!   descriptor requires with `schema` URIs rather than field constraints,
+//!   and some use older format identifiers. These are converted to the v2
+//!   shape (see [`PexVersion`]) so credential matching and submission work
+//!   unchanged.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::parse::{ParseMode, Parsed};
+use crate::presentation::{RequestObject, ResponseRequest};
+
+// Client ID prefixes and the `client_id_scheme` each corresponds to. Other
+// schemes (for example, `x509_san_dns`) are not modelled by `RequestObject`.
+const CLIENT_ID_PREFIXES: [(&str, &str); 3] = [
+    ("redirect_uri:", "redirect_uri"),
+    ("decentralized_identifier:", "did"),
+    ("did:", "did"),
+];
+
+// Client ID prefixes only used from draft 25, where DCQL responses return an
+// array of presentations for each credential query.
+const DRAFT_25_PREFIXES: [&str; 2] = ["decentralized_identifier:", "x509_hash:"];
+
+// Request parameters with JSON values when passed by value in a URL.
+const JSON_PARAMS: [&str; 3] = ["presentation_definition", "dcql_query", "client_metadata"];
+
+// Schema URIs every credential conforms to, which do not constrain the
+// credentials an input descriptor matches.
+const GENERIC_SCHEMAS: [&str; 2] = [
+    "https://www.w3.org/2018/credentials#VerifiableCredential",
+    "https://www.w3.org/2018/credentials/v1",
+];
+
+// Presentation Exchange v1 format identifiers and their current equivalents.
+const V1_FORMATS: [(&str, &str); 2] = [("jwt_vc", "jwt_vc_json"), ("jwt_vp", "jwt_vp_json")];
+
+/// The dialect of OpenID for Verifiable Presentations used by a verifier.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// Presentation Exchange: a `presentation_definition` is requested and
+    /// returned with a `presentation_submission`. The dialect implemented by
+    /// `credibil-vc`.
+    #[default]
+    PresentationExchange,
+
+    /// DCQL: a `dcql_query` is requested and `vp_token` is an object with a
+    /// single presentation for each credential query.
+    Dcql,
+
+    /// DCQL (draft 25 and later): as for [`Dialect::Dcql`], with an array of
+    /// presentations for each credential query.
+    DcqlMultiple,
+}
+
+impl Dialect {
+    /// Detect the dialect used by a verifier from its (JSON) request object.
+    ///
+    /// Requests with a `dcql_query` use DCQL. These return arrays of
+    /// presentations if any credential query allows `multiple` credentials
+    /// or the `client_id` uses a prefix introduced in draft 25. Otherwise
+    /// Presentation Exchange is assumed.
+    #[must_use]
+    pub fn detect(request: &Value) -> Self {
+        let Some(query) = request.get("dcql_query") else {
+            return Self::PresentationExchange;
+        };
+        let multiple = query.get("credentials").and_then(Value::as_array).is_some_and(|creds| {
+            creds.iter().any(|c| c.get("multiple").and_then(Value::as_bool) == Some(true))
+        });
+        let client_id = request.get("client_id").and_then(Value::as_str).unwrap_or_default();
+        if multiple || DRAFT_25_PREFIXES.iter().any(|p| client_id.starts_with(p)) {
+            Self::DcqlMultiple
+        } else {
+            Self::Dcql
+        }
+    }
+
+    /// Convert a (JSON) request object received from a verifier into a
+    /// request object that can be used to start a presentation flow.
+    ///
+    /// # Errors
+    /// Will return an error if the request has neither a presentation
+    /// definition nor a valid DCQL query, or cannot otherwise be
+    /// deserialized.
+    pub fn request_object(self, request: Value) -> anyhow::Result<RequestObject> {
+        self.request_object_with_mode(request, ParseMode::default()).map(|parsed| parsed.value)
+    }
+
+    /// Convert a (JSON) request object as for [`Dialect::request_object`],
+    /// deserializing the converted request according to the parse mode.
+    ///
+    /// # Errors
+    /// Will return an error if the request has neither a presentation
+    /// definition nor a valid DCQL query, or cannot otherwise be deserialized
+    /// in the given mode.
+    pub fn request_object_with_mode(
+        self, request: Value, mode: ParseMode,
+    ) -> anyhow::Result<Parsed<RequestObject>> {
+        let Value::Object(mut request) = request else {
+            bail!("request object is not a JSON object");
+        };
+
+        if !request.contains_key("client_id_scheme") {
+            let client_id = request.get("client_id").and_then(Value::as_str).unwrap_or_default();
+            if let Some((_, scheme)) =
+                CLIENT_ID_PREFIXES.iter().find(|(p, _)| client_id.starts_with(p))
+            {
+                request.insert("client_id_scheme".into(), (*scheme).into());
+            }
+        }
+
+        // later drafts allow client metadata to be omitted (for example, when
+        // it is implied by the client ID scheme)
+        if !request.contains_key("client_metadata") {
+            let client_id = request.get("client_id").cloned().unwrap_or_default();
+            request.insert("client_metadata".into(), json!({"client_id": client_id}));
+        }
+
+        if let Some(query) = request.remove("dcql_query") {
+            if self == Self::PresentationExchange {
+                bail!("DCQL query in a Presentation Exchange request");
+            }
+            request.insert("presentation_definition".into(), presentation_definition(&query)?);
+        }
+        if !request.contains_key("presentation_definition")
+            && !request.contains_key("presentation_definition_uri")
+        {
+            bail!("request has no presentation definition or DCQL query");
+        }
+
+        let mut request = Value::Object(request);
+        pex_v2_request(&mut request)?;
+        mode.parse_value(request).map_err(|e| anyhow!("failed to parse request object: {e}"))
+    }
+
+    /// Encode a response request created by a presentation flow as the form
+    /// parameters to send to the verifier's response URI.
+    ///
+    /// For DCQL verifiers, each credential query ID is mapped to the
+    /// presentation its descriptor in the presentation submission points to.
+    /// Since each presentation is returned for a single query, responses to
+    /// requests with several credential queries must hold a presentation for
+    /// each (see [`PresentationFlow::create_query_response_request`]).
+    ///
+    /// # Errors
+    /// Will return an error if the response has no presentation, a DCQL
+    /// response does not have a presentation for each credential query, or
+    /// the response cannot be serialized.
+    ///
+    /// [`PresentationFlow::create_query_response_request`]: crate::presentation::PresentationFlow::create_query_response_request
+    pub fn response_form(
+        self, response: &ResponseRequest,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let Some(vp_token) = &response.vp_token else {
+            bail!("response has no presentation");
+        };
+        let Some(first) = vp_token.first() else {
+            bail!("response has no presentation");
+        };
+
+        let mut form = HashMap::new();
+        match self {
+            Self::PresentationExchange => {
+                let token = if vp_token.len() == 1 {
+                    serde_json::to_value(first)?
+                } else {
+                    serde_json::to_value(vp_token)?
+                };
+                form.insert("vp_token".into(), form_value(token)?);
+                if let Some(submission) = &response.presentation_submission {
+                    form.insert(
+                        "presentation_submission".into(),
+                        serde_json::to_string(submission)?,
+                    );
+                }
+            }
+            Self::Dcql | Self::DcqlMultiple => {
+                let Some(submission) = &response.presentation_submission else {
+                    bail!("response has no credential query IDs");
+                };
+                let mut token = Map::new();
+                let mut used = vec![];
+                for descriptor in &submission.descriptor_map {
+                    let index = presentation_index(&descriptor.path)?;
+                    let Some(presentation) = vp_token.get(index) else {
+                        bail!("no presentation for credential query {}", descriptor.id);
+                    };
+                    if used.contains(&index) {
+                        bail!("credential queries share presentation {}", descriptor.path);
+                    }
+                    used.push(index);
+
+                    let presentation = serde_json::to_value(presentation)?;
+                    let value = if self == Self::DcqlMultiple {
+                        Value::Array(vec![presentation])
+                    } else {
+                        presentation
+                    };
+                    token.insert(descriptor.id.clone(), value);
+                }
+                form.insert("vp_token".into(), serde_json::to_string(&token)?);
+            }
+        }
+        if let Some(state) = &response.state {
+            form.insert("state".into(), state.clone());
+        }
+        Ok(form)
+    }
+}
+
+/// The version of Presentation Exchange a presentation definition is written
+/// for.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PexVersion {
+    /// Presentation Exchange v1: input descriptors list the `schema` URIs of
+    /// the credentials they require.
+    V1,
+
+    /// Presentation Exchange v2. The version implemented by `credibil-vc`.
+    #[default]
+    V2,
+}
+
+impl PexVersion {
+    /// Detect the version of a (JSON) presentation definition. Definitions
+    /// with an input descriptor listing `schema` URIs are v1.
+    #[must_use]
+    pub fn detect(definition: &Value) -> Self {
+        let descriptors = definition.get("input_descriptors").and_then(Value::as_array);
+        if descriptors.into_iter().flatten().any(|d| d.get("schema").is_some()) {
+            Self::V1
+        } else {
+            Self::V2
+        }
+    }
+
+    /// Convert a (JSON) presentation definition of this version into the v2
+    /// shape used by presentation flows.
+    ///
+    /// Each input descriptor's `schema` URIs are replaced with a constraint on
+    /// the credential type named by the URI (its fragment or last path
+    /// segment). Generic schemas, such as the base Verifiable Credential
+    /// schema, are dropped. v1 format identifiers (for example, `jwt_vc`) are
+    /// renamed and formats listing algorithms (or proof types) as a bare
+    /// array are converted to objects.
+    ///
+    /// # Errors
+    /// Will return an error if the definition or one of its input descriptors
+    /// is not a JSON object, or a schema has no URI.
+    pub fn presentation_definition(self, definition: Value) -> anyhow::Result<Value> {
+        let Value::Object(mut definition) = definition else {
+            bail!("presentation definition is not a JSON object");
+        };
+        if self == Self::V2 {
+            return Ok(Value::Object(definition));
+        }
+
+        if let Some(format) = definition.get_mut("format") {
+            pex_v2_format(format);
+        }
+        let descriptors = definition.get_mut("input_descriptors").and_then(Value::as_array_mut);
+        for descriptor in descriptors.into_iter().flatten() {
+            let Value::Object(descriptor) = descriptor else {
+                bail!("input descriptor is not a JSON object");
+            };
+            if let Some(format) = descriptor.get_mut("format") {
+                pex_v2_format(format);
+            }
+            let types = match descriptor.remove("schema") {
+                Some(schema) => schema_types(&schema)?,
+                None => vec![],
+            };
+
+            let constraints = descriptor.entry("constraints").or_insert_with(|| json!({}));
+            let Value::Object(constraints) = constraints else {
+                bail!("input descriptor constraints are not a JSON object");
+            };
+            let fields = constraints.entry("fields").or_insert_with(|| json!([]));
+            let Value::Array(fields) = fields else {
+                bail!("input descriptor fields are not an array");
+            };
+            let filter = match types.as_slice() {
+                [] => continue,
+                [single] => json!({"type": "string", "const": single}),
+                types => json!({"type": "string", "enum": types}),
+            };
+            fields.insert(0, json!({"path": ["$.type"], "filter": filter}));
+        }
+        Ok(Value::Object(definition))
+    }
+}
+
+/// Convert the presentation definition in a (JSON) request object to the
+/// Presentation Exchange v2 shape, if it is written for v1.
+///
+/// # Errors
+/// Will return an error if the presentation definition cannot be converted.
+pub fn pex_v2_request(request: &mut Value) -> anyhow::Result<()> {
+    let Some(definition) = request.get_mut("presentation_definition") else {
+        return Ok(());
+    };
+    let version = PexVersion::detect(definition);
+    *definition = version.presentation_definition(definition.take())?;
+    Ok(())
+}
+
+/// Extract a presentation `RequestObject` from a URL-encoded string as for
+/// [`crate::presentation::parse_request_object`], deserializing the request
+/// according to the parse mode.
+///
+/// # Errors
+/// If the string cannot be decoded or appears to be an encoded request object
+/// but cannot be successfully converted in the given mode, an error is
+/// returned.
+pub fn parse_request_object_with_mode(
+    request: &str, mode: ParseMode,
+) -> anyhow::Result<Option<Parsed<RequestObject>>> {
+    let query = request.split_once('?').map_or(request, |(_, query)| query);
+
+    let mut params = Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = urlencoding::decode(&value.replace('+', " "))
+            .map_err(|e| anyhow!("failed to decode {key}: {e}"))?
+            .into_owned();
+        let value = if JSON_PARAMS.contains(&key) {
+            serde_json::from_str(&value).map_err(|e| anyhow!("failed to parse {key}: {e}"))?
+        } else {
+            Value::String(value)
+        };
+        params.insert(key.into(), value);
+    }
+    if !params.contains_key("presentation_definition") && !params.contains_key("dcql_query") {
+        return Ok(None);
+    }
+
+    let request = Value::Object(params);
+    Dialect::detect(&request).request_object_with_mode(request, mode).map(Some)
+}
+
+// Convert a DCQL query into a presentation definition with an input
+// descriptor for each credential query.
+fn presentation_definition(query: &Value) -> anyhow::Result<Value> {
+    let Some(credentials) = query.get("credentials").and_then(Value::as_array) else {
+        bail!("DCQL query has no credential queries");
+    };
+    if credentials.is_empty() {
+        bail!("DCQL query has no credential queries");
+    }
+    if query.get("credential_sets").is_some() {
+        bail!("DCQL credential sets are not supported");
+    }
+
+    let mut input_descriptors = vec![];
+    for credential in credentials {
+        let Some(id) = credential.get("id").and_then(Value::as_str) else {
+            bail!("credential query has no id");
+        };
+        if credential.get("claim_sets").is_some() {
+            bail!("claim sets in {id} are not supported");
+        }
+
+        let mut fields = vec![];
+        let meta = credential.get("meta");
+        let type_values = meta.and_then(|m| m.get("type_values"));
+        if let Some(type_values) = type_values {
+            // a single set of types, the most specific of which is matched
+            let Some([Value::Array(types)]) = type_values.as_array().map(Vec::as_slice) else {
+                bail!("type values in {id} cannot be expressed as a filter: {type_values}");
+            };
+            if let Some(type_value) = types.last() {
+                fields.push(json!({
+                    "path": ["$.type"],
+                    "filter": {"type": "string", "const": type_value}
+                }));
+            }
+        }
+        if let Some(vct_values) = meta.and_then(|m| m.get("vct_values")) {
+            let Some([vct_value @ Value::String(_)]) = vct_values.as_array().map(Vec::as_slice)
+            else {
+                bail!("vct values in {id} cannot be expressed as a filter: {vct_values}");
+            };
+            fields.push(json!({
+                "path": ["$.vct"],
+                "filter": {"type": "string", "const": vct_value}
+            }));
+        }
+
+        for claim in credential.get("claims").and_then(Value::as_array).into_iter().flatten() {
+            let Some(path) = claim.get("path").and_then(Value::as_array) else {
+                bail!("claim query in {id} has no path");
+            };
+            let mut field = json!({"path": [json_path(path)?]});
+            // only single string values can be expressed as a filter
+            if let Some(values) = claim.get("values") {
+                let Some([value @ Value::String(_)]) = values.as_array().map(Vec::as_slice) else {
+                    bail!("claim values in {id} cannot be expressed as a filter: {values}");
+                };
+                field["filter"] = json!({"type": "string", "const": value});
+            }
+            fields.push(field);
+        }
+
+        let mut descriptor = json!({
+            "id": id,
+            "constraints": {"fields": fields}
+        });
+        if let Some(format) = credential.get("format") {
+            let Some(format) = format.as_str() else {
+                bail!("credential query {id} has an invalid format: {format}");
+            };
+            descriptor["format"] = json!({format: {}});
+        }
+        input_descriptors.push(descriptor);
+    }
+
+    Ok(json!({
+        "id": "dcql_query",
+        "input_descriptors": input_descriptors
+    }))
+}
+
+// The index in `vp_token` of the presentation a descriptor map path (`$` or
+// `$[n]`) points to.
+fn presentation_index(path: &str) -> anyhow::Result<usize> {
+    if path == "$" {
+        return Ok(0);
+    }
+    let index = path.strip_prefix("$[").and_then(|index| index.strip_suffix(']'));
+    index
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| anyhow!("unsupported presentation path: {path}"))
+}
+
+// Convert a DCQL claims path pointer into a JSONPath expression.
+fn json_path(pointer: &[Value]) -> anyhow::Result<String> {
+    let mut path = String::from("$");
+    for component in pointer {
+        match component {
+            Value::String(name) => {
+                path.push('.');
+                path.push_str(name);
+            }
+            Value::Number(index) => write!(path, "[{index}]")?,
+            Value::Null => path.push_str("[*]"),
+            _ => bail!("invalid claims path component: {component}"),
+        }
+    }
+    Ok(path)
+}
+
+// The credential types named by the URIs in a v1 input descriptor's
+// `schema`, which may be a single schema object or an array of them.
+fn schema_types(schema: &Value) -> anyhow::Result<Vec<String>> {
+    let schemas = match schema {
+        Value::Array(schemas) => schemas.as_slice(),
+        schema => std::slice::from_ref(schema),
+    };
+    let mut types = vec![];
+    for schema in schemas {
+        let Some(uri) = schema.get("uri").and_then(Value::as_str) else {
+            bail!("input descriptor schema has no uri");
+        };
+        if GENERIC_SCHEMAS.contains(&uri) {
+            continue;
+        }
+        let name = uri.rsplit(['#', '/']).next().unwrap_or(uri);
+        types.push(name.to_string());
+    }
+    Ok(types)
+}
+
+// Rename v1 format identifiers and convert bare arrays of algorithms (or
+// proof types, for Linked Data formats) to objects.
+fn pex_v2_format(format: &mut Value) {
+    let Value::Object(formats) = format else {
+        return;
+    };
+    for (v1, v2) in V1_FORMATS {
+        if let Some(support) = formats.remove(v1) {
+            formats.entry(v2).or_insert(support);
+        }
+    }
+    for (name, support) in formats.iter_mut() {
+        if support.is_array() {
+            let key = if name.starts_with("ldp") { "proof_type" } else { "alg" };
+            *support = json!({key: support.take()});
+        }
+    }
+}
+
+// Encode a JSON value as a form parameter: strings are sent as is, other
+// values as JSON.
+fn form_value(value: Value) -> anyhow::Result<String> {
+    match value {
+        Value::String(s) => Ok(s),
+        value => Ok(serde_json::to_string(&value)?),
+    }
+}
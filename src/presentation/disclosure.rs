@@ -0,0 +1,270 @@
+//! # Selective Disclosure Preview
+//!
+//! An SD-JWT credential (`<issuer-signed JWT>~<disclosure>~...~`) hides
+//! selectively disclosable claims behind digests in the issuer-signed JWT.
+//! Each claim is revealed by presenting its disclosure: a base64url-encoded
+//! JSON array of a salt, the claim name (for object properties) and the claim
+//! value.
+//!
+//! [`preview`] works out, for a stored SD-JWT credential and a verifier's
+//! request, exactly which disclosures would be released: those for the claims
+//! named by the request's input descriptors, and those for any claims
+//! containing them (a nested claim cannot be revealed without its parent).
+//! Every other disclosure is withheld. The preview is shown to the user before
+//! they approve the presentation, and [`DisclosurePreview::present`] builds
+//! the SD-JWT to present from it, so what is shown is what is sent.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::Kind;
+use crate::credential::Credential;
+use crate::presentation::{RequestObject, descriptor_types};
+
+// The only digest algorithm supported for disclosures.
+const SD_ALG: &str = "sha-256";
+
+// Decoded disclosures keyed by digest: the encoded disclosure and its
+// elements.
+type Disclosures = HashMap<String, (String, Vec<Value>)>;
+
+/// A selectively disclosable claim of an SD-JWT credential.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Disclosure {
+    /// The path to the claim in the credential: property names, and the
+    /// indexes of array elements.
+    pub path: Vec<String>,
+
+    /// The claim value revealed by the disclosure. Nested selectively
+    /// disclosable claims are revealed by their own disclosures.
+    pub value: Value,
+
+    /// The encoded disclosure.
+    pub disclosure: String,
+}
+
+/// The disclosures of an SD-JWT credential that would be released to a
+/// verifier, and those that would be withheld.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DisclosurePreview {
+    /// Disclosures released to the verifier.
+    pub released: Vec<Disclosure>,
+
+    /// Disclosures withheld from the verifier.
+    pub withheld: Vec<Disclosure>,
+}
+
+impl DisclosurePreview {
+    /// The SD-JWT to present: the issuer-signed JWT of the credential as
+    /// issued, with only the released disclosures.
+    #[must_use]
+    pub fn present(&self, issued: &str) -> String {
+        let jwt = issued.split('~').next().unwrap_or_default();
+        let mut sd_jwt = format!("{jwt}~");
+        for disclosure in &self.released {
+            sd_jwt.push_str(&disclosure.disclosure);
+            sd_jwt.push('~');
+        }
+        sd_jwt
+    }
+}
+
+/// Whether a credential (as issued) is an SD-JWT.
+#[must_use]
+pub fn is_sd_jwt(issued: &str) -> bool {
+    issued.contains('~')
+}
+
+/// Preview which disclosures of an SD-JWT credential would be released to
+/// the verifier that made the request, and which would be withheld.
+///
+/// # Errors
+/// Will return an error if the credential is not a valid SD-JWT using
+/// `sha-256` digests, or the request does not contain a presentation
+/// definition object.
+pub fn preview(
+    credential: &Credential, request: &RequestObject,
+) -> anyhow::Result<DisclosurePreview> {
+    let (payload, disclosures) = decode(&credential.issued)?;
+    let requested = requested_paths(credential, &payload, request)?;
+
+    let mut found = vec![];
+    walk(&payload, &[], &disclosures, &mut found)?;
+
+    let mut preview = DisclosurePreview::default();
+    for disclosure in found {
+        if requested.iter().any(|path| overlaps(path, &disclosure.path)) {
+            preview.released.push(disclosure);
+        } else {
+            preview.withheld.push(disclosure);
+        }
+    }
+    Ok(preview)
+}
+
+// Decode the issuer-signed JWT payload and the disclosures of an SD-JWT. A
+// trailing key binding JWT is ignored.
+fn decode(issued: &str) -> anyhow::Result<(Value, Disclosures)> {
+    if !is_sd_jwt(issued) {
+        bail!("credential is not an SD-JWT");
+    }
+    let mut parts = issued.split('~');
+    let jwt = parts.next().unwrap_or_default();
+    let Some(payload) = jwt.split('.').nth(1) else {
+        bail!("SD-JWT has no issuer-signed JWT");
+    };
+    let payload: Value = serde_json::from_slice(&decode_segment(payload)?)?;
+    let alg = payload.get("_sd_alg").and_then(Value::as_str).unwrap_or(SD_ALG);
+    if alg != SD_ALG {
+        bail!("unsupported disclosure digest algorithm: {alg}");
+    }
+
+    let mut disclosures = HashMap::new();
+    for encoded in parts.filter(|part| !part.is_empty() && !part.contains('.')) {
+        let elements: Vec<Value> = serde_json::from_slice(&decode_segment(encoded)?)
+            .map_err(|e| anyhow!("invalid disclosure: {e}"))?;
+        let digest = Base64UrlUnpadded::encode_string(&Sha256::digest(encoded.as_bytes()));
+        disclosures.insert(digest, (encoded.to_string(), elements));
+    }
+    Ok((payload, disclosures))
+}
+
+fn decode_segment(segment: &str) -> anyhow::Result<Vec<u8>> {
+    Base64UrlUnpadded::decode_vec(segment).map_err(|e| anyhow!("invalid SD-JWT segment: {e}"))
+}
+
+// Find the disclosures for the digests in a value, recording the path to
+// each claim. Digests without a disclosure (decoys, or claims the wallet was
+// not given) are skipped.
+fn walk(
+    value: &Value, path: &[String], disclosures: &Disclosures, found: &mut Vec<Disclosure>,
+) -> anyhow::Result<()> {
+    match value {
+        Value::Object(object) => {
+            let digests = object.get("_sd").and_then(Value::as_array).into_iter().flatten();
+            for digest in digests.filter_map(Value::as_str) {
+                let Some((encoded, elements)) = disclosures.get(digest) else {
+                    continue;
+                };
+                let [_, Value::String(name), value] = elements.as_slice() else {
+                    bail!("invalid object property disclosure: {encoded}");
+                };
+                disclose(encoded, value, &child(path, name), disclosures, found)?;
+            }
+            for (name, value) in object {
+                if name != "_sd" && name != "_sd_alg" {
+                    walk(value, &child(path, name), disclosures, found)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let path = child(path, &index.to_string());
+                let Some(digest) = item.get("...").and_then(Value::as_str) else {
+                    walk(item, &path, disclosures, found)?;
+                    continue;
+                };
+                let Some((encoded, elements)) = disclosures.get(digest) else {
+                    continue;
+                };
+                let [_, value] = elements.as_slice() else {
+                    bail!("invalid array element disclosure: {encoded}");
+                };
+                disclose(encoded, value, &path, disclosures, found)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn disclose(
+    encoded: &str, value: &Value, path: &[String], disclosures: &Disclosures,
+    found: &mut Vec<Disclosure>,
+) -> anyhow::Result<()> {
+    found.push(Disclosure {
+        path: path.to_vec(),
+        value: value.clone(),
+        disclosure: encoded.into(),
+    });
+    walk(value, path, disclosures, found)
+}
+
+fn child(path: &[String], segment: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(segment.into());
+    path
+}
+
+// The claim paths requested by the input descriptors that apply to the
+// credential: those without a type constraint, or whose type constraint names
+// one of the credential's types (or its `vct`).
+fn requested_paths(
+    credential: &Credential, payload: &Value, request: &RequestObject,
+) -> anyhow::Result<Vec<Vec<String>>> {
+    let Kind::Object(pd) = &request.presentation_definition else {
+        bail!("presentation_definition_uri is unsupported");
+    };
+    let vct = payload.get("vct").and_then(Value::as_str);
+    let nested = payload.get("credentialSubject").is_some();
+
+    let mut paths = vec![];
+    for descriptor in &pd.input_descriptors {
+        let types = descriptor_types(descriptor);
+        if !types.is_empty()
+            && !types.iter().any(|t| credential.type_.contains(t) || Some(t.as_str()) == vct)
+        {
+            continue;
+        }
+        let descriptor = serde_json::to_value(descriptor)?;
+        let fields = descriptor["constraints"]["fields"].as_array().into_iter().flatten();
+        for field in fields {
+            for path in field["path"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                let mut segments = parse_path(path)?;
+                // SD-JWT VC claims are not nested under `credentialSubject`
+                if !nested && segments.first().is_some_and(|s| s == "credentialSubject") {
+                    segments.remove(0);
+                }
+                paths.push(segments);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+// Split a JSONPath expression (`$.address.street`, `$['name']`, `$.a[0]`,
+// `$.a[*]`) into its segments.
+fn parse_path(path: &str) -> anyhow::Result<Vec<String>> {
+    let unsupported = || anyhow!("unsupported field path: {path}");
+    let mut rest = path.strip_prefix('$').ok_or_else(unsupported)?;
+    let mut segments = vec![];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("['") {
+            let (name, after) = after.split_once("']").ok_or_else(unsupported)?;
+            segments.push(name.to_string());
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (index, after) = after.split_once(']').ok_or_else(unsupported)?;
+            segments.push(index.to_string());
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            segments.push(after[..end].to_string());
+            rest = &after[end..];
+        } else {
+            return Err(unsupported());
+        }
+    }
+    Ok(segments)
+}
+
+// Whether a requested path names the claim or one it contains, or a claim
+// within it. `*` matches any property or array element.
+fn overlaps(requested: &[String], claim: &[String]) -> bool {
+    requested.iter().zip(claim).all(|(r, c)| r == "*" || r == c)
+}
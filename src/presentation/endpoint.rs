@@ -0,0 +1,148 @@
+//! # Response Endpoints
+//!
+//! A verifier tells the wallet where to send its response using one of two
+//! request parameters, depending on the response mode:
+//!
+//! - `direct_post` and `direct_post.jwt` responses are posted to the
+//!   `response_uri`, and the request must not have a `redirect_uri`.
+//! - Other modes (`fragment`, `query`) redirect the response to the
+//!   `redirect_uri`, and the request must not have a `response_uri`. When the
+//!   verifier uses the `redirect_uri` client ID scheme, its client ID is the
+//!   redirect URI and the parameter may be omitted.
+//!
+//! [`ResponseEndpoint`] applies these rules, removes trailing slashes, and
+//! requires endpoints to use `https` (plain `http` is accepted for loopback
+//! hosts, for local development), so wallets do not need to clean up the
+//! endpoint themselves.
+
+use serde_json::Value;
+
+use crate::error::InvalidResponseEndpoint;
+
+// Response modes whose responses are posted to the `response_uri`.
+const POST_MODES: [&str; 2] = ["direct_post", "direct_post.jwt"];
+
+// The response mode for `vp_token` and `id_token` requests without one.
+const DEFAULT_MODE: &str = "fragment";
+
+// Hosts for which `http` endpoints are accepted.
+const LOOPBACK: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+/// Where a response to a presentation (or SIOP) request is sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResponseEndpoint {
+    /// The response is posted to the request's `response_uri`.
+    Post(String),
+
+    /// The response is returned by redirecting to the request's
+    /// `redirect_uri`.
+    Redirect(String),
+}
+
+impl ResponseEndpoint {
+    /// Validate and normalize the response endpoint for a response mode.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidResponseEndpoint`] error if the endpoint the
+    /// response mode requires is missing, the request has both a
+    /// `response_uri` and a `redirect_uri`, or the endpoint does not use
+    /// `https`.
+    pub fn new(
+        response_mode: Option<&str>, response_uri: Option<&str>, redirect_uri: Option<&str>,
+    ) -> Result<Self, InvalidResponseEndpoint> {
+        let mode = response_mode.unwrap_or(DEFAULT_MODE);
+        let invalid = |reason: String| InvalidResponseEndpoint {
+            response_mode: mode.into(),
+            reason,
+        };
+
+        if POST_MODES.contains(&mode) {
+            if redirect_uri.is_some() {
+                return Err(invalid("`redirect_uri` must not be present".into()));
+            }
+            let Some(uri) = response_uri else {
+                return Err(invalid("`response_uri` is required".into()));
+            };
+            return normalize("response_uri", uri).map(Self::Post).map_err(invalid);
+        }
+
+        if response_uri.is_some() {
+            return Err(invalid("`response_uri` must not be present".into()));
+        }
+        let Some(uri) = redirect_uri else {
+            return Err(invalid("`redirect_uri` is required".into()));
+        };
+        normalize("redirect_uri", uri).map(Self::Redirect).map_err(invalid)
+    }
+
+    /// The response endpoint for a request object (or SIOP request), or
+    /// `None` if the request uses a redirect response mode without a redirect
+    /// URI: the response is returned to the caller rather than sent.
+    ///
+    /// # Errors
+    /// Will return an [`InvalidResponseEndpoint`] error if the endpoint is
+    /// invalid (see [`Self::new`]).
+    pub fn from_request(request: &Value) -> Result<Option<Self>, InvalidResponseEndpoint> {
+        let param = |name: &str| request.get(name).and_then(Value::as_str);
+        let response_mode = param("response_mode");
+        let response_uri = param("response_uri");
+
+        // Only an explicit `redirect_uri` parameter is an error for the post
+        // modes.
+        let post = POST_MODES.contains(&response_mode.unwrap_or(DEFAULT_MODE));
+        if post {
+            return Self::new(response_mode, response_uri, param("redirect_uri")).map(Some);
+        }
+
+        // With the `redirect_uri` client ID scheme, the client ID is the
+        // redirect URI.
+        let client_id = param("client_id").unwrap_or_default();
+        let redirect_uri = param("redirect_uri").or_else(|| match client_id.split_once(':') {
+            Some(("redirect_uri", uri)) => Some(uri),
+            _ if param("client_id_scheme") == Some("redirect_uri") => Some(client_id),
+            _ => None,
+        });
+        if response_uri.is_none() && redirect_uri.is_none() {
+            return Ok(None);
+        }
+        Self::new(response_mode, response_uri, redirect_uri).map(Some)
+    }
+
+    /// The endpoint URI.
+    #[must_use]
+    pub fn uri(&self) -> &str {
+        match self {
+            Self::Post(uri) | Self::Redirect(uri) => uri,
+        }
+    }
+
+    /// The URI to post the response to, if the response is posted.
+    #[must_use]
+    pub fn post_uri(&self) -> Option<&str> {
+        match self {
+            Self::Post(uri) => Some(uri),
+            Self::Redirect(_) => None,
+        }
+    }
+}
+
+// Remove trailing slashes and check the endpoint uses `https` (or `http` for
+// a loopback host).
+fn normalize(param: &str, uri: &str) -> Result<String, String> {
+    let uri = uri.trim_end_matches('/');
+    let secure = match uri.split_once("://") {
+        Some(("https", rest)) => !rest.is_empty(),
+        Some(("http", rest)) => {
+            let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+            LOOPBACK.iter().any(|host| {
+                authority == *host
+                    || authority.strip_prefix(host).is_some_and(|port| port.starts_with(':'))
+            })
+        }
+        _ => false,
+    };
+    if !secure {
+        return Err(format!("`{param}` must be an https URI: {uri}"));
+    }
+    Ok(uri.to_string())
+}
@@ -0,0 +1,180 @@
+//! # Format Negotiation
+//!
+//! Verifiers advertise the formats and signing algorithms they accept in the
+//! `vp_formats` member of their client metadata (`vp_formats_supported` in
+//! later drafts). Before a presentation is built, the wallet chooses a
+//! presentation format that it can create, that it declares in its own
+//! [`WalletMetadata`] and that the verifier accepts, signed with an algorithm
+//! both support, and checks the verifier accepts the format of every
+//! credential being presented. If there is no such format the flow fails
+//! early with a [`NoCommonFormat`] error rather than sending a presentation
+//! the verifier will reject.
+//!
+//! Keys of `vp_formats` name either presentation formats (for example,
+//! `jwt_vp_json`) or credential formats (for example, `jwt_vc_json`). The
+//! algorithms listed for a credential format are those the credential must be
+//! signed with by its issuer, so only the algorithms listed for presentation
+//! formats are matched against the wallet's signing algorithm. A verifier that
+//! lists no presentation (or no credential) formats is assumed to accept any.
+//!
+//! Verifiers often list fewer algorithms than they accept, so a verifier that
+//! accepts a presentation format but does not list the wallet's algorithm for
+//! it is not rejected: the format is chosen anyway and the presentation is
+//! signed with the wallet's algorithm, leaving the verifier to decide. A
+//! format whose algorithm the verifier lists is preferred.
+//!
+//! `RequestObject` only models `jwt_vp_json` in `vp_formats`, so metadata
+//! listing other formats cannot be deserialized with the request object. Use
+//! [`negotiate_metadata`] with the verifier's client metadata as received
+//! (JSON) to negotiate with such verifiers.
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::credential::Credential;
+use crate::error::NoCommonFormat;
+use crate::metadata::WalletMetadata;
+use crate::presentation::RequestObject;
+use crate::presentation::proof::W3cFormat;
+use crate::provider::Algorithm;
+
+/// Presentation formats the wallet can create, in order of preference.
+pub(crate) const PRESENTATION_FORMATS: [&str; 2] = ["jwt_vp_json", "jwt_vp"];
+
+/// Presentation format identifiers, including those the wallet cannot create.
+const KNOWN_PRESENTATION_FORMATS: [&str; 4] = ["jwt_vp_json", "jwt_vp", "ldp_vp", "jwt_vp_json-ld"];
+
+// Keys used for the list of algorithms in successive drafts.
+const ALG_KEYS: [&str; 3] = ["alg", "alg_values", "alg_values_supported"];
+
+/// The presentation format chosen for a verifier.
+#[derive(Clone, Debug)]
+pub struct NegotiatedFormat {
+    /// The presentation format identifier (for example, `jwt_vp_json`).
+    pub format: String,
+
+    /// The algorithm the presentation will be signed with.
+    pub algorithm: Algorithm,
+
+    /// Whether the verifier lists the algorithm for the format (or lists no
+    /// algorithms). When `false`, the verifier may reject the presentation.
+    pub algorithm_listed: bool,
+}
+
+impl NegotiatedFormat {
+    /// The proof format to create the presentation with.
+    #[must_use]
+    pub const fn proof_format(&self) -> W3cFormat {
+        W3cFormat::JwtVcJson
+    }
+}
+
+/// Choose a presentation format for the credentials that both the wallet and
+/// the verifier that made the request support, to be signed with the
+/// wallet's signing algorithm.
+///
+/// # Errors
+/// Returns a [`NoCommonFormat`] error if there is no mutually supported
+/// format, or an error if the request cannot be serialized.
+pub fn negotiate(
+    request: &RequestObject, credentials: &[Credential], wallet: &WalletMetadata,
+    algorithm: &Algorithm,
+) -> anyhow::Result<NegotiatedFormat> {
+    let metadata = serde_json::to_value(&request.client_metadata)?;
+    negotiate_metadata(&metadata, credentials, wallet, algorithm)
+}
+
+/// Choose a presentation format as for [`negotiate`] from the verifier's
+/// (JSON) client metadata, which may list formats `RequestObject` cannot
+/// represent.
+///
+/// # Errors
+/// Returns a [`NoCommonFormat`] error if there is no mutually supported
+/// format, or an error if the algorithm cannot be serialized.
+pub fn negotiate_metadata(
+    metadata: &Value, credentials: &[Credential], wallet: &WalletMetadata, algorithm: &Algorithm,
+) -> anyhow::Result<NegotiatedFormat> {
+    let verifier = metadata
+        .get("vp_formats")
+        .or_else(|| metadata.get("vp_formats_supported"))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    let alg = serde_json::to_value(algorithm)?
+        .as_str()
+        .map(ToString::to_string)
+        .ok_or_else(|| anyhow!("algorithm should serialize to a string"))?;
+
+    let no_common_format = || NoCommonFormat {
+        verifier: verifier.keys().cloned().collect(),
+        wallet: wallet.vp_formats_supported.keys().cloned().collect(),
+    };
+
+    // Every credential must be in a format the wallet and the verifier
+    // accept.
+    let wallet_formats = wallet.vp_formats_supported.keys().map(String::as_str);
+    let verifier_formats = verifier.keys().map(String::as_str);
+    let credential_formats = [wallet_formats.collect::<Vec<_>>(), verifier_formats.collect()];
+    for credential in credentials {
+        if !credential_formats.iter().all(|formats| accepts_credential(formats, &credential.format))
+        {
+            return Err(no_common_format().into());
+        }
+    }
+
+    let verifier_lists_presentations = verifier.keys().any(|f| is_presentation_format(f));
+    let candidates = PRESENTATION_FORMATS
+        .into_iter()
+        .filter(|format| {
+            let wallet_support = wallet.vp_formats_supported.get(*format).is_some_and(|support| {
+                support
+                    .alg_values_supported
+                    .iter()
+                    .any(|a| serde_json::to_value(a).is_ok_and(|a| a == alg.as_str()))
+            });
+            wallet_support && (!verifier_lists_presentations || verifier.contains_key(*format))
+        })
+        .collect::<Vec<_>>();
+
+    // prefer a format the verifier lists the wallet's algorithm for
+    let algorithm_listed =
+        |format: &str| verifier.get(format).is_none_or(|support| accepts_alg(support, &alg));
+    let format = candidates.iter().find(|f| algorithm_listed(f)).or_else(|| candidates.first());
+    let Some(format) = format else {
+        return Err(no_common_format().into());
+    };
+
+    Ok(NegotiatedFormat {
+        format: (*format).to_string(),
+        algorithm: algorithm.clone(),
+        algorithm_listed: algorithm_listed(format),
+    })
+}
+
+// Whether the format identifier names a presentation format rather than a
+// credential format.
+fn is_presentation_format(format: &str) -> bool {
+    KNOWN_PRESENTATION_FORMATS.contains(&format)
+}
+
+// Whether a list of formats accepts a credential format. A list naming no
+// credential formats accepts any.
+fn accepts_credential(formats: &[&str], format: &str) -> bool {
+    let mut credential_formats = formats.iter().filter(|f| !is_presentation_format(f)).peekable();
+    credential_formats.peek().is_none() || credential_formats.any(|f| *f == format)
+}
+
+// Whether the verifier's support for a format includes the algorithm. A
+// format listed without algorithms accepts any.
+fn accepts_alg(support: &Value, alg: &str) -> bool {
+    let Some(support) = support.as_object() else {
+        return true;
+    };
+    let algs = ALG_KEYS.iter().filter_map(|key| support.get(*key)).collect::<Vec<_>>();
+    algs.is_empty() || algs.iter().any(|algs| listed(algs, alg))
+}
+
+// Whether a JSON array of algorithms includes the algorithm.
+fn listed(algs: &Value, alg: &str) -> bool {
+    algs.as_array().is_some_and(|algs| algs.iter().any(|a| a == alg))
+}
@@ -0,0 +1,198 @@
+//! # Reader Authentication
+//!
+//! Verifiers requesting mdocs (ISO/IEC 18013-5 and 18013-7) authenticate
+//! themselves as a "reader" by signing their request with a certificate
+//! issued by a reader authentication CA. Over `OpenID` for Verifiable
+//! Presentations, the request object JWT carries the reader's certificate
+//! chain in its `x5c` header, and the verifier's `client_id` is bound to the
+//! certificate using the `x509_san_dns`, `x509_san_uri` or `x509_hash` client
+//! ID scheme.
+//!
+//! [`parse_signed_request`] verifies the chain and signature using the
+//! wallet's `ReaderTrust` provider, checks the certificate identifies the
+//! `client_id`, and returns the verified [`ReaderIdentity`]. Record the result
+//! on the presentation flow with
+//! [`PresentationFlow::with_reader`](crate::presentation::PresentationFlow::with_reader)
+//! so the consent UI can distinguish authenticated verifiers from anonymous
+//! ones.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::clock::TimeValidator;
+use crate::error::ReaderNotAuthenticated;
+use crate::parse::ParseMode;
+use crate::presentation::{RequestObject, compat};
+use crate::provider::ReaderTrust;
+
+/// The identity of a verifier, taken from its verified reader certificate.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ReaderIdentity {
+    /// The subject (distinguished name) of the reader certificate.
+    pub subject: String,
+
+    /// The issuer (distinguished name) of the reader certificate: the reader
+    /// authentication CA.
+    pub issuer: String,
+
+    /// The DNS names in the certificate's subject alternative names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_names: Vec<String>,
+
+    /// The URIs in the certificate's subject alternative names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uris: Vec<String>,
+}
+
+/// How the verifier of a presentation request was authenticated.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "reader", rename_all = "snake_case")]
+pub enum ReaderAuthentication {
+    /// The request was not signed with a reader certificate.
+    #[default]
+    Anonymous,
+
+    /// The verifier was authenticated by its reader certificate.
+    Authenticated(ReaderIdentity),
+}
+
+impl ReaderAuthentication {
+    /// Whether the request was not signed with a reader certificate.
+    #[must_use]
+    pub const fn is_anonymous(&self) -> bool {
+        matches!(self, Self::Anonymous)
+    }
+
+    /// The verified identity of the verifier, if it was authenticated.
+    #[must_use]
+    pub const fn identity(&self) -> Option<&ReaderIdentity> {
+        match self {
+            Self::Anonymous => None,
+            Self::Authenticated(identity) => Some(identity),
+        }
+    }
+}
+
+/// Whether a request object JWT is signed with a reader certificate (has an
+/// `x5c` header).
+#[must_use]
+pub fn has_reader_certificate(token: &str) -> bool {
+    token
+        .split('.')
+        .next()
+        .and_then(|header| decode_json(header).ok())
+        .is_some_and(|header| header.get("x5c").is_some())
+}
+
+/// Parse a request object JWT signed with a reader certificate, verifying the
+/// certificate chain and signature and that the certificate identifies the
+/// verifier's `client_id`.
+///
+/// # Errors
+/// Will return a [`ReaderNotAuthenticated`] error if the reader is not
+/// authenticated, an [`InvalidTime`](crate::error::InvalidTime) error if the
+/// request object is not valid at the current time, or an error if the JWT
+/// cannot be decoded or the request object cannot be parsed.
+pub async fn parse_signed_request(
+    token: &str, trust: &impl ReaderTrust,
+) -> anyhow::Result<(RequestObject, ReaderIdentity)> {
+    parse_signed_request_with(token, trust, &TimeValidator::default()).await
+}
+
+/// Parse a request object JWT signed with a reader certificate as
+/// [`parse_signed_request`] does, checking its `iat`, `nbf` and `exp` claims
+/// with the time validator.
+///
+/// # Errors
+/// Will return an error as for [`parse_signed_request`].
+pub async fn parse_signed_request_with(
+    token: &str, trust: &impl ReaderTrust, times: &TimeValidator,
+) -> anyhow::Result<(RequestObject, ReaderIdentity)> {
+    let Some((signed, signature)) = token.rsplit_once('.') else {
+        bail!("request object is not a compact JWS");
+    };
+    let Some((header, payload)) = signed.split_once('.') else {
+        bail!("request object is not a compact JWS");
+    };
+    let header = decode_json(header)?;
+    let mut claims = decode_json(payload)?;
+    let signature =
+        Base64UrlUnpadded::decode_vec(signature).map_err(|e| anyhow!("invalid signature: {e}"))?;
+
+    let client_id = claims["client_id"].as_str().unwrap_or_default().to_string();
+    let not_authenticated = |reason: String| {
+        anyhow!(ReaderNotAuthenticated {
+            client_id: client_id.clone(),
+            reason,
+        })
+    };
+
+    let chain = certificates(&header).map_err(not_authenticated)?;
+    let Some(alg) = header["alg"].as_str() else {
+        return Err(not_authenticated("request object has no `alg` header".into()));
+    };
+    let identity = trust
+        .verify_reader(&chain, alg, signed.as_bytes(), &signature)
+        .await
+        .map_err(|e| not_authenticated(format!("{e:#}")))?;
+    let scheme = claims["client_id_scheme"].as_str();
+    check_client_id(&client_id, scheme, &identity, &chain[0]).map_err(not_authenticated)?;
+    times.check_claims(&claims)?;
+
+    // the X.509 scheme has been checked and is not modelled by `RequestObject`
+    if let Some(claims) = claims.as_object_mut() {
+        claims.remove("client_id_scheme");
+    }
+    compat::pex_v2_request(&mut claims)?;
+    let parsed = ParseMode::default()
+        .parse_value(claims)
+        .map_err(|e| anyhow!("failed to parse request object: {e}"))?;
+    Ok((parsed.value, identity))
+}
+
+// Decode a base64url-encoded JSON JWS segment.
+fn decode_json(segment: &str) -> anyhow::Result<Value> {
+    let bytes =
+        Base64UrlUnpadded::decode_vec(segment).map_err(|e| anyhow!("invalid JWT segment: {e}"))?;
+    serde_json::from_slice(&bytes).map_err(|e| anyhow!("invalid JWT segment: {e}"))
+}
+
+// The DER-encoded certificates in the `x5c` header, leaf first.
+fn certificates(header: &Value) -> Result<Vec<Vec<u8>>, String> {
+    let Some(x5c) = header.get("x5c").and_then(Value::as_array) else {
+        return Err("request object has no `x5c` header".into());
+    };
+    if x5c.is_empty() {
+        return Err("`x5c` header is empty".into());
+    }
+    x5c.iter()
+        .map(|cert| {
+            let cert = cert.as_str().ok_or("`x5c` certificate is not a string")?;
+            Base64::decode_vec(cert).map_err(|e| format!("invalid `x5c` certificate: {e}"))
+        })
+        .collect()
+}
+
+// Check the reader certificate identifies the client ID, using the client ID
+// prefix (or, for earlier drafts, the `client_id_scheme`).
+fn check_client_id(
+    client_id: &str, scheme: Option<&str>, identity: &ReaderIdentity, leaf: &[u8],
+) -> Result<(), String> {
+    let (scheme, value) = match client_id.split_once(':') {
+        Some((scheme @ ("x509_san_dns" | "x509_san_uri" | "x509_hash"), value)) => (scheme, value),
+        _ => (scheme.unwrap_or_default(), client_id),
+    };
+    let identified = match scheme {
+        "x509_san_dns" => identity.dns_names.iter().any(|name| name == value),
+        "x509_san_uri" => identity.uris.iter().any(|uri| uri == value),
+        "x509_hash" => Base64UrlUnpadded::encode_string(&Sha256::digest(leaf)) == value,
+        _ => return Err(format!("client ID scheme `{scheme}` does not use a certificate")),
+    };
+    if !identified {
+        return Err("reader certificate does not identify the client ID".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! # Request Object Signatures
+//!
+//! A request object is usually a compact JWS signed by the verifier, but it
+//! may carry more than one signature using the JWS JSON serialization (RFC
+//! 7515 section 7.2): for example, the verifier's signature and a
+//! countersignature by a trust anchor vouching for the verifier.
+//!
+//! [`verify_request_object`] verifies every signature, resolving each
+//! signer's key from the `kid` in its protected header, and reports which
+//! identities signed. A [`SignaturePolicy`] decides which signatures are
+//! required: any one, all of them, the verifier's, or a specific signer's.
+//! Signers are identified by DID, and a signer whose DID is the request's
+//! `client_id` has the [`SignerRole::Verifier`] role; any other signer is a
+//! [`SignerRole::Countersigner`].
+
+use std::fmt::{self, Display};
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_vc::did::{DidResolver, Resource, dereference};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::SignaturePolicyUnmet;
+use crate::parse::{ParseMode, Parsed};
+use crate::presentation::{RequestObject, compat};
+
+/// The signatures a request object must carry to be accepted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignaturePolicy {
+    /// At least one signature must verify.
+    #[default]
+    Any,
+
+    /// Every signature must verify.
+    All,
+
+    /// A signature by a signer with the role must verify.
+    Role(SignerRole),
+
+    /// A signature by the signer (identified by DID) must verify.
+    Signer(String),
+}
+
+impl Display for SignaturePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "any signature"),
+            Self::All => write!(f, "all signatures"),
+            Self::Role(role) => write!(f, "a {role} signature"),
+            Self::Signer(did) => write!(f, "a signature by {did}"),
+        }
+    }
+}
+
+/// The role of a request object's signer.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerRole {
+    /// The verifier that made the request: the signer's DID is the request's
+    /// `client_id`.
+    Verifier,
+
+    /// Another party vouching for the request, such as a trust anchor.
+    Countersigner,
+}
+
+impl Display for SignerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verifier => write!(f, "verifier"),
+            Self::Countersigner => write!(f, "countersigner"),
+        }
+    }
+}
+
+/// A signature on a request object, and whether it was verified.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RequestSignature {
+    /// The key ID from the signature's protected header.
+    pub kid: String,
+
+    /// The DID of the signer (the key ID without its fragment).
+    pub signer: String,
+
+    /// The role of the signer.
+    pub role: SignerRole,
+
+    /// Why the signature was not verified, or `None` if it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RequestSignature {
+    /// Whether the signature was verified.
+    #[must_use]
+    pub const fn is_verified(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A request object whose signatures meet a [`SignaturePolicy`].
+#[derive(Clone, Debug)]
+pub struct SignedRequest {
+    /// The request object.
+    pub request: Parsed<RequestObject>,
+
+    /// The request object's signatures, in the order they appear.
+    pub signatures: Vec<RequestSignature>,
+}
+
+impl SignedRequest {
+    /// The DIDs of the signers whose signatures were verified.
+    #[must_use]
+    pub fn signers(&self) -> Vec<&str> {
+        let verified = self.signatures.iter().filter(|s| s.is_verified());
+        verified.map(|s| s.signer.as_str()).collect()
+    }
+}
+
+/// Verify the signatures of a request object serialized as a compact JWS or
+/// a JWS JSON serialization, and parse the request object according to the
+/// parse mode.
+///
+/// # Errors
+/// Will return a [`SignaturePolicyUnmet`] error if the verified signatures do
+/// not meet the policy, or an error if the JWS cannot be decoded or the
+/// request object cannot be parsed.
+pub async fn verify_request_object(
+    token: &str, resolver: impl DidResolver, policy: &SignaturePolicy, mode: ParseMode,
+) -> anyhow::Result<SignedRequest> {
+    let (payload, signatures) = split(token)?;
+    let claims: Value = serde_json::from_slice(&decode_segment(&payload)?)?;
+    let client_id = claims["client_id"].as_str().unwrap_or_default();
+    let verifier_did = client_id.strip_prefix("decentralized_identifier:").unwrap_or(client_id);
+
+    let mut checked = vec![];
+    for (protected, signature) in signatures {
+        let header: Value = serde_json::from_slice(&decode_segment(&protected)?)?;
+        let kid = header["kid"].as_str().unwrap_or_default().to_string();
+        let signer = kid.split('#').next().unwrap_or_default().to_string();
+        let role =
+            if signer == verifier_did { SignerRole::Verifier } else { SignerRole::Countersigner };
+        let result = verify(&protected, &payload, &signature, &kid, resolver.clone()).await;
+        let error = result.err().map(|e| format!("{e:#}"));
+        checked.push(RequestSignature {
+            kid,
+            signer,
+            role,
+            error,
+        });
+    }
+
+    let verified = || checked.iter().filter(|s| s.is_verified());
+    let met = match policy {
+        SignaturePolicy::Any => verified().next().is_some(),
+        SignaturePolicy::All => checked.iter().all(RequestSignature::is_verified),
+        SignaturePolicy::Role(role) => verified().any(|s| s.role == *role),
+        SignaturePolicy::Signer(did) => verified().any(|s| s.signer == *did),
+    };
+    if !met {
+        return Err(SignaturePolicyUnmet {
+            policy: policy.to_string(),
+            signers: verified().map(|s| s.signer.clone()).collect(),
+            errors: checked.iter().filter_map(|s| s.error.clone()).collect(),
+        }
+        .into());
+    }
+
+    let mut claims = claims;
+    compat::pex_v2_request(&mut claims)?;
+    let request =
+        mode.parse_value(claims).map_err(|e| anyhow!("failed to parse request object: {e}"))?;
+    Ok(SignedRequest {
+        request,
+        signatures: checked,
+    })
+}
+
+// Split a JWS into its encoded payload and the encoded protected header and
+// signature of each signature.
+fn split(token: &str) -> anyhow::Result<(String, Vec<(String, String)>)> {
+    let token = token.trim();
+    if !token.starts_with('{') {
+        let mut parts = token.split('.');
+        let (Some(protected), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("request object is not a compact JWS");
+        };
+        return Ok((payload.into(), vec![(protected.into(), signature.into())]));
+    }
+
+    let json: Value = serde_json::from_str(token)?;
+    let Some(payload) = json["payload"].as_str() else {
+        bail!("JWS has no payload");
+    };
+    // the flattened serialization has a single signature at the top level
+    let signatures = match json.get("signatures") {
+        Some(Value::Array(signatures)) => signatures.clone(),
+        Some(_) => bail!("JWS signatures are not an array"),
+        None => vec![json.clone()],
+    };
+    if signatures.is_empty() {
+        bail!("JWS has no signatures");
+    }
+    let signatures = signatures
+        .iter()
+        .map(|s| match (s["protected"].as_str(), s["signature"].as_str()) {
+            (Some(protected), Some(signature)) => Ok((protected.into(), signature.into())),
+            _ => Err(anyhow!("JWS signature has no protected header or signature")),
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok((payload.into(), signatures))
+}
+
+fn decode_segment(segment: &str) -> anyhow::Result<Vec<u8>> {
+    Base64UrlUnpadded::decode_vec(segment).map_err(|e| anyhow!("invalid JWS segment: {e}"))
+}
+
+// Verify a signature over the encoded protected header and payload as they
+// appear in the JWS, using the key referenced by the `kid` header. The header
+// is not re-serialized, so headers in any member order verify.
+async fn verify(
+    protected: &str, payload: &str, signature: &str, kid: &str, resolver: impl DidResolver,
+) -> anyhow::Result<()> {
+    if kid.is_empty() {
+        bail!("missing key ID in JWS signature");
+    }
+    let resp = dereference(kid, None, resolver)
+        .await
+        .map_err(|e| anyhow!("issue dereferencing DID: {e}"))?;
+    let Some(Resource::VerificationMethod(vm)) = resp.content_stream else {
+        bail!("verification method not found");
+    };
+    let jwk = vm.method_type.jwk().map_err(|e| anyhow!("JWK not found: {e}"))?;
+    jwk.verify(&format!("{protected}.{payload}"), &decode_segment(signature)?)
+}
@@ -0,0 +1,381 @@
+//! # Self-Issued OpenID Provider
+//!
+//! Types needed to respond to [SIOPv2](https://openid.net/specs/openid-connect-self-issued-v2-1_0.html)
+//! authentication requests (`response_type=id_token`), where the wallet acts
+//! as its own OpenID provider and authenticates the holder to a relying party
+//! with a self-issued ID token signed by the holder's key.
+//!
+//! As with presentations, the flow does not sign anything itself. The wallet
+//! signs the claims returned by [`IdTokenFlow::claims`] using the signer
+//! returned by [`IdTokenFlow::signer`], so the holder's consent is obtained
+//! before the ID token is issued.
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail};
+use credibil_vc::did::{DidResolver, Resource, dereference};
+use credibil_vc::infosec::Algorithm;
+use credibil_vc::infosec::jose::jws;
+use credibil_vc::urlencode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::clock::TimeValidator;
+use crate::consent::{GatedSigner, SigningOperation};
+use crate::context::WalletContext;
+use crate::presentation::endpoint::ResponseEndpoint;
+use crate::provider::{ConsentGate, Signer};
+use crate::redact;
+use crate::secret::constant_time_eq;
+
+/// The authorization endpoint used to invoke a self-issued OP.
+pub const SIOP_AUTHORIZATION_ENDPOINT: &str = "siopv2:";
+
+// The number of seconds a self-issued ID token is valid for.
+const ID_TOKEN_LIFETIME: i64 = 600;
+
+/// Utility to extract a self-issued ID token request from a URL-encoded
+/// string. If the request string can be decoded but is not an ID token
+/// request (for example, a `vp_token` request), None is returned.
+///
+/// # Errors
+/// If the request is an ID token request but cannot be successfully
+/// deserialized, an error is returned.
+pub fn parse_id_token_request(request: &str) -> anyhow::Result<Option<IdTokenRequest>> {
+    let query = request.split_once('?').map_or(request, |(_, query)| query);
+    if !query.split('&').any(|pair| pair == "response_type=id_token") {
+        return Ok(None);
+    }
+    let request = urlencode::from_str::<IdTokenRequest>(query)
+        .map_err(|e| anyhow!("failed to parse ID token request: {e}"))?;
+    Ok(Some(request))
+}
+
+/// Decode a self-issued ID token and verify its signature using the key
+/// referenced by the token's `kid` header. Uses a DID resolver to resolve the
+/// key.
+///
+/// The token must be issued to `client_id` in response to the request
+/// carrying `nonce`, and signed by a key belonging to the holder's DID.
+///
+/// This is the check a relying party makes on receipt of the token and is
+/// useful for testing wallets.
+///
+/// # Errors
+/// If decoding or verifying the JWT fails, the token has expired, the
+/// audience or nonce do not match, or the token is not self-issued (issuer,
+/// subject, and signing key DID differ), an error is returned.
+pub async fn verify_id_token(
+    token: &str, client_id: &str, nonce: &str, resolver: impl DidResolver,
+) -> anyhow::Result<IdTokenClaims> {
+    verify_id_token_with(token, client_id, nonce, resolver, &TimeValidator::default()).await
+}
+
+/// Decode and verify a self-issued ID token as [`verify_id_token`] does,
+/// checking its `iat` and `exp` claims with the time validator.
+///
+/// # Errors
+/// If decoding or verifying the JWT fails, the token is not valid at the
+/// current time (an [`InvalidTime`](crate::error::InvalidTime) error), the
+/// audience or nonce do not match, or the token is not self-issued, an error
+/// is returned.
+pub async fn verify_id_token_with(
+    token: &str, client_id: &str, nonce: &str, resolver: impl DidResolver, times: &TimeValidator,
+) -> anyhow::Result<IdTokenClaims> {
+    let signing_did = Arc::new(Mutex::new(String::new()));
+    let kid_did = Arc::clone(&signing_did);
+    let jwt: jws::Jwt<IdTokenClaims> = jws::decode(token, move |kid| {
+        let local_resolver = resolver.clone();
+        let signing_did = Arc::clone(&kid_did);
+        async move {
+            let did = kid.split('#').next().unwrap_or_default().to_string();
+            *signing_did.lock().map_err(|_| anyhow!("signing DID lock poisoned"))? = did;
+            let resp = dereference(&kid, None, local_resolver)
+                .await
+                .map_err(|e| anyhow!("issue dereferencing DID: {e}"))?;
+            let Some(Resource::VerificationMethod(vm)) = resp.content_stream else {
+                return Err(anyhow!("Verification method not found"));
+            };
+            vm.method_type.jwk().map_err(|e| anyhow!("JWK not found: {e}"))
+        }
+    })
+    .await
+    .map_err(|e| anyhow!("failed to parse JWT: {e}"))?;
+
+    let claims = jwt.claims;
+    let signing_did = signing_did.lock().map_err(|_| anyhow!("signing DID lock poisoned"))?.clone();
+    if claims.iss != claims.sub || claims.iss != signing_did {
+        bail!("ID token is not self-issued");
+    }
+    if claims.aud != client_id {
+        bail!("ID token audience does not match the client ID");
+    }
+    if !constant_time_eq(claims.nonce.as_bytes(), nonce.as_bytes()) {
+        bail!("ID token nonce does not match the request");
+    }
+    times.check_claims(&serde_json::to_value(&claims)?)?;
+    Ok(claims)
+}
+
+/// Self-issued OP metadata (SIOPv2 section 7) the wallet can make available
+/// to relying parties.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SiopMetadata {
+    /// The URL (or custom scheme) used to invoke the self-issued OP.
+    pub authorization_endpoint: String,
+
+    /// The response types supported. Only `id_token` is supported.
+    pub response_types_supported: Vec<String>,
+
+    /// The scopes supported. Only `openid` is supported.
+    pub scopes_supported: Vec<String>,
+
+    /// The subject types supported. DID subjects are `pairwise`.
+    pub subject_types_supported: Vec<String>,
+
+    /// The algorithms the wallet uses to sign ID tokens.
+    pub id_token_signing_alg_values_supported: Vec<Algorithm>,
+
+    /// The algorithms the wallet accepts for signed request objects.
+    pub request_object_signing_alg_values_supported: Vec<Algorithm>,
+
+    /// The subject syntax types (DID methods) supported.
+    pub subject_syntax_types_supported: Vec<String>,
+
+    /// The ID token types supported. Only subject-signed ID tokens are
+    /// issued.
+    pub id_token_types_supported: Vec<String>,
+}
+
+impl SiopMetadata {
+    /// Create self-issued OP metadata for a wallet that signs with the
+    /// given algorithm and identifies the holder with the given DID methods
+    /// (for example, `did:key`).
+    #[must_use]
+    pub fn new(algorithm: Algorithm, subject_syntax_types: &[&str]) -> Self {
+        Self {
+            authorization_endpoint: SIOP_AUTHORIZATION_ENDPOINT.into(),
+            response_types_supported: vec!["id_token".into()],
+            scopes_supported: vec!["openid".into()],
+            subject_types_supported: vec!["pairwise".into()],
+            id_token_signing_alg_values_supported: vec![algorithm.clone()],
+            request_object_signing_alg_values_supported: vec![algorithm],
+            subject_syntax_types_supported: subject_syntax_types
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            id_token_types_supported: vec!["subject_signed_id_token".into()],
+        }
+    }
+}
+
+/// A self-issued ID token request (SIOPv2 section 9) from a relying party.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IdTokenRequest {
+    /// The response type. Must be `id_token`.
+    pub response_type: String,
+
+    /// The relying party's client ID.
+    pub client_id: String,
+
+    /// The URI to redirect the response to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+
+    /// The URI to post the response to when the response mode is
+    /// `direct_post`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_uri: Option<String>,
+
+    /// The response mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_mode: Option<String>,
+
+    /// The requested scope. Must include `openid`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+
+    /// A nonce to bind the ID token to the request.
+    pub nonce: String,
+
+    /// Relying party state to be returned with the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+
+    /// The relying party's client metadata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_metadata: Option<Value>,
+
+    /// The ID token types the relying party accepts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token_type: Option<String>,
+}
+
+/// The claims of a self-issued ID token (SIOPv2 section 11).
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct IdTokenClaims {
+    /// The issuer. For a self-issued ID token this is the holder's DID.
+    pub iss: String,
+
+    /// The subject. The holder's DID.
+    pub sub: String,
+
+    /// The audience. The relying party's client ID.
+    pub aud: String,
+
+    /// The nonce from the request.
+    pub nonce: String,
+
+    /// The time the ID token was issued (seconds since the Unix epoch).
+    pub iat: i64,
+
+    /// The time the ID token expires (seconds since the Unix epoch).
+    pub exp: i64,
+}
+
+/// The response to a self-issued ID token request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IdTokenResponse {
+    /// The signed ID token.
+    pub id_token: String,
+
+    /// Relying party state from the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+impl IdTokenResponse {
+    /// Encode the response as form parameters.
+    #[must_use]
+    pub fn form_encode(&self) -> HashMap<String, String> {
+        let mut form = HashMap::from([("id_token".to_string(), self.id_token.clone())]);
+        if let Some(state) = &self.state {
+            form.insert("state".into(), state.clone());
+        }
+        form
+    }
+}
+
+/// A flow used to respond to a self-issued ID token request.
+///
+/// Flows can be serialized so an in-progress authentication can be persisted
+/// and resumed.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct IdTokenFlow {
+    /// Perhaps useful to the wallet for tracking a particular flow instance.
+    id: String,
+    context: WalletContext,
+    request: IdTokenRequest,
+}
+
+/// The request's nonce and state are redacted.
+impl Debug for IdTokenFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdTokenFlow")
+            .field("id", &self.id)
+            .field("context", &self.context)
+            .field("request", &redact::redacted(&self.request))
+            .finish()
+    }
+}
+
+impl IdTokenFlow {
+    /// Create a new flow from an ID token request.
+    ///
+    /// # Errors
+    /// Will return an error if the request is not for an `id_token` response
+    /// with the `openid` scope, or has no valid URI to return the response to
+    /// (see [`ResponseEndpoint`]).
+    pub fn new(request: IdTokenRequest) -> anyhow::Result<Self> {
+        if request.response_type != "id_token" {
+            bail!("unsupported response type: {}", request.response_type);
+        }
+        if !request.scope.as_deref().unwrap_or_default().split(' ').any(|s| s == "openid") {
+            bail!("request scope must include openid");
+        }
+        if ResponseEndpoint::from_request(&serde_json::to_value(&request)?)?.is_none() {
+            bail!("request has no redirect or response URI");
+        }
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            context: WalletContext::default(),
+            request,
+        })
+    }
+
+    /// A view of the flow state suitable for logging, with the request's nonce
+    /// and state redacted.
+    #[must_use]
+    pub fn redacted(&self) -> Value {
+        redact::redacted(self)
+    }
+
+    /// Get the ID of the flow.
+    #[must_use]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    /// Get the profile (and tenant) the flow is running on behalf of.
+    #[must_use]
+    pub const fn context(&self) -> &WalletContext {
+        &self.context
+    }
+
+    /// Set the profile (and tenant) the flow is running on behalf of.
+    #[must_use]
+    pub fn with_context(mut self, context: WalletContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Get the ID token request.
+    #[must_use]
+    pub const fn request(&self) -> &IdTokenRequest {
+        &self.request
+    }
+
+    /// Construct the claims for a self-issued ID token for the holder
+    /// identified by the key ID (DID URL).
+    #[must_use]
+    pub fn claims(&self, key_identifier: &str) -> IdTokenClaims {
+        let holder_did = key_identifier.split('#').next().unwrap_or_default();
+        let iat = chrono::Utc::now().timestamp();
+        IdTokenClaims {
+            iss: holder_did.into(),
+            sub: holder_did.into(),
+            aud: self.request.client_id.clone(),
+            nonce: self.request.nonce.clone(),
+            iat,
+            exp: iat + ID_TOKEN_LIFETIME,
+        }
+    }
+
+    /// Wrap the holder's signer so the consent gate is consulted before the
+    /// ID token is signed.
+    pub fn signer<'a, S: Signer, G: ConsentGate>(
+        &self, signer: &'a S, gate: &'a G,
+    ) -> GatedSigner<'a, S, G> {
+        let operation = SigningOperation::IdToken {
+            client_id: self.request.client_id.clone(),
+        };
+        GatedSigner::new(signer, gate, operation)
+    }
+
+    /// Create the ID token response and the URI to send it to from the
+    /// current flow state and the signed ID token.
+    #[must_use]
+    pub fn create_response(&self, id_token: &str) -> (IdTokenResponse, Option<String>) {
+        let response = IdTokenResponse {
+            id_token: id_token.into(),
+            state: self.request.state.clone(),
+        };
+        let endpoint = serde_json::to_value(&self.request)
+            .ok()
+            .and_then(|request| ResponseEndpoint::from_request(&request).ok().flatten());
+        (response, endpoint.map(|endpoint| endpoint.uri().to_string()))
+    }
+}
@@ -4,6 +4,10 @@
 //! into the wallet such as signing, state management and callbacks.
 //!
 //! See individual trait documentation for specific details.
+//!
+//! When compiled for `wasm32`, provider futures are not required to be `Send`
+//! so that providers can be implemented using browser APIs (`fetch`,
+//! `IndexedDB`, etc.) whose futures are single-threaded.
 
 use std::future::Future;
 
@@ -17,12 +21,57 @@ pub use credibil_vc::issuer::{
 };
 pub use credibil_vc::provider::{Result, StateStore};
 pub use credibil_vc::verifier::Constraints;
+#[cfg(feature = "presentation")]
 use credibil_vc::verifier::{RequestObjectResponse, ResponseRequest, ResponseResponse};
 
-use crate::credential::{Credential, ImageData};
+use crate::consent::{Consent, SigningOperation};
+use crate::context::WalletContext;
+#[cfg(feature = "issuance")]
+use crate::credential::ImageData;
+use crate::credential::{Credential, CredentialMetadata};
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::outbox::SealedOutboxItem;
+#[cfg(feature = "presentation")]
+use crate::presentation::reader::ReaderIdentity;
+#[cfg(feature = "presentation")]
+use crate::presentation::siop::IdTokenResponse;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::push::PushNotification;
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+use crate::registry::FlowRecord;
+use crate::reissue::ArchivedCredential;
+
+/// A marker for types that must be `Send` on native targets but not on
+/// `wasm32`, where futures are single-threaded.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// A marker for types that must be `Send` on native targets but not on
+/// `wasm32`, where futures are single-threaded.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// A marker for types that must be `Sync` on native targets but not on
+/// `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSync: Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Sync> MaybeSync for T {}
+
+/// A marker for types that must be `Sync` on native targets but not on
+/// `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSync for T {}
 
 /// A trait that combines all the provider traits required to be implemented
 /// by holder clients.
+#[cfg(all(feature = "issuance", feature = "presentation"))]
 #[allow(clippy::module_name_repetitions)]
 pub trait HolderProvider:
     Issuer + Verifier + CredentialStorer + StateStore + Signer + DidResolver + Clone
@@ -34,45 +83,46 @@ pub trait HolderProvider:
 ///
 /// While the specification is oriented towards HTTP, the trait allows the
 /// wallet (and issuance services) to be transport layer agnostic.
+#[cfg(feature = "issuance")]
 #[allow(clippy::module_name_repetitions)]
 pub trait Issuer {
     /// Get issuer metadata.
     fn metadata(
         &self, req: MetadataRequest,
-    ) -> impl Future<Output = anyhow::Result<MetadataResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<MetadataResponse>> + MaybeSend;
 
     /// Get OAuth authorization configuration.
     fn oauth_server(
         &self, req: OAuthServerRequest,
-    ) -> impl Future<Output = anyhow::Result<OAuthServerResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<OAuthServerResponse>> + MaybeSend;
 
     /// Get an authorization code.
     fn authorization(
         &self, req: AuthorizationRequest,
-    ) -> impl Future<Output = anyhow::Result<AuthorizationResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<AuthorizationResponse>> + MaybeSend;
 
     /// Get an access token.
     fn token(
         &self, req: TokenRequest,
-    ) -> impl Future<Output = anyhow::Result<TokenResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<TokenResponse>> + MaybeSend;
 
     /// Get a credential.
     fn credential(
         &self, req: CredentialRequest,
-    ) -> impl Future<Output = anyhow::Result<CredentialResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<CredentialResponse>> + MaybeSend;
 
     /// Get a deferred credential.
     fn deferred(
         &self, req: DeferredCredentialRequest,
-    ) -> impl Future<Output = anyhow::Result<DeferredCredentialResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<DeferredCredentialResponse>> + MaybeSend;
 
     /// Get a base64 encoded form of the credential logo.
-    fn image(self, image_url: &str) -> impl Future<Output = anyhow::Result<ImageData>> + Send;
+    fn image(self, image_url: &str) -> impl Future<Output = anyhow::Result<ImageData>> + MaybeSend;
 
     /// Notify the issuer of issuance progress.
     fn notification(
         &self, req: NotificationRequest,
-    ) -> impl Future<Output = anyhow::Result<NotificationResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<NotificationResponse>> + MaybeSend;
 }
 
 /// Allows the wallet to interact with a verifier's services that are compliant
@@ -80,40 +130,306 @@ pub trait Issuer {
 ///
 /// While the specification is oriented towards HTTP, the trait
 /// allows the wallet (and verifier's services) to be transport layer agnostic.
+#[cfg(feature = "presentation")]
 pub trait Verifier {
     /// Get a request object. If an error is returned, the wallet will cancel
     /// the presentation flow.
     fn request_object(
         &self, req: &str,
-    ) -> impl Future<Output = anyhow::Result<RequestObjectResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<RequestObjectResponse>> + MaybeSend;
 
     /// Send the presentation to the verifier.
     fn present(
         &self, uri: Option<&str>, presentation: &ResponseRequest,
-    ) -> impl Future<Output = anyhow::Result<ResponseResponse>> + Send;
+    ) -> impl Future<Output = anyhow::Result<ResponseResponse>> + MaybeSend;
+
+    /// Send a self-issued ID token to the relying party.
+    ///
+    /// The default implementation returns an error, for wallets that do not
+    /// act as a self-issued OP.
+    fn self_issued(
+        &self, uri: Option<&str>, response: &IdTokenResponse,
+    ) -> impl Future<Output = anyhow::Result<ResponseResponse>> + MaybeSend {
+        let _ = (uri, response);
+        async { anyhow::bail!("self-issued ID tokens are not supported") }
+    }
 }
 
 /// `CredentialStorer` is used by wallet implementations to provide persistent
 /// storage of Verifiable Credentials.
-pub trait CredentialStorer: Send + Sync {
+pub trait CredentialStorer: MaybeSend + MaybeSync {
     // TODO: should Credential param be owned?
 
     /// Save a `Credential` to the store. Overwrite any existing credential with
     /// the same ID. Create a new credential if one with the same ID does
     /// not exist.
-    fn save(&self, credential: &Credential) -> impl Future<Output = anyhow::Result<()>> + Send;
+    fn save(&self, credential: &Credential)
+    -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
 
     /// Retrieve a `Credential` from the store with the given ID. Return None if
     /// no credential with the ID exists.
-    fn load(&self, id: &str) -> impl Future<Output = anyhow::Result<Option<Credential>>> + Send;
+    fn load(
+        &self, id: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<Credential>>> + MaybeSend;
 
     /// Find the credentials that match the the provided filter. If `filter` is
     /// None, return all credentials in the store.
     fn find(
         &self, filter: Option<Constraints>,
-    ) -> impl Future<Output = anyhow::Result<Vec<Credential>>> + Send;
+    ) -> impl Future<Output = anyhow::Result<Vec<Credential>>> + MaybeSend;
+
+    /// Find the credentials that match the provided filter, returning only
+    /// their metadata. If `filter` is None, return the metadata of all
+    /// credentials in the store.
+    ///
+    /// Stores holding large credentials should implement this without
+    /// loading the issued credentials. The default implementation uses
+    /// [`Self::find`].
+    fn list(
+        &self, filter: Option<Constraints>,
+    ) -> impl Future<Output = anyhow::Result<Vec<CredentialMetadata>>> + MaybeSend {
+        async move {
+            let credentials = self.find(filter).await?;
+            Ok(credentials.into_iter().map(CredentialMetadata::from).collect())
+        }
+    }
 
     /// Remove the credential with the given ID from the store. Return an error
     /// if the credential does not exist.
-    fn remove(&self, id: &str) -> impl Future<Output = anyhow::Result<()>> + Send;
+    fn remove(&self, id: &str) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+
+    /// Replace the credential with the given ID with a credential superseding
+    /// it (see [`crate::reissue`]).
+    ///
+    /// Stores supporting transactions should implement this atomically. The
+    /// default implementation removes the replaced credential and then saves
+    /// the credential, saving the replaced credential again if that fails.
+    fn replace(
+        &self, id: &str, credential: &Credential,
+    ) -> impl Future<Output = anyhow::Result<()>> + MaybeSend {
+        async move {
+            if credential.id == id {
+                return self.save(credential).await;
+            }
+            let Some(replaced) = self.load(id).await? else {
+                anyhow::bail!("credential {id} not found");
+            };
+            self.remove(id).await?;
+            if let Err(e) = self.save(credential).await {
+                let _ = self.save(&replaced).await;
+                return Err(e);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// `CredentialArchive` is used by wallet implementations to keep the records
+/// of credentials replaced by credentials superseding them. See
+/// [`crate::reissue::replace`].
+pub trait CredentialArchive: MaybeSend + MaybeSync {
+    /// Add a replaced credential's record to the archive.
+    fn archive(
+        &self, record: &ArchivedCredential,
+    ) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+
+    /// Remove the record of the credential with the given ID from the
+    /// archive, returning it. Return None if the archive has no record of the
+    /// credential.
+    fn unarchive(
+        &self, id: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<ArchivedCredential>>> + MaybeSend;
+}
+
+/// `CredentialObserver` is used by wallet implementations to be notified of
+/// changes to the credential store.
+///
+/// For example, an observer can sync wallet contents to a backup service or
+/// update a UI cache incrementally. See [`crate::observe::Observed`].
+///
+/// Each hook is given the metadata of the record that changed, and is called
+/// after the change has been made. Hooks cannot fail the change: an observer
+/// that cannot act on a change should record it to retry later. Hooks default
+/// to doing nothing so observers only implement those they need.
+pub trait CredentialObserver: MaybeSend + MaybeSync {
+    /// A credential was saved to the store for the first time.
+    fn on_added(&self, record: &CredentialMetadata) -> impl Future<Output = ()> + MaybeSend {
+        let _ = record;
+        async {}
+    }
+
+    /// A credential already in the store was saved again, replacing the
+    /// existing record.
+    fn on_updated(&self, record: &CredentialMetadata) -> impl Future<Output = ()> + MaybeSend {
+        let _ = record;
+        async {}
+    }
+
+    /// A credential was removed from the store. The record is the credential
+    /// as it was before removal.
+    fn on_deleted(&self, record: &CredentialMetadata) -> impl Future<Output = ()> + MaybeSend {
+        let _ = record;
+        async {}
+    }
+}
+
+/// `Encryptor` is used by wallet implementations to protect flow snapshots
+/// (see [`crate::snapshot`]) and other secrets at rest.
+///
+/// Implementations MUST use authenticated encryption (for example, AES-GCM or
+/// ChaCha20-Poly1305) so that tampering is detected on decryption, and should
+/// keep the key in a platform key store.
+pub trait Encryptor: MaybeSend + MaybeSync {
+    /// Encrypt and authenticate the plaintext.
+    fn encrypt(
+        &self, plaintext: &[u8],
+    ) -> impl Future<Output = anyhow::Result<Vec<u8>>> + MaybeSend;
+
+    /// Authenticate and decrypt the ciphertext. Return an error if the
+    /// ciphertext has been modified or was not produced by this encryptor.
+    fn decrypt(
+        &self, ciphertext: &[u8],
+    ) -> impl Future<Output = anyhow::Result<Vec<u8>>> + MaybeSend;
+}
+
+/// `FlowStore` is used by wallet implementations to persist in-progress flows
+/// so they can be listed and resumed by ID, for example after the wallet has
+/// been restarted. See [`crate::registry::FlowRegistry`].
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub trait FlowStore: MaybeSend + MaybeSync {
+    /// Save a flow record to the store, overwriting any existing record with
+    /// the same ID.
+    fn put(&self, record: &FlowRecord) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+
+    /// Retrieve the flow record with the given ID. Return None if no record
+    /// with the ID exists.
+    fn get(&self, id: &str)
+    -> impl Future<Output = anyhow::Result<Option<FlowRecord>>> + MaybeSend;
+
+    /// List all flow records in the store, including expired records.
+    fn list(&self) -> impl Future<Output = anyhow::Result<Vec<FlowRecord>>> + MaybeSend;
+
+    /// Remove the flow record with the given ID. Removing a record that does
+    /// not exist is not an error.
+    fn remove(&self, id: &str) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+}
+
+/// `StatusListResolver` is used by wallet implementations to retrieve the
+/// status list credentials issuers publish.
+///
+/// The status of many held credentials can then be checked with one retrieval
+/// per list. See [`crate::status::check_statuses`].
+#[cfg(feature = "status")]
+pub trait StatusListResolver: MaybeSend + MaybeSync {
+    /// Retrieve the status list credential published at the URL, as issued
+    /// (for example, a JWT).
+    fn status_list(&self, url: &str) -> impl Future<Output = anyhow::Result<String>> + MaybeSend;
+}
+
+/// `DidConfigurationResolver` is used by wallet implementations to retrieve
+/// the DID configuration an origin publishes.
+///
+/// The wallet uses it to confirm a verifier's DID controls the origin it is
+/// reached at. See [`crate::linkage::verify_domain_linkage`].
+pub trait DidConfigurationResolver: MaybeSend + MaybeSync {
+    /// Retrieve the DID configuration (JSON) published at the URL.
+    fn did_configuration(
+        &self, url: &str,
+    ) -> impl Future<Output = anyhow::Result<String>> + MaybeSend;
+}
+
+/// `ReaderTrust` is used by wallet implementations to authenticate verifiers
+/// (mdoc readers) that sign their requests with an X.509 certificate chain.
+/// See [`crate::presentation::reader`].
+///
+/// Certificate parsing and path validation are left to the platform's X.509
+/// implementation, as is the set of trust anchors (for example, an IACA list
+/// or a trust list of reader authentication CAs).
+#[cfg(feature = "presentation")]
+pub trait ReaderTrust: MaybeSend + MaybeSync {
+    /// Validate the certificate chain (DER-encoded, leaf first) against the
+    /// wallet's trust anchors and verify the signature over `signed` using the
+    /// leaf certificate's key and the JOSE (or COSE) algorithm, returning the
+    /// identity in the leaf certificate.
+    fn verify_reader(
+        &self, chain: &[Vec<u8>], alg: &str, signed: &[u8], signature: &[u8],
+    ) -> impl Future<Output = anyhow::Result<ReaderIdentity>> + MaybeSend;
+}
+
+/// `OutboxStore` is used by wallet implementations to persist presentation
+/// responses and notifications that could not be sent while the device was
+/// offline. See [`crate::outbox::Outbox`].
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub trait OutboxStore: MaybeSend + MaybeSync {
+    /// Save an item to the store, overwriting any existing item with the same
+    /// ID.
+    fn put(&self, item: &SealedOutboxItem) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+
+    /// List all items in the store, including expired items.
+    fn list(&self) -> impl Future<Output = anyhow::Result<Vec<SealedOutboxItem>>> + MaybeSend;
+
+    /// Remove the item with the given ID. Removing an item that does not
+    /// exist is not an error.
+    fn remove(&self, id: &str) -> impl Future<Output = anyhow::Result<()>> + MaybeSend;
+}
+
+/// `NonceCache` is used by wallet implementations to remember the `c_nonce`
+/// values used in proofs of possession.
+///
+/// A nonce is never used in more than one proof (for example, when an
+/// application retries a credential request after a timeout). See
+/// [`crate::issuance::IssuanceFlow::build_fresh_proof`].
+///
+/// The cache only needs to hold nonces for as long as issuers accept them.
+#[cfg(feature = "issuance")]
+pub trait NonceCache: MaybeSend + MaybeSync {
+    /// Record that the nonce has been used in a proof for the credential
+    /// issuer. Return `false` if it had already been recorded. Implementations
+    /// must check and record the nonce atomically.
+    fn use_nonce(
+        &self, credential_issuer: &str, nonce: &str,
+    ) -> impl Future<Output = anyhow::Result<bool>> + MaybeSend;
+}
+
+/// `PushProvider` is used by wallet implementations that receive push
+/// notifications from issuers, to decode the payload of a notification. See
+/// [`crate::push`].
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub trait PushProvider: MaybeSend + MaybeSync {
+    /// Decode (and, where necessary, decrypt) the payload of a push
+    /// notification. Implementations should return an error if the
+    /// notification cannot be authenticated as coming from a trusted issuer.
+    fn decode_push(
+        &self, payload: &[u8],
+    ) -> impl Future<Output = anyhow::Result<PushNotification>> + MaybeSend;
+}
+
+/// `ConsentGate` is used by wallet implementations to obtain the holder's
+/// approval immediately before any signing operation is performed on their
+/// behalf.
+///
+/// Implementations can use this to enforce biometric or PIN confirmation per
+/// signature. See [`crate::consent::GatedSigner`] for how the gate is applied.
+pub trait ConsentGate: MaybeSend + MaybeSync {
+    /// Ask the holder to approve the signing operation. Return
+    /// `Consent::Refused` to cancel the operation cleanly, or an error if
+    /// consent could not be obtained.
+    fn consent(
+        &self, operation: &SigningOperation,
+    ) -> impl Future<Output = anyhow::Result<Consent>> + MaybeSend;
+}
+
+/// `ContextScoped` is implemented by providers that can isolate storage, keys
+/// and other state per profile (and tenant).
+///
+/// The application scopes its provider to the context of the flow it is
+/// working with so that, for example, credentials saved during an issuance
+/// flow are only visible to the profile that received them.
+pub trait ContextScoped: Sized {
+    /// Return a provider scoped to the specified context.
+    #[must_use]
+    fn scoped(&self, context: &WalletContext) -> Self;
+
+    /// The context the provider is currently scoped to.
+    fn context(&self) -> WalletContext;
 }
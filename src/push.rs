@@ -0,0 +1,96 @@
+//! # Push Notifications
+//!
+//! Issuers can wake the wallet using the platform's push service (APNs, FCM,
+//! Web Push, etc.) rather than waiting for the holder to scan a QR code or for
+//! the wallet to poll. The application receives the push and hands its
+//! payload to [`HolderAgent::handle_push`], which decodes it using the
+//! `PushProvider` and translates it into the corresponding flow action:
+//!
+//! * a deferred credential being ready is retrieved immediately,
+//! * a re-issuance offer starts a new issuance flow, and
+//! * a revocation is reported so the wallet can confirm the credential's
+//!   status before acting on it.
+//!
+//! Push payloads are not otherwise authenticated, so `PushProvider`
+//! implementations should check a notification comes from a trusted issuer
+//! before returning it. Use [`parse`] to decode plain JSON payloads.
+//!
+//! [`HolderAgent::handle_push`]: crate::agent::HolderAgent::handle_push
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::credential::Credential;
+use crate::issuance::CredentialOffer;
+
+/// An issuer-initiated event delivered to the wallet by push.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PushNotification {
+    /// A deferred credential is ready to be retrieved.
+    DeferredReady {
+        /// The transaction ID the issuer returned when issuance was deferred.
+        transaction_id: String,
+    },
+
+    /// A credential held by the wallet has been revoked.
+    CredentialRevoked {
+        /// The ID of the revoked credential.
+        credential_id: String,
+    },
+
+    /// The issuer offers to re-issue a credential held by the wallet.
+    ReissuanceOffered {
+        /// The ID of the credential to be replaced, if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        credential_id: Option<String>,
+
+        /// The subject the credential is offered to.
+        subject_id: String,
+
+        /// The (pre-authorized) credential offer.
+        credential_offer: Box<CredentialOffer>,
+    },
+}
+
+/// The action taken by the agent for a push notification.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PushAction {
+    /// Deferred credentials were retrieved. They are saved to the wallet with
+    /// [`HolderAgent::save`](crate::agent::HolderAgent::save).
+    Retrieved {
+        /// The ID of the issuance flow.
+        id: String,
+
+        /// The credentials retrieved.
+        credentials: Vec<Credential>,
+    },
+
+    /// The issuer reports the credential as revoked. The wallet should check
+    /// its status (see [`crate::status`]) before acting on the report.
+    Revoked {
+        /// The ID of the credential.
+        credential_id: String,
+    },
+
+    /// An issuance flow was started for the re-issued credential and is
+    /// waiting on the holder's acceptance.
+    Offered {
+        /// The ID of the issuance flow.
+        id: String,
+
+        /// The ID of the credential to be replaced, if any. Use
+        /// [`Credential::diff`] to show the holder what changed.
+        replaces: Option<String>,
+    },
+}
+
+/// Parse a push notification's JSON payload.
+///
+/// # Errors
+/// Will return an error if the payload is not a supported notification.
+pub fn parse(payload: &[u8]) -> anyhow::Result<PushNotification> {
+    serde_json::from_slice(payload).context("parsing push notification")
+}
@@ -0,0 +1,118 @@
+//! # QR Codes
+//!
+//! Credential offers and presentation requests are usually handed to the
+//! wallet as QR codes. This module decodes a QR code from an image (PNG or
+//! JPEG) or a greyscale camera frame and passes its contents to the
+//! issuance and presentation parsers, so wallets without a platform QR
+//! library can rely on the SDK.
+
+use anyhow::{anyhow, bail};
+use image::GrayImage;
+
+#[cfg(feature = "issuance")]
+use crate::issuance::{OfferType, parse_offer};
+#[cfg(feature = "presentation")]
+use crate::presentation::{RequestObject, parse_request_object};
+
+/// The contents of a scanned QR code.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum Scanned {
+    /// A credential offer, passed by value or by reference.
+    #[cfg(feature = "issuance")]
+    Offer(OfferType),
+
+    /// A presentation request object passed by value.
+    #[cfg(feature = "presentation")]
+    Request(RequestObject),
+
+    /// A URI from which to retrieve a presentation request object.
+    #[cfg(feature = "presentation")]
+    RequestUri(String),
+}
+
+/// Decode the QR code in an encoded image (PNG or JPEG) and parse its
+/// contents.
+///
+/// # Errors
+/// Will return an error if the image cannot be decoded, contains no QR code,
+/// or the QR code contains neither a credential offer nor a presentation
+/// request.
+pub fn scan(image: &[u8]) -> anyhow::Result<Scanned> {
+    let image = image::load_from_memory(image)
+        .map_err(|e| anyhow!("failed to decode image: {e}"))?
+        .to_luma8();
+    parse(&decode(&image)?)
+}
+
+/// Decode the QR code in a greyscale camera frame (one byte per pixel, row by
+/// row) and parse its contents.
+///
+/// # Errors
+/// Will return an error if the frame is smaller than the given dimensions,
+/// contains no QR code, or the QR code contains neither a credential offer nor
+/// a presentation request.
+pub fn scan_frame(width: u32, height: u32, luma: &[u8]) -> anyhow::Result<Scanned> {
+    let Some(image) = GrayImage::from_raw(width, height, luma.to_vec()) else {
+        bail!("frame is smaller than {width}x{height}");
+    };
+    parse(&decode(&image)?)
+}
+
+// Decode the first QR code found in the image.
+fn decode(image: &GrayImage) -> anyhow::Result<String> {
+    let width = image.width() as usize;
+    let pixels = image.as_raw();
+    let mut prepared =
+        rqrr::PreparedImage::prepare_from_greyscale(width, image.height() as usize, |x, y| {
+            pixels[y * width + x]
+        });
+    let grids = prepared.detect_grids();
+    let Some(grid) = grids.first() else {
+        bail!("no QR code found");
+    };
+    let (_, content) = grid.decode().map_err(|e| anyhow!("failed to decode QR code: {e}"))?;
+    Ok(content)
+}
+
+// Pass the QR code contents to the offer or request parser.
+fn parse(content: &str) -> anyhow::Result<Scanned> {
+    #[cfg(feature = "issuance")]
+    if is_offer(content) {
+        return Ok(Scanned::Offer(parse_offer(content)?));
+    }
+
+    #[cfg(feature = "presentation")]
+    {
+        if let Some(request_object) = parse_request_object(content)? {
+            return Ok(Scanned::Request(request_object));
+        }
+
+        // The request URI is either a query parameter of an authorization
+        // request link or the (URL-encoded) contents of the QR code.
+        let query = content.split_once('?').map_or(content, |(_, query)| query);
+        let encoded =
+            query.split('&').find_map(|pair| pair.strip_prefix("request_uri=")).unwrap_or(content);
+        let request_uri = urlencoding::decode(encoded)?;
+        if request_uri.starts_with("https://") || request_uri.starts_with("http://") {
+            return Ok(Scanned::RequestUri(request_uri.into_owned()));
+        }
+    }
+
+    bail!("QR code contains neither a credential offer nor a presentation request")
+}
+
+// Whether the QR code contents are a credential offer link: the offer scheme,
+// or a query with a `credential_offer` or `credential_offer_uri` parameter.
+#[cfg(feature = "issuance")]
+fn is_offer(content: &str) -> bool {
+    if content.starts_with("openid-credential-offer:") {
+        return true;
+    }
+    let query = content.split_once('?').map_or(content, |(_, query)| query);
+    query.split('&').any(|pair| {
+        let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+        key == "credential_offer" || key == "credential_offer_uri"
+    })
+}
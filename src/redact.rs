@@ -0,0 +1,190 @@
+//! # Redaction
+//!
+//! Flow state, tokens and credentials hold secrets (access tokens, codes,
+//! nonces) and personal data (claim values). Wallets commonly log these types
+//! when tracing is enabled, so the SDK redacts them before they are printed.
+//!
+//! `Debug` output for flows and credentials is redacted. For structured
+//! logging, flows and credentials provide a `redacted()` view: a JSON value
+//! with secrets replaced by [`REDACTED`] and claim values removed (claim names
+//! are kept to help diagnose issues). Use [`redacted`] for any other
+//! serializable value, such as a token request or response.
+//!
+//! For snapshot testing, flows also provide a [`StableView`]: the redacted
+//! view with fields in a deterministic order and values that change from run
+//! to run (generated IDs and timestamps) replaced by [`VOLATILE`].
+
+use std::fmt::{self, Display};
+
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+use chrono::DateTime;
+use serde::Serialize;
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+use serde_json::Map;
+use serde_json::Value;
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+use uuid::Uuid;
+
+/// The value substituted for redacted fields.
+pub const REDACTED: &str = "[redacted]";
+
+/// The value substituted for volatile fields in a [`StableView`].
+pub const VOLATILE: &str = "[volatile]";
+
+// Fields holding (numeric) timestamps.
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+const TIMESTAMPS: [&str; 4] = ["iat", "exp", "nbf", "issued_at"];
+
+// Fields whose values are secrets or personal data.
+const SENSITIVE: [&str; 20] = [
+    "access_token",
+    "refresh_token",
+    "pre-authorized_code",
+    "tx_code",
+    "user_code",
+    "code",
+    "code_verifier",
+    "c_nonce",
+    "nonce",
+    "state",
+    "proof",
+    "proofs",
+    "credential",
+    "credentials",
+    "credentialSubject",
+    "issued",
+    "vp_token",
+    "id_token",
+    "presentation_submission",
+    "pin",
+];
+
+// Fields holding claims, where the claim names are kept but the values are
+// redacted.
+const CLAIMS: [&str; 1] = ["subject_claims"];
+
+/// Redact secrets and personal data from a JSON value.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                if SENSITIVE.contains(&key.as_str()) {
+                    *field = Value::String(REDACTED.into());
+                } else if CLAIMS.contains(&key.as_str()) {
+                    redact_values(field);
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Serialize a value and redact secrets and personal data from it. Values
+/// that cannot be serialized are returned as a message saying so.
+pub fn redacted(value: &impl Serialize) -> Value {
+    let mut value = serde_json::to_value(value)
+        .unwrap_or_else(|e| Value::String(format!("unserializable: {e}")));
+    redact(&mut value);
+    value
+}
+
+/// A redacted view of a flow that is stable across runs, for snapshot testing
+/// and logging.
+///
+/// Object fields are sorted by name, and generated IDs (including the flow
+/// ID) and timestamps are replaced by [`VOLATILE`]. Nonces are already redacted.
+///
+/// `Display` writes the view as pretty-printed JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct StableView(Value);
+
+impl StableView {
+    // Create a stable view from the redacted view of a flow.
+    #[cfg(any(feature = "issuance", feature = "presentation"))]
+    pub(crate) fn new(mut redacted: Value) -> Self {
+        if let Some(id) = redacted.get_mut("id") {
+            *id = Value::String(VOLATILE.into());
+        }
+        Self(stabilize(redacted))
+    }
+
+    /// The view as a JSON value.
+    #[must_use]
+    pub const fn value(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl Display for StableView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(&self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+// Sort object fields by name and replace timestamps with `VOLATILE`.
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+fn stabilize(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<(String, Value)> = fields.into_iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let fields = fields.into_iter().map(|(key, field)| {
+                let field = if TIMESTAMPS.contains(&key.as_str()) && field.is_number() {
+                    Value::String(VOLATILE.into())
+                } else {
+                    stabilize(field)
+                };
+                (key, field)
+            });
+            Value::Object(fields.collect::<Map<String, Value>>())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(stabilize).collect()),
+        Value::String(s) if DateTime::parse_from_rfc3339(&s).is_ok() || is_uuid(&s) => {
+            Value::String(VOLATILE.into())
+        }
+        value => value,
+    }
+}
+
+// Generated IDs (such as presentation submission IDs) are UUIDs, optionally
+// as URNs.
+#[cfg(any(feature = "issuance", feature = "presentation"))]
+fn is_uuid(value: &str) -> bool {
+    Uuid::parse_str(value.strip_prefix("urn:uuid:").unwrap_or(value)).is_ok()
+}
+
+// Write the redacted form of a value wrapped in the type name, for use in
+// `Debug` implementations.
+#[cfg(feature = "issuance")]
+pub(crate) fn fmt_redacted(
+    f: &mut fmt::Formatter<'_>, name: &str, value: &impl Serialize,
+) -> fmt::Result {
+    write!(f, "{name}({})", redacted(value))
+}
+
+// Replace every scalar in a value with `REDACTED`, keeping object keys.
+fn redact_values(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (_, field) in fields {
+                redact_values(field);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_values(item);
+            }
+        }
+        Value::Null => {}
+        _ => *value = Value::String(REDACTED.into()),
+    }
+}
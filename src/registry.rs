@@ -0,0 +1,120 @@
+//! # Flow Registry
+//!
+//! The `FlowRegistry` maps flow IDs to flows persisted using the `FlowStore`
+//! provider so wallets can list in-progress flows and resume them by ID.
+//!
+//! Each flow is registered with an expiry. Expired flows are evicted from the
+//! store whenever the registry is queried, so stale offers and requests (whose
+//! pre-authorized codes, tokens or nonces will have expired at the issuer or
+//! verifier) are never resumed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::Flow;
+use crate::provider::FlowStore;
+
+/// A flow persisted by the [`FlowRegistry`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FlowRecord {
+    /// The flow ID.
+    pub id: String,
+
+    /// The flow state.
+    pub flow: Flow,
+
+    /// The time after which the flow can no longer be resumed.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FlowRecord {
+    /// Whether the flow has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+/// Registry of persisted flows backed by a [`FlowStore`] provider.
+#[derive(Clone, Debug)]
+pub struct FlowRegistry<S: FlowStore> {
+    store: S,
+}
+
+impl<S: FlowStore> FlowRegistry<S> {
+    /// Create a registry using the given store.
+    pub const fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Register (or update) a flow, returning its ID.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error.
+    pub async fn register(&self, flow: Flow, expires_at: DateTime<Utc>) -> anyhow::Result<String> {
+        let id = flow.id();
+        let record = FlowRecord {
+            id: id.clone(),
+            flow,
+            expires_at,
+        };
+        self.store.put(&record).await?;
+        Ok(id)
+    }
+
+    /// Resume the flow with the given ID. Returns `None` if the flow does not
+    /// exist or has expired, in which case it is evicted.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error.
+    pub async fn resume(&self, id: &str) -> anyhow::Result<Option<Flow>> {
+        let Some(record) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+        if record.is_expired() {
+            self.store.remove(id).await?;
+            return Ok(None);
+        }
+        Ok(Some(record.flow))
+    }
+
+    /// List flows that have not expired, evicting any that have.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error.
+    pub async fn list_active(&self) -> anyhow::Result<Vec<FlowRecord>> {
+        let mut active = vec![];
+        for record in self.store.list().await? {
+            if record.is_expired() {
+                self.store.remove(&record.id).await?;
+            } else {
+                active.push(record);
+            }
+        }
+        Ok(active)
+    }
+
+    /// Remove expired flows from the store, returning the number evicted.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error.
+    pub async fn evict_expired(&self) -> anyhow::Result<usize> {
+        let mut evicted = 0;
+        for record in self.store.list().await? {
+            if record.is_expired() {
+                self.store.remove(&record.id).await?;
+                evicted += 1;
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Remove the flow with the given ID, typically once it has completed or
+    /// been cancelled.
+    ///
+    /// # Errors
+    /// Will return an error if the store returns an error.
+    pub async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.store.remove(id).await
+    }
+}
@@ -0,0 +1,143 @@
+//! # Re-issuance
+//!
+//! Issuers renew credentials before they expire, and re-issue them when the
+//! claims they attest to change, by offering the holder a new credential of
+//! the same configuration. The new credential supersedes the one already in
+//! the wallet: the holder should see what changed (see
+//! [`Credential::diff`]), and the wallet should then hold only the new
+//! credential.
+//!
+//! A newly issued credential supersedes a stored one when they are of the
+//! same credential configuration (the same issuer, format and types) and are
+//! issued to the same subjects. [`superseded`] finds the stored credentials a
+//! batch of issued credentials supersede.
+//!
+//! [`replace`] then replaces a stored credential with the credential
+//! superseding it, archiving the old record to a [`CredentialArchive`] rather
+//! than discarding it. The replacement is made using
+//! [`CredentialStorer::replace`], which stores supporting transactions should
+//! implement atomically, and is undone (including the archiving) if it fails.
+//! [`crate::agent::HolderAgent::save_replacing`] does this for the
+//! credentials issued in a flow.
+
+use std::collections::BTreeSet;
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::credential::{Credential, CredentialDiff, CredentialMetadata, SubjectClaims};
+use crate::provider::{CredentialArchive, CredentialStorer};
+
+/// A credential record replaced by a credential superseding it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ArchivedCredential {
+    /// The replaced credential.
+    pub credential: Credential,
+
+    /// The ID of the credential that superseded it.
+    pub superseded_by: String,
+
+    /// When the credential was archived.
+    pub archived_at: DateTime<Utc>,
+}
+
+/// A stored credential superseded by a newly issued one.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Supersession {
+    /// The ID of the newly issued credential.
+    pub credential_id: String,
+
+    /// The stored credential it supersedes.
+    pub replaces: CredentialMetadata,
+}
+
+/// The result of replacing a stored credential.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Replaced {
+    /// The archived record of the replaced credential.
+    pub archived: ArchivedCredential,
+
+    /// What changed between the replaced credential and its replacement.
+    pub diff: CredentialDiff,
+}
+
+/// Whether a newly issued credential supersedes a stored credential.
+///
+/// It does if it is a different credential (with a different ID) with the
+/// same issuer, format, types and subjects, and the stored credential was not
+/// issued after it.
+#[must_use]
+pub fn supersedes(credential: &Credential, stored: &CredentialMetadata) -> bool {
+    credential.id != stored.id
+        && credential.issuer == stored.issuer
+        && credential.format == stored.format
+        && types(&credential.type_) == types(&stored.type_)
+        && subjects(&credential.subject_claims) == subjects(&stored.subject_claims)
+        && stored.issuance_date <= credential.issuance_date
+}
+
+// The set of credential types, ignoring order.
+fn types(types: &[String]) -> BTreeSet<&String> {
+    types.iter().collect()
+}
+
+// The set of subject IDs, ignoring order.
+fn subjects(claims: &[SubjectClaims]) -> BTreeSet<&Option<String>> {
+    claims.iter().map(|s| &s.id).collect()
+}
+
+/// The stored credentials superseded by newly issued credentials. Where an
+/// issued credential supersedes more than one stored credential, the most
+/// recently issued is the one replaced.
+#[must_use]
+pub fn superseded(credentials: &[Credential], stored: &[CredentialMetadata]) -> Vec<Supersession> {
+    let mut supersessions = vec![];
+    for credential in credentials {
+        let candidates = stored.iter().filter(|s| supersedes(credential, s));
+        if let Some(replaces) = candidates.max_by_key(|s| s.issuance_date) {
+            supersessions.push(Supersession {
+                credential_id: credential.id.clone(),
+                replaces: replaces.clone(),
+            });
+        }
+    }
+    supersessions
+}
+
+/// Replace a stored credential with a credential superseding it, archiving
+/// the stored record. If the replacement fails, the record is removed from
+/// the archive again.
+///
+/// # Errors
+/// Will return an error if the stored credential cannot be found, the
+/// credential does not supersede it, or the store or archive returns an
+/// error.
+pub async fn replace(
+    store: &impl CredentialStorer, archive: &impl CredentialArchive, stored_id: &str,
+    credential: &Credential,
+) -> anyhow::Result<Replaced> {
+    let Some(stored) = store.load(stored_id).await? else {
+        bail!("credential {stored_id} not found");
+    };
+    if !supersedes(credential, &CredentialMetadata::from(stored.clone())) {
+        bail!("credential {} does not supersede {stored_id}", credential.id);
+    }
+
+    let archived = ArchivedCredential {
+        credential: stored,
+        superseded_by: credential.id.clone(),
+        archived_at: Utc::now(),
+    };
+    archive.archive(&archived).await?;
+    if let Err(e) = store.replace(stored_id, credential).await {
+        // the stored record is unchanged, so the archived copy is not needed
+        let _ = archive.unarchive(stored_id).await;
+        return Err(e);
+    }
+
+    Ok(Replaced {
+        diff: archived.credential.diff(credential),
+        archived,
+    })
+}
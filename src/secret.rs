@@ -0,0 +1,89 @@
+//! # Secrets
+//!
+//! Flow state holds bearer secrets: PINs (transaction codes), pre-authorized
+//! codes, access tokens and nonces. [`Secret`] wraps a secret string so that
+//! its memory is zeroed when it is dropped and it is never printed by
+//! `Debug`, keeping secrets out of memory dumps, crash reports and logs.
+//!
+//! Secrets serialize as plain strings so flow state can still be persisted
+//! (see [`crate::snapshot`] for encrypting persisted flows).
+//!
+//! Secrets are compared in constant time so that validating a secret (for
+//! example, the `state` returned to a redirect URI) does not leak how much of
+//! it matched through timing. Use [`constant_time_eq`] when comparing secrets
+//! that are not held in a [`Secret`].
+//!
+//! Where a secret must be passed to a request type defined by `credibil-vc`,
+//! flows copy it at the last moment. Requests should be dropped as soon as
+//! they have been sent.
+
+use std::fmt::{self, Debug};
+
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// A secret string that is zeroed on drop and redacted from `Debug` output.
+#[derive(Clone, Default, Deserialize, Serialize, Eq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Create a new secret.
+    #[must_use]
+    pub const fn new(secret: String) -> Self {
+        Self(secret)
+    }
+
+    /// Expose the secret value.
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Compare the secret with a value in constant time.
+    #[must_use]
+    pub fn matches(&self, value: &str) -> bool {
+        constant_time_eq(self.0.as_bytes(), value.as_bytes())
+    }
+}
+
+impl PartialEq for Secret {
+    fn eq(&self, other: &Self) -> bool {
+        self.matches(&other.0)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.into())
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([redacted])")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Compare two byte strings in constant time.
+///
+/// The time taken depends only on the length of the inputs, not their
+/// content, so comparisons of secrets of a known length do not leak how many
+/// bytes matched.
+#[must_use]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
@@ -0,0 +1,65 @@
+//! # Snapshots
+//!
+//! Encrypted snapshots of flow state for wallets that may be terminated part
+//! way through a flow (mobile apps are routinely killed by the OS while in the
+//! background).
+//!
+//! Flow state includes access tokens, PINs and nonces, so it should not be
+//! persisted in the clear. [`snapshot`] serializes a flow and seals it using
+//! the wallet's [`Encryptor`] provider, and [`restore`] reverses the process,
+//! failing if the snapshot has been tampered with.
+//!
+//! Any serializable flow can be snapshotted: an `IssuanceFlow`, a
+//! `PresentationFlow`, or a flow managed by the `HolderAgent`.
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Encryptor;
+
+// Snapshot format version, checked on restore.
+const VERSION: u8 = 1;
+
+#[derive(Deserialize, Serialize)]
+struct Envelope<T> {
+    version: u8,
+    created_at: DateTime<Utc>,
+    flow: T,
+}
+
+/// Serialize and encrypt the flow.
+///
+/// # Errors
+/// Will return an error if the flow cannot be serialized or the encryptor
+/// returns an error.
+pub async fn snapshot<T: Serialize + Sync>(
+    flow: &T, encryptor: &impl Encryptor,
+) -> anyhow::Result<Vec<u8>> {
+    let envelope = Envelope {
+        version: VERSION,
+        created_at: Utc::now(),
+        flow,
+    };
+    let plaintext = serde_json::to_vec(&envelope)?;
+    encryptor.encrypt(&plaintext).await
+}
+
+/// Decrypt and deserialize a flow from a snapshot.
+///
+/// # Errors
+/// Will return an error if the snapshot cannot be decrypted (including if it
+/// has been modified), was created by an incompatible version of this crate,
+/// or does not contain a flow of the expected type.
+pub async fn restore<T: DeserializeOwned>(
+    snapshot: &[u8], encryptor: &impl Encryptor,
+) -> anyhow::Result<T> {
+    let plaintext = encryptor.decrypt(snapshot).await.context("decrypting snapshot")?;
+    let envelope: Envelope<serde_json::Value> =
+        serde_json::from_slice(&plaintext).context("deserializing snapshot")?;
+    if envelope.version != VERSION {
+        bail!("unsupported snapshot version {}", envelope.version);
+    }
+    serde_json::from_value(envelope.flow).context("deserializing flow")
+}
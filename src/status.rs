@@ -0,0 +1,226 @@
+//! # Credential Status
+//!
+//! Issuers can declare that a credential's status (such as revocation or
+//! suspension) is published in a status list. This module allows the wallet to
+//! check the current status of a held credential so it can warn the holder
+//! before they present a credential that is no longer valid.
+//!
+//! Retrieval of the status list is delegated to the `Status` provider trait.
+//! Use [`check_with_cancel`] to allow a slow check to be cancelled.
+//!
+//! To check many credentials at once use [`check_statuses`], which retrieves
+//! each status list the credentials refer to once, using the
+//! [`StatusListResolver`] provider trait, and reads every credential's status
+//! from it.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_vc::issuer::proof::Payload;
+pub use credibil_vc::issuer::{CredentialStatus, CredentialStatusType, StatusPurpose};
+pub use credibil_vc::verifier::status::Status;
+use credibil_vc::{Kind, Quota};
+use flate2::read::GzDecoder;
+use serde_json::Value;
+
+use crate::cancel::CancellationToken;
+use crate::credential::Credential;
+use crate::jwt_vc;
+use crate::provider::{DidResolver, StatusListResolver};
+
+/// Check the status of a held credential against each status list declared by
+/// the issuer.
+///
+/// Returns the status entries that are currently set for the credential. An
+/// empty result means the credential has not been revoked, suspended, etc. (or
+/// the issuer does not publish status information for it).
+///
+/// # Errors
+///
+/// Will return an error if the issued credential cannot be decoded and
+/// verified, or if the provider is unable to resolve a status list.
+pub async fn check(
+    credential: &Credential, provider: impl Status + DidResolver,
+) -> anyhow::Result<Vec<CredentialStatus>> {
+    check_with_cancel(credential, provider, &CancellationToken::new()).await
+}
+
+/// Check the status of a held credential as for [`check`], stopping if the
+/// token is cancelled.
+///
+/// Verifying the credential (including resolving the issuer's DID) and each
+/// status list fetch are aborted on cancellation.
+///
+/// # Errors
+///
+/// Will return an error if the issued credential cannot be decoded and
+/// verified, if the provider is unable to resolve a status list, or a
+/// [`crate::cancel::Cancelled`] error if the token is cancelled.
+pub async fn check_with_cancel(
+    credential: &Credential, provider: impl Status + DidResolver, cancel: &CancellationToken,
+) -> anyhow::Result<Vec<CredentialStatus>> {
+    let vc_kind = Kind::String(credential.issued.clone());
+    let verify = jwt_vc::verify(&vc_kind, provider.clone());
+    let Payload::Vc { vc, .. } = cancel.run(verify).await?? else {
+        bail!("expected a verifiable credential");
+    };
+
+    let entries = match vc.credential_status {
+        None => return Ok(vec![]),
+        Some(Quota::One(status)) => vec![status],
+        Some(Quota::Many(statuses)) => statuses,
+    };
+
+    let mut set = vec![];
+    for entry in entries {
+        if cancel.run(provider.status(&entry, &credential.id)).await?? {
+            set.push(entry);
+        }
+    }
+    Ok(set)
+}
+
+/// Check the status of each of a batch of held credentials as for [`check`].
+///
+/// The credentials are grouped by the status lists they refer to and each
+/// list is retrieved once, however many of the credentials refer to it.
+///
+/// Results are returned in the same order as the credentials. A credential
+/// that cannot be decoded and verified, or whose status list cannot be
+/// retrieved, verified or read, has an error result without affecting the
+/// results for the others.
+pub async fn check_statuses(
+    credentials: &[Credential], provider: impl StatusListResolver + DidResolver,
+) -> Vec<anyhow::Result<Vec<CredentialStatus>>> {
+    let mut entries = Vec::new();
+    for credential in credentials {
+        entries.push(list_entries(credential, provider.clone()).await);
+    }
+
+    // Retrieve each status list once. Failures are kept as messages so they
+    // can be reported for every credential referring to the list.
+    let mut lists: HashMap<String, Result<StatusList, String>> = HashMap::new();
+    for entry in entries.iter().flatten().flatten() {
+        if !lists.contains_key(&entry.list_url) {
+            let list =
+                status_list(&entry.list_url, provider.clone()).await.map_err(|e| format!("{e:#}"));
+            lists.insert(entry.list_url.clone(), list);
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|entries| {
+            let mut set = vec![];
+            for entry in entries? {
+                let list = match &lists[&entry.list_url] {
+                    Ok(list) => list,
+                    Err(e) => bail!("issue retrieving status list {}: {e}", entry.list_url),
+                };
+                if list.is_set(&entry)? {
+                    set.push(entry.status);
+                }
+            }
+            Ok(set)
+        })
+        .collect()
+}
+
+// A credential's entry in a bitstring status list.
+struct ListEntry {
+    status: CredentialStatus,
+    list_url: String,
+    index: usize,
+    size: usize,
+    purpose: Option<String>,
+}
+
+// A decoded bitstring status list.
+struct StatusList {
+    purpose: Option<String>,
+    bits: Vec<u8>,
+}
+
+impl StatusList {
+    // Whether any of the entry's bits are set. Index 0 is the most
+    // significant bit of the first byte.
+    fn is_set(&self, entry: &ListEntry) -> anyhow::Result<bool> {
+        if let (Some(list), Some(purpose)) = (&self.purpose, &entry.purpose) {
+            if list != purpose {
+                bail!("status list {} is for {list}, not {purpose}", entry.list_url);
+            }
+        }
+        let start = entry.index * entry.size;
+        let mut set = false;
+        for bit in start..start + entry.size {
+            let Some(byte) = self.bits.get(bit / 8) else {
+                bail!("status list index {} is out of range", entry.index);
+            };
+            set |= byte & (0x80 >> (bit % 8)) != 0;
+        }
+        Ok(set)
+    }
+}
+
+// Decode and verify the credential and get its status list entries.
+async fn list_entries(
+    credential: &Credential, provider: impl DidResolver,
+) -> anyhow::Result<Vec<ListEntry>> {
+    let vc_kind = Kind::String(credential.issued.clone());
+    let Payload::Vc { vc, .. } = jwt_vc::verify(&vc_kind, provider).await? else {
+        bail!("expected a verifiable credential");
+    };
+    let statuses = match vc.credential_status {
+        None => return Ok(vec![]),
+        Some(Quota::One(status)) => vec![status],
+        Some(Quota::Many(statuses)) => statuses,
+    };
+    statuses.into_iter().map(list_entry).collect()
+}
+
+// Read the status list details from a credential status entry.
+fn list_entry(status: CredentialStatus) -> anyhow::Result<ListEntry> {
+    let CredentialStatusType::Bitstring(bitstring) = &status.credential_status_type;
+    if bitstring.status_list_credential.is_empty() {
+        bail!("credential status entry has no status list credential");
+    }
+    Ok(ListEntry {
+        list_url: bitstring.status_list_credential.clone(),
+        index: bitstring.status_list_index,
+        size: bitstring.status_size.unwrap_or(1),
+        purpose: Some(bitstring.status_purpose.to_string()),
+        status,
+    })
+}
+
+// Retrieve, verify and decode a status list credential.
+async fn status_list(
+    url: &str, provider: impl StatusListResolver + DidResolver,
+) -> anyhow::Result<StatusList> {
+    let issued = provider.status_list(url).await?;
+    let vc_kind = Kind::String(issued);
+    let Payload::Vc { vc, .. } = jwt_vc::verify(&vc_kind, provider).await? else {
+        bail!("expected a status list credential");
+    };
+    let Quota::One(subject) = vc.credential_subject else {
+        bail!("status list credential should have a single subject");
+    };
+    let Some(encoded) = subject.claims.get("encodedList").and_then(Value::as_str) else {
+        bail!("status list credential has no encoded list");
+    };
+
+    // The list is GZIP-compressed and multibase encoded with the base64url
+    // alphabet (the `u` prefix is missing from older lists).
+    let encoded = encoded.strip_prefix('u').unwrap_or(encoded);
+    let compressed = Base64UrlUnpadded::decode_vec(encoded)
+        .map_err(|e| anyhow!("invalid encoded status list: {e}"))?;
+    let mut bits = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut bits)?;
+
+    Ok(StatusList {
+        purpose: subject.claims.get("statusPurpose").and_then(Value::as_str).map(String::from),
+        bits,
+    })
+}
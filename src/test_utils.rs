@@ -0,0 +1,21 @@
+//! # Test Utilities
+//!
+//! Re-exports the `credibil-vc` test utilities (hard-coded issuer, verifier
+//! and holder providers) and adds a [`mock`] wallet provider tailored for
+//! holder-side tests.
+
+#[cfg(all(feature = "issuance", feature = "presentation"))]
+pub mod mock;
+
+use chrono::{DateTime, TimeZone, Utc};
+pub use credibil_vc::test_utils::*;
+
+/// Midnight (UTC) on 1 January of the given year, for credential dates in
+/// test fixtures.
+///
+/// # Panics
+/// Will panic if the year is out of range.
+#[must_use]
+pub fn date(year: i32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single().expect("should be a valid date")
+}
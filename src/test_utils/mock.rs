@@ -0,0 +1,558 @@
+//! # Mock Provider
+//!
+//! An in-process mock issuer and verifier so wallets can run end-to-end flow
+//! tests without standing up `vcservice` or any other HTTP service.
+//!
+//! [`MockProvider`] implements [`HolderProvider`], routing issuer and verifier
+//! requests to the `credibil-vc` test services and storing credentials and
+//! state in memory. Individual endpoints can be overridden with a
+//! [`Responder`] to simulate server behaviour such as error responses, and the
+//! credential store can be seeded with credentials built from arbitrary claims.
+
+// Provider trait methods are async by contract even where the store is not.
+#![allow(clippy::unused_async_trait_impl)]
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use credibil_vc::test_utils::store::keystore::HolderKeystore;
+use credibil_vc::test_utils::store::{resolver, state};
+use credibil_vc::test_utils::{issuer, verifier};
+use credibil_vc::verifier::{CreateRequestRequest, DeviceFlow};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::credential::{Credential, DataModel, ImageData, SharingPolicy, VCDM_2_0_CONTEXT};
+use crate::error::{OAuthError, Offline, RetryLater};
+use crate::issuance::{
+    AuthorizationRequest, AuthorizationResponse, CredentialOffer, CredentialRequest,
+    CredentialResponse, CredentialSubject, DeferredCredentialRequest, DeferredCredentialResponse,
+    MetadataRequest, MetadataResponse, NotificationRequest, NotificationResponse,
+    OAuthServerRequest, OAuthServerResponse, OfferType, SendType, TokenRequest, TokenResponse,
+    VerifiableCredential,
+};
+use crate::outbox::SealedOutboxItem;
+use crate::presentation::proof::{self, Payload, W3cFormat};
+use crate::presentation::siop::IdTokenResponse;
+use crate::presentation::{
+    Constraints, Field, Filter, FilterValue, InputDescriptor, RequestObjectRequest,
+    RequestObjectResponse, ResponseRequest, ResponseResponse,
+};
+use crate::provider::{
+    Algorithm, CredentialStorer, DidConfigurationResolver, DidResolver, Document, HolderProvider,
+    Issuer, NonceCache, OutboxStore, Result, Signer, StateStore, Verifier,
+};
+use crate::{Kind, Quota};
+
+/// Issuer and verifier endpoints that can be overridden with a
+/// [`Responder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Issuer metadata.
+    Metadata,
+
+    /// Authorization server metadata.
+    OAuthServer,
+
+    /// Authorization.
+    Authorization,
+
+    /// Token.
+    Token,
+
+    /// Credential.
+    Credential,
+
+    /// Deferred credential.
+    Deferred,
+
+    /// Notification.
+    Notification,
+
+    /// Verifier request object.
+    RequestObject,
+
+    /// Verifier response (presentation).
+    Present,
+
+    /// Relying party response (self-issued ID token).
+    SelfIssued,
+
+    /// Well-known DID configuration. Not found unless overridden.
+    DidConfiguration,
+}
+
+/// An HTTP-like response returned by a [`Responder`].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    /// The HTTP status code, or `None` if the service could not be reached.
+    pub status: Option<u16>,
+
+    /// The JSON response body.
+    pub body: Value,
+
+    /// The `Retry-After` header value, if any.
+    pub retry_after: Option<String>,
+}
+
+impl MockResponse {
+    /// A successful (200) response.
+    #[must_use]
+    pub const fn ok(body: Value) -> Self {
+        Self {
+            status: Some(200),
+            body,
+            retry_after: None,
+        }
+    }
+
+    /// An error (400) response with an OAuth error body.
+    #[must_use]
+    pub fn error(code: &str, description: &str) -> Self {
+        Self {
+            status: Some(400),
+            body: serde_json::json!({"error": code, "error_description": description}),
+            retry_after: None,
+        }
+    }
+
+    /// A rate limiting (429) response. The provider returns a [`RetryLater`]
+    /// error.
+    #[must_use]
+    pub fn too_many_requests(retry_after: Option<&str>) -> Self {
+        Self {
+            status: Some(429),
+            body: Value::Null,
+            retry_after: retry_after.map(ToString::to_string),
+        }
+    }
+
+    /// Simulate a service that cannot be reached. The provider returns an
+    /// [`Offline`] error.
+    #[must_use]
+    pub const fn offline() -> Self {
+        Self {
+            status: None,
+            body: Value::Null,
+            retry_after: None,
+        }
+    }
+}
+
+/// Produces responses for an overridden endpoint.
+///
+/// Implemented for closures taking the request serialized as JSON.
+pub trait Responder: Send + Sync {
+    /// Respond to the request.
+    fn respond(&self, request: &Value) -> MockResponse;
+}
+
+impl<F: Fn(&Value) -> MockResponse + Send + Sync> Responder for F {
+    fn respond(&self, request: &Value) -> MockResponse {
+        self(request)
+    }
+}
+
+/// A wallet provider backed by an in-process mock issuer and verifier.
+///
+/// Clones share the same credential store, state and responders.
+#[derive(Clone)]
+pub struct MockProvider {
+    issuer: issuer::Provider,
+    verifier: verifier::Provider,
+    state: state::Store,
+    credentials: Arc<Mutex<HashMap<String, Credential>>>,
+    outbox: Arc<Mutex<HashMap<String, SealedOutboxItem>>>,
+    nonces: Arc<Mutex<HashSet<(String, String)>>>,
+    responders: Arc<Mutex<HashMap<Endpoint, Arc<dyn Responder>>>>,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockProvider {
+    /// Create a new provider with an empty credential store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            issuer: issuer::Provider::new(),
+            verifier: verifier::Provider::new(),
+            state: state::Store::new(),
+            credentials: Arc::new(Mutex::new(HashMap::new())),
+            outbox: Arc::new(Mutex::new(HashMap::new())),
+            nonces: Arc::new(Mutex::new(HashSet::new())),
+            responders: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Override the endpoint with a responder.
+    pub fn respond_with(&self, endpoint: Endpoint, responder: impl Responder + 'static) {
+        self.responders().insert(endpoint, Arc::new(responder));
+    }
+
+    /// Remove any override for the endpoint.
+    pub fn reset(&self, endpoint: Endpoint) {
+        self.responders().remove(&endpoint);
+    }
+
+    /// Create a pre-authorized credential offer from the mock issuer for
+    /// [`issuer::NORMAL_USER`], returning the offer and PIN (if required).
+    ///
+    /// # Errors
+    /// Will return an error if the issuer cannot create the offer.
+    pub async fn offer(
+        &self, credential_configuration_ids: &[&str], tx_code_required: bool,
+    ) -> anyhow::Result<(CredentialOffer, Option<String>)> {
+        let request = CreateOfferRequest {
+            credential_issuer: issuer::CREDENTIAL_ISSUER.into(),
+            credential_configuration_ids: credential_configuration_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            subject_id: Some(issuer::NORMAL_USER.into()),
+            grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+            tx_code_required,
+            send_type: SendType::ByVal,
+        };
+        let response = credibil_vc::issuer::create_offer(self.issuer.clone(), request).await?;
+        let OfferType::Object(offer) = response.offer_type else {
+            bail!("expected credential offer object");
+        };
+        Ok((offer, response.tx_code))
+    }
+
+    /// Create a presentation request from the mock verifier for a credential
+    /// of the given type, returning the request URI.
+    ///
+    /// # Errors
+    /// Will return an error if the verifier cannot create the request.
+    pub async fn presentation_request(&self, credential_type: &str) -> anyhow::Result<String> {
+        let request = CreateRequestRequest {
+            client_id: verifier::VERIFIER_ID.into(),
+            device_flow: DeviceFlow::CrossDevice,
+            purpose: format!("To verify {credential_type}"),
+            input_descriptors: vec![InputDescriptor {
+                id: credential_type.into(),
+                constraints: Constraints {
+                    fields: Some(vec![Field {
+                        path: vec!["$.type".into()],
+                        filter: Some(Filter {
+                            type_: "string".into(),
+                            value: FilterValue::Const(credential_type.into()),
+                        }),
+                        ..Field::default()
+                    }]),
+                    ..Constraints::default()
+                },
+                name: None,
+                purpose: None,
+                format: None,
+            }],
+            ..CreateRequestRequest::default()
+        };
+        let response =
+            credibil_vc::verifier::create_request(self.verifier.clone(), &request).await?;
+        let Some(uri) = response.request_uri else {
+            bail!("expected request URI");
+        };
+        Ok(uri)
+    }
+
+    /// Save a credential of the given type, with the given subject claims, to
+    /// the credential store. The credential is signed by the mock issuer.
+    ///
+    /// # Errors
+    /// Will return an error if the credential cannot be signed or saved.
+    pub async fn seed(
+        &self, credential_type: &str, claims: Map<String, Value>,
+    ) -> anyhow::Result<Credential> {
+        let issuance_date = Utc::now();
+        let vc = VerifiableCredential {
+            context: vec![Kind::String(VCDM_2_0_CONTEXT.into())],
+            type_: Quota::Many(vec!["VerifiableCredential".into(), credential_type.into()]),
+            issuer: Kind::String(issuer::CREDENTIAL_ISSUER.into()),
+            id: Some(format!("urn:uuid:{}", uuid::Uuid::new_v4())),
+            valid_from: Some(issuance_date),
+            credential_subject: Quota::One(CredentialSubject {
+                id: Some(HolderKeystore::verification_method()),
+                claims,
+            }),
+            ..VerifiableCredential::default()
+        };
+        let payload = Payload::Vc {
+            vc: vc.clone(),
+            issued_at: issuance_date.timestamp(),
+        };
+        let jwt = proof::create(W3cFormat::JwtVcJson, payload, &self.issuer).await?;
+
+        let Quota::One(subject) = vc.credential_subject else {
+            bail!("expected a single credential subject");
+        };
+        let credential = Credential {
+            id: vc.id.unwrap_or_default(),
+            issuer: issuer::CREDENTIAL_ISSUER.into(),
+            issuer_name: "Mock Issuer".into(),
+            issuer_display: Vec::new(),
+            type_: vec!["VerifiableCredential".into(), credential_type.into()],
+            format: "jwt_vc_json".into(),
+            data_model: DataModel::V2_0,
+            subject_claims: vec![subject.into()],
+            claim_definitions: None,
+            display: None,
+            issued: jwt,
+            issuance_date,
+            valid_from: vc.valid_from,
+            valid_until: None,
+            logo: None,
+            background: None,
+            key_id: None,
+            sharing_policy: SharingPolicy::default(),
+            integrity: None,
+        };
+        self.save(&credential).await?;
+        Ok(credential)
+    }
+
+    // Use the endpoint's responder, if one is set, otherwise call the mock
+    // service.
+    async fn call<Req, Res, F>(
+        &self, endpoint: Endpoint, request: &Req, service: F,
+    ) -> anyhow::Result<Res>
+    where
+        Req: Serialize + Sync,
+        Res: DeserializeOwned,
+        F: Future<Output = anyhow::Result<Res>> + Send,
+    {
+        let responder = self.responders().get(&endpoint).cloned();
+        let Some(responder) = responder else {
+            return service.await;
+        };
+        let response = responder.respond(&serde_json::to_value(request)?);
+        let Some(status) = response.status else {
+            return Err(Offline.into());
+        };
+        if let Some(retry) = RetryLater::from_response(status, response.retry_after.as_deref()) {
+            return Err(retry.into());
+        }
+        if status >= 400 {
+            let body = serde_json::to_vec(&response.body)?;
+            if let Some(err) = OAuthError::parse(&body) {
+                return Err(err.into());
+            }
+            bail!("{endpoint:?} returned status {status}");
+        }
+        Ok(serde_json::from_value(response.body)?)
+    }
+
+    fn responders(&self) -> std::sync::MutexGuard<'_, HashMap<Endpoint, Arc<dyn Responder>>> {
+        self.responders.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn credentials(&self) -> std::sync::MutexGuard<'_, HashMap<String, Credential>> {
+        self.credentials.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn outbox(&self) -> std::sync::MutexGuard<'_, HashMap<String, SealedOutboxItem>> {
+        self.outbox.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl HolderProvider for MockProvider {}
+
+impl Issuer for MockProvider {
+    async fn metadata(&self, req: MetadataRequest) -> anyhow::Result<MetadataResponse> {
+        let service =
+            async { Ok(credibil_vc::issuer::metadata(self.issuer.clone(), req.clone()).await?) };
+        self.call(Endpoint::Metadata, &req, service).await
+    }
+
+    async fn oauth_server(&self, req: OAuthServerRequest) -> anyhow::Result<OAuthServerResponse> {
+        let service = async {
+            Ok(credibil_vc::issuer::oauth_server(self.issuer.clone(), req.clone()).await?)
+        };
+        self.call(Endpoint::OAuthServer, &req, service).await
+    }
+
+    async fn authorization(
+        &self, req: AuthorizationRequest,
+    ) -> anyhow::Result<AuthorizationResponse> {
+        let service =
+            async { Ok(credibil_vc::issuer::authorize(self.issuer.clone(), req.clone()).await?) };
+        self.call(Endpoint::Authorization, &req, service).await
+    }
+
+    async fn token(&self, req: TokenRequest) -> anyhow::Result<TokenResponse> {
+        let service =
+            async { Ok(credibil_vc::issuer::token(self.issuer.clone(), req.clone()).await?) };
+        self.call(Endpoint::Token, &req, service).await
+    }
+
+    async fn credential(&self, req: CredentialRequest) -> anyhow::Result<CredentialResponse> {
+        let service = async {
+            Ok(Box::pin(credibil_vc::issuer::credential(self.issuer.clone(), req.clone())).await?)
+        };
+        Box::pin(self.call(Endpoint::Credential, &req, service)).await
+    }
+
+    async fn deferred(
+        &self, req: DeferredCredentialRequest,
+    ) -> anyhow::Result<DeferredCredentialResponse> {
+        let service = async {
+            Ok(Box::pin(credibil_vc::issuer::deferred(self.issuer.clone(), req.clone())).await?)
+        };
+        Box::pin(self.call(Endpoint::Deferred, &req, service)).await
+    }
+
+    async fn image(self, _image_url: &str) -> anyhow::Result<ImageData> {
+        Ok(ImageData::default())
+    }
+
+    async fn notification(&self, req: NotificationRequest) -> anyhow::Result<NotificationResponse> {
+        let service = async { Ok(NotificationResponse::default()) };
+        self.call(Endpoint::Notification, &req, service).await
+    }
+}
+
+impl Verifier for MockProvider {
+    async fn request_object(&self, req: &str) -> anyhow::Result<RequestObjectResponse> {
+        let service = async {
+            let parts = req.rsplitn(3, '/').collect::<Vec<&str>>();
+            if parts.len() < 3 {
+                bail!("invalid request string");
+            }
+            let request = RequestObjectRequest {
+                client_id: parts[2].into(),
+                id: parts[0].into(),
+            };
+            Ok(credibil_vc::verifier::request_object(self.verifier.clone(), &request).await?)
+        };
+        self.call(Endpoint::RequestObject, &req, service).await
+    }
+
+    async fn present(
+        &self, _uri: Option<&str>, req: &ResponseRequest,
+    ) -> anyhow::Result<ResponseResponse> {
+        let service =
+            async { Ok(credibil_vc::verifier::response(self.verifier.clone(), req).await?) };
+        self.call(Endpoint::Present, req, service).await
+    }
+
+    async fn self_issued(
+        &self, _uri: Option<&str>, res: &IdTokenResponse,
+    ) -> anyhow::Result<ResponseResponse> {
+        let service = async {
+            Ok(ResponseResponse {
+                redirect_uri: None,
+                response_code: None,
+            })
+        };
+        self.call(Endpoint::SelfIssued, res, service).await
+    }
+}
+
+impl CredentialStorer for MockProvider {
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        self.credentials().insert(credential.id.clone(), credential.clone());
+        Ok(())
+    }
+
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        Ok(self.credentials().get(id).cloned())
+    }
+
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        let credentials = self.credentials().values().cloned().collect::<Vec<_>>();
+        let Some(constraints) = filter else {
+            return Ok(credentials);
+        };
+        let mut matched = vec![];
+        for credential in credentials {
+            if constraints.satisfied(&credential)? {
+                matched.push(credential);
+            }
+        }
+        Ok(matched)
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.credentials().remove(id);
+        Ok(())
+    }
+}
+
+impl OutboxStore for MockProvider {
+    async fn put(&self, item: &SealedOutboxItem) -> anyhow::Result<()> {
+        self.outbox().insert(item.id.clone(), item.clone());
+        Ok(())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<SealedOutboxItem>> {
+        Ok(self.outbox().values().cloned().collect())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.outbox().remove(id);
+        Ok(())
+    }
+}
+
+impl NonceCache for MockProvider {
+    async fn use_nonce(&self, credential_issuer: &str, nonce: &str) -> anyhow::Result<bool> {
+        let mut nonces = self.nonces.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(nonces.insert((credential_issuer.into(), nonce.into())))
+    }
+}
+
+impl StateStore for MockProvider {
+    async fn put(&self, key: &str, state: impl Serialize, dt: DateTime<Utc>) -> Result<()> {
+        self.state.put(key, state, dt)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        self.state.get(key)
+    }
+
+    async fn purge(&self, key: &str) -> Result<()> {
+        self.state.purge(key)
+    }
+}
+
+impl DidResolver for MockProvider {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        resolver::resolve_did(url).await
+    }
+}
+
+impl DidConfigurationResolver for MockProvider {
+    async fn did_configuration(&self, url: &str) -> anyhow::Result<String> {
+        let service = async { Err(anyhow!("no DID configuration at {url}")) };
+        let config: Value = self.call(Endpoint::DidConfiguration, &url, service).await?;
+        Ok(config.to_string())
+    }
+}
+
+impl Signer for MockProvider {
+    async fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::try_sign(msg)
+    }
+
+    async fn verifying_key(&self) -> Result<Vec<u8>> {
+        HolderKeystore::public_key()
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        HolderKeystore::algorithm()
+    }
+
+    async fn verification_method(&self) -> Result<String> {
+        Ok(HolderKeystore::verification_method())
+    }
+}
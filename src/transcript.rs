@@ -0,0 +1,215 @@
+//! # Transcripts
+//!
+//! An opt-in diagnostic record of a flow: the requests sent to and responses
+//! received from issuers and verifiers, how long each exchange took, errors
+//! and the flow's state transitions.
+//!
+//! Flows do not record anything themselves. An application that wants a
+//! diagnostic bundle creates a [`Transcript`] for the flow and records each
+//! exchange as it makes it (typically in its provider implementations). When
+//! a flow fails against a third-party service, the transcript can be exported
+//! as JSON and attached to a support request.
+//!
+//! Tokens, codes, nonces, proofs, credentials and presentations are redacted
+//! (see [`crate::redact`]) as they are recorded so an exported transcript is
+//! safe to share. The JOSE headers of JWTs (algorithm, type and key) are kept
+//! so a transcript can be checked against a spec profile (see [`profile`]).
+
+pub mod profile;
+
+use std::collections::{BTreeMap, HashMap};
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use crate::redact::{REDACTED, redact};
+
+/// The kind of event recorded in a transcript.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EntryKind {
+    /// A request sent to an issuer or verifier.
+    Request,
+
+    /// A response received from an issuer or verifier.
+    Response,
+
+    /// An error returned by an issuer or verifier, or by the flow.
+    Error,
+
+    /// A change in the state of the flow.
+    Transition,
+}
+
+/// An event recorded in a transcript.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    /// When the event was recorded.
+    pub at: DateTime<Utc>,
+
+    /// The kind of event.
+    pub kind: EntryKind,
+
+    /// Identifies the exchange (for example, `token` or `credential`) or, for
+    /// transitions, the new state.
+    pub label: String,
+
+    /// For responses and errors, the milliseconds since the matching request
+    /// was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<i64>,
+
+    /// The redacted request or response, or the error message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+
+    /// The JOSE headers of JWTs in the request or response, keyed by the
+    /// JSON pointer of the JWT in the (unredacted) body.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, Value>,
+}
+
+/// A diagnostic record of a flow.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Transcript {
+    /// The ID of the flow the transcript records.
+    pub flow_id: String,
+
+    /// When the transcript was started.
+    pub started_at: DateTime<Utc>,
+
+    /// The events recorded, in order.
+    pub entries: Vec<Entry>,
+
+    // Requests awaiting a response, by label.
+    #[serde(skip)]
+    pending: HashMap<String, DateTime<Utc>>,
+}
+
+impl Transcript {
+    /// Start a transcript for the flow.
+    #[must_use]
+    pub fn new(flow_id: impl Into<String>) -> Self {
+        Self {
+            flow_id: flow_id.into(),
+            started_at: Utc::now(),
+            entries: vec![],
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record a request sent to an issuer or verifier.
+    pub fn request(&mut self, label: impl Into<String>, request: &impl Serialize) {
+        let label = label.into();
+        let at = Utc::now();
+        let (body, headers) = record(request);
+        self.pending.insert(label.clone(), at);
+        self.entries.push(Entry {
+            at,
+            kind: EntryKind::Request,
+            label,
+            elapsed_ms: None,
+            body: Some(body),
+            headers,
+        });
+    }
+
+    /// Record the response to a request.
+    pub fn response(&mut self, label: impl Into<String>, response: &impl Serialize) {
+        let (body, headers) = record(response);
+        self.complete(label.into(), EntryKind::Response, body, headers);
+    }
+
+    /// Record an error returned in place of a response, or raised by the
+    /// flow.
+    pub fn error(&mut self, label: impl Into<String>, error: &anyhow::Error) {
+        let message = Value::String(format!("{error:#}"));
+        self.complete(label.into(), EntryKind::Error, message, BTreeMap::new());
+    }
+
+    /// Record a change in the state of the flow.
+    pub fn transition(&mut self, state: impl Into<String>) {
+        self.entries.push(Entry {
+            at: Utc::now(),
+            kind: EntryKind::Transition,
+            label: state.into(),
+            elapsed_ms: None,
+            body: None,
+            headers: BTreeMap::new(),
+        });
+    }
+
+    /// Export the transcript as (pretty-printed) JSON.
+    ///
+    /// # Errors
+    /// Will return an error if the transcript cannot be serialized.
+    pub fn export(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    fn complete(
+        &mut self, label: String, kind: EntryKind, body: Value, headers: BTreeMap<String, Value>,
+    ) {
+        let at = Utc::now();
+        let elapsed_ms = self.pending.remove(&label).map(|sent| (at - sent).num_milliseconds());
+        self.entries.push(Entry {
+            at,
+            kind,
+            label,
+            elapsed_ms,
+            body: Some(body),
+            headers,
+        });
+    }
+}
+
+// Serialize a value, collecting the JOSE headers of any JWTs it contains
+// before redacting it. Values that cannot be serialized are recorded as a
+// message saying so.
+fn record(value: &impl Serialize) -> (Value, BTreeMap<String, Value>) {
+    let mut value = match serde_json::to_value(value) {
+        Ok(value) => value,
+        Err(e) => return (Value::String(format!("unserializable: {e}")), BTreeMap::new()),
+    };
+    let mut headers = BTreeMap::new();
+    jose_headers(&value, String::new(), &mut headers);
+    redact(&mut value);
+    (value, headers)
+}
+
+// Collect the headers of compact JWS/JWE strings, keyed by JSON pointer.
+fn jose_headers(value: &Value, pointer: String, headers: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                jose_headers(field, format!("{pointer}/{key}"), headers);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                jose_headers(item, format!("{pointer}/{i}"), headers);
+            }
+        }
+        Value::String(token) => {
+            if let Some(header) = jose_header(token) {
+                headers.insert(pointer, header);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The header of a compact JWS or JWE: a JSON object with an `alg`.
+fn jose_header(token: &str) -> Option<Value> {
+    let (header, rest) = token.split_once('.')?;
+    if !rest.contains('.') {
+        return None;
+    }
+    let decoded = Base64UrlUnpadded::decode_vec(header).ok()?;
+    let header: Value = serde_json::from_slice(&decoded).ok()?;
+    header.get("alg").is_some().then_some(header)
+}
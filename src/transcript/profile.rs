@@ -0,0 +1,283 @@
+//! # Profiles
+//!
+//! A [`Profile`] lists the requirements a spec profile (such as HAIP) places
+//! on the requests a holder sends: parameters that must be present or absent,
+//! their permitted values, and the JOSE headers (algorithms, types) of JWTs
+//! such as proofs and presentations. [`Profile::check`] compares each request
+//! recorded in a [`Transcript`] with the profile and reports the deviations,
+//! which helps pin down why a third-party issuer or verifier rejects a flow.
+//!
+//! Requirements are matched to requests by the label the request was recorded
+//! with, so the application must record requests using the labels the profile
+//! expects. Parameters that are redacted in the transcript can only be checked
+//! for presence.
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::transcript::{EntryKind, Transcript};
+
+/// A named set of requirements for the requests a holder sends.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    /// The name of the profile.
+    pub name: String,
+
+    /// The requirements of the profile.
+    pub requirements: Vec<Requirement>,
+}
+
+impl Profile {
+    /// Create an empty profile.
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            requirements: vec![],
+        }
+    }
+
+    /// Add a requirement to the profile.
+    #[must_use]
+    pub fn with_requirement(mut self, requirement: Requirement) -> Self {
+        self.requirements.push(requirement);
+        self
+    }
+
+    /// The requirements of the `OpenID4VC` High Assurance Interoperability
+    /// Profile (HAIP) that apply to the requests a holder sends.
+    ///
+    /// Requests are expected to be recorded with the labels `token` (token
+    /// requests), `credential` (credential requests) and `presentation`
+    /// (authorization responses sent to verifiers).
+    #[must_use]
+    pub fn haip() -> Self {
+        Self::new("HAIP")
+            .with_requirement(
+                Requirement::parameter("token", "/code_verifier")
+                    .when("/grant_type", "authorization_code"),
+            )
+            .with_requirement(
+                Requirement::header("credential", "/proof", "typ").one_of(["openid4vci-proof+jwt"]),
+            )
+            .with_requirement(Requirement::header("credential", "/proof", "alg").one_of(["ES256"]))
+            .with_requirement(
+                Requirement::header("credential", "/proofs", "typ")
+                    .one_of(["openid4vci-proof+jwt"]),
+            )
+            .with_requirement(Requirement::header("credential", "/proofs", "alg").one_of(["ES256"]))
+            .with_requirement(Requirement::parameter("presentation", "/vp_token"))
+            .with_requirement(Requirement::parameter("presentation", "/presentation_submission"))
+            .with_requirement(
+                Requirement::header("presentation", "/vp_token", "alg").one_of(["ES256"]),
+            )
+    }
+
+    /// Check the requests recorded in a transcript against the profile.
+    /// Returns the deviations found, in the order the requests were recorded.
+    #[must_use]
+    pub fn check(&self, transcript: &Transcript) -> Vec<Deviation> {
+        let mut deviations = vec![];
+
+        for (index, entry) in transcript.entries.iter().enumerate() {
+            if entry.kind != EntryKind::Request {
+                continue;
+            }
+            let body = entry.body.as_ref().unwrap_or(&Value::Null);
+
+            for requirement in self.requirements.iter().filter(|r| r.label == entry.label) {
+                if let Some((pointer, value)) = &requirement.when {
+                    if body.pointer(pointer) != Some(value) {
+                        continue;
+                    }
+                }
+
+                // only JWTs in the request are checked for header requirements
+                let found = match &requirement.target {
+                    Target::Parameter(pointer) => vec![body.pointer(pointer)],
+                    Target::Header { jwt, name } => entry
+                        .headers
+                        .iter()
+                        .filter(|(pointer, _)| within(pointer, jwt))
+                        .map(|(_, header)| header.get(name))
+                        .collect(),
+                };
+                for value in found {
+                    if !requirement.rule.allows(value) {
+                        deviations.push(Deviation {
+                            entry: index,
+                            label: entry.label.clone(),
+                            requirement: requirement.to_string(),
+                            found: value.cloned(),
+                        });
+                    }
+                }
+            }
+        }
+
+        deviations
+    }
+}
+
+/// A requirement for the requests recorded with a given label.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Requirement {
+    /// The label of the requests the requirement applies to.
+    pub label: String,
+
+    /// The parameter or header the requirement applies to.
+    pub target: Target,
+
+    /// The rule the parameter or header must satisfy.
+    pub rule: Rule,
+
+    /// When set, the requirement only applies to requests where the parameter
+    /// at the JSON pointer has the value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<(String, Value)>,
+}
+
+impl Requirement {
+    /// Require the request parameter at the JSON pointer to be present.
+    #[must_use]
+    pub fn parameter(label: impl Into<String>, pointer: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            target: Target::Parameter(pointer.into()),
+            rule: Rule::Present,
+            when: None,
+        }
+    }
+
+    /// Require JWTs at (or below) the JSON pointer to have the named JOSE
+    /// header.
+    #[must_use]
+    pub fn header(
+        label: impl Into<String>, jwt: impl Into<String>, name: impl Into<String>,
+    ) -> Self {
+        Self {
+            label: label.into(),
+            target: Target::Header {
+                jwt: jwt.into(),
+                name: name.into(),
+            },
+            rule: Rule::Present,
+            when: None,
+        }
+    }
+
+    /// Require the parameter or header to have one of the values.
+    #[must_use]
+    pub fn one_of<V: Into<Value>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        self.rule = Rule::OneOf(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Require the parameter or header to be absent.
+    #[must_use]
+    pub fn absent(mut self) -> Self {
+        self.rule = Rule::Absent;
+        self
+    }
+
+    /// Only apply the requirement to requests where the parameter at the JSON
+    /// pointer has the value.
+    #[must_use]
+    pub fn when(mut self, pointer: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.when = Some((pointer.into(), value.into()));
+        self
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.target {
+            Target::Parameter(pointer) => write!(f, "parameter `{pointer}`")?,
+            Target::Header { jwt, name } => write!(f, "header `{name}` of JWTs at `{jwt}`")?,
+        }
+        match &self.rule {
+            Rule::Present => write!(f, " must be present")?,
+            Rule::Absent => write!(f, " must be absent")?,
+            Rule::OneOf(values) => write!(f, " must be one of {}", Value::from(values.clone()))?,
+        }
+        if let Some((pointer, value)) = &self.when {
+            write!(f, " when `{pointer}` is {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The part of a request a requirement applies to.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// A request parameter, by JSON pointer.
+    Parameter(String),
+
+    /// A JOSE header of the JWTs at (or below) a JSON pointer.
+    Header {
+        /// The JSON pointer of the JWT(s).
+        jwt: String,
+
+        /// The name of the header.
+        name: String,
+    },
+}
+
+/// The rule a parameter or header must satisfy.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// The parameter or header must be present.
+    Present,
+
+    /// The parameter or header must be absent.
+    Absent,
+
+    /// The parameter or header must have one of the values.
+    OneOf(Vec<Value>),
+}
+
+impl Rule {
+    fn allows(&self, value: Option<&Value>) -> bool {
+        match self {
+            Self::Present => value.is_some(),
+            Self::Absent => value.is_none(),
+            Self::OneOf(values) => value.is_some_and(|value| values.contains(value)),
+        }
+    }
+}
+
+/// A request that does not satisfy a profile requirement.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Deviation {
+    /// The index of the request in the transcript's entries.
+    pub entry: usize,
+
+    /// The label of the request.
+    pub label: String,
+
+    /// The requirement that was not satisfied.
+    pub requirement: String,
+
+    /// The value found, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found: Option<Value>,
+}
+
+impl Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} request (entry {}): {}", self.label, self.entry, self.requirement)?;
+        if let Some(found) = &self.found {
+            write!(f, ", found {found}")?;
+        }
+        Ok(())
+    }
+}
+
+// Whether a JSON pointer is at or below another.
+fn within(pointer: &str, parent: &str) -> bool {
+    pointer.strip_prefix(parent).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
@@ -0,0 +1,455 @@
+//! Tests for managing concurrent issuance and presentation flows with the
+//! `HolderAgent`.
+mod provider;
+
+use std::sync::{Arc, Mutex};
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{Duration, Utc};
+use credibil_holder::agent::{Deadlines, Flow, HolderAgent, HolderEvent, Input, Step};
+use credibil_holder::credential::{CredentialMetadata, Sharing};
+use credibil_holder::error::FlowTimedOut;
+use credibil_holder::issuance::{CredentialOffer, MIN_DEFERRED_INTERVAL, OfferType, SendType};
+use credibil_holder::presentation::{Constraints, Field, Filter, FilterValue, InputDescriptor};
+use credibil_holder::provider::CredentialStorer;
+use credibil_holder::push::PushAction;
+use credibil_holder::test_utils::issuer::{
+    self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER, PENDING_USER,
+};
+use credibil_holder::test_utils::mock::{Endpoint, MockProvider, MockResponse};
+use credibil_holder::test_utils::verifier::{self, VERIFIER_ID};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use credibil_vc::verifier::{CreateRequestRequest, DeviceFlow};
+use futures::StreamExt;
+use serde_json::{Value, json};
+
+use crate::provider as holder;
+
+async fn create_offer(
+    provider: &issuer::Provider, subject_id: &str,
+) -> (CredentialOffer, Option<String>) {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(subject_id.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let response = credibil_vc::issuer::create_offer(provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = response.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    (offer, response.tx_code)
+}
+
+// Create a presentation request for an employee ID credential, returning its
+// URI.
+async fn create_request(verifier_provider: &verifier::Provider) -> String {
+    let request = CreateRequestRequest {
+        client_id: VERIFIER_ID.into(),
+        device_flow: DeviceFlow::CrossDevice,
+        purpose: "To verify employment status".into(),
+        input_descriptors: vec![InputDescriptor {
+            id: "EmployeeID_JWT".into(),
+            constraints: Constraints {
+                fields: Some(vec![Field {
+                    path: vec!["$.type".into()],
+                    filter: Some(Filter {
+                        type_: "string".into(),
+                        value: FilterValue::Const("EmployeeIDCredential".into()),
+                    }),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            name: None,
+            purpose: None,
+            format: None,
+        }],
+        ..Default::default()
+    };
+    let init_request = credibil_vc::verifier::create_request(verifier_provider.clone(), &request)
+        .await
+        .expect("should get request");
+    init_request.request_uri.expect("should have request uri")
+}
+
+// Run two issuance flows side by side, cancelling one, then present the issued
+// credential to a verifier, checking the events emitted along the way.
+#[tokio::test]
+async fn concurrent_flows() {
+    let issuer_provider = issuer::Provider::new();
+    let verifier_provider = verifier::Provider::new();
+    let provider =
+        holder::Provider::new(Some(issuer_provider.clone()), Some(verifier_provider.clone()));
+    let agent = HolderAgent::new(provider, CLIENT_ID);
+    let events = agent.events();
+
+    let (offer, pin) = create_offer(&issuer_provider, NORMAL_USER).await;
+    let first = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    let (offer, _) = create_offer(&issuer_provider, NORMAL_USER).await;
+    let second = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+
+    let mut ids = agent.flow_ids();
+    ids.sort();
+    let mut expected = vec![first.clone(), second.clone()];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    // Cancel the second flow.
+    assert!(matches!(agent.cancel(&second), Some(Flow::Offered(_))));
+    assert!(agent.flow(&second).is_none());
+    assert!(agent.accept(&second, &None, None).is_err());
+
+    // Accept the first, then persist and resume it.
+    agent.accept(&first, &None, pin).expect("should accept offer");
+    agent.persist(&first, Utc::now() + Duration::minutes(5)).await.expect("should persist");
+    agent.cancel(&first);
+    agent.resume(&first).await.expect("should resume");
+    assert!(matches!(agent.flow(&first).as_deref(), Some(Flow::Accepted(_))));
+
+    // Receive and save the credential.
+    let credentials = agent.receive(&first).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 1);
+    assert_eq!(credentials[0].issuer, CREDENTIAL_ISSUER);
+    agent.save(&first).await.expect("should save credentials");
+    assert_eq!(agent.flow_ids(), Vec::<String>::new());
+    let stored = agent.provider().find(None).await.expect("should find credentials");
+    assert_eq!(stored.len(), 1);
+
+    // Present the credential to a verifier.
+    let uri = create_request(&verifier_provider).await;
+
+    let id = agent.request(&uri).await.expect("should start presentation");
+    assert!(agent.present(&id).await.is_err());
+    let matches = agent.matches(&id).await.expect("should find matches");
+    assert_eq!(matches.len(), 1);
+    agent.authorize(&id, &matches).expect("should authorize");
+    agent.present(&id).await.expect("should present");
+    assert!(agent.flow(&id).is_none());
+
+    // The event stream ends once the agent is dropped.
+    drop(agent);
+    let events = events.collect::<Vec<_>>().await;
+    let input_required = |id: &String, input| HolderEvent::InputRequired {
+        id: id.clone(),
+        input,
+    };
+    let progress = |id: &String, step| HolderEvent::Progress { id: id.clone(), step };
+    assert_eq!(
+        events,
+        vec![
+            input_required(&first, Input::Acceptance { pin_required: true }),
+            input_required(&second, Input::Acceptance { pin_required: true }),
+            HolderEvent::Cancelled { id: second.clone() },
+            progress(&first, Step::Accepted),
+            HolderEvent::Cancelled { id: first.clone() },
+            progress(&first, Step::TokenReceived),
+            progress(
+                &first,
+                Step::CredentialIssued {
+                    credential_configuration_id: "EmployeeID_JWT".into(),
+                }
+            ),
+            HolderEvent::Completed { id: first.clone() },
+            input_required(&id, Input::Authorization),
+            progress(&id, Step::Authorized),
+            HolderEvent::Completed { id: id.clone() },
+        ]
+    );
+}
+
+// Credentials authorized by the same token can be requested concurrently.
+#[tokio::test]
+async fn concurrent_credentials() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID).with_concurrency(2);
+
+    let (offer, pin) =
+        provider.offer(&["EmployeeID_JWT", "Developer_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 2);
+    agent.save(&id).await.expect("should save credentials");
+    let stored = provider.find(None).await.expect("should find credentials");
+    assert_eq!(stored.len(), 2);
+}
+
+// A step called on a flow in another state fails without losing the flow.
+#[tokio::test]
+async fn repeated_accept() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin.clone()).expect("should accept offer");
+    assert!(agent.accept(&id, &None, pin).is_err());
+    assert!(matches!(agent.flow(&id).as_deref(), Some(Flow::Accepted(_))));
+
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 1);
+}
+
+// A flow cancelled while a credential request is in flight stays cancelled.
+#[tokio::test]
+async fn cancel_during_issue() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+
+    let (cancelling, cancel_id) = (agent.clone(), id.clone());
+    provider.respond_with(Endpoint::Credential, move |_: &Value| {
+        cancelling.cancel(&cancel_id);
+        MockResponse::ok(json!({"transaction_id": "tx-1"}))
+    });
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert!(agent.flow(&id).is_none());
+}
+
+// The nonce in a credential request's proof of possession.
+fn proof_nonce(request: &Value) -> String {
+    let jwt = request["proof"]["jwt"].as_str().expect("should have proof");
+    let payload = jwt.split('.').nth(1).expect("should have payload");
+    let claims: Value = serde_json::from_slice(
+        &Base64UrlUnpadded::decode_vec(payload).expect("should decode payload"),
+    )
+    .expect("should parse claims");
+    claims["nonce"].as_str().expect("should have nonce").to_string()
+}
+
+// Requests share the token's nonce when the issuer does not rotate it.
+#[tokio::test]
+async fn single_nonce() {
+    for concurrency in [1, 2] {
+        let provider = MockProvider::new();
+        let agent = HolderAgent::new(provider.clone(), CLIENT_ID).with_concurrency(concurrency);
+
+        let (offer, pin) = provider
+            .offer(&["EmployeeID_JWT", "Developer_JWT"], true)
+            .await
+            .expect("should get offer");
+        let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+        agent.accept(&id, &None, pin).expect("should accept offer");
+
+        let nonces = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&nonces);
+        provider.respond_with(Endpoint::Credential, move |request: &Value| {
+            seen.lock().expect("should lock").push(proof_nonce(request));
+            MockResponse::ok(json!({"transaction_id": "tx-1"}))
+        });
+        Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+
+        let nonces = nonces.lock().expect("should lock");
+        assert_eq!(nonces.len(), 2);
+        assert_eq!(nonces[0], nonces[1]);
+    }
+}
+
+// A request whose proof is rejected is sent again with the issuer's fresh
+// nonce.
+#[tokio::test]
+async fn invalid_proof_nonce() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+
+    let nonces = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&nonces);
+    provider.respond_with(Endpoint::Credential, move |request: &Value| {
+        let mut nonces = seen.lock().expect("should lock");
+        nonces.push(proof_nonce(request));
+        if nonces.len() > 1 {
+            return MockResponse::ok(json!({"transaction_id": "tx-1"}));
+        }
+        MockResponse {
+            status: Some(400),
+            body: json!({"error": "invalid_proof", "c_nonce": "fresh-nonce"}),
+            retry_after: None,
+        }
+    });
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+
+    let nonces = nonces.lock().expect("should lock");
+    assert_eq!(nonces.len(), 2);
+    assert_eq!(nonces[1], "fresh-nonce");
+}
+
+// Candidates are listed as metadata and only the selected credentials are
+// loaded in full to be presented.
+#[tokio::test]
+async fn present_selected() {
+    let issuer_provider = issuer::Provider::new();
+    let verifier_provider = verifier::Provider::new();
+    let provider =
+        holder::Provider::new(Some(issuer_provider.clone()), Some(verifier_provider.clone()));
+    let agent = HolderAgent::new(provider, CLIENT_ID);
+
+    let (offer, pin) = create_offer(&issuer_provider, NORMAL_USER).await;
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    agent.save(&id).await.expect("should save credentials");
+
+    let uri = create_request(&verifier_provider).await;
+    let id = agent.request(&uri).await.expect("should start presentation");
+    let candidates =
+        agent.candidates(&id, Sharing::default()).await.expect("should find candidates");
+    assert_eq!(candidates.len(), 1);
+    assert!(candidates[0].type_.contains(&"EmployeeIDCredential".to_string()));
+
+    let missing = CredentialMetadata {
+        id: "urn:uuid:missing".into(),
+        ..CredentialMetadata::default()
+    };
+    assert!(agent.authorize_selected(&id, &[missing]).await.is_err());
+    agent.authorize_selected(&id, &candidates).await.expect("should authorize");
+    agent.present(&id).await.expect("should present");
+}
+
+// Issuer push notifications retrieve deferred credentials without waiting for
+// the next poll and start flows for re-issued credentials.
+#[tokio::test]
+async fn push_notifications() {
+    let issuer_provider = issuer::Provider::new();
+    let provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+    let agent = HolderAgent::new(provider, CLIENT_ID);
+
+    let (offer, pin) = create_offer(&issuer_provider, PENDING_USER).await;
+    let id = agent.offer(offer, PENDING_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    let Some(Flow::Issued(flow)) = agent.flow(&id).as_deref().cloned() else {
+        panic!("expected issued flow");
+    };
+    let tx_id = flow.deferred().into_keys().next().expect("should have transaction");
+
+    let payload = json!({"event": "deferred_ready", "transaction_id": "unknown"});
+    assert!(Box::pin(agent.handle_push(payload.to_string().as_bytes())).await.is_err());
+
+    let payload = json!({"event": "deferred_ready", "transaction_id": tx_id});
+    let action =
+        Box::pin(agent.handle_push(payload.to_string().as_bytes())).await.expect("should retrieve");
+    let PushAction::Retrieved {
+        id: retrieved,
+        credentials,
+    } = action
+    else {
+        panic!("expected retrieved credentials");
+    };
+    assert_eq!(retrieved, id);
+    assert_eq!(credentials.len(), 1);
+    assert_eq!(agent.next_poll(&id), None);
+
+    let (offer, _) = create_offer(&issuer_provider, NORMAL_USER).await;
+    let payload = json!({
+        "event": "reissuance_offered",
+        "credential_id": "urn:uuid:1234",
+        "subject_id": NORMAL_USER,
+        "credential_offer": offer,
+    });
+    let action =
+        Box::pin(agent.handle_push(payload.to_string().as_bytes())).await.expect("should offer");
+    let PushAction::Offered { id, replaces } = action else {
+        panic!("expected an offer");
+    };
+    assert_eq!(replaces.as_deref(), Some("urn:uuid:1234"));
+    assert!(matches!(agent.flow(&id).as_deref(), Some(Flow::Offered(_))));
+
+    let payload = json!({"event": "credential_revoked", "credential_id": "urn:uuid:1234"});
+    let action =
+        Box::pin(agent.handle_push(payload.to_string().as_bytes())).await.expect("should report");
+    assert!(
+        matches!(action, PushAction::Revoked { credential_id } if credential_id == "urn:uuid:1234")
+    );
+}
+
+// Deferred credentials are polled for no more often than the interval allows.
+#[tokio::test]
+async fn poll_deferred() {
+    let issuer_provider = issuer::Provider::new();
+    let provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+    let agent = HolderAgent::new(provider, CLIENT_ID);
+
+    let (offer, pin) = create_offer(&issuer_provider, PENDING_USER).await;
+    let id = agent.offer(offer, PENDING_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 0);
+    agent.save(&id).await.expect("should save credentials");
+
+    // The issuer is not polled before the default interval has passed.
+    let next = agent.next_poll(&id).expect("should have deferred credentials");
+    assert!(next > Utc::now() + Duration::seconds(4));
+    let credentials = Box::pin(agent.poll_deferred(&id)).await.expect("should poll");
+    assert_eq!(credentials.len(), 0);
+
+    // Intervals shorter than the minimum are raised to it.
+    let Some(Flow::Issued(mut flow)) = agent.flow(&id).as_deref().cloned() else {
+        panic!("expected issued flow");
+    };
+    let tx_id = flow.deferred().into_keys().next().expect("should have transaction");
+    flow.set_deferred_interval(&tx_id, std::time::Duration::ZERO);
+    let poll = flow.deferred_poll(&tx_id).expect("should schedule poll");
+    assert_eq!(poll.interval, MIN_DEFERRED_INTERVAL.as_secs());
+    agent.restore(Flow::Issued(flow));
+
+    std::thread::sleep(MIN_DEFERRED_INTERVAL);
+    let credentials = Box::pin(agent.poll_deferred(&id)).await.expect("should poll");
+    assert_eq!(credentials.len(), 1);
+    assert_eq!(agent.next_poll(&id), None);
+    agent.save(&id).await.expect("should save credentials");
+    assert!(agent.flow(&id).is_none());
+}
+
+// Flows past their deadline are timed out and can no longer be advanced.
+#[tokio::test]
+async fn deadlines() {
+    let issuer_provider = issuer::Provider::new();
+    let verifier_provider = verifier::Provider::new();
+    let provider =
+        holder::Provider::new(Some(issuer_provider.clone()), Some(verifier_provider.clone()));
+    let deadlines = Deadlines {
+        issuance: Some(std::time::Duration::ZERO),
+        presentation: Some(std::time::Duration::ZERO),
+    };
+    let agent = HolderAgent::new(provider, CLIENT_ID).with_deadlines(deadlines);
+    let events = agent.events();
+
+    // Issuance times out once the offer is accepted.
+    let (offer, pin) = create_offer(&issuer_provider, NORMAL_USER).await;
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    let Some(Flow::TimedOut(timed_out)) = agent.flow(&id).as_deref().cloned() else {
+        panic!("expected timed out flow");
+    };
+    let err = Box::pin(agent.receive(&id)).await.expect_err("should time out");
+    let err = err.downcast_ref::<FlowTimedOut>().expect("should be FlowTimedOut");
+    assert_eq!(err.deadline, timed_out.deadline);
+
+    // Presentation times out once the request is received.
+    let request_uri = create_request(&verifier_provider).await;
+    let request = agent.request(&request_uri).await.expect("should start flow");
+    assert_eq!(agent.check_deadlines(), std::slice::from_ref(&request));
+    assert_eq!(agent.check_deadlines(), Vec::<String>::new());
+    let err = agent.authorize(&request, &[]).expect_err("should time out");
+    assert!(err.is::<FlowTimedOut>());
+
+    let timed_out: Vec<HolderEvent> = events
+        .take(5)
+        .filter(|e| std::future::ready(matches!(e, HolderEvent::TimedOut { .. })))
+        .collect()
+        .await;
+    assert_eq!(timed_out, [HolderEvent::TimedOut { id }, HolderEvent::TimedOut { id: request }]);
+}
@@ -0,0 +1,117 @@
+//! Tests for bearer material bound to its origin, with expiry and redacted
+//! `Debug` output.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use credibil_holder::bearer::{Nonce, Token};
+use credibil_holder::clock::{Clock, TimeValidator};
+use credibil_holder::error::{OAuthError, OriginMismatch};
+use credibil_holder::issuance::{
+    CredentialOffer, IssuanceFlow, IssuanceFlowBuilder, Issuer, NotAccepted, PreAuthorized,
+    TokenResponse, WithOffer, WithoutToken,
+};
+use serde_json::json;
+
+const METADATA: &str = include_str!("conformance/fixtures/issuer_metadata.json");
+const ISSUER: &str = "https://credential-issuer.example.com";
+
+// A clock fixed at a known time.
+struct Fixed(DateTime<Utc>);
+
+impl Clock for Fixed {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+fn offered_flow() -> IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken> {
+    let issuer: Issuer = serde_json::from_str(METADATA).expect("should parse metadata");
+    let offer: CredentialOffer = serde_json::from_value(json!({
+        "credential_issuer": ISSUER,
+        "credential_configuration_ids": ["UniversityDegreeCredential"],
+        "grants": {
+            "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                "pre-authorized_code": "adhjhdjajkdkhjhdj"
+            }
+        }
+    }))
+    .expect("should parse offer");
+    let grant = offer.pre_authorized_code().expect("should have pre-authorized code grant");
+    IssuanceFlowBuilder::new("s6BhdRkqt3", issuer).subject_id("holder").pre_authorized(offer, grant)
+}
+
+// Material is only exposed for its origin and is redacted from `Debug`.
+#[test]
+fn origin() {
+    let token = Token::new("eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ", ISSUER);
+    assert_eq!(token.origin(), ISSUER);
+    assert_eq!(token.expose_for(ISSUER), Ok("eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ"));
+
+    let err = token.expose_for("https://other.example.com").expect_err("should refuse");
+    assert_eq!(
+        err,
+        OriginMismatch {
+            kind: "Token".into(),
+            origin: ISSUER.into(),
+            used_with: "https://other.example.com".into(),
+        }
+    );
+
+    let debug = format!("{token:?}");
+    assert!(debug.starts_with("Token {"));
+    assert!(!debug.contains("eyJhbGci"));
+    assert!(debug.contains(ISSUER));
+}
+
+// Expiry is checked against a clock, allowing for leeway; material without
+// an expiry never expires.
+#[test]
+fn expiry() {
+    let now = DateTime::from_timestamp(1_800_000_000, 0).expect("should be a valid time");
+    let times = TimeValidator::new(Fixed(now)).with_leeway(TimeDelta::zero());
+
+    let nonce = Nonce::new("wKI4LT17ac15ES9bw8ac4", ISSUER);
+    assert!(!nonce.is_expired(&times));
+
+    let nonce = nonce.expires_in(Some(300), now - TimeDelta::seconds(301));
+    assert_eq!(nonce.expires_at(), Some(now - TimeDelta::seconds(1)));
+    assert!(nonce.is_expired(&times));
+    assert!(!nonce.is_expired(&TimeValidator::new(Fixed(now))));
+
+    let nonce = Nonce::new("wKI4LT17ac15ES9bw8ac4", ISSUER).expires_in(Some(0), now);
+    assert_eq!(nonce.expires_at(), None);
+}
+
+// Flows hand out their token and nonce bound to the issuer, and only accept
+// a nonce from the issuer.
+#[test]
+fn flow_material() {
+    let token: TokenResponse = serde_json::from_value(json!({
+        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+        "token_type": "Bearer",
+        "expires_in": 86400
+    }))
+    .expect("should parse token");
+    let flow = offered_flow().accept(&None, None);
+    let code = flow.pre_authorized_code();
+    assert_eq!(code.expose_for(ISSUER), Ok("adhjhdjajkdkhjhdj"));
+
+    let mut flow = flow.token(token);
+    let token = flow.access_token();
+    assert_eq!(token.expose_for(ISSUER), Ok("eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ"));
+    let expires_at = token.expires_at().expect("should have expiry");
+    assert!(expires_at > Utc::now() + TimeDelta::hours(23));
+    assert!(flow.nonce().is_none());
+
+    let other = Nonce::new("wKI4LT17ac15ES9bw8ac4", "https://other.example.com");
+    flow.set_nonce(&other).expect_err("should refuse nonce from another issuer");
+    assert!(flow.nonce().is_none());
+
+    let error = OAuthError::parse(
+        br#"{"error": "invalid_proof", "c_nonce": "8YE9hCnyV2", "c_nonce_expires_in": 86400}"#,
+    )
+    .expect("should parse error");
+    let nonce = error.nonce(ISSUER).expect("should have nonce");
+    flow.set_nonce(&nonce).expect("should set nonce");
+    assert_eq!(flow.nonce(), Some(nonce));
+    assert_eq!(flow.proof().nonce.as_deref(), Some("8YE9hCnyV2"));
+}
@@ -0,0 +1,72 @@
+//! Tests for checking the holder's key satisfies a credential configuration's
+//! binding methods and proof types before requesting credentials.
+
+use std::collections::BTreeMap;
+
+use credibil_holder::issuance::IssuanceFlowBuilder;
+use credibil_holder::issuance::binding::KeyBinding;
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+fn binding(methods: &[&str], proof_types: &[(&str, &[&str])]) -> KeyBinding {
+    let strings = |values: &[&str]| values.iter().map(ToString::to_string).collect();
+    KeyBinding {
+        configuration_id: "EmployeeID_JWT".into(),
+        binding_methods: strings(methods),
+        proof_types: proof_types
+            .iter()
+            .map(|(name, algorithms)| ((*name).to_string(), strings(algorithms)))
+            .collect::<BTreeMap<_, _>>(),
+    }
+}
+
+// The key's DID method and algorithm must be supported; omitted parameters
+// place no restriction.
+#[test]
+fn requirements() {
+    let kid = "did:key:z6MkExample#z6MkExample";
+
+    let supported = binding(&["did:key", "did:web"], &[("jwt", &["ES256K", "EdDSA"])]);
+    supported.check(kid, "EdDSA").expect("should bind key");
+    binding(&[], &[]).check(kid, "ES256").expect("should bind any key");
+    binding(&["did"], &[("jwt", &[])]).check(kid, "ES256").expect("should bind any DID");
+
+    let err = binding(&["did:web", "jwk"], &[]).check(kid, "EdDSA").expect_err("should refuse");
+    assert_eq!(err.requirement, "cryptographic_binding_methods_supported");
+    assert_eq!(err.supported, vec!["did:web", "jwk"]);
+
+    let err = supported.check(kid, "ES256").expect_err("should refuse algorithm");
+    assert_eq!(err.requirement, "proof_signing_alg_values_supported");
+    assert_eq!(err.algorithm, "ES256");
+
+    let err = binding(&[], &[("cwt", &["EdDSA"])]).check(kid, "EdDSA").expect_err("should refuse");
+    assert_eq!(err.requirement, "proof_types_supported");
+    assert_eq!(err.supported, vec!["cwt"]);
+}
+
+// The accepted credentials' configurations are checked against the signer.
+#[tokio::test]
+async fn accepted_bindings() {
+    let provider = MockProvider::new();
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let flow = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant)
+        .accept(&None, pin);
+
+    let bindings = flow.key_bindings();
+    assert_eq!(bindings.len(), 1);
+    assert_eq!(bindings[0].configuration_id, "EmployeeID_JWT");
+    flow.check_binding(&provider).await.expect("should bind the holder's key");
+
+    let err = bindings[0].check("did:example:123#key-0", "EdDSA").expect_err("should refuse");
+    assert_eq!(err.configuration_id, "EmployeeID_JWT");
+}
@@ -0,0 +1,28 @@
+//! Tests for driving flows from synchronous code.
+
+use credibil_holder::blocking::{BlockingAgent, block_on};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+// Issue a credential, then present it, without an async context.
+#[test]
+fn issue_and_present() {
+    let provider = MockProvider::new();
+    let agent = BlockingAgent::new(provider.clone(), CLIENT_ID);
+
+    let (offer, pin) =
+        block_on(provider.offer(&["EmployeeID_JWT"], true)).expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    agent.receive(&id).expect("should receive credentials");
+    agent.save(&id).expect("should save credentials");
+
+    let uri = block_on(provider.presentation_request("EmployeeIDCredential"))
+        .expect("should get request");
+    let id = agent.request(&uri).expect("should start presentation");
+    let matches = agent.matches(&id).expect("should find matches");
+    assert_eq!(matches.len(), 1);
+    agent.authorize(&id, &matches).expect("should authorize");
+    agent.present(&id).expect("should present");
+    assert!(agent.agent().flow(&id).is_none());
+}
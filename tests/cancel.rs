@@ -0,0 +1,46 @@
+//! Tests for cancelling in-progress work with a `CancellationToken`.
+
+use std::future;
+
+use credibil_holder::agent::{Flow, HolderAgent};
+use credibil_holder::cancel::{CancellationToken, Cancelled};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+// Work that would never complete is aborted when the token is cancelled from
+// another task, and work that completes first is unaffected.
+#[tokio::test]
+async fn cancel_pending() {
+    let cancel = CancellationToken::new();
+    let ui = cancel.clone();
+    tokio::spawn(async move { ui.cancel() });
+
+    let err = cancel.run(future::pending::<()>()).await.expect_err("should be cancelled");
+    assert!(err.is::<Cancelled>());
+    assert!(cancel.is_cancelled());
+
+    let token = CancellationToken::new();
+    let output = token.run(async { 42 }).await.expect("should complete");
+    assert_eq!(output, 42);
+}
+
+// Receiving credentials with a cancelled token leaves the flow unchanged so it
+// can be retried.
+#[tokio::test]
+async fn cancel_receive() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let err =
+        Box::pin(agent.receive_with_cancel(&id, &cancel)).await.expect_err("should be cancelled");
+    assert!(err.is::<Cancelled>());
+    assert!(matches!(agent.flow(&id).as_deref(), Some(Flow::Accepted(_))));
+
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 1);
+}
@@ -0,0 +1,52 @@
+//! Tests for describing the capabilities of the wallet.
+
+use credibil_holder::capabilities::HolderCapabilities;
+use credibil_holder::provider::Signer;
+use credibil_holder::test_utils::mock::MockProvider;
+use serde_json::json;
+
+// Capabilities reflect the compiled features and formats, and the registered
+// signers.
+#[tokio::test]
+async fn capabilities() {
+    let provider = MockProvider::new();
+    let capabilities =
+        HolderCapabilities::new().signer(&provider).await.expect("should register signer");
+
+    assert!(capabilities.has_feature("issuance"));
+    assert!(capabilities.has_feature("presentation"));
+    assert_eq!(capabilities.features.contains(&"qr".into()), cfg!(feature = "qr"));
+    assert_eq!(capabilities.presentation_formats, ["jwt_vp_json", "jwt_vp"]);
+
+    let json = serde_json::to_value(&capabilities).expect("should serialize");
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        json["signers"][0]["verification_method"],
+        provider.verification_method().await.expect("should get verification method")
+    );
+    assert_eq!(json["credential_formats"], json!(["jwt_vc_json", "jwt_vc"]));
+}
+
+// Wallet metadata lists each format with the signers' algorithms.
+#[tokio::test]
+async fn metadata() {
+    assert!(HolderCapabilities::new().metadata().is_err());
+
+    let provider = MockProvider::new();
+    let alg = serde_json::to_value(provider.algorithm()).expect("should serialize algorithm");
+    let capabilities = HolderCapabilities::new()
+        .signer(&provider)
+        .await
+        .expect("should register signer")
+        .signer(&provider)
+        .await
+        .expect("should register signer");
+    let metadata = capabilities.metadata().expect("should create metadata");
+    let json = serde_json::to_value(&metadata).expect("should serialize");
+
+    for format in ["jwt_vc_json", "jwt_vc", "jwt_vp_json", "jwt_vp"] {
+        assert_eq!(json["vp_formats_supported"][format]["alg_values_supported"], json!([alg]));
+    }
+    assert_eq!(json["request_object_signing_alg_values_supported"], json!([alg]));
+    assert_eq!(json["response_types_supported"], json!(["vp_token", "id_token"]));
+}
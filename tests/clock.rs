@@ -0,0 +1,67 @@
+//! Tests for checking token and credential times allowing for clock skew.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use credibil_holder::clock::{Clock, DEFAULT_LEEWAY, TimeValidator};
+use credibil_holder::credential::Credential;
+use serde_json::json;
+
+// A clock fixed at a known time.
+struct Fixed(DateTime<Utc>);
+
+impl Clock for Fixed {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+const fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).expect("should be a valid time")
+}
+
+const NOW: i64 = 1_800_000_000;
+
+// Claims slightly in the future, or slightly expired, are accepted within the
+// leeway and rejected outside it.
+#[test]
+fn claims() {
+    let times = TimeValidator::new(Fixed(at(NOW)));
+    assert_eq!(times.leeway(), DEFAULT_LEEWAY);
+    assert_eq!(times.now(), at(NOW));
+
+    times.check_claims(&json!({"iat": NOW + 30, "nbf": NOW + 30})).expect("should be valid");
+    times.check_claims(&json!({"exp": NOW - 30})).expect("should be valid");
+    times.check_claims(&json!({"aud": "ignored"})).expect("should be valid");
+    times.check_claims(&json!({"nbf": null})).expect("should be valid");
+
+    let err = times.check_claims(&json!({"iat": NOW, "nbf": NOW + 90})).expect_err("too early");
+    assert_eq!(err.claim, "nbf");
+    assert_eq!(err.at, Some(at(NOW + 90)));
+    let err = times.check_claims(&json!({"exp": NOW - 60})).expect_err("should be expired");
+    assert_eq!(err.claim, "exp");
+    let err = times.check_claims(&json!({"exp": "tomorrow"})).expect_err("should be invalid");
+    assert_eq!(err.at, None);
+
+    let strict = TimeValidator::new(Fixed(at(NOW))).with_leeway(TimeDelta::zero());
+    assert!(strict.check_claims(&json!({"iat": NOW + 1})).is_err());
+    assert!(strict.check_claims(&json!({"exp": NOW})).is_err());
+}
+
+// Credential validity periods are checked against the injected clock.
+#[test]
+fn credential_validity() {
+    let credential = Credential {
+        issuance_date: at(NOW + 30),
+        valid_until: Some(at(NOW + 3600)),
+        ..Credential::default()
+    };
+    let times = TimeValidator::new(Fixed(at(NOW)));
+    credential.check_validity(&times).expect("should be valid within leeway");
+
+    let later = TimeValidator::new(Fixed(at(NOW + 3630)));
+    later.check_period(None, credential.valid_until).expect("should be valid within leeway");
+    let much_later = TimeValidator::new(Fixed(at(NOW + 3660)));
+    let err = credential.check_validity(&much_later).expect_err("should be expired");
+    assert_eq!(err.claim, "valid_until");
+    assert!(much_later.is_expired(credential.valid_until));
+    assert!(!times.is_valid(Some(at(NOW + 120)), None));
+}
@@ -0,0 +1,85 @@
+//! Tests for refusing to resend a pre-authorized code that has already been
+//! exchanged for a token.
+
+use credibil_holder::agent::{Flow, HolderAgent};
+use credibil_holder::error::CodeExchanged;
+use credibil_holder::issuance::{
+    Accepted, IssuanceFlow, IssuanceFlowBuilder, PreAuthorized, WithOffer, WithoutToken,
+};
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_holder::test_utils::mock::{Endpoint, MockProvider, MockResponse};
+use serde_json::Value;
+
+// A flow kept from before the exchange refuses to resend the code.
+#[tokio::test]
+async fn retried_exchange() {
+    let provider = MockProvider::new();
+    let state = accepted_flow(&provider).await;
+    let retry = state.clone();
+
+    let request = state.checked_token_request(&provider).await.expect("should get token request");
+    let token = provider.token(request).await.expect("should get token");
+    state.record_exchange(&provider, &token).await.expect("should record exchange");
+    assert!(retry.is_exchanged(&provider).await);
+
+    let err = retry.checked_token_request(&provider).await.expect_err("should refuse to resend");
+    let exchanged = err.downcast_ref::<CodeExchanged>().expect("should be CodeExchanged");
+    assert_eq!(exchanged.credential_issuer, CREDENTIAL_ISSUER);
+}
+
+// The agent keeps the token after a failed credential request, so a retry
+// requests credentials without resending the code.
+#[tokio::test]
+async fn retried_receive() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+
+    provider.respond_with(Endpoint::Credential, |_: &Value| MockResponse::offline());
+    Box::pin(agent.receive(&id)).await.expect_err("should fail to get credentials");
+    assert!(matches!(agent.flow(&id).as_deref(), Some(Flow::Exchanged(_))));
+
+    provider.reset(Endpoint::Credential);
+    provider.respond_with(Endpoint::Token, |_: &Value| {
+        MockResponse::error("invalid_grant", "code already used")
+    });
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    assert_eq!(credentials.len(), 1);
+}
+
+// The exchange is detected for a flow restored from state saved before the
+// exchange.
+#[tokio::test]
+async fn restored_exchange() {
+    let provider = MockProvider::new();
+    let state = accepted_flow(&provider).await;
+    let saved = serde_json::to_string(&state).expect("should serialize");
+
+    let request = state.checked_token_request(&provider).await.expect("should get token request");
+    let token = provider.token(request).await.expect("should get token");
+    state.record_exchange(&provider, &token).await.expect("should record exchange");
+
+    let restored: AcceptedFlow = serde_json::from_str(&saved).expect("should deserialize");
+    let err = restored.checked_token_request(&provider).await.expect_err("should refuse to resend");
+    assert!(err.is::<CodeExchanged>());
+}
+
+type AcceptedFlow = IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>;
+
+async fn accepted_flow(provider: &MockProvider) -> AcceptedFlow {
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant)
+        .accept(&None, pin)
+}
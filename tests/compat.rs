@@ -0,0 +1,99 @@
+//! Tests for adapting requests and responses to the OID4VCI draft implemented
+//! by the issuer.
+
+use credibil_holder::bearer::Nonce;
+use credibil_holder::issuance::compat::{Draft, nonce_endpoint};
+use credibil_holder::issuance::{
+    CredentialOffer, CredentialResponseType, IssuanceFlow, IssuanceFlowBuilder, Issuer,
+    NotAccepted, PreAuthorized, TokenResponse, WithOffer, WithoutToken,
+};
+use serde_json::{Value, json};
+
+const METADATA: &str = include_str!("conformance/fixtures/issuer_metadata.json");
+
+fn offered_flow() -> IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken> {
+    let issuer: Issuer = serde_json::from_str(METADATA).expect("should parse metadata");
+    let offer: CredentialOffer = serde_json::from_value(json!({
+        "credential_issuer": "https://credential-issuer.example.com",
+        "credential_configuration_ids": ["UniversityDegreeCredential"],
+        "grants": {
+            "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                "pre-authorized_code": "adhjhdjajkdkhjhdj"
+            }
+        }
+    }))
+    .expect("should parse offer");
+    let grant = offer.pre_authorized_code().expect("should have pre-authorized code grant");
+    IssuanceFlowBuilder::new("s6BhdRkqt3", issuer).subject_id("holder").pre_authorized(offer, grant)
+}
+
+// The draft is detected from the issuer's metadata.
+#[test]
+fn detect() {
+    let mut metadata: Value = serde_json::from_str(METADATA).expect("should parse metadata");
+    assert_eq!(Draft::detect(&metadata), Draft::Draft14);
+    assert_eq!(nonce_endpoint(&metadata), None);
+
+    metadata["credential_identifiers_supported"] = json!(true);
+    assert_eq!(Draft::detect(&metadata), Draft::Draft13);
+
+    metadata["nonce_endpoint"] = json!("https://credential-issuer.example.com/nonce");
+    assert_eq!(Draft::detect(&metadata), Draft::Draft15);
+    assert_eq!(
+        nonce_endpoint(&metadata).as_deref(),
+        Some("https://credential-issuer.example.com/nonce")
+    );
+}
+
+// Without credential identifiers in the token response, credentials are
+// requested by format (draft 13 and 14) or configuration ID (draft 15) using
+// the nonce from the nonce endpoint.
+#[test]
+fn request_without_identifiers() {
+    let token: TokenResponse = serde_json::from_value(json!({
+        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+        "token_type": "Bearer",
+        "expires_in": 86400
+    }))
+    .expect("should parse token");
+    let mut flow = offered_flow().accept(&None, None).token(token);
+    let nonce = Nonce::new("wKI4LT17ac15ES9bw8ac4", "https://credential-issuer.example.com");
+    flow.set_nonce(&nonce).expect("should set nonce");
+    assert_eq!(flow.proof().nonce.as_deref(), Some("wKI4LT17ac15ES9bw8ac4"));
+
+    let requests = flow.credential_requests(&[], "proof.jwt").collect::<Vec<_>>();
+    assert_eq!(requests.len(), 1);
+    let (cfg_id, request) = &requests[0];
+    assert_eq!(cfg_id, "UniversityDegreeCredential");
+
+    let body = Draft::Draft14.credential_request(cfg_id, request).expect("should adapt");
+    assert_eq!(body["format"], "jwt_vc_json");
+    assert_eq!(body["proof"], json!({"proof_type": "jwt", "jwt": "proof.jwt"}));
+    assert!(body.get("access_token").is_none());
+
+    let body = Draft::Draft15.credential_request(cfg_id, request).expect("should adapt");
+    assert_eq!(
+        body,
+        json!({
+            "credential_configuration_id": "UniversityDegreeCredential",
+            "proofs": {"jwt": ["proof.jwt"]}
+        })
+    );
+}
+
+// Draft 15 credentials are unwrapped from their credential objects.
+#[test]
+fn draft15_response() {
+    let body = json!({
+        "credentials": [{"credential": "eyJ0eXAiOi.ey.sig1"}, {"credential": "eyJ0eXAiOi.ey.sig2"}],
+        "notification_id": "3fwe98js"
+    });
+    let response = Draft::Draft15.credential_response(body).expect("should adapt");
+    let CredentialResponseType::Credentials(credentials) = response.response else {
+        panic!("expected CredentialResponseType::Credentials");
+    };
+    assert_eq!(
+        serde_json::to_value(credentials).expect("should serialize"),
+        json!(["eyJ0eXAiOi.ey.sig1", "eyJ0eXAiOi.ey.sig2"])
+    );
+}
@@ -0,0 +1,139 @@
+//! Conformance tests for requests created by the holder SDK.
+//!
+//! Each fixture in `fixtures/` records the inputs to a step in an OID4VCI or
+//! OID4VP flow together with the request the holder is expected to send (or
+//! the request object it is expected to parse). Requests are compared
+//! field by field: form-encoded values byte-for-byte and JSON values
+//! structurally (JSON object key order is not significant).
+//!
+//! See `README.md` for how fixtures are recorded.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use credibil_holder::issuance::{
+    AuthorizationSpec, CredentialOffer, IssuanceFlow, IssuanceFlowBuilder, Issuer, NotAccepted,
+    PreAuthorized, TokenResponse, WithOffer, WithoutToken,
+};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, parse_request_object};
+use serde_json::{Map, Value, json};
+
+const CLIENT_ID: &str = "s6BhdRkqt3";
+const SUBJECT_ID: &str = "holder";
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/fixtures")
+}
+
+// Load all fixtures of the given kind.
+fn fixtures(kind: &str) -> Vec<(PathBuf, Value)> {
+    let mut fixtures = vec![];
+    for entry in fs::read_dir(fixtures_dir()).expect("should read fixtures") {
+        let path = entry.expect("should read entry").path();
+        let fixture: Value = serde_json::from_slice(&fs::read(&path).expect("should read fixture"))
+            .expect("should parse fixture");
+        if fixture["kind"] == kind {
+            fixtures.push((path, fixture));
+        }
+    }
+    assert!(!fixtures.is_empty(), "no {kind} fixtures found");
+    fixtures
+}
+
+fn issuer() -> Issuer {
+    let json = fs::read(fixtures_dir().join("issuer_metadata.json")).expect("should read metadata");
+    serde_json::from_slice(&json).expect("should parse metadata")
+}
+
+fn offered_flow(
+    fixture: &Value,
+) -> IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken> {
+    let offer: CredentialOffer =
+        serde_json::from_value(fixture["offer"].clone()).expect("should parse offer");
+    let grant = offer.pre_authorized_code().expect("should have pre-authorized code grant");
+    IssuanceFlowBuilder::new(CLIENT_ID, issuer())
+        .subject_id(SUBJECT_ID)
+        .pre_authorized(offer, grant)
+}
+
+// Compare the actual request to the fixture's expectation or, when
+// `CONFORMANCE_RECORD` is set, record it as the expectation.
+fn check(path: &Path, fixture: &Value, actual: Value) {
+    if std::env::var_os("CONFORMANCE_RECORD").is_some() {
+        let mut fixture = fixture.clone();
+        fixture["expected"] = actual;
+        let json = serde_json::to_string_pretty(&fixture).expect("should serialize");
+        fs::write(path, json + "\n").expect("should write fixture");
+        return;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    assert_eq!(actual, fixture["expected"], "{name}: {}", fixture["description"]);
+}
+
+// Form-encoded values that are themselves JSON are parsed so they compare
+// structurally; all other values are compared as strings.
+fn form_value(form: HashMap<String, String>) -> Value {
+    let mut map = Map::new();
+    for (key, value) in form {
+        let value = match serde_json::from_str::<Value>(&value) {
+            Ok(json @ (Value::Object(_) | Value::Array(_))) => json,
+            _ => Value::String(value),
+        };
+        map.insert(key, value);
+    }
+    Value::Object(map)
+}
+
+#[test]
+fn token_requests() {
+    for (path, fixture) in fixtures("token_request") {
+        let accepted: Option<Vec<AuthorizationSpec>> =
+            serde_json::from_value(fixture["accepted"].clone()).unwrap_or_default();
+        let tx_code = fixture["tx_code"].as_str().map(ToString::to_string);
+        let flow = offered_flow(&fixture).accept(&accepted, tx_code);
+
+        let request = flow.token_request();
+        let form = request.form_encode().expect("should form encode");
+        check(&path, &fixture, json!({"form": form_value(form)}));
+    }
+}
+
+#[test]
+fn credential_requests() {
+    for (path, fixture) in fixtures("credential_request") {
+        let token: TokenResponse =
+            serde_json::from_value(fixture["token_response"].clone()).expect("should parse token");
+        let identifiers: Vec<String> =
+            serde_json::from_value(fixture["identifiers"].clone()).expect("should parse ids");
+        let jwt = fixture["proof_jwt"].as_str().expect("should have proof");
+        let flow = offered_flow(&fixture).accept(&None, None).token(token);
+
+        let requests = flow
+            .credential_requests(&identifiers, jwt)
+            .map(|(_, request)| serde_json::to_value(request).expect("should serialize"))
+            .collect::<Vec<_>>();
+        check(&path, &fixture, json!({"requests": requests}));
+    }
+}
+
+#[test]
+fn request_objects() {
+    for (path, fixture) in fixtures("request_object") {
+        let request = fixture["request"].as_str().expect("should have request");
+        let request_object = parse_request_object(request)
+            .expect("should parse request object")
+            .expect("should be a request object");
+        let flow = PresentationFlow::<NotAuthorized>::new(request_object.clone())
+            .expect("should start flow");
+        let filter = flow.filter().expect("should build filter");
+        check(
+            &path,
+            &fixture,
+            json!({
+                "request_object": request_object,
+                "filter": filter,
+            }),
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Tests for obtaining holder consent before signing on their behalf.
+mod provider;
+
+use std::sync::Mutex;
+
+use credibil_holder::consent::{Consent, ConsentRefused, SigningOperation};
+use credibil_holder::issuance::{IssuanceFlowBuilder, OfferType, SendType};
+use credibil_holder::provider::{ConsentGate, Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+
+use crate::provider as holder;
+
+// A consent gate that records the operations it is asked about and responds
+// with a fixed answer.
+struct Gate {
+    answer: Consent,
+    asked: Mutex<Vec<SigningOperation>>,
+}
+
+impl Gate {
+    const fn new(answer: Consent) -> Self {
+        Self {
+            answer,
+            asked: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl ConsentGate for Gate {
+    #[allow(clippy::unused_async_trait_impl)]
+    async fn consent(&self, operation: &SigningOperation) -> anyhow::Result<Consent> {
+        self.asked.lock().expect("should lock").push(operation.clone());
+        Ok(self.answer)
+    }
+}
+
+// Consent is requested for the proof of possession and the proof is signed
+// when the holder agrees, or cancelled when they refuse.
+#[tokio::test]
+async fn proof_consent() {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let issuer_provider = issuer::Provider::new();
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    let provider = holder::Provider::new(Some(issuer_provider), None);
+
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let pre_auth_code_grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, pre_auth_code_grant);
+    let state = state.accept(&None, offer_resp.tx_code);
+    let token_response =
+        provider.token(state.token_request()).await.expect("should get token response");
+    let state = state.token(token_response);
+
+    // Holder agrees.
+    let gate = Gate::new(Consent::Granted);
+    state.build_proof(&state.signer(&provider, &gate)).await.expect("should build proof");
+    let asked = gate.asked.lock().expect("should lock").clone();
+    assert_eq!(
+        asked,
+        vec![SigningOperation::ProofOfPossession {
+            credential_issuer: CREDENTIAL_ISSUER.to_string()
+        }]
+    );
+
+    // Holder refuses.
+    let gate = Gate::new(Consent::Refused);
+    let Err(e) = state.build_proof(&state.signer(&provider, &gate)).await else {
+        panic!("signing should be refused");
+    };
+    assert!(e.downcast_ref::<ConsentRefused>().is_some());
+}
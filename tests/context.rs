@@ -0,0 +1,54 @@
+//! Tests for isolating wallet profiles using a `WalletContext`.
+mod provider;
+
+use credibil_holder::context::WalletContext;
+use credibil_holder::credential::Credential;
+use credibil_holder::issuance::{IssuanceFlowBuilder, Issuer, Server};
+use credibil_holder::provider::{ContextScoped, CredentialStorer};
+
+use crate::provider as holder;
+
+const METADATA: &str = include_str!("conformance/fixtures/issuer_metadata.json");
+
+// Credentials saved by one profile are not visible to another, and flows keep
+// track of the profile they were started for.
+#[tokio::test]
+async fn profile_isolation() {
+    let provider = holder::Provider::new(None, None);
+    let alice = WalletContext::new("alice").tenant("credibil");
+    let bob = WalletContext::new("bob").tenant("credibil");
+
+    let issuer: Issuer = serde_json::from_str(METADATA).expect("should parse metadata");
+    let flow = IssuanceFlowBuilder::new("client", issuer)
+        .subject_id("subject")
+        .wallet_initiated(Server::default())
+        .with_context(alice.clone());
+    assert_eq!(flow.context(), &alice);
+
+    let alice_store = provider.scoped(flow.context());
+    let credential = Credential {
+        id: "urn:uuid:1234".into(),
+        ..Credential::default()
+    };
+    alice_store.save(&credential).await.expect("should save credential");
+
+    let bob_store = provider.scoped(&bob);
+    assert!(bob_store.load(&credential.id).await.expect("should load").is_none());
+    assert_eq!(bob_store.find(None).await.expect("should find"), Vec::<Credential>::new());
+
+    let found = alice_store.find(None).await.expect("should find");
+    assert_eq!(found, vec![credential.clone()]);
+    assert_eq!(alice_store.load(&credential.id).await.expect("should load"), Some(credential));
+}
+
+// Scoped keys for different contexts cannot collide, whatever separators the
+// identifiers contain.
+#[test]
+fn scoped_keys() {
+    let tenant = WalletContext::new("bob").tenant("acme");
+    let profile = WalletContext::new("acme");
+    assert_ne!(tenant.scoped_key("key"), profile.scoped_key("bob/key"));
+    assert_ne!(tenant.scoped_key("key"), profile.scoped_key(&tenant.scoped_key("key")));
+    assert_ne!(WalletContext::new("acme/bob").scoped_key("key"), tenant.scoped_key("key"));
+    assert!(tenant.scoped_key("key").starts_with(&tenant.scoped_key("")));
+}
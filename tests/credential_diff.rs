@@ -0,0 +1,83 @@
+//! Tests for comparing a stored credential with a re-issued one.
+
+use chrono::{DateTime, Utc};
+use credibil_holder::credential::{Credential, SubjectClaims, ValidityPeriod};
+use credibil_holder::test_utils::date;
+use serde_json::{Value, json};
+
+fn credential(claims: &Value, valid_until: DateTime<Utc>) -> Credential {
+    Credential {
+        id: "urn:uuid:1234".into(),
+        subject_claims: vec![SubjectClaims {
+            id: Some("did:example:holder".into()),
+            claims: claims.as_object().cloned().expect("should be an object"),
+        }],
+        issuance_date: date(2024),
+        valid_until: Some(valid_until),
+        ..Credential::default()
+    }
+}
+
+// Added, removed and changed claims (including nested claims) and the change
+// in validity period are reported.
+#[test]
+fn reissued() {
+    let stored = credential(
+        &json!({
+            "given_name": "Normal",
+            "family_name": "Person",
+            "address": {"locality": "Wellington", "country": "NZ"}
+        }),
+        date(2025),
+    );
+    let reissued = Credential {
+        issuance_date: date(2025),
+        ..credential(
+            &json!({
+                "given_name": "Normal",
+                "email": "normal.user@example.com",
+                "address": {"locality": "Auckland", "country": "NZ"}
+            }),
+            date(2026),
+        )
+    };
+
+    let diff = stored.diff(&reissued);
+    assert!(!diff.is_empty());
+
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].path, ["email"]);
+    assert_eq!(diff.added[0].subject.as_deref(), Some("did:example:holder"));
+    assert_eq!(diff.added[0].after, Some(json!("normal.user@example.com")));
+
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].path, ["family_name"]);
+    assert_eq!(diff.removed[0].before, Some(json!("Person")));
+
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].path, ["address", "locality"]);
+    assert_eq!(diff.changed[0].before, Some(json!("Wellington")));
+    assert_eq!(diff.changed[0].after, Some(json!("Auckland")));
+
+    let validity = diff.validity.expect("should have validity change");
+    assert_eq!(
+        validity.before,
+        ValidityPeriod {
+            valid_from: date(2024),
+            valid_until: Some(date(2025)),
+        }
+    );
+    assert_eq!(validity.after.valid_until, Some(date(2026)));
+
+    // claim values are not printed
+    let debug = format!("{diff:?}");
+    assert!(debug.contains("locality"));
+    assert!(!debug.contains("Auckland"));
+}
+
+// An identical credential has no changes.
+#[test]
+fn unchanged() {
+    let stored = credential(&json!({"given_name": "Normal"}), date(2025));
+    assert!(stored.diff(&stored.clone()).is_empty());
+}
@@ -0,0 +1,129 @@
+//! Tests for previewing the disclosures of an SD-JWT credential released to a
+//! verifier.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::disclosure::{self, DisclosurePreview};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+fn encode(value: &Value) -> String {
+    Base64UrlUnpadded::encode_string(value.to_string().as_bytes())
+}
+
+// An encoded disclosure and its digest.
+fn disclosure(elements: &Value) -> (String, String) {
+    let encoded = encode(elements);
+    let digest = Base64UrlUnpadded::encode_string(&Sha256::digest(encoded.as_bytes()));
+    (encoded, digest)
+}
+
+fn credential(vct: &str) -> Credential {
+    let given_name = disclosure(&json!(["s1", "given_name", "Normal"]));
+    let family_name = disclosure(&json!(["s2", "family_name", "Person"]));
+    let street = disclosure(&json!(["s3", "street_address", "1 Main St"]));
+    let locality = disclosure(&json!(["s4", "locality", "Auckland"]));
+    let address = disclosure(&json!(["s5", "address", {"_sd": [street.1, locality.1]}]));
+    let nz = disclosure(&json!(["s6", "NZ"]));
+    let au = disclosure(&json!(["s7", "AU"]));
+
+    let payload = json!({
+        "iss": "https://issuer.example.com",
+        "vct": vct,
+        "_sd_alg": "sha-256",
+        "_sd": [given_name.1, family_name.1, address.1, "decoy"],
+        "nationalities": [{"...": nz.1}, {"...": au.1}]
+    });
+    let jwt = format!("{}.{}.signature", encode(&json!({"alg": "ES256"})), encode(&payload));
+    let disclosures = [given_name, family_name, address, street, locality, nz, au];
+    let issued = disclosures.iter().fold(format!("{jwt}~"), |sd_jwt, (d, _)| sd_jwt + d + "~");
+
+    Credential {
+        id: "urn:uuid:sd-jwt".into(),
+        issued,
+        format: "vc+sd-jwt".into(),
+        ..Credential::default()
+    }
+}
+
+fn flow(paths: &[&str]) -> PresentationFlow<NotAuthorized> {
+    let mut fields = vec![json!({
+        "path": ["$.vct"],
+        "filter": {"type": "string", "const": "EmployeeID"}
+    })];
+    fields.extend(paths.iter().map(|path| json!({"path": [path]})));
+    let request: RequestObject = serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "sd-jwt example",
+            "input_descriptors": [{"id": "EmployeeID", "constraints": {"fields": fields}}]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object");
+    PresentationFlow::<NotAuthorized>::new(request).expect("should start flow")
+}
+
+fn paths(disclosures: &[disclosure::Disclosure]) -> Vec<String> {
+    disclosures.iter().map(|d| d.path.join(".")).collect()
+}
+
+// Only the requested claims, and the claims containing them, are released.
+#[test]
+fn released() {
+    let credential = credential("EmployeeID");
+    let preview = flow(&["$.given_name", "$.address.locality", "$.nationalities[1]"])
+        .disclosure_preview(&credential)
+        .expect("should preview");
+
+    assert_eq!(
+        paths(&preview.released),
+        ["given_name", "address", "address.locality", "nationalities.1"]
+    );
+    assert_eq!(
+        paths(&preview.withheld),
+        ["family_name", "address.street_address", "nationalities.0"]
+    );
+    assert_eq!(preview.released[0].value, json!("Normal"));
+
+    let presented = preview.present(&credential.issued);
+    assert_eq!(presented.matches('~').count(), 5);
+    assert!(presented.starts_with(credential.issued.split('~').next().unwrap_or_default()));
+}
+
+// Requesting a claim releases the claims nested within it; requests for
+// another credential type release nothing.
+#[test]
+fn nested() {
+    let employee = credential("EmployeeID");
+    let preview = flow(&["$.credentialSubject.address", "$.nationalities[*]"])
+        .disclosure_preview(&employee)
+        .expect("should preview");
+    assert_eq!(paths(&preview.withheld), ["given_name", "family_name"]);
+
+    let other = credential("Other");
+    let preview = flow(&["$.given_name"]).disclosure_preview(&other).expect("should preview");
+    assert_eq!(preview.released.len(), 0);
+    assert_eq!(preview.withheld.len(), 7);
+}
+
+// Credentials that are not SD-JWTs cannot be previewed.
+#[test]
+fn not_sd_jwt() {
+    let credential = Credential {
+        issued: "eyJhbGciOiJFUzI1NiJ9.e30.signature".into(),
+        ..Credential::default()
+    };
+    assert!(!disclosure::is_sd_jwt(&credential.issued));
+    assert!(flow(&[]).disclosure_preview(&credential).is_err());
+    assert!(DisclosurePreview::default().present(&credential.issued).ends_with('~'));
+}
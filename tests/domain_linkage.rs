@@ -0,0 +1,209 @@
+//! Tests for confirming a verifier's or issuer's DID controls the origin it is
+//! reached at.
+
+// Provider trait methods are async by contract even where the store is not.
+#![allow(clippy::unused_async_trait_impl)]
+
+use chrono::Utc;
+use credibil_holder::agent::{Flow, HolderAgent};
+use credibil_holder::error::DomainNotLinked;
+use credibil_holder::issuance::{CredentialSubject, VerifiableCredential};
+use credibil_holder::linkage::{self, DID_CONFIGURATION_PATH, IssuerTrust};
+use credibil_holder::presentation::proof::{self, Payload, W3cFormat};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use credibil_holder::provider::{DidConfigurationResolver, DidResolver, Document, Signer};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+use credibil_holder::{Kind, Quota};
+use serde_json::{Map, Value, json};
+
+const ORIGIN: &str = "https://verifier.example.com";
+
+// Serves a DID configuration for `ORIGIN`.
+#[derive(Clone)]
+struct Configuration {
+    provider: MockProvider,
+    linked_dids: Vec<String>,
+}
+
+impl DidConfigurationResolver for Configuration {
+    async fn did_configuration(&self, url: &str) -> anyhow::Result<String> {
+        if url != format!("{ORIGIN}{DID_CONFIGURATION_PATH}") {
+            anyhow::bail!("no DID configuration at {url}");
+        }
+        let config = json!({
+            "@context": "https://identity.foundation/.well-known/did-configuration/v1",
+            "linked_dids": self.linked_dids,
+        });
+        Ok(config.to_string())
+    }
+}
+
+impl DidResolver for Configuration {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        self.provider.resolve(url).await
+    }
+}
+
+// The DID of the provider's signing key.
+async fn did(provider: &MockProvider) -> String {
+    let kid = provider.verification_method().await.expect("should get verification method");
+    kid.split('#').next().unwrap_or_default().to_string()
+}
+
+// A credential of the given type, signed by the provider's DID.
+async fn sign(provider: &MockProvider, type_: &str, claims: Map<String, Value>) -> String {
+    let did = did(provider).await;
+    let vc = VerifiableCredential {
+        context: vec![
+            Kind::String("https://www.w3.org/2018/credentials/v1".into()),
+            Kind::String("https://identity.foundation/.well-known/did-configuration/v1".into()),
+        ],
+        type_: Quota::Many(vec!["VerifiableCredential".into(), type_.into()]),
+        issuer: Kind::String(did.clone()),
+        valid_from: Some(Utc::now()),
+        credential_subject: Quota::One(CredentialSubject {
+            id: Some(did),
+            claims,
+        }),
+        ..VerifiableCredential::default()
+    };
+    let payload = Payload::Vc {
+        vc,
+        issued_at: Utc::now().timestamp(),
+    };
+    proof::create(W3cFormat::JwtVcJson, payload, provider).await.expect("should sign credential")
+}
+
+// A domain linkage credential for the origin, signed by the provider's DID.
+async fn linkage_credential(provider: &MockProvider, origin: &str) -> String {
+    let mut claims = Map::new();
+    claims.insert("origin".into(), origin.into());
+    sign(provider, "DomainLinkageCredential", claims).await
+}
+
+async fn configuration(origin: &str) -> Configuration {
+    let provider = MockProvider::new();
+    Configuration {
+        linked_dids: vec![linkage_credential(&provider, origin).await],
+        provider,
+    }
+}
+
+fn request_object(client_id: &str) -> RequestObject {
+    serde_json::from_value(json!({
+        "client_id": client_id,
+        "client_id_scheme": "did",
+        "client_metadata": {
+            "client_id": client_id,
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256K", "EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": "EmployeeIDCredential"}
+                    }]
+                }
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": format!("{ORIGIN}/post")
+    }))
+    .expect("should parse request object")
+}
+
+// A DID with a domain linkage credential for the origin controls it.
+#[tokio::test]
+async fn linked() {
+    let config = configuration(ORIGIN).await;
+    let did = did(&config.provider).await;
+
+    linkage::verify_domain_linkage(&did, &format!("{ORIGIN}/request/1234"), config.clone())
+        .await
+        .expect("should be linked");
+
+    let flow =
+        PresentationFlow::<NotAuthorized>::new(request_object(&did)).expect("should start flow");
+    flow.verify_domain_linkage(&format!("{ORIGIN}/post"), config).await.expect("should be linked");
+}
+
+// A credential for another origin or issued by another DID does not link the
+// origin.
+#[tokio::test]
+async fn not_linked() {
+    let config = configuration("https://other.example.com").await;
+    let did = did(&config.provider).await;
+    let err = linkage::verify_domain_linkage(&did, ORIGIN, config)
+        .await
+        .expect_err("should not be linked");
+    let err = err.downcast_ref::<DomainNotLinked>().expect("should be a linkage error");
+    assert_eq!(err.origin, ORIGIN);
+
+    let config = configuration(ORIGIN).await;
+    let err = linkage::verify_domain_linkage("did:example:other", ORIGIN, config)
+        .await
+        .expect_err("should not be linked");
+    assert!(err.is::<DomainNotLinked>());
+}
+
+// Only https origins can be linked, and verifiers not identified by a DID are
+// not checked.
+#[tokio::test]
+async fn origins() {
+    assert_eq!(
+        linkage::origin("HTTPS://Verifier.Example.com:8443/post?x=1").expect("should parse"),
+        "https://verifier.example.com:8443"
+    );
+    assert!(linkage::origin("http://verifier.example.com").is_err());
+    assert!(linkage::origin("https://user@verifier.example.com").is_err());
+
+    let config = configuration(ORIGIN).await;
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object(&format!("{ORIGIN}/post")))
+        .expect("should start flow");
+    flow.verify_domain_linkage("https://elsewhere.example.com", config)
+        .await
+        .expect("should not check verifier");
+}
+
+// An issuer whose DID is linked to the credential issuer's origin is trusted.
+#[tokio::test]
+async fn issuer_linked() {
+    let config = configuration(ORIGIN).await;
+    let did = did(&config.provider).await;
+    let issued = sign(&config.provider, "EmployeeIDCredential", Map::new()).await;
+
+    let trust = linkage::issuer_trust(ORIGIN, &issued, config.clone()).await;
+    assert_eq!(trust, IssuerTrust::DomainLinked { did: did.clone() });
+
+    let trust = linkage::issuer_trust("https://other.example.com", &issued, config).await;
+    assert!(matches!(trust, IssuerTrust::Unverified { .. }));
+
+    let config = configuration("https://other.example.com").await;
+    let trust = linkage::issuer_trust(ORIGIN, &issued, config).await;
+    assert_eq!(trust, IssuerTrust::NotLinked { did });
+}
+
+// The agent records the verdict on the flow. The mock issuer is not at an
+// https origin so the linkage cannot be checked.
+#[tokio::test]
+async fn agent_verdict() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+
+    let trust = agent.verify_issuer(&id).await.expect("should check issuer");
+    assert!(matches!(trust, IssuerTrust::Unverified { .. }));
+    let Some(Flow::Issued(flow)) = agent.flow(&id).as_deref().cloned() else {
+        panic!("expected issued flow");
+    };
+    assert_eq!(flow.issuer_trust(), Some(&trust));
+}
@@ -0,0 +1,115 @@
+//! Tests for recovering typed OAuth errors returned by issuers.
+mod provider;
+
+use std::time::Duration;
+
+use credibil_holder::error::{ErrorCode, OAuthError, Recovery, RetryLater};
+use credibil_holder::issuance::{IssuanceFlowBuilder, OfferType, SendType};
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+
+use crate::provider as holder;
+
+// A wrong PIN results in an `invalid_grant` error the wallet can recover from
+// by asking the holder to re-enter their PIN.
+#[tokio::test]
+async fn wrong_pin() {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let issuer_provider = issuer::Provider::new();
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+
+    let provider = holder::Provider::new(Some(issuer_provider), None);
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant);
+    let state = state.accept(&None, Some("wrong".into()));
+
+    let err = provider.token(state.token_request()).await.expect_err("should reject PIN");
+    let oauth = OAuthError::from_error(&err).expect("should be an OAuth error");
+    assert_eq!(oauth.code, ErrorCode::InvalidGrant);
+    assert_eq!(oauth.recovery(true), Some(Recovery::ReenterPin));
+    assert_eq!(oauth.recovery(false), None);
+}
+
+// Error response bodies are parsed into typed errors carrying any fresh nonce
+// or retry interval.
+#[test]
+fn parse_body() {
+    let body = br#"{"error": "invalid_proof", "error_description": "stale nonce", "c_nonce": "abc", "c_nonce_expires_in": 60}"#;
+    let oauth = OAuthError::parse(body).expect("should parse");
+    assert_eq!(oauth.code, ErrorCode::InvalidProof);
+    assert_eq!(oauth.to_string(), "invalid_proof: stale nonce");
+    assert_eq!(
+        oauth.recovery(false),
+        Some(Recovery::RegenerateProof {
+            c_nonce: "abc".into()
+        })
+    );
+
+    let body = br#"{"error": "access_denied", "c_nonce": "def"}"#;
+    let oauth = OAuthError::parse(body).expect("should parse");
+    assert_eq!(oauth.code, ErrorCode::AccessDenied);
+    assert_eq!(oauth.recovery(false), None);
+
+    let body = br#"{"error": "invalid_nonce", "c_nonce": "ghi"}"#;
+    let oauth = OAuthError::parse(body).expect("should parse");
+    assert_eq!(
+        oauth.recovery(false),
+        Some(Recovery::RegenerateProof {
+            c_nonce: "ghi".into()
+        })
+    );
+
+    let oauth = OAuthError::parse(br#"{"error": "issuance_pending"}"#).expect("should parse");
+    assert_eq!(oauth.recovery(false), Some(Recovery::Retry { interval: Some(5) }));
+
+    // Errors can be carried by `anyhow` and recovered.
+    let err = anyhow::Error::new(oauth.clone()).context("credential request failed");
+    assert_eq!(OAuthError::from_error(&err), Some(oauth));
+    assert!(OAuthError::parse(b"<html>Bad Gateway</html>").is_none());
+}
+
+// Rate limiting responses and retry intervals are surfaced as a typed delay.
+#[test]
+fn retry_later() {
+    let retry = RetryLater::from_response(429, Some("120")).expect("should retry later");
+    assert_eq!(retry.delay(), Duration::from_secs(120));
+    assert_eq!(RetryLater::from_response(429, None), Some(RetryLater(Duration::from_secs(5))));
+
+    let retry = RetryLater::from_response(503, Some("Wed, 21 Oct 2015 07:28:00 GMT"))
+        .expect("should retry later");
+    assert_eq!(retry.delay(), Duration::ZERO);
+    assert!(RetryLater::from_response(503, None).is_none());
+    assert!(RetryLater::from_response(400, Some("120")).is_none());
+
+    let err = anyhow::Error::new(retry).context("token request failed");
+    assert_eq!(RetryLater::from_error(&err), Some(retry));
+
+    let body = br#"{"error": "issuance_pending", "interval": 30}"#;
+    let oauth = OAuthError::parse(body).expect("should parse");
+    let err = anyhow::Error::new(oauth).context("deferred credential request failed");
+    assert_eq!(RetryLater::from_error(&err), Some(RetryLater(Duration::from_secs(30))));
+
+    let err = anyhow::anyhow!("connection refused");
+    assert!(RetryLater::from_error(&err).is_none());
+}
@@ -0,0 +1,303 @@
+//! Property-based tests for the issuance and presentation flow state
+//! machines.
+//!
+//! Transitions are enforced at compile time by the typestate pattern, so these
+//! tests exercise the remaining runtime behaviour: arbitrary holder input and
+//! server responses must never panic, must never widen what the holder
+//! accepted or was authorized for, and persisted flow state must not be
+//! resumable in a state it has not reached.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use credibil_holder::credential::Credential;
+use credibil_holder::issuance::{
+    Accepted, AuthorizationSpec, CredentialOffer, IssuanceFlow, IssuanceFlowBuilder, Issuer,
+    NotAccepted, PreAuthorized, TokenResponse, WithOffer, WithToken, WithoutToken,
+};
+use credibil_holder::presentation::{Authorized, NotAuthorized, PresentationFlow, RequestObject};
+use credibil_holder::secret::Secret;
+use proptest::prelude::*;
+use serde_json::json;
+
+type Offered = IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken>;
+
+const SUPPORTED: &str = "UniversityDegreeCredential";
+
+fn issuer() -> Issuer {
+    serde_json::from_str(include_str!("conformance/fixtures/issuer_metadata.json"))
+        .expect("should parse metadata")
+}
+
+fn offered_flow(cfg_ids: &[String]) -> Offered {
+    let offer: CredentialOffer = serde_json::from_value(json!({
+        "credential_issuer": "https://credential-issuer.example.com",
+        "credential_configuration_ids": cfg_ids,
+        "grants": {
+            "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                "pre-authorized_code": "adhjhdjajkdkhjhdj"
+            }
+        }
+    }))
+    .expect("should parse offer");
+    let grant = offer.pre_authorized_code().expect("should have pre-authorized code grant");
+    IssuanceFlowBuilder::new("s6BhdRkqt3", issuer())
+        .subject_id("holder")
+        .pre_authorized(offer, grant)
+}
+
+fn token(authorized: &[(String, Vec<String>)]) -> TokenResponse {
+    let details = authorized
+        .iter()
+        .map(|(cfg_id, identifiers)| {
+            json!({
+                "type": "openid_credential",
+                "credential_configuration_id": cfg_id,
+                "credential_identifiers": identifiers,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::from_value(json!({
+        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+        "token_type": "Bearer",
+        "expires_in": 86400,
+        "c_nonce": "tZignsnFbp",
+        "c_nonce_expires_in": 86400,
+        "authorization_details": details,
+    }))
+    .expect("should parse token response")
+}
+
+fn request_object(descriptor_ids: &[String]) -> RequestObject {
+    let descriptors = descriptor_ids
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": "EmployeeIDCredential"}
+                    }]
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": descriptors
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+// Configuration IDs are either the one supported by the issuer or arbitrary
+// (unsupported) values.
+fn cfg_id() -> impl Strategy<Value = String> {
+    prop_oneof![Just(SUPPORTED.to_string()), "[A-Za-z]{1,12}"]
+}
+
+fn identifier() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9-]{1,16}"
+}
+
+// Events that can be applied to a flow's deferred transactions.
+#[derive(Clone, Debug)]
+enum Deferred {
+    Add(String, String),
+    Remove(String),
+}
+
+fn deferred_event() -> impl Strategy<Value = Deferred> {
+    prop_oneof![
+        ("[a-d]", cfg_id()).prop_map(|(tx_id, cfg_id)| Deferred::Add(tx_id, cfg_id)),
+        "[a-d]".prop_map(Deferred::Remove),
+    ]
+}
+
+proptest! {
+    // Accepting an offer only ever authorizes configurations that were both
+    // offered and supported by the issuer and, when the holder narrows the
+    // offer, were accepted by the holder.
+    #[test]
+    fn accept_never_widens_offer(
+        offered in prop::collection::vec(cfg_id(), 0..4),
+        accepted in prop::option::of(prop::collection::vec(cfg_id(), 0..4)),
+        pin in prop::option::of("[0-9]{4,6}"),
+    ) {
+        let specs = accepted.as_ref().map(|ids| {
+            ids.iter()
+                .map(|id| AuthorizationSpec {
+                    credential_configuration_id: id.clone(),
+                    claims: None,
+                })
+                .collect::<Vec<_>>()
+        });
+        let flow = offered_flow(&offered).accept(&specs, pin.clone());
+        prop_assert_eq!(flow.pin().map(Secret::expose), pin.as_deref());
+
+        let request = serde_json::to_value(flow.token_request()).expect("should serialize");
+        let details = request["authorization_details"].as_array().cloned().unwrap_or_default();
+        for detail in details {
+            let id = detail["credential_configuration_id"].as_str().expect("should have id");
+            prop_assert_eq!(id, SUPPORTED);
+            prop_assert!(offered.iter().any(|o| o == id));
+            if let Some(accepted) = &accepted {
+                prop_assert!(accepted.iter().any(|a| a == id));
+            }
+        }
+    }
+
+    // Credential requests are only created for identifiers the holder asked
+    // for, that the token authorized, and whose configuration the issuer
+    // supports.
+    #[test]
+    fn credential_requests_are_authorized(
+        authorized in prop::collection::vec(
+            (cfg_id(), prop::collection::vec(identifier(), 0..4)), 0..4
+        ),
+        requested in prop::collection::vec(identifier(), 0..6),
+    ) {
+        let flow = offered_flow(&[SUPPORTED.to_string()])
+            .accept(&None, None)
+            .token(token(&authorized));
+
+        let permitted = authorized
+            .iter()
+            .filter(|(cfg_id, _)| cfg_id == SUPPORTED)
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .collect::<HashSet<_>>();
+        for (cfg_id, request) in flow.credential_requests(&requested, "proof.jwt") {
+            prop_assert_eq!(cfg_id.as_str(), SUPPORTED);
+            let request = serde_json::to_value(request).expect("should serialize");
+            let id = request["credential_identifier"].as_str().expect("should have identifier");
+            prop_assert!(requested.iter().any(|r| r == id));
+            prop_assert!(permitted.contains(id));
+        }
+    }
+
+    // Any sequence of deferred transaction updates leaves the flow tracking
+    // exactly the outstanding transactions.
+    #[test]
+    fn deferred_transactions_are_tracked(
+        events in prop::collection::vec(deferred_event(), 0..16),
+    ) {
+        let mut flow = offered_flow(&[SUPPORTED.to_string()])
+            .accept(&None, None)
+            .token(token(&[]));
+        let mut model = HashMap::new();
+        for event in events {
+            match event {
+                Deferred::Add(tx_id, cfg_id) => {
+                    flow.add_deferred(&tx_id, &cfg_id);
+                    model.insert(tx_id, cfg_id);
+                }
+                Deferred::Remove(tx_id) => {
+                    flow.remove_deferred(&tx_id);
+                    model.remove(&tx_id);
+                }
+            }
+        }
+        prop_assert_eq!(flow.deferred(), model);
+    }
+
+    // Persisted issuance state can only be resumed in the state it was saved
+    // in.
+    #[test]
+    fn issuance_state_cannot_skip_ahead(
+        offered in prop::collection::vec(cfg_id(), 0..4),
+        pin in prop::option::of("[0-9]{4,6}"),
+    ) {
+        let flow = offered_flow(&offered);
+        let saved = serde_json::to_value(&flow).expect("should serialize");
+        prop_assert!(serde_json::from_value::<Offered>(saved.clone()).is_ok());
+        prop_assert!(serde_json::from_value::<
+            IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>,
+        >(saved.clone()).is_err());
+        prop_assert!(serde_json::from_value::<
+            IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithToken>,
+        >(saved).is_err());
+
+        let flow = flow.accept(&None, pin);
+        let saved = serde_json::to_value(&flow).expect("should serialize");
+        prop_assert!(serde_json::from_value::<Offered>(saved.clone()).is_err());
+        prop_assert!(serde_json::from_value::<
+            IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>,
+        >(saved.clone()).is_err());
+        let resumed = serde_json::from_value::<
+            IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>,
+        >(saved)
+        .expect("should resume accepted flow");
+        prop_assert_eq!(resumed.id(), flow.id());
+        prop_assert_eq!(resumed.pin(), flow.pin());
+    }
+
+    // A presentation flow can be started from any presentation definition
+    // object, submits one descriptor per input descriptor and presents
+    // exactly the credentials the holder authorized.
+    #[test]
+    fn presentation_flow(
+        descriptor_ids in prop::collection::vec(identifier(), 0..4),
+        credential_ids in prop::collection::vec(identifier(), 0..4),
+    ) {
+        let flow = PresentationFlow::<NotAuthorized>::new(request_object(&descriptor_ids))
+            .expect("should start flow");
+        prop_assert_eq!(flow.filter().is_ok(), !descriptor_ids.is_empty());
+
+        let saved = serde_json::to_value(&flow).expect("should serialize");
+        prop_assert!(serde_json::from_value::<PresentationFlow<Authorized>>(saved).is_err());
+
+        let credentials = credential_ids
+            .iter()
+            .map(|id| Credential {
+                id: id.clone(),
+                ..Credential::default()
+            })
+            .collect::<Vec<_>>();
+        let flow = flow.authorize(&credentials);
+        let presented = flow.credentials().into_iter().map(|c| c.id).collect::<Vec<_>>();
+        prop_assert_eq!(presented, credential_ids);
+
+        let saved = serde_json::to_value(&flow).expect("should serialize");
+        prop_assert!(serde_json::from_value::<PresentationFlow<NotAuthorized>>(saved).is_err());
+
+        let (request, _) = flow.create_response_request("vp.jwt");
+        let request = serde_json::to_value(request).expect("should serialize");
+        let submitted = request["presentation_submission"]["descriptor_map"]
+            .as_array()
+            .map(|map| {
+                map.iter()
+                    .map(|dm| dm["id"].as_str().unwrap_or_default().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        prop_assert_eq!(submitted, descriptor_ids);
+    }
+}
+
+// Issuer metadata and the offer are shared by flow clones and transitions
+// rather than copied.
+#[test]
+fn shared_state() {
+    let flow = offered_flow(&[SUPPORTED.to_string()]);
+    let issuer = flow.issuer();
+    let offer = flow.offer();
+
+    let copy = flow.clone();
+    assert!(Arc::ptr_eq(&copy.issuer(), &issuer));
+
+    let flow = flow.accept(&None, None).token(token(&[]));
+    assert!(Arc::ptr_eq(&flow.issuer(), &issuer));
+    assert!(Arc::ptr_eq(&flow.offer(), &offer));
+}
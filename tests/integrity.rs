@@ -0,0 +1,111 @@
+//! Tests for detecting tampering with stored credential records.
+mod provider;
+
+use std::collections::HashMap;
+
+use credibil_holder::credential::{Credential, SubjectClaims};
+use credibil_holder::error::RecordTampered;
+use credibil_holder::integrity::Protected;
+use credibil_holder::provider::{Constraints, CredentialStorer};
+use serde_json::json;
+
+use crate::provider as holder;
+
+fn credential() -> Credential {
+    Credential {
+        id: "urn:uuid:1234".into(),
+        subject_claims: vec![SubjectClaims {
+            id: Some("did:example:holder".into()),
+            claims: json!({"given_name": "Normal", "role": "employee"})
+                .as_object()
+                .cloned()
+                .expect("should be an object"),
+        }],
+        ..Credential::default()
+    }
+}
+
+// Records saved through the protected store load unchanged, with their
+// integrity tag set.
+#[tokio::test]
+async fn round_trip() {
+    let provider = holder::Provider::new(None, None);
+    let store = Protected::new(provider.clone(), provider.clone());
+    store.save(&credential()).await.expect("should save");
+
+    let loaded = store.load("urn:uuid:1234").await.expect("should load").expect("should exist");
+    assert!(loaded.integrity.is_some());
+    assert_eq!(loaded.subject_claims, credential().subject_claims);
+    assert_eq!(store.find(None).await.expect("should find").len(), 1);
+    assert!(store.load("urn:uuid:unknown").await.expect("should load").is_none());
+}
+
+// Records modified in storage, or saved without a tag, fail their integrity
+// check.
+#[tokio::test]
+async fn tampered() {
+    let provider = holder::Provider::new(None, None);
+    let store = Protected::new(provider.clone(), provider.clone());
+    store.save(&credential()).await.expect("should save");
+
+    let mut record =
+        provider.load("urn:uuid:1234").await.expect("should load").expect("should exist");
+    record.subject_claims[0].claims.insert("role".into(), json!("administrator"));
+    provider.save(&record).await.expect("should save");
+
+    let err = store.load("urn:uuid:1234").await.expect_err("should detect tampering");
+    let tampered = err.downcast_ref::<RecordTampered>().expect("should be RecordTampered");
+    assert_eq!(tampered.id, "urn:uuid:1234");
+    assert!(store.find(None).await.is_err());
+
+    provider.save(&credential()).await.expect("should save");
+    assert!(store.load("urn:uuid:1234").await.is_err());
+}
+
+// A sealed record returned by the store in place of another is rejected.
+#[tokio::test]
+async fn swapped() {
+    let provider = holder::Provider::new(None, None);
+    let sealer = Protected::new(provider.clone(), provider.clone());
+    let other = Credential {
+        id: "urn:uuid:5678".into(),
+        ..credential()
+    };
+    sealer.save(&credential()).await.expect("should save");
+    sealer.save(&other).await.expect("should save");
+
+    let swapped = Swapped {
+        inner: provider.clone(),
+        ids: HashMap::from([("urn:uuid:1234".into(), "urn:uuid:5678".into())]),
+    };
+    let store = Protected::new(swapped, provider.clone());
+    let err = store.load("urn:uuid:1234").await.expect_err("should detect swapped record");
+    let tampered = err.downcast_ref::<RecordTampered>().expect("should be RecordTampered");
+    assert_eq!(tampered.id, "urn:uuid:1234");
+    assert!(store.load("urn:uuid:5678").await.expect("should load").is_some());
+}
+
+// A store returning the record saved under another ID when loading the IDs
+// in `ids`.
+struct Swapped {
+    inner: holder::Provider,
+    ids: HashMap<String, String>,
+}
+
+impl CredentialStorer for Swapped {
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        self.inner.save(credential).await
+    }
+
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        self.inner.load(self.ids.get(id).map_or(id, String::as_str)).await
+    }
+
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        self.inner.find(filter).await
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.inner.remove(id).await
+    }
+}
@@ -2,12 +2,8 @@
 //! authorization.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
-use credibil_holder::issuance::{
-    AuthCode, CredentialResponseType, IssuanceFlow, NotAccepted, OfferType, SendType, WithOffer,
-    WithoutToken,
-};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
+use credibil_holder::issuance::{CredentialResponseType, IssuanceFlowBuilder, OfferType, SendType};
 use credibil_holder::provider::{Issuer, MetadataRequest, OAuthServerRequest};
 use credibil_holder::test_utils::issuer::{
     self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER, REDIRECT_URI,
@@ -70,13 +66,9 @@ async fn issuer_auth() {
     //--------------------------------------------------------------------------
     assert!(offer.pre_authorized_code().is_none());
 
-    let state = IssuanceFlow::<WithOffer, AuthCode, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer,
-        auth_metadata.authorization_server,
-        offer,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .offer(offer, auth_metadata.authorization_server);
     let offered = state.offered();
 
     //--------------------------------------------------------------------------
@@ -125,15 +117,7 @@ async fn issuer_auth() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode jws");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
@@ -0,0 +1,61 @@
+//! Tests for issuer display information stored with credentials.
+
+use credibil_holder::credential::{Credential, IssuerDisplay, IssuerLogo};
+use serde_json::json;
+
+fn display(name: &str, locale: &str) -> IssuerDisplay {
+    IssuerDisplay {
+        name: name.into(),
+        locale: Some(locale.into()),
+        ..IssuerDisplay::default()
+    }
+}
+
+// The entry for the preferred locale is used, falling back to the first.
+#[test]
+fn preferred_locale() {
+    let credential = Credential {
+        issuer_display: vec![display("Credibil", "en-NZ"), display("Credibil Ltée", "fr-CA")],
+        ..Credential::default()
+    };
+
+    let fr = credential.issuer_display_for(Some("fr-CA")).expect("should have display");
+    assert_eq!(fr.name, "Credibil Ltée");
+    let fallback = credential.issuer_display_for(Some("de-DE")).expect("should have display");
+    assert_eq!(fallback.name, "Credibil");
+    assert!(Credential::default().issuer_display_for(None).is_none());
+}
+
+// Issuer display information is stored with the credential, and records
+// stored without it still load.
+#[test]
+fn stored_display() {
+    let credential = Credential {
+        issuer_display: vec![IssuerDisplay {
+            logo: Some(IssuerLogo {
+                uri: "https://credibil.io/logo.png".into(),
+                alt_text: Some("Credibil logo".into()),
+            }),
+            background_color: Some("#323ed2".into()),
+            text_color: Some("#ffffff".into()),
+            ..display("Credibil", "en-NZ")
+        }],
+        ..Credential::default()
+    };
+    let json = serde_json::to_value(&credential).expect("should serialize");
+    assert_eq!(
+        json["issuer_display"],
+        json!([{
+            "name": "Credibil",
+            "locale": "en-NZ",
+            "logo": {"uri": "https://credibil.io/logo.png", "alt_text": "Credibil logo"},
+            "background_color": "#323ed2",
+            "text_color": "#ffffff"
+        }])
+    );
+
+    let mut record = json;
+    record.as_object_mut().expect("should be an object").remove("issuer_display");
+    let restored: Credential = serde_json::from_value(record).expect("should deserialize");
+    assert_eq!(restored.issuer_display.len(), 0);
+}
@@ -0,0 +1,109 @@
+//! Tests for accepting legacy JWT-VCs whose properties are carried in
+//! registered JWT claims.
+
+use credibil_holder::credential::{DataModel, Validity};
+use credibil_holder::infosec::jose::jws::JwsBuilder;
+use credibil_holder::issuance::proof::Payload;
+use credibil_holder::provider::Signer;
+use credibil_holder::test_utils::date;
+use credibil_holder::test_utils::mock::MockProvider;
+use credibil_holder::{Kind, Quota, jwt_vc};
+use serde_json::{Value, json};
+
+const ID: &str = "urn:uuid:6a6d6e2a-4a44-4c42-8a24-3c4b6a0f4f2e";
+const SUBJECT: &str = "did:example:holder";
+
+// Sign a JWT with the given claims using the provider's key.
+async fn sign(provider: &MockProvider, claims: &Value) -> String {
+    let jws = JwsBuilder::new()
+        .jwt_type("JWT")
+        .payload(claims)
+        .add_signer(provider)
+        .build()
+        .await
+        .expect("should sign");
+    jws.encode().expect("should encode")
+}
+
+// Legacy claims: the issuer, ID, subject and validity period are carried only
+// in registered claims.
+async fn legacy_claims(provider: &MockProvider) -> Value {
+    let kid = provider.verification_method().await.expect("should get verification method");
+    let did = kid.split('#').next().unwrap_or_default();
+    json!({
+        "iss": did,
+        "sub": SUBJECT,
+        "jti": ID,
+        "nbf": date(2024).timestamp(),
+        "exp": date(2030).timestamp(),
+        "vc": {
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential", "EmployeeIDCredential"],
+            "credentialSubject": {"given_name": "Normal"},
+        }
+    })
+}
+
+// A legacy JWT-VC is verified and its registered claims mapped to the
+// credential.
+#[tokio::test]
+async fn legacy_credential() {
+    let provider = MockProvider::new();
+    let claims = legacy_claims(&provider).await;
+    let token = sign(&provider, &claims).await;
+
+    let Payload::Vc { vc, issued_at } = jwt_vc::verify(&Kind::String(token.clone()), provider)
+        .await
+        .expect("should verify legacy credential")
+    else {
+        panic!("expected a verifiable credential");
+    };
+    assert_eq!(issued_at, date(2024).timestamp());
+    assert_eq!(vc.id.as_deref(), Some(ID));
+    assert_eq!(serde_json::to_value(&vc.issuer).expect("should serialize"), claims["iss"]);
+    let Quota::One(subject) = vc.credential_subject else {
+        panic!("expected a single subject");
+    };
+    assert_eq!(subject.id.as_deref(), Some(SUBJECT));
+    assert_eq!(subject.claims["given_name"], "Normal");
+
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V1_1);
+    assert_eq!(validity.valid_from, Some(date(2024)));
+    assert_eq!(validity.valid_until, Some(date(2030)));
+}
+
+// Properties in the credential take precedence over registered claims.
+#[test]
+fn credential_precedence() {
+    let claims = json!({
+        "iss": "did:example:other",
+        "nbf": date(2024).timestamp(),
+        "vc": {
+            "issuer": "did:example:issuer",
+            "credentialSubject": {"id": SUBJECT},
+        }
+    });
+    let vc = jwt_vc::credential(&claims).expect("should normalize");
+    assert_eq!(vc["issuer"], "did:example:issuer");
+    assert_eq!(vc["issuanceDate"], "2024-01-01T00:00:00Z");
+    assert_eq!(vc["@context"], json!(["https://www.w3.org/2018/credentials/v1"]));
+}
+
+// A legacy JWT-VC with a signature that does not match its claims is
+// rejected.
+#[tokio::test]
+async fn legacy_tampered() {
+    let provider = MockProvider::new();
+    let claims = legacy_claims(&provider).await;
+    let token = sign(&provider, &claims).await;
+
+    let mut tampered = claims;
+    tampered["sub"] = json!("did:example:attacker");
+    let forged = sign(&provider, &tampered).await;
+    let mut parts: Vec<&str> = token.split('.').collect();
+    parts[1] = forged.split('.').nth(1).unwrap_or_default();
+    let token = parts.join(".");
+
+    assert!(jwt_vc::verify(&Kind::String(token), provider).await.is_err());
+}
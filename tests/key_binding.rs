@@ -0,0 +1,101 @@
+//! Tests for presenting credentials bound to different holder keys.
+
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::proof::Payload;
+use credibil_holder::presentation::{Authorized, NotAuthorized, PresentationFlow, RequestObject};
+use serde_json::json;
+
+fn request_object() -> RequestObject {
+    let descriptor = |id: &str, type_: &str| {
+        json!({
+            "id": id,
+            "constraints": {
+                "fields": [{
+                    "path": ["$.type"],
+                    "filter": {"type": "string", "const": type_}
+                }]
+            }
+        })
+    };
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256K", "EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [
+                descriptor("EmployeeID_JWT", "EmployeeIDCredential"),
+                descriptor("Developer_JWT", "DeveloperCredential"),
+            ]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+fn credential(type_: &str, key_id: Option<&str>) -> Credential {
+    Credential {
+        id: format!("urn:example:{type_}"),
+        type_: vec!["VerifiableCredential".into(), type_.into()],
+        issued: format!("{type_}.jwt"),
+        key_id: key_id.map(String::from),
+        ..Credential::default()
+    }
+}
+
+fn authorized(credentials: &[Credential]) -> PresentationFlow<Authorized> {
+    PresentationFlow::<NotAuthorized>::new(request_object())
+        .expect("should start flow")
+        .authorize(credentials)
+}
+
+// Credentials bound to the same key are presented together.
+#[test]
+fn single_key() {
+    let flow = authorized(&[
+        credential("EmployeeIDCredential", Some("key-1")),
+        credential("DeveloperCredential", Some("key-1")),
+    ]);
+    assert_eq!(flow.holder_keys(), vec![Some("key-1".to_string())]);
+
+    let (request, _) =
+        flow.create_keyed_response_request(&["vp.jwt".into()]).expect("should create request");
+    let json = serde_json::to_value(&request).expect("should serialize");
+    assert_eq!(json["presentation_submission"]["descriptor_map"][1]["path"], "$");
+}
+
+// Each key's credentials are presented in a separate presentation and the
+// submission maps descriptors to the right presentation.
+#[test]
+fn mixed_keys() {
+    let flow = authorized(&[
+        credential("EmployeeIDCredential", Some("key-1")),
+        credential("DeveloperCredential", None),
+    ]);
+    assert_eq!(flow.holder_keys(), vec![Some("key-1".to_string()), None]);
+
+    let Payload::Vp { vp, .. } =
+        flow.key_payload(None, "did:example:holder#key-0").expect("should build payload")
+    else {
+        panic!("expected presentation payload");
+    };
+    let vp = serde_json::to_value(&vp).expect("should serialize");
+    assert_eq!(vp["verifiableCredential"], json!(["DeveloperCredential.jwt"]));
+    assert!(flow.key_payload(Some("key-2"), "did:example:holder#key-2").is_err());
+
+    assert!(flow.create_keyed_response_request(&["vp.jwt".into()]).is_err());
+    let (request, _) = flow
+        .create_keyed_response_request(&["vp1.jwt".into(), "vp2.jwt".into()])
+        .expect("should create request");
+    let json = serde_json::to_value(&request).expect("should serialize");
+    let descriptor_map = &json["presentation_submission"]["descriptor_map"];
+    assert_eq!(descriptor_map[0]["path"], "$[0]");
+    assert_eq!(descriptor_map[1]["path"], "$[1]");
+    assert_eq!(descriptor_map[1]["path_nested"]["path"], "$.verifiableCredential[0]");
+}
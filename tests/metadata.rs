@@ -0,0 +1,47 @@
+//! Tests for generating wallet metadata.
+
+use credibil_holder::metadata::WalletMetadata;
+use credibil_holder::provider::Signer;
+use credibil_holder::test_utils::mock::MockProvider;
+use serde_json::{Value, json};
+
+// Metadata defaults describe a `jwt_vc_json` wallet and can be extended.
+#[test]
+fn wallet_metadata() {
+    let algorithm = MockProvider::new().algorithm();
+    let alg = serde_json::to_value(&algorithm).expect("should serialize algorithm");
+
+    let metadata = WalletMetadata::new(algorithm.clone())
+        .client_name("Credibil Wallet")
+        .redirect_uri("https://wallet.example.com/callback")
+        .format("ldp_vc", vec![algorithm])
+        .binding_method("jwk")
+        .binding_method("did:key")
+        .response_type("id_token")
+        .response_mode("direct_post.jwt");
+    let json = serde_json::to_value(&metadata).expect("should serialize");
+
+    assert_eq!(json["client_name"], "Credibil Wallet");
+    assert_eq!(json["redirect_uris"], json!(["https://wallet.example.com/callback"]));
+    assert_eq!(json["token_endpoint_auth_method"], "none");
+    assert_eq!(json["vp_formats_supported"]["jwt_vp_json"]["alg_values_supported"], json!([alg]));
+    assert_eq!(json["vp_formats_supported"]["ldp_vc"]["alg_values_supported"], json!([alg]));
+    assert_eq!(json["cryptographic_binding_methods_supported"], json!(["did:key", "jwk"]));
+    assert_eq!(json["response_types_supported"], json!(["vp_token", "id_token"]));
+    assert_eq!(json["response_modes_supported"], json!(["direct_post", "direct_post.jwt"]));
+    assert!(json.get("authorization_endpoint").is_none());
+}
+
+// Metadata is sent to a verifier's request URI as a JSON form parameter.
+#[test]
+fn request_uri_form() {
+    let metadata = WalletMetadata::new(MockProvider::new().algorithm());
+    let form = metadata.request_uri_form(Some("qPmxiNFCR3QTm19POc8u")).expect("should encode");
+
+    assert_eq!(form["wallet_nonce"], "qPmxiNFCR3QTm19POc8u");
+    let posted: Value = serde_json::from_str(&form["wallet_metadata"]).expect("should parse");
+    assert_eq!(posted, serde_json::to_value(&metadata).expect("should serialize"));
+
+    let form = metadata.request_uri_form(None).expect("should encode");
+    assert!(!form.contains_key("wallet_nonce"));
+}
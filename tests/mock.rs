@@ -0,0 +1,70 @@
+//! Tests for running end-to-end flows against the mock issuer and verifier.
+
+use credibil_holder::agent::HolderAgent;
+use credibil_holder::error::{ErrorCode, OAuthError};
+use credibil_holder::provider::CredentialStorer;
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::{Endpoint, MockProvider, MockResponse};
+use serde_json::json;
+
+// Issue a credential, then present it.
+#[tokio::test]
+async fn issue_and_present() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    agent.save(&id).await.expect("should save credentials");
+
+    let uri =
+        provider.presentation_request("EmployeeIDCredential").await.expect("should get request");
+    let id = agent.request(&uri).await.expect("should start presentation");
+    let matches = agent.matches(&id).await.expect("should find matches");
+    assert_eq!(matches.len(), 1);
+    agent.authorize(&id, &matches).expect("should authorize");
+    agent.present(&id).await.expect("should present");
+}
+
+// Seeded credentials can be presented without running an issuance flow.
+#[tokio::test]
+async fn seeded_credential() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+
+    let claims = json!({"memberId": "42"}).as_object().cloned().expect("should be an object");
+    provider.seed("MembershipCredential", claims).await.expect("should seed credential");
+    provider.seed("EmployeeIDCredential", serde_json::Map::new()).await.expect("should seed");
+    assert_eq!(provider.find(None).await.expect("should find").len(), 2);
+
+    let uri =
+        provider.presentation_request("MembershipCredential").await.expect("should get request");
+    let id = agent.request(&uri).await.expect("should start presentation");
+    let matches = agent.matches(&id).await.expect("should find matches");
+    assert_eq!(matches.len(), 1);
+    agent.authorize(&id, &matches).expect("should authorize");
+    agent.present(&id).await.expect("should present");
+}
+
+// Overridden endpoints return the responder's response.
+#[tokio::test]
+async fn error_response() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    provider.respond_with(Endpoint::Token, |_: &serde_json::Value| {
+        MockResponse::error("temporarily_unavailable", "try again later")
+    });
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    let err = Box::pin(agent.receive(&id)).await.expect_err("should fail");
+    let oauth = OAuthError::from_error(&err).expect("should be an OAuth error");
+    assert_eq!(oauth.code, ErrorCode::TemporarilyUnavailable);
+
+    // The flow is unchanged so the request can be retried.
+    provider.reset(Endpoint::Token);
+    Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+}
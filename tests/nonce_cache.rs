@@ -0,0 +1,37 @@
+//! Tests for refusing to reuse a `c_nonce` in more than one proof of
+//! possession.
+
+use credibil_holder::bearer::Nonce;
+use credibil_holder::error::NonceReused;
+use credibil_holder::issuance::IssuanceFlowBuilder;
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+// A nonce is used in one proof only; a fresh nonce can be used once set.
+#[tokio::test]
+async fn nonce_reuse() {
+    let provider = MockProvider::new();
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant)
+        .accept(&None, pin);
+    let token = provider.token(state.token_request()).await.expect("should get token");
+    let mut state = state.token(token);
+
+    state.build_fresh_proof(&provider, &provider).await.expect("should build proof");
+    let err = state.build_fresh_proof(&provider, &provider).await.expect_err("should refuse");
+    let reused = err.downcast_ref::<NonceReused>().expect("should be NonceReused");
+    assert_eq!(reused.credential_issuer, CREDENTIAL_ISSUER);
+
+    state.set_nonce(&Nonce::new("fresh-nonce", CREDENTIAL_ISSUER)).expect("should set nonce");
+    state.build_fresh_proof(&provider, &provider).await.expect("should build proof");
+}
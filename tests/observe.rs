@@ -0,0 +1,106 @@
+//! Tests for notifying observers of changes to the credential store.
+mod provider;
+
+use std::sync::{Arc, Mutex};
+
+use credibil_holder::credential::{Credential, CredentialMetadata};
+use credibil_holder::integrity::Protected;
+use credibil_holder::observe::Observed;
+use credibil_holder::provider::{CredentialObserver, CredentialStorer};
+
+use crate::provider as holder;
+
+// Records the changes it is notified of.
+#[derive(Clone, Default)]
+struct Changes(Arc<Mutex<Vec<Change>>>);
+
+// The kind of change, the credential ID and the issuer name.
+type Change = (&'static str, String, String);
+
+impl Changes {
+    fn record(&self, change: &'static str, record: &CredentialMetadata) {
+        let mut changes = self.0.lock().expect("should lock");
+        changes.push((change, record.id.clone(), record.issuer_name.clone()));
+    }
+
+    fn take(&self) -> Vec<(&'static str, String, String)> {
+        std::mem::take(&mut *self.0.lock().expect("should lock"))
+    }
+}
+
+impl CredentialObserver for Changes {
+    async fn on_added(&self, record: &CredentialMetadata) {
+        self.record("added", record);
+    }
+
+    async fn on_updated(&self, record: &CredentialMetadata) {
+        self.record("updated", record);
+    }
+
+    async fn on_deleted(&self, record: &CredentialMetadata) {
+        self.record("deleted", record);
+    }
+}
+
+// Only implements the hook it needs.
+struct Deletions(Changes);
+
+impl CredentialObserver for Deletions {
+    async fn on_deleted(&self, record: &CredentialMetadata) {
+        self.0.record("deleted", record);
+    }
+}
+
+fn credential(id: &str, issuer_name: &str) -> Credential {
+    Credential {
+        id: id.into(),
+        issuer_name: issuer_name.into(),
+        ..Credential::default()
+    }
+}
+
+// Saving a new record, saving over it and removing it each notify the
+// observer with the record's metadata.
+#[tokio::test]
+async fn changes() {
+    let changes = Changes::default();
+    let store = Observed::new(holder::Provider::new(None, None), changes.clone());
+
+    store.save(&credential("urn:uuid:1", "Issuer")).await.expect("should save");
+    store.save(&credential("urn:uuid:2", "Issuer")).await.expect("should save");
+    store.save(&credential("urn:uuid:1", "Renamed Issuer")).await.expect("should save");
+    store.remove("urn:uuid:2").await.expect("should remove");
+
+    assert_eq!(
+        changes.take(),
+        vec![
+            ("added", "urn:uuid:1".into(), "Issuer".into()),
+            ("added", "urn:uuid:2".into(), "Issuer".into()),
+            ("updated", "urn:uuid:1".into(), "Renamed Issuer".into()),
+            ("deleted", "urn:uuid:2".into(), "Issuer".into()),
+        ]
+    );
+
+    // reads are not changes
+    store.load("urn:uuid:1").await.expect("should load");
+    store.find(None).await.expect("should find");
+    assert_eq!(changes.take().len(), 0);
+}
+
+// Hooks an observer does not implement do nothing; a record that cannot be
+// loaded is still reported by ID when removed.
+#[tokio::test]
+async fn default_hooks() {
+    let changes = Changes::default();
+    let provider = holder::Provider::new(None, None);
+    let protected = Protected::new(provider.clone(), provider.clone());
+    let store = Observed::new(protected, Deletions(changes.clone()));
+
+    store.save(&credential("urn:uuid:1", "Issuer")).await.expect("should save");
+    assert_eq!(changes.take().len(), 0);
+
+    // bypass the protected store so the record has no integrity tag
+    provider.save(&credential("urn:uuid:1", "Tampered")).await.expect("should save");
+    store.remove("urn:uuid:1").await.expect("should remove");
+    assert_eq!(changes.take(), vec![("deleted", "urn:uuid:1".into(), String::new())]);
+}
@@ -0,0 +1,66 @@
+//! Tests for extracting credential offers from offer links.
+
+use credibil_holder::issuance::{OfferType, SendType, parse_offer};
+use credibil_holder::test_utils::issuer::{self, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+
+// An offer passed by value is decoded from the link, with or without the `?`
+// separator, and with spaces form-encoded as `+`.
+#[tokio::test]
+async fn offer_by_value() {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let offer_resp = credibil_vc::issuer::create_offer(issuer::Provider::new(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    let json = serde_json::to_string(&offer).expect("should serialize offer");
+    let encoded = urlencoding::encode(&json);
+    let pretty = serde_json::to_string_pretty(&offer).expect("should serialize offer");
+    let form_encoded = urlencoding::encode(&pretty).replace("%20", "+");
+
+    for link in [
+        format!("openid-credential-offer://?credential_offer={encoded}"),
+        format!("openid-credential-offer://credential_offer={encoded}"),
+        format!("credential_offer={encoded}"),
+        format!("openid-credential-offer://?credential_offer={form_encoded}"),
+    ] {
+        let OfferType::Object(parsed) = parse_offer(&link).expect("should parse offer") else {
+            panic!("expected CredentialOfferType::Object");
+        };
+        assert_eq!(
+            serde_json::to_value(&parsed).expect("should serialize parsed offer"),
+            serde_json::to_value(&offer).expect("should serialize offer")
+        );
+    }
+}
+
+// An offer passed by reference is returned as a URI for the wallet to fetch.
+#[test]
+fn offer_by_reference() {
+    let uri = "https://issuance.example.com/credential_offer/1234";
+    let link =
+        format!("openid-credential-offer://?credential_offer_uri={}", urlencoding::encode(uri));
+
+    let OfferType::Uri(parsed) = parse_offer(&link).expect("should parse offer") else {
+        panic!("expected CredentialOfferType::Uri");
+    };
+    assert_eq!(parsed, uri);
+}
+
+// Links without an offer, or with an offer that is not valid JSON, are
+// rejected.
+#[test]
+fn invalid_offer() {
+    assert!(parse_offer("openid-credential-offer://?foo=bar").is_err());
+    assert!(parse_offer("openid-credential-offer://?credential_offer=%7Bnot-json").is_err());
+    assert!(parse_offer("").is_err());
+}
@@ -0,0 +1,74 @@
+//! Tests for grouping and sorting credentials for wallet list views.
+
+use chrono::{TimeDelta, Utc};
+use credibil_holder::credential::{Credential, CredentialGroup, GroupBy, SortOrder, organize};
+
+fn credential(
+    id: &str, issuer: &str, type_: &str, days_old: i64, expires_in: Option<i64>,
+) -> Credential {
+    let now = Utc::now();
+    Credential {
+        id: id.into(),
+        issuer: format!("https://{issuer}.example.com"),
+        issuer_name: issuer.into(),
+        type_: vec![type_.into(), "VerifiableCredential".into()],
+        issuance_date: now - TimeDelta::days(days_old),
+        valid_until: expires_in.map(|days| now + TimeDelta::days(days)),
+        ..Credential::default()
+    }
+}
+
+fn credentials() -> Vec<Credential> {
+    vec![
+        credential("licence", "Transport", "DriverLicence", 400, Some(10)),
+        credential("employee", "Credibil", "EmployeeID", 30, Some(-1)),
+        credential("developer", "Credibil", "Developer", 10, None),
+        credential("member", "Club", "Membership", 5, Some(365)),
+    ]
+}
+
+fn summary(groups: &[CredentialGroup]) -> Vec<(&str, Vec<&str>)> {
+    groups
+        .iter()
+        .map(|g| (g.label.as_str(), g.credentials.iter().map(|c| c.id.as_str()).collect()))
+        .collect()
+}
+
+// Groups are ordered by label and credentials within groups by the sort
+// order.
+#[test]
+fn by_issuer() {
+    let groups = organize(credentials(), GroupBy::Issuer, SortOrder::Newest);
+    assert_eq!(
+        summary(&groups),
+        [
+            ("Club", vec!["member"]),
+            ("Credibil", vec!["developer", "employee"]),
+            ("Transport", vec!["licence"]),
+        ]
+    );
+    assert_eq!(groups[1].key, "https://Credibil.example.com");
+
+    let groups = organize(credentials(), GroupBy::Issuer, SortOrder::Oldest);
+    assert_eq!(summary(&groups)[1], ("Credibil", vec!["employee", "developer"]));
+}
+
+// Type groups use the most specific credential type.
+#[test]
+fn by_type() {
+    let groups = organize(credentials(), GroupBy::Type, SortOrder::Name);
+    let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, ["Developer", "DriverLicence", "EmployeeID", "Membership"]);
+}
+
+// Expiry groups are in a fixed order, most urgent first.
+#[test]
+fn by_expiry() {
+    let groups = organize(credentials(), GroupBy::Expiry, SortOrder::Expiring);
+    let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, ["expired", "expiring_soon", "valid", "no_expiry"]);
+
+    let all = organize(credentials(), GroupBy::Issuer, SortOrder::Expiring);
+    assert_eq!(summary(&all)[1], ("Credibil", vec!["employee", "developer"]));
+    assert_eq!(organize(Vec::new(), GroupBy::Expiry, SortOrder::Newest).len(), 0);
+}
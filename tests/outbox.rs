@@ -0,0 +1,133 @@
+//! Tests for queueing outbound requests while offline and flushing the
+//! outbox once back online.
+mod provider;
+
+use chrono::{Duration, Utc};
+use credibil_holder::Kind;
+use credibil_holder::issuance::NotificationRequest;
+use credibil_holder::outbox::{Delivery, Flushed, OutboundRequest, Outbox};
+use credibil_holder::presentation::ResponseRequest;
+use credibil_holder::provider::OutboxStore;
+use credibil_holder::test_utils::mock::{Endpoint, MockProvider, MockResponse};
+use serde_json::{Value, json};
+
+use crate::provider as holder;
+
+fn response_request() -> ResponseRequest {
+    ResponseRequest {
+        vp_token: Some(vec![Kind::String("eyJhbGciOiJFUzI1NiJ9.e30.c2ln".into())]),
+        presentation_submission: None,
+        state: Some("af0ifjsldkj".into()),
+    }
+}
+
+// A presentation that cannot be sent while offline is queued and delivered
+// when the outbox is flushed.
+#[tokio::test]
+async fn queue_and_flush() {
+    let provider = MockProvider::new();
+    let outbox = Outbox::new(provider.clone(), holder::Provider::new(None, None));
+    let expires_at = Utc::now() + Duration::minutes(5);
+
+    provider.respond_with(Endpoint::Present, |_: &Value| MockResponse::offline());
+    let delivery = outbox
+        .present(
+            &provider,
+            Some("https://verifier.example.com/post"),
+            &response_request(),
+            expires_at,
+        )
+        .await
+        .expect("should queue presentation");
+    let Delivery::Queued(id) = delivery else {
+        panic!("expected presentation to be queued");
+    };
+    let pending = outbox.pending().await.expect("should list outbox");
+    assert_eq!(pending.len(), 1);
+    assert!(matches!(pending[0].request, OutboundRequest::Presentation { .. }));
+
+    // The store only sees the sealed request.
+    let stored = OutboxStore::list(&provider).await.expect("should list store");
+    assert!(!String::from_utf8_lossy(&stored[0].sealed).contains("eyJhbGciOiJFUzI1NiJ9"));
+
+    // Still offline: the presentation remains queued.
+    let flushed = outbox.flush_outbox(&provider).await.expect("should flush");
+    assert!(matches!(
+        &flushed[..],
+        [Flushed::Pending { id: pending_id, retry_after: None }] if *pending_id == id
+    ));
+    assert_eq!(outbox.pending().await.expect("should list outbox")[0].attempts, 2);
+
+    // Rate limited: the presentation remains queued until the service is
+    // ready for it.
+    provider
+        .respond_with(Endpoint::Present, |_: &Value| MockResponse::too_many_requests(Some("30")));
+    let flushed = outbox.flush_outbox(&provider).await.expect("should flush");
+    assert!(matches!(
+        &flushed[..],
+        [Flushed::Pending { retry_after: Some(delay), .. }] if delay.as_secs() == 30
+    ));
+    assert_eq!(outbox.pending().await.expect("should list outbox")[0].attempts, 3);
+
+    // Back online: the presentation is delivered and removed.
+    provider.respond_with(Endpoint::Present, |_: &Value| {
+        MockResponse::ok(json!({"redirect_uri": "https://verifier.example.com/done"}))
+    });
+    let flushed = outbox.flush_outbox(&provider).await.expect("should flush");
+    let [
+        Flushed::Presented {
+            id: presented_id,
+            response,
+        },
+    ] = &flushed[..]
+    else {
+        panic!("expected presentation to be delivered");
+    };
+    assert_eq!(*presented_id, id);
+    assert_eq!(response.redirect_uri.as_deref(), Some("https://verifier.example.com/done"));
+    assert!(outbox.pending().await.expect("should list outbox").is_empty());
+}
+
+// Expired requests are discarded and rejected requests are reported without
+// being retried.
+#[tokio::test]
+async fn expired_and_rejected() {
+    let provider = MockProvider::new();
+    let outbox = Outbox::new(provider.clone(), holder::Provider::new(None, None));
+
+    provider.respond_with(Endpoint::Notification, |_: &Value| MockResponse::offline());
+    provider.respond_with(Endpoint::Present, |_: &Value| MockResponse::offline());
+    let notification: NotificationRequest = serde_json::from_value(json!({
+        "credential_issuer": "https://credential-issuer.example.com",
+        "access_token": "eyJhbGciOiJSUzI1NiJ9",
+        "notification_id": "3fwe98js",
+        "event": "credential_accepted"
+    }))
+    .expect("should parse notification");
+    let delivery = outbox
+        .notify(&provider, notification, Utc::now() + Duration::minutes(5))
+        .await
+        .expect("should queue notification");
+    assert!(matches!(delivery, Delivery::Queued(_)));
+    outbox
+        .present(&provider, None, &response_request(), Utc::now() - Duration::seconds(1))
+        .await
+        .expect("should queue presentation");
+
+    provider.respond_with(Endpoint::Notification, |_: &Value| {
+        MockResponse::error("invalid_notification_id", "unknown notification")
+    });
+    let flushed = outbox.flush_outbox(&provider).await.expect("should flush");
+    assert_eq!(flushed.len(), 2);
+    assert!(flushed.iter().any(|f| matches!(f, Flushed::Expired { .. })));
+    assert!(flushed.iter().any(
+        |f| matches!(f, Flushed::Failed { error, .. } if error.contains("invalid_notification_id"))
+    ));
+    assert!(outbox.pending().await.expect("should list outbox").is_empty());
+
+    // Errors other than being offline are returned without queueing.
+    provider
+        .respond_with(Endpoint::Present, |_: &Value| MockResponse::error("invalid_request", "bad"));
+    assert!(outbox.present(&provider, None, &response_request(), Utc::now()).await.is_err());
+    assert!(outbox.pending().await.expect("should list outbox").is_empty());
+}
@@ -0,0 +1,102 @@
+//! Tests for strict and lenient parsing of offers, metadata and request
+//! objects.
+
+use credibil_holder::issuance::{OfferType, parse_metadata, parse_offer_with_mode};
+use credibil_holder::parse::ParseMode;
+use credibil_holder::presentation::compat::parse_request_object_with_mode;
+use serde_json::{Value, json};
+
+const METADATA: &str = include_str!("conformance/fixtures/issuer_metadata.json");
+
+fn offer_link(offer: &Value) -> String {
+    let encoded = urlencoding::encode(&offer.to_string()).into_owned();
+    format!("openid-credential-offer://?credential_offer={encoded}")
+}
+
+fn offer() -> Value {
+    json!({
+        "credential_issuer": "https://credential-issuer.example.com",
+        "credential_configuration_ids": ["UniversityDegreeCredential"],
+        "grants": {
+            "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                "pre-authorized_code": "adhjhdjajkdkhjhdj"
+            }
+        }
+    })
+}
+
+// A conformant offer parses in both modes without warnings.
+#[test]
+fn conformant_offer() {
+    for mode in [ParseMode::Strict, ParseMode::Lenient] {
+        let parsed = parse_offer_with_mode(&offer_link(&offer()), mode).expect("should parse");
+        assert!(matches!(parsed.value, OfferType::Object(_)));
+        assert_eq!(parsed.warnings, Vec::<String>::new());
+    }
+}
+
+// Unknown fields are rejected in strict mode and recorded as warnings in
+// lenient mode.
+#[test]
+fn unknown_field() {
+    let mut offer = offer();
+    offer["issuer_logo"] = json!("https://credential-issuer.example.com/logo.png");
+    let link = offer_link(&offer);
+
+    assert!(parse_offer_with_mode(&link, ParseMode::Strict).is_err());
+
+    let parsed = parse_offer_with_mode(&link, ParseMode::Lenient).expect("should parse");
+    assert_eq!(parsed.warnings.len(), 1);
+    assert!(parsed.warnings[0].contains("issuer_logo"));
+}
+
+// Null values and invalid optional fields are ignored in lenient mode.
+#[test]
+fn invalid_field() {
+    let mut offer = offer();
+    offer["grants"]["authorization_code"] = Value::Null;
+    offer["grants"]["urn:ietf:params:oauth:grant-type:pre-authorized_code"]["tx_code"] =
+        json!("not an object");
+    let link = offer_link(&offer);
+
+    assert!(parse_offer_with_mode(&link, ParseMode::Strict).is_err());
+
+    let parsed = parse_offer_with_mode(&link, ParseMode::Lenient).expect("should parse");
+    assert!(parsed.warnings.iter().any(|w| w.contains("authorization_code")));
+    assert!(parsed.warnings.iter().any(|w| w.ends_with("tx_code")));
+
+    // only the invalid field is dropped
+    let OfferType::Object(offer) = parsed.value else {
+        panic!("should be an offer object");
+    };
+    let grants = offer.grants.expect("should keep grants");
+    let pre_auth = grants.pre_authorized_code.expect("should keep pre-authorized code grant");
+    assert_eq!(pre_auth.pre_authorized_code, "adhjhdjajkdkhjhdj");
+    assert!(pre_auth.tx_code.is_none());
+}
+
+// Metadata and request objects are parsed according to the mode.
+#[test]
+fn metadata_and_request_object() {
+    let mut metadata: Value = serde_json::from_str(METADATA).expect("should parse metadata");
+    parse_metadata(&metadata.to_string(), ParseMode::Strict).expect("should parse strictly");
+
+    metadata["signed_metadata"] = json!(42);
+    metadata["x_vendor_extension"] = json!(true);
+    assert!(parse_metadata(&metadata.to_string(), ParseMode::Strict).is_err());
+    let parsed = parse_metadata(&metadata.to_string(), ParseMode::Lenient).expect("should parse");
+    assert_ne!(parsed.warnings, Vec::<String>::new());
+
+    let request = format!(
+        "openid4vp://?response_type=vp_token&client_id={client_id}&nonce=n-0S6_WzA2Mj\
+        &response_mode=direct_post&response_uri={uri}&x_debug=1&dcql_query={query}",
+        client_id = urlencoding::encode("redirect_uri:https://verifier.example.com"),
+        uri = urlencoding::encode("https://verifier.example.com"),
+        query = urlencoding::encode(r#"{"credentials":[{"id":"employee"}]}"#),
+    );
+    assert!(parse_request_object_with_mode(&request, ParseMode::Strict).is_err());
+    let parsed = parse_request_object_with_mode(&request, ParseMode::Lenient)
+        .expect("should parse")
+        .expect("should be a request object");
+    assert!(parsed.warnings.iter().any(|w| w.contains("x_debug")));
+}
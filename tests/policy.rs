@@ -0,0 +1,118 @@
+//! Tests for the allow/deny policy applied to issuers and credentials.
+
+use credibil_holder::agent::HolderAgent;
+use credibil_holder::credential::{Credential, Sharing};
+use credibil_holder::error::PolicyDenied;
+use credibil_holder::policy::{Effect, Policy, PolicyRule, PolicyTarget};
+use credibil_holder::test_utils::issuer::{CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+fn target(issuer: &str, credential_type: &str, format: &str) -> PolicyTarget {
+    PolicyTarget {
+        issuer: issuer.into(),
+        credential_types: vec!["VerifiableCredential".into(), credential_type.into()],
+        format: format.into(),
+    }
+}
+
+// The first matching rule decides; the default applies when none matches.
+#[test]
+fn rule_order() {
+    let policy = Policy::default()
+        .rule(
+            PolicyRule::allow().issuer("https://gov.example").credential_type("PassportCredential"),
+        )
+        .rule(PolicyRule::deny().credential_type("PassportCredential"))
+        .rule(PolicyRule::deny().format("ldp_vc"));
+
+    let passport = target("https://gov.example", "PassportCredential", "jwt_vc_json");
+    assert_eq!(policy.evaluate(&passport), (Effect::Allow, Some(0)));
+
+    let forged = target("https://other.example", "PassportCredential", "jwt_vc_json");
+    assert_eq!(policy.evaluate(&forged), (Effect::Deny, Some(1)));
+
+    let linked = target("https://other.example", "EmployeeIDCredential", "ldp_vc");
+    let denied = policy.check(&linked).expect_err("should deny format");
+    assert_eq!(denied.rule, Some(2));
+    assert_eq!(denied.format, "ldp_vc");
+
+    let employee = target("https://other.example", "EmployeeIDCredential", "jwt_vc_json");
+    assert_eq!(policy.evaluate(&employee), (Effect::Allow, None));
+}
+
+// A policy denying by default and allowing any jurisdiction accepts only
+// issuers on the trusted list; prefixes match jurisdictions.
+#[test]
+fn trusted_list() {
+    let policy = Policy::deny_by_default()
+        .trusted_issuer("https://gov.example", "EU-DE")
+        .trusted_issuer("https://bank.example", "AU")
+        .rule(PolicyRule::deny().jurisdiction("EU-*").credential_type("LoyaltyCredential"))
+        .rule(PolicyRule::allow().jurisdiction("*"));
+
+    assert!(policy.permits(&target("https://gov.example", "PidCredential", "jwt_vc_json")));
+    assert!(policy.permits(&target("https://bank.example", "LoyaltyCredential", "jwt_vc")));
+    assert!(!policy.permits(&target("https://gov.example", "LoyaltyCredential", "jwt_vc")));
+
+    let unlisted = target("https://unknown.example", "PidCredential", "jwt_vc_json");
+    let denied = policy.check(&unlisted).expect_err("should deny unlisted issuer");
+    assert_eq!(denied.rule, None);
+    assert_eq!(denied.issuer, "https://unknown.example");
+}
+
+// The agent refuses offers and presentations the policy denies.
+#[tokio::test]
+async fn agent_policy() {
+    let provider = MockProvider::new();
+    let (offer, _) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID)
+        .with_policy(Policy::default().rule(PolicyRule::deny().issuer(CREDENTIAL_ISSUER)));
+    let err = agent.offer(offer.clone(), NORMAL_USER).await.expect_err("should deny offer");
+    let denied = err.downcast_ref::<PolicyDenied>().expect("should be PolicyDenied");
+    assert_eq!(denied.issuer, CREDENTIAL_ISSUER);
+    assert_eq!(agent.flow_ids(), Vec::<String>::new());
+
+    let agent = HolderAgent::new(provider, CLIENT_ID)
+        .with_policy(Policy::default().rule(PolicyRule::deny().issuer("https://other.example")));
+    agent.offer(offer, NORMAL_USER).await.expect("should allow offer");
+
+    let credential = Credential {
+        issuer: "https://other.example".into(),
+        ..Credential::default()
+    };
+    let err = agent.authorize("unknown", &[credential]).expect_err("should deny credential");
+    assert!(err.downcast_ref::<PolicyDenied>().is_some());
+}
+
+// One policy identifies the issuer by the same URL when its offer is received
+// and when its credentials are presented.
+#[tokio::test]
+async fn offer_and_presentation() {
+    let provider = MockProvider::new();
+    let allow = Policy::deny_by_default().rule(PolicyRule::allow().issuer(CREDENTIAL_ISSUER));
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID).with_policy(allow);
+
+    let (offer, pin) = provider.offer(&["EmployeeID_JWT"], true).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should allow offer");
+    agent.accept(&id, &None, pin).expect("should accept offer");
+    let credentials = Box::pin(agent.receive(&id)).await.expect("should receive credentials");
+    agent.save(&id).await.expect("should save credentials");
+
+    let uri = provider.presentation_request("EmployeeIDCredential").await.expect("should get uri");
+    let id = agent.request(&uri).await.expect("should start presentation");
+    let candidates =
+        agent.candidates(&id, Sharing::default()).await.expect("should find candidates");
+    assert_eq!(candidates.len(), 1);
+    agent.authorize(&id, &credentials).expect("should allow credentials");
+
+    let deny = Policy::default().rule(PolicyRule::deny().issuer(CREDENTIAL_ISSUER));
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID).with_policy(deny);
+    let uri = provider.presentation_request("EmployeeIDCredential").await.expect("should get uri");
+    let id = agent.request(&uri).await.expect("should start presentation");
+    let candidates =
+        agent.candidates(&id, Sharing::default()).await.expect("should find candidates");
+    assert!(candidates.is_empty());
+    let err = agent.authorize(&id, &credentials).expect_err("should deny credentials");
+    assert!(err.is::<PolicyDenied>());
+}
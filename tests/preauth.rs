@@ -2,12 +2,8 @@
 //! accepts all credentials and all claims on offer.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
-use credibil_holder::issuance::{
-    CredentialResponseType, IssuanceFlow, NotAccepted, OfferType, PreAuthorized, SendType,
-    WithOffer, WithoutToken,
-};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
+use credibil_holder::issuance::{CredentialResponseType, IssuanceFlowBuilder, OfferType, SendType};
 use credibil_holder::provider::{Issuer, MetadataRequest};
 use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
 use credibil_vc::issuer::{CreateOfferRequest, GrantType};
@@ -57,13 +53,9 @@ async fn preauth() {
     //--------------------------------------------------------------------------
 
     let pre_auth_code_grant = offer.pre_authorized_code().expect("should get pre-authorized code");
-    let state = IssuanceFlow::<WithOffer, PreAuthorized, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer,
-        offer,
-        pre_auth_code_grant,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, pre_auth_code_grant);
     let offered = state.offered();
 
     //--------------------------------------------------------------------------
@@ -111,15 +103,7 @@ async fn preauth() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode proof claims");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
@@ -3,12 +3,8 @@
 //! deferred.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
-use credibil_holder::issuance::{
-    CredentialResponseType, IssuanceFlow, NotAccepted, OfferType, PreAuthorized, SendType,
-    WithOffer, WithoutToken,
-};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
+use credibil_holder::issuance::{CredentialResponseType, IssuanceFlowBuilder, OfferType, SendType};
 use credibil_holder::provider::{Issuer, MetadataRequest};
 use credibil_vc::issuer::{CreateOfferRequest, GrantType};
 use credibil_vc::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, PENDING_USER};
@@ -58,13 +54,9 @@ async fn preauth_deferred() {
     //--------------------------------------------------------------------------
 
     let pre_auth_code_grant = offer.pre_authorized_code().expect("should get pre-authorized code");
-    let state = IssuanceFlow::<WithOffer, PreAuthorized, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        PENDING_USER,
-        issuer_metadata.credential_issuer,
-        offer,
-        pre_auth_code_grant,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(PENDING_USER)
+        .pre_authorized(offer, pre_auth_code_grant);
     let offered = state.offered();
 
     //--------------------------------------------------------------------------
@@ -107,15 +99,7 @@ async fn preauth_deferred() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode proof claims");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
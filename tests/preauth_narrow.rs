@@ -5,11 +5,10 @@ mod provider;
 
 use std::collections::HashMap;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
 use credibil_holder::issuance::{
-    AuthorizationSpec, Claim, CredentialResponseType, IssuanceFlow, NotAccepted, OfferType,
-    PreAuthorized, SendType, WithOffer, WithoutToken,
+    AuthorizationSpec, Claim, CredentialAuthorization, CredentialResponseType, IssuanceFlowBuilder,
+    OfferType, ProfileClaims, SendType,
 };
 use credibil_holder::provider::{Issuer, MetadataRequest};
 use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
@@ -63,13 +62,9 @@ async fn preauth_narrow() {
     //--------------------------------------------------------------------------
 
     let pre_auth_code_grant = offer.pre_authorized_code().expect("should get pre-authorized code");
-    let state = IssuanceFlow::<WithOffer, PreAuthorized, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer,
-        offer,
-        pre_auth_code_grant,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, pre_auth_code_grant);
     let offered = state.offered();
 
     //--------------------------------------------------------------------------
@@ -94,6 +89,19 @@ async fn preauth_narrow() {
     // Request an access token from the issuer.
     //--------------------------------------------------------------------------
     let token_request = state.token_request();
+
+    // Only the accepted claim is requested from the issuer.
+    let details = token_request.authorization_details.as_ref().expect("should have details");
+    assert_eq!(details.len(), 1);
+    let CredentialAuthorization::ConfigurationId {
+        claims: Some(ProfileClaims::W3c(definition)),
+        ..
+    } = &details[0].credential
+    else {
+        panic!("expected claims by configuration ID");
+    };
+    let subject = definition.credential_subject.as_ref().expect("should have claims");
+    assert_eq!(subject.keys().collect::<Vec<_>>(), vec!["proficiency"]);
     let token_response = provider.token(token_request).await.expect("should get token response");
     let mut state = state.token(token_response.clone());
 
@@ -114,15 +122,7 @@ async fn preauth_narrow() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode proof claims");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
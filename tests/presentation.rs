@@ -4,7 +4,7 @@ mod provider;
 use std::collections::HashMap;
 
 use chrono::Utc;
-use credibil_holder::credential::Credential;
+use credibil_holder::credential::{Credential, DataModel, SharingPolicy};
 use credibil_holder::issuance::{
     Claim, ClaimDefinition, CredentialSubject, Display, ValueType, VerifiableCredential,
 };
@@ -13,7 +13,7 @@ use credibil_holder::presentation::{
     Constraints, Field, Filter, FilterValue, InputDescriptor, NotAuthorized, PresentationFlow,
     parse_request_object_response,
 };
-use credibil_holder::provider::{CredentialStorer, Signer, Verifier};
+use credibil_holder::provider::{CredentialStorer, Verifier};
 use credibil_holder::test_utils::verifier::{self, VERIFIER_ID};
 use credibil_holder::{Kind, Quota};
 use credibil_vc::verifier::{CreateRequestRequest, DeviceFlow};
@@ -114,8 +114,10 @@ async fn sample_credential() -> Credential {
         id: vc.id.clone().expect("should have id"),
         issuer: "https://credibil.io".into(),
         issuer_name: "Credibil".into(),
+        issuer_display: Vec::new(),
         type_,
         format: "jwt_vc_json".into(),
+        data_model: DataModel::V2_0,
         subject_claims,
         claim_definitions: Some(claim_def),
         display: None,
@@ -125,6 +127,9 @@ async fn sample_credential() -> Credential {
         valid_until: vc.valid_until.clone(),
         logo: None,
         background: None,
+        key_id: None,
+        sharing_policy: SharingPolicy::default(),
+        integrity: None,
     }
 }
 
@@ -196,22 +201,11 @@ async fn presentation_uri() {
     let state = state.authorize(&credentials);
 
     //--------------------------------------------------------------------------
-    // Construct a verifiable presentation payload
+    // Sign a verifiable presentation and use it to create a presentation
+    // response request.
     //--------------------------------------------------------------------------
 
-    let kid = provider.verification_method().await.expect("should get verification method");
-    let vp = state.payload(&kid).expect("should get verifiable presentation payload");
-
-    //--------------------------------------------------------------------------
-    // Create a proof and use it to create a presentation response request.
-    //--------------------------------------------------------------------------
-
-    let Payload::Vp { vp, client_id, nonce } = vp else {
-        panic!("expected Payload::Vp");
-    };
-    let jwt = proof::create(W3cFormat::JwtVcJson, Payload::Vp { vp, client_id, nonce }, &provider)
-        .await
-        .expect("should create proof");
+    let jwt = state.sign_payload(&provider).await.expect("should sign presentation");
     let (res_req, uri) = state.create_response_request(&jwt);
 
     //--------------------------------------------------------------------------
@@ -279,22 +273,11 @@ async fn presentation_obj() {
     let state = state.authorize(&credentials);
 
     //--------------------------------------------------------------------------
-    // Construct a presentation submission and verifiable presentation payload
+    // Sign a verifiable presentation and use it to create a presentation
+    // response request.
     //--------------------------------------------------------------------------
 
-    let kid = provider.verification_method().await.expect("should get verification method");
-    let vp = state.payload(&kid).expect("should get verifiable presentation payload");
-
-    //--------------------------------------------------------------------------
-    // Create a proof and use it to create a presentation response request.
-    //--------------------------------------------------------------------------
-
-    let Payload::Vp { vp, client_id, nonce } = vp else {
-        panic!("expected Payload::Vp");
-    };
-    let jwt = proof::create(W3cFormat::JwtVcJson, Payload::Vp { vp, client_id, nonce }, &provider)
-        .await
-        .expect("should create proof");
+    let jwt = state.sign_payload(&provider).await.expect("should sign presentation");
     let (res_req, uri) = state.create_response_request(&jwt);
 
     //--------------------------------------------------------------------------
@@ -0,0 +1,99 @@
+//! Tests for configuring the contexts, types and properties of built
+//! presentations.
+
+use credibil_holder::credential::Credential;
+use credibil_holder::presentation::proof::Payload;
+use credibil_holder::presentation::{
+    NotAuthorized, PresentationFlow, PresentationTemplate, RequestObject,
+};
+use serde_json::{Map, Value, json};
+
+const KID: &str = "did:example:holder#key-1";
+
+fn request_object() -> RequestObject {
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": "EmployeeIDCredential"}
+                    }]
+                }
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+// The presentation built by a flow using the template, as JSON.
+fn presentation(template: PresentationTemplate) -> anyhow::Result<Value> {
+    let credential = Credential {
+        id: "urn:example:EmployeeIDCredential".into(),
+        issued: "EmployeeIDCredential.jwt".into(),
+        ..Credential::default()
+    };
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object())?
+        .with_presentation(template)
+        .authorize(&[credential]);
+    let Payload::Vp { vp, .. } = flow.payload(KID)? else {
+        panic!("expected a verifiable presentation");
+    };
+    Ok(serde_json::to_value(vp)?)
+}
+
+// Without a template, presentations are described as in earlier releases.
+#[test]
+fn default_template() {
+    let vp = presentation(PresentationTemplate::default()).expect("should build presentation");
+    assert_eq!(vp["@context"][1], "https://www.w3.org/2018/credentials/examples/v1");
+    assert_eq!(vp["type"], json!(["VerifiablePresentation", "EmployeeIDCredential"]));
+    assert_eq!(vp["holder"], "did:example:holder");
+}
+
+// Configured contexts, types and properties replace the defaults.
+#[test]
+fn configured_template() {
+    let mut properties = Map::new();
+    properties.insert("id".into(), json!("urn:uuid:3978344f-8596-4c3a-a978-8fcaba3903c5"));
+    let vp = presentation(PresentationTemplate {
+        contexts: Some(vec!["https://example.com/wallet/v1".into()]),
+        types: Some(vec![]),
+        properties,
+    })
+    .expect("should build presentation");
+
+    let contexts = vp["@context"].as_array().expect("should have contexts");
+    assert_eq!(contexts.len(), 2);
+    assert_eq!(contexts[1], "https://example.com/wallet/v1");
+    assert_eq!(vp["type"], json!(["VerifiablePresentation"]));
+    assert_eq!(vp["id"], "urn:uuid:3978344f-8596-4c3a-a978-8fcaba3903c5");
+}
+
+// Properties set by the flow or not supported by the presentation model are
+// rejected.
+#[test]
+fn rejected_properties() {
+    for name in ["holder", "unknownProperty"] {
+        let mut properties = Map::new();
+        properties.insert(name.into(), json!("did:example:other"));
+        let template = PresentationTemplate {
+            properties,
+            ..PresentationTemplate::default()
+        };
+        let err = presentation(template).expect_err("should reject property");
+        assert!(err.to_string().contains(name));
+    }
+}
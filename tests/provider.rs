@@ -1,10 +1,13 @@
 //! Provider implementation for tests
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str;
 use std::sync::{Arc, Mutex};
 
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
 use chrono::{DateTime, Utc};
+use credibil_holder::context::WalletContext;
 use credibil_holder::credential::{Credential, ImageData};
 use credibil_holder::issuance::{
     AuthorizationRequest, AuthorizationResponse, CredentialRequest, CredentialResponse,
@@ -16,9 +19,11 @@ use credibil_holder::presentation::{
     Constraints, RequestObjectRequest, RequestObjectResponse, ResponseRequest, ResponseResponse,
 };
 use credibil_holder::provider::{
-    Algorithm, CredentialStorer, DidResolver, Document, HolderProvider, Issuer, Result, Signer,
-    StateStore, Verifier,
+    Algorithm, ContextScoped, CredentialStorer, DidResolver, Document, Encryptor, FlowStore,
+    HolderProvider, Issuer, NonceCache, PushProvider, Result, Signer, StateStore, Verifier,
 };
+use credibil_holder::push::{self, PushNotification};
+use credibil_holder::registry::FlowRecord;
 use credibil_vc::test_utils::store::keystore::HolderKeystore;
 use credibil_vc::test_utils::store::{resolver, state};
 use credibil_vc::test_utils::{issuer, verifier};
@@ -31,7 +36,10 @@ pub struct Provider {
     issuer: Option<issuer::Provider>,
     verifier: Option<verifier::Provider>,
     state: state::Store,
+    context: WalletContext,
     cred_store: Arc<Mutex<HashMap<String, Credential>>>,
+    flow_store: Arc<Mutex<HashMap<String, FlowRecord>>>,
+    nonces: Arc<Mutex<HashSet<(String, String)>>>,
 }
 
 impl Provider {
@@ -42,7 +50,10 @@ impl Provider {
             issuer,
             verifier,
             state: state::Store::new(),
+            context: WalletContext::default(),
             cred_store: Arc::new(Mutex::new(HashMap::new())),
+            flow_store: Arc::new(Mutex::new(HashMap::new())),
+            nonces: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 }
@@ -115,21 +126,42 @@ impl Verifier for Provider {
     }
 }
 
+impl ContextScoped for Provider {
+    fn scoped(&self, context: &WalletContext) -> Self {
+        Self {
+            context: context.clone(),
+            ..self.clone()
+        }
+    }
+
+    fn context(&self) -> WalletContext {
+        self.context.clone()
+    }
+}
+
 impl CredentialStorer for Provider {
     async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
         self.cred_store
             .lock()
             .expect("should lock")
-            .insert(credential.clone().id, credential.clone());
+            .insert(self.context.scoped_key(&credential.id), credential.clone());
         Ok(())
     }
 
     async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
-        Ok(self.cred_store.lock().expect("should lock").get(id).cloned())
+        Ok(self.cred_store.lock().expect("should lock").get(&self.context.scoped_key(id)).cloned())
     }
 
     async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
-        let creds = self.cred_store.lock().expect("should lock").values().cloned().collect();
+        let prefix = self.context.scoped_key("");
+        let creds = self
+            .cred_store
+            .lock()
+            .expect("should lock")
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, cred)| cred.clone())
+            .collect();
         if filter.is_none() {
             return Ok(creds);
         }
@@ -146,11 +178,77 @@ impl CredentialStorer for Provider {
     }
 
     async fn remove(&self, id: &str) -> anyhow::Result<()> {
-        self.cred_store.lock().expect("should lock").remove(id);
+        self.cred_store.lock().expect("should lock").remove(&self.context.scoped_key(id));
+        Ok(())
+    }
+}
+
+// A fixed key is fine for tests; wallets should keep the key in a platform
+// key store.
+const SNAPSHOT_KEY: [u8; 32] = [7; 32];
+
+impl Encryptor for Provider {
+    async fn encrypt(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let cipher = Aes256Gcm::new(&SNAPSHOT_KEY.into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, plaintext).map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    async fn decrypt(&self, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if ciphertext.len() < 12 {
+            return Err(anyhow::anyhow!("ciphertext too short"));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(12);
+        let cipher = Aes256Gcm::new(&SNAPSHOT_KEY.into());
+        cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|e| anyhow::anyhow!("{e}"))
+    }
+}
+
+impl PushProvider for Provider {
+    async fn decode_push(&self, payload: &[u8]) -> anyhow::Result<PushNotification> {
+        push::parse(payload)
+    }
+}
+
+impl FlowStore for Provider {
+    async fn put(&self, record: &FlowRecord) -> anyhow::Result<()> {
+        self.flow_store
+            .lock()
+            .expect("should lock")
+            .insert(self.context.scoped_key(&record.id), record.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<FlowRecord>> {
+        Ok(self.flow_store.lock().expect("should lock").get(&self.context.scoped_key(id)).cloned())
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<FlowRecord>> {
+        let prefix = self.context.scoped_key("");
+        Ok(self
+            .flow_store
+            .lock()
+            .expect("should lock")
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, record)| record.clone())
+            .collect())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        self.flow_store.lock().expect("should lock").remove(&self.context.scoped_key(id));
         Ok(())
     }
 }
 
+impl NonceCache for Provider {
+    async fn use_nonce(&self, credential_issuer: &str, nonce: &str) -> anyhow::Result<bool> {
+        let mut nonces = self.nonces.lock().expect("should lock");
+        Ok(nonces.insert((credential_issuer.into(), nonce.into())))
+    }
+}
+
 impl StateStore for Provider {
     async fn put(&self, key: &str, state: impl Serialize, dt: DateTime<Utc>) -> Result<()> {
         self.state.put(key, state, dt)
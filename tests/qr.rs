@@ -0,0 +1,79 @@
+//! Tests for decoding offers and presentation requests from QR codes.
+
+use std::io::Cursor;
+
+use credibil_holder::issuance::OfferType;
+use credibil_holder::qr::{self, Scanned};
+use image::{GrayImage, ImageFormat};
+use qrcode::{Color, QrCode};
+
+// Pixels per QR code module and modules of quiet zone around the code.
+const SCALE: u32 = 4;
+const QUIET_ZONE: u32 = 4;
+
+// Render the content as a greyscale QR code frame.
+fn frame(content: &str) -> GrayImage {
+    let code = QrCode::new(content).expect("should encode QR code");
+    let modules = u32::try_from(code.width()).expect("should fit");
+    let colors = code.to_colors();
+    let size = (modules + 2 * QUIET_ZONE) * SCALE;
+    GrayImage::from_fn(size, size, |x, y| {
+        let (mx, my) = (x / SCALE, y / SCALE);
+        let inside = (QUIET_ZONE..modules + QUIET_ZONE).contains(&mx)
+            && (QUIET_ZONE..modules + QUIET_ZONE).contains(&my);
+        let dark = inside
+            && colors[((my - QUIET_ZONE) * modules + (mx - QUIET_ZONE)) as usize] == Color::Dark;
+        image::Luma([if dark { 0 } else { 255 }])
+    })
+}
+
+// An offer passed by reference is decoded from a PNG image.
+#[test]
+fn offer_from_png() {
+    let uri = "https://issuance.example.com/credential_offer/1234";
+    let link =
+        format!("openid-credential-offer://?credential_offer_uri={}", urlencoding::encode(uri));
+    let mut png = Cursor::new(vec![]);
+    frame(&link).write_to(&mut png, ImageFormat::Png).expect("should encode PNG");
+
+    let Scanned::Offer(OfferType::Uri(offer_uri)) = qr::scan(png.get_ref()).expect("should scan")
+    else {
+        panic!("expected an offer URI");
+    };
+    assert_eq!(offer_uri, uri);
+}
+
+// A presentation request URI is decoded from a camera frame.
+#[test]
+fn request_uri_from_frame() {
+    let uri = "https://verifier.example.com/request/1234";
+    let image = frame(&urlencoding::encode(uri));
+
+    let Scanned::RequestUri(request_uri) =
+        qr::scan_frame(image.width(), image.height(), image.as_raw()).expect("should scan")
+    else {
+        panic!("expected a request URI");
+    };
+    assert_eq!(request_uri, uri);
+
+    // A request URI mentioning offers in its path is not an offer.
+    let uri = "https://verifier.example.com/credential_offer/1234";
+    let image = frame(&format!("openid4vp://?request_uri={}", urlencoding::encode(uri)));
+    let Scanned::RequestUri(request_uri) =
+        qr::scan_frame(image.width(), image.height(), image.as_raw()).expect("should scan")
+    else {
+        panic!("expected a request URI");
+    };
+    assert_eq!(request_uri, uri);
+}
+
+// Frames without a QR code, or with unsupported contents, are rejected.
+#[test]
+fn unsupported() {
+    let blank = GrayImage::from_pixel(64, 64, image::Luma([255]));
+    assert!(qr::scan_frame(64, 64, blank.as_raw()).is_err());
+    assert!(qr::scan_frame(128, 64, blank.as_raw()).is_err());
+
+    let image = frame("hello world");
+    assert!(qr::scan_frame(image.width(), image.height(), image.as_raw()).is_err());
+}
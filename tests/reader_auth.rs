@@ -0,0 +1,115 @@
+//! Tests for authenticating verifiers (mdoc readers) that sign their requests
+//! with a reader certificate.
+
+// Provider trait methods are async by contract even where the check is not.
+#![allow(clippy::unused_async_trait_impl)]
+
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use credibil_holder::error::ReaderNotAuthenticated;
+use credibil_holder::presentation::reader::{
+    ReaderAuthentication, ReaderIdentity, has_reader_certificate, parse_signed_request,
+};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow};
+use credibil_holder::provider::ReaderTrust;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+const READER_CERT: &[u8] = b"reader certificate";
+const VERIFIER: &str = "verifier.example.com";
+
+// Trusts the reader certificate, and "signatures" that are the SHA-256 digest
+// of the signed bytes.
+struct Trust;
+
+impl ReaderTrust for Trust {
+    async fn verify_reader(
+        &self, chain: &[Vec<u8>], _alg: &str, signed: &[u8], signature: &[u8],
+    ) -> anyhow::Result<ReaderIdentity> {
+        if chain != [READER_CERT.to_vec()] {
+            anyhow::bail!("untrusted certificate chain");
+        }
+        if Sha256::digest(signed).as_slice() != signature {
+            anyhow::bail!("invalid signature");
+        }
+        Ok(ReaderIdentity {
+            subject: "CN=Verifier".into(),
+            issuer: "CN=Reader CA".into(),
+            dns_names: vec![VERIFIER.into()],
+            ..ReaderIdentity::default()
+        })
+    }
+}
+
+fn request(client_id: &str) -> Value {
+    json!({
+        "client_id": client_id,
+        "client_id_scheme": "x509_san_dns",
+        "client_metadata": {
+            "client_id": client_id,
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "mdl request",
+            "input_descriptors": [{
+                "id": "org.iso.18013.5.1.mDL",
+                "constraints": {"fields": [{"path": ["$.type"]}]}
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": format!("https://{VERIFIER}/post")
+    })
+}
+
+fn sign(header: &Value, claims: &Value) -> String {
+    let encode = |value: &Value| Base64UrlUnpadded::encode_string(value.to_string().as_bytes());
+    let signed = format!("{}.{}", encode(header), encode(claims));
+    let signature = Base64UrlUnpadded::encode_string(&Sha256::digest(signed.as_bytes()));
+    format!("{signed}.{signature}")
+}
+
+fn header() -> Value {
+    json!({"alg": "ES256", "x5c": [Base64::encode_string(READER_CERT)]})
+}
+
+// A request signed with a trusted reader certificate naming the verifier is
+// authenticated, and the identity recorded on the flow.
+#[tokio::test]
+async fn authenticated() {
+    let token = sign(&header(), &request(VERIFIER));
+    assert!(has_reader_certificate(&token));
+
+    let (request_object, identity) =
+        parse_signed_request(&token, &Trust).await.expect("should authenticate reader");
+    assert_eq!(identity.subject, "CN=Verifier");
+
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object)
+        .expect("should start flow")
+        .with_reader(ReaderAuthentication::Authenticated(identity.clone()));
+    assert_eq!(flow.reader().identity(), Some(&identity));
+    let authorized = flow.authorize(&[]);
+    assert!(!authorized.reader().is_anonymous());
+}
+
+// Requests from another verifier, with an untrusted chain, or with a bad
+// signature are not authenticated.
+#[tokio::test]
+async fn not_authenticated() {
+    let other = sign(&header(), &request("other.example.com"));
+    let err = parse_signed_request(&other, &Trust).await.expect_err("should not authenticate");
+    let err = err.downcast_ref::<ReaderNotAuthenticated>().expect("should be not authenticated");
+    assert_eq!(err.client_id, "other.example.com");
+
+    let untrusted = json!({"alg": "ES256", "x5c": [Base64::encode_string(b"other certificate")]});
+    let token = sign(&untrusted, &request(VERIFIER));
+    assert!(parse_signed_request(&token, &Trust).await.is_err());
+
+    let token = sign(&header(), &request(VERIFIER));
+    let (signed, _) = token.rsplit_once('.').expect("should be a JWS");
+    let forged = format!("{signed}.{}", Base64UrlUnpadded::encode_string(b"forged"));
+    assert!(parse_signed_request(&forged, &Trust).await.is_err());
+
+    let anonymous = sign(&json!({"alg": "ES256"}), &request(VERIFIER));
+    assert!(!has_reader_certificate(&anonymous));
+}
@@ -0,0 +1,134 @@
+//! Tests for redacting secrets and personal data from logs.
+mod provider;
+
+use credibil_holder::credential::{Credential, SubjectClaims};
+use credibil_holder::issuance::{IssuanceFlowBuilder, OfferType, SendType};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::redact::{REDACTED, VOLATILE};
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use serde_json::{Value, json};
+
+use crate::provider as holder;
+
+// Claim values and the issued credential are redacted from a credential's
+// debug output and logging view, but claim names are kept.
+#[test]
+fn credential_redaction() {
+    let credential = Credential {
+        id: "urn:uuid:1234".into(),
+        issued: "eyJhbGciOiJFUzI1NiJ9".into(),
+        subject_claims: vec![SubjectClaims {
+            id: Some(NORMAL_USER.into()),
+            claims: json!({"family_name": "Person", "address": {"locality": "Wellington"}})
+                .as_object()
+                .cloned()
+                .expect("should be an object"),
+        }],
+        ..Credential::default()
+    };
+
+    let debug = format!("{credential:?}");
+    assert!(debug.contains("family_name"));
+    assert!(!debug.contains("Person"));
+    assert!(!debug.contains("eyJhbGciOiJFUzI1NiJ9"));
+
+    let redacted = credential.redacted();
+    assert_eq!(redacted["id"], "urn:uuid:1234");
+    assert_eq!(redacted["issued"], REDACTED);
+    assert_eq!(redacted["subject_claims"][0]["claims"]["family_name"], REDACTED);
+    assert_eq!(redacted["subject_claims"][0]["claims"]["address"]["locality"], REDACTED);
+}
+
+// The pre-authorized code and PIN are redacted from a flow's debug output and
+// logging view.
+#[tokio::test]
+async fn flow_redaction() {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let issuer_provider = issuer::Provider::new();
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+
+    let provider = holder::Provider::new(Some(issuer_provider), None);
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let code = grant.pre_authorized_code.clone();
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant);
+    let pin = offer_resp.tx_code.expect("should have user code");
+    let state = state.accept(&None, Some(pin.clone()));
+
+    let debug = format!("{state:?}");
+    assert!(!debug.contains(&code));
+    assert!(!debug.contains(&pin));
+
+    let redacted = state.redacted().to_string();
+    assert!(!redacted.contains(&code));
+    assert!(!redacted.contains(&pin));
+    assert_eq!(state.redacted()["id"], state.id());
+}
+
+// Stable views of flows started from the same request are identical: the flow
+// ID is volatile, the nonce is redacted and fields are sorted.
+#[test]
+fn stable_view() {
+    let request: RequestObject = serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "state": "af0ifjsldkj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {"fields": [{"path": ["$.type"]}]}
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object");
+    let view = |request: &RequestObject| {
+        PresentationFlow::<NotAuthorized>::new(request.clone())
+            .expect("should start flow")
+            .stable_view()
+    };
+
+    let first = view(&request);
+    assert_eq!(first, view(&request));
+    assert_eq!(first.value()["id"], VOLATILE);
+    assert_eq!(first.value()["request"]["nonce"], REDACTED);
+    assert_eq!(first.value()["request"]["state"], REDACTED);
+
+    let Value::Object(fields) = first.value() else {
+        panic!("expected an object");
+    };
+    let keys: Vec<&String> = fields.keys().collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+    assert!(!first.to_string().contains("n-0S6_WzA2Mj"));
+}
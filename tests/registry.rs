@@ -0,0 +1,92 @@
+//! Tests for persisting, listing and resuming flows using the `FlowRegistry`.
+mod provider;
+
+use chrono::{Duration, Utc};
+use credibil_holder::agent::{Flow, HolderAgent};
+use credibil_holder::context::WalletContext;
+use credibil_holder::issuance::{CredentialOffer, OfferType, SendType};
+use credibil_holder::provider::ContextScoped;
+use credibil_holder::registry::FlowRegistry;
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+
+use crate::provider as holder;
+
+async fn create_offer(issuer_provider: &issuer::Provider) -> CredentialOffer {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: false,
+        send_type: SendType::ByVal,
+    };
+    let response = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = response.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+    offer
+}
+
+// Flows registered with an expiry can be listed and resumed until they expire,
+// after which they are evicted.
+#[tokio::test]
+async fn resume_and_evict() {
+    let issuer_provider = issuer::Provider::new();
+    let provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    let registry = FlowRegistry::new(provider.clone());
+
+    let mut ids = vec![];
+    for _ in 0..2 {
+        let offer = create_offer(&issuer_provider).await;
+        ids.push(agent.offer(offer, NORMAL_USER).await.expect("should start flow"));
+    }
+
+    // Register one flow that is still live and one that has already expired.
+    let live = agent.cancel(&ids[0]).expect("should have flow");
+    let expired = agent.cancel(&ids[1]).expect("should have flow");
+    registry.register(live, Utc::now() + Duration::minutes(5)).await.expect("should register");
+    registry.register(expired, Utc::now() - Duration::seconds(1)).await.expect("should register");
+
+    let active = registry.list_active().await.expect("should list flows");
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, ids[0]);
+    assert!(registry.resume(&ids[1]).await.expect("should query store").is_none());
+    assert_eq!(registry.evict_expired().await.expect("should evict"), 0);
+
+    // Resume the live flow into the agent and carry on.
+    let flow = registry.resume(&ids[0]).await.expect("should query store").expect("should resume");
+    assert_eq!(agent.restore(flow), ids[0]);
+    agent.accept(&ids[0], &None, None).expect("should accept offer");
+    assert!(matches!(agent.flow(&ids[0]).as_deref(), Some(Flow::Accepted(_))));
+
+    registry.remove(&ids[0]).await.expect("should remove");
+    assert!(registry.list_active().await.expect("should list flows").is_empty());
+}
+
+// Flows registered for one profile are not visible to another.
+#[tokio::test]
+async fn profile_isolation() {
+    let issuer_provider = issuer::Provider::new();
+    let provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+    let alice = provider.scoped(&WalletContext::new("alice"));
+    let bob = provider.scoped(&WalletContext::new("bob"));
+    let agent = HolderAgent::new(alice.clone(), CLIENT_ID);
+
+    let id = agent.offer(create_offer(&issuer_provider).await, NORMAL_USER).await.expect("should start flow");
+    let flow = agent.cancel(&id).expect("should have flow");
+    let expiry = Utc::now() + Duration::minutes(5);
+    FlowRegistry::new(alice.clone()).register(flow, expiry).await.expect("should register");
+
+    let registry = FlowRegistry::new(bob);
+    assert!(registry.list_active().await.expect("should list flows").is_empty());
+    assert!(registry.resume(&id).await.expect("should query store").is_none());
+    registry.remove(&id).await.expect("should remove");
+
+    let registry = FlowRegistry::new(alice);
+    assert_eq!(registry.list_active().await.expect("should list flows").len(), 1);
+    assert!(registry.resume(&id).await.expect("should query store").is_some());
+}
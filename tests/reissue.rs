@@ -0,0 +1,143 @@
+//! Tests for replacing stored credentials with re-issued credentials that
+//! supersede them.
+
+// Provider trait methods are async by contract even where the store is not.
+#![allow(clippy::unused_async_trait_impl)]
+
+mod provider;
+
+use std::sync::{Arc, Mutex};
+
+use credibil_holder::credential::{Credential, CredentialMetadata, SubjectClaims};
+use credibil_holder::provider::{Constraints, CredentialArchive, CredentialStorer};
+use credibil_holder::reissue::{self, ArchivedCredential};
+use credibil_holder::test_utils::date;
+use serde_json::json;
+
+use crate::provider as holder;
+
+fn credential(id: &str, subject: &str, role: &str, issued: i32) -> Credential {
+    Credential {
+        id: id.into(),
+        issuer: "https://issuer.example.com".into(),
+        type_: vec!["VerifiableCredential".into(), "EmployeeIDCredential".into()],
+        format: "jwt_vc_json".into(),
+        subject_claims: vec![SubjectClaims {
+            id: Some(subject.into()),
+            claims: json!({"role": role}).as_object().cloned().expect("should be an object"),
+        }],
+        issuance_date: date(issued),
+        ..Credential::default()
+    }
+}
+
+// An in-memory archive.
+#[derive(Clone, Default)]
+struct Archive(Arc<Mutex<Vec<ArchivedCredential>>>);
+
+impl Archive {
+    fn records(&self) -> Vec<ArchivedCredential> {
+        self.0.lock().expect("should lock").clone()
+    }
+}
+
+impl CredentialArchive for Archive {
+    async fn archive(&self, record: &ArchivedCredential) -> anyhow::Result<()> {
+        self.0.lock().expect("should lock").push(record.clone());
+        Ok(())
+    }
+
+    async fn unarchive(&self, id: &str) -> anyhow::Result<Option<ArchivedCredential>> {
+        let mut records = self.0.lock().expect("should lock");
+        let record = records.iter().position(|r| r.credential.id == id).map(|i| records.remove(i));
+        drop(records);
+        Ok(record)
+    }
+}
+
+// A store that cannot remove credentials.
+struct Failing(holder::Provider);
+
+impl CredentialStorer for Failing {
+    async fn save(&self, credential: &Credential) -> anyhow::Result<()> {
+        self.0.save(credential).await
+    }
+
+    async fn load(&self, id: &str) -> anyhow::Result<Option<Credential>> {
+        self.0.load(id).await
+    }
+
+    async fn find(&self, filter: Option<Constraints>) -> anyhow::Result<Vec<Credential>> {
+        self.0.find(filter).await
+    }
+
+    async fn remove(&self, _: &str) -> anyhow::Result<()> {
+        anyhow::bail!("store is read-only")
+    }
+}
+
+// A credential supersedes stored credentials of the same configuration and
+// subject that were not issued after it; the most recent is replaced.
+#[test]
+fn supersedes() {
+    let renewed = credential("urn:uuid:3", "did:example:alice", "manager", 2025);
+    let stored = [
+        credential("urn:uuid:1", "did:example:alice", "employee", 2023),
+        credential("urn:uuid:2", "did:example:alice", "employee", 2024),
+        credential("urn:uuid:4", "did:example:bob", "employee", 2024),
+        Credential {
+            format: "vc+sd-jwt".into(),
+            ..credential("urn:uuid:5", "did:example:alice", "employee", 2024)
+        },
+        credential("urn:uuid:6", "did:example:alice", "employee", 2026),
+    ]
+    .map(CredentialMetadata::from);
+
+    assert!(reissue::supersedes(&renewed, &stored[0]));
+    assert!(!reissue::supersedes(&renewed, &stored[2]));
+    assert!(!reissue::supersedes(&renewed, &stored[3]));
+    assert!(!reissue::supersedes(&renewed, &stored[4]));
+    assert!(!reissue::supersedes(&renewed, &CredentialMetadata::from(renewed.clone())));
+
+    let supersessions = reissue::superseded(&[renewed], &stored);
+    assert_eq!(supersessions.len(), 1);
+    assert_eq!(supersessions[0].credential_id, "urn:uuid:3");
+    assert_eq!(supersessions[0].replaces.id, "urn:uuid:2");
+}
+
+// Replacing a credential archives the old record and reports what changed.
+#[tokio::test]
+async fn replace() {
+    let store = holder::Provider::new(None, None);
+    let archive = Archive::default();
+    store.save(&credential("urn:uuid:1", "did:example:alice", "employee", 2024)).await.unwrap();
+
+    let renewed = credential("urn:uuid:2", "did:example:alice", "manager", 2025);
+    let replaced =
+        reissue::replace(&store, &archive, "urn:uuid:1", &renewed).await.expect("should replace");
+    assert_eq!(replaced.archived.superseded_by, "urn:uuid:2");
+    assert_eq!(replaced.diff.changed.len(), 1);
+    assert_eq!(archive.records(), vec![replaced.archived]);
+
+    let ids: Vec<String> = store.find(None).await.unwrap().into_iter().map(|c| c.id).collect();
+    assert_eq!(ids, vec!["urn:uuid:2"]);
+
+    let other = credential("urn:uuid:3", "did:example:bob", "manager", 2025);
+    reissue::replace(&store, &archive, "urn:uuid:2", &other).await.expect_err("should refuse");
+    assert_eq!(archive.records().len(), 1);
+}
+
+// A replacement that fails leaves the store and archive unchanged.
+#[tokio::test]
+async fn rollback() {
+    let store = Failing(holder::Provider::new(None, None));
+    let archive = Archive::default();
+    store.save(&credential("urn:uuid:1", "did:example:alice", "employee", 2024)).await.unwrap();
+
+    let renewed = credential("urn:uuid:2", "did:example:alice", "manager", 2025);
+    reissue::replace(&store, &archive, "urn:uuid:1", &renewed).await.expect_err("should fail");
+
+    assert_eq!(archive.records().len(), 0);
+    assert!(store.load("urn:uuid:1").await.unwrap().is_some());
+    assert!(store.load("urn:uuid:2").await.unwrap().is_none());
+}
@@ -0,0 +1,110 @@
+//! Tests for verifying request objects with more than one signature.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_holder::error::SignaturePolicyUnmet;
+use credibil_holder::parse::ParseMode;
+use credibil_holder::presentation::parse_request_object_jwt;
+use credibil_holder::presentation::signatures::{
+    SignaturePolicy, SignerRole, verify_request_object,
+};
+use credibil_holder::provider::Signer;
+use credibil_holder::test_utils::mock::MockProvider;
+use serde_json::{Value, json};
+
+const ANCHOR: &str = "did:example:trust-anchor";
+
+fn encode(value: &Value) -> String {
+    Base64UrlUnpadded::encode_string(value.to_string().as_bytes())
+}
+
+fn request(client_id: &str) -> Value {
+    json!({
+        "client_id": client_id,
+        "client_id_scheme": "did",
+        "client_metadata": {
+            "client_id": client_id,
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {"fields": [{"path": ["$.type"]}]}
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    })
+}
+
+// A request object signed by the verifier (the mock provider's key) and
+// countersigned by a trust anchor whose signature cannot be verified.
+async fn signed(provider: &MockProvider) -> (String, String) {
+    let kid = provider.verification_method().await.expect("should get verification method");
+    let verifier = kid.split('#').next().unwrap_or_default().to_string();
+    let payload = encode(&request(&verifier));
+
+    let protected = encode(&json!({"alg": "EdDSA", "typ": "oauth-authz-req+jwt", "kid": kid}));
+    let signature =
+        provider.try_sign(format!("{protected}.{payload}").as_bytes()).await.expect("should sign");
+    let signature = Base64UrlUnpadded::encode_string(&signature);
+    let anchor = encode(&json!({"alg": "EdDSA", "kid": format!("{ANCHOR}#key-1")}));
+
+    let jws = json!({
+        "payload": payload,
+        "signatures": [
+            {"protected": protected, "signature": signature},
+            {"protected": anchor, "signature": signature}
+        ]
+    });
+    (jws.to_string(), format!("{protected}.{payload}.{signature}"))
+}
+
+// Each signature is verified and reported with its signer and role.
+#[tokio::test]
+async fn signers() {
+    let provider = MockProvider::new();
+    let (jws, _) = signed(&provider).await;
+
+    let signed =
+        verify_request_object(&jws, provider.clone(), &SignaturePolicy::Any, ParseMode::default())
+            .await
+            .expect("should verify");
+    assert_eq!(signed.signatures.len(), 2);
+    assert_eq!(signed.signatures[0].role, SignerRole::Verifier);
+    assert!(signed.signatures[0].is_verified());
+    assert_eq!(signed.signatures[1].signer, ANCHOR);
+    assert_eq!(signed.signatures[1].role, SignerRole::Countersigner);
+    assert!(!signed.signatures[1].is_verified());
+    assert_eq!(signed.signers(), [signed.signatures[0].signer.as_str()]);
+    assert_eq!(signed.request.value.nonce, "n-0S6_WzA2Mj");
+}
+
+// The policy decides which signatures are required.
+#[tokio::test]
+async fn policies() {
+    let provider = MockProvider::new();
+    let (jws, compact) = signed(&provider).await;
+    let verify = |policy: SignaturePolicy| {
+        let (jws, provider) = (jws.clone(), provider.clone());
+        async move { verify_request_object(&jws, provider, &policy, ParseMode::default()).await }
+    };
+
+    verify(SignaturePolicy::Role(SignerRole::Verifier)).await.expect("verifier signed");
+    for policy in [
+        SignaturePolicy::All,
+        SignaturePolicy::Role(SignerRole::Countersigner),
+        SignaturePolicy::Signer(ANCHOR.into()),
+    ] {
+        let err = verify(policy).await.expect_err("should not meet policy");
+        let unmet = err.downcast_ref::<SignaturePolicyUnmet>().expect("should be typed");
+        assert_eq!(unmet.signers.len(), 1);
+        assert_eq!(unmet.errors.len(), 1);
+    }
+
+    // compact request objects have a single signature
+    let request = parse_request_object_jwt(&compact, provider).await.expect("should parse");
+    assert_eq!(request.nonce, "n-0S6_WzA2Mj");
+}
@@ -0,0 +1,117 @@
+//! Tests for validating and normalizing the response endpoint of presentation
+//! requests.
+
+use credibil_holder::error::InvalidResponseEndpoint;
+use credibil_holder::presentation::endpoint::ResponseEndpoint;
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use serde_json::{Value, json};
+
+fn request_object(endpoint: &Value) -> RequestObject {
+    let mut request = json!({
+        "client_id": "https://client.example.org",
+        "client_id_scheme": "did",
+        "client_metadata": {
+            "client_id": "https://client.example.org",
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {"fields": [{"path": ["$.type"]}]}
+            }]
+        },
+        "response_type": "vp_token"
+    });
+    for (name, value) in endpoint.as_object().expect("should be an object") {
+        request[name] = value.clone();
+    }
+    serde_json::from_value(request).expect("should parse request object")
+}
+
+// The endpoint required by the response mode is used, with trailing slashes
+// removed.
+#[test]
+fn response_modes() {
+    let endpoint = ResponseEndpoint::new(
+        Some("direct_post.jwt"),
+        Some("https://client.example.org/post/"),
+        None,
+    )
+    .expect("should be valid");
+    assert_eq!(endpoint, ResponseEndpoint::Post("https://client.example.org/post".into()));
+    assert_eq!(endpoint.post_uri(), Some("https://client.example.org/post"));
+
+    let endpoint = ResponseEndpoint::new(None, None, Some("https://client.example.org/cb"))
+        .expect("should be valid");
+    assert_eq!(endpoint.uri(), "https://client.example.org/cb");
+    assert_eq!(endpoint.post_uri(), None);
+
+    let local = ResponseEndpoint::new(Some("direct_post"), Some("http://localhost:8080/"), None)
+        .expect("should accept loopback");
+    assert_eq!(local.uri(), "http://localhost:8080");
+}
+
+// Requests with the wrong, both or neither endpoint, or an insecure one, are
+// rejected.
+#[test]
+fn invalid() {
+    let cases = [
+        (Some("direct_post"), None, None),
+        (Some("direct_post"), Some("https://a.example.org"), Some("https://a.example.org")),
+        (Some("fragment"), Some("https://a.example.org"), None),
+        (Some("query"), None, None),
+        (Some("direct_post"), Some("http://client.example.org/post"), None),
+        (Some("direct_post"), Some("http://localhost.example.org/post"), None),
+        (None, None, Some("client.example.org/cb")),
+    ];
+    for (mode, response_uri, redirect_uri) in cases {
+        let err =
+            ResponseEndpoint::new(mode, response_uri, redirect_uri).expect_err("should be invalid");
+        assert_eq!(err.response_mode, mode.unwrap_or("fragment"));
+    }
+}
+
+// Presentation flows check the endpoint when created and return the
+// normalized URI with the response.
+#[test]
+fn flow() {
+    let request = request_object(&json!({
+        "response_mode": "direct_post",
+        "response_uri": "https://client.example.org/post/"
+    }));
+    let flow = PresentationFlow::<NotAuthorized>::new(request).expect("should start flow");
+    let endpoint = flow.response_endpoint().expect("should be valid").expect("should be sent");
+    assert_eq!(endpoint.post_uri(), Some("https://client.example.org/post"));
+
+    let request = request_object(&json!({
+        "response_mode": "direct_post",
+        "redirect_uri": "https://client.example.org/cb"
+    }));
+    let err = PresentationFlow::<NotAuthorized>::new(request).expect_err("should be rejected");
+    let invalid = err.downcast_ref::<InvalidResponseEndpoint>().expect("should be typed");
+    assert_eq!(invalid.response_mode, "direct_post");
+}
+
+// A verifier using the `redirect_uri` client ID scheme can still ask for its
+// response to be posted: the client ID only stands in for a missing
+// `redirect_uri` in the redirect modes.
+#[test]
+fn redirect_uri_client_id() {
+    let request = json!({
+        "client_id": "https://client.example.org/cb",
+        "client_id_scheme": "redirect_uri",
+        "response_mode": "direct_post",
+        "response_uri": "https://client.example.org/post"
+    });
+    let endpoint = ResponseEndpoint::from_request(&request).expect("should be valid");
+    assert_eq!(endpoint, Some(ResponseEndpoint::Post("https://client.example.org/post".into())));
+
+    let request = json!({
+        "client_id": "redirect_uri:https://client.example.org/cb",
+        "response_mode": "fragment"
+    });
+    let endpoint = ResponseEndpoint::from_request(&request).expect("should be valid");
+    assert_eq!(endpoint, Some(ResponseEndpoint::Redirect("https://client.example.org/cb".into())));
+}
@@ -0,0 +1,79 @@
+//! Tests for returning the verifier's `state` in presentation responses.
+
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject, StatePolicy};
+use serde_json::json;
+
+fn request_object(state: Option<&str>) -> RequestObject {
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": {"jwt_vp_json": {"alg": ["ES256K", "EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "state": state,
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": "EmployeeIDCredential"}
+                    }]
+                }
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+fn flow(state: Option<&str>) -> PresentationFlow<NotAuthorized> {
+    PresentationFlow::<NotAuthorized>::new(request_object(state)).expect("should start flow")
+}
+
+// The request's state is returned unchanged by default.
+#[test]
+fn echo() {
+    let flow = flow(Some("af0ifjsldkj")).authorize(&[]);
+    let (mut request, _) = flow.create_response_request("vp.jwt");
+    assert_eq!(request.state.as_deref(), Some("af0ifjsldkj"));
+    flow.verify_state(&request).expect("should match state");
+
+    request.state = Some("other".into());
+    assert!(flow.verify_state(&request).is_err());
+    request.state = None;
+    assert!(flow.verify_state(&request).is_err());
+}
+
+// A request without a state is rejected when the profile requires one.
+#[test]
+fn required() {
+    assert!(flow(None).with_state_policy(StatePolicy::Required).is_err());
+
+    let flow = flow(Some("af0ifjsldkj"))
+        .with_state_policy(StatePolicy::Required)
+        .expect("should accept state")
+        .authorize(&[]);
+    let (request, _) = flow.create_response_request("vp.jwt");
+    assert_eq!(request.state.as_deref(), Some("af0ifjsldkj"));
+}
+
+// The state is left out of the response for verifiers that do not expect it.
+#[test]
+fn omit() {
+    let flow = flow(Some("af0ifjsldkj"))
+        .with_state_policy(StatePolicy::Omit)
+        .expect("should set policy")
+        .authorize(&[]);
+    let (mut request, _) = flow.create_response_request("vp.jwt");
+    assert_eq!(request.state, None);
+    flow.verify_state(&request).expect("should match state");
+
+    request.state = Some("af0ifjsldkj".into());
+    assert!(flow.verify_state(&request).is_err());
+}
@@ -0,0 +1,80 @@
+//! Tests that flows can be driven without tokio, using a simple
+//! single-threaded executor.
+mod provider;
+
+use credibil_holder::issuance::proof::{self, Payload, Verify};
+use credibil_holder::issuance::{CredentialResponseType, IssuanceFlowBuilder, OfferType, SendType};
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+use futures::executor::block_on;
+
+use crate::provider as holder;
+
+// Run a pre-authorized issuance flow to completion on the `futures` executor.
+#[test]
+fn futures_executor() {
+    block_on(async {
+        let request = CreateOfferRequest {
+            credential_issuer: CREDENTIAL_ISSUER.to_string(),
+            credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+            subject_id: Some(NORMAL_USER.to_string()),
+            grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+            tx_code_required: true,
+            send_type: SendType::ByVal,
+        };
+        let issuer_provider = issuer::Provider::new();
+        let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+            .await
+            .expect("should get offer");
+        let OfferType::Object(offer) = offer_resp.offer_type else {
+            panic!("expected CredentialOfferType::Object");
+        };
+        let provider = holder::Provider::new(Some(issuer_provider), None);
+
+        let metadata_request = MetadataRequest {
+            credential_issuer: offer.credential_issuer.clone(),
+            languages: None,
+        };
+        let issuer_metadata =
+            provider.metadata(metadata_request).await.expect("should get issuer metadata");
+        let pre_auth_code_grant =
+            offer.pre_authorized_code().expect("should get pre-authorized code");
+        let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+            .subject_id(NORMAL_USER)
+            .pre_authorized(offer, pre_auth_code_grant);
+        let state = state.accept(&None, offer_resp.tx_code);
+        let token_response =
+            provider.token(state.token_request()).await.expect("should get token response");
+        let mut state = state.token(token_response);
+
+        let identifiers = state
+            .get_token()
+            .authorization_details
+            .iter()
+            .flatten()
+            .flat_map(|auth| auth.credential_identifiers.clone())
+            .collect::<Vec<_>>();
+        let jwt = state.build_proof(&provider).await.expect("should build proof");
+
+        for (cfg_id, request) in state.credential_requests(&identifiers, &jwt) {
+            let credential_response =
+                provider.credential(request).await.expect("should get credentials");
+            let CredentialResponseType::Credential(vc_kind) = credential_response.response else {
+                panic!("expected a single credential");
+            };
+            let Payload::Vc { vc, issued_at } =
+                proof::verify(Verify::Vc(&vc_kind), provider.clone())
+                    .await
+                    .expect("should parse credential")
+            else {
+                panic!("expected Payload::Vc");
+            };
+            state
+                .add_credential(&vc, &vc_kind, &issued_at, &cfg_id, None, None)
+                .expect("should add credential");
+        }
+
+        assert_eq!(state.credentials().len(), 1);
+    });
+}
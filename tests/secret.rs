@@ -0,0 +1,40 @@
+//! Tests for handling secrets held in flow state.
+
+use credibil_holder::issuance::{IssuanceFlowBuilder, Issuer, Server};
+use credibil_holder::secret::{Secret, constant_time_eq};
+
+const METADATA: &str = include_str!("conformance/fixtures/issuer_metadata.json");
+
+// Secrets are redacted from debug output but serialize as plain strings so
+// flow state can be persisted and restored.
+#[test]
+fn secret_handling() {
+    let secret = Secret::from("123456");
+    assert_eq!(secret.expose(), "123456");
+    assert!(!format!("{secret:?}").contains("123456"));
+
+    let json = serde_json::to_string(&secret).expect("should serialize");
+    assert_eq!(json, "\"123456\"");
+    let restored: Secret = serde_json::from_str(&json).expect("should deserialize");
+    assert_eq!(restored, secret);
+}
+
+// Secrets are compared in constant time, including the state returned to the
+// wallet's redirect URI.
+#[test]
+fn constant_time_comparison() {
+    let secret = Secret::from("123456");
+    assert!(secret.matches("123456"));
+    assert!(!secret.matches("123457"));
+    assert!(!secret.matches("12345"));
+    assert!(constant_time_eq(b"nonce", b"nonce"));
+    assert!(!constant_time_eq(b"nonce", b"nonse"));
+
+    let issuer: Issuer = serde_json::from_str(METADATA).expect("should parse metadata");
+    let flow = IssuanceFlowBuilder::new("client", issuer)
+        .subject_id("subject")
+        .wallet_initiated(Server::default());
+    flow.verify_state(Some(&flow.id())).expect("should match state");
+    flow.verify_state(Some("other")).expect_err("should reject mismatched state");
+    flow.verify_state(None).expect_err("should reject missing state");
+}
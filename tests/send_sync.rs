@@ -0,0 +1,53 @@
+//! Tests that flows and agents can be shared between threads and tasks.
+mod provider;
+
+use std::sync::Arc;
+
+use credibil_holder::agent::{Flow, HolderAgent};
+use credibil_holder::cancel::CancellationToken;
+use credibil_holder::issuance::{
+    Accepted, AuthCode, IssuanceFlow, NotAccepted, PreAuthorized, WithOffer, WithToken,
+    WithoutOffer, WithoutToken,
+};
+use credibil_holder::outbox::Outbox;
+use credibil_holder::presentation::siop::IdTokenFlow;
+use credibil_holder::presentation::{Authorized, NotAuthorized, PresentationFlow};
+use credibil_holder::registry::FlowRegistry;
+use credibil_holder::test_utils::issuer::{CLIENT_ID, NORMAL_USER};
+use credibil_holder::test_utils::mock::MockProvider;
+
+use crate::provider as holder;
+
+const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+// Flow types, the agent and its helpers are `Send + Sync`.
+#[test]
+fn send_sync() {
+    assert_send_sync::<IssuanceFlow<WithOffer, PreAuthorized, NotAccepted, WithoutToken>>();
+    assert_send_sync::<IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithToken>>();
+    assert_send_sync::<IssuanceFlow<WithoutOffer, AuthCode, Accepted, WithToken>>();
+    assert_send_sync::<PresentationFlow<NotAuthorized>>();
+    assert_send_sync::<PresentationFlow<Authorized>>();
+    assert_send_sync::<IdTokenFlow>();
+    assert_send_sync::<Flow>();
+    assert_send_sync::<HolderAgent<MockProvider>>();
+    assert_send_sync::<FlowRegistry<holder::Provider>>();
+    assert_send_sync::<Outbox<MockProvider, holder::Provider>>();
+    assert_send_sync::<CancellationToken>();
+}
+
+// Flows read from the agent are shared rather than copied and can be used
+// from other tasks.
+#[tokio::test]
+async fn shared_flows() {
+    let provider = MockProvider::new();
+    let agent = HolderAgent::new(provider.clone(), CLIENT_ID);
+    let (offer, _) = provider.offer(&["EmployeeID_JWT"], false).await.expect("should get offer");
+    let id = agent.offer(offer, NORMAL_USER).await.expect("should start flow");
+
+    let flow = agent.flow(&id).expect("should have flow");
+    assert!(Arc::ptr_eq(&flow, &agent.flow(&id).expect("should have flow")));
+
+    let handle = tokio::spawn(async move { flow.id() });
+    assert_eq!(handle.await.expect("should join"), id);
+}
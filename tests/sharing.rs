@@ -0,0 +1,108 @@
+//! Tests for enforcing per-credential sharing policies when selecting
+//! credentials for presentation.
+
+use credibil_holder::credential::{Credential, Sharing, SharingPolicy};
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, RequestObject};
+use serde_json::json;
+
+const VERIFIER: &str = "https://client.example.org/post";
+
+fn flow() -> PresentationFlow<NotAuthorized> {
+    let request: RequestObject = serde_json::from_value(json!({
+        "client_id": VERIFIER,
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": VERIFIER,
+            "vp_formats": {"jwt_vp_json": {"alg": ["EdDSA"]}}
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {"fields": [{"path": ["$.type"]}]}
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": VERIFIER
+    }))
+    .expect("should parse request object");
+    PresentationFlow::<NotAuthorized>::new(request).expect("should start flow")
+}
+
+fn credential(id: &str, sharing_policy: SharingPolicy) -> Credential {
+    Credential {
+        id: id.into(),
+        sharing_policy,
+        ..Credential::default()
+    }
+}
+
+// Each policy flag removes the credential from the candidates unless the
+// circumstances permit it.
+#[test]
+fn policies() {
+    let credentials = vec![
+        credential("unrestricted", SharingPolicy::default()),
+        credential(
+            "manual",
+            SharingPolicy {
+                never_auto_present: true,
+                ..SharingPolicy::default()
+            },
+        ),
+        credential(
+            "verified",
+            SharingPolicy {
+                require_user_verification: true,
+                ..SharingPolicy::default()
+            },
+        ),
+        credential(
+            "trusted",
+            SharingPolicy {
+                trusted_verifiers: Some(vec!["https://other.example.org".into()]),
+                ..SharingPolicy::default()
+            },
+        ),
+    ];
+    let ids = |sharing: Sharing| {
+        let shareable = flow().shareable(credentials.clone(), sharing);
+        shareable.into_iter().map(|c| c.id).collect::<Vec<_>>()
+    };
+
+    assert_eq!(ids(Sharing::default()), ["unrestricted", "manual"]);
+    let automatic = Sharing {
+        automatic: true,
+        user_verified: true,
+    };
+    assert_eq!(ids(automatic), ["unrestricted", "verified"]);
+
+    let policy = SharingPolicy {
+        trusted_verifiers: Some(vec![VERIFIER.into()]),
+        ..SharingPolicy::default()
+    };
+    assert!(policy.permits(VERIFIER, Sharing::default()));
+}
+
+// Policies are stored with the credential, and credentials without one are
+// unrestricted.
+#[test]
+fn stored_policy() {
+    let stored = credential(
+        "manual",
+        SharingPolicy {
+            never_auto_present: true,
+            ..SharingPolicy::default()
+        },
+    );
+    let json = serde_json::to_value(&stored).expect("should serialize");
+    assert_eq!(json["sharing_policy"], json!({"never_auto_present": true}));
+
+    let unrestricted = serde_json::to_value(credential("any", SharingPolicy::default()))
+        .expect("should serialize");
+    assert!(unrestricted.get("sharing_policy").is_none());
+    let restored: Credential = serde_json::from_value(unrestricted).expect("should deserialize");
+    assert!(restored.sharing_policy.is_unrestricted());
+}
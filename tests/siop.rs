@@ -0,0 +1,113 @@
+//! Tests for responding to self-issued ID token (SIOPv2) requests.
+
+use credibil_holder::consent::{Consent, SigningOperation};
+use credibil_holder::infosec::jose::jws::JwsBuilder;
+use credibil_holder::presentation::siop::{
+    IdTokenFlow, SIOP_AUTHORIZATION_ENDPOINT, SiopMetadata, parse_id_token_request, verify_id_token,
+};
+use credibil_holder::provider::{ConsentGate, Signer, Verifier};
+use credibil_holder::test_utils::mock::MockProvider;
+use serde_json::json;
+
+const RP_ID: &str = "https://rp.example.com";
+
+// A consent gate that always agrees.
+struct Gate;
+
+impl ConsentGate for Gate {
+    #[allow(clippy::unused_async_trait_impl)]
+    async fn consent(&self, operation: &SigningOperation) -> anyhow::Result<Consent> {
+        assert_eq!(
+            *operation,
+            SigningOperation::IdToken {
+                client_id: RP_ID.into()
+            }
+        );
+        Ok(Consent::Granted)
+    }
+}
+
+fn request(scope: &str) -> String {
+    format!(
+        "siopv2://?response_type=id_token&client_id={rp}&scope={scope}\
+        &response_mode=direct_post&response_uri={rp}%2Fpost&nonce=n-0S6_WzA2Mj&state=af0ifjsldkj",
+        rp = urlencoding::encode(RP_ID)
+    )
+}
+
+// Respond to an ID token request end-to-end: parse the request, sign the ID
+// token with the holder's consent and send it to the relying party.
+#[tokio::test]
+async fn id_token() {
+    let provider = MockProvider::new();
+
+    let request = parse_id_token_request(&request("openid"))
+        .expect("should parse request")
+        .expect("should be an ID token request");
+    let flow = IdTokenFlow::new(request).expect("should start flow");
+
+    let kid = provider.verification_method().await.expect("should get verification method");
+    let claims = flow.claims(&kid);
+    assert_eq!(claims.aud, RP_ID);
+    assert_eq!(claims.nonce, "n-0S6_WzA2Mj");
+    assert_eq!(claims.iss, claims.sub);
+
+    let signer = flow.signer(&provider, &Gate);
+    let jws = JwsBuilder::new()
+        .payload(claims.clone())
+        .add_signer(&signer)
+        .build()
+        .await
+        .expect("should build jws");
+    let id_token = jws.encode().expect("should encode ID token");
+
+    let (response, uri) = flow.create_response(&id_token);
+    assert_eq!(uri.as_deref(), Some("https://rp.example.com/post"));
+    assert_eq!(response.state.as_deref(), Some("af0ifjsldkj"));
+    assert_eq!(
+        verify_id_token(&response.id_token, RP_ID, "n-0S6_WzA2Mj", provider.clone())
+            .await
+            .expect("should verify"),
+        claims
+    );
+    assert!(
+        verify_id_token(
+            &response.id_token,
+            "https://other.example.com",
+            "n-0S6_WzA2Mj",
+            provider.clone()
+        )
+        .await
+        .is_err()
+    );
+    assert!(
+        verify_id_token(&response.id_token, RP_ID, "replayed", provider.clone()).await.is_err()
+    );
+    provider.self_issued(uri.as_deref(), &response).await.expect("should send ID token");
+}
+
+// Requests that are not for an ID token, or without the `openid` scope, are
+// not handled by the SIOP flow.
+#[test]
+fn unsupported_request() {
+    let vp_request = request("openid").replace("response_type=id_token", "response_type=vp_token");
+    assert!(parse_id_token_request(&vp_request).expect("should parse").is_none());
+
+    let request = parse_id_token_request(&request("profile"))
+        .expect("should parse request")
+        .expect("should be an ID token request");
+    assert!(IdTokenFlow::new(request).is_err());
+}
+
+// The wallet's self-issued OP metadata advertises ID token support.
+#[test]
+fn metadata() {
+    let provider = MockProvider::new();
+    let metadata = SiopMetadata::new(provider.algorithm(), &["did:key"]);
+    let json = serde_json::to_value(&metadata).expect("should serialize");
+
+    assert_eq!(json["authorization_endpoint"], SIOP_AUTHORIZATION_ENDPOINT);
+    assert_eq!(json["response_types_supported"], json!(["id_token"]));
+    assert_eq!(json["subject_syntax_types_supported"], json!(["did:key"]));
+    assert_eq!(json["id_token_types_supported"], json!(["subject_signed_id_token"]));
+}
@@ -0,0 +1,70 @@
+//! Tests for encrypted flow snapshots.
+mod provider;
+
+use credibil_holder::issuance::{
+    Accepted, IssuanceFlow, IssuanceFlowBuilder, OfferType, PreAuthorized, SendType, WithOffer,
+    WithoutToken,
+};
+use credibil_holder::provider::{Issuer, MetadataRequest};
+use credibil_holder::secret::Secret;
+use credibil_holder::snapshot;
+use credibil_holder::test_utils::issuer::{self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+use credibil_vc::issuer::{CreateOfferRequest, GrantType};
+
+use crate::provider as holder;
+
+// A flow killed part way through can be restored from an encrypted snapshot
+// and completed. The PIN is not visible in the snapshot and modified
+// snapshots are rejected.
+#[tokio::test]
+async fn snapshot_restore() {
+    let request = CreateOfferRequest {
+        credential_issuer: CREDENTIAL_ISSUER.to_string(),
+        credential_configuration_ids: vec!["EmployeeID_JWT".to_string()],
+        subject_id: Some(NORMAL_USER.to_string()),
+        grant_types: Some(vec![GrantType::PreAuthorizedCode]),
+        tx_code_required: true,
+        send_type: SendType::ByVal,
+    };
+    let issuer_provider = issuer::Provider::new();
+    let offer_resp = credibil_vc::issuer::create_offer(issuer_provider.clone(), request)
+        .await
+        .expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+
+    let provider = holder::Provider::new(Some(issuer_provider), None);
+    let metadata_request = MetadataRequest {
+        credential_issuer: offer.credential_issuer.clone(),
+        languages: None,
+    };
+    let issuer_metadata =
+        provider.metadata(metadata_request).await.expect("should get issuer metadata");
+    let grant = offer.pre_authorized_code().expect("should get pre-authorized code");
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer)
+        .subject_id(NORMAL_USER)
+        .pre_authorized(offer, grant);
+    let pin = offer_resp.tx_code.expect("should have user code");
+    let state = state.accept(&None, Some(pin.clone()));
+
+    let blob = snapshot::snapshot(&state, &provider).await.expect("should snapshot");
+    assert!(!blob.windows(pin.len()).any(|w| w == pin.as_bytes()));
+
+    let mut tampered = blob.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(
+        snapshot::restore::<IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken>>(
+            &tampered, &provider
+        )
+        .await
+        .is_err()
+    );
+
+    let restored: IssuanceFlow<WithOffer, PreAuthorized, Accepted, WithoutToken> =
+        snapshot::restore(&blob, &provider).await.expect("should restore");
+    assert_eq!(restored.id(), state.id());
+    assert_eq!(restored.pin().map(Secret::expose), Some(pin.as_str()));
+    provider.token(restored.token_request()).await.expect("should get token");
+}
@@ -0,0 +1,145 @@
+//! Tests for checking the status of a batch of credentials.
+
+// Provider trait methods are async by contract even where the store is not.
+#![allow(clippy::unused_async_trait_impl)]
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::Utc;
+use credibil_holder::credential::Credential;
+use credibil_holder::issuance::{CredentialSubject, VerifiableCredential};
+use credibil_holder::presentation::proof::{self, Payload, W3cFormat};
+use credibil_holder::provider::{DidResolver, Document, Signer, StatusListResolver};
+use credibil_holder::status::check_statuses;
+use credibil_holder::test_utils::mock::MockProvider;
+use credibil_holder::{Kind, Quota};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde_json::{Map, Value, json};
+
+const LIST_A: &str = "https://issuer.example.com/statuslists/a";
+const LIST_B: &str = "https://issuer.example.com/statuslists/b";
+
+// Serves status lists and counts how many times they are retrieved.
+#[derive(Clone)]
+struct Lists {
+    provider: MockProvider,
+    served: HashMap<String, String>,
+    fetches: Arc<AtomicUsize>,
+}
+
+impl StatusListResolver for Lists {
+    async fn status_list(&self, url: &str) -> anyhow::Result<String> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        self.served.get(url).cloned().ok_or_else(|| anyhow::anyhow!("no status list at {url}"))
+    }
+}
+
+impl DidResolver for Lists {
+    async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+        self.provider.resolve(url).await
+    }
+}
+
+// Sign a credential with the given subject claims and status entries.
+async fn sign(
+    provider: &MockProvider, type_: &str, claims: Value, status: Option<Value>,
+) -> String {
+    let kid = provider.verification_method().await.expect("should get verification method");
+    let did = kid.split('#').next().unwrap_or_default().to_string();
+    let claims: Map<String, Value> = serde_json::from_value(claims).expect("should be an object");
+    let vc = VerifiableCredential {
+        context: vec![Kind::String("https://www.w3.org/ns/credentials/v2".into())],
+        type_: Quota::Many(vec!["VerifiableCredential".into(), type_.into()]),
+        issuer: Kind::String(did.clone()),
+        id: Some(format!("urn:example:{type_}")),
+        valid_from: Some(Utc::now()),
+        credential_subject: Quota::One(CredentialSubject {
+            id: Some(did),
+            claims,
+        }),
+        credential_status: status
+            .map(|status| serde_json::from_value(status).expect("should parse status")),
+        ..VerifiableCredential::default()
+    };
+    let payload = Payload::Vc {
+        vc,
+        issued_at: Utc::now().timestamp(),
+    };
+    proof::create(W3cFormat::JwtVcJson, payload, provider).await.expect("should sign credential")
+}
+
+// Sign a revocation status list with the given indexes set.
+async fn status_list(provider: &MockProvider, revoked: &[usize]) -> String {
+    let mut bits = vec![0u8; 16];
+    for index in revoked {
+        bits[index / 8] |= 0x80 >> (index % 8);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&bits).expect("should compress");
+    let compressed = encoder.finish().expect("should compress");
+    let claims = json!({
+        "type": "BitstringStatusList",
+        "statusPurpose": "revocation",
+        "encodedList": format!("u{}", Base64UrlUnpadded::encode_string(&compressed)),
+    });
+    sign(provider, "BitstringStatusListCredential", claims, None).await
+}
+
+// A credential with a revocation entry in the list.
+async fn credential(provider: &MockProvider, list: &str, index: usize) -> Credential {
+    // credibil-vc reads the entry's fields in snake case
+    let status = json!({
+        "id": format!("{list}#{index}"),
+        "type": "BitstringStatusListEntry",
+        "status_purpose": "revocation",
+        "status_list_index": index,
+        "status_list_credential": list,
+    });
+    Credential {
+        id: format!("{list}#{index}"),
+        issued: sign(provider, "EmployeeIDCredential", json!({}), Some(status)).await,
+        ..Credential::default()
+    }
+}
+
+// Each status list is retrieved once and each credential gets its own result.
+#[tokio::test]
+async fn check_batch() {
+    let provider = MockProvider::new();
+    let lists = Lists {
+        served: HashMap::from([
+            (LIST_A.to_string(), status_list(&provider, &[3]).await),
+            (LIST_B.to_string(), status_list(&provider, &[0]).await),
+        ]),
+        provider: provider.clone(),
+        fetches: Arc::new(AtomicUsize::new(0)),
+    };
+
+    let no_status = Credential {
+        id: "no-status".into(),
+        issued: sign(&provider, "EmployeeIDCredential", json!({}), None).await,
+        ..Credential::default()
+    };
+    let credentials = vec![
+        credential(&provider, LIST_A, 3).await,
+        credential(&provider, LIST_A, 4).await,
+        credential(&provider, LIST_B, 0).await,
+        no_status,
+        credential(&provider, "https://issuer.example.com/statuslists/missing", 0).await,
+    ];
+
+    let results = check_statuses(&credentials, lists.clone()).await;
+    assert_eq!(results.len(), credentials.len());
+    let set = |i: usize| results[i].as_ref().map(Vec::len).expect("should check status");
+    assert_eq!(set(0), 1);
+    assert_eq!(set(1), 0);
+    assert_eq!(set(2), 1);
+    assert_eq!(set(3), 0);
+    assert!(results[4].is_err());
+    assert_eq!(lists.fetches.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,111 @@
+//! Tests for recording and exporting diagnostic transcripts.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_holder::transcript::profile::{Profile, Requirement};
+use credibil_holder::transcript::{EntryKind, REDACTED, Transcript};
+use serde_json::{Value, json};
+
+// An (unsigned) JWT with the given header.
+fn jwt(header: &Value) -> String {
+    let header = Base64UrlUnpadded::encode_string(header.to_string().as_bytes());
+    format!("{header}.eyJub25jZSI6IjEyMyJ9.c2lnbmF0dXJl")
+}
+
+// Exchanges, errors and transitions are recorded in order and secrets are
+// redacted before export.
+#[test]
+fn record_and_export() {
+    let mut transcript = Transcript::new("flow-1");
+    transcript.transition("offered");
+    transcript.request(
+        "token",
+        &json!({
+            "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+            "pre-authorized_code": "adhjhdjajkdkhjhdj",
+            "tx_code": "1234"
+        }),
+    );
+    transcript.response(
+        "token",
+        &json!({
+            "access_token": "eyJhbGciOiJSUzI1NiJ9",
+            "token_type": "Bearer",
+            "expires_in": 86400
+        }),
+    );
+    transcript.request("credential", &json!({"proof": {"proof_type": "jwt", "jwt": "eyJ"}}));
+    transcript.error("credential", &anyhow::anyhow!("invalid_proof: nonce is stale"));
+
+    let kinds: Vec<EntryKind> = transcript.entries.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            EntryKind::Transition,
+            EntryKind::Request,
+            EntryKind::Response,
+            EntryKind::Request,
+            EntryKind::Error
+        ]
+    );
+    assert!(transcript.entries[2].elapsed_ms.is_some());
+    assert!(transcript.entries[4].elapsed_ms.is_some());
+
+    let exported: Value =
+        serde_json::from_str(&transcript.export().expect("should export")).expect("should be JSON");
+    assert_eq!(exported["flow_id"], "flow-1");
+    assert_eq!(exported["entries"][1]["body"]["pre-authorized_code"], REDACTED);
+    assert_eq!(exported["entries"][1]["body"]["tx_code"], REDACTED);
+    assert_eq!(exported["entries"][2]["body"]["access_token"], REDACTED);
+    assert_eq!(exported["entries"][2]["body"]["token_type"], "Bearer");
+    assert_eq!(exported["entries"][3]["body"]["proof"], REDACTED);
+    assert_eq!(exported["entries"][4]["body"], "invalid_proof: nonce is stale");
+}
+
+// JOSE headers are kept when the JWTs carrying them are redacted.
+#[test]
+fn jose_headers() {
+    let mut transcript = Transcript::new("flow-1");
+    let header = json!({"alg": "ES256", "typ": "openid4vci-proof+jwt", "kid": "did:example:1#0"});
+    transcript.request("credential", &json!({"proof": {"proof_type": "jwt", "jwt": jwt(&header)}}));
+
+    let entry = &transcript.entries[0];
+    assert_eq!(entry.body.as_ref().expect("should have body")["proof"], REDACTED);
+    assert_eq!(entry.headers["/proof/jwt"], header);
+    assert_eq!(entry.headers.len(), 1);
+}
+
+// Requests that do not satisfy the HAIP profile are reported as deviations.
+#[test]
+fn haip_deviations() {
+    let mut transcript = Transcript::new("flow-1");
+    transcript.request(
+        "token",
+        &json!({"grant_type": "authorization_code", "code": "abc", "code_verifier": "xyz"}),
+    );
+    let header = json!({"alg": "EdDSA", "typ": "openid4vci-proof+jwt"});
+    transcript.request("credential", &json!({"proof": {"proof_type": "jwt", "jwt": jwt(&header)}}));
+    transcript.request("presentation", &json!({"vp_token": [jwt(&json!({"alg": "ES256"}))]}));
+
+    let deviations = Profile::haip().check(&transcript);
+    assert_eq!(deviations.len(), 2);
+    assert_eq!(deviations[0].entry, 1);
+    assert_eq!(deviations[0].found, Some(json!("EdDSA")));
+    assert_eq!(
+        deviations[0].to_string(),
+        r#"credential request (entry 1): header `alg` of JWTs at `/proof` must be one of ["ES256"], found "EdDSA""#
+    );
+    assert_eq!(deviations[1].label, "presentation");
+    assert_eq!(deviations[1].found, None);
+
+    // conditional requirements only apply when their condition is met
+    let mut transcript = Transcript::new("flow-2");
+    transcript.request("token", &json!({"grant_type": "authorization_code", "code": "abc"}));
+    transcript.request(
+        "token",
+        &json!({"grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code"}),
+    );
+    let profile = Profile::new("custom")
+        .with_requirement(Requirement::parameter("token", "/tx_code").absent());
+    assert_eq!(Profile::haip().check(&transcript).len(), 1);
+    assert_eq!(profile.check(&transcript).len(), 0);
+}
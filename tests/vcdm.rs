@@ -0,0 +1,137 @@
+//! Tests for normalizing credentials issued under VCDM 1.1 and 2.0.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use credibil_holder::credential::{Credential, DataModel, Validity};
+use credibil_holder::test_utils::date;
+use serde_json::{Value, json};
+
+// An (unsigned) JWT with the given claims.
+fn jwt(claims: &Value) -> String {
+    let header = Base64UrlUnpadded::encode_string(br#"{"alg":"EdDSA","typ":"JWT"}"#);
+    let payload = Base64UrlUnpadded::encode_string(claims.to_string().as_bytes());
+    format!("{header}.{payload}.c2lnbmF0dXJl")
+}
+
+// A VCDM 1.1 credential's validity is read from `issuanceDate` and
+// `expirationDate`.
+#[test]
+fn vcdm_1_1() {
+    let token = jwt(&json!({
+        "iss": "did:example:issuer",
+        "vc": {
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+            "issuanceDate": "2024-01-01T00:00:00Z",
+            "expirationDate": "2026-01-01T00:00:00Z",
+        }
+    }));
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V1_1);
+    assert_eq!(validity.valid_from, Some(date(2024)));
+    assert_eq!(validity.valid_until, Some(date(2026)));
+}
+
+// The JWT `exp` claim stands in for a 1.1 `expirationDate` that has been
+// removed from the credential.
+#[test]
+fn vcdm_1_1_jwt_claims() {
+    let token = jwt(&json!({
+        "exp": date(2026).timestamp(),
+        "vc": {
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+        }
+    }));
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V1_1);
+    assert_eq!(validity.valid_from, None);
+    assert_eq!(validity.valid_until, Some(date(2026)));
+}
+
+// A VCDM 2.0 credential's validity is read from `validFrom` and `validUntil`.
+// With an unknown context, a 1.1 `issuanceDate` identifies the version.
+#[test]
+fn vcdm_2_0() {
+    let token = jwt(&json!({
+        "exp": date(2030).timestamp(),
+        "vc": {
+            "@context": "https://www.w3.org/ns/credentials/v2",
+            "validFrom": "2024-01-01T00:00:00Z",
+            "validUntil": "2026-01-01T00:00:00Z",
+        }
+    }));
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V2_0);
+    assert_eq!(validity.valid_from, Some(date(2024)));
+    assert_eq!(validity.valid_until, Some(date(2026)));
+
+    let token = jwt(&json!({
+        "vc": {
+            "@context": ["https://example.com/context"],
+            "issuanceDate": "2024-01-01T00:00:00Z",
+        }
+    }));
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V1_1);
+
+    let token = jwt(&json!({"vc": {"issuanceDate": "yesterday"}}));
+    assert!(Validity::from_jwt(&token).is_err());
+    assert!(Validity::from_jwt("not a jwt").is_err());
+}
+
+// Unset dates serialized as `null` are absent rather than invalid.
+#[test]
+fn null_dates() {
+    let token = jwt(&json!({
+        "vc": {
+            "@context": "https://www.w3.org/ns/credentials/v2",
+            "type": ["VerifiableCredential"],
+            "validFrom": null,
+            "validUntil": null,
+        }
+    }));
+    let validity = Validity::from_jwt(&token).expect("should read validity");
+    assert_eq!(validity.data_model, DataModel::V2_0);
+    assert_eq!(validity.valid_from, None);
+    assert_eq!(validity.valid_until, None);
+}
+
+// Credentials are only valid within their validity period, which starts at
+// issuance when no `valid_from` date is given.
+#[test]
+fn validity_period() {
+    let credential = Credential {
+        issuance_date: date(2024),
+        valid_until: Some(date(2026)),
+        ..Credential::default()
+    };
+    assert!(!credential.is_valid_at(date(2023)));
+    assert!(credential.is_valid_at(date(2025)));
+    assert!(!credential.is_valid_at(date(2026)));
+    assert!(credential.is_expired());
+
+    let credential = Credential {
+        valid_from: Some(date(2030)),
+        ..credential
+    };
+    assert!(!credential.is_valid_at(date(2025)));
+}
+
+// Credentials stored before the data model version was recorded are read as
+// VCDM 2.0.
+#[test]
+fn stored_default() {
+    let stored = json!({
+        "id": "urn:uuid:1234",
+        "issuer": "https://issuer.example.com",
+        "issuer_name": "Example",
+        "issued": "eyJ",
+        "type": ["VerifiableCredential"],
+        "format": "jwt_vc_json",
+        "claim_definitions": null,
+        "subject_claims": [],
+        "issuance_date": "2024-01-01T00:00:00Z",
+    });
+    let credential: Credential = serde_json::from_value(stored).expect("should deserialize");
+    assert_eq!(credential.data_model, DataModel::V2_0);
+}
@@ -0,0 +1,277 @@
+//! Tests for adapting requests and responses to the OID4VP dialect used by
+//! the verifier.
+
+use credibil_holder::presentation::compat::{Dialect, PexVersion};
+use credibil_holder::presentation::proof::Payload;
+use credibil_holder::presentation::{NotAuthorized, PresentationFlow, parse_request_object};
+use credibil_holder::provider::CredentialStorer;
+use credibil_holder::test_utils::mock::MockProvider;
+use credibil_vc::Kind;
+use serde_json::{Map, Value, json};
+
+const KID: &str = "did:example:holder#key-0";
+const FIXTURE: &str = include_str!("conformance/fixtures/request_object_by_value.json");
+
+fn dcql_request(client_id: &str) -> Value {
+    json!({
+        "response_type": "vp_token",
+        "client_id": client_id,
+        "response_mode": "direct_post",
+        "response_uri": "https://verifier.example.com/post",
+        "nonce": "n-0S6_WzA2Mj",
+        "state": "af0ifjsldkj",
+        "dcql_query": {
+            "credentials": [{
+                "id": "employee",
+                "format": "jwt_vc_json",
+                "meta": {
+                    "type_values": [["VerifiableCredential", "EmployeeIDCredential"]]
+                },
+                "claims": [
+                    {"path": ["credentialSubject", "family_name"]},
+                    {"path": ["credentialSubject", "degrees", null, "type"]},
+                    {"path": ["credentialSubject", "status"], "values": ["active"]}
+                ]
+            }]
+        }
+    })
+}
+
+fn pex_v1_request() -> Value {
+    json!({
+        "response_type": "vp_token",
+        "client_id": "https://verifier.example.com/post",
+        "client_id_scheme": "redirect_uri",
+        "response_mode": "direct_post",
+        "response_uri": "https://verifier.example.com/post",
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "employee",
+            "format": {"jwt_vc": ["ES256K", "EdDSA"]},
+            "input_descriptors": [{
+                "id": "employee_id",
+                "schema": [
+                    {"uri": "https://www.w3.org/2018/credentials#VerifiableCredential"},
+                    {"uri": "https://credibil.io/schemas#EmployeeIDCredential"}
+                ]
+            }]
+        }
+    })
+}
+
+// The dialect is detected from the request content.
+#[test]
+fn detect() {
+    let fixture: Value = serde_json::from_str(FIXTURE).expect("should parse fixture");
+    let request = &fixture["expected"]["request_object"];
+    assert_eq!(Dialect::detect(request), Dialect::PresentationExchange);
+
+    let request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    assert_eq!(Dialect::detect(&request), Dialect::Dcql);
+
+    let request = dcql_request("decentralized_identifier:did:example:verifier");
+    assert_eq!(Dialect::detect(&request), Dialect::DcqlMultiple);
+
+    let mut request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    request["dcql_query"]["credentials"][0]["multiple"] = json!(false);
+    assert_eq!(Dialect::detect(&request), Dialect::Dcql);
+    request["dcql_query"]["credentials"][0]["multiple"] = json!(true);
+    assert_eq!(Dialect::detect(&request), Dialect::DcqlMultiple);
+}
+
+// A DCQL query is converted to a presentation definition and the client ID
+// scheme derived from the client ID prefix.
+#[test]
+fn dcql_request_object() {
+    let client_id = "redirect_uri:https://verifier.example.com/post";
+    let request = dcql_request(client_id);
+    let request_object =
+        Dialect::detect(&request).request_object(request).expect("should convert request");
+
+    let json = serde_json::to_value(&request_object).expect("should serialize");
+    assert_eq!(json["client_id"], client_id);
+    assert_eq!(json["client_id_scheme"], "redirect_uri");
+
+    let descriptor = &json["presentation_definition"]["input_descriptors"][0];
+    assert_eq!(descriptor["id"], "employee");
+    assert_eq!(descriptor["format"], json!({"jwt_vc_json": {}}));
+    assert_eq!(
+        descriptor["constraints"]["fields"],
+        json!([
+            {"path": ["$.type"], "filter": {"type": "string", "const": "EmployeeIDCredential"}},
+            {"path": ["$.credentialSubject.family_name"]},
+            {"path": ["$.credentialSubject.degrees[*].type"]},
+            {
+                "path": ["$.credentialSubject.status"],
+                "filter": {"type": "string", "const": "active"}
+            }
+        ])
+    );
+
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object).expect("should start flow");
+    let constraints = flow.filter().expect("should get filter");
+    assert_eq!(constraints.fields.expect("should have fields").len(), 4);
+}
+
+// DCQL queries that cannot be expressed as a presentation definition are
+// rejected rather than loosened.
+#[test]
+fn untranslatable_query() {
+    let client_id = "redirect_uri:https://verifier.example.com/post";
+    let request = dcql_request(client_id);
+    assert!(Dialect::Dcql.request_object(request).is_ok());
+
+    let mut request = dcql_request(client_id);
+    request["dcql_query"]["credentials"][0]["claims"][2]["values"] = json!(["active", "retired"]);
+    assert!(Dialect::Dcql.request_object(request).is_err(), "should reject several values");
+
+    let mut request = dcql_request(client_id);
+    request["dcql_query"]["credentials"][0]["claims"][2]["values"] = json!([true]);
+    assert!(Dialect::Dcql.request_object(request).is_err(), "should reject non-string values");
+
+    let mut request = dcql_request(client_id);
+    request["dcql_query"]["credentials"][0]["claim_sets"] = json!([["a"]]);
+    assert!(Dialect::Dcql.request_object(request).is_err(), "should reject claim sets");
+
+    let mut request = dcql_request(client_id);
+    request["dcql_query"]["credential_sets"] = json!([{"options": [["employee"]]}]);
+    assert!(Dialect::Dcql.request_object(request).is_err(), "should reject credential sets");
+}
+
+// Requests without a presentation definition or DCQL query are rejected.
+#[test]
+fn no_query() {
+    let mut request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    request.as_object_mut().expect("should be an object").remove("dcql_query");
+    assert!(Dialect::Dcql.request_object(request).is_err());
+}
+
+// URL-encoded requests are parsed in either dialect.
+#[test]
+fn parse_url() {
+    let request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    let url = format!(
+        "openid4vp://?response_type=vp_token&client_id={}&nonce=n-0S6_WzA2Mj\
+        &response_mode=direct_post&response_uri={}&dcql_query={}",
+        urlencoding::encode(request["client_id"].as_str().expect("should be a string")),
+        urlencoding::encode("https://verifier.example.com/post"),
+        urlencoding::encode(&request["dcql_query"].to_string()),
+    );
+    let request_object = parse_request_object(&url)
+        .expect("should parse request")
+        .expect("should be a request object");
+    assert_eq!(request_object.nonce, "n-0S6_WzA2Mj");
+
+    let url = "openid://?response_type=id_token&client_id=https%3A%2F%2Frp.example.com";
+    assert!(parse_request_object(url).expect("should parse").is_none());
+}
+
+// The `vp_token` is shaped for the verifier's dialect.
+#[test]
+fn response_form() {
+    let request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    let request_object = Dialect::Dcql.request_object(request).expect("should convert request");
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object)
+        .expect("should start flow")
+        .authorize(&[]);
+    let (response, _) = flow.create_response_request("eyJhbGciOiJFUzI1NiJ9.e30.c2ln");
+
+    let form = Dialect::PresentationExchange.response_form(&response).expect("should encode");
+    assert_eq!(form["vp_token"], "eyJhbGciOiJFUzI1NiJ9.e30.c2ln");
+    assert!(form.contains_key("presentation_submission"));
+    assert_eq!(form["state"], "af0ifjsldkj");
+
+    let form = Dialect::Dcql.response_form(&response).expect("should encode");
+    let vp_token: Value = serde_json::from_str(&form["vp_token"]).expect("should be JSON");
+    assert_eq!(vp_token, json!({"employee": "eyJhbGciOiJFUzI1NiJ9.e30.c2ln"}));
+    assert!(!form.contains_key("presentation_submission"));
+
+    let form = Dialect::DcqlMultiple.response_form(&response).expect("should encode");
+    let vp_token: Value = serde_json::from_str(&form["vp_token"]).expect("should be JSON");
+    assert_eq!(vp_token, json!({"employee": ["eyJhbGciOiJFUzI1NiJ9.e30.c2ln"]}));
+}
+
+// Each DCQL credential query is answered with a presentation of the
+// credentials matching it.
+#[tokio::test]
+async fn query_response_form() {
+    let provider = MockProvider::new();
+    let employee = provider.seed("EmployeeIDCredential", Map::new()).await;
+    let developer = provider.seed("DeveloperCredential", Map::new()).await;
+    let credentials =
+        [employee.expect("should seed credential"), developer.expect("should seed credential")];
+
+    let mut request = dcql_request("redirect_uri:https://verifier.example.com/post");
+    request["dcql_query"]["credentials"] = json!([
+        {"id": "employee", "meta": {"type_values": [["EmployeeIDCredential"]]}},
+        {"id": "developer", "meta": {"type_values": [["DeveloperCredential"]]}}
+    ]);
+    let request_object = Dialect::Dcql.request_object(request).expect("should convert request");
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object)
+        .expect("should start flow")
+        .authorize(&credentials);
+
+    let Payload::Vp { vp, .. } =
+        flow.query_payload("developer", KID).expect("should build payload")
+    else {
+        panic!("should be a presentation payload");
+    };
+    let presented = vp.verifiable_credential.expect("should have credentials");
+    assert_eq!(presented, vec![Kind::String(credentials[1].issued.clone())]);
+
+    let jwts = ["employee.jwt".to_string(), "developer.jwt".to_string()];
+    let (response, _) = flow.create_query_response_request(&jwts).expect("should create response");
+    let form = Dialect::Dcql.response_form(&response).expect("should encode");
+    let vp_token: Value = serde_json::from_str(&form["vp_token"]).expect("should be JSON");
+    assert_eq!(vp_token, json!({"employee": "employee.jwt", "developer": "developer.jwt"}));
+
+    // a single presentation cannot answer both queries
+    let (response, _) = flow.create_response_request("all.jwt");
+    assert!(Dialect::Dcql.response_form(&response).is_err());
+}
+
+// Presentation Exchange v1 schemas are converted to type constraints and v1
+// formats to the current identifiers and shape.
+#[test]
+fn pex_v1_definition() {
+    let request = pex_v1_request();
+    let definition = &request["presentation_definition"];
+    assert_eq!(PexVersion::detect(definition), PexVersion::V1);
+
+    let request_object =
+        Dialect::detect(&request).request_object(request).expect("should convert request");
+    let json = serde_json::to_value(&request_object).expect("should serialize");
+    let definition = &json["presentation_definition"];
+    assert_eq!(PexVersion::detect(definition), PexVersion::V2);
+
+    let descriptor = &definition["input_descriptors"][0];
+    assert!(descriptor.get("schema").is_none());
+    assert_eq!(
+        descriptor["constraints"]["fields"],
+        json!([
+            {"path": ["$.type"], "filter": {"type": "string", "const": "EmployeeIDCredential"}}
+        ])
+    );
+
+    let converted = PexVersion::V1
+        .presentation_definition(pex_v1_request()["presentation_definition"].clone())
+        .expect("should convert definition");
+    assert_eq!(converted["format"], json!({"jwt_vc_json": {"alg": ["ES256K", "EdDSA"]}}));
+}
+
+// Credentials are matched against a converted v1 definition as for v2.
+#[tokio::test]
+async fn pex_v1_matches() {
+    let provider = MockProvider::new();
+    provider.seed("EmployeeIDCredential", Map::new()).await.expect("should seed credential");
+    provider.seed("DeveloperCredential", Map::new()).await.expect("should seed credential");
+
+    let request = pex_v1_request();
+    let request_object =
+        Dialect::detect(&request).request_object(request).expect("should convert request");
+    let flow = PresentationFlow::<NotAuthorized>::new(request_object).expect("should start flow");
+    let matches = provider.find(Some(flow.filter().expect("should get filter"))).await;
+    let matches = matches.expect("should find credentials");
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0].type_.contains(&"EmployeeIDCredential".to_string()));
+}
@@ -0,0 +1,149 @@
+//! Tests for negotiating the presentation format with a verifier.
+
+use credibil_holder::credential::Credential;
+use credibil_holder::error::NoCommonFormat;
+use credibil_holder::metadata::WalletMetadata;
+use credibil_holder::presentation::{Authorized, NotAuthorized, PresentationFlow, RequestObject};
+use credibil_holder::provider::Signer;
+use credibil_holder::test_utils::mock::MockProvider;
+use serde_json::{Value, json};
+
+fn request_object(vp_formats: &Value) -> RequestObject {
+    serde_json::from_value(json!({
+        "client_id": "https://client.example.org/post",
+        "client_id_scheme": "redirect_uri",
+        "client_metadata": {
+            "client_id": "https://client.example.org/post",
+            "vp_formats": vp_formats
+        },
+        "nonce": "n-0S6_WzA2Mj",
+        "presentation_definition": {
+            "id": "vp token example",
+            "input_descriptors": [{
+                "id": "EmployeeID_JWT",
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": "EmployeeIDCredential"}
+                    }]
+                }
+            }]
+        },
+        "response_mode": "direct_post",
+        "response_type": "vp_token",
+        "response_uri": "https://client.example.org/post"
+    }))
+    .expect("should parse request object")
+}
+
+fn authorized(vp_formats: &Value, credential_format: &str) -> PresentationFlow<Authorized> {
+    let credential = Credential {
+        id: "EmployeeID".into(),
+        format: credential_format.into(),
+        ..Credential::default()
+    };
+    PresentationFlow::<NotAuthorized>::new(request_object(vp_formats))
+        .expect("should start flow")
+        .authorize(&[credential])
+}
+
+// Client metadata listing formats `RequestObject` cannot represent.
+fn metadata(vp_formats: &Value) -> Value {
+    json!({"client_id": "https://client.example.org/post", "vp_formats": vp_formats})
+}
+
+// The wallet's signing algorithm as it appears in metadata.
+fn alg() -> Value {
+    serde_json::to_value(MockProvider::new().algorithm()).expect("should serialize algorithm")
+}
+
+// A verifier accepting the wallet's algorithm for `jwt_vp_json` gets a
+// `jwt_vp_json` presentation.
+#[test]
+fn common_format() {
+    let algorithm = MockProvider::new().algorithm();
+    let flow = authorized(&json!({"jwt_vp_json": {"alg": [alg()]}}), "jwt_vc_json");
+
+    let negotiated = flow
+        .negotiate(&WalletMetadata::new(algorithm.clone()), &algorithm)
+        .expect("should negotiate");
+    assert_eq!(negotiated.format, "jwt_vp_json");
+    assert!(negotiated.algorithm_listed);
+}
+
+// A verifier listing only credential formats does not constrain the
+// presentation format.
+#[test]
+fn credential_formats_only() {
+    let algorithm = MockProvider::new().algorithm();
+    let metadata = metadata(&json!({"jwt_vc_json": {"alg": ["ES256"]}}));
+
+    let flow = authorized(&json!({}), "jwt_vc_json");
+    let negotiated = flow
+        .negotiate_metadata(&metadata, &WalletMetadata::new(algorithm.clone()), &algorithm)
+        .expect("should negotiate");
+    assert_eq!(negotiated.format, "jwt_vp_json");
+}
+
+// A verifier that does not list the wallet's signing algorithm still gets a
+// presentation in a format it accepts.
+#[test]
+fn unlisted_algorithm() {
+    let algorithm = MockProvider::new().algorithm();
+    let flow = authorized(&json!({"jwt_vp_json": {"alg": ["ES256K"]}}), "jwt_vc_json");
+
+    let negotiated = flow
+        .negotiate(&WalletMetadata::new(algorithm.clone()), &algorithm)
+        .expect("should negotiate");
+    assert_eq!(negotiated.format, "jwt_vp_json");
+    assert!(!negotiated.algorithm_listed);
+}
+
+// A format the verifier lists the wallet's algorithm for is preferred.
+#[test]
+fn listed_algorithm_preferred() {
+    let algorithm = MockProvider::new().algorithm();
+    let metadata = metadata(&json!({
+        "jwt_vp_json": {"alg": ["ES256K"]},
+        "jwt_vp": {"alg": [alg()]}
+    }));
+
+    let mut wallet = WalletMetadata::new(algorithm.clone());
+    let support = wallet.vp_formats_supported["jwt_vp_json"].clone();
+    wallet.vp_formats_supported.insert("jwt_vp".into(), support);
+
+    let flow = authorized(&json!({}), "jwt_vc_json");
+    let negotiated =
+        flow.negotiate_metadata(&metadata, &wallet, &algorithm).expect("should negotiate");
+    assert_eq!(negotiated.format, "jwt_vp");
+    assert!(negotiated.algorithm_listed);
+}
+
+// A verifier that only accepts presentation formats the wallet cannot create
+// is rejected.
+#[test]
+fn unsupported_presentation_format() {
+    let algorithm = MockProvider::new().algorithm();
+    let metadata = metadata(&json!({"ldp_vp": {"proof_type": ["Ed25519Signature2018"]}}));
+
+    let flow = authorized(&json!({}), "jwt_vc_json");
+    let err = flow
+        .negotiate_metadata(&metadata, &WalletMetadata::new(algorithm.clone()), &algorithm)
+        .expect_err("should not negotiate");
+    assert!(err.is::<NoCommonFormat>());
+}
+
+// Credentials in a format the verifier does not accept are rejected.
+#[test]
+fn unsupported_credential_format() {
+    let algorithm = MockProvider::new().algorithm();
+    let metadata = metadata(
+        &json!({"jwt_vp_json": {"alg": [alg()]}, "ldp_vc": {"proof_type": ["Ed25519Signature2018"]}}),
+    );
+
+    let flow = authorized(&json!({}), "jwt_vc_json");
+    let err = flow
+        .negotiate_metadata(&metadata, &WalletMetadata::new(algorithm.clone()), &algorithm)
+        .expect_err("should not negotiate");
+    assert!(err.is::<NoCommonFormat>());
+}
@@ -2,12 +2,10 @@
 //! made using a credential definition.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
 use credibil_holder::issuance::{
-    AuthCode, AuthorizationDetail, AuthorizationDetailType, CredentialAuthorization,
-    CredentialResponseType, Format, IssuanceFlow, NotAccepted, ProfileClaims, WithoutOffer,
-    WithoutToken,
+    AuthorizationDetail, AuthorizationDetailType, CredentialAuthorization, CredentialResponseType,
+    Format, IssuanceFlowBuilder, ProfileClaims,
 };
 use credibil_holder::provider::{Issuer, MetadataRequest, OAuthServerRequest};
 use credibil_vc::test_utils::issuer::{
@@ -47,12 +45,9 @@ async fn wallet_credential_definition() {
     //--------------------------------------------------------------------------
     // Initiate flow state with the offer and metadata.
     //--------------------------------------------------------------------------
-    let state = IssuanceFlow::<WithoutOffer, AuthCode, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer.clone(),
-        auth_metadata.authorization_server,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer.clone())
+        .subject_id(NORMAL_USER)
+        .wallet_initiated(auth_metadata.authorization_server);
 
     //--------------------------------------------------------------------------
     // Construct an authorization request using the credential definition for
@@ -108,15 +103,7 @@ async fn wallet_credential_definition() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode jws");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
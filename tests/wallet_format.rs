@@ -2,12 +2,10 @@
 //! made using a format.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
 use credibil_holder::issuance::{
-    AuthCode, AuthorizationDetail, AuthorizationDetailType, CredentialAuthorization,
-    CredentialResponseType, Format, IssuanceFlow, NotAccepted, ProfileW3c, WithoutOffer,
-    WithoutToken,
+    AuthorizationDetail, AuthorizationDetailType, AuthorizationRequest, CredentialAuthorization,
+    CredentialResponseType, Format, IssuanceFlowBuilder, ProfileW3c,
 };
 use credibil_holder::provider::{Issuer, MetadataRequest, OAuthServerRequest};
 use credibil_vc::test_utils::issuer::{
@@ -47,12 +45,9 @@ async fn wallet_format() {
     //--------------------------------------------------------------------------
     // Initiate flow state with the offer and metadata.
     //--------------------------------------------------------------------------
-    let state = IssuanceFlow::<WithoutOffer, AuthCode, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer.clone(),
-        auth_metadata.authorization_server,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer.clone())
+        .subject_id(NORMAL_USER)
+        .wallet_initiated(auth_metadata.authorization_server);
 
     //--------------------------------------------------------------------------
     // Construct an authorization request using the credential definition for
@@ -83,6 +78,12 @@ async fn wallet_format() {
     let (auth_request, verifier) = state
         .authorization_request(Some(REDIRECT_URI))
         .expect("should construct authorization request");
+    let AuthorizationRequest::Object(request_object) = &auth_request else {
+        panic!("expected authorization request object");
+    };
+    let details = request_object.authorization_details.as_ref().expect("should have details");
+    assert_eq!(details[0].locations, Some(vec![CREDENTIAL_ISSUER.to_string()]));
+    assert_eq!(request_object.resource.as_deref(), Some(CREDENTIAL_ISSUER));
     let auth_response = provider.authorization(auth_request).await.expect("should authorize");
 
     //--------------------------------------------------------------------------
@@ -107,15 +108,7 @@ async fn wallet_format() {
             identifiers.push(id.clone());
         }
     }
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode jws");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let credential_requests = state.credential_requests(&identifiers, &jwt).clone();
     for request in credential_requests {
         let credential_response =
@@ -2,11 +2,8 @@
 //! made using a format.
 mod provider;
 
-use credibil_holder::infosec::jose::jws::JwsBuilder;
-use credibil_holder::issuance::proof::{self, Payload, Type, Verify};
-use credibil_holder::issuance::{
-    AuthCode, CredentialResponseType, IssuanceFlow, NotAccepted, WithoutOffer, WithoutToken,
-};
+use credibil_holder::issuance::proof::{self, Payload, Verify};
+use credibil_holder::issuance::{CredentialResponseType, IssuanceFlowBuilder};
 use credibil_holder::provider::{Issuer, MetadataRequest, OAuthServerRequest};
 use credibil_vc::test_utils::issuer::{
     self, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER, REDIRECT_URI,
@@ -45,12 +42,9 @@ async fn wallet_scope() {
     //--------------------------------------------------------------------------
     // Initiate flow state with the offer and metadata.
     //--------------------------------------------------------------------------
-    let state = IssuanceFlow::<WithoutOffer, AuthCode, NotAccepted, WithoutToken>::new(
-        CLIENT_ID,
-        NORMAL_USER,
-        issuer_metadata.credential_issuer.clone(),
-        auth_metadata.authorization_server,
-    );
+    let state = IssuanceFlowBuilder::new(CLIENT_ID, issuer_metadata.credential_issuer.clone())
+        .subject_id(NORMAL_USER)
+        .wallet_initiated(auth_metadata.authorization_server);
 
     //--------------------------------------------------------------------------
     // Construct an authorization request using the credential definition for
@@ -86,15 +80,7 @@ async fn wallet_scope() {
     // For this test we are going to accept all credentials on offer. (Just one
     // in this case but we demonstate the pattern for multiple credentials.) We
     // are making the request by credential identifier.
-    let jws_claims = state.proof();
-    let jws = JwsBuilder::new()
-        .jwt_type(Type::Openid4VciProofJwt)
-        .payload(jws_claims)
-        .add_signer(&provider)
-        .build()
-        .await
-        .expect("should build jws");
-    let jwt = jws.encode().expect("should encode jws");
+    let jwt = state.build_proof(&provider).await.expect("should build proof");
     let request = state
         .credential_request(&scope, &format, &jwt)
         .expect("should construct credential request");